@@ -1,6 +1,7 @@
 
 
 use crate::heal::{
+    fresh_disk::FreshDiskTrackers,
     progress::HealProgress,
     resume::{CheckpointManager, ResumeManager, ResumeUtils},
     storage::HealStorageAPI,
@@ -19,6 +20,8 @@ pub struct ErasureSetHealer {
     progress: Arc<RwLock<HealProgress>>,
     cancel_token: tokio_util::sync::CancellationToken,
     disk: DiskStore,
+    /// Trackers of the fresh drives the heal rebuilds
+    trackers: Option<Arc<FreshDiskTrackers>>,
 }
 
 impl ErasureSetHealer {
@@ -33,9 +36,16 @@ impl ErasureSetHealer {
             progress,
             cancel_token,
             disk,
+            trackers: None,
         }
     }
 
+    /// Records the progress of the heal in the trackers of the fresh drives it rebuilds
+    pub fn with_trackers(mut self, trackers: Arc<FreshDiskTrackers>) -> Self {
+        self.trackers = Some(trackers);
+        self
+    }
+
     /// execute erasure set heal with resume
     pub async fn heal_erasure_set(&self, buckets: &[String], set_disk_id: &str) -> Result<()> {
         info!("Starting erasure set heal for {} buckets on set disk {}", buckets.len(), set_disk_id);
@@ -154,6 +164,7 @@ impl ErasureSetHealer {
         let mut successful_objects = state.successful_objects;
         let mut failed_objects = state.failed_objects;
         let mut skipped_objects = state.skipped_objects;
+        let mut failed_buckets = Vec::new();
 
         // 4. process remaining buckets
         for (bucket_idx, bucket) in buckets.iter().enumerate().skip(current_bucket_index) {
@@ -161,6 +172,12 @@ impl ErasureSetHealer {
             if state.completed_buckets.contains(bucket) {
                 continue;
             }
+            if let Some(trackers) = &self.trackers {
+                if trackers.bucket_healed(bucket).await {
+                    info!("Bucket {} already healed onto the fresh drives, skipping", bucket);
+                    continue;
+                }
+            }
 
             // update current bucket
             resume_manager.set_current_item(Some(bucket.clone()), None).await?;
@@ -198,10 +215,14 @@ impl ErasureSetHealer {
             match bucket_result {
                 Ok(_) => {
                     resume_manager.complete_bucket(bucket).await?;
+                    if let Some(trackers) = &self.trackers {
+                        trackers.bucket_done(bucket).await;
+                    }
                     info!("Completed heal for bucket: {}", bucket);
                 }
                 Err(e) => {
                     error!("Failed to heal bucket {}: {}", bucket, e);
+                    failed_buckets.push(bucket.clone());
                     // continue to next bucket, do not interrupt the whole process
                 }
             }
@@ -212,6 +233,9 @@ impl ErasureSetHealer {
 
         // 5. mark task completed
         resume_manager.mark_completed().await?;
+        if let Some(trackers) = &self.trackers {
+            trackers.finish(&failed_buckets).await;
+        }
 
         info!("Erasure set heal completed successfully");
         Ok(())
@@ -274,6 +298,9 @@ impl ErasureSetHealer {
                 );
                 checkpoint_manager.add_processed_object(object.clone()).await?;
                 *successful_objects += 1; // Treat as successful - object is gone as intended
+                if let Some(trackers) = &self.trackers {
+                    trackers.item_skipped(0).await;
+                }
                 *current_object_index = obj_idx + 1;
                 continue;
             }
@@ -287,19 +314,28 @@ impl ErasureSetHealer {
             };
 
             match self.storage.heal_object(bucket, object, None, &heal_opts).await {
-                Ok((_result, None)) => {
+                Ok((result, None)) => {
                     *successful_objects += 1;
                     checkpoint_manager.add_processed_object(object.clone()).await?;
+                    if let Some(trackers) = &self.trackers {
+                        trackers.item_healed(bucket, object, result.object_size as u64).await;
+                    }
                     info!("Successfully healed object {}/{}", bucket, object);
                 }
-                Ok((_, Some(err))) => {
+                Ok((result, Some(err))) => {
                     *failed_objects += 1;
                     checkpoint_manager.add_failed_object(object.clone()).await?;
+                    if let Some(trackers) = &self.trackers {
+                        trackers.item_failed(bucket, object, result.object_size as u64).await;
+                    }
                     warn!("Failed to heal object {}/{}: {}", bucket, object, err);
                 }
                 Err(err) => {
                     *failed_objects += 1;
                     checkpoint_manager.add_failed_object(object.clone()).await?;
+                    if let Some(trackers) = &self.trackers {
+                        trackers.item_failed(bucket, object, 0).await;
+                    }
                     warn!("Error healing object {}/{}: {}", bucket, object, err);
                 }
             }
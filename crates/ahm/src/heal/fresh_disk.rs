@@ -0,0 +1,229 @@
+
+
+//! Healing of drives that replaced failed ones. The auto disk scanner spots local drives that are
+//! unformatted, or that carry a healing tracker from a heal that did not finish, and queues a heal of
+//! their erasure set. The set heal formats the fresh drives into their place in the set, gives them
+//! a tracker and records its progress in it until every bucket is healed.
+
+use nebulafx_ecstore::disk::error::DiskError;
+use nebulafx_ecstore::disk::healing::{HealingTracker, local_healing_disks};
+use nebulafx_ecstore::disk::{DiskAPI, DiskStore};
+use nebulafx_ecstore::global::GLOBAL_LOCAL_DISK_MAP;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Items healed between two saves of the trackers
+const TRACKER_SAVE_INTERVAL: u64 = 100;
+
+/// Local drives of an erasure set
+pub async fn local_set_disks(pool_idx: usize, set_idx: usize) -> Vec<DiskStore> {
+    GLOBAL_LOCAL_DISK_MAP
+        .read()
+        .await
+        .values()
+        .flatten()
+        .filter(|disk| {
+            let ep = disk.endpoint();
+            ep.pool_idx == pool_idx as i32 && ep.set_idx == set_idx as i32
+        })
+        .cloned()
+        .collect()
+}
+
+/// Endpoints of the local drives of an erasure set that are not formatted yet
+pub async fn fresh_set_disks(pool_idx: usize, set_idx: usize) -> Vec<String> {
+    let mut fresh = Vec::new();
+    for disk in local_set_disks(pool_idx, set_idx).await {
+        if let Err(DiskError::UnformattedDisk) = disk.get_disk_id().await {
+            fresh.push(disk.endpoint().to_string());
+        }
+    }
+    fresh
+}
+
+/// Local drives whose heal has not finished, the heal resumes after a restart
+pub async fn unfinished_healing_disks() -> Vec<DiskStore> {
+    local_healing_disks()
+        .await
+        .into_iter()
+        .filter(|(_, tracker)| !tracker.finished)
+        .map(|(disk, _)| disk)
+        .collect()
+}
+
+/// Healing trackers of the drives an erasure set heal rebuilds
+#[derive(Debug, Default)]
+pub struct FreshDiskTrackers {
+    trackers: Mutex<Vec<(DiskStore, HealingTracker)>>,
+}
+
+impl FreshDiskTrackers {
+    /// Gives the just formatted drives of the set a new tracker, and loads the trackers the other local
+    /// drives of the set still have from an earlier run
+    pub async fn start(pool_idx: usize, set_idx: usize, fresh: &[String], heal_id: &str, buckets: &[String]) -> Self {
+        let mut trackers = Vec::new();
+        for disk in local_set_disks(pool_idx, set_idx).await {
+            let endpoint = disk.endpoint().to_string();
+            let mut tracker = match HealingTracker::load(&disk).await {
+                Ok(mut tracker) => {
+                    tracker.retry_attempts += 1;
+                    info!("resuming heal of drive {} ({} buckets healed)", endpoint, tracker.healed_buckets.len());
+                    tracker
+                }
+                Err(DiskError::FileNotFound) if fresh.contains(&endpoint) => {
+                    info!("healing fresh drive {}", endpoint);
+                    HealingTracker::new(&disk, heal_id).await
+                }
+                Err(DiskError::FileNotFound) => continue,
+                Err(err) => {
+                    warn!("load healing tracker of {} failed: {}", endpoint, err);
+                    continue;
+                }
+            };
+
+            tracker.queue_buckets(buckets);
+            if let Err(err) = tracker.save(&disk).await {
+                warn!("save healing tracker of {} failed: {}", endpoint, err);
+            }
+            trackers.push((disk, tracker));
+        }
+
+        Self {
+            trackers: Mutex::new(trackers),
+        }
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.trackers.lock().await.is_empty()
+    }
+
+    /// Whether every tracked drive already healed the bucket in an earlier run
+    pub async fn bucket_healed(&self, bucket: &str) -> bool {
+        let trackers = self.trackers.lock().await;
+        !trackers.is_empty() && trackers.iter().all(|(_, tracker)| tracker.is_healed(bucket))
+    }
+
+    pub async fn item_healed(&self, bucket: &str, object: &str, size: u64) {
+        let mut trackers = self.trackers.lock().await;
+        for (_, tracker) in trackers.iter_mut() {
+            tracker.item_healed(bucket, object, size);
+        }
+        Self::save_periodically(&mut trackers).await;
+    }
+
+    pub async fn item_failed(&self, bucket: &str, object: &str, size: u64) {
+        let mut trackers = self.trackers.lock().await;
+        for (_, tracker) in trackers.iter_mut() {
+            tracker.item_failed(bucket, object, size);
+        }
+        Self::save_periodically(&mut trackers).await;
+    }
+
+    pub async fn item_skipped(&self, size: u64) {
+        let mut trackers = self.trackers.lock().await;
+        for (_, tracker) in trackers.iter_mut() {
+            tracker.item_skipped(size);
+        }
+    }
+
+    pub async fn bucket_done(&self, bucket: &str) {
+        let mut trackers = self.trackers.lock().await;
+        for (disk, tracker) in trackers.iter_mut() {
+            tracker.bucket_done(bucket);
+            if let Err(err) = tracker.save(disk).await {
+                warn!("save healing tracker of {} failed: {}", disk.endpoint(), err);
+            }
+        }
+    }
+
+    /// Removes the trackers once every bucket is healed, the drives are then part of their set again.
+    /// When a bucket failed the trackers are kept unfinished, so the auto disk scanner queues the heal
+    /// again and the next run only heals the buckets still missing
+    pub async fn finish(&self, failed_buckets: &[String]) {
+        let mut trackers = self.trackers.lock().await;
+        for (disk, tracker) in trackers.iter_mut() {
+            if !failed_buckets.is_empty() {
+                warn!(
+                    "heal of drive {} incomplete, {} buckets failed and will be retried: {:?}",
+                    disk.endpoint(),
+                    failed_buckets.len(),
+                    failed_buckets
+                );
+                if let Err(err) = tracker.save(disk).await {
+                    warn!("save healing tracker of {} failed: {}", disk.endpoint(), err);
+                }
+                continue;
+            }
+
+            tracker.finished = true;
+            info!(
+                "heal of drive {} finished: {} items healed, {} failed",
+                disk.endpoint(),
+                tracker.items_healed,
+                tracker.items_failed
+            );
+            if let Err(err) = HealingTracker::delete(disk).await {
+                warn!("delete healing tracker of {} failed: {}", disk.endpoint(), err);
+            }
+        }
+    }
+
+    async fn save_periodically(trackers: &mut [(DiskStore, HealingTracker)]) {
+        for (disk, tracker) in trackers.iter_mut() {
+            if (tracker.items_healed + tracker.items_failed) % TRACKER_SAVE_INTERVAL == 0 {
+                if let Err(err) = tracker.save(disk).await {
+                    warn!("save healing tracker of {} failed: {}", disk.endpoint(), err);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nebulafx_ecstore::disk::endpoint::Endpoint;
+    use nebulafx_ecstore::disk::{DiskOption, NEUBULAFX_META_BUCKET, new_disk};
+    use tempfile::TempDir;
+
+    async fn test_trackers(buckets: &[String]) -> (FreshDiskTrackers, DiskStore, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        tokio::fs::create_dir_all(temp_dir.path().join(NEUBULAFX_META_BUCKET))
+            .await
+            .unwrap();
+        let endpoint = Endpoint::try_from(temp_dir.path().to_str().unwrap()).unwrap();
+        let disk = new_disk(&endpoint, &DiskOption::default()).await.unwrap();
+
+        let mut tracker = HealingTracker::new(&disk, "heal-1").await;
+        tracker.queue_buckets(buckets);
+        tracker.save(&disk).await.unwrap();
+
+        let trackers = FreshDiskTrackers {
+            trackers: Mutex::new(vec![(disk.clone(), tracker)]),
+        };
+        (trackers, disk, temp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_tracker_kept_until_every_bucket_healed() {
+        let buckets = vec!["healed".to_string(), "broken".to_string()];
+        let (trackers, disk, _temp_dir) = test_trackers(&buckets).await;
+
+        // First run: one bucket heals, the other fails
+        trackers.item_healed("healed", "obj", 10).await;
+        trackers.bucket_done("healed").await;
+        trackers.finish(&["broken".to_string()]).await;
+
+        let tracker = HealingTracker::load(&disk).await.expect("tracker is kept after a failed bucket");
+        assert!(!tracker.finished);
+        assert_eq!(tracker.healed_buckets, vec!["healed".to_string()]);
+        assert_eq!(tracker.queued_buckets, vec!["broken".to_string()]);
+        assert!(trackers.bucket_healed("healed").await);
+        assert!(!trackers.bucket_healed("broken").await);
+
+        // Retry: the missing bucket heals and the tracker goes away
+        trackers.bucket_done("broken").await;
+        trackers.finish(&[]).await;
+        assert!(matches!(HealingTracker::load(&disk).await, Err(DiskError::FileNotFound)));
+    }
+}
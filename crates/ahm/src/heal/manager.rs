@@ -1,6 +1,7 @@
 
 
 use crate::heal::{
+    fresh_disk::unfinished_healing_disks,
     progress::{HealProgress, HealStatistics},
     storage::HealStorageAPI,
     task::{HealOptions, HealPriority, HealRequest, HealTask, HealTaskStatus, HealType},
@@ -282,6 +283,10 @@ impl HealManager {
                                 }
                             }
                         }
+                        // resume heals of fresh drives interrupted by a restart or a failure
+                        for disk in unfinished_healing_disks().await {
+                            endpoints.push(disk.endpoint());
+                        }
 
                         if endpoints.is_empty() {
                             continue;
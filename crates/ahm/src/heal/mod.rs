@@ -3,6 +3,7 @@
 pub mod channel;
 pub mod erasure_healer;
 pub mod event;
+pub mod fresh_disk;
 pub mod manager;
 pub mod progress;
 pub mod resume;
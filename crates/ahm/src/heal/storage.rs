@@ -469,7 +469,7 @@ impl HealStorageAPI for ECStoreHealStorage {
 
         match self.ecstore.heal_format(dry_run).await {
             Ok((result, ecstore_error)) => {
-                let error = ecstore_error.map(Error::other);
+                let error = ecstore_error.map(Error::Storage);
                 info!("Heal format completed - result: {:?}, error: {:?}", result, error);
                 Ok((result, error))
             }
@@ -1,8 +1,14 @@
 
 
-use crate::heal::{ErasureSetHealer, progress::HealProgress, storage::HealStorageAPI};
+use crate::heal::{
+    ErasureSetHealer,
+    fresh_disk::{FreshDiskTrackers, fresh_set_disks},
+    progress::HealProgress,
+    storage::HealStorageAPI,
+};
 use crate::{Error, Result};
 use nebulafx_common::heal_channel::{HealOpts, HealScanMode};
+use nebulafx_ecstore::error::StorageError;
 use serde::{Deserialize, Serialize};
 use std::{
     future::Future,
@@ -871,11 +877,18 @@ impl HealTask {
             buckets
         };
 
+        let (pool_idx, set_idx) = crate::heal::utils::parse_set_disk_id(&set_disk_id)?;
+        let fresh_disks = fresh_set_disks(pool_idx, set_idx).await;
+
         // Step 1: Perform disk format heal using ecstore
         info!("Step 1: Performing disk format heal using ecstore");
         let format_result = self.await_with_control(self.storage.heal_format(self.options.dry_run)).await;
 
         match format_result {
+            Ok((_, Some(Error::Storage(StorageError::NoHealRequired)))) => {
+                // Formatted already, by an earlier run of this heal or by another node
+                info!("Disk format heal not required: {}", set_disk_id);
+            }
             Ok((result, error)) => {
                 if let Some(e) = error {
                     error!("Disk format heal failed: {} - {}", set_disk_id, e);
@@ -942,7 +955,14 @@ impl HealTask {
 
         // Step 3: Create erasure set healer with resume support
         info!("Step 3: Creating erasure set healer with resume support");
-        let erasure_healer = ErasureSetHealer::new(self.storage.clone(), self.progress.clone(), self.cancel_token.clone(), disk);
+        let mut erasure_healer =
+            ErasureSetHealer::new(self.storage.clone(), self.progress.clone(), self.cancel_token.clone(), disk);
+        if !self.options.dry_run {
+            let trackers = FreshDiskTrackers::start(pool_idx, set_idx, &fresh_disks, &self.id, &buckets).await;
+            if !trackers.is_empty().await {
+                erasure_healer = erasure_healer.with_trackers(Arc::new(trackers));
+            }
+        }
 
         {
             let mut progress = self.progress.write().await;
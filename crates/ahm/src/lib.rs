@@ -93,6 +93,21 @@ pub fn get_heal_manager() -> Option<&'static Arc<HealManager>> {
     GLOBAL_HEAL_MANAGER.get()
 }
 
+/// Global scanner instance
+static GLOBAL_SCANNER: OnceLock<Arc<Scanner>> = OnceLock::new();
+
+/// Register the running scanner so its metrics can be read by other components
+pub fn set_global_scanner(scanner: Arc<Scanner>) -> Result<()> {
+    GLOBAL_SCANNER
+        .set(scanner)
+        .map_err(|_| Error::Config("Scanner already initialized".to_string()))
+}
+
+/// Get global scanner instance
+pub fn get_global_scanner() -> Option<&'static Arc<Scanner>> {
+    GLOBAL_SCANNER.get()
+}
+
 /// Get global heal channel processor instance
 pub fn get_heal_channel_processor() -> Option<&'static Arc<tokio::sync::Mutex<HealChannelProcessor>>> {
     GLOBAL_HEAL_CHANNEL_PROCESSOR.get()
@@ -9,10 +9,12 @@ use crate::{
         NodeScanner, NodeScannerConfig, ScannerMetrics,
         lifecycle::ScannerItem,
         local_scan::{self, LocalObjectRecord, LocalScanOutcome},
+        stale_uploads::{DEFAULT_STALE_UPLOADS_CLEANUP_INTERVAL, stale_uploads_expiry_from_env, sweep_stale_uploads},
     },
 };
 use nebulafx_common::data_usage::{DataUsageInfo, SizeSummary};
 use nebulafx_common::metrics::{Metric, Metrics, global_metrics};
+use nebulafx_common::trace::{publish_trace, trace_enabled};
 use nebulafx_ecstore::{
     self as ecstore, StorageAPI,
     bucket::versioning::VersioningApi,
@@ -23,6 +25,7 @@ use nebulafx_ecstore::{
     store_api::ObjectInfo,
 };
 use nebulafx_filemeta::{MetacacheReader, VersionType};
+use nebulafx_madmin::trace::{TraceInfo, TraceType};
 use s3s::dto::{BucketVersioningStatus, VersioningConfiguration};
 use std::{
     collections::HashMap,
@@ -62,6 +65,10 @@ pub struct ScannerConfig {
     pub scan_mode: ScanMode,
     /// Whether to enable data usage statistics collection
     pub enable_data_usage_stats: bool,
+    /// Multipart uploads initiated longer ago than this are aborted
+    pub stale_uploads_expiry: Duration,
+    /// Interval between sweeps for stale multipart uploads
+    pub stale_uploads_cleanup_interval: Duration,
 }
 
 impl Default for ScannerConfig {
@@ -74,6 +81,8 @@ impl Default for ScannerConfig {
             enable_metrics: true,
             scan_mode: ScanMode::Normal,
             enable_data_usage_stats: true,
+            stale_uploads_expiry: stale_uploads_expiry_from_env(),
+            stale_uploads_cleanup_interval: DEFAULT_STALE_UPLOADS_CLEANUP_INTERVAL,
         }
     }
 }
@@ -602,6 +611,12 @@ impl Scanner {
             }
         });
 
+        // Abort abandoned multipart uploads in the background
+        let scanner = self.clone_for_background();
+        tokio::spawn(async move {
+            scanner.stale_uploads_loop().await;
+        });
+
         // Trigger an immediate data usage collection so that admin APIs have fresh data after startup.
         let scanner = self.clone_for_background();
         tokio::spawn(async move {
@@ -874,6 +889,8 @@ impl Scanner {
         // Complete global metrics collection for this cycle
         stop_fn();
 
+        trace_scanner("ScanCycle", String::new(), start_time, None);
+
         info!("Optimized scan cycle completed in {:?}", scan_duration);
         Ok(())
     }
@@ -1240,6 +1257,7 @@ impl Scanner {
                         index: p.index.clone(),
                         checksums: p.checksums.clone(),
                         error: None,
+                        nonce: p.nonce.clone(),
                     })
                     .collect(),
                 erasure: nebulafx_filemeta::ErasureInfo {
@@ -1764,7 +1782,16 @@ impl Scanner {
                 }
             }
 
-            match self.scan_volume(disk, &volume.name).await {
+            let volume_start = SystemTime::now();
+            let result = self.scan_volume(disk, &volume.name).await;
+            trace_scanner(
+                "ScanBucketDrive",
+                format!("{}/{}", disk_path, volume.name),
+                volume_start,
+                result.as_ref().err().map(|e| e.to_string()),
+            );
+
+            match result {
                 Ok(object_metadata) => {
                     disk_objects.insert(volume.name, object_metadata);
                 }
@@ -2502,6 +2529,31 @@ impl Scanner {
         Ok(())
     }
 
+    /// Sweep stale multipart uploads every `stale_uploads_cleanup_interval`
+    async fn stale_uploads_loop(&self) {
+        loop {
+            let (expiry, interval) = {
+                let config = self.config.read().await;
+                (config.stale_uploads_expiry, config.stale_uploads_cleanup_interval)
+            };
+
+            match get_ahm_services_cancel_token() {
+                Some(token) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(interval) => {}
+                        _ = token.cancelled() => {
+                            info!("Cancellation requested, exiting stale uploads loop");
+                            break;
+                        }
+                    }
+                }
+                None => tokio::time::sleep(interval).await,
+            }
+
+            sweep_stale_uploads(expiry).await;
+        }
+    }
+
     /// Update legacy metrics from aggregated statistics
     async fn update_legacy_metrics_from_aggregated(&self, aggregated: &super::stats_aggregator::AggregatedStats) {
         // Update metrics collector with aggregated data
@@ -2563,6 +2615,23 @@ impl Scanner {
     }
 }
 
+/// Publish a scanner trace record for work that started at `started`
+fn trace_scanner(func: &str, path: String, started: SystemTime, error: Option<String>) {
+    if !trace_enabled(TraceType::SCANNER) {
+        return;
+    }
+
+    publish_trace(TraceInfo {
+        trace_type: TraceType::SCANNER.mask(),
+        func_name: format!("scanner.{func}"),
+        time: started.into(),
+        path,
+        duration: SystemTime::now().duration_since(started).unwrap_or(Duration::ZERO),
+        error,
+        ..Default::default()
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
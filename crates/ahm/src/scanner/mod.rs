@@ -10,6 +10,7 @@ pub mod local_scan;
 pub mod local_stats;
 pub mod metrics;
 pub mod node_scanner;
+pub mod stale_uploads;
 pub mod stats_aggregator;
 
 pub use checkpoint::{CheckpointData, CheckpointInfo, CheckpointManager};
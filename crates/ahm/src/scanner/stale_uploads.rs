@@ -0,0 +1,85 @@
+
+
+//! Sweeper for abandoned multipart uploads.
+//!
+//! Every node periodically aborts the uploads of the sets it owns the sweep of
+//! that were initiated longer than the configured expiry ago, or that are due
+//! under the AbortIncompleteMultipartUpload rule of their bucket's lifecycle
+//! configuration.
+
+use nebulafx_common::metrics::{IlmAction, Metrics, global_metrics};
+use nebulafx_ecstore::store_api::StaleUploadsStats;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Uploads older than this are aborted whatever the bucket lifecycle says
+pub const DEFAULT_STALE_UPLOADS_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Interval between two sweeps
+pub const DEFAULT_STALE_UPLOADS_CLEANUP_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Overrides [`DEFAULT_STALE_UPLOADS_EXPIRY`], e.g. `72h` or `14d`; plain numbers are seconds
+pub const ENV_STALE_UPLOADS_EXPIRY: &str = "NEUBULAFX_API_STALE_UPLOADS_EXPIRY";
+
+fn parse_expiry(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (num, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => value.split_at(idx),
+        None => (value, "s"),
+    };
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    num.parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(secs))
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// Global max age of a multipart upload, from [`ENV_STALE_UPLOADS_EXPIRY`]
+pub fn stale_uploads_expiry_from_env() -> Duration {
+    let Ok(value) = std::env::var(ENV_STALE_UPLOADS_EXPIRY) else {
+        return DEFAULT_STALE_UPLOADS_EXPIRY;
+    };
+    parse_expiry(&value).unwrap_or_else(|| {
+        warn!("invalid {}: {}, using the default", ENV_STALE_UPLOADS_EXPIRY, value);
+        DEFAULT_STALE_UPLOADS_EXPIRY
+    })
+}
+
+/// Abort the stale multipart uploads of the sets this node sweeps and record them in the scanner metrics
+pub async fn sweep_stale_uploads(expiry: Duration) -> StaleUploadsStats {
+    let Some(store) = nebulafx_ecstore::new_object_layer_fn() else {
+        return StaleUploadsStats::default();
+    };
+
+    let done = Metrics::time_ilm(IlmAction::AbortMultipartUploadAction);
+    let stats = store.cleanup_stale_uploads(expiry).await;
+    if stats.uploads > 0 {
+        done(stats.uploads)();
+        global_metrics().add_stale_uploads(stats.uploads, stats.bytes);
+        info!("aborted {} stale multipart uploads, reclaimed {} bytes", stats.uploads, stats.bytes);
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_expiry() {
+        assert_eq!(parse_expiry("3600"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_expiry("72h"), Some(Duration::from_secs(72 * 3600)));
+        assert_eq!(parse_expiry("7d"), Some(DEFAULT_STALE_UPLOADS_EXPIRY));
+        assert_eq!(parse_expiry("30m"), Some(Duration::from_secs(1800)));
+        assert_eq!(parse_expiry("0"), None);
+        assert_eq!(parse_expiry("1w"), None);
+        assert_eq!(parse_expiry("h"), None);
+    }
+}
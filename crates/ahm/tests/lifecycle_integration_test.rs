@@ -2,6 +2,8 @@
 
 use nebulafx_ahm::scanner::{Scanner, data_scanner::ScannerConfig};
 use nebulafx_ecstore::{
+    bucket::lifecycle::bucket_lifecycle_ops::LifecycleOps,
+    bucket::lifecycle::lifecycle::{TRANSITION_PENDING, TransitionOptions},
     bucket::metadata::BUCKET_LIFECYCLE_CONFIG,
     bucket::metadata_sys,
     disk::endpoint::Endpoint,
@@ -9,7 +11,7 @@ use nebulafx_ecstore::{
     global::GLOBAL_TierConfigMgr,
     store::ECStore,
     store_api::{MakeBucketOptions, ObjectIO, ObjectOptions, PutObjReader, StorageAPI},
-    tier::tier_config::{TierConfig, TierMinIO, TierType},
+    tier::tier_config::{TierConfig, TierFileSystem, TierMinIO, TierType},
 };
 use serial_test::serial;
 use std::{
@@ -241,6 +243,7 @@ async fn create_test_tier(server: u32) {
         gcs: None,
         r2: None,
         nebulafx: None,
+        filesystem: None,
         minio: if server == 1 {
             Some(TierMinIO {
                 access_key: "minioadmin".to_string(),
@@ -275,6 +278,35 @@ async fn create_test_tier(server: u32) {
     println!("Created test tier: COLDTIER44");
 }
 
+/// Test helper: Create a filesystem tier in a directory
+async fn create_filesystem_tier(name: &str, path: &std::path::Path) {
+    let args = TierConfig {
+        version: "v1".to_string(),
+        tier_type: TierType::FileSystem,
+        name: name.to_string(),
+        filesystem: Some(TierFileSystem {
+            name: name.to_string(),
+            path: path.to_string_lossy().to_string(),
+            prefix: "tiered".to_string(),
+        }),
+        ..Default::default()
+    };
+    let mut tier_config_mgr = GLOBAL_TierConfigMgr.write().await;
+    if let Err(err) = tier_config_mgr.add(args, false).await {
+        panic!("tier add failed. {err}");
+    }
+    println!("Created filesystem tier: {name}");
+}
+
+/// Test helper: Read an object back
+async fn read_test_object(ecstore: &Arc<ECStore>, bucket: &str, object: &str) -> Vec<u8> {
+    let mut reader = (**ecstore)
+        .get_object_reader(bucket, object, None, Default::default(), &ObjectOptions::default())
+        .await
+        .expect("Failed to get object reader");
+    reader.read_all().await.expect("Failed to read object")
+}
+
 /// Test helper: Check if object exists
 async fn object_exists(ecstore: &Arc<ECStore>, bucket: &str, object: &str) -> bool {
     match (**ecstore).get_object_info(bucket, object, &ObjectOptions::default()).await {
@@ -682,4 +714,76 @@ mod serial_tests {
 
         println!("Lifecycle transition basic test completed");
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[serial]
+    async fn test_lifecycle_transition_restore_filesystem_tier() {
+        let (_disk_paths, ecstore) = setup_test_env().await;
+
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let tier_name = format!("FSTIER{}", suffix[..8].to_uppercase());
+        let tier_dir = tempfile::tempdir().unwrap();
+        create_filesystem_tier(&tier_name, tier_dir.path()).await;
+
+        let bucket_name = format!("test-lc-fs-tier-{}", &suffix[..8]);
+        let object_name = "test/object.txt";
+        let test_data = b"Hello, this is test data for the filesystem tier!";
+
+        create_test_bucket(&ecstore, bucket_name.as_str()).await;
+        upload_test_object(&ecstore, bucket_name.as_str(), object_name, test_data).await;
+
+        let oi = ecstore
+            .get_object_info(bucket_name.as_str(), object_name, &ObjectOptions::default())
+            .await
+            .unwrap();
+        let opts = ObjectOptions {
+            transition: TransitionOptions {
+                status: TRANSITION_PENDING.to_string(),
+                tier: tier_name.clone(),
+                etag: oi.etag.clone().unwrap_or_default(),
+                ..Default::default()
+            },
+            mod_time: oi.mod_time,
+            ..Default::default()
+        };
+        ecstore
+            .transition_object(bucket_name.as_str(), object_name, &opts)
+            .await
+            .expect("Failed to transition object");
+        assert!(object_is_transitioned(&ecstore, &bucket_name, object_name).await);
+        println!("✅ Object transitioned to {tier_name}");
+
+        // The data now lives in the tier directory, next to its sidecar metadata
+        let transitioned = ecstore
+            .get_object_info(bucket_name.as_str(), object_name, &ObjectOptions::default())
+            .await
+            .unwrap()
+            .transitioned_object;
+        assert_eq!(transitioned.tier, tier_name);
+        let tiered = tier_dir.path().join("tiered").join(&transitioned.name);
+        assert_eq!(fs::read(&tiered).await.unwrap(), test_data);
+        assert!(fs::try_exists(format!("{}.meta.json", tiered.display())).await.unwrap());
+
+        // Reads of a transitioned object are served from the tier
+        assert_eq!(read_test_object(&ecstore, &bucket_name, object_name).await, test_data);
+        println!("✅ Transitioned object read from the tier");
+
+        ecstore
+            .clone()
+            .restore_transitioned_object(bucket_name.as_str(), object_name, &ObjectOptions::default())
+            .await
+            .expect("Failed to restore object");
+        let restored = ecstore
+            .get_object_info(bucket_name.as_str(), object_name, &ObjectOptions::default())
+            .await
+            .unwrap();
+        assert!(!restored.is_remote(), "restored object is still served from the tier");
+
+        // The restored copy is read from the local drives, the tier copy is no longer needed for it
+        fs::remove_file(&tiered).await.unwrap();
+        assert_eq!(read_test_object(&ecstore, &bucket_name, object_name).await, test_data);
+        println!("✅ Object restored from the tier");
+
+        println!("Lifecycle filesystem tier test completed");
+    }
 }
@@ -7,6 +7,7 @@ pub mod globals;
 pub mod heal_channel;
 pub mod last_minute;
 pub mod metrics;
+pub mod trace;
 
 // is ','
 pub static DEFAULT_DELIMITER: u8 = 44;
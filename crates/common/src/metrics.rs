@@ -26,6 +26,7 @@ pub enum IlmAction {
     DeleteRestoredVersionAction,
     DeleteAllVersionsAction,
     DelMarkerDeleteAllVersionsAction,
+    AbortMultipartUploadAction,
     ActionCount,
 }
 
@@ -246,6 +247,10 @@ pub struct Metrics {
 
     // Cycle information
     cycle_info: Arc<RwLock<Option<CurrentCycle>>>,
+
+    // Abandoned multipart uploads aborted by the stale upload sweeper
+    stale_uploads: AtomicU64,
+    stale_upload_bytes: AtomicU64,
 }
 
 // This is a placeholder. We'll need to define this struct.
@@ -271,6 +276,8 @@ impl Metrics {
             actions_latency: vec![LockedLastMinuteLatency::default(); IlmAction::ActionCount as usize],
             current_paths: Arc::new(RwLock::new(HashMap::new())),
             cycle_info: Arc::new(RwLock::new(None)),
+            stale_uploads: AtomicU64::new(0),
+            stale_upload_bytes: AtomicU64::new(0),
         }
     }
 
@@ -409,6 +416,12 @@ impl Metrics {
         self.latency[metric].total().await
     }
 
+    /// Record multipart uploads reclaimed by the stale upload sweeper
+    pub fn add_stale_uploads(&self, uploads: u64, bytes: u64) {
+        self.stale_uploads.fetch_add(uploads, Ordering::Relaxed);
+        self.stale_upload_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
     /// Set current cycle information
     pub async fn set_cycle(&self, cycle: Option<CurrentCycle>) {
         *self.cycle_info.write().await = cycle;
@@ -450,6 +463,8 @@ impl Metrics {
 
         metrics.collected_at = Utc::now();
         metrics.active_paths = self.get_current_paths().await;
+        metrics.stale_uploads = self.stale_uploads.load(Ordering::Relaxed);
+        metrics.stale_upload_bytes = self.stale_upload_bytes.load(Ordering::Relaxed);
 
         // Lifetime operations
         for i in 0..Metric::Last as usize {
@@ -0,0 +1,105 @@
+
+
+//! Process-wide publisher for admin trace records.
+//!
+//! Producers call [`trace_enabled`] before building a record so tracing costs
+//! nothing while no `admin trace` client is connected to this node.
+
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use nebulafx_madmin::trace::{TraceInfo, TraceType};
+use tokio::sync::broadcast;
+
+const TRACE_CHANNEL_CAPACITY: usize = 10000;
+const TRACE_TYPE_BITS: usize = TraceType::ALL.mask().count_ones() as usize;
+
+static GLOBAL_TRACE: LazyLock<broadcast::Sender<TraceInfo>> = LazyLock::new(|| broadcast::channel(TRACE_CHANNEL_CAPACITY).0);
+
+// Number of live subscriptions per trace type bit
+static SUBSCRIBERS: [AtomicUsize; TRACE_TYPE_BITS] = [const { AtomicUsize::new(0) }; TRACE_TYPE_BITS];
+
+fn for_each_bit(t: TraceType, f: impl Fn(&AtomicUsize)) {
+    for (bit, counter) in SUBSCRIBERS.iter().enumerate() {
+        if t.mask() & (1 << bit) != 0 {
+            f(counter);
+        }
+    }
+}
+
+/// Whether any subscriber wants records of type `t`
+pub fn trace_enabled(t: TraceType) -> bool {
+    SUBSCRIBERS
+        .iter()
+        .enumerate()
+        .any(|(bit, counter)| t.mask() & (1 << bit) != 0 && counter.load(Ordering::Relaxed) > 0)
+}
+
+/// Send a record to every subscriber on this node
+pub fn publish_trace(info: TraceInfo) {
+    if trace_enabled(TraceType::new(info.trace_type)) {
+        let _ = GLOBAL_TRACE.send(info);
+    }
+}
+
+/// A live subscription; producers stop building records of its types once it is dropped
+pub struct TraceSubscription {
+    types: TraceType,
+    rx: broadcast::Receiver<TraceInfo>,
+}
+
+impl TraceSubscription {
+    /// Next record, skipping over whatever was dropped because the subscriber fell behind.
+    ///
+    /// Returns `None` once the publisher is gone.
+    pub async fn recv(&mut self) -> Option<TraceInfo> {
+        loop {
+            match self.rx.recv().await {
+                Ok(info) => return Some(info),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for TraceSubscription {
+    fn drop(&mut self) {
+        for_each_bit(self.types, |c| {
+            c.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+}
+
+/// Subscribe to the records of the given types published on this node
+pub fn subscribe_trace(types: TraceType) -> TraceSubscription {
+    let rx = GLOBAL_TRACE.subscribe();
+    for_each_bit(types, |c| {
+        c.fetch_add(1, Ordering::Relaxed);
+    });
+    TraceSubscription { types, rx }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscription_enables_only_its_types() {
+        assert!(!trace_enabled(TraceType::FTP));
+
+        let mut sub = subscribe_trace(TraceType::FTP);
+        assert!(trace_enabled(TraceType::FTP));
+        assert!(!trace_enabled(TraceType::BOOTSTRAP));
+
+        publish_trace(TraceInfo {
+            trace_type: TraceType::FTP.mask(),
+            func_name: "ftp.Login".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(sub.rx.try_recv().unwrap().func_name, "ftp.Login");
+
+        drop(sub);
+        assert!(!trace_enabled(TraceType::FTP));
+    }
+}
@@ -0,0 +1,31 @@
+//ldap env vars
+pub const ENV_IDENTITY_LDAP_SERVER_ADDR: &str = "NEUBULAFX_IDENTITY_LDAP_SERVER_ADDR";
+pub const ENV_IDENTITY_LDAP_LOOKUP_BIND_DN: &str = "NEUBULAFX_IDENTITY_LDAP_LOOKUP_BIND_DN";
+pub const ENV_IDENTITY_LDAP_LOOKUP_BIND_PASSWORD: &str = "NEUBULAFX_IDENTITY_LDAP_LOOKUP_BIND_PASSWORD";
+pub const ENV_IDENTITY_LDAP_USER_DN_SEARCH_BASE_DN: &str = "NEUBULAFX_IDENTITY_LDAP_USER_DN_SEARCH_BASE_DN";
+pub const ENV_IDENTITY_LDAP_USER_DN_SEARCH_FILTER: &str = "NEUBULAFX_IDENTITY_LDAP_USER_DN_SEARCH_FILTER";
+pub const ENV_IDENTITY_LDAP_GROUP_SEARCH_BASE_DN: &str = "NEUBULAFX_IDENTITY_LDAP_GROUP_SEARCH_BASE_DN";
+pub const ENV_IDENTITY_LDAP_GROUP_SEARCH_FILTER: &str = "NEUBULAFX_IDENTITY_LDAP_GROUP_SEARCH_FILTER";
+pub const ENV_IDENTITY_LDAP_SERVER_INSECURE: &str = "NEUBULAFX_IDENTITY_LDAP_SERVER_INSECURE";
+pub const ENV_IDENTITY_LDAP_SERVER_STARTTLS: &str = "NEUBULAFX_IDENTITY_LDAP_SERVER_STARTTLS";
+pub const ENV_IDENTITY_LDAP_TLS_SKIP_VERIFY: &str = "NEUBULAFX_IDENTITY_LDAP_TLS_SKIP_VERIFY";
+pub const ENV_IDENTITY_LDAP_SYNC_INTERVAL: &str = "NEUBULAFX_IDENTITY_LDAP_SYNC_INTERVAL";
+
+pub const ENV_IDENTITY_LDAP_KEYS: &[&str] = &[
+    ENV_IDENTITY_LDAP_SERVER_ADDR,
+    ENV_IDENTITY_LDAP_LOOKUP_BIND_DN,
+    ENV_IDENTITY_LDAP_LOOKUP_BIND_PASSWORD,
+    ENV_IDENTITY_LDAP_USER_DN_SEARCH_BASE_DN,
+    ENV_IDENTITY_LDAP_USER_DN_SEARCH_FILTER,
+    ENV_IDENTITY_LDAP_GROUP_SEARCH_BASE_DN,
+    ENV_IDENTITY_LDAP_GROUP_SEARCH_FILTER,
+    ENV_IDENTITY_LDAP_SERVER_INSECURE,
+    ENV_IDENTITY_LDAP_SERVER_STARTTLS,
+    ENV_IDENTITY_LDAP_TLS_SKIP_VERIFY,
+    ENV_IDENTITY_LDAP_SYNC_INTERVAL,
+];
+
+pub const IDENTITY_LDAP_SUB_SYS: &str = "identity_ldap";
+
+/// Seconds between two checks of the users holding temporary credentials against the directory
+pub const DEFAULT_IDENTITY_LDAP_SYNC_INTERVAL: u64 = 3600;
@@ -20,7 +20,11 @@ pub use constants::tls::*;
 pub mod audit;
 #[cfg(feature = "notify")]
 pub mod notify;
+#[cfg(feature = "ldap")]
+pub mod ldap;
 #[cfg(feature = "observability")]
 pub mod observability;
 #[cfg(feature = "opa")]
 pub mod opa;
+#[cfg(feature = "openid")]
+pub mod openid;
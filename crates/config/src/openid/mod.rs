@@ -0,0 +1,23 @@
+//openid env vars, each one also accepts a `_<NAME>` suffix to configure more than one provider
+pub const ENV_IDENTITY_OPENID_ISSUER: &str = "NEUBULAFX_IDENTITY_OPENID_ISSUER";
+pub const ENV_IDENTITY_OPENID_JWKS_URL: &str = "NEUBULAFX_IDENTITY_OPENID_JWKS_URL";
+pub const ENV_IDENTITY_OPENID_JWKS_FILE: &str = "NEUBULAFX_IDENTITY_OPENID_JWKS_FILE";
+pub const ENV_IDENTITY_OPENID_CLIENT_ID: &str = "NEUBULAFX_IDENTITY_OPENID_CLIENT_ID";
+pub const ENV_IDENTITY_OPENID_CLAIM_NAME: &str = "NEUBULAFX_IDENTITY_OPENID_CLAIM_NAME";
+pub const ENV_IDENTITY_OPENID_ROLE_POLICY: &str = "NEUBULAFX_IDENTITY_OPENID_ROLE_POLICY";
+
+pub const ENV_IDENTITY_OPENID_KEYS: &[&str] = &[
+    ENV_IDENTITY_OPENID_ISSUER,
+    ENV_IDENTITY_OPENID_JWKS_URL,
+    ENV_IDENTITY_OPENID_JWKS_FILE,
+    ENV_IDENTITY_OPENID_CLIENT_ID,
+    ENV_IDENTITY_OPENID_CLAIM_NAME,
+    ENV_IDENTITY_OPENID_ROLE_POLICY,
+];
+
+pub const IDENTITY_OPENID_SUB_SYS: &str = "identity_openid";
+
+/// Name of the provider configured by the variables without a suffix
+pub const DEFAULT_IDENTITY_OPENID_PROVIDER: &str = "default";
+/// Claim of the ID token holding the policies of the identity
+pub const DEFAULT_IDENTITY_OPENID_CLAIM_NAME: &str = "policy";
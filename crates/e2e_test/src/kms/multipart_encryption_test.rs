@@ -481,6 +481,37 @@ async fn test_step5_all_encryption_types_multipart() -> Result<(), Box<dyn std::
     Ok(())
 }
 
+/// Step 6: an SSE-C multipart upload of a single part reads back like one of several parts
+#[tokio::test]
+#[serial]
+async fn test_step6_sse_c_single_part_multipart_upload() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    init_logging();
+    info!("🧪 Step 6: test an SSE-C multipart upload of a single part");
+
+    let mut kms_env = LocalKMSTestEnvironment::new().await?;
+    let _default_key_id = kms_env.start_nebulafx_for_local_kms().await?;
+    tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+
+    let s3_client = kms_env.base_env.create_s3_client();
+    kms_env.base_env.create_test_bucket(TEST_BUCKET).await?;
+
+    let part_size = 1024 * 1024;
+    test_multipart_encryption_type(
+        &s3_client,
+        TEST_BUCKET,
+        "test-multipart-sse-c-single-part",
+        part_size,
+        part_size,
+        1,
+        EncryptionType::SSEC,
+    )
+    .await?;
+
+    kms_env.base_env.delete_test_bucket(TEST_BUCKET).await?;
+    info!("✅ Step 6 passed: a single part SSE-C multipart upload reads back");
+    Ok(())
+}
+
 #[derive(Debug)]
 enum EncryptionType {
     SSEKMS,
@@ -0,0 +1,15 @@
+
+
+//! Replication bandwidth limits per bucket target.
+//!
+//! A target's `bandwidth_limit` is a cluster-wide budget in bytes per second,
+//! every node throttles its own replication traffic to an equal share of it.
+//! Readers feeding a target are wrapped in a [`MonitoredReader`] which waits
+//! for the throttle and records the bytes sent, the measured throughput is
+//! reported through [`Monitor::report`].
+
+mod monitor;
+mod reader;
+
+pub use monitor::{BandwidthDetails, BucketBandwidthReport, Monitor, get_global_bucket_monitor};
+pub use reader::MonitoredReader;
@@ -0,0 +1,332 @@
+
+
+use super::MonitoredReader;
+use crate::global::get_global_endpoints;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncRead;
+
+/// Weight of the newest throughput sample in the moving average
+const MEASUREMENT_BETA: f64 = 0.1;
+
+/// Throughput is sampled over windows of at least this length
+const MEASUREMENT_WINDOW: Duration = Duration::from_secs(1);
+
+static GLOBAL_BUCKET_MONITOR: OnceLock<Monitor> = OnceLock::new();
+
+pub fn get_global_bucket_monitor() -> &'static Monitor {
+    GLOBAL_BUCKET_MONITOR.get_or_init(Monitor::new)
+}
+
+/// Configured limit and measured throughput of one replication target
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthDetails {
+    pub limit_in_bytes_per_second: i64,
+    pub current_bandwidth_in_bytes_per_second: f64,
+}
+
+/// Bandwidth of the replication targets of a bucket, keyed by target ARN
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketBandwidthReport {
+    pub bucket_stats: HashMap<String, BandwidthDetails>,
+}
+
+/// Token bucket refilled at `rate` bytes per second and holding at most one second of traffic.
+/// A rate of zero means unlimited.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: u64,
+    tokens: f64,
+    last: Instant,
+}
+
+pub(crate) enum Acquire {
+    Unlimited,
+    Granted(usize),
+    Wait(Duration),
+}
+
+impl TokenBucket {
+    fn new(rate: u64, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate as f64,
+            last: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.rate as f64);
+        self.last = self.last.max(now);
+    }
+
+    fn set_rate(&mut self, rate: u64, now: Instant) {
+        self.refill(now);
+        self.rate = rate;
+        self.tokens = self.tokens.min(rate as f64);
+    }
+
+    /// Take up to `want` bytes. Waits until `want` bytes or a full second worth of tokens are
+    /// available so a slow limit doesn't turn into many tiny reads.
+    fn acquire(&mut self, want: usize, now: Instant) -> Acquire {
+        if self.rate == 0 {
+            return Acquire::Unlimited;
+        }
+        self.refill(now);
+
+        let needed = (want as u64).min(self.rate).max(1) as f64;
+        if self.tokens >= needed {
+            self.tokens -= needed;
+            Acquire::Granted(needed as usize)
+        } else {
+            Acquire::Wait(Duration::from_secs_f64((needed - self.tokens) / self.rate as f64))
+        }
+    }
+
+    /// Return tokens that were acquired but not used
+    fn release(&mut self, n: usize) {
+        if self.rate > 0 {
+            self.tokens = (self.tokens + n as f64).min(self.rate as f64);
+        }
+    }
+}
+
+/// Exponential moving average of the bytes sent per second
+#[derive(Debug)]
+struct Measurement {
+    window_bytes: u64,
+    window_start: Instant,
+    average: f64,
+}
+
+impl Measurement {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_bytes: 0,
+            window_start: now,
+            average: 0.0,
+        }
+    }
+
+    fn record(&mut self, n: usize, now: Instant) {
+        self.window_bytes += n as u64;
+        self.update(now);
+    }
+
+    /// Fold the current window into the average once it is long enough. A window spanning
+    /// several periods decays the average as if one sample had been taken per period.
+    fn update(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < MEASUREMENT_WINDOW {
+            return;
+        }
+
+        let sample = self.window_bytes as f64 / elapsed.as_secs_f64();
+        let periods = elapsed.as_secs_f64() / MEASUREMENT_WINDOW.as_secs_f64();
+        let keep = (1.0 - MEASUREMENT_BETA).powf(periods);
+        self.average = self.average * keep + sample * (1.0 - keep);
+        self.window_bytes = 0;
+        self.window_start = now;
+    }
+
+    fn rate(&mut self, now: Instant) -> f64 {
+        self.update(now);
+        self.average
+    }
+}
+
+/// Throttle and measurement of one replication target on this node
+#[derive(Debug)]
+pub struct TargetTraffic {
+    limit: Mutex<i64>,
+    bucket: Mutex<TokenBucket>,
+    measurement: Mutex<Measurement>,
+}
+
+impl TargetTraffic {
+    fn new(now: Instant) -> Self {
+        Self {
+            limit: Mutex::new(0),
+            bucket: Mutex::new(TokenBucket::new(0, now)),
+            measurement: Mutex::new(Measurement::new(now)),
+        }
+    }
+
+    /// `limit` is cluster-wide, this node gets `limit / nodes` of it
+    fn set_limit(&self, limit: i64, nodes: usize) {
+        let limit = limit.max(0);
+        *self.limit.lock().unwrap_or_else(|e| e.into_inner()) = limit;
+
+        let share = if limit == 0 {
+            0
+        } else {
+            (limit as u64 / nodes.max(1) as u64).max(1)
+        };
+        self.bucket
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .set_rate(share, Instant::now());
+    }
+
+    pub(crate) fn acquire(&self, want: usize) -> Acquire {
+        self.bucket
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .acquire(want, Instant::now())
+    }
+
+    pub(crate) fn release(&self, n: usize) {
+        self.bucket.lock().unwrap_or_else(|e| e.into_inner()).release(n);
+    }
+
+    pub(crate) fn record(&self, n: usize) {
+        self.measurement
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record(n, Instant::now());
+    }
+
+    fn details(&self) -> BandwidthDetails {
+        BandwidthDetails {
+            limit_in_bytes_per_second: *self.limit.lock().unwrap_or_else(|e| e.into_inner()),
+            current_bandwidth_in_bytes_per_second: self
+                .measurement
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .rate(Instant::now()),
+        }
+    }
+}
+
+/// Replication traffic of every (bucket, target ARN) pair on this node
+#[derive(Debug, Default)]
+pub struct Monitor {
+    targets: RwLock<HashMap<(String, String), Arc<TargetTraffic>>>,
+}
+
+impl Monitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn traffic(&self, bucket: &str, arn: &str) -> Arc<TargetTraffic> {
+        let key = (bucket.to_string(), arn.to_string());
+        if let Some(traffic) = self.targets.read().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return traffic.clone();
+        }
+        self.targets
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key)
+            .or_insert_with(|| Arc::new(TargetTraffic::new(Instant::now())))
+            .clone()
+    }
+
+    /// Set the cluster-wide limit of a target in bytes per second, zero or less removes it.
+    /// Readers already replicating to the target pick up the new limit on their next read.
+    pub fn set_bandwidth_limit(&self, bucket: &str, arn: &str, limit: i64) {
+        let nodes = get_global_endpoints().get_nodes().len();
+        self.traffic(bucket, arn).set_limit(limit, nodes);
+    }
+
+    /// Forget a target, readers still holding it continue without a limit
+    pub fn delete_bucket_throttle(&self, bucket: &str, arn: &str) {
+        let removed = self
+            .targets
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&(bucket.to_string(), arn.to_string()));
+        if let Some(traffic) = removed {
+            traffic.set_limit(0, 1);
+        }
+    }
+
+    /// Wrap a reader whose data is replicated from `bucket` to the target `arn`
+    pub fn monitored_reader<R>(&self, bucket: &str, arn: &str, reader: R) -> MonitoredReader<R>
+    where
+        R: AsyncRead + Unpin,
+    {
+        MonitoredReader::new(reader, self.traffic(bucket, arn))
+    }
+
+    pub fn report(&self, bucket: &str) -> BucketBandwidthReport {
+        let targets = self.targets.read().unwrap_or_else(|e| e.into_inner());
+        BucketBandwidthReport {
+            bucket_stats: targets
+                .iter()
+                .filter(|((b, _), _)| b == bucket)
+                .map(|((_, arn), traffic)| (arn.clone(), traffic.details()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(0, now);
+        assert!(matches!(bucket.acquire(1 << 20, now), Acquire::Unlimited));
+
+        bucket.set_rate(1000, now);
+        // Leaving unlimited mode starts with an empty bucket
+        assert!(matches!(bucket.acquire(4096, now), Acquire::Wait(_)));
+
+        let later = now + Duration::from_secs(1);
+        assert!(matches!(bucket.acquire(4096, later), Acquire::Granted(1000)));
+        match bucket.acquire(500, later) {
+            Acquire::Wait(wait) => assert_eq!(wait, Duration::from_millis(500)),
+            _ => panic!("expected to wait for tokens"),
+        }
+
+        bucket.release(300);
+        assert!(matches!(bucket.acquire(300, later), Acquire::Granted(300)));
+
+        let much_later = later + Duration::from_secs(10);
+        assert!(matches!(bucket.acquire(100, much_later), Acquire::Granted(100)));
+        assert!(matches!(bucket.acquire(2000, much_later), Acquire::Wait(_)));
+    }
+
+    #[test]
+    fn test_measurement() {
+        let now = Instant::now();
+        let mut measurement = Measurement::new(now);
+
+        measurement.record(1000, now);
+        assert_eq!(measurement.rate(now), 0.0);
+
+        let rate = measurement.rate(now + Duration::from_secs(1));
+        assert!((rate - 100.0).abs() < 1e-6, "rate {rate}");
+
+        // An idle period decays the average
+        let idle = measurement.rate(now + Duration::from_secs(11));
+        assert!(idle < rate && idle > 0.0, "idle rate {idle}");
+    }
+
+    #[test]
+    fn test_monitor_limits() {
+        let monitor = Monitor::new();
+        monitor.set_bandwidth_limit("bucket", "arn:1", 2048);
+        monitor.set_bandwidth_limit("other", "arn:2", 0);
+
+        let report = monitor.report("bucket");
+        assert_eq!(report.bucket_stats.len(), 1);
+        assert_eq!(report.bucket_stats["arn:1"].limit_in_bytes_per_second, 2048);
+
+        let traffic = monitor.traffic("bucket", "arn:1");
+        monitor.set_bandwidth_limit("bucket", "arn:1", 4096);
+        assert_eq!(traffic.details().limit_in_bytes_per_second, 4096);
+
+        monitor.delete_bucket_throttle("bucket", "arn:1");
+        assert!(monitor.report("bucket").bucket_stats.is_empty());
+        assert!(matches!(traffic.acquire(1 << 20), Acquire::Unlimited));
+    }
+}
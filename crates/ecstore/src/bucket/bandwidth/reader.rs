@@ -0,0 +1,104 @@
+
+
+use super::monitor::{Acquire, TargetTraffic};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, ready};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::time::Sleep;
+
+/// Reader that holds back data to stay within a target's bandwidth limit and records what
+/// was read in the target's measurement
+pub struct MonitoredReader<R> {
+    inner: R,
+    traffic: Arc<TargetTraffic>,
+    // Tokens acquired for data not read yet
+    granted: usize,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<R> MonitoredReader<R> {
+    pub(crate) fn new(inner: R, traffic: Arc<TargetTraffic>) -> Self {
+        Self {
+            inner,
+            traffic,
+            granted: 0,
+            sleep: None,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for MonitoredReader<R> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let limit = loop {
+            if this.granted > 0 {
+                break this.granted.min(buf.remaining());
+            }
+            if let Some(sleep) = this.sleep.as_mut() {
+                ready!(sleep.as_mut().poll(cx));
+                this.sleep = None;
+            }
+            match this.traffic.acquire(buf.remaining()) {
+                Acquire::Unlimited => break buf.remaining(),
+                Acquire::Granted(n) => this.granted = n,
+                Acquire::Wait(wait) => this.sleep = Some(Box::pin(tokio::time::sleep(wait))),
+            }
+        };
+
+        let mut limited = buf.take(limit);
+        ready!(Pin::new(&mut this.inner).poll_read(cx, &mut limited))?;
+        let n = limited.filled().len();
+        // SAFETY: the inner reader initialized and filled `n` bytes of the unfilled part of `buf`
+        unsafe { buf.assume_init(n) };
+        buf.advance(n);
+
+        this.traffic.record(n);
+        if n == 0 {
+            // End of stream, hand back what is left
+            this.traffic.release(this.granted);
+            this.granted = 0;
+        } else {
+            this.granted = this.granted.saturating_sub(n);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R> Drop for MonitoredReader<R> {
+    fn drop(&mut self) {
+        if self.granted > 0 {
+            self.traffic.release(self.granted);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bucket::bandwidth::Monitor;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_monitored_reader_throttles() {
+        let monitor = Monitor::new();
+        monitor.set_bandwidth_limit("bucket", "arn", 1000);
+
+        let data = vec![7u8; 1500];
+        let mut reader = monitor.monitored_reader("bucket", "arn", data.as_slice());
+
+        let start = std::time::Instant::now();
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).await.unwrap();
+
+        assert_eq!(out, data);
+        // The bucket starts empty, 1500 bytes at 1000 bytes per second take 1.5 seconds
+        assert!(start.elapsed() >= std::time::Duration::from_millis(1400));
+    }
+}
@@ -1,5 +1,6 @@
 
 
+use crate::bucket::bandwidth::get_global_bucket_monitor;
 use crate::bucket::metadata::BucketMetadata;
 use aws_credential_types::Credentials as SdkCredentials;
 use aws_sdk_s3::config::Region as SdkRegion;
@@ -651,9 +652,12 @@ impl BucketTargetSys {
         Ok(true)
     }
 
-    fn update_bandwidth_limit(&self, _bucket: &str, _arn: &str, _limit: i64) {
-        // Implementation for bandwidth limit update
-        // This would interact with the global bucket monitor
+    fn update_bandwidth_limit(&self, bucket: &str, arn: &str, limit: i64) {
+        if limit <= 0 {
+            get_global_bucket_monitor().delete_bucket_throttle(bucket, arn);
+            return;
+        }
+        get_global_bucket_monitor().set_bandwidth_limit(bucket, arn, limit);
     }
 
     pub async fn get_remote_target_client_by_arn(&self, _bucket: &str, arn: &str) -> Option<Arc<TargetClient>> {
@@ -0,0 +1,144 @@
+
+
+use s3s::dto::{CORSConfiguration, CORSRule};
+
+/// S3 allows at most this many rules in one configuration
+pub const MAX_CORS_RULES: usize = 100;
+
+const ALLOWED_METHODS: [&str; 5] = ["GET", "PUT", "HEAD", "POST", "DELETE"];
+
+pub trait CorsApi {
+    fn validate(&self) -> Result<(), std::io::Error>;
+    fn find_rule(&self, origin: &str, method: &str, request_headers: &[&str]) -> Option<&CORSRule>;
+}
+
+impl CorsApi for CORSConfiguration {
+    fn validate(&self) -> Result<(), std::io::Error> {
+        if self.cors_rules.is_empty() {
+            return Err(std::io::Error::other("CORS configuration must contain at least one rule"));
+        }
+        if self.cors_rules.len() > MAX_CORS_RULES {
+            return Err(std::io::Error::other(format!(
+                "CORS configuration must not contain more than {MAX_CORS_RULES} rules"
+            )));
+        }
+
+        for rule in self.cors_rules.iter() {
+            if rule.allowed_origins.is_empty() {
+                return Err(std::io::Error::other("CORS rule must have at least one AllowedOrigin"));
+            }
+            if rule.allowed_methods.is_empty() {
+                return Err(std::io::Error::other("CORS rule must have at least one AllowedMethod"));
+            }
+            if let Some(method) = rule.allowed_methods.iter().find(|m| !ALLOWED_METHODS.contains(&m.as_str())) {
+                return Err(std::io::Error::other(format!("unsupported CORS AllowedMethod: {method}")));
+            }
+            if let Some(origin) = rule.allowed_origins.iter().find(|o| o.matches('*').count() > 1) {
+                return Err(std::io::Error::other(format!(
+                    "CORS AllowedOrigin can not have more than one wildcard: {origin}"
+                )));
+            }
+            if let Some(header) = rule.allowed_headers.iter().flatten().find(|h| h.matches('*').count() > 1) {
+                return Err(std::io::Error::other(format!(
+                    "CORS AllowedHeader can not have more than one wildcard: {header}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// First rule that allows `origin` to send a `method` request with `request_headers`
+    fn find_rule(&self, origin: &str, method: &str, request_headers: &[&str]) -> Option<&CORSRule> {
+        self.cors_rules.iter().find(|rule| {
+            rule.allowed_origins.iter().any(|o| wildcard_match(o, origin))
+                && rule.allowed_methods.iter().any(|m| m == method)
+                && request_headers.iter().all(|header| {
+                    rule.allowed_headers
+                        .iter()
+                        .flatten()
+                        .any(|allowed| wildcard_match(&allowed.to_ascii_lowercase(), &header.to_ascii_lowercase()))
+                })
+        })
+    }
+}
+
+/// Match `value` against a pattern with at most one `*`, which matches any run of characters
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            value.len() >= prefix.len() + suffix.len() && value.starts_with(prefix) && value.ends_with(suffix)
+        }
+        None => pattern == value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(origins: &[&str], methods: &[&str], headers: Option<&[&str]>) -> CORSRule {
+        CORSRule {
+            allowed_headers: headers.map(|h| h.iter().map(|s| s.to_string()).collect()),
+            allowed_methods: methods.iter().map(|s| s.to_string()).collect(),
+            allowed_origins: origins.iter().map(|s| s.to_string()).collect(),
+            expose_headers: None,
+            id: None,
+            max_age_seconds: None,
+        }
+    }
+
+    #[test]
+    fn test_find_rule() {
+        let config = CORSConfiguration {
+            cors_rules: vec![
+                rule(&["https://app.example.com"], &["GET", "PUT"], Some(&["x-amz-*", "content-type"])),
+                rule(&["https://*.example.org"], &["GET"], None),
+            ],
+        };
+
+        assert!(
+            config
+                .find_rule("https://app.example.com", "PUT", &["Content-Type", "x-amz-date"])
+                .is_some()
+        );
+        assert!(config.find_rule("https://app.example.com", "DELETE", &[]).is_none());
+        assert!(
+            config
+                .find_rule("https://app.example.com", "PUT", &["authorization"])
+                .is_none()
+        );
+        assert!(config.find_rule("https://cdn.example.org", "GET", &[]).is_some());
+        assert!(config.find_rule("https://cdn.example.org", "GET", &["x-custom"]).is_none());
+        assert!(config.find_rule("https://evil.com", "GET", &[]).is_none());
+    }
+
+    #[test]
+    fn test_validate() {
+        let ok = CORSConfiguration {
+            cors_rules: vec![rule(&["*"], &["GET"], None)],
+        };
+        assert!(ok.validate().is_ok());
+
+        let bad_method = CORSConfiguration {
+            cors_rules: vec![rule(&["*"], &["PATCH"], None)],
+        };
+        assert!(bad_method.validate().is_err());
+
+        let bad_origin = CORSConfiguration {
+            cors_rules: vec![rule(&["https://*.*.example.com"], &["GET"], None)],
+        };
+        assert!(bad_origin.validate().is_err());
+
+        assert!(CORSConfiguration { cors_rules: vec![] }.validate().is_err());
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("http://*.example.com", "http://a.example.com"));
+        assert!(!wildcard_match("http://*.example.com", "http://example.com"));
+        assert!(!wildcard_match("ab*ba", "aba"));
+        assert!(wildcard_match("exact", "exact"));
+    }
+}
@@ -18,7 +18,7 @@ use time::macros::{datetime, offset};
 use time::{self, Duration, OffsetDateTime};
 use tracing::info;
 
-use crate::bucket::lifecycle::rule::TransitionOps;
+use crate::bucket::lifecycle::rule::{Filter, TransitionOps};
 
 pub const TRANSITION_COMPLETE: &str = "complete";
 pub const TRANSITION_PENDING: &str = "pending";
@@ -26,10 +26,11 @@ pub const TRANSITION_PENDING: &str = "pending";
 const ERR_LIFECYCLE_TOO_MANY_RULES: &str = "Lifecycle configuration allows a maximum of 1000 rules";
 const ERR_LIFECYCLE_NO_RULE: &str = "Lifecycle configuration should have at least one rule";
 const ERR_LIFECYCLE_DUPLICATE_ID: &str = "Rule ID must be unique. Found same ID for more than one rule";
-const _ERR_XML_NOT_WELL_FORMED: &str =
-    "The XML you provided was not well-formed or did not validate against our published schema";
+const ERR_XML_NOT_WELL_FORMED: &str = "The XML you provided was not well-formed or did not validate against our published schema";
 const ERR_LIFECYCLE_BUCKET_LOCKED: &str =
     "ExpiredObjectAllVersions element and DelMarkerExpiration action cannot be used on an retention bucket";
+const ERR_LIFECYCLE_DELETE_MARKER_WITH_TAGS: &str = "ExpiredObjectDeleteMarker cannot be specified with a Tag filter";
+const ERR_LIFECYCLE_ABORT_UPLOAD_WITH_TAGS: &str = "AbortIncompleteMultipartUpload cannot be specified with a Tag filter";
 
 pub use nebulafx_common::metrics::IlmAction;
 
@@ -111,10 +112,37 @@ impl RuleValidate for LifecycleRule {
         if !self.expiration.set && !self.transition.set && !self.noncurrent_version_expiration.set && !self.noncurrent_version_transitions.unwrap()[0].set && self.delmarker_expiration.Empty() {
           return errXMLNotWellFormed
         }*/
+        // The deprecated rule level Prefix and Filter are mutually exclusive
+        if self.prefix.is_some() && self.filter.is_some() {
+            return Err(std::io::Error::other(ERR_XML_NOT_WELL_FORMED));
+        }
+        if let Some(filter) = &self.filter {
+            Filter::validate(filter)?;
+            let delete_marker = self
+                .expiration
+                .as_ref()
+                .is_some_and(|e| e.expired_object_delete_marker == Some(true));
+            if delete_marker && filter.has_tags() {
+                return Err(std::io::Error::other(ERR_LIFECYCLE_DELETE_MARKER_WITH_TAGS));
+            }
+            // Multipart uploads carry no tags yet
+            if self.abort_incomplete_multipart_upload.is_some() && filter.has_tags() {
+                return Err(std::io::Error::other(ERR_LIFECYCLE_ABORT_UPLOAD_WITH_TAGS));
+            }
+        }
         Ok(())
     }
 }
 
+/// Key name prefix the rule applies to, from the deprecated rule Prefix or the Filter
+fn rule_prefix(rule: &LifecycleRule) -> &str {
+    match (&rule.prefix, &rule.filter) {
+        (Some(prefix), _) => prefix,
+        (None, Some(filter)) => filter.prefix(),
+        (None, None) => "",
+    }
+}
+
 #[async_trait::async_trait]
 pub trait Lifecycle {
     async fn has_transition(&self) -> bool;
@@ -124,6 +152,7 @@ pub trait Lifecycle {
     async fn filter_rules(&self, obj: &ObjectOpts) -> Option<Vec<LifecycleRule>>;
     async fn eval(&self, obj: &ObjectOpts) -> Event;
     async fn eval_inner(&self, obj: &ObjectOpts, now: OffsetDateTime) -> Event;
+    fn eval_incomplete_upload(&self, object: &str, initiated: OffsetDateTime, now: OffsetDateTime) -> Event;
     //fn set_prediction_headers(&self, w: http.ResponseWriter, obj: ObjectOpts);
     async fn noncurrent_versions_expiration_limit(self: Arc<Self>, obj: &ObjectOpts) -> Event;
 }
@@ -157,7 +186,7 @@ impl Lifecycle for BucketLifecycleConfiguration {
                 continue;
             }
 
-            let rule_prefix = rule.prefix.as_ref().expect("err!");
+            let rule_prefix = rule_prefix(rule);
             if prefix.len() > 0 && rule_prefix.len() > 0 && !prefix.starts_with(rule_prefix) && !rule_prefix.starts_with(&prefix)
             {
                 continue;
@@ -244,17 +273,17 @@ impl Lifecycle for BucketLifecycleConfiguration {
             if rule.status.as_str() == ExpirationStatus::DISABLED {
                 continue;
             }
-            if let Some(prefix) = rule.prefix.clone() {
-                if !obj.name.starts_with(prefix.as_str()) {
+            if !obj.name.starts_with(rule_prefix(rule)) {
+                continue;
+            }
+            if let Some(filter) = &rule.filter {
+                if !Filter::test_tags(filter, &obj.user_tags) {
+                    continue;
+                }
+                // Delete markers have no size
+                if !obj.delete_marker && !Filter::by_size(filter, obj.size as i64) {
                     continue;
                 }
-            }
-            /*if !rule.filter.test_tags(obj.user_tags) {
-                continue;
-            }*/
-            //if !obj.delete_marker && !rule.filter.BySize(obj.size) {
-            if !obj.delete_marker && false {
-                continue;
             }
             rules.push(rule.clone());
         }
@@ -265,6 +294,40 @@ impl Lifecycle for BucketLifecycleConfiguration {
         self.eval_inner(obj, OffsetDateTime::now_utc()).await
     }
 
+    /// Evaluate AbortIncompleteMultipartUpload for an upload of `object` initiated at `initiated`
+    fn eval_incomplete_upload(&self, object: &str, initiated: OffsetDateTime, now: OffsetDateTime) -> Event {
+        let mut due_rules = Vec::new();
+        for rule in self.rules.iter() {
+            if rule.status.as_str() == ExpirationStatus::DISABLED || !object.starts_with(rule_prefix(rule)) {
+                continue;
+            }
+            let Some(days) = rule
+                .abort_incomplete_multipart_upload
+                .as_ref()
+                .and_then(|abort| abort.days_after_initiation)
+            else {
+                continue;
+            };
+            let due = expected_expiry_time(initiated, days);
+            if now.unix_timestamp() >= due.unix_timestamp() {
+                due_rules.push((due, rule.id.clone().unwrap_or_default()));
+            }
+        }
+
+        // The earliest due rule wins, like for expiration
+        match due_rules.into_iter().min_by_key(|(due, _)| *due) {
+            Some((due, rule_id)) => Event {
+                action: IlmAction::AbortMultipartUploadAction,
+                rule_id,
+                due: Some(due),
+                noncurrent_days: 0,
+                newer_noncurrent_versions: 0,
+                storage_class: "".into(),
+            },
+            None => Event::default(),
+        }
+    }
+
     async fn eval_inner(&self, obj: &ObjectOpts, now: OffsetDateTime) -> Event {
         let mut events = Vec::<Event>::new();
         info!(
@@ -705,3 +768,35 @@ impl Default for TransitionOptions {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bucket::utils::deserialize;
+
+    #[test]
+    fn test_eval_incomplete_upload_days_boundary() {
+        let lc = deserialize::<BucketLifecycleConfiguration>(
+            br#"<LifecycleConfiguration><Rule><ID>abort-uploads</ID><Status>Enabled</Status>
+            <Filter><Prefix>logs/</Prefix></Filter>
+            <AbortIncompleteMultipartUpload><DaysAfterInitiation>3</DaysAfterInitiation></AbortIncompleteMultipartUpload>
+            </Rule></LifecycleConfiguration>"#,
+        )
+        .unwrap();
+
+        let initiated = datetime!(2024-05-01 10:30:00 UTC);
+        let due = initiated + Duration::days(3);
+
+        let event = lc.eval_incomplete_upload("logs/a.log", initiated, due - Duration::seconds(1));
+        assert_eq!(event.action, IlmAction::NoneAction);
+
+        let event = lc.eval_incomplete_upload("logs/a.log", initiated, due);
+        assert_eq!(event.action, IlmAction::AbortMultipartUploadAction);
+        assert_eq!(event.rule_id, "abort-uploads");
+        assert_eq!(event.due, Some(due));
+
+        // Uploads outside the prefix of the rule are kept
+        let event = lc.eval_incomplete_upload("data/a.bin", initiated, due + Duration::days(30));
+        assert_eq!(event.action, IlmAction::NoneAction);
+    }
+}
@@ -6,7 +6,10 @@
 #![allow(unused_must_use)]
 #![allow(clippy::all)]
 
-use s3s::dto::{LifecycleRuleFilter, Transition};
+use s3s::dto::{LifecycleRuleFilter, Tag, Transition};
+use std::collections::HashSet;
+
+use crate::bucket::tagging::decode_tags_to_map;
 
 const _ERR_TRANSITION_INVALID_DAYS: &str = "Days must be 0 or greater when used with Transition";
 const _ERR_TRANSITION_INVALID_DATE: &str = "Date must be provided in ISO 8601 format";
@@ -14,21 +17,125 @@ const ERR_TRANSITION_INVALID: &str =
     "Exactly one of Days (0 or greater) or Date (positive ISO 8601 format) should be present in Transition.";
 const _ERR_TRANSITION_DATE_NOT_MIDNIGHT: &str = "'Date' must be at midnight GMT";
 
+const ERR_FILTER_INVALID: &str =
+    "Filter must have exactly one of Prefix, Tag, ObjectSizeGreaterThan, ObjectSizeLessThan or And specified";
+const ERR_FILTER_AND_INVALID: &str = "And must combine at least two of Prefix, Tags, ObjectSizeGreaterThan or ObjectSizeLessThan";
+const ERR_FILTER_DUPLICATE_TAG_KEY: &str = "Duplicate Tag Keys are not allowed";
+const ERR_FILTER_INVALID_TAG_KEY: &str = "The TagKey you have provided is invalid";
+const ERR_FILTER_NEGATIVE_SIZE: &str = "ObjectSizeGreaterThan and ObjectSizeLessThan must not be negative";
+const ERR_FILTER_INVALID_SIZE_RANGE: &str = "ObjectSizeLessThan must be greater than ObjectSizeGreaterThan";
+
 pub trait Filter {
+    /// Key name prefix of the filter, either given directly or inside And
+    fn prefix(&self) -> &str;
+    /// Whether the filter selects objects by tag
+    fn has_tags(&self) -> bool;
+    /// Every tag of the filter is present with the same value in the url-encoded `user_tags`
     fn test_tags(&self, user_tags: &str) -> bool;
+    /// `sz` is within the object size bounds of the filter
     fn by_size(&self, sz: i64) -> bool;
+    fn validate(&self) -> Result<(), std::io::Error>;
 }
 
 impl Filter for LifecycleRuleFilter {
+    fn prefix(&self) -> &str {
+        self.prefix
+            .as_deref()
+            .or_else(|| self.and.as_ref().and_then(|and| and.prefix.as_deref()))
+            .unwrap_or_default()
+    }
+
+    fn has_tags(&self) -> bool {
+        filter_tags(self).next().is_some()
+    }
+
     fn test_tags(&self, user_tags: &str) -> bool {
-        true
+        if !self.has_tags() {
+            return true;
+        }
+
+        let object_tags = decode_tags_to_map(user_tags);
+        filter_tags(self).all(|tag| {
+            let key = tag.key.as_deref().unwrap_or_default();
+            let value = tag.value.as_deref().unwrap_or_default();
+            object_tags.get(key).is_some_and(|v| v == value)
+        })
     }
 
     fn by_size(&self, sz: i64) -> bool {
-        true
+        let and = self.and.as_ref();
+        let greater_than = self
+            .object_size_greater_than
+            .or_else(|| and.and_then(|and| and.object_size_greater_than));
+        let less_than = self
+            .object_size_less_than
+            .or_else(|| and.and_then(|and| and.object_size_less_than));
+
+        greater_than.is_none_or(|gt| sz > gt) && less_than.is_none_or(|lt| sz < lt)
+    }
+
+    fn validate(&self) -> Result<(), std::io::Error> {
+        let predicates = [
+            self.prefix.is_some(),
+            self.tag.is_some(),
+            self.object_size_greater_than.is_some(),
+            self.object_size_less_than.is_some(),
+            self.and.is_some(),
+        ];
+        // An empty filter applies the rule to the whole bucket
+        if predicates.iter().filter(|set| **set).count() > 1 {
+            return Err(std::io::Error::other(ERR_FILTER_INVALID));
+        }
+
+        let (greater_than, less_than) = match &self.and {
+            Some(and) => {
+                let predicates = [
+                    and.prefix.is_some(),
+                    and.tags.as_ref().is_some_and(|tags| !tags.is_empty()),
+                    and.object_size_greater_than.is_some(),
+                    and.object_size_less_than.is_some(),
+                ];
+                let tag_count = and.tags.as_ref().map_or(0, Vec::len);
+                // Several tags alone are a valid combination
+                if predicates.iter().filter(|set| **set).count() < 2 && tag_count < 2 {
+                    return Err(std::io::Error::other(ERR_FILTER_AND_INVALID));
+                }
+                (and.object_size_greater_than, and.object_size_less_than)
+            }
+            None => (self.object_size_greater_than, self.object_size_less_than),
+        };
+
+        let mut keys = HashSet::new();
+        for tag in filter_tags(self) {
+            let key = tag.key.as_deref().unwrap_or_default();
+            if key.is_empty() || key.len() > 128 {
+                return Err(std::io::Error::other(ERR_FILTER_INVALID_TAG_KEY));
+            }
+            if !keys.insert(key) {
+                return Err(std::io::Error::other(ERR_FILTER_DUPLICATE_TAG_KEY));
+            }
+        }
+
+        if greater_than.is_some_and(|gt| gt < 0) || less_than.is_some_and(|lt| lt < 0) {
+            return Err(std::io::Error::other(ERR_FILTER_NEGATIVE_SIZE));
+        }
+        if let (Some(gt), Some(lt)) = (greater_than, less_than) {
+            if lt <= gt {
+                return Err(std::io::Error::other(ERR_FILTER_INVALID_SIZE_RANGE));
+            }
+        }
+
+        Ok(())
     }
 }
 
+fn filter_tags(filter: &LifecycleRuleFilter) -> impl Iterator<Item = &Tag> {
+    filter
+        .tag
+        .iter()
+        .chain(filter.and.iter().flat_map(|and| and.tags.iter().flatten()))
+}
+
 pub trait TransitionOps {
     fn validate(&self) -> Result<(), std::io::Error>;
 }
@@ -49,9 +156,127 @@ impl TransitionOps for Transition {
 #[cfg(test)]
 mod test {
     use super::*;
+    use s3s::dto::LifecycleRuleAndOperator;
 
     #[tokio::test]
     async fn test_rule() {
         //assert!(skip_access_checks(p.to_str().unwrap()));
     }
+
+    fn tag(key: &str, value: &str) -> Tag {
+        Tag {
+            key: Some(key.to_string()),
+            value: Some(value.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_filter_tags() {
+        // <Filter><Tag><Key>key1</Key><Value>value1</Value></Tag></Filter>
+        let filter = LifecycleRuleFilter {
+            tag: Some(tag("key1", "value1")),
+            ..Default::default()
+        };
+        assert!(filter.has_tags());
+        assert!(filter.test_tags("key1=value1"));
+        assert!(filter.test_tags("key0=value0&key1=value1"));
+        assert!(!filter.test_tags("key1=value2"));
+        assert!(!filter.test_tags(""));
+
+        // <Filter><And><Prefix>key-prefix</Prefix><Tag>..</Tag><Tag>..</Tag></And></Filter>
+        let filter = LifecycleRuleFilter {
+            and: Some(LifecycleRuleAndOperator {
+                prefix: Some("key-prefix".to_string()),
+                tags: Some(vec![tag("key1", "value1"), tag("key2", "value 2")]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(filter.prefix(), "key-prefix");
+        assert!(filter.test_tags("key1=value1&key2=value%202"));
+        assert!(!filter.test_tags("key1=value1"));
+
+        assert!(LifecycleRuleFilter::default().test_tags(""));
+    }
+
+    #[test]
+    fn test_filter_by_size() {
+        // <Filter><ObjectSizeGreaterThan>500</ObjectSizeGreaterThan></Filter>
+        let filter = LifecycleRuleFilter {
+            object_size_greater_than: Some(500),
+            ..Default::default()
+        };
+        assert!(!filter.by_size(500));
+        assert!(filter.by_size(501));
+
+        // <Filter><And><ObjectSizeGreaterThan>500</ObjectSizeGreaterThan><ObjectSizeLessThan>64000</ObjectSizeLessThan></And></Filter>
+        let filter = LifecycleRuleFilter {
+            and: Some(LifecycleRuleAndOperator {
+                object_size_greater_than: Some(500),
+                object_size_less_than: Some(64000),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(filter.by_size(1024));
+        assert!(!filter.by_size(100));
+        assert!(!filter.by_size(64000));
+
+        assert!(LifecycleRuleFilter::default().by_size(0));
+    }
+
+    #[test]
+    fn test_filter_validate() {
+        assert!(LifecycleRuleFilter::default().validate().is_ok());
+
+        let prefix_and_tag = LifecycleRuleFilter {
+            prefix: Some("logs/".to_string()),
+            tag: Some(tag("key1", "value1")),
+            ..Default::default()
+        };
+        assert!(prefix_and_tag.validate().is_err());
+
+        let single_predicate_and = LifecycleRuleFilter {
+            and: Some(LifecycleRuleAndOperator {
+                prefix: Some("logs/".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(single_predicate_and.validate().is_err());
+
+        let two_tags = LifecycleRuleFilter {
+            and: Some(LifecycleRuleAndOperator {
+                tags: Some(vec![tag("key1", "value1"), tag("key2", "value2")]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(two_tags.validate().is_ok());
+
+        let duplicate_tags = LifecycleRuleFilter {
+            and: Some(LifecycleRuleAndOperator {
+                tags: Some(vec![tag("key1", "value1"), tag("key1", "value2")]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(duplicate_tags.validate().is_err());
+
+        let bad_range = LifecycleRuleFilter {
+            and: Some(LifecycleRuleAndOperator {
+                object_size_greater_than: Some(64000),
+                object_size_less_than: Some(500),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(bad_range.validate().is_err());
+
+        let negative = LifecycleRuleFilter {
+            object_size_less_than: Some(-1),
+            ..Default::default()
+        };
+        assert!(negative.validate().is_err());
+    }
 }
@@ -1,6 +1,8 @@
 
 
+pub mod bandwidth;
 pub mod bucket_target_sys;
+pub mod cors;
 pub mod error;
 pub mod lifecycle;
 pub mod metadata;
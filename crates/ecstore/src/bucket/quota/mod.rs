@@ -1,19 +1,26 @@
 
 
+mod quota_sys;
+
+pub use quota_sys::BucketQuotaSys;
+
 use crate::error::Result;
-use rmp_serde::Serializer as rmpSerializer;
 use serde::{Deserialize, Serialize};
 
 // Define the QuotaType enum
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum QuotaType {
+    #[serde(rename = "hard", alias = "Hard")]
     Hard,
 }
 
-// Define the BucketQuota structure
-#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+// Define the BucketQuota structure, stored as JSON in quota.json
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq, Eq)]
+#[serde(default)]
 pub struct BucketQuota {
-    quota: Option<u64>, // Use Option to represent optional fields
+    // Deprecated, superseded by `size`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quota: Option<u64>,
 
     size: u64,
 
@@ -21,20 +28,70 @@ pub struct BucketQuota {
 
     requests: u64,
 
+    #[serde(rename = "quotatype", alias = "quotaType", alias = "quota_type", skip_serializing_if = "Option::is_none")]
     quota_type: Option<QuotaType>,
 }
 
 impl BucketQuota {
-    pub fn marshal_msg(&self) -> Result<Vec<u8>> {
-        let mut buf = Vec::new();
+    /// A hard quota of `size` bytes
+    pub fn hard(size: u64) -> Self {
+        Self {
+            size,
+            quota_type: Some(QuotaType::Hard),
+            ..Default::default()
+        }
+    }
 
-        self.serialize(&mut rmpSerializer::new(&mut buf).with_struct_map())?;
+    /// Maximum number of bytes allowed in the bucket, 0 means unlimited
+    pub fn size(&self) -> u64 {
+        if self.size > 0 { self.size } else { self.quota.unwrap_or_default() }
+    }
 
-        Ok(buf)
+    pub fn is_hard(&self) -> bool {
+        // Configs written before the type was introduced are hard quotas
+        matches!(self.quota_type, None | Some(QuotaType::Hard))
+    }
+
+    /// Whether writing `incoming` more bytes on top of `usage` breaks a hard limit
+    pub fn exceeded(&self, usage: u64, incoming: u64) -> bool {
+        let limit = self.size();
+        self.is_hard() && limit > 0 && usage.saturating_add(incoming) > limit
+    }
+
+    pub fn marshal(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
     }
 
     pub fn unmarshal(buf: &[u8]) -> Result<Self> {
-        let t: BucketQuota = rmp_serde::from_slice(buf)?;
+        let t: BucketQuota = serde_json::from_slice(buf)?;
         Ok(t)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unmarshal_accepts_legacy_and_current_fields() {
+        let legacy = BucketQuota::unmarshal(br#"{"quota":1073741824,"quotaType":"hard"}"#).unwrap();
+        assert_eq!(legacy.size(), 1073741824);
+        assert!(legacy.is_hard());
+
+        let current = BucketQuota::unmarshal(br#"{"size":1024,"quotatype":"hard"}"#).unwrap();
+        assert_eq!(current.size(), 1024);
+
+        let roundtrip = BucketQuota::unmarshal(&BucketQuota::hard(2048).marshal().unwrap()).unwrap();
+        assert_eq!(roundtrip, BucketQuota::hard(2048));
+    }
+
+    #[test]
+    fn test_exceeded() {
+        let quota = BucketQuota::hard(100);
+        assert!(!quota.exceeded(60, 40));
+        assert!(quota.exceeded(60, 41));
+        assert!(quota.exceeded(u64::MAX, 1));
+
+        assert!(!BucketQuota::default().exceeded(u64::MAX, u64::MAX));
+    }
+}
@@ -0,0 +1,145 @@
+
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use lazy_static::lazy_static;
+use tokio::sync::RwLock;
+use tracing::warn;
+
+use super::BucketQuota;
+use crate::bucket::metadata_sys;
+use crate::data_usage::load_data_usage_from_backend;
+use crate::error::{Error, Result};
+use crate::new_object_layer_fn;
+
+/// How long a loaded data usage snapshot is reused before reading it again
+const USAGE_CACHE_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct UsageSnapshot {
+    loaded_at: Option<Instant>,
+    last_update: Option<SystemTime>,
+    buckets: HashMap<String, u64>,
+}
+
+lazy_static! {
+    static ref USAGE_SNAPSHOT: RwLock<UsageSnapshot> = RwLock::new(UsageSnapshot::default());
+    // Bytes written per bucket since the current scanner snapshot was published
+    static ref WRITTEN_SINCE_SNAPSHOT: Mutex<HashMap<String, u64>> = Mutex::new(HashMap::new());
+}
+
+/// Enforces bucket quotas on the write path.
+///
+/// Usage is the size reported by the last scanner cycle plus the bytes written
+/// through this node since then. Deletes are not subtracted until the next
+/// cycle, so the check errs on the side of rejecting writes.
+pub struct BucketQuotaSys {}
+
+impl BucketQuotaSys {
+    pub async fn get(bucket: &str) -> Result<Option<BucketQuota>> {
+        match metadata_sys::get_quota_config(bucket).await {
+            Ok((quota, _)) => Ok(Some(quota)),
+            Err(Error::ConfigNotFound) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Hard quota of `bucket`, `None` when writes to it are not limited. Callers that need work to
+    /// find the size of a write check this first.
+    pub async fn hard_quota(bucket: &str) -> Option<BucketQuota> {
+        match Self::get(bucket).await {
+            Ok(Some(quota)) if quota.is_hard() && quota.size() > 0 => Some(quota),
+            Ok(_) => None,
+            Err(err) => {
+                warn!("get quota config for {} failed: {:?}", bucket, err);
+                None
+            }
+        }
+    }
+
+    /// Reject a write of `size` bytes if it would push the bucket over its hard quota.
+    ///
+    /// A negative `size` (unknown length) is checked as zero, which still rejects
+    /// writes to a bucket that is already full.
+    pub async fn check_bucket_quota(bucket: &str, size: i64) -> Result<()> {
+        let Some(quota) = Self::hard_quota(bucket).await else {
+            return Ok(());
+        };
+
+        let usage = Self::bucket_usage(bucket).await;
+        if quota.exceeded(usage, size.max(0) as u64) {
+            return Err(Error::BucketQuotaExceeded(bucket.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Account for bytes written to `bucket` until the next scanner cycle reports them
+    pub fn record_write(bucket: &str, size: i64) {
+        if size <= 0 {
+            return;
+        }
+        let mut written = WRITTEN_SINCE_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = written.entry(bucket.to_string()).or_default();
+        *entry = entry.saturating_add(size as u64);
+    }
+
+    /// Last scanner-reported size of `bucket` plus what was written since
+    pub async fn bucket_usage(bucket: &str) -> u64 {
+        Self::refresh_snapshot().await;
+
+        let scanned = USAGE_SNAPSHOT.read().await.buckets.get(bucket).copied().unwrap_or_default();
+        let written = WRITTEN_SINCE_SNAPSHOT
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(bucket)
+            .copied()
+            .unwrap_or_default();
+
+        scanned.saturating_add(written)
+    }
+
+    async fn refresh_snapshot() {
+        if USAGE_SNAPSHOT
+            .read()
+            .await
+            .loaded_at
+            .is_some_and(|t| t.elapsed() < USAGE_CACHE_TTL)
+        {
+            return;
+        }
+
+        let mut snapshot = USAGE_SNAPSHOT.write().await;
+        // Another writer may have refreshed it while we waited for the lock
+        if snapshot.loaded_at.is_some_and(|t| t.elapsed() < USAGE_CACHE_TTL) {
+            return;
+        }
+        snapshot.loaded_at = Some(Instant::now());
+
+        let Some(store) = new_object_layer_fn() else {
+            return;
+        };
+
+        let info = match load_data_usage_from_backend(store).await {
+            Ok(info) => info,
+            Err(err) => {
+                warn!("load data usage for quota failed: {:?}", err);
+                return;
+            }
+        };
+
+        if info.last_update != snapshot.last_update {
+            // The new cycle already counts what was written before it, start a fresh delta
+            WRITTEN_SINCE_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()).clear();
+            snapshot.last_update = info.last_update;
+        }
+
+        snapshot.buckets = info
+            .buckets_usage
+            .into_iter()
+            .map(|(bucket, usage)| (bucket, usage.size))
+            .collect();
+    }
+}
@@ -11,4 +11,5 @@ pub use config::*;
 pub use datatypes::*;
 pub use replication_pool::*;
 pub use replication_resyncer::*;
+pub use replication_state::SRMetricsSummary;
 pub use rule::*;
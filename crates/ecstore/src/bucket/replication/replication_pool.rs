@@ -735,12 +735,41 @@ impl<S: StorageAPI> ReplicationPool<S> {
         // global_site_resync_metrics.delete_bucket(bucket);
     }
 
+    /// Start resyncing the existing objects of `opts.bucket` to the target `opts.arn`
+    pub async fn start_resync(&self, opts: ResyncOpts) -> Result<(), EcstoreError> {
+        self.resyncer.clone().start(self.storage.clone(), opts).await
+    }
+
+    /// Cancel a running resync, returns false when none is running on this node
+    pub async fn cancel_resync(&self, bucket: &str, arn: &str) -> Result<bool, EcstoreError> {
+        self.resyncer.cancel(self.storage.clone(), bucket, arn).await
+    }
+
+    /// Whether a resync of the bucket to the target runs on this node
+    pub async fn resync_running(&self, bucket: &str, arn: &str) -> bool {
+        self.resyncer.is_running(bucket, arn).await
+    }
+
+    /// Resync status of every target of a bucket, whichever node runs the resync
+    pub async fn resync_status(&self, bucket: &str) -> Result<BucketReplicationResyncStatus, EcstoreError> {
+        let persisted = load_bucket_resync_metadata(bucket, self.storage.clone()).await?;
+        Ok(self.resyncer.status(bucket, persisted).await)
+    }
+
     /// Initialize bucket replication resync for all buckets
     pub async fn init_resync_internal(
         self: Arc<Self>,
         cancellation_token: CancellationToken,
         buckets: Vec<String>,
     ) -> Result<(), EcstoreError> {
+        self.resyncer.set_shutdown(cancellation_token.clone());
+
+        // Persist the progress of the running resyncs so every node can report it
+        let resyncer = self.resyncer.clone();
+        let storage = self.storage.clone();
+        let ctx = cancellation_token.clone();
+        tokio::spawn(async move { resyncer.persist_to_disk(ctx, storage).await });
+
         // Load bucket metadata system in background
         let pool_clone = self.clone();
 
@@ -853,7 +882,7 @@ async fn load_bucket_resync_metadata<S: StorageAPI>(
     let mut brs = BucketReplicationResyncStatus::new();
 
     // Constants that would be defined elsewhere
-    const REPLICATION_DIR: &str = "replication";
+    const REPLICATION_DIR: &str = ".replication";
     const RESYNC_FILE_NAME: &str = "resync.bin";
     const RESYNC_META_FORMAT: u16 = 1;
     const RESYNC_META_VERSION: u16 = 1;
@@ -912,6 +941,10 @@ pub trait ReplicationPoolTrait: std::fmt::Debug {
         cancellation_token: CancellationToken,
         buckets: Vec<String>,
     ) -> Result<(), EcstoreError>;
+    async fn start_resync(&self, opts: ResyncOpts) -> Result<(), EcstoreError>;
+    async fn cancel_resync(&self, bucket: &str, arn: &str) -> Result<bool, EcstoreError>;
+    async fn resync_running(&self, bucket: &str, arn: &str) -> bool;
+    async fn resync_status(&self, bucket: &str) -> Result<BucketReplicationResyncStatus, EcstoreError>;
 }
 
 // Implement the trait for ReplicationPool
@@ -936,6 +969,22 @@ impl<S: StorageAPI> ReplicationPoolTrait for ReplicationPool<S> {
     ) -> Result<(), EcstoreError> {
         self.init_resync_internal(cancellation_token, buckets).await
     }
+
+    async fn start_resync(&self, opts: ResyncOpts) -> Result<(), EcstoreError> {
+        self.start_resync(opts).await
+    }
+
+    async fn cancel_resync(&self, bucket: &str, arn: &str) -> Result<bool, EcstoreError> {
+        self.cancel_resync(bucket, arn).await
+    }
+
+    async fn resync_running(&self, bucket: &str, arn: &str) -> bool {
+        self.resync_running(bucket, arn).await
+    }
+
+    async fn resync_status(&self, bucket: &str) -> Result<BucketReplicationResyncStatus, EcstoreError> {
+        self.resync_status(bucket).await
+    }
 }
 
 lazy_static! {
@@ -1,3 +1,4 @@
+use crate::bucket::bandwidth::get_global_bucket_monitor;
 use crate::bucket::bucket_target_sys::{
     AdvancedPutOptions, BucketTargetSys, PutObjectOptions, PutObjectPartOptions, RemoveObjectOptions, TargetClient,
 };
@@ -128,6 +129,10 @@ pub struct ReplicationResyncer {
     pub worker_size: usize,
     pub resync_cancel_tx: CancellationToken,
     pub resync_cancel_rx: CancellationToken,
+    // Cancellation of the running resyncs by bucket and target ARN, with their resync id
+    target_cancels: Arc<RwLock<HashMap<(String, String), (String, CancellationToken)>>>,
+    // Server shutdown, set once the resyncs of the existing buckets are loaded
+    shutdown: std::sync::OnceLock<CancellationToken>,
     pub worker_tx: tokio::sync::broadcast::Sender<()>,
     pub worker_rx: tokio::sync::broadcast::Receiver<()>,
 }
@@ -149,6 +154,8 @@ impl ReplicationResyncer {
             worker_size: RESYNC_WORKER_COUNT,
             resync_cancel_tx,
             resync_cancel_rx,
+            target_cancels: Arc::new(RwLock::new(HashMap::new())),
+            shutdown: std::sync::OnceLock::new(),
             worker_tx,
             worker_rx,
         }
@@ -273,12 +280,154 @@ impl ReplicationResyncer {
         // TODO: Metrics
     }
 
+    /// Ties the resyncs started from now on to the server shutdown
+    pub fn set_shutdown(&self, shutdown: CancellationToken) {
+        let _ = self.shutdown.set(shutdown);
+    }
+
+    /// Start resyncing the existing objects of a bucket to the target `opts.arn`. Fails when a
+    /// resync of that target is already running on this node.
+    pub async fn start<S: StorageAPI>(self: Arc<Self>, storage: Arc<S>, opts: ResyncOpts) -> Result<()> {
+        if opts.bucket.is_empty() || opts.arn.is_empty() {
+            return Err(Error::other("bucket and arn are required to start a replication resync"));
+        }
+        let Some(shutdown) = self.shutdown.get().cloned() else {
+            return Err(Error::other("replication resync is not initialized"));
+        };
+
+        {
+            let mut target_cancels = self.target_cancels.write().await;
+            let key = (opts.bucket.clone(), opts.arn.clone());
+            if target_cancels.contains_key(&key) {
+                return Err(Error::other(format!(
+                    "replication resync of bucket {} to {} is already in progress",
+                    opts.bucket, opts.arn
+                )));
+            }
+            target_cancels.insert(key, (opts.resync_id.clone(), self.resync_cancel_rx.child_token()));
+        }
+
+        let bucket_status = {
+            let mut status_map = self.status_map.write().await;
+            let bucket_status = status_map
+                .entry(opts.bucket.clone())
+                .or_insert_with(BucketReplicationResyncStatus::new);
+
+            let now = OffsetDateTime::now_utc();
+            bucket_status.targets_map.insert(
+                opts.arn.clone(),
+                TargetReplicationResyncStatus {
+                    start_time: Some(now),
+                    last_update: Some(now),
+                    resync_id: opts.resync_id.clone(),
+                    resync_before_date: opts.resync_before,
+                    resync_status: ResyncStatusType::ResyncPending,
+                    bucket: opts.bucket.clone(),
+                    ..Default::default()
+                },
+            );
+            bucket_status.last_update = Some(now);
+            bucket_status.clone()
+        };
+
+        if let Err(err) = save_resync_status(&opts.bucket, &bucket_status, storage.clone()).await {
+            self.target_cancels
+                .write()
+                .await
+                .remove(&(opts.bucket.clone(), opts.arn.clone()));
+            return Err(err);
+        }
+
+        tokio::spawn(self.resync_bucket(shutdown, storage, false, opts));
+
+        Ok(())
+    }
+
+    /// Cancel the resync of a bucket to a target, returns false when none is running on this node
+    pub async fn cancel<S: StorageAPI>(&self, storage: Arc<S>, bucket: &str, arn: &str) -> Result<bool> {
+        let Some((resync_id, token)) = self
+            .target_cancels
+            .write()
+            .await
+            .remove(&(bucket.to_string(), arn.to_string()))
+        else {
+            return Ok(false);
+        };
+        token.cancel();
+
+        let opts = ResyncOpts {
+            bucket: bucket.to_string(),
+            arn: arn.to_string(),
+            resync_id,
+            resync_before: None,
+        };
+        self.mark_status(ResyncStatusType::ResyncCanceled, opts, storage).await?;
+
+        Ok(true)
+    }
+
+    /// Whether a resync of the bucket to the target runs on this node
+    pub async fn is_running(&self, bucket: &str, arn: &str) -> bool {
+        self.target_cancels
+            .read()
+            .await
+            .contains_key(&(bucket.to_string(), arn.to_string()))
+    }
+
+    /// Resync status of every target of a bucket, from the status persisted by whichever node runs
+    /// the resync, updated with the progress of the resyncs running here
+    pub async fn status(&self, bucket: &str, persisted: BucketReplicationResyncStatus) -> BucketReplicationResyncStatus {
+        match self.status_map.read().await.get(bucket) {
+            Some(local) => merge_resync_status(persisted, local),
+            None => persisted,
+        }
+    }
+
+    /// Token cancelled when the resync described by `opts` is canceled
+    async fn resync_cancel_token(&self, opts: &ResyncOpts) -> CancellationToken {
+        let mut target_cancels = self.target_cancels.write().await;
+        let key = (opts.bucket.clone(), opts.arn.clone());
+        if let Some((resync_id, token)) = target_cancels.get(&key)
+            && *resync_id == opts.resync_id
+        {
+            return token.clone();
+        }
+
+        let token = self.resync_cancel_rx.child_token();
+        target_cancels.insert(key, (opts.resync_id.clone(), token.clone()));
+        token
+    }
+
     pub async fn resync_bucket<S: StorageAPI>(
         self: Arc<Self>,
         cancellation_token: CancellationToken,
         storage: Arc<S>,
         heal: bool,
         opts: ResyncOpts,
+    ) {
+        let resync_cancel = self.resync_cancel_token(&opts).await;
+
+        self.clone()
+            .resync_bucket_internal(cancellation_token, resync_cancel, storage, heal, opts.clone())
+            .await;
+
+        let mut target_cancels = self.target_cancels.write().await;
+        let key = (opts.bucket.clone(), opts.arn.clone());
+        if target_cancels
+            .get(&key)
+            .is_some_and(|(resync_id, _)| *resync_id == opts.resync_id)
+        {
+            target_cancels.remove(&key);
+        }
+    }
+
+    async fn resync_bucket_internal<S: StorageAPI>(
+        self: Arc<Self>,
+        cancellation_token: CancellationToken,
+        resync_cancel: CancellationToken,
+        storage: Arc<S>,
+        heal: bool,
+        opts: ResyncOpts,
     ) {
         let mut worker_rx = self.worker_rx.resubscribe();
 
@@ -287,6 +436,10 @@ impl ReplicationResyncer {
                 return;
             }
 
+            _ = resync_cancel.cancelled() => {
+                return;
+            }
+
             _ = worker_rx.recv() => {}
         }
 
@@ -407,7 +560,7 @@ impl ReplicationResyncer {
 
             let cancel_token = cancellation_token.clone();
             let target_client = target_client.clone();
-            let resync_cancel_rx = self.resync_cancel_rx.clone();
+            let resync_cancel_rx = resync_cancel.clone();
             let storage = storage.clone();
             let results_tx = results_tx.clone();
             let bucket_name = opts.bucket.clone();
@@ -498,7 +651,7 @@ impl ReplicationResyncer {
             futures.push(f);
         }
 
-        let resync_cancel_rx = self.resync_cancel_rx.clone();
+        let resync_cancel_rx = resync_cancel.clone();
 
         while let Some(res) = rx.recv().await {
             if let Some(err) = res.err {
@@ -664,6 +817,24 @@ pub async fn get_heal_replicate_object_info(oi: &ObjectInfo, rcfg: &ReplicationC
     }
 }
 
+/// Keeps the newer status of every target
+fn merge_resync_status(
+    mut persisted: BucketReplicationResyncStatus,
+    local: &BucketReplicationResyncStatus,
+) -> BucketReplicationResyncStatus {
+    for (arn, status) in &local.targets_map {
+        let newer = persisted
+            .targets_map
+            .get(arn)
+            .is_none_or(|stored| status.last_update > stored.last_update);
+        if newer {
+            persisted.targets_map.insert(arn.clone(), status.clone());
+        }
+    }
+    persisted.last_update = persisted.last_update.max(local.last_update);
+    persisted
+}
+
 async fn save_resync_status<S: StorageAPI>(bucket: &str, status: &BucketReplicationResyncStatus, api: Arc<S>) -> Result<()> {
     let buf = status.marshal_msg()?;
 
@@ -1789,7 +1960,8 @@ impl ReplicateObjectInfoExt for ReplicateObjectInfo {
             }
         };
 
-        // TODO:bandwidth
+        // Hold the transfer to the target's bandwidth limit
+        gr.stream = Box::new(get_global_bucket_monitor().monitored_reader(&bucket, &tgt_client.arn, gr.stream));
 
         if let Some(err) = if is_multipart {
             replicate_object_with_multipart(tgt_client.clone(), &tgt_client.bucket, &object, gr.stream, &object_info, put_opts)
@@ -2077,6 +2249,8 @@ impl ReplicateObjectInfoExt for ReplicateObjectInfo {
                     return rinfo;
                 }
             };
+
+            gr.stream = Box::new(get_global_bucket_monitor().monitored_reader(&bucket, &tgt_client.arn, gr.stream));
             if let Some(err) = if is_multipart {
                 replicate_object_with_multipart(
                     tgt_client.clone(),
@@ -2454,3 +2628,50 @@ fn get_replication_action(oi1: &ObjectInfo, oi2: &HeadObjectOutput, op_type: Rep
 
     ReplicationAction::None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn target(status: ResyncStatusType, replicated_count: i64, last_update: OffsetDateTime) -> TargetReplicationResyncStatus {
+        TargetReplicationResyncStatus {
+            resync_status: status,
+            replicated_count,
+            last_update: Some(last_update),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merge_resync_status() {
+        let mut persisted = BucketReplicationResyncStatus::new();
+        persisted.targets_map.insert(
+            "arn:a".to_string(),
+            target(ResyncStatusType::ResyncCanceled, 5, datetime!(2025-06-10 12:10 UTC)),
+        );
+        persisted.targets_map.insert(
+            "arn:b".to_string(),
+            target(ResyncStatusType::ResyncStarted, 1, datetime!(2025-06-10 12:00 UTC)),
+        );
+
+        let mut local = BucketReplicationResyncStatus::new();
+        local.targets_map.insert(
+            "arn:a".to_string(),
+            target(ResyncStatusType::ResyncStarted, 4, datetime!(2025-06-10 12:05 UTC)),
+        );
+        local.targets_map.insert(
+            "arn:b".to_string(),
+            target(ResyncStatusType::ResyncStarted, 7, datetime!(2025-06-10 12:20 UTC)),
+        );
+        local.last_update = Some(datetime!(2025-06-10 12:20 UTC));
+
+        let merged = merge_resync_status(persisted, &local);
+        // Canceled on another node after this node last saw it
+        assert_eq!(merged.targets_map["arn:a"].resync_status, ResyncStatusType::ResyncCanceled);
+        assert_eq!(merged.targets_map["arn:a"].replicated_count, 5);
+        // Progress of the resync running here is ahead of the persisted one
+        assert_eq!(merged.targets_map["arn:b"].replicated_count, 7);
+        assert_eq!(merged.last_update, Some(datetime!(2025-06-10 12:20 UTC)));
+    }
+}
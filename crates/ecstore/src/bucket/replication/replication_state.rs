@@ -571,6 +571,10 @@ pub struct BucketReplicationStat {
     pub latency: LatencyStats,
     pub xfer_rate_lrg: XferStats,
     pub xfer_rate_sml: XferStats,
+    #[serde(default)]
+    pub bandwidth_limit_in_bytes_per_second: i64,
+    #[serde(default)]
+    pub current_bandwidth_in_bytes_per_second: f64,
 }
 
 impl BucketReplicationStat {
@@ -651,6 +655,23 @@ pub struct SRMetricsSummary {
     pub replica_count: i64,
 }
 
+impl SRMetricsSummary {
+    /// Fold in the summary of another node of the cluster
+    pub fn merge(&mut self, other: &SRMetricsSummary) {
+        self.uptime = self.uptime.max(other.uptime);
+        self.queued = self.queued.merge(&other.queued);
+        self.active_workers.curr += other.active_workers.curr;
+        self.active_workers.max += other.active_workers.max;
+        self.active_workers.avg += other.active_workers.avg;
+        for (name, value) in &other.metrics {
+            *self.metrics.entry(name.clone()).or_default() += value;
+        }
+        self.proxied.add(&other.proxied);
+        self.replica_size += other.replica_size;
+        self.replica_count += other.replica_count;
+    }
+}
+
 /// Active worker statistics
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ActiveWorkerStat {
@@ -1005,6 +1026,9 @@ impl ReplicationStats {
                     latency: stat.latency.merge(&old_stat.latency),
                     xfer_rate_lrg: lrg,
                     xfer_rate_sml: sml,
+                    bandwidth_limit_in_bytes_per_second: stat.bandwidth_limit_in_bytes_per_second,
+                    current_bandwidth_in_bytes_per_second: stat.current_bandwidth_in_bytes_per_second
+                        + old_stat.current_bandwidth_in_bytes_per_second,
                 };
 
                 tot_replicated_size += stat.replicated_size;
@@ -1198,4 +1222,34 @@ mod tests {
         assert_eq!(stats_map["replica_size"], 0);
         assert_eq!(stats_map["replica_count"], 0);
     }
+
+    #[test]
+    fn test_sr_metrics_summary_merge() {
+        let mut summary = SRMetricsSummary {
+            uptime: 10,
+            replica_size: 100,
+            replica_count: 1,
+            metrics: HashMap::from([("replica_size".to_string(), 100)]),
+            ..Default::default()
+        };
+        let other = SRMetricsSummary {
+            uptime: 20,
+            replica_size: 50,
+            replica_count: 2,
+            metrics: HashMap::from([("replica_size".to_string(), 50), ("replica_count".to_string(), 2)]),
+            proxied: ProxyMetric {
+                get_total: 3,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        summary.merge(&other);
+        assert_eq!(summary.uptime, 20);
+        assert_eq!(summary.replica_size, 150);
+        assert_eq!(summary.replica_count, 3);
+        assert_eq!(summary.metrics["replica_size"], 150);
+        assert_eq!(summary.metrics["replica_count"], 2);
+        assert_eq!(summary.proxied.get_total, 3);
+    }
 }
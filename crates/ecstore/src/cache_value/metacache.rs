@@ -0,0 +1,507 @@
+
+
+use crate::config::com::delete_config;
+use crate::disk::BUCKET_META_PREFIX;
+use crate::error::{Error, Result};
+use crate::notification_sys::get_global_notification_sys;
+use crate::rpc::PeerRestClient;
+use crate::store::ECStore;
+use crate::store_list_objects::ListPathOptions;
+use crate::store_utils::is_reserved_or_invalid_bucket;
+use nebulafx_filemeta::{MetaCacheEntry, MetacacheReader, MetacacheWriter};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Number of entries persisted in one block of a listing
+pub const METACACHE_BLOCK_SIZE: usize = 5000;
+
+/// Most blocks handed out with a listing, pages needing more are walked from the drives
+const METACACHE_MAX_HANDOUT_BLOCKS: usize = 16;
+
+/// A listing no page was served from for this long is dropped
+const METACACHE_MAX_CLIENT_WAIT: Duration = Duration::from_secs(3 * 60);
+
+/// A scan that reported no progress for this long is considered dead
+const METACACHE_MAX_RUNNING_AGE: Duration = Duration::from_secs(60);
+
+/// Listings are never reused past this age, however active they are
+const METACACHE_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+const METACACHE_CLEANUP_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScanStatus {
+    #[default]
+    None,
+    Started,
+    Success,
+    Error,
+}
+
+/// A persisted block of a listing and the range of names it holds
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetacacheBlock {
+    pub n: usize,
+    pub first: String,
+    pub last: String,
+}
+
+/// State of a listing cached under `.metacache` of its bucket. The node owning the bucket keeps
+/// the state of all its listings, the node walking the drives reports every block it saved.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metacache {
+    pub id: String,
+    pub bucket: String,
+    pub root: String,
+    pub filter: Option<String>,
+    pub recursive: bool,
+    pub versioned: bool,
+    pub status: ScanStatus,
+    pub error: String,
+    pub started: Option<OffsetDateTime>,
+    pub ended: Option<OffsetDateTime>,
+    pub last_update: Option<OffsetDateTime>,
+    pub last_handout: Option<OffsetDateTime>,
+    /// Blocks saved so far in name order. Updates only carry the new blocks and handouts only
+    /// the blocks from the requested marker on.
+    pub blocks: Vec<MetacacheBlock>,
+    /// Number of blocks saved so far
+    pub block_count: usize,
+}
+
+impl Metacache {
+    pub fn new(o: &ListPathOptions) -> Self {
+        let now = OffsetDateTime::now_utc();
+        Self {
+            id: o.id.clone().unwrap_or_default(),
+            bucket: o.bucket.clone(),
+            root: o.base_dir.clone(),
+            filter: o.filter_prefix.clone(),
+            recursive: o.recursive,
+            versioned: o.versioned,
+            status: ScanStatus::Started,
+            started: Some(now),
+            last_update: Some(now),
+            last_handout: Some(now),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the listing walked the same tree `o` asks for
+    pub fn matches(&self, o: &ListPathOptions) -> bool {
+        self.bucket == o.bucket
+            && self.root == o.base_dir
+            && self.filter == o.filter_prefix
+            && self.recursive == o.recursive
+            && self.versioned == o.versioned
+    }
+
+    /// Whether this is the update a walker registers its listing with, before any block is saved
+    pub fn is_registration(&self) -> bool {
+        self.status == ScanStatus::Started && self.blocks.is_empty() && self.block_count == 0
+    }
+
+    pub fn finished(&self) -> bool {
+        self.status == ScanStatus::Success
+    }
+
+    /// Whether the blocks at hand hold the end of a finished listing
+    pub fn holds_end(&self) -> bool {
+        self.finished() && self.blocks.last().is_none_or(|block| block.n + 1 >= self.block_count)
+    }
+
+    pub fn expired(&self, now: OffsetDateTime) -> bool {
+        let older_than = |t: Option<OffsetDateTime>, age: Duration| t.is_none_or(|t| now - t > age);
+
+        match self.status {
+            ScanStatus::None | ScanStatus::Error => true,
+            ScanStatus::Started if older_than(self.last_update, METACACHE_MAX_RUNNING_AGE) => true,
+            _ => older_than(self.started, METACACHE_MAX_AGE) || older_than(self.last_handout, METACACHE_MAX_CLIENT_WAIT),
+        }
+    }
+
+    /// Path of the listing in the meta bucket
+    pub fn prefix(&self) -> String {
+        metacache_prefix(&self.bucket, &self.id)
+    }
+
+    pub fn block_path(&self, n: usize) -> String {
+        format!("{}/block-{}", self.prefix(), n)
+    }
+}
+
+pub fn metacache_prefix(bucket: &str, id: &str) -> String {
+    format!("{BUCKET_META_PREFIX}/{bucket}/.metacache/{id}")
+}
+
+pub async fn encode_block(entries: &[MetaCacheEntry]) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let mut writer = MetacacheWriter::new(&mut buf);
+    writer.write(entries).await?;
+    writer.close().await?;
+    Ok(buf)
+}
+
+pub async fn decode_block(data: &[u8]) -> Result<Vec<MetaCacheEntry>> {
+    let mut reader = MetacacheReader::new(data);
+    Ok(reader.read_all().await?)
+}
+
+/// Listings of the buckets this node owns and the last local write to each bucket
+#[derive(Debug, Default)]
+pub struct MetacacheManager {
+    caches: Mutex<HashMap<String, HashMap<String, Metacache>>>,
+    last_write: Mutex<HashMap<String, OffsetDateTime>>,
+}
+
+pub static GLOBAL_METACACHE: LazyLock<MetacacheManager> = LazyLock::new(MetacacheManager::default);
+
+impl MetacacheManager {
+    /// Hands out the listing `o` continues, with the blocks from its marker on
+    pub fn find(&self, o: &ListPathOptions) -> Option<Metacache> {
+        let id = o.id.as_ref()?;
+        let now = OffsetDateTime::now_utc();
+
+        let mut caches = self.caches.lock().unwrap();
+        let cache = caches.get_mut(&o.bucket)?.get_mut(id)?;
+        if !cache.matches(o) || cache.expired(now) {
+            return None;
+        }
+        cache.last_handout = Some(now);
+
+        let mut handout = cache.clone();
+        let start = match &o.marker {
+            Some(marker) => handout.blocks.partition_point(|block| &block.last < marker),
+            None => 0,
+        };
+        handout.blocks = handout
+            .blocks
+            .into_iter()
+            .skip(start)
+            .take(METACACHE_MAX_HANDOUT_BLOCKS)
+            .collect();
+        Some(handout)
+    }
+
+    /// Records the progress of a listing and returns its state, an update without id invalidates all
+    /// listings of the bucket
+    ///
+    /// Only the registration of a listing creates its state. A later update for a listing that is not
+    /// tracked, because it expired in between, gets an error back so its walker stops: a state built
+    /// from that update would miss the blocks saved before it.
+    pub fn update(&self, update: Metacache) -> Metacache {
+        if update.id.is_empty() {
+            self.invalidate(&update.bucket);
+            return update;
+        }
+
+        let mut caches = self.caches.lock().unwrap();
+        let Some(cache) = caches.get_mut(&update.bucket).and_then(|bucket| bucket.get_mut(&update.id)) else {
+            if !update.is_registration() {
+                return Metacache {
+                    status: ScanStatus::Error,
+                    error: "listing no longer tracked".to_string(),
+                    blocks: Vec::new(),
+                    ..update
+                };
+            }
+            let handout = update.clone();
+            caches.entry(update.bucket.clone()).or_default().insert(update.id.clone(), update);
+            return handout;
+        };
+
+        // A listing stays failed once invalidated, its walker stops when told so
+        if cache.status != ScanStatus::Error {
+            for block in update.blocks {
+                match block.n.cmp(&cache.blocks.len()) {
+                    Ordering::Less => {}
+                    Ordering::Equal => cache.blocks.push(block),
+                    Ordering::Greater => {
+                        // A block went missing, the listing would skip its names
+                        cache.status = ScanStatus::Error;
+                        cache.error = format!("metacache block {} reported before block {}", block.n, cache.blocks.len());
+                        return Metacache {
+                            blocks: Vec::new(),
+                            ..cache.clone()
+                        };
+                    }
+                }
+            }
+            cache.block_count = cache.blocks.len();
+            cache.status = update.status;
+            cache.error = update.error;
+            cache.ended = update.ended;
+            cache.last_update = Some(OffsetDateTime::now_utc());
+        }
+
+        Metacache {
+            blocks: Vec::new(),
+            ..cache.clone()
+        }
+    }
+
+    pub fn invalidate(&self, bucket: &str) {
+        let mut caches = self.caches.lock().unwrap();
+        for cache in caches.get_mut(bucket).into_iter().flat_map(|caches| caches.values_mut()) {
+            if cache.status != ScanStatus::Error {
+                cache.status = ScanStatus::Error;
+                cache.error = "listing invalidated by a write".to_string();
+            }
+        }
+    }
+
+    /// Removes the expired listings and returns them so their blocks can be deleted
+    pub fn take_expired(&self, now: OffsetDateTime) -> Vec<Metacache> {
+        let mut expired = Vec::new();
+        let mut caches = self.caches.lock().unwrap();
+        for bucket in caches.values_mut() {
+            bucket.retain(|_, cache| {
+                if cache.expired(now) {
+                    expired.push(cache.clone());
+                    return false;
+                }
+                true
+            });
+        }
+        caches.retain(|_, bucket| !bucket.is_empty());
+        expired
+    }
+
+    /// Notes a committed write to the bucket
+    pub fn record_write(&self, bucket: &str) {
+        let mut last_write = self.last_write.lock().unwrap();
+        last_write.insert(bucket.to_string(), OffsetDateTime::now_utc());
+    }
+
+    /// Whether this node wrote to the bucket since `t`
+    pub fn written_since(&self, bucket: &str, t: Option<OffsetDateTime>) -> bool {
+        let last_write = self.last_write.lock().unwrap();
+        match (last_write.get(bucket), t) {
+            (Some(last_write), Some(t)) => *last_write >= t,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+/// Peer owning the listings of the bucket, `None` when it is this node
+fn bucket_owner(bucket: &str) -> Option<PeerRestClient> {
+    get_global_notification_sys().and_then(|sys| sys.rest_client_from_hash(bucket))
+}
+
+/// Looks the listing `o` continues up on the node owning its bucket
+pub async fn find_listing(o: &ListPathOptions) -> Option<Metacache> {
+    let Some(client) = bucket_owner(&o.bucket) else {
+        return GLOBAL_METACACHE.find(o);
+    };
+
+    match client.get_metacache_listing(o).await {
+        Ok(cache) => cache,
+        Err(err) => {
+            warn!("get metacache listing from {} failed: {}", client.host, err);
+            None
+        }
+    }
+}
+
+/// Reports the progress of a listing to the node owning its bucket
+pub async fn update_listing(update: Metacache) -> Result<Metacache> {
+    match bucket_owner(&update.bucket) {
+        Some(client) => client.update_metacache_listing(&update).await,
+        None => Ok(GLOBAL_METACACHE.update(update)),
+    }
+}
+
+/// Invalidates the cached listings of a bucket once an object write or delete to it committed
+pub fn bucket_written(bucket: &str) {
+    if is_reserved_or_invalid_bucket(bucket, false) {
+        return;
+    }
+
+    GLOBAL_METACACHE.record_write(bucket);
+    match bucket_owner(bucket) {
+        None => GLOBAL_METACACHE.invalidate(bucket),
+        Some(client) => {
+            let update = Metacache {
+                bucket: bucket.to_string(),
+                ..Default::default()
+            };
+            tokio::spawn(async move {
+                if let Err(err) = client.update_metacache_listing(&update).await {
+                    warn!("invalidate listings of {} on {} failed: {}", update.bucket, client.host, err);
+                }
+            });
+        }
+    }
+}
+
+/// Periodically drops the expired listings this node owns along with their blocks
+pub fn init_metacache_cleanup(store: Arc<ECStore>, cancel: CancellationToken) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(METACACHE_CLEANUP_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    info!("metacache cleanup stopped");
+                    return;
+                }
+                _ = interval.tick() => {}
+            }
+
+            for cache in GLOBAL_METACACHE.take_expired(OffsetDateTime::now_utc()) {
+                match delete_config(store.clone(), &cache.prefix()).await {
+                    Ok(()) | Err(Error::ConfigNotFound) => {}
+                    Err(err) => warn!("delete metacache {} of {} failed: {}", cache.id, cache.bucket, err),
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(id: &str, marker: Option<&str>) -> ListPathOptions {
+        ListPathOptions {
+            id: Some(id.to_string()),
+            bucket: "photos".to_string(),
+            recursive: true,
+            marker: marker.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn block(n: usize, first: &str, last: &str) -> MetacacheBlock {
+        MetacacheBlock {
+            n,
+            first: first.to_string(),
+            last: last.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_metacache_expired() {
+        let now = OffsetDateTime::now_utc();
+        let mut cache = Metacache::new(&opts("a", None));
+        assert!(!cache.expired(now));
+
+        cache.last_update = Some(now - Duration::from_secs(120));
+        assert!(cache.expired(now));
+
+        cache.status = ScanStatus::Success;
+        assert!(!cache.expired(now));
+
+        cache.last_handout = Some(now - Duration::from_secs(10 * 60));
+        assert!(cache.expired(now));
+
+        cache.last_handout = Some(now);
+        cache.status = ScanStatus::Error;
+        assert!(cache.expired(now));
+    }
+
+    #[test]
+    fn test_metacache_manager() {
+        let manager = MetacacheManager::default();
+        assert!(manager.find(&opts("a", None)).is_none());
+
+        let mut update = Metacache::new(&opts("a", None));
+        manager.update(update.clone());
+        update.blocks = vec![block(0, "a", "f")];
+        manager.update(update.clone());
+
+        update.blocks = vec![block(1, "g", "m"), block(2, "n", "z")];
+        update.status = ScanStatus::Success;
+        let state = manager.update(update);
+        assert!(state.finished());
+        assert!(state.blocks.is_empty());
+
+        let cache = manager.find(&opts("a", Some("h"))).unwrap();
+        assert_eq!(cache.blocks, vec![block(1, "g", "m"), block(2, "n", "z")]);
+        assert_eq!(cache.block_count, 3);
+        assert!(cache.holds_end());
+        assert_eq!(manager.find(&opts("a", None)).unwrap().blocks.len(), 3);
+        assert!(manager.find(&opts("a", Some("zz"))).unwrap().holds_end());
+
+        let mut other = opts("a", None);
+        other.versioned = true;
+        assert!(manager.find(&other).is_none());
+
+        manager.update(Metacache {
+            bucket: "photos".to_string(),
+            ..Default::default()
+        });
+        assert!(manager.find(&opts("a", None)).is_none());
+        assert_eq!(manager.take_expired(OffsetDateTime::now_utc()).len(), 1);
+        assert!(manager.take_expired(OffsetDateTime::now_utc()).is_empty());
+    }
+
+    #[test]
+    fn test_metacache_expired_mid_walk() {
+        let manager = MetacacheManager::default();
+        let mut update = Metacache::new(&opts("a", None));
+        assert_eq!(manager.update(update.clone()).status, ScanStatus::Started);
+
+        update.blocks = vec![block(0, "a", "f")];
+        manager.update(update.clone());
+
+        // The owner dropped the listing, its walker is told to stop instead of the later blocks
+        // starting a new listing that misses the names of block 0
+        assert_eq!(manager.take_expired(OffsetDateTime::now_utc() + METACACHE_MAX_AGE * 2).len(), 1);
+        update.blocks = vec![block(1, "g", "m"), block(2, "n", "z")];
+        assert_eq!(manager.update(update.clone()).status, ScanStatus::Error);
+
+        update.blocks.clear();
+        update.status = ScanStatus::Success;
+        assert_eq!(manager.update(update).status, ScanStatus::Error);
+        assert!(manager.find(&opts("a", None)).is_none());
+        assert!(manager.find(&opts("a", Some("h"))).is_none());
+
+        // A gap in the blocks of a tracked listing fails it as well
+        let mut update = Metacache::new(&opts("b", None));
+        manager.update(update.clone());
+        update.blocks = vec![block(1, "g", "m")];
+        assert_eq!(manager.update(update.clone()).status, ScanStatus::Error);
+        update.blocks.clear();
+        update.status = ScanStatus::Success;
+        manager.update(update);
+        assert!(manager.find(&opts("b", Some("h"))).is_none());
+    }
+
+    #[test]
+    fn test_metacache_writes() {
+        let manager = MetacacheManager::default();
+        let before = OffsetDateTime::now_utc();
+        assert!(!manager.written_since("photos", Some(before)));
+        assert!(!manager.written_since("photos", None));
+
+        manager.record_write("photos");
+        assert!(manager.written_since("photos", Some(before)));
+        assert!(manager.written_since("photos", None));
+        assert!(!manager.written_since("photos", Some(OffsetDateTime::now_utc() + Duration::from_secs(1))));
+    }
+
+    #[tokio::test]
+    async fn test_metacache_block() {
+        let entries: Vec<MetaCacheEntry> = ["a", "b/c", "d"]
+            .into_iter()
+            .map(|name| MetaCacheEntry {
+                name: name.to_string(),
+                metadata: name.as_bytes().to_vec(),
+                ..Default::default()
+            })
+            .collect();
+
+        let data = encode_block(&entries).await.unwrap();
+        let decoded = decode_block(&data).await.unwrap();
+        let names: Vec<&str> = decoded.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b/c", "d"]);
+        assert_eq!(decoded[1].metadata, b"b/c".to_vec());
+    }
+}
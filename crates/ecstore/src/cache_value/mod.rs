@@ -5,6 +5,7 @@ use std::sync::Arc;
 use lazy_static::lazy_static;
 use tokio_util::sync::CancellationToken;
 
+pub mod metacache;
 pub mod metacache_set;
 
 lazy_static! {
@@ -0,0 +1,237 @@
+
+
+//! A drive that replaced a failed one is healed from the other drives of its set. While that runs the
+//! drive carries a tracker in `.nebulafx.sys/.healing.bin` recording the buckets done and the items
+//! and bytes healed, so the heal resumes where it stopped after a restart and its progress shows in
+//! storage info and the background heal status.
+
+use super::error::{DiskError, Result};
+use super::{DeleteOptions, DiskAPI, DiskStore, NEUBULAFX_META_BUCKET};
+use crate::global::GLOBAL_LOCAL_DISK_MAP;
+use nebulafx_madmin::HealingDisk;
+use nebulafx_madmin::heal_commands::BgHealState;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+use time::OffsetDateTime;
+use tracing::warn;
+
+pub const HEALING_TRACKER_FILENAME: &str = ".healing.bin";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealingTracker {
+    /// Id of the drive in `format.json`
+    pub id: String,
+    pub heal_id: String,
+    pub pool_index: Option<usize>,
+    pub set_index: Option<usize>,
+    pub disk_index: Option<usize>,
+    pub endpoint: String,
+    pub path: String,
+    pub started: Option<OffsetDateTime>,
+    pub last_update: Option<OffsetDateTime>,
+    /// Times the heal was started again, after a restart or a failed run
+    pub retry_attempts: u64,
+
+    pub items_healed: u64,
+    pub items_failed: u64,
+    pub items_skipped: u64,
+    pub bytes_done: u64,
+    pub bytes_failed: u64,
+    pub bytes_skipped: u64,
+
+    /// Bucket and object healed last
+    pub bucket: String,
+    pub object: String,
+    pub queued_buckets: Vec<String>,
+    pub healed_buckets: Vec<String>,
+    pub finished: bool,
+}
+
+impl HealingTracker {
+    pub async fn new(disk: &DiskStore, heal_id: &str) -> Self {
+        let location = disk.get_disk_location();
+        Self {
+            id: disk
+                .get_disk_id()
+                .await
+                .ok()
+                .flatten()
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            heal_id: heal_id.to_string(),
+            pool_index: location.pool_idx,
+            set_index: location.set_idx,
+            disk_index: location.disk_idx,
+            endpoint: disk.endpoint().to_string(),
+            path: disk.path().to_string_lossy().to_string(),
+            started: Some(OffsetDateTime::now_utc()),
+            ..Default::default()
+        }
+    }
+
+    /// Tracker of the drive, `DiskError::FileNotFound` when the drive is not being healed
+    pub async fn load(disk: &DiskStore) -> Result<Self> {
+        let data = disk.read_all(NEUBULAFX_META_BUCKET, HEALING_TRACKER_FILENAME).await?;
+        Self::unmarshal(&data)
+    }
+
+    pub async fn save(&mut self, disk: &DiskStore) -> Result<()> {
+        self.last_update = Some(OffsetDateTime::now_utc());
+        disk.write_all(NEUBULAFX_META_BUCKET, HEALING_TRACKER_FILENAME, self.marshal()?.into())
+            .await
+    }
+
+    pub async fn delete(disk: &DiskStore) -> Result<()> {
+        match disk
+            .delete(NEUBULAFX_META_BUCKET, HEALING_TRACKER_FILENAME, DeleteOptions::default())
+            .await
+        {
+            Err(DiskError::FileNotFound) => Ok(()),
+            res => res,
+        }
+    }
+
+    pub fn marshal(&self) -> Result<Vec<u8>> {
+        Ok(rmp_serde::to_vec(self)?)
+    }
+
+    pub fn unmarshal(data: &[u8]) -> Result<Self> {
+        Ok(rmp_serde::from_slice(data)?)
+    }
+
+    /// Queues the buckets of the deployment that are not healed yet
+    pub fn queue_buckets(&mut self, buckets: &[String]) {
+        self.queued_buckets = buckets.iter().filter(|b| !self.is_healed(b)).cloned().collect();
+    }
+
+    pub fn is_healed(&self, bucket: &str) -> bool {
+        self.healed_buckets.iter().any(|b| b == bucket)
+    }
+
+    pub fn bucket_done(&mut self, bucket: &str) {
+        self.queued_buckets.retain(|b| b != bucket);
+        if !self.is_healed(bucket) {
+            self.healed_buckets.push(bucket.to_string());
+        }
+    }
+
+    pub fn item_healed(&mut self, bucket: &str, object: &str, size: u64) {
+        self.bucket = bucket.to_string();
+        self.object = object.to_string();
+        self.items_healed += 1;
+        self.bytes_done += size;
+    }
+
+    pub fn item_failed(&mut self, bucket: &str, object: &str, size: u64) {
+        self.bucket = bucket.to_string();
+        self.object = object.to_string();
+        self.items_failed += 1;
+        self.bytes_failed += size;
+    }
+
+    pub fn item_skipped(&mut self, size: u64) {
+        self.items_skipped += 1;
+        self.bytes_skipped += size;
+    }
+
+    pub fn to_healing_disk(&self) -> HealingDisk {
+        HealingDisk {
+            id: self.id.clone(),
+            heal_id: self.heal_id.clone(),
+            pool_index: self.pool_index,
+            set_index: self.set_index,
+            disk_index: self.disk_index,
+            endpoint: self.endpoint.clone(),
+            path: self.path.clone(),
+            started: self.started,
+            last_update: self.last_update.map(SystemTime::from),
+            retry_attempts: self.retry_attempts,
+            items_healed: self.items_healed,
+            items_failed: self.items_failed,
+            item_skipped: self.items_skipped,
+            bytes_done: self.bytes_done,
+            bytes_failed: self.bytes_failed,
+            bytes_skipped: self.bytes_skipped,
+            objects_healed: self.items_healed,
+            objects_failed: self.items_failed,
+            bucket: self.bucket.clone(),
+            object: self.object.clone(),
+            queue_buckets: self.queued_buckets.clone(),
+            healed_buckets: self.healed_buckets.clone(),
+            finished: self.finished,
+            ..Default::default()
+        }
+    }
+}
+
+/// Local drives with a healing tracker, with their trackers
+pub async fn local_healing_disks() -> Vec<(DiskStore, HealingTracker)> {
+    let disks: Vec<DiskStore> = GLOBAL_LOCAL_DISK_MAP.read().await.values().flatten().cloned().collect();
+
+    let mut healing = Vec::new();
+    for disk in disks {
+        match HealingTracker::load(&disk).await {
+            Ok(tracker) => healing.push((disk, tracker)),
+            Err(DiskError::FileNotFound | DiskError::VolumeNotFound | DiskError::UnformattedDisk) => {}
+            Err(err) => warn!("load healing tracker of {} failed: {}", disk.endpoint(), err),
+        }
+    }
+    healing
+}
+
+/// Background heal state of this node
+pub async fn local_bg_heal_state() -> BgHealState {
+    let mut state = BgHealState::default();
+    for (disk, tracker) in local_healing_disks().await {
+        state.heal_disks.push(disk.endpoint().to_string());
+        state.healing_disks.push(tracker.to_healing_disk());
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_healing_tracker_progress() {
+        let mut tracker = HealingTracker {
+            id: "b3f7a5d2-0000-4000-8000-000000000001".to_string(),
+            pool_index: Some(0),
+            set_index: Some(1),
+            disk_index: Some(2),
+            endpoint: "/data/disk3".to_string(),
+            ..Default::default()
+        };
+
+        let buckets = vec!["photos".to_string(), "logs".to_string()];
+        tracker.queue_buckets(&buckets);
+        assert_eq!(tracker.queued_buckets, buckets);
+
+        tracker.item_healed("photos", "a.jpg", 100);
+        tracker.item_healed("photos", "b.jpg", 50);
+        tracker.item_failed("photos", "c.jpg", 10);
+        tracker.item_skipped(5);
+        tracker.bucket_done("photos");
+        assert!(tracker.is_healed("photos"));
+        assert!(!tracker.is_healed("logs"));
+        assert_eq!(tracker.queued_buckets, vec!["logs".to_string()]);
+
+        // A restarted heal only queues what is left
+        tracker.queue_buckets(&buckets);
+        assert_eq!(tracker.queued_buckets, vec!["logs".to_string()]);
+
+        let decoded = HealingTracker::unmarshal(&tracker.marshal().unwrap()).unwrap();
+        assert_eq!(decoded, tracker);
+
+        let disk = tracker.to_healing_disk();
+        assert_eq!(disk.items_healed, 2);
+        assert_eq!(disk.items_failed, 1);
+        assert_eq!(disk.item_skipped, 1);
+        assert_eq!(disk.bytes_done, 150);
+        assert_eq!(disk.bytes_failed, 10);
+        assert_eq!(disk.object, "c.jpg");
+        assert_eq!(disk.healed_buckets, vec!["photos".to_string()]);
+        assert_eq!(disk.set_index, Some(1));
+    }
+}
@@ -1,6 +1,7 @@
 
 
 use super::error::{Error, Result};
+use super::healing::HEALING_TRACKER_FILENAME;
 use super::os::{is_root_disk, rename_all};
 use super::{
     BUCKET_META_PREFIX, CheckPartsResp, DeleteOptions, DiskAPI, DiskInfo, DiskInfoOptions, DiskLocation, DiskMetrics,
@@ -176,6 +177,9 @@ impl LocalDisk {
             let disk_id = id.map_or("".to_string(), |id| id.to_string());
             let root = root_clone.clone();
             Box::pin(async move {
+                let healing = fs::try_exists(root.join(NEUBULAFX_META_BUCKET).join(HEALING_TRACKER_FILENAME))
+                    .await
+                    .unwrap_or_default();
                 match get_disk_info(root.clone()).await {
                     Ok((info, root)) => {
                         let disk_info = DiskInfo {
@@ -189,13 +193,13 @@ impl LocalDisk {
                             fs_type: info.fstype,
                             root_disk: root,
                             id: disk_id.to_string(),
+                            healing,
                             ..Default::default()
                         };
                         // if root {
                         //     return Err(Error::new(DiskError::DriveIsRoot));
                         // }
 
-                        // disk_info.healing =
                         Ok(disk_info)
                     }
                     Err(err) => Err(err.into()),
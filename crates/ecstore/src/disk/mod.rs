@@ -6,6 +6,7 @@ pub mod error_conv;
 pub mod error_reduce;
 pub mod format;
 pub mod fs;
+pub mod healing;
 pub mod local;
 pub mod os;
 
@@ -20,14 +21,17 @@ pub const STORAGE_FORMAT_FILE_BACKUP: &str = "xl.meta.bkp";
 
 use crate::rpc::RemoteDisk;
 use bytes::Bytes;
+use chrono::Utc;
 use endpoint::Endpoint;
 use error::DiskError;
 use error::{Error, Result};
 use local::LocalDisk;
 use nebulafx_filemeta::{FileInfo, ObjectPartInfo, RawFileInfo};
+use nebulafx_common::trace::{publish_trace, trace_enabled};
 use nebulafx_madmin::info_commands::DiskMetrics;
+use nebulafx_madmin::trace::{TraceInfo, TraceType};
 use serde::{Deserialize, Serialize};
-use std::{fmt::Debug, path::PathBuf, sync::Arc};
+use std::{fmt::Debug, future::Future, path::PathBuf, sync::Arc, time::Instant};
 use time::OffsetDateTime;
 use tokio::io::{AsyncRead, AsyncWrite};
 use uuid::Uuid;
@@ -43,6 +47,31 @@ pub enum Disk {
     Remote(Box<RemoteDisk>),
 }
 
+/// Run a call against a local drive, publishing a storage trace record when someone is tracing.
+///
+/// Remote drives are not traced here, the owning node traces the call when it serves it.
+async fn trace_local<T>(disk: &LocalDisk, func: &str, volume: &str, path: &str, fut: impl Future<Output = Result<T>>) -> Result<T> {
+    if !trace_enabled(TraceType::STORAGE) {
+        return fut.await;
+    }
+
+    let time = Utc::now();
+    let start = Instant::now();
+    let res = fut.await;
+
+    publish_trace(TraceInfo {
+        trace_type: TraceType::STORAGE.mask(),
+        func_name: format!("storage.{func}"),
+        time,
+        path: format!("{}/{}/{}", disk.path().display(), volume, path),
+        duration: start.elapsed(),
+        error: res.as_ref().err().map(|e| e.to_string()),
+        ..Default::default()
+    });
+
+    res
+}
+
 #[async_trait::async_trait]
 impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
@@ -128,7 +157,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn make_volume(&self, volume: &str) -> Result<()> {
         match self {
-            Disk::Local(local_disk) => local_disk.make_volume(volume).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "MakeVolume", volume, "", local_disk.make_volume(volume)).await,
             Disk::Remote(remote_disk) => remote_disk.make_volume(volume).await,
         }
     }
@@ -152,7 +181,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn stat_volume(&self, volume: &str) -> Result<VolumeInfo> {
         match self {
-            Disk::Local(local_disk) => local_disk.stat_volume(volume).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "StatVolume", volume, "", local_disk.stat_volume(volume)).await,
             Disk::Remote(remote_disk) => remote_disk.stat_volume(volume).await,
         }
     }
@@ -160,7 +189,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn delete_volume(&self, volume: &str) -> Result<()> {
         match self {
-            Disk::Local(local_disk) => local_disk.delete_volume(volume).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "DeleteVolume", volume, "", local_disk.delete_volume(volume)).await,
             Disk::Remote(remote_disk) => remote_disk.delete_volume(volume).await,
         }
     }
@@ -183,7 +212,7 @@ impl DiskAPI for Disk {
         opts: DeleteOptions,
     ) -> Result<()> {
         match self {
-            Disk::Local(local_disk) => local_disk.delete_version(volume, path, fi, force_del_marker, opts).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "DeleteVersion", volume, path, local_disk.delete_version(volume, path, fi, force_del_marker, opts)).await,
             Disk::Remote(remote_disk) => remote_disk.delete_version(volume, path, fi, force_del_marker, opts).await,
         }
     }
@@ -207,7 +236,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn write_metadata(&self, _org_volume: &str, volume: &str, path: &str, fi: FileInfo) -> Result<()> {
         match self {
-            Disk::Local(local_disk) => local_disk.write_metadata(_org_volume, volume, path, fi).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "WriteMetadata", volume, path, local_disk.write_metadata(_org_volume, volume, path, fi)).await,
             Disk::Remote(remote_disk) => remote_disk.write_metadata(_org_volume, volume, path, fi).await,
         }
     }
@@ -215,7 +244,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn update_metadata(&self, volume: &str, path: &str, fi: FileInfo, opts: &UpdateMetadataOpts) -> Result<()> {
         match self {
-            Disk::Local(local_disk) => local_disk.update_metadata(volume, path, fi, opts).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "UpdateMetadata", volume, path, local_disk.update_metadata(volume, path, fi, opts)).await,
             Disk::Remote(remote_disk) => remote_disk.update_metadata(volume, path, fi, opts).await,
         }
     }
@@ -230,7 +259,7 @@ impl DiskAPI for Disk {
         opts: &ReadOptions,
     ) -> Result<FileInfo> {
         match self {
-            Disk::Local(local_disk) => local_disk.read_version(_org_volume, volume, path, version_id, opts).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "ReadVersion", volume, path, local_disk.read_version(_org_volume, volume, path, version_id, opts)).await,
             Disk::Remote(remote_disk) => remote_disk.read_version(_org_volume, volume, path, version_id, opts).await,
         }
     }
@@ -238,7 +267,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn read_xl(&self, volume: &str, path: &str, read_data: bool) -> Result<RawFileInfo> {
         match self {
-            Disk::Local(local_disk) => local_disk.read_xl(volume, path, read_data).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "ReadXL", volume, path, local_disk.read_xl(volume, path, read_data)).await,
             Disk::Remote(remote_disk) => remote_disk.read_xl(volume, path, read_data).await,
         }
     }
@@ -253,7 +282,7 @@ impl DiskAPI for Disk {
         dst_path: &str,
     ) -> Result<RenameDataResp> {
         match self {
-            Disk::Local(local_disk) => local_disk.rename_data(src_volume, src_path, fi, dst_volume, dst_path).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "RenameData", dst_volume, dst_path, local_disk.rename_data(src_volume, src_path, fi, dst_volume, dst_path)).await,
             Disk::Remote(remote_disk) => remote_disk.rename_data(src_volume, src_path, fi, dst_volume, dst_path).await,
         }
     }
@@ -261,7 +290,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn list_dir(&self, _origvolume: &str, volume: &str, _dir_path: &str, _count: i32) -> Result<Vec<String>> {
         match self {
-            Disk::Local(local_disk) => local_disk.list_dir(_origvolume, volume, _dir_path, _count).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "ListDir", volume, _dir_path, local_disk.list_dir(_origvolume, volume, _dir_path, _count)).await,
             Disk::Remote(remote_disk) => remote_disk.list_dir(_origvolume, volume, _dir_path, _count).await,
         }
     }
@@ -277,7 +306,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn read_file_stream(&self, volume: &str, path: &str, offset: usize, length: usize) -> Result<FileReader> {
         match self {
-            Disk::Local(local_disk) => local_disk.read_file_stream(volume, path, offset, length).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "ReadFileStream", volume, path, local_disk.read_file_stream(volume, path, offset, length)).await,
             Disk::Remote(remote_disk) => remote_disk.read_file_stream(volume, path, offset, length).await,
         }
     }
@@ -293,7 +322,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn create_file(&self, _origvolume: &str, volume: &str, path: &str, _file_size: i64) -> Result<FileWriter> {
         match self {
-            Disk::Local(local_disk) => local_disk.create_file(_origvolume, volume, path, _file_size).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "CreateFile", volume, path, local_disk.create_file(_origvolume, volume, path, _file_size)).await,
             Disk::Remote(remote_disk) => remote_disk.create_file(_origvolume, volume, path, _file_size).await,
         }
     }
@@ -301,7 +330,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn rename_file(&self, src_volume: &str, src_path: &str, dst_volume: &str, dst_path: &str) -> Result<()> {
         match self {
-            Disk::Local(local_disk) => local_disk.rename_file(src_volume, src_path, dst_volume, dst_path).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "RenameFile", dst_volume, dst_path, local_disk.rename_file(src_volume, src_path, dst_volume, dst_path)).await,
             Disk::Remote(remote_disk) => remote_disk.rename_file(src_volume, src_path, dst_volume, dst_path).await,
         }
     }
@@ -317,7 +346,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn rename_part(&self, src_volume: &str, src_path: &str, dst_volume: &str, dst_path: &str, meta: Bytes) -> Result<()> {
         match self {
-            Disk::Local(local_disk) => local_disk.rename_part(src_volume, src_path, dst_volume, dst_path, meta).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "RenamePart", dst_volume, dst_path, local_disk.rename_part(src_volume, src_path, dst_volume, dst_path, meta)).await,
             Disk::Remote(remote_disk) => {
                 remote_disk
                     .rename_part(src_volume, src_path, dst_volume, dst_path, meta)
@@ -329,7 +358,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn delete(&self, volume: &str, path: &str, opt: DeleteOptions) -> Result<()> {
         match self {
-            Disk::Local(local_disk) => local_disk.delete(volume, path, opt).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "Delete", volume, path, local_disk.delete(volume, path, opt)).await,
             Disk::Remote(remote_disk) => remote_disk.delete(volume, path, opt).await,
         }
     }
@@ -337,7 +366,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn verify_file(&self, volume: &str, path: &str, fi: &FileInfo) -> Result<CheckPartsResp> {
         match self {
-            Disk::Local(local_disk) => local_disk.verify_file(volume, path, fi).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "VerifyFile", volume, path, local_disk.verify_file(volume, path, fi)).await,
             Disk::Remote(remote_disk) => remote_disk.verify_file(volume, path, fi).await,
         }
     }
@@ -345,7 +374,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn check_parts(&self, volume: &str, path: &str, fi: &FileInfo) -> Result<CheckPartsResp> {
         match self {
-            Disk::Local(local_disk) => local_disk.check_parts(volume, path, fi).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "CheckParts", volume, path, local_disk.check_parts(volume, path, fi)).await,
             Disk::Remote(remote_disk) => remote_disk.check_parts(volume, path, fi).await,
         }
     }
@@ -361,7 +390,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn write_all(&self, volume: &str, path: &str, data: Bytes) -> Result<()> {
         match self {
-            Disk::Local(local_disk) => local_disk.write_all(volume, path, data).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "WriteAll", volume, path, local_disk.write_all(volume, path, data)).await,
             Disk::Remote(remote_disk) => remote_disk.write_all(volume, path, data).await,
         }
     }
@@ -369,7 +398,7 @@ impl DiskAPI for Disk {
     #[tracing::instrument(skip(self))]
     async fn read_all(&self, volume: &str, path: &str) -> Result<Bytes> {
         match self {
-            Disk::Local(local_disk) => local_disk.read_all(volume, path).await,
+            Disk::Local(local_disk) => trace_local(local_disk, "ReadAll", volume, path, local_disk.read_all(volume, path)).await,
             Disk::Remote(remote_disk) => remote_disk.read_all(volume, path).await,
         }
     }
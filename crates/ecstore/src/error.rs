@@ -184,6 +184,9 @@ pub enum StorageError {
 
     #[error("Invalid range specified: {0}")]
     InvalidRangeSpec(String),
+
+    #[error("Bucket quota exceeded for bucket: {0}")]
+    BucketQuotaExceeded(String),
 }
 
 impl StorageError {
@@ -416,6 +419,7 @@ impl Clone for StorageError {
             StorageError::InsufficientWriteQuorum(a, b) => StorageError::InsufficientWriteQuorum(a.clone(), b.clone()),
             StorageError::PreconditionFailed => StorageError::PreconditionFailed,
             StorageError::InvalidRangeSpec(a) => StorageError::InvalidRangeSpec(a.clone()),
+            StorageError::BucketQuotaExceeded(a) => StorageError::BucketQuotaExceeded(a.clone()),
         }
     }
 }
@@ -484,6 +488,7 @@ impl StorageError {
             StorageError::PreconditionFailed => 0x3B,
             StorageError::EntityTooSmall(_, _, _) => 0x3C,
             StorageError::InvalidRangeSpec(_) => 0x3D,
+            StorageError::BucketQuotaExceeded(_) => 0x3E,
         }
     }
 
@@ -554,6 +559,7 @@ impl StorageError {
             0x3B => Some(StorageError::PreconditionFailed),
             0x3C => Some(StorageError::EntityTooSmall(Default::default(), Default::default(), Default::default())),
             0x3D => Some(StorageError::InvalidRangeSpec(Default::default())),
+            0x3E => Some(StorageError::BucketQuotaExceeded(Default::default())),
             _ => None,
         }
     }
@@ -21,6 +21,7 @@ pub mod file_cache;
 pub mod global;
 pub mod metrics_realtime;
 pub mod notification_sys;
+pub mod ns_lock;
 pub mod pools;
 pub mod rebalance;
 pub mod rpc;
@@ -2,6 +2,7 @@
 
 use crate::StorageAPI;
 use crate::admin_server_info::get_commit_id;
+use crate::bucket::replication::SRMetricsSummary;
 use crate::error::{Error, Result};
 use crate::global::{GLOBAL_BOOT_TIME, get_global_endpoints};
 use crate::metrics_realtime::{CollectMetricsOpts, MetricType};
@@ -9,6 +10,7 @@ use crate::rpc::PeerRestClient;
 use crate::{endpoints::EndpointServerPools, new_object_layer_fn};
 use futures::future::join_all;
 use lazy_static::lazy_static;
+use nebulafx_madmin::heal_commands::BgHealState;
 use nebulafx_madmin::health::{Cpus, MemInfo, OsInfo, Partitions, ProcInfo, SysConfig, SysErrors, SysService};
 use nebulafx_madmin::metrics::RealtimeMetrics;
 use nebulafx_madmin::net::NetInfo;
@@ -628,6 +630,36 @@ impl NotificationSys {
         join_all(futures).await
     }
 
+    /// Site replication metrics of the other nodes, unreachable nodes are skipped
+    pub async fn get_sr_metrics(&self) -> Vec<SRMetricsSummary> {
+        let mut futures = Vec::with_capacity(self.peer_clients.len());
+        for client in self.peer_clients.iter().flatten() {
+            futures.push(async move {
+                client
+                    .get_sr_metrics()
+                    .await
+                    .inspect_err(|err| warn!("get site replication metrics from {} failed: {}", client.host, err))
+                    .ok()
+            });
+        }
+        join_all(futures).await.into_iter().flatten().collect()
+    }
+
+    /// Background heal state of the other nodes, unreachable nodes are skipped
+    pub async fn background_heal_status(&self) -> Vec<BgHealState> {
+        let mut futures = Vec::with_capacity(self.peer_clients.len());
+        for client in self.peer_clients.iter().flatten() {
+            futures.push(async move {
+                client
+                    .background_heal_status()
+                    .await
+                    .inspect_err(|err| warn!("get background heal status from {} failed: {}", client.host, err))
+                    .ok()
+            });
+        }
+        join_all(futures).await.into_iter().flatten().collect()
+    }
+
     pub async fn reload_site_replication_config(&self) -> Vec<NotificationPeerErr> {
         let mut futures = Vec::with_capacity(self.peer_clients.len());
         for client in self.peer_clients.iter() {
@@ -654,6 +686,72 @@ impl NotificationSys {
         join_all(futures).await
     }
 
+    pub async fn signal_service(&self, sig: u64, sub_sys: &str, dry_run: bool) -> Vec<NotificationPeerErr> {
+        let exec_at = SystemTime::now();
+        let mut futures = Vec::with_capacity(self.peer_clients.len());
+        for client in self.peer_clients.iter() {
+            futures.push(async move {
+                if let Some(client) = client {
+                    match client.signal_service(sig, sub_sys, dry_run, exec_at).await {
+                        Ok(_) => NotificationPeerErr {
+                            host: client.host.to_string(),
+                            err: None,
+                        },
+                        Err(e) => NotificationPeerErr {
+                            host: client.host.to_string(),
+                            err: Some(e),
+                        },
+                    }
+                } else {
+                    NotificationPeerErr {
+                        host: "".to_string(),
+                        err: Some(Error::other("peer is not reachable")),
+                    }
+                }
+            });
+        }
+        join_all(futures).await
+    }
+
+    /// Cancel the replication resync of a bucket to a target on every peer, returns whether any
+    /// peer was running it
+    pub async fn cancel_replication_resync(&self, bucket: &str, arn: &str) -> (bool, Vec<NotificationPeerErr>) {
+        let mut futures = Vec::with_capacity(self.peer_clients.len());
+        for client in self.peer_clients.iter() {
+            futures.push(async move {
+                if let Some(client) = client {
+                    match client.cancel_replication_resync(bucket, arn).await {
+                        Ok(canceled) => (
+                            canceled,
+                            NotificationPeerErr {
+                                host: client.host.to_string(),
+                                err: None,
+                            },
+                        ),
+                        Err(e) => (
+                            false,
+                            NotificationPeerErr {
+                                host: client.host.to_string(),
+                                err: Some(e),
+                            },
+                        ),
+                    }
+                } else {
+                    (
+                        false,
+                        NotificationPeerErr {
+                            host: "".to_string(),
+                            err: Some(Error::other("peer is not reachable")),
+                        },
+                    )
+                }
+            });
+        }
+        let results = join_all(futures).await;
+        let canceled = results.iter().any(|(canceled, _)| *canceled);
+        (canceled, results.into_iter().map(|(_, err)| err).collect())
+    }
+
     pub async fn load_transition_tier_config(&self) -> Vec<NotificationPeerErr> {
         let mut futures = Vec::with_capacity(self.peer_clients.len());
         for client in self.peer_clients.iter() {
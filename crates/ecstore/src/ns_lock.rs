@@ -0,0 +1,241 @@
+
+
+//! Namespace locking for erasure sets.
+//!
+//! Every `SetDisks` serializes access to an object through a process-local
+//! [`FastObjectLockManager`]. On a single node this is sufficient, but in a
+//! distributed deployment several nodes serve the same set, so each lock must
+//! additionally be granted by a quorum of those nodes. The distributed part is
+//! built on [`NamespaceLock`] with one [`LocalClient`] for this node and one
+//! [`RemoteClient`] per peer, and the lease is refreshed for as long as the
+//! guard is held. Peers drop leases that stop being refreshed, so locks held by
+//! a node that died are released after their TTL.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use nebulafx_lock::{
+    FastLockGuard, LocalClient, LockClient, LockGuard, LockId, LockRequest, LockType, NamespaceLock, RemoteClient,
+    fast_lock::{LockMode, LockResult},
+};
+use rand::Rng;
+use tracing::{debug, info, warn};
+
+use crate::disk::endpoint::Endpoint;
+
+/// Selects how erasure sets lock objects: `auto`, `local` or `distributed`
+pub const ENV_LOCK_MODE: &str = "NEUBULAFX_LOCK_MODE";
+
+/// How long a distributed lock stays valid without being refreshed
+pub const DIST_LOCK_TTL: Duration = Duration::from_secs(30);
+
+/// How long to keep retrying to reach lock quorum before giving up
+pub const DIST_LOCK_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Per-node wait for a contended lock within a single quorum attempt
+const DIST_LOCK_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Upper bound for the randomized back-off between quorum attempts
+const DIST_LOCK_MAX_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Locking strategy for erasure sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NsLockMode {
+    /// Distributed locks in distributed erasure mode, local locks otherwise
+    #[default]
+    Auto,
+    /// Always use the process-local fast lock
+    Local,
+    /// Always take locks on a quorum of the nodes serving the set
+    Distributed,
+}
+
+impl NsLockMode {
+    pub fn from_env() -> Self {
+        match std::env::var(ENV_LOCK_MODE) {
+            Ok(v) => v.parse().unwrap_or_else(|_| {
+                warn!("invalid {}={}, falling back to auto", ENV_LOCK_MODE, v);
+                Self::Auto
+            }),
+            Err(_) => Self::Auto,
+        }
+    }
+
+    pub fn is_distributed(&self, is_dist_erasure: bool) -> bool {
+        match self {
+            Self::Auto => is_dist_erasure,
+            Self::Local => false,
+            Self::Distributed => true,
+        }
+    }
+}
+
+impl std::str::FromStr for NsLockMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "auto" => Ok(Self::Auto),
+            "local" => Ok(Self::Local),
+            "distributed" | "dist" => Ok(Self::Distributed),
+            other => Err(format!("unknown lock mode: {other}")),
+        }
+    }
+}
+
+/// Build the namespace lock shared by all nodes that serve a set's drives.
+///
+/// Returns `None` when the set is only served by this node, in which case the
+/// fast local lock already provides mutual exclusion.
+pub fn new_set_namespace_lock(pool_idx: usize, set_idx: usize, set_endpoints: &[Endpoint]) -> Option<Arc<NamespaceLock>> {
+    let mut seen = HashSet::new();
+    let mut clients: Vec<Arc<dyn LockClient>> = Vec::new();
+
+    for endpoint in set_endpoints {
+        if endpoint.is_local {
+            if seen.insert("local".to_string()) {
+                clients.push(Arc::new(LocalClient::new()));
+            }
+            continue;
+        }
+
+        let host = endpoint.grid_host();
+        if host.is_empty() {
+            continue;
+        }
+        if seen.insert(host.clone()) {
+            clients.push(Arc::new(RemoteClient::new(host)));
+        }
+    }
+
+    if clients.len() <= 1 {
+        return None;
+    }
+
+    let quorum = clients.len() / 2 + 1;
+    info!(
+        "pool {} set {} uses distributed locks across {} nodes (quorum {})",
+        pool_idx,
+        set_idx,
+        clients.len(),
+        quorum
+    );
+
+    Some(Arc::new(NamespaceLock::with_clients_and_quorum(
+        format!("{pool_idx}/{set_idx}"),
+        clients,
+        quorum,
+    )))
+}
+
+/// Take a lock on a quorum of nodes, retrying with back-off until `DIST_LOCK_ACQUIRE_TIMEOUT`.
+///
+/// A single attempt can fail even without a real holder when concurrent writers
+/// split the nodes between them; all of them roll back and try again.
+pub async fn acquire_distributed(
+    ns_lock: &NamespaceLock,
+    bucket: &str,
+    object: &str,
+    owner: &str,
+    lock_type: LockType,
+) -> Result<LockGuard, LockResult> {
+    let resource = ns_lock.get_resource_key(&format!("{bucket}/{object}"));
+    let deadline = Instant::now() + DIST_LOCK_ACQUIRE_TIMEOUT;
+
+    loop {
+        let request = LockRequest::new(&resource, lock_type, owner)
+            .with_lock_id(LockId::new(&resource))
+            .with_acquire_timeout(DIST_LOCK_ATTEMPT_TIMEOUT)
+            .with_ttl(DIST_LOCK_TTL);
+
+        match ns_lock.acquire_guard(&request).await {
+            Ok(Some(guard)) => return Ok(guard),
+            Ok(None) => debug!("distributed {:?} lock on {} not granted by quorum, retrying", lock_type, resource),
+            Err(err) => warn!("distributed {:?} lock on {} failed: {}", lock_type, resource, err),
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(LockResult::Timeout);
+        }
+
+        let backoff = Duration::from_millis(rand::rng().random_range(10..=DIST_LOCK_MAX_BACKOFF.as_millis() as u64));
+        tokio::time::sleep(backoff.min(deadline - now)).await;
+    }
+}
+
+/// Object lock held by a `SetDisks`; dropping it releases every part of the lock.
+#[derive(Debug)]
+pub struct NsLockGuard {
+    // Field order matters: the distributed lock is released before the local one
+    distributed: Option<LockGuard>,
+    local: FastLockGuard,
+}
+
+impl NsLockGuard {
+    pub fn new(local: FastLockGuard, distributed: Option<LockGuard>) -> Self {
+        Self { distributed, local }
+    }
+
+    pub fn mode(&self) -> LockMode {
+        self.local.mode()
+    }
+
+    pub fn is_distributed(&self) -> bool {
+        self.distributed.is_some()
+    }
+
+    /// Whether the distributed lease could no longer be refreshed on a quorum of nodes
+    pub fn is_lost(&self) -> bool {
+        self.distributed.as_ref().is_some_and(|guard| guard.is_lost())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_mode_parse() {
+        assert_eq!("".parse::<NsLockMode>().unwrap(), NsLockMode::Auto);
+        assert_eq!("AUTO".parse::<NsLockMode>().unwrap(), NsLockMode::Auto);
+        assert_eq!("local".parse::<NsLockMode>().unwrap(), NsLockMode::Local);
+        assert_eq!("distributed".parse::<NsLockMode>().unwrap(), NsLockMode::Distributed);
+        assert!("quorum".parse::<NsLockMode>().is_err());
+    }
+
+    #[test]
+    fn test_lock_mode_is_distributed() {
+        assert!(NsLockMode::Auto.is_distributed(true));
+        assert!(!NsLockMode::Auto.is_distributed(false));
+        assert!(!NsLockMode::Local.is_distributed(true));
+        assert!(NsLockMode::Distributed.is_distributed(false));
+    }
+
+    #[test]
+    fn test_single_node_set_has_no_namespace_lock() {
+        let mut endpoints = Vec::new();
+        for i in 0..4 {
+            let mut ep = Endpoint::try_from(format!("/tmp/nebulafx-ns-lock-{i}").as_str()).unwrap();
+            ep.is_local = true;
+            endpoints.push(ep);
+        }
+        assert!(new_set_namespace_lock(0, 0, &endpoints).is_none());
+    }
+
+    #[test]
+    fn test_multi_node_set_uses_majority_quorum() {
+        let mut endpoints = Vec::new();
+        for host in ["node1", "node2", "node3"] {
+            for disk in 0..2 {
+                let mut ep = Endpoint::try_from(format!("http://{host}:9000/data{disk}").as_str()).unwrap();
+                ep.is_local = host == "node1";
+                endpoints.push(ep);
+            }
+        }
+        let ns_lock = new_set_namespace_lock(0, 1, &endpoints).unwrap();
+        assert_eq!(ns_lock.namespace(), "0/1");
+        assert_eq!(ns_lock.quorum(), 2);
+    }
+}
@@ -6,6 +6,6 @@ mod peer_s3_client;
 mod remote_disk;
 
 pub use http_auth::{build_auth_headers, verify_rpc_signature};
-pub use peer_rest_client::PeerRestClient;
+pub use peer_rest_client::{PEER_RESTDRY_RUN, PEER_RESTSIGNAL, PeerRestClient};
 pub use peer_s3_client::{LocalPeerS3Client, PeerS3Client, RemotePeerS3Client, S3PeerSys};
 pub use remote_disk::RemoteDisk;
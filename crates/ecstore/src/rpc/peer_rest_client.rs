@@ -1,27 +1,35 @@
 
 
+use crate::bucket::replication::SRMetricsSummary;
+use crate::cache_value::metacache::Metacache;
 use crate::error::{Error, Result};
 use crate::{
     endpoints::EndpointServerPools,
     global::is_dist_erasure,
     metrics_realtime::{CollectMetricsOpts, MetricType},
+    store_list_objects::ListPathOptions,
 };
 use rmp_serde::{Deserializer, Serializer};
+use futures::{StreamExt, stream::BoxStream};
 use nebulafx_madmin::{
     ServerProperties,
+    heal_commands::BgHealState,
     health::{Cpus, MemInfo, OsInfo, Partitions, ProcInfo, SysConfig, SysErrors, SysService},
     metrics::RealtimeMetrics,
     net::NetInfo,
+    trace::TraceInfo,
 };
 use nebulafx_protos::{
     node_service_time_out_client,
     proto_gen::node_service::{
-        DeleteBucketMetadataRequest, DeletePolicyRequest, DeleteServiceAccountRequest, DeleteUserRequest, GetCpusRequest,
-        GetMemInfoRequest, GetMetricsRequest, GetNetInfoRequest, GetOsInfoRequest, GetPartitionsRequest, GetProcInfoRequest,
-        GetSeLinuxInfoRequest, GetSysConfigRequest, GetSysErrorsRequest, LoadBucketMetadataRequest, LoadGroupRequest,
+        BackgroundHealStatusRequest, CancelReplicationResyncRequest, DeleteBucketMetadataRequest, DeletePolicyRequest,
+        DeleteServiceAccountRequest, DeleteUserRequest, GetCpusRequest, GetMemInfoRequest, GetMetacacheListingRequest,
+        GetMetricsRequest, GetNetInfoRequest, GetOsInfoRequest, GetPartitionsRequest, GetProcInfoRequest, GetSeLinuxInfoRequest,
+        GetSrMetricsDataRequest, GetSysConfigRequest, GetSysErrorsRequest, LoadBucketMetadataRequest, LoadGroupRequest,
         LoadPolicyMappingRequest, LoadPolicyRequest, LoadRebalanceMetaRequest, LoadServiceAccountRequest,
         LoadTransitionTierConfigRequest, LoadUserRequest, LocalStorageInfoRequest, Mss, ReloadPoolMetaRequest,
         ReloadSiteReplicationConfigRequest, ServerInfoRequest, SignalServiceRequest, StartProfilingRequest, StopRebalanceRequest,
+        TraceRequest, UpdateMetacacheListingRequest,
     },
 };
 use nebulafx_utils::XHost;
@@ -358,8 +366,46 @@ impl PeerRestClient {
         todo!()
     }
 
-    pub async fn get_sr_metrics(&self) -> Result<()> {
-        todo!()
+    pub async fn get_sr_metrics(&self) -> Result<SRMetricsSummary> {
+        let mut client = node_service_time_out_client(&self.grid_host)
+            .await
+            .map_err(|err| Error::other(err.to_string()))?;
+        let request = Request::new(GetSrMetricsDataRequest {});
+
+        let response = client.get_sr_metrics(request).await?.into_inner();
+        if !response.success {
+            if let Some(msg) = response.error_info {
+                return Err(Error::other(msg));
+            }
+            return Err(Error::other(""));
+        }
+        let data = response.sr_metrics_summary;
+
+        let mut buf = Deserializer::new(Cursor::new(data));
+        let summary: SRMetricsSummary = Deserialize::deserialize(&mut buf)?;
+
+        Ok(summary)
+    }
+
+    pub async fn background_heal_status(&self) -> Result<BgHealState> {
+        let mut client = node_service_time_out_client(&self.grid_host)
+            .await
+            .map_err(|err| Error::other(err.to_string()))?;
+        let request = Request::new(BackgroundHealStatusRequest {});
+
+        let response = client.background_heal_status(request).await?.into_inner();
+        if !response.success {
+            if let Some(msg) = response.error_info {
+                return Err(Error::other(msg));
+            }
+            return Err(Error::other(""));
+        }
+        let data = response.bg_heal_state;
+
+        let mut buf = Deserializer::new(Cursor::new(data));
+        let state: BgHealState = Deserialize::deserialize(&mut buf)?;
+
+        Ok(state)
     }
 
     pub async fn get_all_bucket_stats(&self) -> Result<()> {
@@ -587,18 +633,50 @@ impl PeerRestClient {
         Ok(())
     }
 
-    pub async fn get_metacache_listing(&self) -> Result<()> {
-        let _client = node_service_time_out_client(&self.grid_host)
+    pub async fn get_metacache_listing(&self, opts: &ListPathOptions) -> Result<Option<Metacache>> {
+        let mut client = node_service_time_out_client(&self.grid_host)
             .await
             .map_err(|err| Error::other(err.to_string()))?;
-        todo!()
+        let mut buf_o = Vec::new();
+        opts.serialize(&mut Serializer::new(&mut buf_o))?;
+        let request = Request::new(GetMetacacheListingRequest { opts: buf_o.into() });
+
+        let response = client.get_metacache_listing(request).await?.into_inner();
+        if !response.success {
+            if let Some(msg) = response.error_info {
+                return Err(Error::other(msg));
+            }
+            return Err(Error::other(""));
+        }
+        let data = response.metacache;
+
+        let mut buf = Deserializer::new(Cursor::new(data));
+        let metacache: Option<Metacache> = Deserialize::deserialize(&mut buf)?;
+
+        Ok(metacache)
     }
 
-    pub async fn update_metacache_listing(&self) -> Result<()> {
-        let _client = node_service_time_out_client(&self.grid_host)
+    pub async fn update_metacache_listing(&self, update: &Metacache) -> Result<Metacache> {
+        let mut client = node_service_time_out_client(&self.grid_host)
             .await
             .map_err(|err| Error::other(err.to_string()))?;
-        todo!()
+        let mut buf_m = Vec::new();
+        update.serialize(&mut Serializer::new(&mut buf_m))?;
+        let request = Request::new(UpdateMetacacheListingRequest { metacache: buf_m.into() });
+
+        let response = client.update_metacache_listing(request).await?.into_inner();
+        if !response.success {
+            if let Some(msg) = response.error_info {
+                return Err(Error::other(msg));
+            }
+            return Err(Error::other(""));
+        }
+        let data = response.metacache;
+
+        let mut buf = Deserializer::new(Cursor::new(data));
+        let metacache: Metacache = Deserialize::deserialize(&mut buf)?;
+
+        Ok(metacache)
     }
 
     pub async fn reload_pool_meta(&self) -> Result<()> {
@@ -654,6 +732,25 @@ impl PeerRestClient {
         Ok(())
     }
 
+    /// Stream the trace records published on the peer, filtered by the URL-encoded admin trace query
+    pub async fn trace(&self, opts: &str) -> Result<BoxStream<'static, Result<TraceInfo>>> {
+        let mut client = node_service_time_out_client(&self.grid_host)
+            .await
+            .map_err(|err| Error::other(err.to_string()))?;
+        let request = Request::new(TraceRequest { opts: opts.to_string() });
+
+        let stream = client.trace(request).await?.into_inner();
+        Ok(stream
+            .map(|item| {
+                let response = item?;
+                if !response.success {
+                    return Err(Error::other(response.error_info.unwrap_or_default()));
+                }
+                serde_json::from_str::<TraceInfo>(&response.trace_info).map_err(|err| Error::other(err.to_string()))
+            })
+            .boxed())
+    }
+
     pub async fn load_transition_tier_config(&self) -> Result<()> {
         let mut client = node_service_time_out_client(&self.grid_host)
             .await
@@ -670,4 +767,25 @@ impl PeerRestClient {
 
         Ok(())
     }
+
+    /// Cancel the replication resync of a bucket to a target, returns whether it was running on the peer
+    pub async fn cancel_replication_resync(&self, bucket: &str, arn: &str) -> Result<bool> {
+        let mut client = node_service_time_out_client(&self.grid_host)
+            .await
+            .map_err(|err| Error::other(err.to_string()))?;
+        let request = Request::new(CancelReplicationResyncRequest {
+            bucket: bucket.to_string(),
+            arn: arn.to_string(),
+        });
+
+        let response = client.cancel_replication_resync(request).await?.into_inner();
+        if !response.success {
+            if let Some(msg) = response.error_info {
+                return Err(Error::other(msg));
+            }
+            return Err(Error::other(""));
+        }
+
+        Ok(response.canceled)
+    }
 }
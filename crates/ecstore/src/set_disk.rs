@@ -5,13 +5,15 @@
 
 use crate::batch_processor::{AsyncBatchProcessor, get_global_processors};
 use crate::bitrot::{create_bitrot_reader, create_bitrot_writer};
-use crate::bucket::lifecycle::lifecycle::TRANSITION_COMPLETE;
+use crate::bucket::lifecycle::lifecycle::{Lifecycle, TRANSITION_COMPLETE};
+use crate::bucket::metadata_sys;
 use crate::bucket::replication::check_replicate_delete;
 use crate::bucket::versioning::VersioningApi;
 use crate::bucket::versioning_sys::BucketVersioningSys;
 use crate::client::{object_api_utils::get_raw_etag, transition_api::ReaderImpl};
 use crate::disk::STORAGE_FORMAT_FILE;
 use crate::disk::error_reduce::{OBJECT_OP_IGNORED_ERRS, reduce_read_quorum_errs, reduce_write_quorum_errs};
+use crate::disk::healing::HealingTracker;
 use crate::disk::{
     self, CHECK_PART_DISK_NOT_FOUND, CHECK_PART_FILE_CORRUPT, CHECK_PART_FILE_NOT_FOUND, CHECK_PART_SUCCESS,
     conv_part_err_to_int, has_part_err,
@@ -21,9 +23,10 @@ use crate::erasure_coding::bitrot_verify;
 use crate::error::{Error, Result, is_err_version_not_found};
 use crate::error::{GenericError, ObjectApiError, is_err_object_not_found};
 use crate::global::{GLOBAL_LocalNodeName, GLOBAL_TierConfigMgr};
+use crate::ns_lock::{NsLockGuard, acquire_distributed};
 use crate::store_api::ListObjectVersionsInfo;
 use crate::store_api::{ListPartsInfo, ObjectOptions, ObjectToDelete};
-use crate::store_api::{ObjectInfoOrErr, WalkOptions};
+use crate::store_api::{ObjectInfoOrErr, StaleUploadsStats, WalkOptions};
 use crate::{
     bucket::lifecycle::bucket_lifecycle_ops::{
         LifecycleOps, gen_transition_objname, get_transitioned_object_reader, put_restore_opts,
@@ -56,11 +59,13 @@ use md5::{Digest as Md5Digest, Md5};
 use rand::{Rng, seq::SliceRandom};
 use regex::Regex;
 use nebulafx_common::heal_channel::{DriveState, HealChannelPriority, HealItemType, HealOpts, HealScanMode, send_heal_disk};
+use nebulafx_common::metrics::IlmAction;
 use nebulafx_filemeta::{
     FileInfo, FileMeta, FileMetaShallowVersion, MetaCacheEntries, MetaCacheEntry, MetadataResolutionParams, ObjectPartInfo,
-    RawFileInfo, ReplicationStatusType, VersionPurgeStatusType, file_info_from_raw, merge_file_meta_versions,
+    PART_NONCES_KEY, RawFileInfo, ReplicationStatusType, VersionPurgeStatusType, file_info_from_raw, merge_file_meta_versions,
 };
 use nebulafx_lock::fast_lock::types::LockResult;
+use nebulafx_lock::{FastLockGuard, LockType};
 use nebulafx_madmin::heal_commands::{HealDriveInfo, HealResultItem};
 use nebulafx_rio::{EtagResolvable, HashReader, HashReaderMut, TryGetIndex as _, WarpReader};
 use nebulafx_utils::http::NEUBULAFX_BUCKET_REPLICATION_SSEC_CHECKSUM;
@@ -72,6 +77,7 @@ use nebulafx_utils::{
     path::{SLASH_SEPARATOR, encode_dir_object, has_suffix, path_join_buf},
 };
 use nebulafx_workers::workers::Workers;
+use s3s::dto::BucketLifecycleConfiguration;
 use s3s::header::X_AMZ_RESTORE;
 use sha2::{Digest, Sha256};
 use std::hash::Hash;
@@ -104,9 +110,15 @@ pub const MAX_PARTS_COUNT: usize = 10000;
 const DISK_ONLINE_TIMEOUT: Duration = Duration::from_secs(1);
 const DISK_HEALTH_CACHE_TTL: Duration = Duration::from_millis(750);
 
+// Bucket and object of a multipart upload, the upload path only has their hash
+const MULTIPART_UPLOAD_BUCKET_KEY: &str = "x-nebulafx-internal-multipart-bucket";
+const MULTIPART_UPLOAD_OBJECT_KEY: &str = "x-nebulafx-internal-multipart-object";
+
 #[derive(Clone, Debug)]
 pub struct SetDisks {
     pub fast_lock_manager: Arc<nebulafx_lock::FastObjectLockManager>,
+    /// Quorum lock across all nodes serving this set, `None` when locking is node-local
+    pub ns_lock: Option<Arc<nebulafx_lock::NamespaceLock>>,
     pub locker_owner: String,
     pub disks: Arc<RwLock<Vec<Option<DiskStore>>>>,
     pub set_endpoints: Vec<Endpoint>,
@@ -138,6 +150,7 @@ impl SetDisks {
     #[allow(clippy::too_many_arguments)]
     pub async fn new(
         fast_lock_manager: Arc<nebulafx_lock::FastObjectLockManager>,
+        ns_lock: Option<Arc<nebulafx_lock::NamespaceLock>>,
         locker_owner: String,
         disks: Arc<RwLock<Vec<Option<DiskStore>>>>,
         set_drive_count: usize,
@@ -149,6 +162,7 @@ impl SetDisks {
     ) -> Arc<Self> {
         Arc::new(SetDisks {
             fast_lock_manager,
+            ns_lock,
             locker_owner,
             disks,
             set_drive_count,
@@ -211,6 +225,42 @@ impl SetDisks {
 
         (filtered, online_count)
     }
+
+    /// Acquire a shared object lock, on a quorum of nodes when the set is distributed.
+    pub async fn acquire_read_lock(&self, bucket: &str, object: &str) -> std::result::Result<NsLockGuard, LockResult> {
+        let local = self
+            .fast_lock_manager
+            .acquire_read_lock(bucket, object, self.locker_owner.as_str())
+            .await?;
+        self.with_distributed_lock(local, bucket, object, LockType::Shared).await
+    }
+
+    /// Acquire an exclusive object lock, on a quorum of nodes when the set is distributed.
+    pub async fn acquire_write_lock(&self, bucket: &str, object: &str) -> std::result::Result<NsLockGuard, LockResult> {
+        let local = self
+            .fast_lock_manager
+            .acquire_write_lock(bucket, object, self.locker_owner.as_str())
+            .await?;
+        self.with_distributed_lock(local, bucket, object, LockType::Exclusive).await
+    }
+
+    /// The local lock is taken first so that concurrent requests on this node queue
+    /// up locally instead of competing for the remote quorum.
+    async fn with_distributed_lock(
+        &self,
+        local: FastLockGuard,
+        bucket: &str,
+        object: &str,
+        lock_type: LockType,
+    ) -> std::result::Result<NsLockGuard, LockResult> {
+        let Some(ns_lock) = self.ns_lock.as_ref() else {
+            return Ok(NsLockGuard::new(local, None));
+        };
+
+        let distributed = acquire_distributed(ns_lock, bucket, object, self.locker_owner.as_str(), lock_type).await?;
+        Ok(NsLockGuard::new(local, Some(distributed)))
+    }
+
     fn format_lock_error(&self, bucket: &str, object: &str, mode: &str, err: &LockResult) -> String {
         match err {
             LockResult::Timeout => {
@@ -223,6 +273,15 @@ impl SetDisks {
             LockResult::Acquired => format!("unexpected lock state while acquiring {mode} lock on {bucket}/{object}"),
         }
     }
+    /// Fails once the distributed lease of a write lock is lost, another node may hold the object then.
+    /// Writers check it before each step that makes their data visible.
+    fn check_write_lock(guard: Option<&NsLockGuard>, bucket: &str, object: &str) -> Result<()> {
+        if guard.is_some_and(NsLockGuard::is_lost) {
+            return Err(Error::other(format!("write lock on {bucket}/{object} was lost")));
+        }
+        Ok(())
+    }
+
     async fn get_disks_internal(&self) -> Vec<Option<DiskStore>> {
         let rl = self.disks.read().await;
 
@@ -609,6 +668,147 @@ impl SetDisks {
         }
     }
 
+    /// Whether this node sweeps the stale uploads of the set: the node of the first drive, in set
+    /// order, that is online. Nodes that see a different drive state may both sweep for a while,
+    /// aborting an upload twice is harmless.
+    async fn owns_stale_uploads_sweep(&self) -> bool {
+        for disk in self.get_disks_internal().await.into_iter().flatten() {
+            if disk.is_online().await {
+                return disk.is_local();
+            }
+        }
+        false
+    }
+
+    /// Abort the multipart uploads of this set that are older than `max_age`, or due
+    /// under the AbortIncompleteMultipartUpload lifecycle rule of their bucket.
+    ///
+    /// Uploads are listed from a local drive. Every node of the set holds them all, so only the node
+    /// of the first online drive sweeps, see [`Self::owns_stale_uploads_sweep`].
+    pub async fn cleanup_stale_uploads(&self, max_age: Duration) -> StaleUploadsStats {
+        let mut stats = StaleUploadsStats::default();
+        if !self.owns_stale_uploads_sweep().await {
+            return stats;
+        }
+
+        // Every drive of the set holds all uploads
+        let Some(disk) = self.get_online_local_disks().await.into_iter().flatten().next() else {
+            return stats;
+        };
+        let Ok(sha_dirs) = disk
+            .list_dir(NEUBULAFX_META_MULTIPART_BUCKET, NEUBULAFX_META_MULTIPART_BUCKET, "", -1)
+            .await
+        else {
+            return stats;
+        };
+
+        let now = OffsetDateTime::now_utc();
+        let mut lifecycles: HashMap<String, Option<BucketLifecycleConfiguration>> = HashMap::new();
+
+        for sha_dir in sha_dirs.iter() {
+            let sha_dir = sha_dir.trim_end_matches(SLASH_SEPARATOR);
+            let Ok(upload_uuids) = disk
+                .list_dir(NEUBULAFX_META_MULTIPART_BUCKET, NEUBULAFX_META_MULTIPART_BUCKET, sha_dir, -1)
+                .await
+            else {
+                continue;
+            };
+
+            for upload_uuid in upload_uuids.iter() {
+                let upload_uuid = upload_uuid.trim_end_matches(SLASH_SEPARATOR);
+                let upload_path = format!("{sha_dir}/{upload_uuid}");
+                let Ok(fi) = disk
+                    .read_version(
+                        NEUBULAFX_META_MULTIPART_BUCKET,
+                        NEUBULAFX_META_MULTIPART_BUCKET,
+                        &upload_path,
+                        "",
+                        &ReadOptions::default(),
+                    )
+                    .await
+                else {
+                    continue;
+                };
+                let Some(initiated) = fi.mod_time else {
+                    continue;
+                };
+
+                let target = fi
+                    .metadata
+                    .get(MULTIPART_UPLOAD_BUCKET_KEY)
+                    .zip(fi.metadata.get(MULTIPART_UPLOAD_OBJECT_KEY));
+
+                let mut expired = now - initiated >= max_age;
+                if !expired {
+                    if let Some((bucket, object)) = target {
+                        if !lifecycles.contains_key(bucket) {
+                            let lc = metadata_sys::get_lifecycle_config(bucket).await.ok().map(|(lc, _)| lc);
+                            lifecycles.insert(bucket.clone(), lc);
+                        }
+                        expired = lifecycles[bucket].as_ref().is_some_and(|lc| {
+                            lc.eval_incomplete_upload(object, initiated, now).action == IlmAction::AbortMultipartUploadAction
+                        });
+                    }
+                }
+                if !expired {
+                    continue;
+                }
+
+                let bytes = Self::multipart_upload_size(&disk, &upload_path, &fi).await;
+                match target {
+                    Some((bucket, object)) => {
+                        let upload_id = base64_simd::URL_SAFE_NO_PAD.encode_to_string(
+                            format!("{}.{}", get_global_deployment_id().unwrap_or_default(), upload_uuid).as_bytes(),
+                        );
+                        if let Err(err) = self
+                            .abort_multipart_upload(bucket, object, &upload_id, &ObjectOptions::default())
+                            .await
+                        {
+                            warn!("abort stale upload {bucket}/{object} {upload_uuid} failed: {err}");
+                            continue;
+                        }
+                    }
+                    // Uploads started before the bucket and object were recorded
+                    None => {
+                        let disks = self.get_disks_internal().await;
+                        Self::cleanup_multipart_path(&disks, &[upload_path.clone()]).await;
+                    }
+                }
+
+                debug!("aborted stale multipart upload {upload_path}, initiated {initiated}");
+                stats.uploads += 1;
+                stats.bytes += bytes;
+            }
+        }
+
+        stats
+    }
+
+    /// Size of the parts uploaded so far, from the part metadata on `disk`
+    async fn multipart_upload_size(disk: &DiskStore, upload_path: &str, fi: &FileInfo) -> u64 {
+        let part_path = format!("{}/{}", upload_path, fi.data_dir.unwrap_or(Uuid::nil()));
+        let Ok(entries) = disk
+            .list_dir(NEUBULAFX_META_MULTIPART_BUCKET, NEUBULAFX_META_MULTIPART_BUCKET, &part_path, -1)
+            .await
+        else {
+            return 0;
+        };
+
+        let part_meta_paths: Vec<String> = entries
+            .iter()
+            .filter(|entry| entry.starts_with("part.") && entry.ends_with(".meta"))
+            .map(|entry| format!("{part_path}/{entry}"))
+            .collect();
+        if part_meta_paths.is_empty() {
+            return 0;
+        }
+
+        match disk.read_parts(NEUBULAFX_META_MULTIPART_BUCKET, &part_meta_paths).await {
+            Ok(parts) => parts.iter().filter(|p| p.error.is_none()).map(|p| p.size as u64).sum(),
+            Err(_) => 0,
+        }
+    }
+
     async fn read_parts(
         disks: &[Option<DiskStore>],
         bucket: &str,
@@ -2513,7 +2713,7 @@ impl SetDisks {
             ..Default::default()
         };
 
-        let _write_lock_guard = if !opts.no_lock {
+        let write_lock_guard = if !opts.no_lock {
             info!("Acquiring write lock for object: {}, owner: {}", object, self.locker_owner);
 
             // Check if lock is already held
@@ -2536,16 +2736,12 @@ impl SetDisks {
                 None
             } else {
                 let start_time = std::time::Instant::now();
-                let lock_result = self
-                    .fast_lock_manager
-                    .acquire_write_lock(bucket, object, self.locker_owner.as_str())
-                    .await
-                    .map_err(|e| {
-                        let elapsed = start_time.elapsed();
-                        let message = self.format_lock_error(bucket, object, "write", &e);
-                        error!("Failed to acquire write lock for heal operation after {:?}: {}", elapsed, message);
-                        DiskError::other(message)
-                    })?;
+                let lock_result = self.acquire_write_lock(bucket, object).await.map_err(|e| {
+                    let elapsed = start_time.elapsed();
+                    let message = self.format_lock_error(bucket, object, "write", &e);
+                    error!("Failed to acquire write lock for heal operation after {:?}: {}", elapsed, message);
+                    DiskError::other(message)
+                })?;
                 let elapsed = start_time.elapsed();
                 info!("Successfully acquired write lock for object: {} in {:?}", object, elapsed);
                 Some(lock_result)
@@ -2990,6 +3186,7 @@ impl SetDisks {
                                         part.actual_size,
                                         part.index.clone(),
                                         part.checksums.clone(),
+                                        part.nonce.clone(),
                                     );
                                     if is_inline_buffer {
                                         if let Some(writer) = writers[index].take() {
@@ -3015,6 +3212,10 @@ impl SetDisks {
                                 }
                             }
                         }
+                        if let Err(err) = Self::check_write_lock(write_lock_guard.as_ref(), bucket, object) {
+                            let _ = self.delete_all(NEUBULAFX_META_TMP_BUCKET, &tmp_id).await;
+                            return Err(err.into());
+                        }
                         // Rename from tmp location to the actual location.
                         for (index, outdated_disk) in out_dated_disks.iter().enumerate() {
                             if let Some(disk) = outdated_disk {
@@ -3261,14 +3462,10 @@ impl SetDisks {
         dry_run: bool,
         remove: bool,
     ) -> Result<(HealResultItem, Option<DiskError>)> {
-        let _write_lock_guard = self
-            .fast_lock_manager
-            .acquire_write_lock(bucket, object, self.locker_owner.as_str())
-            .await
-            .map_err(|e| {
-                let message = self.format_lock_error(bucket, object, "write", &e);
-                DiskError::other(message)
-            })?;
+        let _write_lock_guard = self.acquire_write_lock(bucket, object).await.map_err(|e| {
+            let message = self.format_lock_error(bucket, object, "write", &e);
+            DiskError::other(message)
+        })?;
 
         self.heal_object_dir_locked(bucket, object, dry_run, remove).await
     }
@@ -3535,8 +3732,7 @@ impl ObjectIO for SetDisks {
         // Acquire a shared read-lock early to protect read consistency
         let _read_lock_guard = if !opts.no_lock {
             Some(
-                self.fast_lock_manager
-                    .acquire_read_lock(bucket, object, self.locker_owner.as_str())
+                self.acquire_read_lock(bucket, object)
                     .await
                     .map_err(|e| Error::other(self.format_lock_error(bucket, object, "read", &e)))?,
             )
@@ -3626,10 +3822,9 @@ impl ObjectIO for SetDisks {
         let (disks, filtered_online) = self.filter_online_disks(disks_snapshot).await;
 
         // Acquire per-object exclusive lock via RAII guard. It auto-releases asynchronously on drop.
-        let _object_lock_guard = if !opts.no_lock {
+        let object_lock_guard = if !opts.no_lock {
             Some(
-                self.fast_lock_manager
-                    .acquire_write_lock(bucket, object, self.locker_owner.as_str())
+                self.acquire_write_lock(bucket, object)
                     .await
                     .map_err(|e| Error::other(self.format_lock_error(bucket, object, "write", &e)))?,
             )
@@ -3843,7 +4038,7 @@ impl ObjectIO for SetDisks {
             pfi.mod_time = mod_time;
             pfi.size = w_size as i64;
             pfi.versioned = opts.versioned || opts.version_suspended;
-            pfi.add_object_part(1, etag.clone(), w_size, mod_time, actual_size, index_op.clone(), None);
+            pfi.add_object_part(1, etag.clone(), w_size, mod_time, actual_size, index_op.clone(), None, None);
             pfi.checksum = fi.checksum.clone();
 
             if opts.data_movement {
@@ -3853,6 +4048,11 @@ impl ObjectIO for SetDisks {
 
         drop(writers); // drop writers to close all files, this is to prevent FileAccessDenied errors when renaming data
 
+        if let Err(err) = Self::check_write_lock(object_lock_guard.as_ref(), bucket, object) {
+            let _ = self.delete_all(NEUBULAFX_META_TMP_BUCKET, &tmp_dir).await;
+            return Err(err);
+        }
+
         let (online_disks, _, op_old_dir) = Self::rename_data(
             &shuffle_disks,
             NEUBULAFX_META_TMP_BUCKET,
@@ -3865,6 +4065,7 @@ impl ObjectIO for SetDisks {
         .await?;
 
         if let Some(old_dir) = op_old_dir {
+            Self::check_write_lock(object_lock_guard.as_ref(), bucket, object)?;
             self.commit_rename_data_dir(&shuffle_disks, bucket, object, &old_dir.to_string(), write_quorum)
                 .await?;
         }
@@ -3949,8 +4150,7 @@ impl StorageAPI for SetDisks {
 
         // Guard lock for source object metadata update
         let _lock_guard = self
-            .fast_lock_manager
-            .acquire_write_lock(src_bucket, src_object, self.locker_owner.as_str())
+            .acquire_write_lock(src_bucket, src_object)
             .await
             .map_err(|e| Error::other(self.format_lock_error(src_bucket, src_object, "write", &e)))?;
 
@@ -4119,7 +4319,7 @@ impl StorageAPI for SetDisks {
         }
 
         let batch_result = self.fast_lock_manager.acquire_locks_batch(batch).await;
-        let locked_objects: HashSet<String> = batch_result
+        let mut locked_objects: HashSet<String> = batch_result
             .successful_locks
             .iter()
             .map(|key| key.object.as_ref().to_string())
@@ -4140,6 +4340,29 @@ impl StorageAPI for SetDisks {
             }
         }
 
+        // Objects locked locally must also be locked on a quorum of nodes in distributed mode
+        let mut _dist_lock_guards = Vec::new();
+        if let Some(ns_lock) = self.ns_lock.as_ref() {
+            let futs = locked_objects.iter().map(|object| async move {
+                let res = acquire_distributed(ns_lock, bucket, object, self.locker_owner.as_str(), LockType::Exclusive).await;
+                (object.clone(), res)
+            });
+            for (object, res) in join_all(futs).await {
+                match res {
+                    Ok(guard) => _dist_lock_guards.push(guard),
+                    Err(err) => {
+                        let message = self.format_lock_error(bucket, object.as_str(), "write", &err);
+                        for (i, dobj) in objects.iter().enumerate() {
+                            if dobj.object_name == object {
+                                del_errs[i] = Some(Error::other(message.clone()));
+                            }
+                        }
+                        locked_objects.remove(&object);
+                    }
+                }
+            }
+        }
+
         // let mut del_fvers = Vec::with_capacity(objects.len());
 
         let ver_cfg = BucketVersioningSys::get(bucket).await.unwrap_or_default();
@@ -4307,8 +4530,7 @@ impl StorageAPI for SetDisks {
         // Guard lock for single object delete
         let _lock_guard = if !opts.delete_prefix {
             Some(
-                self.fast_lock_manager
-                    .acquire_write_lock(bucket, object, self.locker_owner.as_str())
+                self.acquire_write_lock(bucket, object)
                     .await
                     .map_err(|e| Error::other(self.format_lock_error(bucket, object, "write", &e)))?,
             )
@@ -4484,8 +4706,7 @@ impl StorageAPI for SetDisks {
         // Acquire a shared read-lock to protect consistency during info fetch
         let _read_lock_guard = if !opts.no_lock {
             Some(
-                self.fast_lock_manager
-                    .acquire_read_lock(bucket, object, self.locker_owner.as_str())
+                self.acquire_read_lock(bucket, object)
                     .await
                     .map_err(|e| Error::other(self.format_lock_error(bucket, object, "read", &e)))?,
             )
@@ -4524,8 +4745,7 @@ impl StorageAPI for SetDisks {
         // Guard lock for metadata update
         let _lock_guard = if !opts.no_lock {
             Some(
-                self.fast_lock_manager
-                    .acquire_write_lock(bucket, object, self.locker_owner.as_str())
+                self.acquire_write_lock(bucket, object)
                     .await
                     .map_err(|e| Error::other(self.format_lock_error(bucket, object, "write", &e)))?,
             )
@@ -5062,6 +5282,7 @@ impl StorageAPI for SetDisks {
             actual_size,
             index: index_op,
             checksums: if checksums.is_empty() { None } else { Some(checksums) },
+            nonce: opts.part_nonce.clone(),
             ..Default::default()
         };
 
@@ -5443,6 +5664,10 @@ impl StorageAPI for SetDisks {
             );
         }
 
+        // Lets the stale upload sweeper evaluate the bucket lifecycle for this upload
+        user_defined.insert(MULTIPART_UPLOAD_BUCKET_KEY.to_string(), bucket.to_owned());
+        user_defined.insert(MULTIPART_UPLOAD_OBJECT_KEY.to_string(), object.to_owned());
+
         let (shuffle_disks, mut parts_metadatas) = Self::shuffle_disks_and_parts_metadata(&disks, &parts_metadata, &fi);
 
         let mod_time = opts.mod_time.unwrap_or(OffsetDateTime::now_utc());
@@ -5611,6 +5836,7 @@ impl StorageAPI for SetDisks {
                 part.actual_size,
                 part.index.clone(),
                 part.checksums.clone(),
+                part.nonce.clone(),
             );
         }
 
@@ -5748,10 +5974,24 @@ impl StorageAPI for SetDisks {
                 mod_time: ext_part.mod_time,
                 actual_size: ext_part.actual_size,
                 index: ext_part.index.clone(),
+                nonce: ext_part.nonce.clone(),
                 ..Default::default()
             });
         }
 
+        if fi.parts.iter().any(|part| part.nonce.is_some()) {
+            let nonces = fi
+                .parts
+                .iter()
+                .filter_map(|part| {
+                    let nonce = part.nonce.as_ref()?;
+                    Some(format!("{}:{}", part.number, base64_simd::STANDARD.encode_to_string(nonce)))
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            fi.metadata.insert(PART_NONCES_KEY.to_owned(), nonces);
+        }
+
         if let Some(wtcs) = opts.want_checksum.as_ref() {
             if checksum_type.full_object_requested() {
                 if wtcs.encoded != checksum.encoded {
@@ -5797,6 +6037,8 @@ impl StorageAPI for SetDisks {
 
         fi.metadata.remove(nebulafx_rio::NEUBULAFX_MULTIPART_CHECKSUM);
         fi.metadata.remove(nebulafx_rio::NEUBULAFX_MULTIPART_CHECKSUM_TYPE);
+        fi.metadata.remove(MULTIPART_UPLOAD_BUCKET_KEY);
+        fi.metadata.remove(MULTIPART_UPLOAD_OBJECT_KEY);
 
         fi.size = object_size as i64;
         fi.mod_time = opts.mod_time;
@@ -5975,8 +6217,7 @@ impl StorageAPI for SetDisks {
                 None
             } else {
                 Some(
-                    self.fast_lock_manager
-                        .acquire_write_lock(bucket, object, self.locker_owner.as_str())
+                    self.acquire_write_lock(bucket, object)
                         .await
                         .map_err(|e| Error::other(self.format_lock_error(bucket, object, "write", &e)))?,
                 )
@@ -6425,6 +6666,11 @@ async fn get_disks_info(disks: &[Option<DiskStore>], eps: &[Endpoint]) -> Vec<ne
         if let Some(disk) = pool {
             match disk.disk_info(&DiskInfoOptions::default()).await {
                 Ok(res) => ret.push(nebulafx_madmin::Disk {
+                    heal_info: if res.healing {
+                        HealingTracker::load(disk).await.ok().map(|tracker| tracker.to_healing_disk())
+                    } else {
+                        None
+                    },
                     endpoint: eps[i].to_string(),
                     local: eps[i].is_local,
                     pool_index: eps[i].pool_idx,
@@ -5,7 +5,7 @@ use std::{collections::HashMap, sync::Arc};
 
 use crate::disk::error_reduce::count_errs;
 use crate::error::{Error, Result};
-use crate::store_api::{ListPartsInfo, ObjectInfoOrErr, WalkOptions};
+use crate::store_api::{ListPartsInfo, ObjectInfoOrErr, StaleUploadsStats, WalkOptions};
 use crate::{
     disk::{
         DiskAPI, DiskInfo, DiskOption, DiskStore,
@@ -16,6 +16,7 @@ use crate::{
     endpoints::{Endpoints, PoolEndpoints},
     error::StorageError,
     global::{GLOBAL_LOCAL_DISK_SET_DRIVES, is_dist_erasure},
+    ns_lock::{NsLockMode, new_set_namespace_lock},
     set_disk::SetDisks,
     store_api::{
         BucketInfo, BucketOptions, CompletePart, DeleteBucketOptions, DeletedObject, GetObjectReader, HTTPRangeSpec,
@@ -97,6 +98,8 @@ impl Sets {
             }
         }
 
+        let use_dist_locks = NsLockMode::from_env().is_distributed(is_dist_erasure().await);
+
         let mut disk_set = Vec::with_capacity(set_count);
 
         for i in 0..set_count {
@@ -150,13 +153,18 @@ impl Sets {
                 }
             }
 
-            // Note: write_quorum was used for the old lock system, no longer needed with FastLock
-            let _write_quorum = set_drive_count - parity_count;
             // Create fast lock manager for high performance
             let fast_lock_manager = Arc::new(nebulafx_lock::FastObjectLockManager::new());
+            // Nodes sharing this set must also agree on locks, otherwise two nodes can write the same object
+            let ns_lock = if use_dist_locks {
+                new_set_namespace_lock(pool_idx, i, &set_endpoints)
+            } else {
+                None
+            };
 
             let set_disks = SetDisks::new(
                 fast_lock_manager,
+                ns_lock,
                 GLOBAL_Local_Node_Name.read().await.to_string(),
                 Arc::new(RwLock::new(set_drive)),
                 set_drive_count,
@@ -278,6 +286,15 @@ impl Sets {
         self.get_disks(self.get_hashed_set_index(key))
     }
 
+    /// Abort stale multipart uploads in every set, see [`SetDisks::cleanup_stale_uploads`]
+    pub async fn cleanup_stale_uploads(&self, max_age: Duration) -> StaleUploadsStats {
+        let mut stats = StaleUploadsStats::default();
+        for set in self.disk_set.iter() {
+            stats.merge(set.cleanup_stale_uploads(max_age).await);
+        }
+        stats
+    }
+
     fn get_hashed_set_index(&self, input: &str) -> usize {
         match self.distribution_algo {
             DistributionAlgoVersion::V1 => crc_hash(input, self.disk_set.len()),
@@ -5,6 +5,7 @@
 use crate::bucket::lifecycle::bucket_lifecycle_ops::init_background_expiry;
 use crate::bucket::metadata_sys::{self, set_bucket_metadata};
 use crate::bucket::utils::{check_valid_bucket_name, check_valid_bucket_name_strict, is_meta_bucketname};
+use crate::cache_value::metacache::bucket_written;
 use crate::config::GLOBAL_STORAGE_CLASS;
 use crate::config::storageclass;
 use crate::disk::endpoint::{Endpoint, EndpointType};
@@ -23,7 +24,8 @@ use crate::notification_sys::get_global_notification_sys;
 use crate::pools::PoolMeta;
 use crate::rebalance::RebalanceMeta;
 use crate::store_api::{
-    ListMultipartsInfo, ListObjectVersionsInfo, ListPartsInfo, MultipartInfo, ObjectIO, ObjectInfoOrErr, WalkOptions,
+    ListMultipartsInfo, ListObjectVersionsInfo, ListPartsInfo, MultipartInfo, ObjectIO, ObjectInfoOrErr, StaleUploadsStats,
+    WalkOptions,
 };
 use crate::store_init::{check_disk_fatal_errs, ec_drives_no_config};
 use crate::{
@@ -350,6 +352,16 @@ impl ECStore {
         self.pools.len() == 1
     }
 
+    /// Abort the multipart uploads on this node's drives that are older than `max_age`
+    /// or due under their bucket's AbortIncompleteMultipartUpload lifecycle rule
+    pub async fn cleanup_stale_uploads(&self, max_age: Duration) -> StaleUploadsStats {
+        let mut stats = StaleUploadsStats::default();
+        for pool in self.pools.iter() {
+            stats.merge(pool.cleanup_stale_uploads(max_age).await);
+        }
+        stats
+    }
+
     // define in store_list_objects.rs
     // pub async fn list_path(&self, opts: &ListPathOptions, delimiter: &str) -> Result<ListObjectsInfo> {
     //     // if opts.prefix.ends_with(SLASH_SEPARATOR) {
@@ -939,6 +951,209 @@ impl ECStore {
         // *self.pool_meta.write().unwrap() = meta;
         Ok(())
     }
+
+    /// Cached listings of the bucket are invalidated by the caller once the write committed
+    async fn put_object_inner(
+        &self,
+        bucket: &str,
+        object: &str,
+        data: &mut PutObjReader,
+        opts: &ObjectOptions,
+    ) -> Result<ObjectInfo> {
+        let object = encode_dir_object(object);
+
+        if self.single_pool() {
+            return self.pools[0].put_object(bucket, object.as_str(), data, opts).await;
+        }
+
+        let idx = self.get_pool_idx(bucket, &object, data.size()).await?;
+
+        if opts.data_movement && idx == opts.src_pool_idx {
+            return Err(StorageError::DataMovementOverwriteErr(
+                bucket.to_owned(),
+                object.to_owned(),
+                opts.version_id.clone().unwrap_or_default(),
+            ));
+        }
+
+        self.pools[idx].put_object(bucket, &object, data, opts).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn copy_object_inner(
+        &self,
+        src_bucket: &str,
+        src_object: &str,
+        dst_bucket: &str,
+        dst_object: &str,
+        src_info: &mut ObjectInfo,
+        src_opts: &ObjectOptions,
+        dst_opts: &ObjectOptions,
+    ) -> Result<ObjectInfo> {
+        let src_object = encode_dir_object(src_object);
+        let dst_object = encode_dir_object(dst_object);
+
+        let cp_src_dst_same = path_join_buf(&[src_bucket, &src_object]) == path_join_buf(&[dst_bucket, &dst_object]);
+
+        // TODO: nslock
+
+        let pool_idx = self.get_pool_idx_no_lock(src_bucket, &src_object, src_info.size).await?;
+
+        if cp_src_dst_same {
+            if let (Some(src_vid), Some(dst_vid)) = (&src_opts.version_id, &dst_opts.version_id) {
+                if src_vid == dst_vid {
+                    return self.pools[pool_idx]
+                        .copy_object(src_bucket, &src_object, dst_bucket, &dst_object, src_info, src_opts, dst_opts)
+                        .await;
+                }
+            }
+
+            if !dst_opts.versioned && src_opts.version_id.is_none() {
+                return self.pools[pool_idx]
+                    .copy_object(src_bucket, &src_object, dst_bucket, &dst_object, src_info, src_opts, dst_opts)
+                    .await;
+            }
+
+            if dst_opts.versioned && src_opts.version_id != dst_opts.version_id {
+                src_info.version_only = true;
+                return self.pools[pool_idx]
+                    .copy_object(src_bucket, &src_object, dst_bucket, &dst_object, src_info, src_opts, dst_opts)
+                    .await;
+            }
+        }
+
+        let put_opts = ObjectOptions {
+            user_defined: src_info.user_defined.clone(),
+            versioned: dst_opts.versioned,
+            version_id: dst_opts.version_id.clone(),
+            no_lock: true,
+            mod_time: dst_opts.mod_time,
+            ..Default::default()
+        };
+
+        if let Some(put_object_reader) = src_info.put_object_reader.as_mut() {
+            return self.pools[pool_idx]
+                .put_object(dst_bucket, &dst_object, put_object_reader, &put_opts)
+                .await;
+        }
+
+        Err(StorageError::InvalidArgument(
+            src_bucket.to_owned(),
+            src_object.to_owned(),
+            "put_object_reader is none".to_owned(),
+        ))
+    }
+
+    async fn delete_object_inner(&self, bucket: &str, object: &str, opts: ObjectOptions) -> Result<ObjectInfo> {
+        if opts.delete_prefix {
+            self.delete_prefix(bucket, object).await?;
+            return Ok(ObjectInfo::default());
+        }
+
+        // TODO: nslock
+
+        let object = encode_dir_object(object);
+        let object = object.as_str();
+
+        let mut gopts = opts.clone();
+        gopts.no_lock = true;
+
+        // Determine which pool contains it
+        let (mut pinfo, errs) = self
+            .get_pool_info_existing_with_opts(bucket, object, &gopts)
+            .await
+            .map_err(|e| {
+                if is_err_read_quorum(&e) {
+                    StorageError::ErasureWriteQuorum
+                } else {
+                    e
+                }
+            })?;
+
+        if pinfo.object_info.delete_marker && opts.version_id.is_none() {
+            pinfo.object_info.name = decode_dir_object(object);
+            return Ok(pinfo.object_info);
+        }
+
+        if opts.data_movement && opts.src_pool_idx == pinfo.index {
+            return Err(StorageError::DataMovementOverwriteErr(
+                bucket.to_owned(),
+                object.to_owned(),
+                opts.version_id.unwrap_or_default(),
+            ));
+        }
+
+        if opts.data_movement {
+            let mut obj = self.pools[pinfo.index].delete_object(bucket, object, opts).await?;
+            obj.name = decode_dir_object(obj.name.as_str());
+            return Ok(obj);
+        }
+
+        if !errs.is_empty() && !opts.versioned && !opts.version_suspended {
+            return self.delete_object_from_all_pools(bucket, object, &opts, errs).await;
+        }
+
+        for pool in self.pools.iter() {
+            match pool.delete_object(bucket, object, opts.clone()).await {
+                Ok(res) => {
+                    let mut obj = res;
+                    obj.name = decode_dir_object(object);
+                    return Ok(obj);
+                }
+                Err(err) => {
+                    if !is_err_object_not_found(&err) && !is_err_version_not_found(&err) {
+                        return Err(err);
+                    }
+                }
+            }
+        }
+
+        if let Some(ver) = opts.version_id {
+            return Err(StorageError::VersionNotFound(bucket.to_owned(), object.to_owned(), ver));
+        }
+
+        Err(StorageError::ObjectNotFound(bucket.to_owned(), object.to_owned()))
+    }
+
+    async fn complete_multipart_upload_inner(
+        &self,
+        bucket: &str,
+        object: &str,
+        upload_id: &str,
+        uploaded_parts: Vec<CompletePart>,
+        opts: &ObjectOptions,
+    ) -> Result<ObjectInfo> {
+        if self.single_pool() {
+            return self.pools[0]
+                .clone()
+                .complete_multipart_upload(bucket, object, upload_id, uploaded_parts, opts)
+                .await;
+        }
+
+        for pool in self.pools.iter() {
+            if self.is_suspended(pool.pool_idx).await {
+                continue;
+            }
+
+            let pool = pool.clone();
+            let err = match pool
+                .complete_multipart_upload(bucket, object, upload_id, uploaded_parts.clone(), opts)
+                .await
+            {
+                Ok(res) => return Ok(res),
+                Err(err) => {
+                    //
+                    if is_err_invalid_upload_id(&err) { None } else { Some(err) }
+                }
+            };
+
+            if let Some(er) = err {
+                return Err(er);
+            }
+        }
+
+        Err(StorageError::InvalidUploadID(bucket.to_owned(), object.to_owned(), upload_id.to_owned()))
+    }
 }
 
 pub async fn find_local_disk(disk_path: &String) -> Option<DiskStore> {
@@ -1099,24 +1314,11 @@ impl ObjectIO for ECStore {
     #[instrument(level = "debug", skip(self, data))]
     async fn put_object(&self, bucket: &str, object: &str, data: &mut PutObjReader, opts: &ObjectOptions) -> Result<ObjectInfo> {
         check_put_object_args(bucket, object)?;
-
-        let object = encode_dir_object(object);
-
-        if self.single_pool() {
-            return self.pools[0].put_object(bucket, object.as_str(), data, opts).await;
+        let res = self.put_object_inner(bucket, object, data, opts).await;
+        if res.is_ok() {
+            bucket_written(bucket);
         }
-
-        let idx = self.get_pool_idx(bucket, &object, data.size()).await?;
-
-        if opts.data_movement && idx == opts.src_pool_idx {
-            return Err(StorageError::DataMovementOverwriteErr(
-                bucket.to_owned(),
-                object.to_owned(),
-                opts.version_id.clone().unwrap_or_default(),
-            ));
-        }
-
-        self.pools[idx].put_object(bucket, &object, data, opts).await
+        res
     }
 }
 
@@ -1306,7 +1508,8 @@ impl StorageAPI for ECStore {
 
         // TODO: replication opts.srdelete_op
 
-        // Delete the metadata
+        // Delete the metadata, cached listings included
+        bucket_written(bucket);
         self.delete_all(NEUBULAFX_META_BUCKET, format!("{BUCKET_META_PREFIX}/{bucket}").as_str())
             .await?;
         Ok(())
@@ -1387,132 +1590,23 @@ impl StorageAPI for ECStore {
     ) -> Result<ObjectInfo> {
         check_copy_obj_args(src_bucket, src_object)?;
         check_copy_obj_args(dst_bucket, dst_object)?;
-
-        let src_object = encode_dir_object(src_object);
-        let dst_object = encode_dir_object(dst_object);
-
-        let cp_src_dst_same = path_join_buf(&[src_bucket, &src_object]) == path_join_buf(&[dst_bucket, &dst_object]);
-
-        // TODO: nslock
-
-        let pool_idx = self.get_pool_idx_no_lock(src_bucket, &src_object, src_info.size).await?;
-
-        if cp_src_dst_same {
-            if let (Some(src_vid), Some(dst_vid)) = (&src_opts.version_id, &dst_opts.version_id) {
-                if src_vid == dst_vid {
-                    return self.pools[pool_idx]
-                        .copy_object(src_bucket, &src_object, dst_bucket, &dst_object, src_info, src_opts, dst_opts)
-                        .await;
-                }
-            }
-
-            if !dst_opts.versioned && src_opts.version_id.is_none() {
-                return self.pools[pool_idx]
-                    .copy_object(src_bucket, &src_object, dst_bucket, &dst_object, src_info, src_opts, dst_opts)
-                    .await;
-            }
-
-            if dst_opts.versioned && src_opts.version_id != dst_opts.version_id {
-                src_info.version_only = true;
-                return self.pools[pool_idx]
-                    .copy_object(src_bucket, &src_object, dst_bucket, &dst_object, src_info, src_opts, dst_opts)
-                    .await;
-            }
-        }
-
-        let put_opts = ObjectOptions {
-            user_defined: src_info.user_defined.clone(),
-            versioned: dst_opts.versioned,
-            version_id: dst_opts.version_id.clone(),
-            no_lock: true,
-            mod_time: dst_opts.mod_time,
-            ..Default::default()
-        };
-
-        if let Some(put_object_reader) = src_info.put_object_reader.as_mut() {
-            return self.pools[pool_idx]
-                .put_object(dst_bucket, &dst_object, put_object_reader, &put_opts)
-                .await;
+        let res = self
+            .copy_object_inner(src_bucket, src_object, dst_bucket, dst_object, src_info, src_opts, dst_opts)
+            .await;
+        if res.is_ok() {
+            bucket_written(dst_bucket);
         }
-
-        Err(StorageError::InvalidArgument(
-            src_bucket.to_owned(),
-            src_object.to_owned(),
-            "put_object_reader is none".to_owned(),
-        ))
+        res
     }
     #[instrument(skip(self))]
     async fn delete_object(&self, bucket: &str, object: &str, opts: ObjectOptions) -> Result<ObjectInfo> {
         check_del_obj_args(bucket, object)?;
 
-        if opts.delete_prefix {
-            self.delete_prefix(bucket, object).await?;
-            return Ok(ObjectInfo::default());
+        let res = self.delete_object_inner(bucket, object, opts).await;
+        if res.is_ok() {
+            bucket_written(bucket);
         }
-
-        // TODO: nslock
-
-        let object = encode_dir_object(object);
-        let object = object.as_str();
-
-        let mut gopts = opts.clone();
-        gopts.no_lock = true;
-
-        // Determine which pool contains it
-        let (mut pinfo, errs) = self
-            .get_pool_info_existing_with_opts(bucket, object, &gopts)
-            .await
-            .map_err(|e| {
-                if is_err_read_quorum(&e) {
-                    StorageError::ErasureWriteQuorum
-                } else {
-                    e
-                }
-            })?;
-
-        if pinfo.object_info.delete_marker && opts.version_id.is_none() {
-            pinfo.object_info.name = decode_dir_object(object);
-            return Ok(pinfo.object_info);
-        }
-
-        if opts.data_movement && opts.src_pool_idx == pinfo.index {
-            return Err(StorageError::DataMovementOverwriteErr(
-                bucket.to_owned(),
-                object.to_owned(),
-                opts.version_id.unwrap_or_default(),
-            ));
-        }
-
-        if opts.data_movement {
-            let mut obj = self.pools[pinfo.index].delete_object(bucket, object, opts).await?;
-            obj.name = decode_dir_object(obj.name.as_str());
-            return Ok(obj);
-        }
-
-        if !errs.is_empty() && !opts.versioned && !opts.version_suspended {
-            return self.delete_object_from_all_pools(bucket, object, &opts, errs).await;
-        }
-
-        for pool in self.pools.iter() {
-            match pool.delete_object(bucket, object, opts.clone()).await {
-                Ok(res) => {
-                    let mut obj = res;
-                    obj.name = decode_dir_object(object);
-                    return Ok(obj);
-                }
-                Err(err) => {
-                    if !is_err_object_not_found(&err) && !is_err_version_not_found(&err) {
-                        return Err(err);
-                    }
-                }
-            }
-        }
-
-        if let Some(ver) = opts.version_id {
-            return Err(StorageError::VersionNotFound(bucket.to_owned(), object.to_owned(), ver));
-        }
-
-        Err(StorageError::ObjectNotFound(bucket.to_owned(), object.to_owned()))
+        res
     }
     // TODO: review
     #[instrument(skip(self))]
@@ -1569,6 +1663,10 @@ impl StorageAPI for ECStore {
             v.object_name = decode_dir_object(&v.object_name);
         });
 
+        if del_errs.iter().any(|err| err.is_none()) {
+            bucket_written(bucket);
+        }
+
         (del_objects, del_errs)
 
         // let mut futures = Vec::with_capacity(objects.len());
@@ -2007,36 +2105,13 @@ impl StorageAPI for ECStore {
     ) -> Result<ObjectInfo> {
         check_complete_multipart_args(bucket, object, upload_id)?;
 
-        if self.single_pool() {
-            return self.pools[0]
-                .clone()
-                .complete_multipart_upload(bucket, object, upload_id, uploaded_parts, opts)
-                .await;
-        }
-
-        for pool in self.pools.iter() {
-            if self.is_suspended(pool.pool_idx).await {
-                continue;
-            }
-
-            let pool = pool.clone();
-            let err = match pool
-                .complete_multipart_upload(bucket, object, upload_id, uploaded_parts.clone(), opts)
-                .await
-            {
-                Ok(res) => return Ok(res),
-                Err(err) => {
-                    //
-                    if is_err_invalid_upload_id(&err) { None } else { Some(err) }
-                }
-            };
-
-            if let Some(er) = err {
-                return Err(er);
-            }
+        let res = self
+            .complete_multipart_upload_inner(bucket, object, upload_id, uploaded_parts, opts)
+            .await;
+        if res.is_ok() {
+            bucket_written(bucket);
         }
-
-        Err(StorageError::InvalidUploadID(bucket.to_owned(), object.to_owned(), upload_id.to_owned()))
+        res
     }
 
     #[instrument(skip(self))]
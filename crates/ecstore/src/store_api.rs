@@ -383,6 +383,8 @@ pub struct ObjectOptions {
     pub eval_metadata: Option<HashMap<String, String>>,
 
     pub want_checksum: Option<Checksum>,
+    /// Nonce an encrypted part was sealed with, kept in the part metadata
+    pub part_nonce: Option<Bytes>,
 }
 
 impl ObjectOptions {
@@ -700,6 +702,7 @@ impl ObjectInfo {
                 checksums: part.checksums.clone(),
                 number: part.number,
                 error: part.error.clone(),
+                nonce: part.nonce.clone(),
             })
             .collect();
 
@@ -997,6 +1000,20 @@ pub struct MultipartInfo {
     pub user_defined: HashMap<String, String>,
 }
 
+/// Multipart uploads aborted by a stale upload sweep and the bytes their parts held
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StaleUploadsStats {
+    pub uploads: u64,
+    pub bytes: u64,
+}
+
+impl StaleUploadsStats {
+    pub fn merge(&mut self, other: StaleUploadsStats) {
+        self.uploads += other.uploads;
+        self.bytes += other.bytes;
+    }
+}
+
 // ListMultipartsInfo - represents bucket resources for incomplete multipart uploads.
 #[derive(Debug, Clone, Default)]
 pub struct ListMultipartsInfo {
@@ -3,7 +3,12 @@
 use crate::StorageAPI;
 use crate::bucket::metadata_sys::get_versioning_config;
 use crate::bucket::versioning::VersioningApi;
+use crate::cache_value::metacache::{
+    GLOBAL_METACACHE, METACACHE_BLOCK_SIZE, Metacache, MetacacheBlock, ScanStatus, decode_block, encode_block, find_listing,
+    update_listing,
+};
 use crate::cache_value::metacache_set::{ListPathRawOptions, list_path_raw};
+use crate::config::com::{read_config, save_config};
 use crate::disk::error::DiskError;
 use crate::disk::{DiskInfo, DiskStore};
 use crate::error::{
@@ -23,12 +28,14 @@ use nebulafx_filemeta::{
     merge_file_meta_versions,
 };
 use nebulafx_utils::path::{self, SLASH_SEPARATOR, base_dir_from_prefix};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use time::OffsetDateTime;
 use tokio::sync::broadcast::{self};
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 const MAX_OBJECT_LIST: i32 = 1000;
@@ -49,7 +56,7 @@ pub fn max_keys_plus_one(max_keys: i32, add_one: bool) -> i32 {
     max_keys
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ListPathOptions {
     pub id: Option<String>,
 
@@ -190,7 +197,7 @@ impl ListPathOptions {
                 MARKER_TAG_VERSION,
                 id.to_owned(),
                 self.pool_idx.unwrap_or_default(),
-                self.pool_idx.unwrap_or_default(),
+                self.set_idx.unwrap_or_default(),
             )
         } else {
             format!("{marker}[nebulafx_cache:{MARKER_TAG_VERSION},return:]")
@@ -240,7 +247,7 @@ impl ECStore {
         delimiter: Option<String>,
         max_keys: i32,
     ) -> Result<ListObjectsInfo> {
-        let opts = ListPathOptions {
+        let mut opts = ListPathOptions {
             bucket: bucket.to_owned(),
             prefix: prefix.to_owned(),
             separator: delimiter.clone(),
@@ -250,6 +257,7 @@ impl ECStore {
             ask_disks: "strict".to_owned(), //TODO: from config
             ..Default::default()
         };
+        opts.parse_marker();
 
         // use get
         if !opts.prefix.is_empty() && opts.limit == 1 && opts.marker.is_none() {
@@ -293,11 +301,13 @@ impl ECStore {
         }
 
         if let Some(result) = list_result.entries.as_mut() {
-            result.forward_past(opts.marker);
+            result.forward_past(opts.marker.clone());
         }
 
         // contextCanceled
 
+        let list_id = list_result.entries.as_ref().and_then(|entries| entries.list_id.clone());
+
         let mut get_objects = ObjectInfo::from_meta_cache_entries_sorted_infos(
             &list_result.entries.unwrap_or_default(),
             bucket,
@@ -317,7 +327,7 @@ impl ECStore {
 
         let next_marker = {
             if is_truncated {
-                get_objects.last().map(|last| last.name.clone())
+                get_objects.last().map(|last| encode_list_marker(&opts, list_id, &last.name))
             } else {
                 None
             }
@@ -377,7 +387,7 @@ impl ECStore {
         };
 
         // if marker set, limit +1
-        let opts = ListPathOptions {
+        let mut opts = ListPathOptions {
             bucket: bucket.to_owned(),
             prefix: prefix.to_owned(),
             separator: delimiter.clone(),
@@ -388,6 +398,7 @@ impl ECStore {
             versioned: true,
             ..Default::default()
         };
+        opts.parse_marker();
 
         let mut list_result = match self.list_path(&opts).await {
             Ok(res) => res,
@@ -404,9 +415,11 @@ impl ECStore {
         }
 
         if let Some(result) = list_result.entries.as_mut() {
-            result.forward_past(opts.marker);
+            result.forward_past(opts.marker.clone());
         }
 
+        let list_id = list_result.entries.as_ref().and_then(|entries| entries.list_id.clone());
+
         let mut get_objects = ObjectInfo::from_meta_cache_entries_sorted_versions(
             &list_result.entries.unwrap_or_default(),
             bucket,
@@ -429,7 +442,12 @@ impl ECStore {
             if is_truncated {
                 get_objects
                     .last()
-                    .map(|last| (Some(last.name.clone()), last.version_id.map(|v| v.to_string())))
+                    .map(|last| {
+                        (
+                            Some(encode_list_marker(&opts, list_id, &last.name)),
+                            last.version_id.map(|v| v.to_string()),
+                        )
+                    })
                     .unwrap_or_default()
             } else {
                 (None, None)
@@ -519,6 +537,18 @@ impl ECStore {
             o.create = false;
         }
 
+        // Continue the listing from its cached blocks when they hold the whole page
+        let cached = if o.id.is_some() && !o.create && !o.transient {
+            find_metacache_listing(&o).await
+        } else {
+            None
+        };
+        if let Some(cache) = &cached {
+            if let Some(result) = self.list_path_from_metacache(&o, cache).await {
+                return Ok(result);
+            }
+        }
+
         // cancel channel
         let cancel = CancellationToken::new();
 
@@ -589,11 +619,12 @@ impl ECStore {
             let truncated = !entries.entries().is_empty() || result.err.is_none();
             entries.o.0.truncate(o.limit as usize);
             if !o.transient && truncated {
-                entries.list_id = if let Some(id) = o.id {
-                    Some(id)
-                } else {
-                    Some(Uuid::new_v4().to_string())
+                if cached.is_none() {
+                    // Cache the rest of the listing so the next pages do not walk the drives again
+                    o.id = Some(Uuid::new_v4().to_string());
+                    self.clone().start_metacache_listing(o.clone());
                 }
+                entries.list_id = o.id.clone();
             }
 
             if !truncated {
@@ -604,6 +635,157 @@ impl ECStore {
         Ok(result)
     }
 
+    /// Serves a page of a listing from its cached blocks, `None` when they do not hold the whole page
+    async fn list_path_from_metacache(
+        self: &Arc<Self>,
+        o: &ListPathOptions,
+        cache: &Metacache,
+    ) -> Option<MetaCacheEntriesSortedResult> {
+        let limit = o.limit as usize;
+        let mut entries = Vec::with_capacity(limit);
+        let mut truncated = false;
+
+        'blocks: for block in cache.blocks.iter() {
+            let data = match read_config(self.clone(), &cache.block_path(block.n)).await {
+                Ok(data) => data,
+                Err(err) => {
+                    warn!("read metacache block {} of {} failed: {}", block.n, cache.id, err);
+                    return None;
+                }
+            };
+            let block_entries = match decode_block(&data).await {
+                Ok(entries) => entries,
+                Err(err) => {
+                    warn!("decode metacache block {} of {} failed: {}", block.n, cache.id, err);
+                    return None;
+                }
+            };
+
+            for mut entry in block_entries {
+                if !keep_entry(o, &mut entry) {
+                    continue;
+                }
+                if entries.len() >= limit {
+                    truncated = true;
+                    break 'blocks;
+                }
+                entries.push(Some(entry));
+            }
+        }
+
+        if !truncated && !cache.holds_end() {
+            return None;
+        }
+
+        Some(MetaCacheEntriesSortedResult {
+            entries: Some(MetaCacheEntriesSorted {
+                o: MetaCacheEntries(entries),
+                list_id: truncated.then(|| cache.id.clone()),
+                reuse: true,
+                ..Default::default()
+            }),
+            err: if truncated { None } else { Some(Error::Unexpected.into()) },
+        })
+    }
+
+    /// Walks the rest of the listing in the background and saves it in blocks under the
+    /// `.metacache` of the bucket, reporting each block to the node owning the bucket
+    fn start_metacache_listing(self: Arc<Self>, o: ListPathOptions) {
+        tokio::spawn(async move {
+            let mut cache = Metacache::new(&o);
+            if let Err(err) = update_listing(cache.clone()).await {
+                warn!("register metacache listing {} of {} failed: {}", cache.id, cache.bucket, err);
+                return;
+            }
+
+            let cancel = CancellationToken::new();
+            let (sender, mut recv) = mpsc::channel(100);
+            let mut opts = o;
+            opts.limit = 0;
+            opts.stop_disk_at_limit = false;
+
+            let store = self.clone();
+            let walk_cancel = cancel.clone();
+            let walk = tokio::spawn(async move { store.list_merged(walk_cancel, opts, sender).await });
+
+            let mut block = Vec::with_capacity(METACACHE_BLOCK_SIZE);
+            let mut n = 0;
+            let mut result = Ok(());
+            while let Some(entry) = recv.recv().await {
+                block.push(entry);
+                if block.len() < METACACHE_BLOCK_SIZE {
+                    continue;
+                }
+
+                match self.save_metacache_block(&cache, n, &block).await {
+                    Ok(state) if state.status == ScanStatus::Error => {
+                        // Invalidated or expired on the owner, nobody will read the rest
+                        cancel.cancel();
+                        drop(recv);
+                        let _ = walk.await;
+                        return;
+                    }
+                    Ok(_) => {
+                        n += 1;
+                        block.clear();
+                    }
+                    Err(err) => {
+                        result = Err(err);
+                        break;
+                    }
+                }
+            }
+            cancel.cancel();
+            drop(recv);
+
+            if result.is_ok() {
+                result = match walk.await {
+                    Ok(res) => res.map(|_| ()),
+                    Err(err) => Err(Error::other(err)),
+                };
+            }
+            if result.is_ok() && !block.is_empty() {
+                result = self.save_metacache_block(&cache, n, &block).await.map(|_| ());
+            }
+
+            cache.ended = Some(OffsetDateTime::now_utc());
+            match result {
+                Ok(()) => cache.status = ScanStatus::Success,
+                Err(err) => {
+                    warn!("metacache listing {} of {} failed: {}", cache.id, cache.bucket, err);
+                    cache.status = ScanStatus::Error;
+                    cache.error = err.to_string();
+                }
+            }
+            if let Err(err) = update_listing(cache.clone()).await {
+                warn!("finish metacache listing {} of {} failed: {}", cache.id, cache.bucket, err);
+            }
+        });
+    }
+
+    async fn save_metacache_block(
+        self: &Arc<Self>,
+        cache: &Metacache,
+        n: usize,
+        entries: &[MetaCacheEntry],
+    ) -> Result<Metacache> {
+        let (Some(first), Some(last)) = (entries.first(), entries.last()) else {
+            return Err(Error::other("empty metacache block"));
+        };
+
+        save_config(self.clone(), &cache.block_path(n), encode_block(entries).await?).await?;
+
+        update_listing(Metacache {
+            blocks: vec![MetacacheBlock {
+                n,
+                first: first.name.clone(),
+                last: last.name.clone(),
+            }],
+            ..cache.clone()
+        })
+        .await
+    }
+
     // Read all
     async fn list_merged(
         &self,
@@ -940,47 +1122,16 @@ async fn gather_results(
     let mut recv = recv;
     let mut entries = Vec::new();
     while let Some(mut entry) = recv.recv().await {
-        #[cfg(windows)]
-        {
-            // normalize windows path separator
-            entry.name = entry.name.replace("\\", "/");
-        }
-
         if returned {
             continue;
         }
 
         // TODO: rx.recv()
 
-        // TODO: isLatestDeletemarker
-        if !opts.include_directories
-            && (entry.is_dir() || (!opts.versioned && entry.is_object() && entry.is_latest_delete_marker()))
-        {
-            continue;
-        }
-
-        if let Some(marker) = &opts.marker {
-            if &entry.name < marker {
-                continue;
-            }
-        }
-
-        if !entry.name.starts_with(&opts.prefix) {
-            continue;
-        }
-
-        if let Some(separator) = &opts.separator {
-            if !opts.recursive && !entry.is_in_dir(&opts.prefix, separator) {
-                continue;
-            }
-        }
-
-        if !opts.incl_deleted && entry.is_object() && entry.is_latest_delete_marker() && !entry.is_object_dir() {
+        if !keep_entry(&opts, &mut entry) {
             continue;
         }
 
-        // TODO: Lifecycle
-
         if opts.limit > 0 && entries.len() >= opts.limit as usize {
             if let Some(tx) = sender {
                 tx.send(MetaCacheEntriesSortedResult {
@@ -1019,6 +1170,77 @@ async fn gather_results(
     Ok(())
 }
 
+/// Marker of the next page, tagged with the cached listing the page came from so the next page
+/// continues it
+fn encode_list_marker(opts: &ListPathOptions, list_id: Option<String>, name: &str) -> String {
+    if list_id.is_none() {
+        return name.to_owned();
+    }
+
+    let mut opts = ListPathOptions {
+        id: list_id,
+        pool_idx: opts.pool_idx,
+        set_idx: opts.set_idx,
+        ..Default::default()
+    };
+    opts.encode_marker(name)
+}
+
+/// Whether a walked entry belongs in the listing `opts` asks for
+fn keep_entry(opts: &ListPathOptions, entry: &mut MetaCacheEntry) -> bool {
+    #[cfg(windows)]
+    {
+        // normalize windows path separator
+        entry.name = entry.name.replace("\\", "/");
+    }
+
+    // TODO: isLatestDeletemarker
+    if !opts.include_directories && (entry.is_dir() || (!opts.versioned && entry.is_object() && entry.is_latest_delete_marker()))
+    {
+        return false;
+    }
+
+    if let Some(marker) = &opts.marker {
+        if &entry.name < marker {
+            return false;
+        }
+    }
+
+    if !entry.name.starts_with(&opts.prefix) {
+        return false;
+    }
+
+    if let Some(separator) = &opts.separator {
+        if !opts.recursive && !entry.is_in_dir(&opts.prefix, separator) {
+            return false;
+        }
+    }
+
+    if !opts.incl_deleted && entry.is_object() && entry.is_latest_delete_marker() && !entry.is_object_dir() {
+        return false;
+    }
+
+    // TODO: Lifecycle
+
+    true
+}
+
+/// The listing `o` continues, unless this node wrote to the bucket after it started
+async fn find_metacache_listing(o: &ListPathOptions) -> Option<Metacache> {
+    let mut cache = find_listing(o).await?;
+
+    if GLOBAL_METACACHE.written_since(&o.bucket, cache.started) {
+        cache.status = ScanStatus::Error;
+        cache.error = "listing invalidated by a write".to_owned();
+        cache.blocks.clear();
+        if let Err(err) = update_listing(cache).await {
+            warn!("invalidate metacache listing of {} failed: {}", o.bucket, err);
+        }
+        return None;
+    }
+    Some(cache)
+}
+
 async fn select_from(
     in_channels: &mut [Receiver<MetaCacheEntry>],
     idx: usize,
@@ -30,6 +30,8 @@ pub enum TierType {
     GCS,
     #[serde(rename = "r2")]
     R2,
+    #[serde(rename = "filesystem")]
+    FileSystem,
 }
 
 impl Display for TierType {
@@ -62,6 +64,9 @@ impl Display for TierType {
             TierType::R2 => {
                 write!(f, "R2")
             }
+            TierType::FileSystem => {
+                write!(f, "FileSystem")
+            }
             _ => {
                 write!(f, "Unsupported")
             }
@@ -81,6 +86,7 @@ impl TierType {
             "Azure" => TierType::Azure,
             "GCS" => TierType::GCS,
             "R2" => TierType::R2,
+            "FileSystem" => TierType::FileSystem,
             _ => TierType::Unsupported,
         }
     }
@@ -96,6 +102,7 @@ impl TierType {
             TierType::Azure => "azure".to_string(),
             TierType::GCS => "gcs".to_string(),
             TierType::R2 => "r2".to_string(),
+            TierType::FileSystem => "filesystem".to_string(),
             _ => "unsupported".to_string(),
         }
     }
@@ -128,6 +135,8 @@ pub struct TierConfig {
     pub nebulafx: Option<TierNebulaFX>,
     #[serde(rename = "minio", skip_serializing_if = "Option::is_none")]
     pub minio: Option<TierMinIO>,
+    #[serde(rename = "filesystem", skip_serializing_if = "Option::is_none")]
+    pub filesystem: Option<TierFileSystem>,
 }
 
 impl Clone for TierConfig {
@@ -141,6 +150,7 @@ impl Clone for TierConfig {
         let mut azure = None;
         let mut gcs = None;
         let mut r2 = None;
+        let mut filesystem = None;
         match self.tier_type {
             TierType::S3 => {
                 let mut s3_ = self.s3.as_ref().expect("err").clone();
@@ -187,6 +197,9 @@ impl Clone for TierConfig {
                 r2_.secret_key = "REDACTED".to_string();
                 r2 = Some(r2_);
             }
+            TierType::FileSystem => {
+                filesystem = self.filesystem.clone();
+            }
             _ => (),
         }
         TierConfig {
@@ -202,6 +215,7 @@ impl Clone for TierConfig {
             azure,
             gcs,
             r2,
+            filesystem,
         }
     }
 }
@@ -219,6 +233,7 @@ impl TierConfig {
             TierType::Azure => self.azure.as_ref().expect("err").endpoint.clone(),
             TierType::GCS => self.gcs.as_ref().expect("err").endpoint.clone(),
             TierType::R2 => self.r2.as_ref().expect("err").endpoint.clone(),
+            TierType::FileSystem => self.filesystem.as_ref().expect("err").path.clone(),
             _ => {
                 info!("unexpected tier type {}", self.tier_type);
                 "".to_string()
@@ -237,6 +252,7 @@ impl TierConfig {
             TierType::Azure => self.azure.as_ref().expect("err").bucket.clone(),
             TierType::GCS => self.gcs.as_ref().expect("err").bucket.clone(),
             TierType::R2 => self.r2.as_ref().expect("err").bucket.clone(),
+            TierType::FileSystem => "".to_string(),
             _ => {
                 info!("unexpected tier type {}", self.tier_type);
                 "".to_string()
@@ -255,6 +271,7 @@ impl TierConfig {
             TierType::Azure => self.azure.as_ref().expect("err").prefix.clone(),
             TierType::GCS => self.gcs.as_ref().expect("err").prefix.clone(),
             TierType::R2 => self.r2.as_ref().expect("err").prefix.clone(),
+            TierType::FileSystem => self.filesystem.as_ref().expect("err").prefix.clone(),
             _ => {
                 info!("unexpected tier type {}", self.tier_type);
                 "".to_string()
@@ -273,6 +290,7 @@ impl TierConfig {
             TierType::Azure => self.azure.as_ref().expect("err").region.clone(),
             TierType::GCS => self.gcs.as_ref().expect("err").region.clone(),
             TierType::R2 => self.r2.as_ref().expect("err").region.clone(),
+            TierType::FileSystem => "".to_string(),
             _ => {
                 info!("unexpected tier type {}", self.tier_type);
                 "".to_string()
@@ -568,3 +586,12 @@ pub struct TierR2 {
     pub prefix: String,
     pub region: String,
 }
+
+/// Tier on a directory of the nodes, a local volume or a share mounted at the same path on every node
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct TierFileSystem {
+    pub name: String,
+    pub path: String,
+    pub prefix: String,
+}
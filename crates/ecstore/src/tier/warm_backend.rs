@@ -17,6 +17,7 @@ use crate::tier::{
     tier_handlers::{ERR_TIER_BUCKET_NOT_FOUND, ERR_TIER_PERM_ERR},
     warm_backend_aliyun::WarmBackendAliyun,
     warm_backend_azure::WarmBackendAzure,
+    warm_backend_filesystem::WarmBackendFileSystem,
     warm_backend_gcs::WarmBackendGCS,
     warm_backend_huaweicloud::WarmBackendHuaweicloud,
     warm_backend_minio::WarmBackendMinIO,
@@ -57,9 +58,9 @@ pub trait WarmBackend {
 
 pub async fn check_warm_backend(w: Option<&WarmBackendImpl>) -> Result<(), AdminError> {
     let w = w.expect("err");
-    let remote_version_id = w
-        .put(PROBE_OBJECT, ReaderImpl::Body(Bytes::from("NebulaFX".as_bytes().to_vec())), 5)
-        .await;
+    let probe = Bytes::from_static(b"NebulaFX");
+    let probe_len = probe.len() as i64;
+    let remote_version_id = w.put(PROBE_OBJECT, ReaderImpl::Body(probe), probe_len).await;
     if let Err(err) = remote_version_id {
         return Err(ERR_TIER_PERM_ERR.clone());
     }
@@ -194,6 +195,18 @@ pub async fn new_warm_backend(tier: &TierConfig, probe: bool) -> Result<WarmBack
             }
             d = Some(Box::new(dd.expect("err")));
         }
+        TierType::FileSystem => {
+            let dd = WarmBackendFileSystem::new(tier.filesystem.as_ref().expect("err"), &tier.name).await;
+            if let Err(err) = dd {
+                warn!("{}", err);
+                return Err(AdminError {
+                    code: "XNebulaFXAdminTierInvalidConfig".to_string(),
+                    message: format!("Unable to setup remote tier, check tier configuration: {}", err.to_string()),
+                    status_code: StatusCode::BAD_REQUEST,
+                });
+            }
+            d = Some(Box::new(dd.expect("err")));
+        }
         _ => {
             return Err(ERR_TIER_TYPE_UNSUPPORTED.clone());
         }
@@ -0,0 +1,324 @@
+//! Warm tier on a local directory, typically a large HDD volume or an NFS/CIFS share mounted on
+//! every node. Objects are written to a temp file under the tier root and renamed into place, so a
+//! crashed transition never leaves a partial object behind. Each object carries a sidecar file
+//! with its size and the metadata it was transitioned with.
+
+use std::collections::HashMap;
+use std::io::{Cursor, ErrorKind, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader};
+use uuid::Uuid;
+
+use crate::client::transition_api::{ReadCloser, ReaderImpl};
+use crate::tier::{
+    tier_config::TierFileSystem,
+    warm_backend::{WarmBackend, WarmBackendGetOpts},
+};
+
+/// Suffix of the sidecar file holding the metadata of an object
+pub const FS_META_SUFFIX: &str = ".meta.json";
+
+/// Directory under the tier root where objects are written before being renamed into place
+const FS_TMP_DIR: &str = ".nebulafx.tmp";
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct FileSystemObjectMeta {
+    pub size: i64,
+    /// Unix timestamp of the transition
+    #[serde(rename = "modTime")]
+    pub mod_time: i64,
+    #[serde(rename = "userMetadata")]
+    pub user_metadata: HashMap<String, String>,
+}
+
+pub struct WarmBackendFileSystem {
+    pub root: PathBuf,
+}
+
+impl WarmBackendFileSystem {
+    pub async fn new(conf: &TierFileSystem, tier: &str) -> Result<Self, std::io::Error> {
+        if conf.path.is_empty() {
+            return Err(std::io::Error::other("no path was provided"));
+        }
+        let path = Path::new(&conf.path);
+        if !path.is_absolute() {
+            return Err(std::io::Error::other(format!("path {} of tier {} is not absolute", conf.path, tier)));
+        }
+        // The path must exist already, a share that is not mounted must not fill up the local disk
+        let meta = fs::metadata(path)
+            .await
+            .map_err(|err| std::io::Error::other(format!("path {} is not accessible: {}", conf.path, err)))?;
+        if !meta.is_dir() {
+            return Err(std::io::Error::other(format!("path {} is not a directory", conf.path)));
+        }
+
+        // The prefix names a directory below the path, it must not lead out of it
+        if Path::new(&conf.prefix).is_absolute() || conf.prefix.split(['/', '\\']).any(|part| part == "..") {
+            return Err(std::io::Error::other(format!("prefix {} of tier {} must stay below its path", conf.prefix, tier)));
+        }
+        let prefix = conf.prefix.trim_matches('/');
+        let root = if prefix.is_empty() {
+            path.to_path_buf()
+        } else {
+            path.join(prefix)
+        };
+        fs::create_dir_all(root.join(FS_TMP_DIR)).await?;
+
+        Ok(Self { root })
+    }
+
+    pub fn get_dest(&self, object: &str) -> Result<PathBuf, std::io::Error> {
+        let object = object.trim_start_matches('/');
+        if object.is_empty()
+            || object.ends_with(FS_META_SUFFIX)
+            || object
+                .split('/')
+                .any(|part| part == "." || part == ".." || part == FS_TMP_DIR)
+        {
+            return Err(std::io::Error::new(ErrorKind::InvalidInput, format!("invalid object name {object}")));
+        }
+        Ok(self.root.join(object))
+    }
+
+    /// Metadata stored with the object when it was transitioned
+    pub async fn get_meta(&self, object: &str) -> Result<FileSystemObjectMeta, std::io::Error> {
+        let data = fs::read(meta_path(&self.get_dest(object)?)).await?;
+        serde_json::from_slice(&data).map_err(std::io::Error::other)
+    }
+
+    async fn write_object(
+        &self,
+        tmp: &Path,
+        dest: &Path,
+        r: ReaderImpl,
+        length: i64,
+        meta: HashMap<String, String>,
+    ) -> Result<(), std::io::Error> {
+        let size = write_data(tmp, r, length).await?;
+
+        let sidecar = FileSystemObjectMeta {
+            size,
+            mod_time: OffsetDateTime::now_utc().unix_timestamp(),
+            user_metadata: meta,
+        };
+        fs::write(meta_path(tmp), serde_json::to_vec(&sidecar).map_err(std::io::Error::other)?).await?;
+
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        // The data is renamed last, an object is only visible once its sidecar is in place
+        fs::rename(meta_path(tmp), meta_path(dest)).await?;
+        fs::rename(tmp, dest).await
+    }
+}
+
+#[async_trait::async_trait]
+impl WarmBackend for WarmBackendFileSystem {
+    async fn put_with_meta(
+        &self,
+        object: &str,
+        r: ReaderImpl,
+        length: i64,
+        meta: HashMap<String, String>,
+    ) -> Result<String, std::io::Error> {
+        let dest = self.get_dest(object)?;
+        let tmp = self.root.join(FS_TMP_DIR).join(Uuid::new_v4().to_string());
+
+        if let Err(err) = self.write_object(&tmp, &dest, r, length, meta).await {
+            let _ = fs::remove_file(&tmp).await;
+            let _ = fs::remove_file(meta_path(&tmp)).await;
+            return Err(err);
+        }
+        // Objects are not versioned on a filesystem tier
+        Ok(String::new())
+    }
+
+    async fn put(&self, object: &str, r: ReaderImpl, length: i64) -> Result<String, std::io::Error> {
+        self.put_with_meta(object, r, length, HashMap::new()).await
+    }
+
+    async fn get(&self, object: &str, _rv: &str, opts: WarmBackendGetOpts) -> Result<ReadCloser, std::io::Error> {
+        let mut file = fs::File::open(self.get_dest(object)?).await?;
+        if opts.start_offset > 0 {
+            file.seek(SeekFrom::Start(opts.start_offset as u64)).await?;
+        }
+
+        let limit = if opts.length > 0 { opts.length as u64 } else { u64::MAX };
+        let mut data = Vec::new();
+        file.take(limit).read_to_end(&mut data).await?;
+
+        Ok(BufReader::new(Cursor::new(data)))
+    }
+
+    async fn remove(&self, object: &str, _rv: &str) -> Result<(), std::io::Error> {
+        let dest = self.get_dest(object)?;
+        remove_if_exists(&dest).await?;
+        remove_if_exists(&meta_path(&dest)).await?;
+
+        // Drop the directories the object leaves empty, remove_dir fails on the first one that is not
+        let mut dir = dest.parent();
+        while let Some(d) = dir {
+            if d == self.root || fs::remove_dir(d).await.is_err() {
+                break;
+            }
+            dir = d.parent();
+        }
+        Ok(())
+    }
+
+    async fn in_use(&self) -> Result<bool, std::io::Error> {
+        let mut entries = fs::read_dir(&self.root).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_name() != FS_TMP_DIR {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+fn meta_path(path: &Path) -> PathBuf {
+    let mut p = path.as_os_str().to_owned();
+    p.push(FS_META_SUFFIX);
+    PathBuf::from(p)
+}
+
+async fn write_data(path: &Path, r: ReaderImpl, length: i64) -> Result<i64, std::io::Error> {
+    let mut file = fs::File::create(path).await?;
+    let written = match r {
+        ReaderImpl::Body(body) => {
+            file.write_all(&body).await?;
+            body.len() as u64
+        }
+        ReaderImpl::ObjectBody(mut body) => tokio::io::copy(&mut body.stream, &mut file).await?,
+    };
+    if length >= 0 && written != length as u64 {
+        return Err(std::io::Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("object size mismatch, expected {length} bytes, got {written}"),
+        ));
+    }
+    file.sync_all().await?;
+    Ok(written as i64)
+}
+
+async fn remove_if_exists(path: &Path) -> Result<(), std::io::Error> {
+    match fs::remove_file(path).await {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use tempfile::tempdir;
+
+    async fn new_backend(path: &Path) -> WarmBackendFileSystem {
+        let conf = TierFileSystem {
+            name: "FSTIER".to_string(),
+            path: path.to_string_lossy().to_string(),
+            prefix: "tiered/".to_string(),
+        };
+        WarmBackendFileSystem::new(&conf, "FSTIER").await.unwrap()
+    }
+
+    async fn read_all(mut r: ReadCloser) -> Vec<u8> {
+        let mut data = Vec::new();
+        r.read_to_end(&mut data).await.unwrap();
+        data
+    }
+
+    #[tokio::test]
+    async fn test_filesystem_backend_put_get_remove() {
+        let dir = tempdir().unwrap();
+        let backend = new_backend(dir.path()).await;
+        assert!(!backend.in_use().await.unwrap());
+
+        let object = "0123456789abcdef/ab/cd/abcd-uuid";
+        let meta = HashMap::from([("name".to_string(), "photos/a.jpg".to_string())]);
+        let rv = backend
+            .put_with_meta(object, ReaderImpl::Body(Bytes::from_static(b"hello warm tier")), 15, meta.clone())
+            .await
+            .unwrap();
+        assert!(rv.is_empty());
+        assert!(backend.in_use().await.unwrap());
+        assert!(dir.path().join("tiered").join(object).is_file());
+
+        let sidecar = backend.get_meta(object).await.unwrap();
+        assert_eq!(sidecar.size, 15);
+        assert_eq!(sidecar.user_metadata, meta);
+
+        let full = backend.get(object, "", WarmBackendGetOpts::default()).await.unwrap();
+        assert_eq!(read_all(full).await, b"hello warm tier");
+        let range = WarmBackendGetOpts {
+            start_offset: 6,
+            length: 4,
+        };
+        assert_eq!(read_all(backend.get(object, "", range).await.unwrap()).await, b"warm");
+
+        backend.remove(object, "").await.unwrap();
+        assert!(backend.get(object, "", WarmBackendGetOpts::default()).await.is_err());
+        // Removing the last object leaves the tier empty again
+        assert!(!backend.in_use().await.unwrap());
+        backend.remove(object, "").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_filesystem_backend_rejects_partial_writes() {
+        let dir = tempdir().unwrap();
+        let backend = new_backend(dir.path()).await;
+
+        let res = backend.put("short", ReaderImpl::Body(Bytes::from_static(b"abc")), 10).await;
+        assert!(res.is_err());
+        assert!(!backend.in_use().await.unwrap());
+        let mut tmp = fs::read_dir(backend.root.join(FS_TMP_DIR)).await.unwrap();
+        assert!(tmp.next_entry().await.unwrap().is_none());
+
+        for object in ["", "../escape", "a/./b", "a.meta.json"] {
+            assert!(backend.get_dest(object).is_err(), "{object}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filesystem_backend_config() {
+        let conf = TierFileSystem {
+            name: "FSTIER".to_string(),
+            path: "relative/path".to_string(),
+            ..Default::default()
+        };
+        assert!(WarmBackendFileSystem::new(&conf, "FSTIER").await.is_err());
+
+        let dir = tempdir().unwrap();
+        let conf = TierFileSystem {
+            name: "FSTIER".to_string(),
+            path: dir.path().join("not-mounted").to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        assert!(WarmBackendFileSystem::new(&conf, "FSTIER").await.is_err());
+
+        for prefix in ["../outside", "tiered/../../outside", "/etc", "a\\..\\b"] {
+            let conf = TierFileSystem {
+                name: "FSTIER".to_string(),
+                path: dir.path().to_string_lossy().to_string(),
+                prefix: prefix.to_string(),
+                ..Default::default()
+            };
+            assert!(WarmBackendFileSystem::new(&conf, "FSTIER").await.is_err(), "{prefix}");
+        }
+        let conf = TierFileSystem {
+            name: "FSTIER".to_string(),
+            path: dir.path().to_string_lossy().to_string(),
+            prefix: "tiered/".to_string(),
+            ..Default::default()
+        };
+        let backend = WarmBackendFileSystem::new(&conf, "FSTIER").await.unwrap();
+        assert_eq!(backend.root, dir.path().join("tiered"));
+    }
+}
@@ -23,6 +23,10 @@ pub const TIER_FV_ID: &str = "tier-free-versionID";
 pub const TIER_FV_MARKER: &str = "tier-free-marker";
 pub const TIER_SKIP_FV_ID: &str = "tier-skip-fvid";
 
+/// Nonces of the encrypted parts of a completed multipart object, as `number:base64` pairs. xl.meta keeps
+/// no per-part field for them, so `complete_multipart_upload` moves them here from the part metadata.
+pub const PART_NONCES_KEY: &str = "x-nebulafx-internal-part-nonces";
+
 const ERR_RESTORE_HDR_MALFORMED: &str = "x-amz-restore header malformed";
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
@@ -37,6 +41,9 @@ pub struct ObjectPartInfo {
     // Checksums holds checksums of the part
     pub checksums: Option<HashMap<String, String>>,
     pub error: Option<String>,
+    // Nonce the part was encrypted with, a part uploaded again under the same number gets a new one
+    #[serde(default)]
+    pub nonce: Option<Bytes>,
 }
 
 impl ObjectPartInfo {
@@ -284,6 +291,7 @@ impl FileInfo {
         actual_size: i64,
         index: Option<Bytes>,
         checksums: Option<HashMap<String, String>>,
+        nonce: Option<Bytes>,
     ) {
         let part = ObjectPartInfo {
             etag,
@@ -294,6 +302,7 @@ impl FileInfo {
             index,
             checksums,
             error: None,
+            nonce,
         };
 
         for p in self.parts.iter_mut() {
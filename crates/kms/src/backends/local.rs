@@ -46,6 +46,17 @@ struct StoredMasterKey {
     encrypted_key_material: Vec<u8>,
     /// Nonce used for encryption
     nonce: Vec<u8>,
+    /// Key material of earlier versions, kept so data keys sealed before a rotation still decrypt
+    #[serde(default)]
+    previous_versions: Vec<StoredKeyVersion>,
+}
+
+/// Key material of a rotated-out master key version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredKeyVersion {
+    version: u32,
+    encrypted_key_material: Vec<u8>,
+    nonce: Vec<u8>,
 }
 
 /// Data key envelope stored with each data key generation
@@ -60,6 +71,17 @@ struct DataKeyEnvelope {
     created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Key ids become file names, so only allow plain names made of `[A-Za-z0-9._-]`
+fn validate_key_id(key_id: &str) -> Result<()> {
+    let plain = key_id
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'));
+    if key_id.is_empty() || key_id == "." || key_id == ".." || !plain {
+        return Err(KmsError::validation_error(format!("invalid key id: {key_id:?}")));
+    }
+    Ok(())
+}
+
 impl LocalKmsClient {
     /// Create a new local KMS client
     pub async fn new(config: LocalConfig) -> Result<Self> {
@@ -98,14 +120,15 @@ impl LocalKmsClient {
         Ok(key)
     }
 
-    /// Get the file path for a master key
-    fn master_key_path(&self, key_id: &str) -> PathBuf {
-        self.config.key_dir.join(format!("{key_id}.key"))
+    /// Get the file path for a master key, refusing ids that would leave the key directory
+    fn master_key_path(&self, key_id: &str) -> Result<PathBuf> {
+        validate_key_id(key_id)?;
+        Ok(self.config.key_dir.join(format!("{key_id}.key")))
     }
 
     /// Load a master key from disk
     async fn load_master_key(&self, key_id: &str) -> Result<MasterKey> {
-        let key_path = self.master_key_path(key_id);
+        let key_path = self.master_key_path(key_id)?;
         if !key_path.exists() {
             return Err(KmsError::key_not_found(key_id));
         }
@@ -144,9 +167,33 @@ impl LocalKmsClient {
         })
     }
 
-    /// Save a master key to disk
+    /// Save a master key to disk, keeping the key material of its earlier versions
     async fn save_master_key(&self, master_key: &MasterKey, key_material: &[u8]) -> Result<()> {
-        let key_path = self.master_key_path(&master_key.key_id);
+        let previous_versions = match self.read_stored_key(&master_key.key_id).await {
+            Ok(stored_key) => stored_key.previous_versions,
+            Err(_) => Vec::new(),
+        };
+
+        self.write_master_key(master_key, key_material, previous_versions).await
+    }
+
+    async fn read_stored_key(&self, key_id: &str) -> Result<StoredMasterKey> {
+        let key_path = self.master_key_path(key_id)?;
+        if !key_path.exists() {
+            return Err(KmsError::key_not_found(key_id));
+        }
+
+        let content = fs::read(&key_path).await?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    async fn write_master_key(
+        &self,
+        master_key: &MasterKey,
+        key_material: &[u8],
+        previous_versions: Vec<StoredKeyVersion>,
+    ) -> Result<()> {
+        let key_path = self.master_key_path(&master_key.key_id)?;
 
         // Encrypt key material if master cipher is available
         let (encrypted_key_material, nonce) = if let Some(ref cipher) = self.master_cipher {
@@ -175,6 +222,7 @@ impl LocalKmsClient {
             created_by: master_key.created_by.clone(),
             encrypted_key_material,
             nonce,
+            previous_versions,
         };
 
         let content = serde_json::to_vec_pretty(&stored_key)?;
@@ -206,7 +254,7 @@ impl LocalKmsClient {
 
     /// Get the actual key material for a master key
     async fn get_key_material(&self, key_id: &str) -> Result<Vec<u8>> {
-        let key_path = self.master_key_path(key_id);
+        let key_path = self.master_key_path(key_id)?;
 
         if !key_path.exists() {
             return Err(KmsError::key_not_found(key_id));
@@ -233,6 +281,31 @@ impl LocalKmsClient {
         Ok(key_material)
     }
 
+    /// Key material of the earlier versions of a master key, newest first
+    async fn get_previous_key_materials(&self, key_id: &str) -> Result<Vec<Vec<u8>>> {
+        let stored_key = self.read_stored_key(key_id).await?;
+
+        let mut materials = Vec::with_capacity(stored_key.previous_versions.len());
+        for version in stored_key.previous_versions.iter().rev() {
+            let material = if let Some(ref cipher) = self.master_cipher {
+                if version.nonce.len() != 12 {
+                    return Err(KmsError::cryptographic_error("nonce", "Invalid nonce length"));
+                }
+                let mut nonce_array = [0u8; 12];
+                nonce_array.copy_from_slice(&version.nonce);
+                let nonce = Nonce::from(nonce_array);
+                cipher
+                    .decrypt(&nonce, version.encrypted_key_material.as_ref())
+                    .map_err(|e| KmsError::cryptographic_error("decrypt", e.to_string()))?
+            } else {
+                version.encrypted_key_material.clone()
+            };
+            materials.push(material);
+        }
+
+        Ok(materials)
+    }
+
     /// Encrypt data using a master key
     async fn encrypt_with_master_key(&self, key_id: &str, plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
         // Load the actual master key material
@@ -258,21 +331,33 @@ impl LocalKmsClient {
         if nonce.len() != 12 {
             return Err(KmsError::cryptographic_error("nonce", "Invalid nonce length"));
         }
-        // Load the actual master key material
-        let key_material = self.get_key_material(key_id).await?;
-        let key = Key::<Aes256Gcm>::try_from(key_material.as_slice())
-            .map_err(|_| KmsError::cryptographic_error("key", "Invalid key length"))?;
-        let cipher = Aes256Gcm::new(&key);
-
         let mut nonce_array = [0u8; 12];
         nonce_array.copy_from_slice(nonce);
         let nonce_ref = Nonce::from(nonce_array);
 
-        let plaintext = cipher
-            .decrypt(&nonce_ref, ciphertext)
-            .map_err(|e| KmsError::cryptographic_error("decrypt", e.to_string()))?;
+        let decrypt_with = |key_material: &[u8]| -> Result<Vec<u8>> {
+            let key = Key::<Aes256Gcm>::try_from(key_material)
+                .map_err(|_| KmsError::cryptographic_error("key", "Invalid key length"))?;
+            Aes256Gcm::new(&key)
+                .decrypt(&nonce_ref, ciphertext)
+                .map_err(|e| KmsError::cryptographic_error("decrypt", e.to_string()))
+        };
 
-        Ok(plaintext)
+        // Load the actual master key material
+        let key_material = self.get_key_material(key_id).await?;
+        let err = match decrypt_with(&key_material) {
+            Ok(plaintext) => return Ok(plaintext),
+            Err(err) => err,
+        };
+
+        // Sealed before the key was rotated
+        for key_material in self.get_previous_key_materials(key_id).await? {
+            if let Ok(plaintext) = decrypt_with(&key_material) {
+                return Ok(plaintext);
+            }
+        }
+
+        Err(err)
     }
 }
 
@@ -373,7 +458,7 @@ impl KmsClient for LocalKmsClient {
         debug!("Creating master key: {}", key_id);
 
         // Check if key already exists
-        if self.master_key_path(key_id).exists() {
+        if self.master_key_path(key_id)?.exists() {
             return Err(KmsError::key_already_exists(key_id));
         }
 
@@ -477,9 +562,8 @@ impl KmsClient for LocalKmsClient {
         let mut master_key = self.load_master_key(key_id).await?;
         master_key.status = KeyStatus::Active;
 
-        // For simplicity, we'll regenerate key material
-        // In a real implementation, we'd preserve the original key material
-        let key_material = Self::generate_key_material();
+        // Only the status changes, data keys sealed with this key must stay decryptable
+        let key_material = self.get_key_material(key_id).await?;
         self.save_master_key(&master_key, &key_material).await?;
 
         // Update cache
@@ -496,7 +580,7 @@ impl KmsClient for LocalKmsClient {
         let mut master_key = self.load_master_key(key_id).await?;
         master_key.status = KeyStatus::Disabled;
 
-        let key_material = Self::generate_key_material();
+        let key_material = self.get_key_material(key_id).await?;
         self.save_master_key(&master_key, &key_material).await?;
 
         // Update cache
@@ -518,7 +602,7 @@ impl KmsClient for LocalKmsClient {
         let mut master_key = self.load_master_key(key_id).await?;
         master_key.status = KeyStatus::PendingDeletion;
 
-        let key_material = Self::generate_key_material();
+        let key_material = self.get_key_material(key_id).await?;
         self.save_master_key(&master_key, &key_material).await?;
 
         // Update cache
@@ -535,7 +619,7 @@ impl KmsClient for LocalKmsClient {
         let mut master_key = self.load_master_key(key_id).await?;
         master_key.status = KeyStatus::Active;
 
-        let key_material = Self::generate_key_material();
+        let key_material = self.get_key_material(key_id).await?;
         self.save_master_key(&master_key, &key_material).await?;
 
         // Update cache
@@ -550,12 +634,22 @@ impl KmsClient for LocalKmsClient {
         debug!("Rotating key: {}", key_id);
 
         let mut master_key = self.load_master_key(key_id).await?;
+
+        // Keep the current version, it is still needed to decrypt existing data keys
+        let stored_key = self.read_stored_key(key_id).await?;
+        let mut previous_versions = stored_key.previous_versions;
+        previous_versions.push(StoredKeyVersion {
+            version: stored_key.version,
+            encrypted_key_material: stored_key.encrypted_key_material,
+            nonce: stored_key.nonce,
+        });
+
         master_key.version += 1;
         master_key.rotated_at = Some(chrono::Utc::now());
 
         // Generate new key material
         let key_material = Self::generate_key_material();
-        self.save_master_key(&master_key, &key_material).await?;
+        self.write_master_key(&master_key, &key_material, previous_versions).await?;
 
         // Update cache
         let mut cache = self.key_cache.write().await;
@@ -612,6 +706,11 @@ impl KmsBackend for LocalKmsBackend {
     async fn create_key(&self, request: CreateKeyRequest) -> Result<CreateKeyResponse> {
         let key_id = request.key_name.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
+        // Never overwrite the key material of an existing key
+        if self.client.master_key_path(&key_id)?.exists() {
+            return Err(KmsError::key_already_exists(&key_id));
+        }
+
         // Create master key with description directly
         let _master_key = {
             // Generate key material
@@ -740,7 +839,7 @@ impl KmsBackend for LocalKmsBackend {
 
         let (deletion_date_str, deletion_date_dt) = if request.force_immediate.unwrap_or(false) {
             // For immediate deletion, actually delete the key from filesystem
-            let key_path = self.client.master_key_path(key_id);
+            let key_path = self.client.master_key_path(key_id)?;
             tokio::fs::remove_file(&key_path)
                 .await
                 .map_err(|e| KmsError::internal_error(format!("Failed to delete key file: {e}")))?;
@@ -784,7 +883,7 @@ impl KmsBackend for LocalKmsBackend {
 
         // Save the updated key to disk - preserve existing key material!
         // Load the stored key from disk to get the existing key material
-        let key_path = self.client.master_key_path(key_id);
+        let key_path = self.client.master_key_path(key_id)?;
         let content = tokio::fs::read(&key_path)
             .await
             .map_err(|e| KmsError::internal_error(format!("Failed to read key file: {e}")))?;
@@ -849,8 +948,8 @@ impl KmsBackend for LocalKmsBackend {
         // Cancel the deletion by resetting the state
         master_key.status = KeyStatus::Active;
 
-        // Save the updated key to disk - this is the missing critical step!
-        let key_material = LocalKmsClient::generate_key_material();
+        // Save the updated key to disk, keeping its key material
+        let key_material = self.client.get_key_material(key_id).await?;
         self.client.save_master_key(&master_key, &key_material).await?;
 
         // Update cache
@@ -876,6 +975,18 @@ impl KmsBackend for LocalKmsBackend {
         })
     }
 
+    async fn enable_key(&self, key_id: &str) -> Result<()> {
+        self.client.enable_key(key_id, None).await
+    }
+
+    async fn disable_key(&self, key_id: &str) -> Result<()> {
+        self.client.disable_key(key_id, None).await
+    }
+
+    async fn rotate_key(&self, key_id: &str) -> Result<MasterKey> {
+        self.client.rotate_key(key_id, None).await
+    }
+
     async fn health_check(&self) -> Result<bool> {
         self.client.health_check().await.map(|_| true)
     }
@@ -897,6 +1008,19 @@ mod tests {
         (client, temp_dir)
     }
 
+    #[tokio::test]
+    async fn test_key_id_must_be_a_plain_name() {
+        let (client, temp_dir) = create_test_client().await;
+
+        for key_id in ["../escape", "..", ".", "a/b", "a\\b", ""] {
+            let result = client.create_key(key_id, "AES_256", None).await;
+            assert!(matches!(result, Err(KmsError::ValidationError { .. })), "{key_id:?} should be rejected");
+        }
+        assert!(!temp_dir.path().parent().unwrap().join("escape.key").exists());
+
+        client.create_key("tenant.key_1-a", "AES_256", None).await.expect("plain id is accepted");
+    }
+
     #[tokio::test]
     async fn test_key_lifecycle() {
         let (client, _temp_dir) = create_test_client().await;
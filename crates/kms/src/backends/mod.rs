@@ -167,6 +167,15 @@ pub trait KmsBackend: Send + Sync {
     /// Cancel key deletion
     async fn cancel_key_deletion(&self, request: CancelKeyDeletionRequest) -> Result<CancelKeyDeletionResponse>;
 
+    /// Enable a key
+    async fn enable_key(&self, key_id: &str) -> Result<()>;
+
+    /// Disable a key
+    async fn disable_key(&self, key_id: &str) -> Result<()>;
+
+    /// Rotate a key to new key material; data keys encrypted under older versions stay decryptable
+    async fn rotate_key(&self, key_id: &str) -> Result<MasterKey>;
+
     /// Health check
     async fn health_check(&self) -> Result<bool>;
 }
@@ -9,7 +9,7 @@ use crate::error::Result;
 use crate::types::{
     CancelKeyDeletionRequest, CancelKeyDeletionResponse, CreateKeyRequest, CreateKeyResponse, DecryptRequest, DecryptResponse,
     DeleteKeyRequest, DeleteKeyResponse, DescribeKeyRequest, DescribeKeyResponse, EncryptRequest, EncryptResponse,
-    GenerateDataKeyRequest, GenerateDataKeyResponse, ListKeysRequest, ListKeysResponse,
+    GenerateDataKeyRequest, GenerateDataKeyResponse, ListKeysRequest, ListKeysResponse, MasterKey,
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -163,6 +163,44 @@ impl KmsManager {
         Ok(response)
     }
 
+    /// Enable a key
+    pub async fn enable_key(&self, key_id: &str) -> Result<()> {
+        self.backend.enable_key(key_id).await?;
+
+        if self.config.enable_cache {
+            let mut cache = self.cache.write().await;
+            cache.remove_key_metadata(key_id).await;
+        }
+
+        Ok(())
+    }
+
+    /// Disable a key; cached data keys are dropped so no new objects are encrypted under it
+    pub async fn disable_key(&self, key_id: &str) -> Result<()> {
+        self.backend.disable_key(key_id).await?;
+
+        if self.config.enable_cache {
+            let mut cache = self.cache.write().await;
+            cache.remove_key_metadata(key_id).await;
+            cache.remove_data_key(key_id).await;
+        }
+
+        Ok(())
+    }
+
+    /// Rotate a key to new key material
+    pub async fn rotate_key(&self, key_id: &str) -> Result<MasterKey> {
+        let master_key = self.backend.rotate_key(key_id).await?;
+
+        if self.config.enable_cache {
+            let mut cache = self.cache.write().await;
+            cache.remove_key_metadata(key_id).await;
+            cache.remove_data_key(key_id).await;
+        }
+
+        Ok(master_key)
+    }
+
     /// Perform health check on the KMS backend
     pub async fn health_check(&self) -> Result<bool> {
         self.backend.health_check().await
@@ -225,4 +263,51 @@ mod tests {
         let health = manager.health_check().await.expect("Health check failed");
         assert!(health);
     }
+
+    #[tokio::test]
+    async fn test_rotate_keeps_old_data_keys_decryptable() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let config = KmsConfig::local(temp_dir.path().to_path_buf());
+
+        let backend = Arc::new(LocalKmsBackend::new(config.clone()).await.expect("Failed to create backend"));
+        let manager = KmsManager::new(backend, config);
+
+        let key_id = manager
+            .create_key(CreateKeyRequest::default())
+            .await
+            .expect("Failed to create key")
+            .key_id;
+
+        let old_data_key = manager
+            .generate_data_key(GenerateDataKeyRequest {
+                key_id: key_id.clone(),
+                key_spec: KeySpec::Aes256,
+                encryption_context: Default::default(),
+            })
+            .await
+            .expect("Failed to generate data key");
+
+        let rotated = manager.rotate_key(&key_id).await.expect("Failed to rotate key");
+        assert_eq!(rotated.version, 2);
+
+        let decrypted = manager
+            .decrypt(DecryptRequest::new(old_data_key.ciphertext_blob))
+            .await
+            .expect("Failed to decrypt data key from before rotation");
+        assert_eq!(decrypted.plaintext, old_data_key.plaintext_key);
+
+        manager.disable_key(&key_id).await.expect("Failed to disable key");
+        let described = manager
+            .describe_key(DescribeKeyRequest { key_id: key_id.clone() })
+            .await
+            .expect("Failed to describe key");
+        assert_eq!(described.key_metadata.key_state, KeyState::Disabled);
+
+        manager.enable_key(&key_id).await.expect("Failed to enable key");
+        let described = manager
+            .describe_key(DescribeKeyRequest { key_id })
+            .await
+            .expect("Failed to describe key");
+        assert_eq!(described.key_metadata.key_state, KeyState::Enabled);
+    }
 }
@@ -2,6 +2,7 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 use crate::{
@@ -12,10 +13,42 @@ use crate::{
     types::{LockId, LockInfo, LockMetadata, LockPriority, LockRequest, LockResponse, LockStats, LockType},
 };
 
+/// Default interval of the background sweeper that drops expired leases
+pub const DEFAULT_LEASE_SWEEP_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A held lock together with its lease
+///
+/// Locks taken on behalf of remote nodes are only kept while their owner keeps
+/// refreshing them. If the owner dies, the lease runs out and the lock is dropped.
+#[derive(Debug)]
+struct LeasedGuard {
+    guard: FastLockGuard,
+    ttl: Duration,
+    expires_at: Instant,
+}
+
+impl LeasedGuard {
+    fn new(guard: FastLockGuard, ttl: Duration) -> Self {
+        Self {
+            guard,
+            ttl,
+            expires_at: Instant::now() + ttl,
+        }
+    }
+
+    fn renew(&mut self) {
+        self.expires_at = Instant::now() + self.ttl;
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        !self.ttl.is_zero() && self.expires_at <= now
+    }
+}
+
 /// Local lock client using FastLock
 #[derive(Debug, Clone)]
 pub struct LocalClient {
-    guard_storage: Arc<RwLock<HashMap<LockId, FastLockGuard>>>,
+    guard_storage: Arc<RwLock<HashMap<LockId, LeasedGuard>>>,
 }
 
 impl LocalClient {
@@ -30,6 +63,48 @@ impl LocalClient {
     pub fn get_lock_manager(&self) -> Arc<GlobalLockManager> {
         crate::get_global_lock_manager()
     }
+
+    /// Drop every lock whose lease has not been refreshed within its TTL.
+    /// Returns the number of locks released.
+    pub async fn evict_expired(&self) -> usize {
+        let now = Instant::now();
+        let mut guards = self.guard_storage.write().await;
+        let before = guards.len();
+        guards.retain(|lock_id, leased| {
+            let expired = leased.is_expired(now);
+            if expired {
+                tracing::warn!("Lock lease expired for {}, releasing stale lock", lock_id);
+            }
+            !expired
+        });
+        before - guards.len()
+    }
+
+    /// Spawn a background task that periodically evicts expired leases.
+    /// Does nothing when called outside of a Tokio runtime.
+    pub fn start_lease_sweeper(&self, interval: Duration) {
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            tracing::warn!("No Tokio runtime available, lock lease sweeper not started");
+            return;
+        };
+
+        let client = self.clone();
+        handle.spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let evicted = client.evict_expired().await;
+                if evicted > 0 {
+                    tracing::info!("Lock lease sweeper released {} stale locks", evicted);
+                }
+            }
+        });
+    }
+
+    async fn store_guard(&self, lock_id: LockId, guard: FastLockGuard, ttl: Duration) {
+        let mut guards = self.guard_storage.write().await;
+        guards.insert(lock_id, LeasedGuard::new(guard, ttl));
+    }
 }
 
 impl Default for LocalClient {
@@ -41,17 +116,17 @@ impl Default for LocalClient {
 #[async_trait::async_trait]
 impl LockClient for LocalClient {
     async fn acquire_exclusive(&self, request: &LockRequest) -> Result<LockResponse> {
+        self.evict_expired().await;
         let lock_manager = self.get_lock_manager();
         let lock_request = crate::fast_lock::ObjectLockRequest::new_write("", request.resource.clone(), request.owner.clone())
             .with_acquire_timeout(request.acquire_timeout);
 
         match lock_manager.acquire_lock(lock_request).await {
             Ok(guard) => {
-                let lock_id = crate::types::LockId::new_deterministic(&request.resource);
+                let lock_id = request.lock_id.clone();
 
-                // Store guard for later release
-                let mut guards = self.guard_storage.write().await;
-                guards.insert(lock_id.clone(), guard);
+                // Store guard for later release; the lease keeps it alive while the owner refreshes
+                self.store_guard(lock_id.clone(), guard, request.ttl).await;
 
                 let lock_info = LockInfo {
                     id: lock_id,
@@ -85,17 +160,17 @@ impl LockClient for LocalClient {
     }
 
     async fn acquire_shared(&self, request: &LockRequest) -> Result<LockResponse> {
+        self.evict_expired().await;
         let lock_manager = self.get_lock_manager();
         let lock_request = crate::fast_lock::ObjectLockRequest::new_read("", request.resource.clone(), request.owner.clone())
             .with_acquire_timeout(request.acquire_timeout);
 
         match lock_manager.acquire_lock(lock_request).await {
             Ok(guard) => {
-                let lock_id = crate::types::LockId::new_deterministic(&request.resource);
+                let lock_id = request.lock_id.clone();
 
-                // Store guard for later release
-                let mut guards = self.guard_storage.write().await;
-                guards.insert(lock_id.clone(), guard);
+                // Store guard for later release; the lease keeps it alive while the owner refreshes
+                self.store_guard(lock_id.clone(), guard, request.ttl).await;
 
                 let lock_info = LockInfo {
                     id: lock_id,
@@ -130,9 +205,9 @@ impl LockClient for LocalClient {
 
     async fn release(&self, lock_id: &LockId) -> Result<bool> {
         let mut guards = self.guard_storage.write().await;
-        if let Some(guard) = guards.remove(lock_id) {
+        if let Some(leased) = guards.remove(lock_id) {
             // Guard automatically releases the lock when dropped
-            drop(guard);
+            drop(leased.guard);
             Ok(true)
         } else {
             // Lock not found or already released
@@ -140,9 +215,20 @@ impl LockClient for LocalClient {
         }
     }
 
-    async fn refresh(&self, _lock_id: &LockId) -> Result<bool> {
-        // For local locks, refresh is not needed as they don't expire automatically
-        Ok(true)
+    async fn refresh(&self, lock_id: &LockId) -> Result<bool> {
+        let mut guards = self.guard_storage.write().await;
+        match guards.get_mut(lock_id) {
+            Some(leased) if !leased.is_expired(Instant::now()) => {
+                leased.renew();
+                Ok(true)
+            }
+            Some(_) => {
+                // Lease already ran out, the lock must be considered lost
+                guards.remove(lock_id);
+                Ok(false)
+            }
+            None => Ok(false),
+        }
     }
 
     async fn force_release(&self, lock_id: &LockId) -> Result<bool> {
@@ -151,8 +237,9 @@ impl LockClient for LocalClient {
 
     async fn check_status(&self, lock_id: &LockId) -> Result<Option<LockInfo>> {
         let guards = self.guard_storage.read().await;
-        if let Some(guard) = guards.get(lock_id) {
+        if let Some(leased) = guards.get(lock_id) {
             // We have an active guard for this lock
+            let guard = &leased.guard;
             let lock_type = match guard.mode() {
                 crate::fast_lock::types::LockMode::Shared => crate::types::LockType::Shared,
                 crate::fast_lock::types::LockMode::Exclusive => crate::types::LockType::Exclusive,
@@ -164,7 +251,7 @@ impl LockClient for LocalClient {
                 status: crate::types::LockStatus::Acquired,
                 owner: guard.owner().to_string(),
                 acquired_at: std::time::SystemTime::now(),
-                expires_at: std::time::SystemTime::now() + std::time::Duration::from_secs(30),
+                expires_at: std::time::SystemTime::now() + leased.expires_at.saturating_duration_since(Instant::now()),
                 last_refreshed: std::time::SystemTime::now(),
                 metadata: LockMetadata::default(),
                 priority: LockPriority::Normal,
@@ -368,4 +455,64 @@ mod tests {
             let _ = client2.release(&lock_info.id).await;
         }
     }
+
+    #[tokio::test]
+    async fn test_local_client_lease_expires_without_refresh() {
+        let client = LocalClient::new();
+        let resource_name = format!("test-lease-expiry-{}", uuid::Uuid::new_v4());
+
+        let request = LockRequest::new(&resource_name, LockType::Exclusive, "dead-owner")
+            .with_acquire_timeout(std::time::Duration::from_millis(50))
+            .with_ttl(std::time::Duration::from_millis(50));
+        let response = client.acquire_exclusive(&request).await.unwrap();
+        assert!(response.is_success());
+
+        // Owner never refreshes, so the lease runs out and the lock is swept
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert_eq!(client.evict_expired().await, 1);
+        assert!(!client.refresh(&request.lock_id).await.unwrap());
+
+        let other = LockRequest::new(&resource_name, LockType::Exclusive, "new-owner")
+            .with_acquire_timeout(std::time::Duration::from_millis(50));
+        let other_response = client.acquire_exclusive(&other).await.unwrap();
+        assert!(other_response.is_success(), "stale lock should have been released");
+
+        let _ = client.release(&other.lock_id).await;
+    }
+
+    #[tokio::test]
+    async fn test_local_client_refresh_keeps_lease_alive() {
+        let client = LocalClient::new();
+        let resource_name = format!("test-lease-refresh-{}", uuid::Uuid::new_v4());
+
+        let request = LockRequest::new(&resource_name, LockType::Exclusive, "live-owner")
+            .with_acquire_timeout(std::time::Duration::from_millis(50))
+            .with_ttl(std::time::Duration::from_millis(200));
+        let response = client.acquire_exclusive(&request).await.unwrap();
+        assert!(response.is_success());
+
+        for _ in 0..3 {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            assert!(client.refresh(&request.lock_id).await.unwrap());
+        }
+        assert_eq!(client.evict_expired().await, 0);
+
+        assert!(client.release(&request.lock_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_local_client_shared_locks_with_unique_ids() {
+        let client = LocalClient::new();
+        let resource_name = format!("test-shared-unique-{}", uuid::Uuid::new_v4());
+
+        let req1 = LockRequest::new(&resource_name, LockType::Shared, "reader1").with_lock_id(LockId::new(&resource_name));
+        let req2 = LockRequest::new(&resource_name, LockType::Shared, "reader2").with_lock_id(LockId::new(&resource_name));
+        assert!(client.acquire_shared(&req1).await.unwrap().is_success());
+        assert!(client.acquire_shared(&req2).await.unwrap().is_success());
+
+        // Releasing one reader must not drop the other one's lock
+        assert!(client.release(&req1.lock_id).await.unwrap());
+        assert!(client.check_status(&req2.lock_id).await.unwrap().is_some());
+        assert!(client.release(&req2.lock_id).await.unwrap());
+    }
 }
@@ -1,9 +1,12 @@
 
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use once_cell::sync::Lazy;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 use crate::{client::LockClient, types::LockId};
 
@@ -52,6 +55,10 @@ pub struct LockGuard {
     clients: Vec<Arc<dyn LockClient>>,
     /// If true, Drop will not try to release (used if user manually released).
     disarmed: bool,
+    /// Set when the lease could no longer be refreshed on a quorum of clients.
+    lost: Arc<AtomicBool>,
+    /// Background task refreshing the lease while the guard is alive.
+    refresher: Option<JoinHandle<()>>,
 }
 
 impl LockGuard {
@@ -60,9 +67,54 @@ impl LockGuard {
             lock_id,
             clients,
             disarmed: false,
+            lost: Arc::new(AtomicBool::new(false)),
+            refresher: None,
         }
     }
 
+    /// Keep the lease alive by refreshing it on every client each `interval`.
+    /// If fewer than `quorum` clients accept a refresh, the lock is marked as lost
+    /// and refreshing stops; remaining holders will drop it once their lease expires.
+    pub(crate) fn with_refresh(mut self, interval: Duration, quorum: usize) -> Self {
+        if interval.is_zero() {
+            return self;
+        }
+
+        let lock_id = self.lock_id.clone();
+        let clients = self.clients.clone();
+        let lost = self.lost.clone();
+        self.refresher = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes immediately, the lease was just granted
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let futs = clients.iter().map(|client| {
+                    let id = lock_id.clone();
+                    async move { client.refresh(&id).await.unwrap_or(false) }
+                });
+                let refreshed = futures::future::join_all(futs).await.into_iter().filter(|ok| *ok).count();
+                if refreshed < quorum {
+                    tracing::error!(
+                        "LockGuard lost lock {}: refreshed on {}/{} clients, quorum {}",
+                        lock_id,
+                        refreshed,
+                        clients.len(),
+                        quorum
+                    );
+                    lost.store(true, Ordering::SeqCst);
+                    break;
+                }
+            }
+        }));
+        self
+    }
+
+    /// Whether the lease could not be kept alive on a quorum of clients
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::SeqCst)
+    }
+
     /// Get the lock id associated with this guard
     pub fn lock_id(&self) -> &LockId {
         &self.lock_id
@@ -77,6 +129,10 @@ impl LockGuard {
 
 impl Drop for LockGuard {
     fn drop(&mut self) {
+        if let Some(refresher) = self.refresher.take() {
+            refresher.abort();
+        }
+
         if self.disarmed {
             return;
         }
@@ -103,13 +103,14 @@ impl NamespaceLock {
             return Err(LockError::internal("No lock clients available"));
         }
 
+        let refresh_interval = Self::refresh_interval(request.ttl);
+
         if self.clients.len() == 1 {
             let resp = self.clients[0].acquire_lock(request).await?;
             if resp.success {
-                return Ok(Some(LockGuard::new(
-                    LockId::new_deterministic(&request.resource),
-                    vec![self.clients[0].clone()],
-                )));
+                return Ok(Some(
+                    LockGuard::new(request.lock_id.clone(), vec![self.clients[0].clone()]).with_refresh(refresh_interval, 1),
+                ));
             }
             return Ok(None);
         }
@@ -117,12 +118,24 @@ impl NamespaceLock {
         let (resp, idxs) = self.acquire_lock_quorum(request).await?;
         if resp.success {
             let subset: Vec<_> = idxs.into_iter().filter_map(|i| self.clients.get(i).cloned()).collect();
-            Ok(Some(LockGuard::new(LockId::new_deterministic(&request.resource), subset)))
+            Ok(Some(
+                LockGuard::new(request.lock_id.clone(), subset).with_refresh(refresh_interval, self.quorum),
+            ))
         } else {
             Ok(None)
         }
     }
 
+    /// Get the number of clients that must grant a lock
+    pub fn quorum(&self) -> usize {
+        self.quorum
+    }
+
+    /// Leases are refreshed three times per TTL so a single missed refresh does not lose the lock
+    fn refresh_interval(ttl: Duration) -> Duration {
+        ttl / 3
+    }
+
     /// Convenience: acquire exclusive lock as a guard
     pub async fn lock_guard(&self, resource: &str, owner: &str, timeout: Duration, ttl: Duration) -> Result<Option<LockGuard>> {
         let req = LockRequest::new(self.get_resource_key(resource), LockType::Exclusive, owner)
@@ -163,7 +176,7 @@ impl NamespaceLock {
         if successful_clients.len() >= self.quorum {
             let resp = LockResponse::success(
                 LockInfo {
-                    id: LockId::new_deterministic(&request.resource),
+                    id: request.lock_id.clone(),
                     resource: request.resource.clone(),
                     lock_type: request.lock_type,
                     status: LockStatus::Acquired,
@@ -192,7 +205,7 @@ impl NamespaceLock {
 
     /// Rollback lock acquisitions on specified clients
     async fn rollback_acquisitions(&self, request: &LockRequest, client_indices: &[usize]) {
-        let lock_id = LockId::new_deterministic(&request.resource);
+        let lock_id = request.lock_id.clone();
         let rollback_futures: Vec<_> = client_indices
             .iter()
             .filter_map(|&idx| self.clients.get(idx))
@@ -265,6 +265,12 @@ impl LockRequest {
         self
     }
 
+    /// Set lock ID (use a unique ID so that concurrent holders of a shared lock can be told apart)
+    pub fn with_lock_id(mut self, lock_id: LockId) -> Self {
+        self.lock_id = lock_id;
+        self
+    }
+
     /// Set lock TTL (how long the lock remains valid after acquisition)
     pub fn with_ttl(mut self, ttl: Duration) -> Self {
         self.ttl = ttl;
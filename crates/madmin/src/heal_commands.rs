@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::HealingDisk;
+
 pub type HealItemType = String;
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -46,3 +48,23 @@ pub struct HealResultItem {
     #[serde(rename = "objectSize")]
     pub object_size: usize,
 }
+
+/// Background healing of a deployment, reported by the background heal status API
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BgHealState {
+    /// Endpoints of the drives being healed
+    #[serde(rename = "heal_disks")]
+    pub heal_disks: Vec<String>,
+    /// Progress of the drives being healed
+    #[serde(rename = "healing_disks")]
+    pub healing_disks: Vec<HealingDisk>,
+}
+
+impl BgHealState {
+    pub fn merge(&mut self, other: BgHealState) {
+        self.heal_disks.extend(other.heal_disks);
+        self.healing_disks.extend(other.healing_disks);
+        self.heal_disks.sort();
+        self.heal_disks.dedup();
+    }
+}
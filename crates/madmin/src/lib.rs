@@ -7,7 +7,9 @@ pub mod info_commands;
 pub mod metrics;
 pub mod net;
 pub mod policy;
+pub mod replication;
 pub mod service_commands;
+pub mod site_replication;
 pub mod trace;
 pub mod user;
 pub mod utils;
@@ -128,6 +128,10 @@ pub struct ScannerMetrics {
     pub last_minute: LastMinute,
     #[serde(rename = "active")]
     pub active_paths: Vec<String>,
+    #[serde(rename = "stale_uploads", default)]
+    pub stale_uploads: u64,
+    #[serde(rename = "stale_upload_bytes", default)]
+    pub stale_upload_bytes: u64,
 }
 
 impl ScannerMetrics {
@@ -170,6 +174,9 @@ impl ScannerMetrics {
             self.last_minute.ilm.entry(k.clone()).or_default().merge(v);
         }
 
+        self.stale_uploads += other.stale_uploads;
+        self.stale_upload_bytes += other.stale_upload_bytes;
+
         self.active_paths.extend(other.active_paths.clone());
 
         self.active_paths.sort();
@@ -0,0 +1,37 @@
+
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Resync progress of existing objects to one replication target
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ResyncTarget {
+    pub arn: String,
+    #[serde(rename = "resetid")]
+    pub reset_id: String,
+    #[serde(rename = "startTime", with = "time::serde::rfc3339::option", default)]
+    pub start_time: Option<OffsetDateTime>,
+    /// Set once the resync completed, failed or was canceled
+    #[serde(rename = "endTime", with = "time::serde::rfc3339::option", default)]
+    pub end_time: Option<OffsetDateTime>,
+    #[serde(rename = "resyncStatus")]
+    pub resync_status: String,
+    #[serde(rename = "completedReplicationSize")]
+    pub replicated_size: i64,
+    #[serde(rename = "failedReplicationSize")]
+    pub failed_size: i64,
+    #[serde(rename = "failedReplicationCount")]
+    pub failed_count: i64,
+    #[serde(rename = "replicationCount")]
+    pub replicated_count: i64,
+    pub bucket: String,
+    /// Last object processed
+    pub object: String,
+}
+
+/// Resync status of the replication targets of a bucket
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ResyncTargetsInfo {
+    #[serde(rename = "target", default)]
+    pub targets: Vec<ResyncTarget>,
+}
@@ -3,11 +3,86 @@
 use std::{collections::HashMap, time::Duration};
 
 use hyper::Uri;
+use serde::{Deserialize, Serialize};
 
-use crate::{trace::TraceType, utils::parse_duration};
+use crate::{
+    trace::{TraceInfo, TraceType},
+    utils::parse_duration,
+};
 
-#[derive(Debug, Default)]
-#[allow(dead_code)]
+/// Service control action sent by `admin service <action>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceAction {
+    Restart,
+    Stop,
+    /// Reject new S3 calls until unfrozen, in-flight calls keep running
+    Freeze,
+    Unfreeze,
+}
+
+impl ServiceAction {
+    /// Signal numbers carried in peer `signal_service` calls
+    pub const SIG_RESTART: u64 = 1;
+    pub const SIG_STOP: u64 = 2;
+    pub const SIG_FREEZE: u64 = 8;
+    pub const SIG_UNFREEZE: u64 = 16;
+
+    pub fn parse(action: &str) -> Option<Self> {
+        match action {
+            "restart" => Some(Self::Restart),
+            "stop" => Some(Self::Stop),
+            "freeze" => Some(Self::Freeze),
+            "unfreeze" => Some(Self::Unfreeze),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Restart => "restart",
+            Self::Stop => "stop",
+            Self::Freeze => "freeze",
+            Self::Unfreeze => "unfreeze",
+        }
+    }
+
+    pub fn signal(&self) -> u64 {
+        match self {
+            Self::Restart => Self::SIG_RESTART,
+            Self::Stop => Self::SIG_STOP,
+            Self::Freeze => Self::SIG_FREEZE,
+            Self::Unfreeze => Self::SIG_UNFREEZE,
+        }
+    }
+
+    pub fn from_signal(sig: u64) -> Option<Self> {
+        match sig {
+            Self::SIG_RESTART => Some(Self::Restart),
+            Self::SIG_STOP => Some(Self::Stop),
+            Self::SIG_FREEZE => Some(Self::Freeze),
+            Self::SIG_UNFREEZE => Some(Self::Unfreeze),
+            _ => None,
+        }
+    }
+}
+
+/// Per-node outcome of a service action, returned by the admin API
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ServiceActionPeerResult {
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub err: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ServiceActionResult {
+    pub action: String,
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+    pub results: Vec<ServiceActionPeerResult>,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct ServiceTraceOpts {
     s3: bool,
     internal: bool,
@@ -27,11 +102,12 @@ pub struct ServiceTraceOpts {
     ilm: bool,
     only_errors: bool,
     threshold: Duration,
+    headers: bool,
+    body: bool,
 }
 
-#[allow(dead_code)]
 impl ServiceTraceOpts {
-    fn trace_types(&self) -> TraceType {
+    pub fn trace_types(&self) -> TraceType {
         let mut tt = TraceType::default();
         tt.set_if(self.s3, &TraceType::S3);
         tt.set_if(self.internal, &TraceType::INTERNAL);
@@ -81,22 +157,24 @@ impl ServiceTraceOpts {
         self.batch_replication = query_pairs.get("batch-replication").is_some_and(|v| v == "true");
         self.batch_key_rotation = query_pairs.get("batch-keyrotation").is_some_and(|v| v == "true");
         self.batch_expire = query_pairs.get("batch-expire").is_some_and(|v| v == "true");
+        self.rebalance = query_pairs.get("rebalance").is_some_and(|v| v == "true");
+        self.storage = query_pairs.get("storage").is_some_and(|v| v == "true");
+        self.internal = query_pairs.get("internal").is_some_and(|v| v == "true");
         if query_pairs.get("all").is_some_and(|v| v == "true") {
             self.s3 = true;
             self.internal = true;
             self.storage = true;
             self.os = true;
         }
-
-        self.rebalance = query_pairs.get("rebalance").is_some_and(|v| v == "true");
-        self.storage = query_pairs.get("storage").is_some_and(|v| v == "true");
-        self.internal = query_pairs.get("internal").is_some_and(|v| v == "true");
         self.only_errors = query_pairs.get("err").is_some_and(|v| v == "true");
         self.replication_resync = query_pairs.get("replication-resync").is_some_and(|v| v == "true");
         self.bootstrap = query_pairs.get("bootstrap").is_some_and(|v| v == "true");
         self.ftp = query_pairs.get("ftp").is_some_and(|v| v == "true");
         self.ilm = query_pairs.get("ilm").is_some_and(|v| v == "true");
 
+        self.headers = query_pairs.get("headers").is_some_and(|v| v == "true");
+        self.body = query_pairs.get("body").is_some_and(|v| v == "true");
+
         if let Some(threshold) = query_pairs.get("threshold") {
             let duration = parse_duration(threshold)?;
             self.threshold = duration;
@@ -104,4 +182,93 @@ impl ServiceTraceOpts {
 
         Ok(())
     }
+
+    /// Whether a trace record passes the type, error-only and latency filters
+    pub fn matches(&self, info: &TraceInfo) -> bool {
+        if !self.trace_types().overlaps(&TraceType::new(info.trace_type)) {
+            return false;
+        }
+
+        if self.only_errors && !info.is_error() {
+            return false;
+        }
+
+        info.duration >= self.threshold
+    }
+
+    /// Drop the headers and bodies the subscriber did not ask for
+    pub fn redact(&self, info: &mut TraceInfo) {
+        let Some(http) = info.http.as_mut() else {
+            return;
+        };
+
+        if !self.headers {
+            http.req_info.headers = None;
+            http.resp_info.headers = None;
+        }
+        if !self.body {
+            http.req_info.body = None;
+            http.resp_info.body = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(query: &str) -> ServiceTraceOpts {
+        let uri: Uri = format!("/nebulafx/admin/v3/trace?{query}").parse().unwrap();
+        let mut opts = ServiceTraceOpts::default();
+        opts.parse_params(&uri).unwrap();
+        opts
+    }
+
+    #[test]
+    fn test_service_action_signals() {
+        for action in [
+            ServiceAction::Restart,
+            ServiceAction::Stop,
+            ServiceAction::Freeze,
+            ServiceAction::Unfreeze,
+        ] {
+            assert_eq!(ServiceAction::from_signal(action.signal()), Some(action));
+            assert_eq!(ServiceAction::parse(action.as_str()), Some(action));
+        }
+        assert_eq!(ServiceAction::parse("reboot"), None);
+        assert_eq!(ServiceAction::from_signal(4), None);
+    }
+
+    #[test]
+    fn test_all_enables_storage_and_internal() {
+        let tt = parse("all=true").trace_types();
+        assert!(tt.contains(&TraceType::S3));
+        assert!(tt.contains(&TraceType::INTERNAL));
+        assert!(tt.contains(&TraceType::STORAGE));
+        assert!(!tt.contains(&TraceType::SCANNER));
+    }
+
+    #[test]
+    fn test_matches_filters() {
+        let opts = parse("s3=true&err=true&threshold=100ms");
+
+        let mut info = TraceInfo {
+            trace_type: TraceType::S3.mask(),
+            duration: Duration::from_millis(200),
+            error: Some("boom".to_string()),
+            ..Default::default()
+        };
+        assert!(opts.matches(&info));
+
+        info.duration = Duration::from_millis(50);
+        assert!(!opts.matches(&info));
+
+        info.duration = Duration::from_millis(200);
+        info.error = None;
+        assert!(!opts.matches(&info));
+
+        info.error = Some("boom".to_string());
+        info.trace_type = TraceType::STORAGE.mask();
+        assert!(!opts.matches(&info));
+    }
 }
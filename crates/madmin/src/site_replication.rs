@@ -0,0 +1,210 @@
+
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+
+/// A deployment to link, as passed to `site-replication/add`. The credentials are only used
+/// to set up the link, sites talk to each other with a shared service account afterwards.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PeerSite {
+    pub name: String,
+    #[serde(rename = "endpoints")]
+    pub endpoint: String,
+    #[serde(rename = "accessKey")]
+    pub access_key: String,
+    #[serde(rename = "secretKey")]
+    pub secret_key: String,
+}
+
+/// A linked deployment
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerInfo {
+    pub name: String,
+    pub endpoint: String,
+    #[serde(rename = "deploymentID")]
+    pub deployment_id: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ReplicateAddStatus {
+    pub success: bool,
+    pub status: String,
+    #[serde(rename = "errorDetail", default, skip_serializing_if = "String::is_empty")]
+    pub err_detail: String,
+    #[serde(rename = "initialSyncErrorMessage", default, skip_serializing_if = "String::is_empty")]
+    pub initial_sync_error_message: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ReplicateRemoveStatus {
+    pub status: String,
+    #[serde(rename = "errorDetail", default, skip_serializing_if = "String::is_empty")]
+    pub err_detail: String,
+}
+
+/// Sites to unlink, `all` disables site replication everywhere
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SRRemoveReq {
+    #[serde(rename = "sites", default)]
+    pub site_names: Vec<String>,
+    #[serde(rename = "all", default)]
+    pub remove_all: bool,
+}
+
+/// Site replication configuration of this deployment
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SiteReplicationInfo {
+    pub enabled: bool,
+    pub name: String,
+    pub sites: Vec<PeerInfo>,
+    #[serde(rename = "serviceAccountAccessKey")]
+    pub service_account_access_key: String,
+}
+
+/// Sent by the site running `site-replication/add` to every other site
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SRPeerJoinReq {
+    #[serde(rename = "svcAcctAccessKey")]
+    pub svc_acct_access_key: String,
+    #[serde(rename = "svcAcctSecretKey")]
+    pub svc_acct_secret_key: String,
+    /// Linked sites by deployment id, a site missing from it leaves site replication
+    pub peers: BTreeMap<String, PeerInfo>,
+    #[serde(rename = "updatedAt", with = "time::serde::rfc3339::option", default)]
+    pub updated_at: Option<OffsetDateTime>,
+}
+
+/// Bucket operations a site asks its peers to perform
+pub const SR_BUCKET_OP_MAKE_WITH_VERSIONING: &str = "make-with-versioning";
+pub const SR_BUCKET_OP_CONFIGURE_REPLICATION: &str = "configure-replication";
+pub const SR_BUCKET_OP_DELETE_BUCKET: &str = "delete-bucket";
+
+/// A bucket configuration file changed on a site, `data` is `None` when it was deleted
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SRBucketMeta {
+    pub bucket: String,
+    #[serde(rename = "configFile")]
+    pub config_file: String,
+    #[serde(default)]
+    pub data: Option<String>,
+}
+
+/// An IAM change to apply on the peers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SRIAMItem {
+    /// A canned policy was added or updated, `policy` is `None` when it was removed
+    Policy {
+        name: String,
+        #[serde(default)]
+        policy: Option<serde_json::Value>,
+    },
+    /// Policies attached to a user or group, comma separated
+    PolicyMapping {
+        #[serde(rename = "userOrGroup")]
+        user_or_group: String,
+        #[serde(rename = "isGroup")]
+        is_group: bool,
+        policy: String,
+    },
+    /// A user was added or updated, `secret_key` is `None` when it was removed
+    User {
+        #[serde(rename = "accessKey")]
+        access_key: String,
+        #[serde(rename = "secretKey", default)]
+        secret_key: Option<String>,
+        #[serde(default)]
+        status: String,
+        #[serde(default)]
+        policy: Option<String>,
+    },
+    GroupMembers {
+        group: String,
+        members: Vec<String>,
+        #[serde(rename = "isRemove")]
+        is_remove: bool,
+    },
+    GroupStatus {
+        group: String,
+        enabled: bool,
+    },
+    /// A service account was added or updated. `parentIsRoot` maps accounts of the root user
+    /// to the root user of the peer.
+    ServiceAccount {
+        #[serde(rename = "accessKey")]
+        access_key: String,
+        #[serde(rename = "secretKey")]
+        secret_key: String,
+        parent: String,
+        #[serde(rename = "parentIsRoot", default)]
+        parent_is_root: bool,
+        #[serde(default)]
+        groups: Vec<String>,
+        #[serde(rename = "sessionPolicy", default)]
+        session_policy: Option<serde_json::Value>,
+        #[serde(default)]
+        status: String,
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        description: String,
+        #[serde(with = "time::serde::rfc3339::option", default)]
+        expiration: Option<OffsetDateTime>,
+    },
+    ServiceAccountDelete {
+        #[serde(rename = "accessKey")]
+        access_key: String,
+    },
+}
+
+/// Buckets and IAM entities of one site, used to detect what is out of sync
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SRInfo {
+    pub name: String,
+    #[serde(rename = "deploymentID")]
+    pub deployment_id: String,
+    pub buckets: Vec<String>,
+    pub policies: Vec<String>,
+    pub users: Vec<String>,
+    pub groups: Vec<String>,
+    #[serde(rename = "serviceAccounts")]
+    pub service_accounts: Vec<String>,
+}
+
+/// Entities present on some sites but missing on others, by deployment id of the sites lacking them
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SRMismatches {
+    pub buckets: BTreeMap<String, Vec<String>>,
+    pub policies: BTreeMap<String, Vec<String>>,
+    pub users: BTreeMap<String, Vec<String>>,
+    pub groups: BTreeMap<String, Vec<String>>,
+    #[serde(rename = "serviceAccounts")]
+    pub service_accounts: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SRSiteStatus {
+    pub name: String,
+    #[serde(rename = "deploymentID")]
+    pub deployment_id: String,
+    pub endpoint: String,
+    pub online: bool,
+    #[serde(rename = "errorDetail", default, skip_serializing_if = "String::is_empty")]
+    pub err_detail: String,
+    #[serde(rename = "bucketCount")]
+    pub bucket_count: usize,
+    #[serde(rename = "policyCount")]
+    pub policy_count: usize,
+    #[serde(rename = "userCount")]
+    pub user_count: usize,
+    #[serde(rename = "groupCount")]
+    pub group_count: usize,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SRStatusInfo {
+    pub enabled: bool,
+    pub sites: Vec<SRSiteStatus>,
+    pub mismatches: SRMismatches,
+}
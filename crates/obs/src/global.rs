@@ -1,6 +1,6 @@
 
 
-use crate::{GlobalError, ObservabilityConfig, LoggingGuard, telemetry::init_telemetry};
+use crate::{GlobalError, ObservabilityConfig, LoggingGuard, init_metrics_recorder, telemetry::init_telemetry};
 use std::fmt;
 use std::sync::{Arc, Mutex};
 use tokio::sync::OnceCell;
@@ -42,6 +42,7 @@ impl fmt::Display for Success {
 /// ```
 pub fn init_obs(config: Option<&ObservabilityConfig>) -> Result<Success, GlobalError> {
     let config = config.cloned().unwrap_or_default();
+    init_metrics_recorder();
     let logging_guard = init_telemetry(&config)?;
     // Store in global storage automatically
     GLOBAL_GUARD.set(Arc::new(Mutex::new(logging_guard))).map_err(GlobalError::SetError)?;
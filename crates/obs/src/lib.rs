@@ -38,9 +38,11 @@
 mod config;
 mod error;
 mod global;
+mod prometheus;
 mod telemetry;
 
 pub use config::{ObservabilityConfig, is_production_environment};
 pub use error::*;
 pub use global::*;
+pub use prometheus::{init_metrics_recorder, render_metrics};
 pub use telemetry::LoggingGuard;
@@ -0,0 +1,43 @@
+
+
+//! Process-wide `metrics` recorder rendered in the Prometheus text format.
+//!
+//! The `counter!`, `gauge!` and `histogram!` macros used across the code base
+//! record into this recorder once [`init_metrics_recorder`] has run; the admin
+//! Prometheus endpoint appends [`render_metrics`] to its own output.
+
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::sync::OnceLock;
+use tracing::warn;
+
+static PROMETHEUS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Install the Prometheus recorder as the global `metrics` recorder.
+///
+/// Calling it more than once is harmless; only the first call installs the recorder.
+pub fn init_metrics_recorder() {
+    if PROMETHEUS_HANDLE.get().is_some() {
+        return;
+    }
+
+    match PrometheusBuilder::new().install_recorder() {
+        Ok(handle) => {
+            let _ = PROMETHEUS_HANDLE.set(handle);
+        }
+        Err(e) => warn!("failed to install prometheus metrics recorder: {}", e),
+    }
+}
+
+/// Render every metric recorded through the `metrics` macros.
+///
+/// Returns an empty string when the recorder is not installed.
+pub fn render_metrics() -> String {
+    match PROMETHEUS_HANDLE.get() {
+        Some(handle) => {
+            // Scrapes are periodic, so they double as the upkeep tick for histograms
+            handle.run_upkeep();
+            handle.render()
+        }
+        None => String::new(),
+    }
+}
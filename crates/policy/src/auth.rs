@@ -1,6 +1,8 @@
 
 
 mod credentials;
+pub mod ldap;
+pub mod openid;
 
 pub use credentials::Credentials;
 pub use credentials::*;
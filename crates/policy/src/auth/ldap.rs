@@ -0,0 +1,494 @@
+//! Identities from an LDAP directory. `AssumeRoleWithLDAPIdentity` finds the DN of a user with the
+//! lookup account and binds as that DN with the password it is given. The policies of the temporary
+//! credentials are then the ones mapped to the DN of the user and to the DNs of the groups it belongs to.
+
+use crate::error::{Error, Result};
+use ldap3::{Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry, ldap_escape};
+use nebulafx_config::{ENV_PREFIX, EnableState, ldap::*};
+use std::env;
+use std::time::Duration;
+use tracing::info;
+
+/// Claims of the credentials issued for a directory user, they back the `ldap:user`, `ldap:username`
+/// and `ldap:groups` condition keys
+pub const LDAP_USER_CLAIM: &str = "ldapUser";
+pub const LDAP_USERNAME_CLAIM: &str = "ldapUsername";
+pub const LDAP_GROUPS_CLAIM: &str = "ldapGroups";
+
+const LDAP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Result code of a search whose base does not exist
+const LDAP_NO_SUCH_OBJECT: u32 = 32;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LdapConfig {
+    /// `host:port`, or a full `ldap://` or `ldaps://` url
+    pub server_addr: String,
+    pub lookup_bind_dn: String,
+    pub lookup_bind_password: String,
+    pub user_dn_search_base_dn: String,
+    /// `%s` is replaced with the username
+    pub user_dn_search_filter: String,
+    pub group_search_base_dn: String,
+    /// `%s` is replaced with the username and `%d` with the DN of the user
+    pub group_search_filter: String,
+    /// Connect without TLS
+    pub server_insecure: bool,
+    pub server_starttls: bool,
+    pub tls_skip_verify: bool,
+    /// Interval of the check of the users holding temporary credentials against the directory
+    pub sync_interval: Duration,
+}
+
+/// A user authenticated by the directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LdapUser {
+    pub dn: String,
+    pub username: String,
+    pub groups: Vec<String>,
+}
+
+/// Whether a name is a DN such as `uid=alice,ou=people,dc=example,dc=com`, the admin API maps policies to
+/// the DNs of directory users and groups
+pub fn is_dn(name: &str) -> bool {
+    name.split(',').all(|rdn| {
+        rdn.split_once('=')
+            .is_some_and(|(attr, value)| !attr.trim().is_empty() && !value.trim().is_empty())
+    })
+}
+
+/// Form of a DN the policy mappings are stored under, directories compare DNs without case and spaces
+/// around the RDNs
+pub fn normalize_dn(dn: &str) -> String {
+    dn.split(',').map(str::trim).collect::<Vec<_>>().join(",").to_lowercase()
+}
+
+/// Reads the directory settings from the `NEUBULAFX_IDENTITY_LDAP_*` variables
+pub fn lookup_config() -> Result<Option<LdapConfig>> {
+    let conf = parse_config(env::vars())?;
+    if conf.is_none() {
+        info!("LDAP is not enabled.");
+    }
+    Ok(conf)
+}
+
+/// LDAP is enabled when a server address is set
+pub fn parse_config(vars: impl IntoIterator<Item = (String, String)>) -> Result<Option<LdapConfig>> {
+    let prefix = format!("{ENV_PREFIX}{IDENTITY_LDAP_SUB_SYS}").to_uppercase();
+
+    let mut conf = LdapConfig {
+        sync_interval: Duration::from_secs(DEFAULT_IDENTITY_LDAP_SYNC_INTERVAL),
+        ..Default::default()
+    };
+    for (key, value) in vars {
+        if !key.starts_with(&prefix) {
+            continue;
+        }
+
+        let value = value.trim().to_string();
+        match key.as_str() {
+            ENV_IDENTITY_LDAP_SERVER_ADDR => conf.server_addr = value,
+            ENV_IDENTITY_LDAP_LOOKUP_BIND_DN => conf.lookup_bind_dn = value,
+            ENV_IDENTITY_LDAP_LOOKUP_BIND_PASSWORD => conf.lookup_bind_password = value,
+            ENV_IDENTITY_LDAP_USER_DN_SEARCH_BASE_DN => conf.user_dn_search_base_dn = value,
+            ENV_IDENTITY_LDAP_USER_DN_SEARCH_FILTER => conf.user_dn_search_filter = value,
+            ENV_IDENTITY_LDAP_GROUP_SEARCH_BASE_DN => conf.group_search_base_dn = value,
+            ENV_IDENTITY_LDAP_GROUP_SEARCH_FILTER => conf.group_search_filter = value,
+            ENV_IDENTITY_LDAP_SERVER_INSECURE => conf.server_insecure = parse_bool(&key, &value)?,
+            ENV_IDENTITY_LDAP_SERVER_STARTTLS => conf.server_starttls = parse_bool(&key, &value)?,
+            ENV_IDENTITY_LDAP_TLS_SKIP_VERIFY => conf.tls_skip_verify = parse_bool(&key, &value)?,
+            ENV_IDENTITY_LDAP_SYNC_INTERVAL => {
+                let secs = value
+                    .parse::<u64>()
+                    .map_err(|_| Error::other(format!("Invalid value of {key}: {value}")))?;
+                conf.sync_interval = Duration::from_secs(secs);
+            }
+            _ => return Err(Error::other(format!("Invalid env var: {key}"))),
+        }
+    }
+
+    if conf.server_addr.is_empty() {
+        return Ok(None);
+    }
+    conf.validate()?;
+    Ok(Some(conf))
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    value
+        .parse::<EnableState>()
+        .map(EnableState::is_enabled)
+        .map_err(|_| Error::other(format!("Invalid value of {key}: {value}")))
+}
+
+impl LdapConfig {
+    fn validate(&self) -> Result<()> {
+        if self.lookup_bind_dn.is_empty() {
+            return Err(Error::other("ldap: missing lookup bind DN"));
+        }
+        if self.user_dn_search_base_dn.is_empty() || !self.user_dn_search_filter.contains("%s") {
+            return Err(Error::other("ldap: a user DN search base DN and a filter with %s are required"));
+        }
+        if self.group_search_base_dn.is_empty() != self.group_search_filter.is_empty() {
+            return Err(Error::other("ldap: the group search needs both a base DN and a filter"));
+        }
+        if self.server_insecure && self.server_starttls {
+            return Err(Error::other("ldap: an insecure connection cannot use StartTLS"));
+        }
+        if self.sync_interval.is_zero() {
+            return Err(Error::other("ldap: the sync interval must be positive"));
+        }
+        Ok(())
+    }
+
+    fn url(&self) -> String {
+        if self.server_addr.contains("://") {
+            return self.server_addr.clone();
+        }
+        let scheme = if self.server_insecure || self.server_starttls {
+            "ldap"
+        } else {
+            "ldaps"
+        };
+        format!("{scheme}://{}", self.server_addr)
+    }
+
+    /// Opens a connection to the directory bound as the lookup account
+    pub async fn connect(&self) -> Result<LdapConnection> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(LDAP_TIMEOUT)
+            .set_starttls(self.server_starttls)
+            .set_no_tls_verify(self.tls_skip_verify);
+        let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &self.url())
+            .await
+            .map_err(|e| Error::other(format!("ldap: connect to {} failed: {}", self.server_addr, e)))?;
+        ldap3::drive!(conn);
+        ldap.with_timeout(LDAP_TIMEOUT);
+
+        let mut conn = LdapConnection { ldap };
+        conn.simple_bind(&self.lookup_bind_dn, &self.lookup_bind_password).await?;
+        Ok(conn)
+    }
+
+    /// Checks the password of a user and returns its DN and groups
+    pub async fn authenticate(&self, username: &str, password: &str) -> Result<LdapUser> {
+        let mut conn = self.connect().await?;
+        let user = authenticate(self, &mut conn, username, password).await;
+        conn.close().await;
+        user
+    }
+
+    /// Whether a user DN is still in the directory, on a connection from `connect`
+    pub async fn user_exists(&self, conn: &mut LdapConnection, dn: &str) -> Result<bool> {
+        user_exists(conn, dn).await
+    }
+}
+
+/// Operations of the directory used here, on a connection to the server or on a fixed set of entries in the tests
+trait Directory {
+    async fn simple_bind(&mut self, dn: &str, password: &str) -> Result<()>;
+
+    /// DNs of the entries under `base` matching `filter`
+    async fn search(&mut self, base: &str, scope: Scope, filter: &str) -> Result<Vec<String>>;
+}
+
+pub struct LdapConnection {
+    ldap: Ldap,
+}
+
+impl LdapConnection {
+    pub async fn close(mut self) {
+        let _ = self.ldap.unbind().await;
+    }
+}
+
+impl Directory for LdapConnection {
+    async fn simple_bind(&mut self, dn: &str, password: &str) -> Result<()> {
+        self.ldap
+            .simple_bind(dn, password)
+            .await
+            .and_then(|res| res.success())
+            .map_err(|e| Error::other(format!("ldap: bind as {dn} failed: {e}")))?;
+        Ok(())
+    }
+
+    async fn search(&mut self, base: &str, scope: Scope, filter: &str) -> Result<Vec<String>> {
+        let res = self
+            .ldap
+            .search(base, scope, filter, vec!["dn"])
+            .await
+            .map_err(|e| Error::other(format!("ldap: search {base} failed: {e}")))?;
+        if res.1.rc == LDAP_NO_SUCH_OBJECT {
+            return Ok(Vec::new());
+        }
+        let (entries, _) = res
+            .success()
+            .map_err(|e| Error::other(format!("ldap: search {base} failed: {e}")))?;
+        Ok(entries.into_iter().map(|entry| SearchEntry::construct(entry).dn).collect())
+    }
+}
+
+async fn authenticate<D: Directory>(conf: &LdapConfig, dir: &mut D, username: &str, password: &str) -> Result<LdapUser> {
+    // A simple bind with an empty password is an anonymous bind and always succeeds
+    if username.is_empty() || password.is_empty() {
+        return Err(Error::other("ldap: username and password are required"));
+    }
+
+    dir.simple_bind(&conf.lookup_bind_dn, &conf.lookup_bind_password).await?;
+
+    let filter = conf.user_dn_search_filter.replace("%s", &ldap_escape(username));
+    let mut dns = dir.search(&conf.user_dn_search_base_dn, Scope::Subtree, &filter).await?;
+    let dn = match (dns.pop(), dns.is_empty()) {
+        (Some(dn), true) => dn,
+        (Some(_), false) => return Err(Error::other(format!("ldap: several users match {username}"))),
+        (None, _) => return Err(Error::other(format!("ldap: user {username} not found"))),
+    };
+
+    dir.simple_bind(&dn, password).await?;
+
+    let mut groups = Vec::new();
+    if !conf.group_search_filter.is_empty() {
+        // Groups are looked up with the lookup account, the user may not be allowed to read them
+        dir.simple_bind(&conf.lookup_bind_dn, &conf.lookup_bind_password).await?;
+        let filter = conf
+            .group_search_filter
+            .replace("%s", &ldap_escape(username))
+            .replace("%d", &ldap_escape(dn.as_str()));
+        groups = dir.search(&conf.group_search_base_dn, Scope::Subtree, &filter).await?;
+        groups.sort();
+    }
+
+    Ok(LdapUser {
+        dn,
+        username: username.to_string(),
+        groups,
+    })
+}
+
+async fn user_exists<D: Directory>(dir: &mut D, dn: &str) -> Result<bool> {
+    Ok(!dir.search(dn, Scope::Base, "(objectClass=*)").await?.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Entries with their attributes and passwords, searched with equality, `&` and `|` filters
+    #[derive(Default)]
+    struct TestDirectory {
+        entries: Vec<(String, HashMap<String, Vec<String>>)>,
+        passwords: HashMap<String, String>,
+        bound: Option<String>,
+    }
+
+    impl TestDirectory {
+        fn add(&mut self, dn: &str, attrs: &[(&str, &str)]) {
+            let mut map: HashMap<String, Vec<String>> = HashMap::new();
+            for (k, v) in attrs {
+                map.entry(k.to_lowercase()).or_default().push(v.to_string());
+            }
+            self.entries.push((dn.to_string(), map));
+        }
+    }
+
+    fn split_filters(s: &str) -> Vec<&str> {
+        let (mut depth, mut start, mut parts) = (0, 0, Vec::new());
+        for (i, c) in s.char_indices() {
+            match c {
+                '(' if depth == 0 => {
+                    start = i;
+                    depth += 1;
+                }
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        parts.push(&s[start..=i]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        parts
+    }
+
+    fn matches(filter: &str, attrs: &HashMap<String, Vec<String>>) -> bool {
+        let inner = &filter[1..filter.len() - 1];
+        if let Some(rest) = inner.strip_prefix('&') {
+            return split_filters(rest).iter().all(|f| matches(f, attrs));
+        }
+        if let Some(rest) = inner.strip_prefix('|') {
+            return split_filters(rest).iter().any(|f| matches(f, attrs));
+        }
+        let (key, value) = inner.split_once('=').unwrap();
+        attrs
+            .get(&key.to_lowercase())
+            .is_some_and(|values| value == "*" || values.iter().any(|v| v.eq_ignore_ascii_case(value)))
+    }
+
+    impl Directory for TestDirectory {
+        async fn simple_bind(&mut self, dn: &str, password: &str) -> Result<()> {
+            if self.passwords.get(dn).is_some_and(|p| p == password) {
+                self.bound = Some(dn.to_string());
+                Ok(())
+            } else {
+                self.bound = None;
+                Err(Error::other(format!("invalid credentials for {dn}")))
+            }
+        }
+
+        async fn search(&mut self, base: &str, scope: Scope, filter: &str) -> Result<Vec<String>> {
+            if self.bound.is_none() {
+                return Err(Error::other("not bound"));
+            }
+            Ok(self
+                .entries
+                .iter()
+                .filter(|(dn, _)| match scope {
+                    Scope::Base => dn.eq_ignore_ascii_case(base),
+                    _ => dn.to_lowercase().ends_with(&base.to_lowercase()),
+                })
+                .filter(|(_, attrs)| matches(filter, attrs))
+                .map(|(dn, _)| dn.clone())
+                .collect())
+        }
+    }
+
+    fn test_config() -> LdapConfig {
+        parse_config([
+            (ENV_IDENTITY_LDAP_SERVER_ADDR.to_string(), "ldap.example.com:636".to_string()),
+            (ENV_IDENTITY_LDAP_LOOKUP_BIND_DN.to_string(), "cn=admin,dc=example,dc=com".to_string()),
+            (ENV_IDENTITY_LDAP_LOOKUP_BIND_PASSWORD.to_string(), "admin-secret".to_string()),
+            (
+                ENV_IDENTITY_LDAP_USER_DN_SEARCH_BASE_DN.to_string(),
+                "ou=people,dc=example,dc=com".to_string(),
+            ),
+            (ENV_IDENTITY_LDAP_USER_DN_SEARCH_FILTER.to_string(), "(uid=%s)".to_string()),
+            (
+                ENV_IDENTITY_LDAP_GROUP_SEARCH_BASE_DN.to_string(),
+                "ou=groups,dc=example,dc=com".to_string(),
+            ),
+            (
+                ENV_IDENTITY_LDAP_GROUP_SEARCH_FILTER.to_string(),
+                "(&(objectClass=groupOfNames)(member=%d))".to_string(),
+            ),
+        ])
+        .unwrap()
+        .unwrap()
+    }
+
+    fn test_directory() -> TestDirectory {
+        let mut dir = TestDirectory::default();
+        dir.add("cn=admin,dc=example,dc=com", &[("objectClass", "person"), ("cn", "admin")]);
+        dir.add(
+            "uid=alice,ou=people,dc=example,dc=com",
+            &[("objectClass", "inetOrgPerson"), ("uid", "alice")],
+        );
+        dir.add("uid=bob,ou=people,dc=example,dc=com", &[("objectClass", "inetOrgPerson"), ("uid", "bob")]);
+        dir.add(
+            "cn=devs,ou=groups,dc=example,dc=com",
+            &[
+                ("objectClass", "groupOfNames"),
+                ("member", "uid=alice,ou=people,dc=example,dc=com"),
+            ],
+        );
+        dir.add(
+            "cn=ops,ou=groups,dc=example,dc=com",
+            &[
+                ("objectClass", "groupOfNames"),
+                ("member", "uid=alice,ou=people,dc=example,dc=com"),
+                ("member", "uid=bob,ou=people,dc=example,dc=com"),
+            ],
+        );
+        dir.passwords
+            .insert("cn=admin,dc=example,dc=com".to_string(), "admin-secret".to_string());
+        dir.passwords
+            .insert("uid=alice,ou=people,dc=example,dc=com".to_string(), "alice-secret".to_string());
+        dir.passwords
+            .insert("uid=bob,ou=people,dc=example,dc=com".to_string(), "bob-secret".to_string());
+        dir
+    }
+
+    #[test]
+    fn test_parse_config() {
+        assert_eq!(parse_config(Vec::new()).unwrap(), None);
+
+        let conf = test_config();
+        assert_eq!(conf.url(), "ldaps://ldap.example.com:636");
+        assert_eq!(conf.sync_interval, Duration::from_secs(DEFAULT_IDENTITY_LDAP_SYNC_INTERVAL));
+
+        let vars = |extra: &[(&str, &str)]| {
+            let mut vars = vec![
+                (ENV_IDENTITY_LDAP_SERVER_ADDR.to_string(), "ldap.example.com:389".to_string()),
+                (ENV_IDENTITY_LDAP_LOOKUP_BIND_DN.to_string(), "cn=admin,dc=example,dc=com".to_string()),
+                (ENV_IDENTITY_LDAP_USER_DN_SEARCH_BASE_DN.to_string(), "dc=example,dc=com".to_string()),
+                (ENV_IDENTITY_LDAP_USER_DN_SEARCH_FILTER.to_string(), "(uid=%s)".to_string()),
+            ];
+            vars.extend(extra.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+            vars
+        };
+
+        let conf = parse_config(vars(&[
+            (ENV_IDENTITY_LDAP_SERVER_STARTTLS, "on"),
+            (ENV_IDENTITY_LDAP_SYNC_INTERVAL, "60"),
+        ]))
+        .unwrap()
+        .unwrap();
+        assert!(conf.server_starttls);
+        assert_eq!(conf.url(), "ldap://ldap.example.com:389");
+        assert_eq!(conf.sync_interval, Duration::from_secs(60));
+
+        assert!(parse_config(vars(&[(ENV_IDENTITY_LDAP_SERVER_INSECURE, "maybe")])).is_err());
+        assert!(parse_config(vars(&[(ENV_IDENTITY_LDAP_GROUP_SEARCH_FILTER, "(member=%d)")])).is_err());
+        assert!(parse_config(vars(&[(ENV_IDENTITY_LDAP_USER_DN_SEARCH_FILTER, "(uid=alice)")])).is_err());
+        assert!(parse_config(vars(&[("NEUBULAFX_IDENTITY_LDAP_UNKNOWN", "x")])).is_err());
+    }
+
+    #[test]
+    fn test_dn() {
+        assert!(is_dn("uid=alice,ou=people,dc=example,dc=com"));
+        assert!(is_dn("cn=devs"));
+        assert!(!is_dn("alice"));
+        assert!(!is_dn("openid:default:alice"));
+        assert!(!is_dn("uid=alice,"));
+        assert_eq!(
+            normalize_dn("UID=Alice, ou=People,dc=example, dc=com"),
+            "uid=alice,ou=people,dc=example,dc=com"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_authenticate() {
+        let conf = test_config();
+        let mut dir = test_directory();
+
+        let user = authenticate(&conf, &mut dir, "alice", "alice-secret").await.unwrap();
+        assert_eq!(user.dn, "uid=alice,ou=people,dc=example,dc=com");
+        assert_eq!(
+            user.groups,
+            vec![
+                "cn=devs,ou=groups,dc=example,dc=com".to_string(),
+                "cn=ops,ou=groups,dc=example,dc=com".to_string()
+            ]
+        );
+
+        let user = authenticate(&conf, &mut dir, "bob", "bob-secret").await.unwrap();
+        assert_eq!(user.groups, vec!["cn=ops,ou=groups,dc=example,dc=com".to_string()]);
+
+        assert!(authenticate(&conf, &mut dir, "alice", "wrong").await.is_err());
+        assert!(authenticate(&conf, &mut dir, "alice", "").await.is_err());
+        assert!(authenticate(&conf, &mut dir, "carol", "carol-secret").await.is_err());
+        // The username is escaped, a wildcard does not match every user
+        assert!(authenticate(&conf, &mut dir, "*", "alice-secret").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_user_exists() {
+        let mut dir = test_directory();
+        dir.simple_bind("cn=admin,dc=example,dc=com", "admin-secret").await.unwrap();
+
+        assert!(user_exists(&mut dir, "uid=alice,ou=people,dc=example,dc=com").await.unwrap());
+        assert!(!user_exists(&mut dir, "uid=carol,ou=people,dc=example,dc=com").await.unwrap());
+    }
+}
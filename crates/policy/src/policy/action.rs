@@ -493,6 +493,24 @@ pub enum StsAction {}
 #[derive(Serialize, Deserialize, Hash, PartialEq, Eq, Clone, EnumString, IntoStaticStr, Debug, Copy)]
 #[serde(try_from = "&str", into = "&str")]
 pub enum KmsAction {
+    #[strum(serialize = "kms:Status")]
+    StatusAction,
+    #[strum(serialize = "kms:CreateKey")]
+    CreateKeyAction,
+    #[strum(serialize = "kms:ListKeys")]
+    ListKeysAction,
+    #[strum(serialize = "kms:DescribeKey")]
+    DescribeKeyAction,
+    #[strum(serialize = "kms:RotateKey")]
+    RotateKeyAction,
+    #[strum(serialize = "kms:EnableKey")]
+    EnableKeyAction,
+    #[strum(serialize = "kms:DisableKey")]
+    DisableKeyAction,
+    #[strum(serialize = "kms:DeleteKey")]
+    DeleteKeyAction,
+    #[strum(serialize = "kms:CancelKeyDeletion")]
+    CancelKeyDeletionAction,
     #[strum(serialize = "kms:*")]
     AllActions,
 }
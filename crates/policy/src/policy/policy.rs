@@ -127,6 +127,12 @@ impl Policy {
         self.statements.is_empty()
     }
 
+    /// Whether a condition of the policy may test a key of `key_name`, e.g. `s3:ExistingObjectTag`.
+    /// Lets callers skip loading condition values no statement looks at.
+    pub fn mentions_condition_key(&self, key_name: &str) -> bool {
+        mentions_condition_key(self, key_name)
+    }
+
     pub fn validate(&self) -> Result<()> {
         self.is_valid()
     }
@@ -154,9 +160,30 @@ impl Validator for Policy {
     }
 }
 
+/// Outcome of evaluating one policy for a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyDecision {
+    Allow,
+    /// A Deny statement matched
+    Deny,
+    /// No statement applies
+    NotApplicable,
+}
+
+impl PolicyDecision {
+    /// Combines the decisions of the identity and resource policies of a request the way AWS does: an
+    /// explicit Deny in any of them wins, otherwise an Allow in any of them grants, and nothing
+    /// applying denies.
+    pub fn combine(decisions: &[PolicyDecision]) -> bool {
+        !decisions.contains(&PolicyDecision::Deny) && decisions.contains(&PolicyDecision::Allow)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BucketPolicyArgs<'a> {
     pub account: &'a str,
+    /// User a service account or STS session acts for, empty for users
+    pub parent_user: &'a str,
     pub groups: &'a Option<Vec<String>>,
     pub action: Action,
     pub bucket: &'a str,
@@ -177,23 +204,41 @@ pub struct BucketPolicy {
 
 impl BucketPolicy {
     pub fn is_allowed(&self, args: &BucketPolicyArgs) -> bool {
+        match self.evaluate(args) {
+            PolicyDecision::Allow => true,
+            PolicyDecision::Deny => false,
+            PolicyDecision::NotApplicable => args.is_owner,
+        }
+    }
+
+    pub fn evaluate(&self, args: &BucketPolicyArgs) -> PolicyDecision {
         for statement in self.statements.iter().filter(|s| matches!(s.effect, Effect::Deny)) {
             if !statement.is_allowed(args) {
-                return false;
+                return PolicyDecision::Deny;
             }
         }
 
-        if args.is_owner {
-            return true;
-        }
-
         for statement in self.statements.iter().filter(|s| matches!(s.effect, Effect::Allow)) {
             if statement.is_allowed(args) {
-                return true;
+                return PolicyDecision::Allow;
             }
         }
 
-        false
+        PolicyDecision::NotApplicable
+    }
+
+    /// Whether a condition of the policy may test a key of `key_name`, see [`Policy::mentions_condition_key`]
+    pub fn mentions_condition_key(&self, key_name: &str) -> bool {
+        mentions_condition_key(self, key_name)
+    }
+}
+
+/// Searches the serialized policy, condition keys are matched case-insensitively and a policy that
+/// can not be serialized is assumed to mention the key
+fn mentions_condition_key<T: Serialize>(policy: &T, key_name: &str) -> bool {
+    match serde_json::to_string(policy) {
+        Ok(data) => data.to_lowercase().contains(&key_name.to_lowercase()),
+        Err(_) => true,
     }
 }
 
@@ -451,6 +496,7 @@ pub mod default {
 mod test {
     use super::*;
     use crate::error::Result;
+    use crate::policy::action::S3Action;
 
     #[tokio::test]
     async fn test_parse_policy() -> Result<()> {
@@ -513,4 +559,126 @@ mod test {
         // assert_eq!(p, p2);
         Ok(())
     }
+
+    #[test]
+    fn test_mentions_condition_key() -> Result<()> {
+        let tagged = Policy::parse_config(
+            br#"{"Version": "2012-10-17", "Statement": [{"Effect": "Allow", "Action": ["s3:GetObject"],
+                "Resource": ["arn:aws:s3:::dada/*"],
+                "Condition": {"StringEquals": {"s3:ExistingObjectTag/security": "public"}}}]}"#,
+        )?;
+        assert!(tagged.mentions_condition_key("s3:existingobjecttag"));
+        assert!(!tagged.mentions_condition_key("s3:RequestObjectTag"));
+
+        let plain = Policy::parse_config(
+            br#"{"Version": "2012-10-17", "Statement": [{"Effect": "Allow", "Action": ["s3:GetObject"],
+                "Resource": ["arn:aws:s3:::dada/*"]}]}"#,
+        )?;
+        assert!(!plain.mentions_condition_key("s3:ExistingObjectTag"));
+
+        let bucket: BucketPolicy = serde_json::from_str(
+            r#"{"Version": "2012-10-17", "Statement": [{"Effect": "Deny", "Principal": {"AWS": ["*"]},
+                "Action": ["s3:GetObject"], "Resource": ["arn:aws:s3:::dada/*"],
+                "Condition": {"StringEquals": {"s3:ExistingObjectTag/security": "private"}}}]}"#,
+        )?;
+        assert!(bucket.mentions_condition_key("s3:ExistingObjectTag"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_decision_combine() {
+        use PolicyDecision::*;
+
+        let cases = [
+            (Allow, Allow, true),
+            (Allow, Deny, false),
+            (Allow, NotApplicable, true),
+            (Deny, Allow, false),
+            (Deny, Deny, false),
+            (Deny, NotApplicable, false),
+            (NotApplicable, Allow, true),
+            (NotApplicable, Deny, false),
+            (NotApplicable, NotApplicable, false),
+        ];
+        for (iam, bucket, allowed) in cases {
+            assert_eq!(PolicyDecision::combine(&[iam, bucket]), allowed, "iam {iam:?}, bucket {bucket:?}");
+        }
+    }
+
+    #[test]
+    fn test_bucket_policy_evaluate() -> Result<()> {
+        let data = r#"
+{
+  "Version": "2012-10-17",
+  "Statement": [
+    {
+      "Effect": "Allow",
+      "Principal": {"AWS": ["alice", "arn:aws:iam::*:group/readers"]},
+      "Action": ["s3:GetObject"],
+      "Resource": ["arn:aws:s3:::shared/*"]
+    },
+    {
+      "Effect": "Deny",
+      "Principal": {"AWS": ["*"]},
+      "Action": ["s3:GetObject"],
+      "Resource": ["arn:aws:s3:::shared/private/*"]
+    }
+  ]
+}
+"#;
+        let policy: BucketPolicy = serde_json::from_str(data)?;
+        policy.is_valid()?;
+
+        fn args<'a>(
+            account: &'a str,
+            parent_user: &'a str,
+            groups: &'a Option<Vec<String>>,
+            object: &'a str,
+            is_owner: bool,
+        ) -> BucketPolicyArgs<'a> {
+            static CONDITIONS: std::sync::LazyLock<HashMap<String, Vec<String>>> = std::sync::LazyLock::new(HashMap::new);
+            BucketPolicyArgs {
+                account,
+                parent_user,
+                groups,
+                action: Action::S3Action(S3Action::GetObjectAction),
+                bucket: "shared",
+                conditions: &CONDITIONS,
+                is_owner,
+                object,
+            }
+        }
+
+        let no_groups = None;
+        let readers = Some(vec!["readers".to_string()]);
+
+        // users, service accounts and STS sessions of alice, and group members
+        assert_eq!(policy.evaluate(&args("alice", "", &no_groups, "a.txt", false)), PolicyDecision::Allow);
+        assert_eq!(
+            policy.evaluate(&args("SVCKEY", "alice", &no_groups, "a.txt", false)),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            policy.evaluate(&args("STSKEY", "alice", &no_groups, "a.txt", false)),
+            PolicyDecision::Allow
+        );
+        assert_eq!(policy.evaluate(&args("bob", "", &readers, "a.txt", false)), PolicyDecision::Allow);
+        assert_eq!(
+            policy.evaluate(&args("bob", "", &no_groups, "a.txt", false)),
+            PolicyDecision::NotApplicable
+        );
+        assert_eq!(policy.evaluate(&args("", "", &no_groups, "a.txt", false)), PolicyDecision::NotApplicable);
+
+        // an explicit Deny applies to everyone, the owner included
+        assert_eq!(
+            policy.evaluate(&args("alice", "", &no_groups, "private/a.txt", false)),
+            PolicyDecision::Deny
+        );
+        assert_eq!(policy.evaluate(&args("", "", &no_groups, "private/a.txt", false)), PolicyDecision::Deny);
+        assert!(!policy.is_allowed(&args("owner", "", &no_groups, "private/a.txt", true)));
+        assert!(policy.is_allowed(&args("owner", "", &no_groups, "a.txt", true)));
+        assert!(!policy.is_allowed(&args("bob", "", &no_groups, "a.txt", false)));
+
+        Ok(())
+    }
 }
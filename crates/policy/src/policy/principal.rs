@@ -12,6 +12,8 @@ pub struct Principal {
     aws: HashSet<String>,
 }
 
+const IAM_ARN_PREFIX: &str = "arn:aws:iam::";
+
 impl Principal {
     pub fn is_match(&self, parincipal: &str) -> bool {
         for pattern in self.aws.iter() {
@@ -21,6 +23,43 @@ impl Principal {
         }
         false
     }
+
+    /// Whether the principal names the requester. `*` matches everyone, anonymous requests included,
+    /// other entries only authenticated requests:
+    /// - `<user>` or `arn:aws:iam::<account>:user/<user>` matches the access key, or the user a service
+    ///   account or STS session belongs to
+    /// - `arn:aws:iam::<account>:group/<group>` matches the members of the group
+    /// - `arn:aws:iam::<account>:root` matches every authenticated requester
+    pub fn is_match_identity(&self, account: &str, parent_user: &str, groups: &[String]) -> bool {
+        let user_matches = |pattern: &str| {
+            wildcard::is_simple_match(pattern, account)
+                || (!parent_user.is_empty() && wildcard::is_simple_match(pattern, parent_user))
+        };
+
+        self.aws.iter().any(|pattern| {
+            if pattern == "*" {
+                return true;
+            }
+            if account.is_empty() {
+                return false;
+            }
+
+            let Some(arn) = pattern.strip_prefix(IAM_ARN_PREFIX) else {
+                return user_matches(pattern);
+            };
+            let resource = arn.split_once(':').map_or("", |(_, resource)| resource);
+            if resource == "root" {
+                return true;
+            }
+            if let Some(user) = resource.strip_prefix("user/") {
+                return user_matches(user);
+            }
+            if let Some(group) = resource.strip_prefix("group/") {
+                return groups.iter().any(|name| wildcard::is_simple_match(group, name));
+            }
+            false
+        })
+    }
 }
 
 impl Validator for Principal {
@@ -32,3 +71,53 @@ impl Validator for Principal {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(patterns: &[&str]) -> Principal {
+        Principal {
+            aws: patterns.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_principal_match_identity() {
+        let groups = vec!["dev".to_string()];
+
+        let everyone = principal(&["*"]);
+        assert!(everyone.is_match_identity("", "", &[]));
+        assert!(everyone.is_match_identity("alice", "", &[]));
+
+        // users, directly or as the parent of a service account or STS session
+        let alice = principal(&["alice"]);
+        assert!(alice.is_match_identity("alice", "", &[]));
+        assert!(alice.is_match_identity("SVCACCESSKEY", "alice", &[]));
+        assert!(alice.is_match_identity("STSACCESSKEY", "alice", &[]));
+        assert!(!alice.is_match_identity("bob", "", &[]));
+        assert!(!alice.is_match_identity("", "", &[]));
+
+        let alice_arn = principal(&["arn:aws:iam::*:user/alice"]);
+        assert!(alice_arn.is_match_identity("alice", "", &[]));
+        assert!(alice_arn.is_match_identity("SVCACCESSKEY", "alice", &[]));
+        assert!(!alice_arn.is_match_identity("bob", "", &groups));
+
+        let svc = principal(&["SVCACCESSKEY"]);
+        assert!(svc.is_match_identity("SVCACCESSKEY", "alice", &[]));
+        assert!(!svc.is_match_identity("alice", "", &[]));
+
+        let dev = principal(&["arn:aws:iam::*:group/dev"]);
+        assert!(dev.is_match_identity("bob", "", &groups));
+        assert!(!dev.is_match_identity("bob", "", &[]));
+
+        let account = principal(&["arn:aws:iam::123456789012:root"]);
+        assert!(account.is_match_identity("bob", "", &[]));
+        assert!(!account.is_match_identity("", "", &[]));
+
+        // Wildcards never extend to anonymous requests
+        let any_user = principal(&["arn:aws:iam::*:user/*"]);
+        assert!(any_user.is_match_identity("bob", "", &[]));
+        assert!(!any_user.is_match_identity("", "", &[]));
+    }
+}
@@ -145,7 +145,10 @@ pub struct BPStatement {
 impl BPStatement {
     pub fn is_allowed(&self, args: &BucketPolicyArgs) -> bool {
         let check = 'c: {
-            if !self.principal.is_match(args.account) {
+            if !self
+                .principal
+                .is_match_identity(args.account, args.parent_user, args.groups.as_deref().unwrap_or_default())
+            {
                 break 'c false;
             }
 
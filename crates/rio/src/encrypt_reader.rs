@@ -8,6 +8,7 @@ use aes_gcm::aead::Aead;
 use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
 use pin_project_lite::pin_project;
 use nebulafx_utils::{put_uvarint, put_uvarint_len};
+use std::collections::VecDeque;
 use std::io::Error;
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -178,6 +179,8 @@ pin_project! {
         current_nonce: [u8; 12], // Active nonce for the current encrypted segment
         multipart_mode: bool,
         current_part: usize,
+        // Nonces of the segments still to come, when they are not derived from the base nonce
+        part_nonces: Option<VecDeque<[u8; 12]>>,
         buffer: Vec<u8>,
         buffer_pos: usize,
         finished: bool,
@@ -203,6 +206,7 @@ where
             current_nonce: nonce,
             multipart_mode: false,
             current_part: 0,
+            part_nonces: None,
             buffer: Vec::new(),
             buffer_pos: 0,
             finished: false,
@@ -228,6 +232,7 @@ where
             current_nonce: initial_nonce,
             multipart_mode: true,
             current_part: first_part,
+            part_nonces: None,
             buffer: Vec::new(),
             buffer_pos: 0,
             finished: false,
@@ -239,6 +244,32 @@ where
             ciphertext_len: 0,
         }
     }
+
+    /// Decrypts segments read back to back, each sealed with the next nonce of `nonces`, for parts
+    /// whose numbers do not follow each other from 1
+    pub fn new_parts(inner: R, key: [u8; 32], nonces: Vec<[u8; 12]>) -> Self {
+        let mut nonces = VecDeque::from(nonces);
+        let first = nonces.pop_front();
+
+        Self {
+            inner,
+            key,
+            base_nonce: first.unwrap_or_default(),
+            current_nonce: first.unwrap_or_default(),
+            multipart_mode: true,
+            current_part: 1,
+            part_nonces: Some(nonces),
+            buffer: Vec::new(),
+            buffer_pos: 0,
+            finished: first.is_none(),
+            header_buf: [0u8; 8],
+            header_read: 0,
+            header_done: false,
+            ciphertext_buf: None,
+            ciphertext_read: 0,
+            ciphertext_len: 0,
+        }
+    }
 }
 
 impl<R> AsyncRead for DecryptReader<R>
@@ -314,7 +345,16 @@ where
                         "decrypt_reader: reached segment terminator, advancing to next part"
                     );
                     *this.current_part += 1;
-                    *this.current_nonce = derive_part_nonce(this.base_nonce, *this.current_part);
+                    *this.current_nonce = match this.part_nonces.as_mut() {
+                        Some(nonces) => match nonces.pop_front() {
+                            Some(nonce) => nonce,
+                            None => {
+                                *this.finished = true;
+                                *this.current_nonce
+                            }
+                        },
+                        None => derive_part_nonce(this.base_nonce, *this.current_part),
+                    };
                     this.ciphertext_buf.take();
                     *this.ciphertext_read = 0;
                     *this.ciphertext_len = 0;
@@ -586,4 +626,29 @@ mod tests {
 
         assert_eq!(decrypted, expected);
     }
+
+    #[tokio::test]
+    async fn test_decrypt_reader_parts_with_own_nonces() {
+        let mut key = [0u8; 32];
+        rand::rng().fill_bytes(&mut key);
+
+        let parts: Vec<(Vec<u8>, [u8; 12])> = vec![(vec![0x11; 64 * 1024], [1u8; 12]), (vec![0x22; 1000], [7u8; 12])];
+
+        let mut combined = Vec::new();
+        for (data, nonce) in &parts {
+            let reader = BufReader::new(Cursor::new(data.clone()));
+            let mut encrypt_reader = EncryptReader::new(WarpReader::new(reader), key, *nonce);
+            encrypt_reader.read_to_end(&mut combined).await.unwrap();
+        }
+        // Trailing bytes past the last part are not read
+        combined.extend_from_slice(b"garbage");
+
+        let nonces = parts.iter().map(|(_, nonce)| *nonce).collect();
+        let reader = BufReader::new(Cursor::new(combined));
+        let mut decrypt_reader = DecryptReader::new_parts(WarpReader::new(reader), key, nonces);
+        let mut decrypted = Vec::new();
+        decrypt_reader.read_to_end(&mut decrypted).await.unwrap();
+
+        assert_eq!(decrypted, [parts[0].0.as_slice(), parts[1].0.as_slice()].concat());
+    }
 }
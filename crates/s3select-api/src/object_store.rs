@@ -8,7 +8,7 @@ use futures::{Stream, StreamExt};
 use futures_core::stream::BoxStream;
 use http::HeaderMap;
 use object_store::{
-    Attributes, Error as o_Error, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    Attributes, Error as o_Error, GetOptions, GetRange, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
     PutMultipartOptions, PutOptions, PutPayload, PutResult, Result, path::Path,
 };
 use pin_project_lite::pin_project;
@@ -19,8 +19,10 @@ use nebulafx_ecstore::set_disk::DEFAULT_READ_BUFFER_SIZE;
 use nebulafx_ecstore::store::ECStore;
 use nebulafx_ecstore::store_api::ObjectIO;
 use nebulafx_ecstore::store_api::ObjectOptions;
+use nebulafx_ecstore::store_api::{GetObjectReader, HTTPRangeSpec};
+use nebulafx_zip::CompressionFormat;
 use s3s::S3Result;
-use s3s::dto::SelectObjectContentInput;
+use s3s::dto::{CompressionType, FileHeaderInfo, JSONType, ScanRange, SelectObjectContentInput};
 use s3s::s3_error;
 use std::ops::Range;
 use std::pin::Pin;
@@ -32,16 +34,24 @@ use tokio_util::io::ReaderStream;
 use tracing::info;
 use transform_stream::AsyncTryStream;
 
+use crate::query::ScanStats;
+
 #[derive(Debug)]
 pub struct EcObjectStore {
     input: Arc<SelectObjectContentInput>,
     need_convert: bool,
     delimiter: String,
+    stats: Arc<ScanStats>,
+    compression: Option<CompressionFormat>,
+    // Last byte of the record delimiter, used to align scan ranges to whole records
+    record_delimiter: u8,
+    // The first CSV line is a header that has to be kept when a scan range skips it
+    has_header: bool,
 
     store: Arc<ECStore>,
 }
 impl EcObjectStore {
-    pub fn new(input: Arc<SelectObjectContentInput>) -> S3Result<Self> {
+    pub fn new(input: Arc<SelectObjectContentInput>, stats: Arc<ScanStats>) -> S3Result<Self> {
         let Some(store) = new_object_layer_fn() else {
             return Err(s3_error!(InternalError, "ec store not inited"));
         };
@@ -60,13 +70,184 @@ impl EcObjectStore {
             (false, String::new())
         };
 
+        let csv = input.request.input_serialization.csv.as_ref();
+        let record_delimiter = csv
+            .and_then(|csv| csv.record_delimiter.as_ref())
+            .and_then(|d| d.as_bytes().last().copied())
+            .unwrap_or(b'\n');
+        let has_header = csv
+            .and_then(|csv| csv.file_header_info.as_ref())
+            .is_some_and(|info| info.as_str() == FileHeaderInfo::USE || info.as_str() == FileHeaderInfo::IGNORE);
+
         Ok(Self {
+            compression: input_compression(&input)?,
             input,
             need_convert,
             delimiter,
+            stats,
+            record_delimiter,
+            has_header,
             store,
         })
     }
+
+    async fn reader(&self, range: Option<HTTPRangeSpec>) -> Result<GetObjectReader> {
+        let opts = ObjectOptions::default();
+        let h = HeaderMap::new();
+        self.store
+            .get_object_reader(&self.input.bucket, &self.input.key, range, h, &opts)
+            .await
+            .map_err(|_| o_Error::NotFound {
+                path: format!("{}/{}", self.input.bucket, self.input.key),
+                source: "can not get object info".into(),
+            })
+    }
+
+    /// Field delimiter to convert to `DEFAULT_DELIMITER`, if the input uses a multi-byte one
+    fn convert_delimiter(&self) -> Option<&[u8]> {
+        self.need_convert.then_some(self.delimiter.as_bytes())
+    }
+
+    /// Content of `reader`, counted as scanned before it is decompressed
+    fn raw(&self, reader: GetObjectReader) -> CountingReader<Box<dyn AsyncRead + Unpin + Send + Sync>> {
+        CountingReader {
+            inner: reader.stream,
+            stats: self.stats.clone(),
+        }
+    }
+
+    /// First record of the object, prepended to a scan range that starts past the CSV header
+    async fn header_line(&self) -> Result<Bytes> {
+        let raw = self.raw(self.reader(None).await?);
+        let records = record_stream(raw, self.compression, usize::MAX, None, self.convert_delimiter())?;
+        first_record(records, self.record_delimiter).await
+    }
+}
+
+/// Records a query reads from `raw`: decompressed, cut to the scan range and with the field delimiter
+/// converted, in that order. Scan range offsets are offsets of the stored bytes, a multi-byte field
+/// delimiter would shift them if the filter ran on converted records.
+fn record_stream<R>(
+    raw: R,
+    compression: Option<CompressionFormat>,
+    content_length: usize,
+    filter: Option<ScanRangeFilter>,
+    delimiter: Option<&[u8]>,
+) -> Result<BoxStream<'static, Result<Bytes>>>
+where
+    R: AsyncRead + Send + Unpin + 'static,
+{
+    let decoded: Box<dyn AsyncRead + Send + Unpin> = match compression {
+        Some(format) => format.get_decoder(raw).map_err(|e| o_Error::Generic {
+            store: "EcObjectStore",
+            source: Box::new(e),
+        })?,
+        None => Box::new(raw),
+    };
+
+    let stream = bytes_stream(ReaderStream::with_capacity(decoded, DEFAULT_READ_BUFFER_SIZE), content_length);
+    let stream = match filter {
+        Some(filter) => scan_range_stream(stream, filter).boxed(),
+        None => stream.boxed(),
+    };
+    Ok(match delimiter {
+        Some(delimiter) => convert_stream(stream, DelimiterConverter::new(delimiter)).boxed(),
+        None => stream,
+    })
+}
+
+/// Bytes of `records` up to and including the first record delimiter
+async fn first_record(mut records: BoxStream<'static, Result<Bytes>>, record_delimiter: u8) -> Result<Bytes> {
+    let mut header = Vec::new();
+    while let Some(chunk) = records.next().await {
+        let chunk = chunk?;
+        if let Some(i) = chunk.iter().position(|b| *b == record_delimiter) {
+            header.extend_from_slice(&chunk[..=i]);
+            break;
+        }
+        header.extend_from_slice(&chunk);
+    }
+    Ok(Bytes::from(header))
+}
+
+/// Decoder for the input `CompressionType`, `None` for uncompressed input
+fn input_compression(input: &SelectObjectContentInput) -> S3Result<Option<CompressionFormat>> {
+    let Some(compression) = input.request.input_serialization.compression_type.as_ref() else {
+        return Ok(None);
+    };
+    match compression.as_str() {
+        CompressionType::NONE => Ok(None),
+        CompressionType::GZIP => Ok(Some(CompressionFormat::Gzip)),
+        CompressionType::BZIP2 => Ok(Some(CompressionFormat::Bzip2)),
+        other => Err(s3_error!(InvalidArgument, "unsupported CompressionType: {other}")),
+    }
+}
+
+/// Reject input serialization settings the object store can't honour
+pub fn validate_select_input(input: &SelectObjectContentInput) -> S3Result<()> {
+    let serialization = &input.request.input_serialization;
+    let compression = input_compression(input)?;
+    if compression.is_some() && serialization.parquet.is_some() {
+        return Err(s3_error!(InvalidArgument, "CompressionType is not supported for Parquet input"));
+    }
+
+    let Some(range) = input.request.scan_range.as_ref() else {
+        return Ok(());
+    };
+    if compression.is_some() {
+        return Err(s3_error!(InvalidArgument, "ScanRange is not supported for compressed input"));
+    }
+    let json_lines = serialization
+        .json
+        .as_ref()
+        .is_some_and(|json| json.type_.as_ref().is_some_and(|t| t.as_str() == JSONType::LINES));
+    if serialization.csv.is_none() && !json_lines {
+        return Err(s3_error!(InvalidArgument, "ScanRange is only supported for CSV and JSON LINES input"));
+    }
+    if range.start.is_some_and(|s| s < 0) || range.end.is_some_and(|e| e < 0) {
+        return Err(s3_error!(InvalidArgument, "ScanRange start and end must not be negative"));
+    }
+    if let (Some(start), Some(end)) = (range.start, range.end) {
+        if start > end {
+            return Err(s3_error!(InvalidArgument, "ScanRange start must not be after end"));
+        }
+    }
+    Ok(())
+}
+
+/// Byte offsets `[start, end)` covered by a scan range over an object of `size` bytes.
+///
+/// `End` is inclusive, and on its own it selects the last `End` bytes.
+pub fn scan_range_bounds(range: &ScanRange, size: u64) -> (u64, u64) {
+    let start = range.start.map(|s| s.max(0) as u64);
+    let end = range.end.map(|e| e.max(0) as u64);
+    let (start, end) = match (start, end) {
+        (Some(start), Some(end)) => (start, end.saturating_add(1)),
+        (Some(start), None) => (start, size),
+        (None, Some(suffix)) => (size.saturating_sub(suffix), size),
+        (None, None) => (0, size),
+    };
+    (start.min(size), end.min(size))
+}
+
+fn get_range_spec(range: &GetRange) -> HTTPRangeSpec {
+    match range {
+        GetRange::Bounded(r) => HTTPRangeSpec {
+            is_suffix_length: false,
+            start: r.start as i64,
+            end: r.end as i64 - 1,
+        },
+        GetRange::Offset(o) => HTTPRangeSpec {
+            is_suffix_length: false,
+            start: *o as i64,
+            end: -1,
+        },
+        GetRange::Suffix(n) => HTTPRangeSpec {
+            is_suffix_length: true,
+            start: *n as i64,
+            end: -1,
+        },
+    }
 }
 
 impl std::fmt::Display for EcObjectStore {
@@ -85,58 +266,96 @@ impl ObjectStore for EcObjectStore {
         unimplemented!()
     }
 
-    async fn get_opts(&self, location: &Path, _options: GetOptions) -> Result<GetResult> {
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
         info!("{:?}", location);
-        let opts = ObjectOptions::default();
-        let h = HeaderMap::new();
-        let reader = self
-            .store
-            .get_object_reader(&self.input.bucket, &self.input.key, None, h, &opts)
-            .await
-            .map_err(|_| o_Error::NotFound {
-                path: format!("{}/{}", self.input.bucket, self.input.key),
-                source: "can not get object info".into(),
-            })?;
+
+        if options.range.is_some() && self.compression.is_some() {
+            return Err(o_Error::NotSupported {
+                source: "range reads of compressed input".into(),
+            });
+        }
+
+        // Scan ranges apply to whole-object reads, DataFusion's own ranged reads are served as asked
+        let scan_range = match (&options.range, &self.input.request.scan_range) {
+            (None, Some(range)) => {
+                let meta = self.head(location).await?;
+                Some(scan_range_bounds(range, meta.size)).filter(|&(start, end)| (start, end) != (0, meta.size))
+            }
+            _ => None,
+        };
+
+        let range_spec = match (&options.range, scan_range) {
+            (Some(range), _) => Some(get_range_spec(range)),
+            // Start one byte early so a record beginning exactly at `start` is recognized
+            (None, Some((start, _))) if start > 0 => Some(HTTPRangeSpec {
+                is_suffix_length: false,
+                start: start as i64 - 1,
+                end: -1,
+            }),
+            _ => None,
+        };
+
+        let reader = self.reader(range_spec.clone()).await?;
+        let size = reader.object_info.size as u64;
+        let range = match &range_spec {
+            Some(spec) => {
+                let (offset, length) = spec.get_offset_length(size as i64).map_err(|e| o_Error::Generic {
+                    store: "EcObjectStore",
+                    source: Box::new(e),
+                })?;
+                offset as u64..offset as u64 + length as u64
+            }
+            None => 0..size,
+        };
 
         let meta = ObjectMeta {
             location: location.clone(),
             last_modified: Utc::now(),
-            size: reader.object_info.size as u64,
-            e_tag: reader.object_info.etag,
+            size,
+            e_tag: reader.object_info.etag.clone(),
             version: None,
         };
         let attributes = Attributes::default();
 
-        let payload = if self.need_convert {
-            object_store::GetResultPayload::Stream(
-                bytes_stream(
-                    ReaderStream::with_capacity(
-                        ConvertStream::new(reader.stream, self.delimiter.clone()),
-                        DEFAULT_READ_BUFFER_SIZE,
-                    ),
-                    reader.object_info.size as usize,
-                )
-                .boxed(),
-            )
+        // Decompressed output doesn't match the stored length
+        let content_length = if self.compression.is_some() {
+            usize::MAX
         } else {
-            object_store::GetResultPayload::Stream(
-                bytes_stream(
-                    ReaderStream::with_capacity(reader.stream, DEFAULT_READ_BUFFER_SIZE),
-                    reader.object_info.size as usize,
-                )
-                .boxed(),
-            )
+            (range.end - range.start) as usize
         };
+        let filter =
+            scan_range.map(|(start, end)| ScanRangeFilter::new(self.record_delimiter, start.saturating_sub(1), start > 0, end));
+        let records = record_stream(self.raw(reader), self.compression, content_length, filter, self.convert_delimiter())?;
+
+        let stats = self.stats.clone();
+        let records = records.inspect(move |bytes: &Result<Bytes>| {
+            if let Ok(bytes) = bytes {
+                stats.add_processed(bytes.len() as u64);
+            }
+        });
+
+        let stream = match scan_range {
+            Some((start, _)) if self.has_header && start > 0 => {
+                let header = self.header_line().await?;
+                futures::stream::once(async move { Ok(header) }).chain(records).boxed()
+            }
+            _ => records.boxed(),
+        };
+
         Ok(GetResult {
-            payload,
+            payload: object_store::GetResultPayload::Stream(stream),
             meta,
-            range: 0..reader.object_info.size as u64,
+            range,
             attributes,
         })
     }
 
-    async fn get_ranges(&self, _location: &Path, _ranges: &[Range<u64>]) -> Result<Vec<Bytes>> {
-        unimplemented!()
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<u64>]) -> Result<Vec<Bytes>> {
+        let mut result = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            result.push(self.get_range(location, range.clone()).await?);
+        }
+        Ok(result)
     }
 
     async fn head(&self, location: &Path) -> Result<ObjectMeta> {
@@ -182,50 +401,187 @@ impl ObjectStore for EcObjectStore {
 }
 
 pin_project! {
-    struct ConvertStream<R> {
+    /// Counts the bytes read from storage, before any decompression
+    struct CountingReader<R> {
+        #[pin]
         inner: R,
-        delimiter: Vec<u8>,
-    }
-}
-
-impl<R> ConvertStream<R> {
-    fn new(inner: R, delimiter: String) -> Self {
-        ConvertStream {
-            inner,
-            delimiter: delimiter.as_bytes().to_vec(),
-        }
+        stats: Arc<ScanStats>,
     }
 }
 
-impl<R: AsyncRead + Unpin> AsyncRead for ConvertStream<R> {
-    #[tracing::instrument(level = "debug", skip_all)]
+impl<R: AsyncRead> AsyncRead for CountingReader<R> {
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
         buf: &mut tokio::io::ReadBuf<'_>,
     ) -> Poll<std::io::Result<()>> {
         let me = self.project();
-        ready!(Pin::new(&mut *me.inner).poll_read(cx, buf))?;
-        let bytes = buf.filled();
-        let replaced = replace_symbol(me.delimiter, bytes);
-        buf.clear();
-        buf.put_slice(&replaced);
+        let before = buf.filled().len();
+        ready!(me.inner.poll_read(cx, buf))?;
+        me.stats.add_scanned((buf.filled().len() - before) as u64);
         Poll::Ready(Ok(()))
     }
 }
 
-fn replace_symbol(delimiter: &[u8], slice: &[u8]) -> Vec<u8> {
-    let mut result = Vec::with_capacity(slice.len());
-    let mut i = 0;
-    while i < slice.len() {
-        if slice[i..].starts_with(delimiter) {
-            result.push(DEFAULT_DELIMITER);
-            i += delimiter.len();
-        } else {
-            result.push(slice[i]);
-            i += 1;
+/// Keeps the records that start inside a scan range
+#[derive(Debug)]
+struct ScanRangeFilter {
+    delimiter: u8,
+    // Offset of the next byte
+    pos: u64,
+    // Drop everything up to the first delimiter, the read started inside a record
+    skip_partial: bool,
+    at_record_start: bool,
+    // Records starting at or after this offset are dropped
+    end: u64,
+    done: bool,
+}
+
+impl ScanRangeFilter {
+    fn new(delimiter: u8, pos: u64, skip_partial: bool, end: u64) -> Self {
+        Self {
+            delimiter,
+            pos,
+            skip_partial,
+            at_record_start: !skip_partial,
+            end,
+            done: false,
         }
     }
+
+    /// Part of the next chunk that belongs to the range
+    fn filter(&mut self, chunk: &[u8]) -> Range<usize> {
+        let base = self.pos;
+        self.pos += chunk.len() as u64;
+        if self.done {
+            return 0..0;
+        }
+
+        let mut start = 0;
+        if self.skip_partial {
+            match chunk.iter().position(|b| *b == self.delimiter) {
+                Some(i) => {
+                    self.skip_partial = false;
+                    self.at_record_start = true;
+                    start = i + 1;
+                }
+                None => return 0..0,
+            }
+        }
+
+        let mut i = start;
+        loop {
+            if self.at_record_start {
+                if i == chunk.len() {
+                    return start..i;
+                }
+                if base + i as u64 >= self.end {
+                    self.done = true;
+                    return start..i;
+                }
+            }
+            match chunk[i..].iter().position(|b| *b == self.delimiter) {
+                Some(j) => {
+                    i += j + 1;
+                    self.at_record_start = true;
+                }
+                None => {
+                    self.at_record_start = false;
+                    return start..chunk.len();
+                }
+            }
+        }
+    }
+}
+
+fn scan_range_stream<S>(stream: S, mut filter: ScanRangeFilter) -> impl Stream<Item = Result<Bytes>> + Send + 'static
+where
+    S: Stream<Item = Result<Bytes>> + Send + 'static,
+{
+    AsyncTryStream::<Bytes, o_Error, _>::new(|mut y| async move {
+        pin_mut!(stream);
+        while let Some(bytes) = stream.next().await {
+            let bytes = bytes?;
+            let range = filter.filter(&bytes);
+            if !range.is_empty() {
+                y.yield_ok(bytes.slice(range)).await;
+            }
+            // Stop reading once the last record of the range is complete
+            if filter.done {
+                break;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Replaces a multi-byte field delimiter with `DEFAULT_DELIMITER`. Bytes at the end of a chunk that
+/// may start a delimiter are held back until the next chunk.
+#[derive(Debug)]
+struct DelimiterConverter {
+    delimiter: Vec<u8>,
+    pending: Vec<u8>,
+}
+
+impl DelimiterConverter {
+    fn new(delimiter: &[u8]) -> Self {
+        Self {
+            delimiter: delimiter.to_vec(),
+            pending: Vec::new(),
+        }
+    }
+
+    fn convert(&mut self, chunk: &[u8]) -> Vec<u8> {
+        let mut data = std::mem::take(&mut self.pending);
+        data.extend_from_slice(chunk);
+
+        let mut result = Vec::with_capacity(data.len());
+        let mut i = 0;
+        while i < data.len() {
+            if data[i..].starts_with(&self.delimiter) {
+                result.push(DEFAULT_DELIMITER);
+                i += self.delimiter.len();
+            } else if data.len() - i < self.delimiter.len() && self.delimiter.starts_with(&data[i..]) {
+                self.pending = data[i..].to_vec();
+                break;
+            } else {
+                result.push(data[i]);
+                i += 1;
+            }
+        }
+        result
+    }
+
+    /// Bytes held back at the end of the input, they did not turn into a delimiter
+    fn finish(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+fn convert_stream<S>(stream: S, mut converter: DelimiterConverter) -> impl Stream<Item = Result<Bytes>> + Send + 'static
+where
+    S: Stream<Item = Result<Bytes>> + Send + 'static,
+{
+    AsyncTryStream::<Bytes, o_Error, _>::new(|mut y| async move {
+        pin_mut!(stream);
+        while let Some(bytes) = stream.next().await {
+            let converted = converter.convert(&bytes?);
+            if !converted.is_empty() {
+                y.yield_ok(Bytes::from(converted)).await;
+            }
+        }
+        let rest = converter.finish();
+        if !rest.is_empty() {
+            y.yield_ok(Bytes::from(rest)).await;
+        }
+        Ok(())
+    })
+}
+
+fn replace_symbol(delimiter: &[u8], slice: &[u8]) -> Vec<u8> {
+    let mut converter = DelimiterConverter::new(delimiter);
+    let mut result = converter.convert(slice);
+    result.extend(converter.finish());
     result
 }
 
@@ -253,7 +609,106 @@ where
 
 #[cfg(test)]
 mod test {
-    use super::replace_symbol;
+    use super::{DelimiterConverter, ScanRangeFilter, first_record, record_stream, replace_symbol, scan_range_bounds};
+    use bytes::Bytes;
+    use futures::TryStreamExt;
+    use futures_core::stream::BoxStream;
+    use nebulafx_zip::{CompressionFormat, CompressionLevel};
+    use object_store::Result;
+    use s3s::dto::ScanRange;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const DATA: &[u8] = b"a,1\nbb,2\nccc,3\ndddd,4\n";
+
+    fn scan(start: u64, end: u64, chunk_size: usize) -> String {
+        let from = start.saturating_sub(1) as usize;
+        let mut filter = ScanRangeFilter::new(b'\n', from as u64, start > 0, end);
+        let mut out = Vec::new();
+        for chunk in DATA[from..].chunks(chunk_size) {
+            out.extend_from_slice(&chunk[filter.filter(chunk)]);
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_scan_range_filter() {
+        for chunk_size in [1, 3, DATA.len()] {
+            assert_eq!(scan(0, DATA.len() as u64, chunk_size), "a,1\nbb,2\nccc,3\ndddd,4\n");
+            // Record 1 starts at 4, before the range
+            assert_eq!(scan(5, 11, chunk_size), "ccc,3\n");
+            assert_eq!(scan(4, 5, chunk_size), "bb,2\n");
+            assert_eq!(scan(0, 1, chunk_size), "a,1\n");
+            assert_eq!(scan(16, DATA.len() as u64, chunk_size), "");
+        }
+    }
+
+    // Records of DATA with a two-byte field delimiter, the offsets of the converted records differ
+    const WIDE: &[u8] = b"h1&&h2\na&&1\nbb&&2\nccc&&3\ndddd&&4\n";
+
+    async fn collect(records: BoxStream<'static, Result<Bytes>>) -> String {
+        let chunks: Vec<Bytes> = records.try_collect().await.unwrap();
+        String::from_utf8(chunks.concat()).unwrap()
+    }
+
+    async fn compress(format: CompressionFormat, data: &[u8]) -> Vec<u8> {
+        let (writer, mut reader) = tokio::io::duplex(64 * 1024);
+        let mut encoder = format.get_encoder(writer, CompressionLevel::Default).unwrap();
+        encoder.write_all(data).await.unwrap();
+        encoder.shutdown().await.unwrap();
+        drop(encoder);
+        let mut compressed = Vec::new();
+        reader.read_to_end(&mut compressed).await.unwrap();
+        compressed
+    }
+
+    #[test]
+    fn test_delimiter_converter_across_chunks() {
+        for chunk_size in 1..=WIDE.len() {
+            let mut converter = DelimiterConverter::new(b"&&");
+            let mut out = Vec::new();
+            for chunk in WIDE.chunks(chunk_size) {
+                out.extend(converter.convert(chunk));
+            }
+            out.extend(converter.finish());
+            assert_eq!(out, b"h1,h2\na,1\nbb,2\nccc,3\ndddd,4\n", "chunk size {chunk_size}");
+        }
+        assert_eq!(replace_symbol(b"&&", b"a&&&b&"), b"a,&b&");
+    }
+
+    #[tokio::test]
+    async fn test_record_stream_compressed() {
+        for format in [CompressionFormat::Gzip, CompressionFormat::Bzip2] {
+            let compressed = compress(format, WIDE).await;
+            let records = record_stream(std::io::Cursor::new(compressed), Some(format), usize::MAX, None, Some(b"&&")).unwrap();
+            assert_eq!(collect(records).await, "h1,h2\na,1\nbb,2\nccc,3\ndddd,4\n", "{format:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_stream_scan_range_with_header() {
+        // "ccc&&3" starts at byte 18 of the stored object, the next record at 25
+        let (start, end) = (18, 25);
+        let filter = ScanRangeFilter::new(b'\n', start - 1, true, end);
+        let raw = std::io::Cursor::new(WIDE[start as usize - 1..].to_vec());
+        let records = record_stream(raw, None, usize::MAX, Some(filter), Some(b"&&")).unwrap();
+        assert_eq!(collect(records).await, "ccc,3\n");
+
+        let all = record_stream(std::io::Cursor::new(WIDE.to_vec()), None, usize::MAX, None, Some(b"&&")).unwrap();
+        assert_eq!(first_record(all, b'\n').await.unwrap(), Bytes::from_static(b"h1,h2\n"));
+
+        let compressed = compress(CompressionFormat::Gzip, WIDE).await;
+        let all = record_stream(std::io::Cursor::new(compressed), Some(CompressionFormat::Gzip), usize::MAX, None, None).unwrap();
+        assert_eq!(first_record(all, b'\n').await.unwrap(), Bytes::from_static(b"h1&&h2\n"));
+    }
+
+    #[test]
+    fn test_scan_range_bounds() {
+        let range = |start, end| ScanRange { start, end };
+        assert_eq!(scan_range_bounds(&range(Some(5), Some(10)), 100), (5, 11));
+        assert_eq!(scan_range_bounds(&range(Some(5), None), 100), (5, 100));
+        assert_eq!(scan_range_bounds(&range(None, Some(10)), 100), (90, 100));
+        assert_eq!(scan_range_bounds(&range(Some(50), Some(500)), 100), (50, 100));
+    }
 
     #[test]
     fn test_replace() {
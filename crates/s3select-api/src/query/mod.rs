@@ -1,6 +1,7 @@
 
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use s3s::dto::SelectObjectContentInput;
 
@@ -21,6 +22,43 @@ pub mod session;
 pub struct Context {
     // maybe we need transfer some info?
     pub input: Arc<SelectObjectContentInput>,
+    pub stats: Arc<ScanStats>,
+}
+
+impl Context {
+    pub fn new(input: Arc<SelectObjectContentInput>) -> Self {
+        Self {
+            input,
+            stats: Arc::new(ScanStats::default()),
+        }
+    }
+}
+
+/// Bytes read from the object while a query runs, reported in Progress and Stats events
+#[derive(Debug, Default)]
+pub struct ScanStats {
+    bytes_scanned: AtomicU64,
+    bytes_processed: AtomicU64,
+}
+
+impl ScanStats {
+    /// Bytes read from storage
+    pub fn add_scanned(&self, n: u64) {
+        self.bytes_scanned.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Bytes handed to the query engine, after decompression
+    pub fn add_processed(&self, n: u64) {
+        self.bytes_processed.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn bytes_scanned(&self) -> u64 {
+        self.bytes_scanned.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_processed(&self) -> u64 {
+        self.bytes_processed.load(Ordering::Relaxed)
+    }
 }
 
 #[derive(Clone)]
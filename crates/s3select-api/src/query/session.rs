@@ -5,7 +5,7 @@ use std::sync::Arc;
 use datafusion::{
     execution::{SessionStateBuilder, context::SessionState, runtime_env::RuntimeEnvBuilder},
     parquet::data_type::AsBytes,
-    prelude::SessionContext,
+    prelude::{SessionConfig, SessionContext},
 };
 use object_store::{ObjectStore, memory::InMemory, path::Path};
 use tracing::error;
@@ -50,7 +50,10 @@ impl SessionCtxFactory {
         let path = format!("s3://{}", context.input.bucket);
         let store_url = url::Url::parse(&path).unwrap();
         let rt = RuntimeEnvBuilder::new().build()?;
+        // One scan in object order, ranged partitions would break scan ranges and compressed input
+        let config = SessionConfig::new().with_repartition_file_scans(false);
         let df_session_state = SessionStateBuilder::new()
+            .with_config(config)
             .with_runtime_env(Arc::new(rt))
             .with_default_features();
 
@@ -88,8 +91,8 @@ impl SessionCtxFactory {
 
             df_session_state.with_object_store(&store_url, Arc::new(store)).build()
         } else {
-            let store =
-                EcObjectStore::new(context.input.clone()).map_err(|_| QueryError::NotImplemented { err: String::new() })?;
+            let store = EcObjectStore::new(context.input.clone(), context.stats.clone())
+                .map_err(|_| QueryError::NotImplemented { err: String::new() })?;
             df_session_state.with_object_store(&store_url, Arc::new(store)).build()
         };
 
@@ -213,6 +213,8 @@ impl SimpleQueryDispatcher {
                 });
             };
 
+        // The object is addressed directly, its key doesn't have to end in the format's extension (e.g. `data.csv.gz`)
+        let listing_options = listing_options.with_file_extension("");
         let resolve_schema = listing_options.infer_schema(session.inner(), &table_path).await?;
         let config = if need_rename_volume_name {
             let mut new_fields = Vec::new();
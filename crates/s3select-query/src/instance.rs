@@ -165,7 +165,7 @@ mod tests {
             },
         };
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+        let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
         let result = db.execute(&query).await.unwrap();
 
@@ -224,7 +224,7 @@ mod tests {
             },
         };
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+        let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
         let result = db.execute(&query).await.unwrap();
 
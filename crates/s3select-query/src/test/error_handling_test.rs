@@ -51,7 +51,7 @@ mod error_handling_tests {
         for sql in invalid_sqls {
             let input = create_test_input_with_sql(sql);
             let db = get_global_db(input.clone(), true).await.unwrap();
-            let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+            let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
             let result = db.execute(&query).await;
             assert!(result.is_err(), "Expected error for SQL: {sql}");
@@ -69,7 +69,7 @@ mod error_handling_tests {
         for sql in multi_statement_sqls {
             let input = create_test_input_with_sql(sql);
             let db = get_global_db(input.clone(), true).await.unwrap();
-            let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+            let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
             let result = db.execute(&query).await;
             assert!(result.is_err(), "Expected multi-statement error for SQL: {sql}");
@@ -93,7 +93,7 @@ mod error_handling_tests {
         for sql in unsupported_sqls {
             let input = create_test_input_with_sql(sql);
             let db = get_global_db(input.clone(), true).await.unwrap();
-            let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+            let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
             let result = db.execute(&query).await;
             // These should either fail with syntax error or not implemented error
@@ -113,7 +113,7 @@ mod error_handling_tests {
         for sql in invalid_column_sqls {
             let input = create_test_input_with_sql(sql);
             let db = get_global_db(input.clone(), true).await.unwrap();
-            let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+            let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
             let result = db.execute(&query).await;
             // These might succeed or fail depending on schema inference
@@ -150,7 +150,7 @@ mod error_handling_tests {
 
         let input = create_test_input_with_sql(complex_invalid_sql);
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, complex_invalid_sql.to_string());
+        let query = Query::new(Context::new(Arc::new(input)), complex_invalid_sql.to_string());
 
         let result = db.execute(&query).await;
         assert!(result.is_err(), "Expected error for complex invalid SQL");
@@ -163,7 +163,7 @@ mod error_handling_tests {
         for sql in empty_sqls {
             let input = create_test_input_with_sql(sql);
             let db = get_global_db(input.clone(), true).await.unwrap();
-            let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+            let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
             let result = db.execute(&query).await;
             // Empty queries might be handled differently by the parser
@@ -192,7 +192,7 @@ mod error_handling_tests {
 
         let input = create_test_input_with_sql(&long_sql);
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, long_sql);
+        let query = Query::new(Context::new(Arc::new(input)), long_sql);
 
         let result = db.execute(&query).await;
         // This should either succeed or fail gracefully
@@ -218,7 +218,7 @@ mod error_handling_tests {
         for sql in injection_patterns {
             let input = create_test_input_with_sql(sql);
             let db = get_global_db(input.clone(), true).await.unwrap();
-            let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+            let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
             let result = db.execute(&query).await;
             // These should be handled safely - either succeed with limited scope or fail
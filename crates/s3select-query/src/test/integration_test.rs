@@ -66,7 +66,7 @@ mod integration_tests {
         let sql = "SELECT * FROM S3Object";
         let input = create_test_input(sql);
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+        let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
         let result = db.execute(&query).await;
         assert!(result.is_ok());
@@ -81,7 +81,7 @@ mod integration_tests {
         let sql = "SELECT name, age FROM S3Object WHERE age > 30";
         let input = create_test_input(sql);
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+        let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
         let result = db.execute(&query).await;
         assert!(result.is_ok());
@@ -92,7 +92,7 @@ mod integration_tests {
         let sql = "SELECT department, COUNT(*) as count FROM S3Object GROUP BY department";
         let input = create_test_input(sql);
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+        let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
         let result = db.execute(&query).await;
         // Aggregation queries might fail due to lack of actual data, which is acceptable
@@ -111,7 +111,7 @@ mod integration_tests {
         let sql = "INVALID SQL SYNTAX";
         let input = create_test_input(sql);
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+        let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
         let result = db.execute(&query).await;
         assert!(result.is_err());
@@ -122,7 +122,7 @@ mod integration_tests {
         let sql = "SELECT * FROM S3Object; SELECT 1;";
         let input = create_test_input(sql);
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+        let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
         let result = db.execute(&query).await;
         assert!(result.is_err());
@@ -139,7 +139,7 @@ mod integration_tests {
         let sql = "SELECT * FROM S3Object";
         let input = create_test_input(sql);
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+        let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
         // Test state machine creation
         let state_machine = db.build_query_state_machine(query.clone()).await;
@@ -163,7 +163,7 @@ mod integration_tests {
         let sql = "SELECT * FROM S3Object LIMIT 5";
         let input = create_test_input(sql);
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+        let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
         let result = db.execute(&query).await;
         assert!(result.is_ok());
@@ -181,7 +181,7 @@ mod integration_tests {
         let sql = "SELECT name, age FROM S3Object ORDER BY age DESC";
         let input = create_test_input(sql);
         let db = get_global_db(input.clone(), true).await.unwrap();
-        let query = Query::new(Context { input: Arc::new(input) }, sql.to_string());
+        let query = Query::new(Context::new(Arc::new(input)), sql.to_string());
 
         let result = db.execute(&query).await;
         assert!(result.is_ok());
@@ -196,12 +196,7 @@ mod integration_tests {
         // Execute multiple queries concurrently
         let mut handles = vec![];
         for i in 0..3 {
-            let query = Query::new(
-                Context {
-                    input: Arc::new(input.clone()),
-                },
-                format!("SELECT * FROM S3Object LIMIT {}", i + 1),
-            );
+            let query = Query::new(Context::new(Arc::new(input.clone())), format!("SELECT * FROM S3Object LIMIT {}", i + 1));
             let db_clone = db.clone();
             let handle = tokio::spawn(async move { db_clone.execute(&query).await });
             handles.push(handle);
@@ -0,0 +1,57 @@
+-- IAM change notifications
+-- Version: 2
+-- Description: Publish every write to the IAM tables on the iam_changes channel so that all
+-- nodes can drop what they cached about the changed row
+
+CREATE OR REPLACE FUNCTION notify_iam_change() RETURNS trigger AS $$
+DECLARE
+    new_row JSONB;
+    old_row JSONB;
+    payload JSONB;
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        old_row := to_jsonb(OLD);
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        new_row := to_jsonb(NEW);
+    END IF;
+
+    -- users are keyed by access key, the other tables by name
+    payload := jsonb_build_object(
+        'table', TG_TABLE_NAME,
+        'name', COALESCE(new_row->>'name', new_row->>'access_key', old_row->>'name', old_row->>'access_key'),
+        'is_group', COALESCE((COALESCE(new_row, old_row)->>'is_group')::boolean, false)
+    );
+    IF TG_OP = 'UPDATE' THEN
+        payload := payload || jsonb_build_object('old_name', COALESCE(old_row->>'name', old_row->>'access_key'));
+    END IF;
+
+    PERFORM pg_notify('iam_changes', payload::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS users_notify_iam_change ON users;
+CREATE TRIGGER users_notify_iam_change
+    AFTER INSERT OR UPDATE OR DELETE ON users
+    FOR EACH ROW EXECUTE FUNCTION notify_iam_change();
+
+DROP TRIGGER IF EXISTS policies_notify_iam_change ON policies;
+CREATE TRIGGER policies_notify_iam_change
+    AFTER INSERT OR UPDATE OR DELETE ON policies
+    FOR EACH ROW EXECUTE FUNCTION notify_iam_change();
+
+DROP TRIGGER IF EXISTS groups_notify_iam_change ON groups;
+CREATE TRIGGER groups_notify_iam_change
+    AFTER INSERT OR UPDATE OR DELETE ON groups
+    FOR EACH ROW EXECUTE FUNCTION notify_iam_change();
+
+DROP TRIGGER IF EXISTS mapped_policies_notify_iam_change ON mapped_policies;
+CREATE TRIGGER mapped_policies_notify_iam_change
+    AFTER INSERT OR UPDATE OR DELETE ON mapped_policies
+    FOR EACH ROW EXECUTE FUNCTION notify_iam_change();
+
+DROP TRIGGER IF EXISTS user_identities_notify_iam_change ON user_identities;
+CREATE TRIGGER user_identities_notify_iam_change
+    AFTER INSERT OR UPDATE OR DELETE ON user_identities
+    FOR EACH ROW EXECUTE FUNCTION notify_iam_change();
@@ -0,0 +1,6 @@
+-- Sealed IAM secrets
+-- Version: 3
+-- Description: Secret keys are stored sealed, which does not fit the length of the plaintext
+-- column. Rows written before are sealed at startup, see init::seal_secrets
+
+ALTER TABLE users ALTER COLUMN secret_key TYPE TEXT;
@@ -0,0 +1,412 @@
+//! In-process cache of the IAM data read on every signed request: the identities behind access keys,
+//! the policies mapped to users and groups, and policy documents.
+//!
+//! Every write to the IAM tables fires a trigger that publishes the changed row on the
+//! `IAM_NOTIFY_CHANNEL` Postgres channel. Each node listens on it and drops the entries the change
+//! touches, so all nodes see a change as soon as it is committed. Notifications sent while a node
+//! is not listening are lost, so the whole cache is dropped whenever the listener (re)connects and
+//! on a fixed interval as a fallback.
+
+use crate::repository::IAM_NOTIFY_CHANNEL;
+use metrics::{counter, describe_counter, describe_gauge, gauge};
+use nebulafx_policy::auth::UserIdentity;
+use nebulafx_policy::policy::PolicyDoc;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+use tracing::{debug, info, warn};
+
+/// Seconds between two full reloads of the cache, the fallback for lost notifications
+pub const ENV_IAM_CACHE_RELOAD_INTERVAL: &str = "NEUBULAFX_IAM_CACHE_RELOAD_INTERVAL";
+pub const DEFAULT_IAM_CACHE_RELOAD_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+const LISTENER_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const METRICS_INTERVAL: Duration = Duration::from_secs(10);
+
+const M_IAM_CACHE_REQUESTS: &str = "nebulafx.iam.cache.requests";
+const M_IAM_CACHE_INVALIDATIONS: &str = "nebulafx.iam.cache.invalidations";
+const M_IAM_CACHE_HIT_RATIO: &str = "nebulafx.iam.cache.hit.ratio";
+const M_IAM_CACHE_STALENESS: &str = "nebulafx.iam.cache.staleness.seconds";
+const M_IAM_CACHE_LISTENING: &str = "nebulafx.iam.cache.listening";
+
+fn init_cache_metrics() {
+    static METRICS_DESC_INIT: OnceLock<()> = OnceLock::new();
+    METRICS_DESC_INIT.get_or_init(|| {
+        describe_counter!(M_IAM_CACHE_REQUESTS, "IAM cache lookups (labeled by kind and result).");
+        describe_counter!(M_IAM_CACHE_INVALIDATIONS, "IAM cache invalidations (labeled by source).");
+        describe_gauge!(M_IAM_CACHE_HIT_RATIO, "Share of IAM cache lookups served from the cache.");
+        describe_gauge!(M_IAM_CACHE_STALENESS, "Seconds since the IAM cache was last known to be in sync.");
+        describe_gauge!(M_IAM_CACHE_LISTENING, "Whether the node listens for IAM changes (1) or not (0).");
+    });
+}
+
+/// A row written to one of the IAM tables, as published by the `notify_iam_change` trigger
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IamChange {
+    pub table: String,
+    pub name: String,
+    /// Name of the row before an update that renamed it
+    pub old_name: Option<String>,
+    pub is_group: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IamCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f64,
+    pub users: usize,
+    pub policy_mappings: usize,
+    pub policies: usize,
+    pub listening: bool,
+    pub staleness_seconds: u64,
+}
+
+pub struct IamCache {
+    /// Identities by access key, as resolved by `IamSys::check_key`
+    users: RwLock<HashMap<String, UserIdentity>>,
+    /// Policies mapped to a user or group, with the groups of a user folded in
+    policy_mappings: RwLock<HashMap<(String, bool), (Vec<String>, OffsetDateTime)>>,
+    policies: RwLock<HashMap<String, PolicyDoc>>,
+    /// Bumped by every invalidation, a lookup that raced with one does not store its result
+    generation: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    listening: AtomicBool,
+    /// Last time the cache was known to hold what the database holds
+    synced_at: RwLock<Instant>,
+    reloaded_at: RwLock<Instant>,
+}
+
+impl Default for IamCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IamCache {
+    pub fn new() -> Self {
+        init_cache_metrics();
+        Self {
+            users: RwLock::new(HashMap::new()),
+            policy_mappings: RwLock::new(HashMap::new()),
+            policies: RwLock::new(HashMap::new()),
+            generation: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            listening: AtomicBool::new(false),
+            synced_at: RwLock::new(Instant::now()),
+            reloaded_at: RwLock::new(Instant::now()),
+        }
+    }
+
+    /// Generation to pass to the `insert_*` calls of a lookup that missed
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    pub fn is_listening(&self) -> bool {
+        self.listening.load(Ordering::Acquire)
+    }
+
+    pub fn get_user(&self, access_key: &str) -> Option<UserIdentity> {
+        let user = self.users.read().unwrap().get(access_key).cloned();
+        self.record_lookup("user", user.is_some());
+        user
+    }
+
+    pub fn insert_user(&self, access_key: &str, user: UserIdentity, generation: u64) {
+        let mut users = self.users.write().unwrap();
+        if self.generation() == generation {
+            users.insert(access_key.to_string(), user);
+        }
+    }
+
+    pub fn get_policy_mapping(&self, name: &str, is_group: bool) -> Option<(Vec<String>, OffsetDateTime)> {
+        let mapping = self
+            .policy_mappings
+            .read()
+            .unwrap()
+            .get(&(name.to_string(), is_group))
+            .cloned();
+        self.record_lookup("policy_mapping", mapping.is_some());
+        mapping
+    }
+
+    pub fn insert_policy_mapping(&self, name: &str, is_group: bool, mapping: (Vec<String>, OffsetDateTime), generation: u64) {
+        let mut mappings = self.policy_mappings.write().unwrap();
+        if self.generation() == generation {
+            mappings.insert((name.to_string(), is_group), mapping);
+        }
+    }
+
+    pub fn get_policy(&self, name: &str) -> Option<PolicyDoc> {
+        let policy = self.policies.read().unwrap().get(name).cloned();
+        self.record_lookup("policy", policy.is_some());
+        policy
+    }
+
+    pub fn insert_policy(&self, name: &str, policy: PolicyDoc, generation: u64) {
+        let mut policies = self.policies.write().unwrap();
+        if self.generation() == generation {
+            policies.insert(name.to_string(), policy);
+        }
+    }
+
+    /// Drops the entries a change to the IAM tables makes stale
+    pub fn apply(&self, change: &IamChange, source: &'static str) {
+        debug!("IAM cache invalidation from {}: {:?}", source, change);
+        counter!(M_IAM_CACHE_INVALIDATIONS, "source" => source).increment(1);
+
+        let names: Vec<&str> = std::iter::once(change.name.as_str())
+            .chain(change.old_name.as_deref())
+            .collect();
+
+        // Hold every lock while bumping the generation so that no lookup stores a result read before the change
+        let mut users = self.users.write().unwrap();
+        let mut mappings = self.policy_mappings.write().unwrap();
+        let mut policies = self.policies.write().unwrap();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+
+        match change.table.as_str() {
+            "users" | "user_identities" => {
+                for name in names {
+                    users.remove(name);
+                    mappings.remove(&(name.to_string(), false));
+                }
+            }
+            "policies" => {
+                for name in names {
+                    policies.remove(name);
+                }
+            }
+            "mapped_policies" if !change.is_group => {
+                for name in names {
+                    mappings.remove(&(name.to_string(), false));
+                }
+            }
+            // Group policies and memberships are folded into the mappings of every member
+            "groups" | "mapped_policies" => mappings.clear(),
+            _ => {
+                users.clear();
+                mappings.clear();
+                policies.clear();
+            }
+        }
+    }
+
+    /// Drops the whole cache, entries are read from the database again on their next use
+    pub fn invalidate_all(&self, source: &'static str) {
+        info!("IAM cache dropped ({})", source);
+        self.apply(
+            &IamChange {
+                table: "*".to_string(),
+                ..Default::default()
+            },
+            source,
+        );
+        let now = Instant::now();
+        *self.reloaded_at.write().unwrap() = now;
+        *self.synced_at.write().unwrap() = now;
+    }
+
+    pub fn stats(&self) -> IamCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        IamCacheStats {
+            hits,
+            misses,
+            hit_rate: if hits + misses == 0 {
+                0.0
+            } else {
+                hits as f64 / (hits + misses) as f64
+            },
+            users: self.users.read().unwrap().len(),
+            policy_mappings: self.policy_mappings.read().unwrap().len(),
+            policies: self.policies.read().unwrap().len(),
+            listening: self.is_listening(),
+            staleness_seconds: self.synced_at.read().unwrap().elapsed().as_secs(),
+        }
+    }
+
+    fn record_lookup(&self, kind: &'static str, hit: bool) {
+        if hit {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            counter!(M_IAM_CACHE_REQUESTS, "kind" => kind, "result" => "hit").increment(1);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            counter!(M_IAM_CACHE_REQUESTS, "kind" => kind, "result" => "miss").increment(1);
+        }
+    }
+
+    fn set_listening(&self, listening: bool) {
+        self.listening.store(listening, Ordering::Release);
+    }
+
+    /// Reloads the cache on the configured interval and publishes the cache gauges. While the node
+    /// listens for changes the cache is in sync, otherwise its staleness grows until the next reload.
+    async fn run_reloader(self: Arc<Self>) {
+        let reload_interval = std::env::var(ENV_IAM_CACHE_RELOAD_INTERVAL)
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .map_or(DEFAULT_IAM_CACHE_RELOAD_INTERVAL, Duration::from_secs);
+
+        let mut interval = tokio::time::interval(METRICS_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            if self.reloaded_at.read().unwrap().elapsed() >= reload_interval {
+                self.invalidate_all("reload");
+            } else if self.is_listening() {
+                *self.synced_at.write().unwrap() = Instant::now();
+            }
+
+            let stats = self.stats();
+            gauge!(M_IAM_CACHE_HIT_RATIO).set(stats.hit_rate);
+            gauge!(M_IAM_CACHE_STALENESS).set(stats.staleness_seconds as f64);
+            gauge!(M_IAM_CACHE_LISTENING).set(if stats.listening { 1.0 } else { 0.0 });
+        }
+    }
+
+    /// Applies the changes published on `IAM_NOTIFY_CHANNEL`, reconnecting when the connection drops
+    async fn run_listener(self: Arc<Self>, pool: PgPool) {
+        loop {
+            let mut listener = match PgListener::connect_with(&pool).await {
+                Ok(listener) => listener,
+                Err(err) => {
+                    warn!("IAM change listener failed to connect: {}", err);
+                    tokio::time::sleep(LISTENER_RETRY_INTERVAL).await;
+                    continue;
+                }
+            };
+            if let Err(err) = listener.listen(IAM_NOTIFY_CHANNEL).await {
+                warn!("IAM change listener failed to listen on {}: {}", IAM_NOTIFY_CHANNEL, err);
+                tokio::time::sleep(LISTENER_RETRY_INTERVAL).await;
+                continue;
+            }
+
+            // Changes committed before the listener was up were not seen
+            self.invalidate_all("listen");
+            self.set_listening(true);
+
+            let lost = loop {
+                match listener.try_recv().await {
+                    Ok(Some(notification)) => match serde_json::from_str::<IamChange>(notification.payload()) {
+                        Ok(change) => self.apply(&change, "notify"),
+                        Err(err) => {
+                            warn!("invalid IAM change notification {}: {}", notification.payload(), err);
+                            self.invalidate_all("notify");
+                        }
+                    },
+                    // What is sent until the listener is back is lost. A new listener is set up right
+                    // away, it invalidates again once it listens, which the reconnect inside `try_recv`
+                    // would do only after waiting for the next notification
+                    Ok(None) => {
+                        warn!("IAM change listener lost its connection");
+                        self.invalidate_all("listen");
+                        break true;
+                    }
+                    Err(err) => {
+                        warn!("IAM change listener failed: {}", err);
+                        break false;
+                    }
+                }
+            };
+
+            self.set_listening(false);
+            if !lost {
+                tokio::time::sleep(LISTENER_RETRY_INTERVAL).await;
+            }
+        }
+    }
+
+    /// Starts listening for IAM changes and the periodic reload. Only the Postgres backend publishes
+    /// changes, with the object backend the peer notifications and the reload keep the nodes in sync
+    pub fn start(self: &Arc<Self>, pool: Option<PgPool>) {
+        if let Some(pool) = pool {
+            tokio::spawn(self.clone().run_listener(pool));
+        }
+        tokio::spawn(self.clone().run_reloader());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nebulafx_policy::auth::Credentials;
+
+    fn identity(access_key: &str) -> UserIdentity {
+        UserIdentity::new(Credentials {
+            access_key: access_key.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn change(table: &str, name: &str, is_group: bool) -> IamChange {
+        IamChange {
+            table: table.to_string(),
+            name: name.to_string(),
+            is_group,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_iam_cache_invalidation() {
+        let cache = IamCache::new();
+        let generation = cache.generation();
+        let mapping = (vec!["readwrite".to_string()], OffsetDateTime::now_utc());
+        cache.insert_user("alice", identity("alice"), generation);
+        cache.insert_user("bob", identity("bob"), generation);
+        cache.insert_policy_mapping("alice", false, mapping.clone(), generation);
+        cache.insert_policy_mapping("bob", false, mapping.clone(), generation);
+        cache.insert_policy_mapping("devs", true, mapping.clone(), generation);
+        cache.insert_policy("readwrite", PolicyDoc::default(), generation);
+
+        cache.apply(&change("user_identities", "alice", false), "local");
+        assert!(cache.get_user("alice").is_none());
+        assert!(cache.get_policy_mapping("alice", false).is_none());
+        assert!(cache.get_user("bob").is_some());
+        assert!(cache.get_policy("readwrite").is_some());
+
+        // A lookup that started before the change must not store what it read
+        cache.insert_user("alice", identity("alice"), generation);
+        assert!(cache.get_user("alice").is_none());
+
+        cache.apply(&change("mapped_policies", "devs", true), "notify");
+        assert!(cache.get_policy_mapping("bob", false).is_none());
+        assert!(cache.get_policy_mapping("devs", true).is_none());
+        assert!(cache.get_user("bob").is_some());
+
+        cache.apply(&change("policies", "readwrite", false), "notify");
+        assert!(cache.get_policy("readwrite").is_none());
+
+        let renamed = IamChange {
+            old_name: Some("bob".to_string()),
+            ..change("users", "robert", false)
+        };
+        cache.apply(&renamed, "notify");
+        assert!(cache.get_user("bob").is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 7);
+        assert_eq!(stats.users + stats.policy_mappings + stats.policies, 0);
+    }
+
+    #[test]
+    fn test_iam_change_payload() {
+        let parsed: IamChange = serde_json::from_str(r#"{"table":"mapped_policies","name":"devs","is_group":true}"#).unwrap();
+        assert_eq!(parsed, change("mapped_policies", "devs", true));
+
+        let parsed: IamChange = serde_json::from_str(r#"{"table":"users","name":"robert","old_name":"bob"}"#).unwrap();
+        assert_eq!(parsed.old_name.as_deref(), Some("bob"));
+    }
+}
@@ -1,6 +1,6 @@
+use crate::error::Result as IamResult;
 use crate::migrations::run_migrations;
-use crate::repository::user::UserRepository;
-use sqlx::PgPool;
+use crate::store::IamStore;
 use tracing::info;
 
 /// Initialize database tables using versioned migrations
@@ -25,30 +25,49 @@ pub async fn init_database(database_url: &str) -> Result<(), Box<dyn std::error:
 /// Initialize root user (ID = 1) if it doesn't exist
 /// 
 /// # Arguments
-/// * `pool` - PostgreSQL connection pool
+/// * `store` - Backend keeping the IAM objects
 /// * `access_key` - Root user access key
 /// * `secret_key` - Root user secret key
 /// 
 /// # Returns
 /// Returns `Ok(())` on success, or an error if initialization fails
 pub async fn init_root_user(
-    pool: &PgPool,
+    store: &dyn IamStore,
     access_key: &str,
     secret_key: &str,
-) -> Result<(), sqlx::Error> {
-    // Check if root user (ID = 1) already exists
-    let exists = UserRepository::exists_by_id(pool, 1).await?;
+) -> IamResult<()> {
+    // Check if the root user already exists
+    let exists = store.find_account(access_key).await?.is_some();
     
     if !exists {
         info!("Creating root user with ID=1...");
-        UserRepository::create_root_user(pool, access_key, secret_key).await?;
+        store.save_root_user(access_key, secret_key).await?;
         info!("Root user created successfully");
     } else {
         info!("Root user already exists, updating credentials...");
-        UserRepository::create_root_user(pool, access_key, secret_key).await?;
+        store.save_root_user(access_key, secret_key).await?;
         info!("Root user credentials updated successfully");
     }
     
     Ok(())
 }
 
+/// Seal the IAM secrets that are stored in plaintext
+///
+/// Entries written before sealing was enabled are sealed under the current key. With `rotate`,
+/// entries sealed under a previous key are re-sealed under the current one as well, after which
+/// the previous keys can be removed from the configuration. Every sealed entry is opened, so a key
+/// that changed without being added to the previous keys is reported before IAM serves requests.
+///
+/// # Arguments
+/// * `store` - Backend keeping the IAM objects
+/// * `rotate` - Whether to re-seal the entries sealed under a previous key
+///
+/// # Returns
+/// Returns the number of entries that were re-written, or an error if one does not open
+/// with the configured keys
+pub async fn seal_secrets(store: &dyn IamStore, rotate: bool) -> IamResult<usize> {
+    let sealed = store.reseal_secrets(rotate).await?;
+    info!("Sealed IAM secrets of {} entries", sealed);
+    Ok(sealed)
+}
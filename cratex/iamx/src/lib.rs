@@ -10,15 +10,18 @@ use crate::sys::IamSys;
 use crate::error::{Error, Result};
 
 // Core modules
+pub mod cache;
 pub mod error;
 pub mod sys;
 pub mod types;
 
-// Database layer
+// Storage layer
 pub mod entity;
 pub mod repository;
 pub mod migrations;
 pub mod init;
+pub mod seal;
+pub mod store;
 
 // Business logic layer
 pub mod manager;
@@ -35,14 +38,19 @@ pub use repository::{
     MappedPolicyRepository, UserIdentityRepository
 };
 
+// Re-export storage backends
+pub use store::{IamBackend, IamStore, ObjectStore, PostgresStore};
+
 // Re-export manager utils for convenience
 pub use manager::utils::get_token_signing_key;
 
 static IAM_SYS: OnceLock<Arc<IamSys>> = OnceLock::new();
 
-/// Initialize IAM system with database connection pool
-pub async fn init_iam_sys(pool: sqlx::PgPool) -> Result<()> {
-    let iam_sys = Arc::new(IamSys::new(pool));
+/// Initialize IAM system with the backend keeping the IAM objects
+pub async fn init_iam_sys(store: Arc<dyn IamStore>) -> Result<()> {
+    let iam_sys = Arc::new(IamSys::new(store));
+    iam_sys.start_cache();
+    iam_sys.start_ldap_sync();
     
     IAM_SYS.set(iam_sys)
         .map_err(|_| Error::other("IAM system already initialized"))?;
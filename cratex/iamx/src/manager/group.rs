@@ -1,7 +1,6 @@
 // Group-related methods for IamSys
 
 use crate::error::{Error, Result, is_err_no_such_group};
-use crate::repository::{GroupRepository, MappedPolicyRepository, UserIdentityRepository};
 use crate::types::{GroupInfo, UserType};
 use crate::sys::STATUS_DISABLED;
 use nebulafx_madmin::GroupDesc;
@@ -23,7 +22,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
     async fn remove_members_from_group(&self, name: &str, members: Vec<String>, update_cache_only: bool) -> Result<OffsetDateTime> {
         // Load group from database
         let mut groups_map = HashMap::new();
-            GroupRepository::load_group(&self.pool, name, &mut groups_map)
+            self.store.load_group(name, &mut groups_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load group: {}", e)))?;
         
@@ -36,7 +35,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
         gi.members = s.difference(&d).map(|v| v.to_string()).collect::<Vec<String>>();
 
         if !update_cache_only {
-            GroupRepository::save(&self.pool, name, &gi).await
+            self.store.save_group(name, &gi).await
             .map_err(|e| Error::other(format!("Failed to save group info: {}", e)))?;
         }
 
@@ -51,7 +50,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
         // Verify all members exist and are valid
         for member in members.iter() {
             let mut users_map = HashMap::new();
-            UserIdentityRepository::load_user(&self.pool, member, UserType::Reg, &mut users_map)
+            self.store.load_user(member, UserType::Reg, &mut users_map)
                 .await
                 .map_err(|e| Error::other(format!("Failed to load user: {}", e)))?;
             
@@ -66,7 +65,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
 
         // Load existing group or create new
         let mut groups_map = HashMap::new();
-        let gi = if GroupRepository::load_group(&self.pool, group, &mut groups_map)
+        let gi = if self.store.load_group(group, &mut groups_map)
             .await
             .is_ok()
         {
@@ -85,7 +84,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
             GroupInfo::new(members.clone())
         };
 
-        GroupRepository::save(&self.pool, group, &gi).await
+        self.store.save_group(group, &gi).await
             .map_err(|e| Error::other(format!("Failed to save group info: {}", e)))?;
 
         Ok(OffsetDateTime::now_utc())
@@ -100,7 +99,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
 
         // Load group from database
         let mut groups_map = HashMap::new();
-            GroupRepository::load_group(&self.pool, name, &mut groups_map)
+            self.store.load_group(name, &mut groups_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load group: {}", e)))?;
         
@@ -114,7 +113,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
             gi.status = STATUS_DISABLED.to_owned();
         }
 
-        GroupRepository::save(&self.pool, name, &gi).await
+        self.store.save_group(name, &gi).await
             .map_err(|e| Error::other(format!("Failed to save group info: {}", e)))?;
 
         Ok(OffsetDateTime::now_utc())
@@ -127,7 +126,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
 
         // Load group from database
         let mut groups_map = HashMap::new();
-            GroupRepository::load_group(&self.pool, name, &mut groups_map)
+            self.store.load_group(name, &mut groups_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load group: {}", e)))?;
         
@@ -146,7 +145,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
 
     async fn list_groups(&self) -> Result<Vec<String>> {
         let mut groups_map = HashMap::new();
-        GroupRepository::load_groups(&self.pool, &mut groups_map)
+        self.store.load_groups(&mut groups_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load groups: {}", e)))?;
         
@@ -158,7 +157,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
         
         // Load all groups
         let mut m = HashMap::new();
-        GroupRepository::load_groups(&self.pool, &mut m).await
+        self.store.load_groups(&mut m).await
             .map_err(|e| Error::other(format!("Failed to load groups: {}", e)))?;
         for group in m.keys() {
             groups_set.insert(group.clone());
@@ -166,7 +165,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
 
         // Load all group policies
         let mut m = HashMap::new();
-        MappedPolicyRepository::load_mapped_policies(&self.pool, UserType::Reg, true, &mut m).await
+        self.store.load_mapped_policies(UserType::Reg, true, &mut m).await
             .map_err(|e| Error::other(format!("Failed to load mapped policies: {}", e)))?;
         for group in m.keys() {
             groups_set.insert(group.clone());
@@ -183,7 +182,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
         // Verify all members exist and are valid
         for member in members.iter() {
             let mut users_map = HashMap::new();
-            UserIdentityRepository::load_user(&self.pool, member, UserType::Reg, &mut users_map)
+            self.store.load_user(member, UserType::Reg, &mut users_map)
                 .await
                 .map_err(|e| Error::other(format!("Failed to load user: {}", e)))?;
             
@@ -198,7 +197,7 @@ impl IamSysGroupExt for crate::sys::IamSys {
 
         // Load group from database
         let mut groups_map = HashMap::new();
-            GroupRepository::load_group(&self.pool, group, &mut groups_map)
+            self.store.load_group(group, &mut groups_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load group: {}", e)))?;
         
@@ -212,13 +211,13 @@ impl IamSysGroupExt for crate::sys::IamSys {
 
         if members.is_empty() {
             // Delete group
-            if let Err(err) = MappedPolicyRepository::delete(&self.pool, group, UserType::Reg, true).await {
-                // sqlx::Error means database error, not "not found"
+            if let Err(err) = self.store.delete_mapped_policy(group, UserType::Reg, true).await {
+                // A storage error, not "not found"
                 return Err(Error::other(format!("Failed to delete mapped policy: {}", err)));
             }
 
-            if let Err(err) = GroupRepository::delete(&self.pool, group).await {
-                // Convert sqlx::Error to Error
+            if let Err(err) = self.store.delete_group(group).await {
+                // Convert the storage error to Error
                 let converted_err = Error::other(format!("{}", err));
                 if !is_err_no_such_group(&converted_err) {
                     return Err(converted_err);
@@ -235,8 +234,8 @@ impl IamSysGroupExt for crate::sys::IamSys {
         // Group notification handler - no cache operations needed
         // Data will be queried directly from database when needed
         let mut m = HashMap::new();
-        if let Err(err) = GroupRepository::load_group(&self.pool, group, &mut m).await {
-            // Convert sqlx::Error to Error
+        if let Err(err) = self.store.load_group(group, &mut m).await {
+            // Convert the storage error to Error
             let converted_err = Error::other(format!("{}", err));
             if !is_err_no_such_group(&converted_err) {
                 return Err(converted_err);
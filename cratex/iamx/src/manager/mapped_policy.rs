@@ -1,7 +1,6 @@
 // Mapped policy-related methods for IamSys
 
 use crate::error::{Error, Result, is_err_no_such_policy};
-use crate::repository::{GroupRepository, MappedPolicyRepository, UserIdentityRepository, PolicyRepository};
 use crate::types::{MappedPolicy, UserType};
 use crate::sys::STATUS_DISABLED;
 use std::collections::{HashMap, HashSet};
@@ -15,6 +14,7 @@ pub(crate) trait IamSysMappedPolicyExt {
         is_group: bool,
         policy_present: bool,
     ) -> Result<(Vec<String>, OffsetDateTime)>;
+    async fn policy_db_load(&self, name: &str, is_group: bool) -> Result<(Vec<String>, OffsetDateTime)>;
     async fn get_mapped_policy(&self, name: &str, is_group: bool) -> Option<MappedPolicy>;
     async fn policy_db_get(&self, name: &str, groups: &Option<Vec<String>>) -> Result<Vec<String>>;
     async fn policy_db_set(&self, name: &str, user_type: UserType, is_group: bool, policy: &str) -> Result<OffsetDateTime>;
@@ -28,10 +28,21 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
         is_group: bool,
         _policy_present: bool,
     ) -> Result<(Vec<String>, OffsetDateTime)> {
+        if let Some(mapping) = self.cache.get_policy_mapping(name, is_group) {
+            return Ok(mapping);
+        }
+
+        let generation = self.cache.generation();
+        let mapping = self.policy_db_load(name, is_group).await?;
+        self.cache.insert_policy_mapping(name, is_group, mapping.clone(), generation);
+        Ok(mapping)
+    }
+
+    async fn policy_db_load(&self, name: &str, is_group: bool) -> Result<(Vec<String>, OffsetDateTime)> {
         if is_group {
             // Load group from database
             let mut groups_map = HashMap::new();
-            GroupRepository::load_group(&self.pool, name, &mut groups_map).await
+            self.store.load_group(name, &mut groups_map).await
                 .map_err(|e| Error::other(format!("Failed to load group: {}", e)))?;
 
             let g = groups_map.get(name)
@@ -44,8 +55,8 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
 
             // Load group policy
             let mut policy_map = HashMap::new();
-                    if let Err(err) = MappedPolicyRepository::load_mapped_policy(&self.pool, name, UserType::Reg, true, &mut policy_map).await {
-                // Convert sqlx::Error to Error
+                    if let Err(err) = self.store.load_mapped_policy(name, UserType::Reg, true, &mut policy_map).await {
+                // Convert the storage error to Error
                 let converted_err = Error::other(format!("{}", err));
                 if !is_err_no_such_policy(&converted_err) {
                     return Err(converted_err);
@@ -61,7 +72,7 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
 
         // Load user from database
         let mut users_map = HashMap::new();
-        UserIdentityRepository::load_user(&self.pool, name, UserType::Reg, &mut users_map).await
+        self.store.load_user(name, UserType::Reg, &mut users_map).await
             .map_err(|e| Error::other(format!("Failed to load user: {}", e)))?;
         
         let u = users_map.get(name).cloned().unwrap_or_default();
@@ -71,13 +82,13 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
 
         // Load user policy
         let mut policy_map = HashMap::new();
-        let mp = if MappedPolicyRepository::load_mapped_policy(&self.pool, name, UserType::Reg, false, &mut policy_map).await.is_ok() {
+        let mp = if self.store.load_mapped_policy(name, UserType::Reg, false, &mut policy_map).await.is_ok() {
             if let Some(p) = policy_map.get(name) {
                 p.clone()
             } else {
                 // Try STS policy
                 let mut sts_policy_map = HashMap::new();
-                if MappedPolicyRepository::load_mapped_policy(&self.pool, name, UserType::Sts, false, &mut sts_policy_map).await.is_ok() {
+                if self.store.load_mapped_policy(name, UserType::Sts, false, &mut sts_policy_map).await.is_ok() {
                     sts_policy_map.get(name).cloned().unwrap_or_default()
                 } else {
                     MappedPolicy::default()
@@ -95,7 +106,7 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
             for group in groups.iter() {
                 // Check if group is disabled
                 let mut groups_map = HashMap::new();
-                if GroupRepository::load_group(&self.pool, group, &mut groups_map).await.is_ok() {
+                if self.store.load_group(group, &mut groups_map).await.is_ok() {
                     if let Some(g) = groups_map.get(group) {
                         if g.status == STATUS_DISABLED {
                             return Ok((Vec::new(), OffsetDateTime::now_utc()));
@@ -105,7 +116,7 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
 
                 // Load group policy
                 let mut group_policy_map = HashMap::new();
-                if MappedPolicyRepository::load_mapped_policy(&self.pool, group, UserType::Reg, true, &mut group_policy_map).await.is_ok() {
+                if self.store.load_mapped_policy(group, UserType::Reg, true, &mut group_policy_map).await.is_ok() {
                     if let Some(p) = group_policy_map.get(group) {
                         p.to_slice().iter().for_each(|v| {
                             policies.insert(v.clone());
@@ -117,7 +128,7 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
 
         // Load groups from group memberships
         let mut all_groups_map = HashMap::new();
-        if GroupRepository::load_groups(&self.pool, &mut all_groups_map).await.is_ok() {
+        if self.store.load_groups(&mut all_groups_map).await.is_ok() {
             for (group_name, group) in all_groups_map.iter() {
                 if group.members.contains(&name.to_string()) {
                     if group.status == STATUS_DISABLED {
@@ -126,7 +137,7 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
 
                     // Load group policy
                     let mut group_policy_map = HashMap::new();
-                    if MappedPolicyRepository::load_mapped_policy(&self.pool, group_name, UserType::Reg, true, &mut group_policy_map).await.is_ok() {
+                    if self.store.load_mapped_policy(group_name, UserType::Reg, true, &mut group_policy_map).await.is_ok() {
                         if let Some(p) = group_policy_map.get(group_name) {
                             p.to_slice().iter().for_each(|v| {
                                 policies.insert(v.clone());
@@ -144,7 +155,7 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
         let mut policy_map = HashMap::new();
         let user_type = if is_group { UserType::Reg } else { UserType::Reg };
         
-                if MappedPolicyRepository::load_mapped_policy(&self.pool, name, user_type, is_group, &mut policy_map)
+                if self.store.load_mapped_policy(name, user_type, is_group, &mut policy_map)
             .await
             .is_ok()
         {
@@ -180,7 +191,7 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
         }
 
         if policy.is_empty() {
-            MappedPolicyRepository::delete(&self.pool, name, user_type, is_group).await
+            self.store.delete_mapped_policy(name, user_type, is_group).await
                 .map_err(|e| Error::other(format!("Failed to delete mapped policy: {}", e)))?;
             return Ok(OffsetDateTime::now_utc());
         }
@@ -191,7 +202,7 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
         let mut policy_docs_map = HashMap::new();
         for p in mp.to_slice() {
             if !p.is_empty() {
-                PolicyRepository::load_policy_doc(&self.pool, &p, &mut policy_docs_map)
+                self.store.load_policy_doc(&p, &mut policy_docs_map)
                     .await
                     .map_err(|e| Error::other(format!("Failed to load policy doc: {}", e)))?;
                 
@@ -201,7 +212,7 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
             }
         }
 
-        MappedPolicyRepository::save(&self.pool, name, user_type, is_group, &mp).await
+        self.store.save_mapped_policy(name, user_type, is_group, &mp).await
             .map_err(|e| Error::other(format!("Failed to save mapped policy: {}", e)))?;
 
         Ok(OffsetDateTime::now_utc())
@@ -211,8 +222,8 @@ impl IamSysMappedPolicyExt for crate::sys::IamSys {
         // Policy mapping notification handler - no cache operations needed
         // Data will be queried directly from database when needed
         let mut m = HashMap::new();
-                    if let Err(err) = MappedPolicyRepository::load_mapped_policy(&self.pool, name, user_type, is_group, &mut m).await {
-            // Convert sqlx::Error to Error
+                    if let Err(err) = self.store.load_mapped_policy(name, user_type, is_group, &mut m).await {
+            // Convert the storage error to Error
             let converted_err = Error::other(format!("{}", err));
             if !is_err_no_such_policy(&converted_err) {
                 return Err(converted_err);
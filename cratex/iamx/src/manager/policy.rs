@@ -1,7 +1,6 @@
 // Policy-related methods for IamSys
 
 use crate::error::{Error, Result, is_err_no_such_policy};
-use crate::repository::{PolicyRepository, MappedPolicyRepository, UserIdentityRepository, GroupRepository};
 use crate::manager::utils::{set_default_canned_policies};
 use crate::types::{MappedPolicy, UserType};
 use nebulafx_madmin::AccountStatus;
@@ -20,6 +19,7 @@ pub(crate) trait IamSysPolicyExt {
     async fn list_policy_docs_internal(&self, bucket_name: &str) -> Result<HashMap<String, PolicyDoc>>;
     async fn get_bucket_users(&self, bucket_name: &str) -> Result<HashMap<String, nebulafx_madmin::UserInfo>>;
     async fn policy_notification_handler(&self, policy: &str) -> Result<()>;
+    async fn load_cached_policy_doc(&self, name: &str, m: &mut HashMap<String, PolicyDoc>) -> Result<()>;
 }
 
 impl IamSysPolicyExt for crate::sys::IamSys {
@@ -39,7 +39,7 @@ impl IamSysPolicyExt for crate::sys::IamSys {
             }
 
             if !policy_docs_map.contains_key(&policy) {
-                PolicyRepository::load_policy_doc(&self.pool, &policy, &mut policy_docs_map)
+                self.load_cached_policy_doc(&policy, &mut policy_docs_map)
                     .await
                     .map_err(|e| Error::other(format!("Failed to load policy doc: {}", e)))?;
             }
@@ -62,7 +62,7 @@ impl IamSysPolicyExt for crate::sys::IamSys {
         }
 
         let mut policy_docs_map = HashMap::new();
-        PolicyRepository::load_policy_doc(&self.pool, name, &mut policy_docs_map)
+        self.store.load_policy_doc(name, &mut policy_docs_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load policy doc: {}", e)))?;
         
@@ -77,7 +77,7 @@ impl IamSysPolicyExt for crate::sys::IamSys {
         if is_from_notify {
             // Check if policy is in use by users
             let mut user_policies = HashMap::new();
-            MappedPolicyRepository::load_mapped_policies(&self.pool, UserType::Reg, false, &mut user_policies)
+            self.store.load_mapped_policies(UserType::Reg, false, &mut user_policies)
                 .await
                 .map_err(|e| Error::other(format!("Failed to load user policies: {}", e)))?;
 
@@ -86,7 +86,7 @@ impl IamSysPolicyExt for crate::sys::IamSys {
                 if v.policy_set().contains(name) {
                     // Verify user still exists
                     let mut users_map = HashMap::new();
-                    if UserIdentityRepository::load_user(&self.pool, k, UserType::Reg, &mut users_map)
+                    if self.store.load_user(k, UserType::Reg, &mut users_map)
                         .await
                         .is_ok()
                     {
@@ -99,7 +99,7 @@ impl IamSysPolicyExt for crate::sys::IamSys {
 
             // Check if policy is in use by groups
             let mut group_policies = HashMap::new();
-            MappedPolicyRepository::load_mapped_policies(&self.pool, UserType::Reg, true, &mut group_policies)
+            self.store.load_mapped_policies(UserType::Reg, true, &mut group_policies)
                 .await
                 .map_err(|e| Error::other(format!("Failed to load group policies: {}", e)))?;
 
@@ -114,16 +114,11 @@ impl IamSysPolicyExt for crate::sys::IamSys {
                 return Err(Error::PolicyInUse);
             }
 
-            if let Err(err) = PolicyRepository::delete(&self.pool, name).await {
-                let sqlx_err = err;
-                if sqlx_err.as_database_error().is_none() {
-                    return Ok(());
-                }
-                return Err(Error::other(format!("Failed to delete policy: {}", sqlx_err)));
-            }
+            self.store.delete_policy_doc(name).await
+                .map_err(|e| Error::other(format!("Failed to delete policy: {}", e)))?;
         } else {
             // Direct delete
-            PolicyRepository::delete(&self.pool, name).await
+            self.store.delete_policy_doc(name).await
                 .map_err(|e| Error::other(format!("Failed to delete policy: {}", e)))?;
         }
 
@@ -137,7 +132,7 @@ impl IamSysPolicyExt for crate::sys::IamSys {
 
         // Try to load existing policy
         let mut policy_docs_map = HashMap::new();
-        let policy_doc = if PolicyRepository::load_policy_doc(&self.pool, name, &mut policy_docs_map)
+        let policy_doc = if self.store.load_policy_doc(name, &mut policy_docs_map)
             .await
             .is_ok()
         {
@@ -152,7 +147,7 @@ impl IamSysPolicyExt for crate::sys::IamSys {
             PolicyDoc::new(policy)
         };
 
-        PolicyRepository::save(&self.pool, name, &policy_doc).await
+        self.store.save_policy_doc(name, &policy_doc).await
             .map_err(|e| Error::other(format!("Failed to save policy: {}", e)))?;
 
         Ok(OffsetDateTime::now_utc())
@@ -161,7 +156,7 @@ impl IamSysPolicyExt for crate::sys::IamSys {
     async fn list_polices(&self, bucket_name: &str) -> Result<HashMap<String, Policy>> {
         let mut m = HashMap::new();
 
-        PolicyRepository::load_policy_docs(&self.pool, &mut m).await
+        self.store.load_policy_docs(&mut m).await
             .map_err(|e| Error::other(format!("Failed to load policy docs: {}", e)))?;
         set_default_canned_policies(&mut m);
 
@@ -185,7 +180,7 @@ impl IamSysPolicyExt for crate::sys::IamSys {
             }
 
             if !policy_docs_map.contains_key(&policy) {
-                let _ = PolicyRepository::load_policy_doc(&self.pool, &policy, &mut policy_docs_map)
+                let _ = self.load_cached_policy_doc(&policy, &mut policy_docs_map)
                     .await
                     .map_err(|e| Error::other(format!("Failed to load policy doc: {}", e)));
             }
@@ -202,7 +197,7 @@ impl IamSysPolicyExt for crate::sys::IamSys {
     async fn list_policy_docs(&self, bucket_name: &str) -> Result<HashMap<String, PolicyDoc>> {
         let mut m = HashMap::new();
 
-        PolicyRepository::load_policy_docs(&self.pool, &mut m).await
+        self.store.load_policy_docs(&mut m).await
             .map_err(|e| Error::other(format!("Failed to load policy docs: {}", e)))?;
         set_default_canned_policies(&mut m);
 
@@ -217,7 +212,7 @@ impl IamSysPolicyExt for crate::sys::IamSys {
     async fn list_policy_docs_internal(&self, bucket_name: &str) -> Result<HashMap<String, PolicyDoc>> {
         let mut m = HashMap::new();
 
-        PolicyRepository::load_policy_docs(&self.pool, &mut m).await
+        self.store.load_policy_docs(&mut m).await
             .map_err(|e| Error::other(format!("Failed to load policy docs: {}", e)))?;
         set_default_canned_policies(&mut m);
 
@@ -234,31 +229,31 @@ impl IamSysPolicyExt for crate::sys::IamSys {
         
         // Load all Reg users
         let mut users_map = HashMap::new();
-        UserIdentityRepository::load_users(&self.pool, UserType::Reg, &mut users_map)
+        self.store.load_users(UserType::Reg, &mut users_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load users: {}", e)))?;
 
         // Load all user policies
         let mut user_policies = HashMap::new();
-        MappedPolicyRepository::load_mapped_policies(&self.pool, UserType::Reg, false, &mut user_policies)
+        self.store.load_mapped_policies(UserType::Reg, false, &mut user_policies)
             .await
             .map_err(|e| Error::other(format!("Failed to load user policies: {}", e)))?;
 
         // Load all groups
         let mut groups_map = HashMap::new();
-        GroupRepository::load_groups(&self.pool, &mut groups_map)
+        self.store.load_groups(&mut groups_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load groups: {}", e)))?;
 
         // Load all group policies
         let mut group_policies = HashMap::new();
-        MappedPolicyRepository::load_mapped_policies(&self.pool, UserType::Reg, true, &mut group_policies)
+        self.store.load_mapped_policies(UserType::Reg, true, &mut group_policies)
             .await
             .map_err(|e| Error::other(format!("Failed to load group policies: {}", e)))?;
 
         // Load all policy docs
         let mut policy_docs = HashMap::new();
-        PolicyRepository::load_policy_docs(&self.pool, &mut policy_docs)
+        self.store.load_policy_docs(&mut policy_docs)
             .await
             .map_err(|e| Error::other(format!("Failed to load policy docs: {}", e)))?;
 
@@ -325,8 +320,8 @@ impl IamSysPolicyExt for crate::sys::IamSys {
         // Policy notification handler - no cache operations needed
         // Data will be queried directly from database when needed
         let mut m = HashMap::new();
-        if let Err(err) = PolicyRepository::load_policy_doc(&self.pool, policy, &mut m).await {
-            // Convert sqlx::Error to Error
+        if let Err(err) = self.store.load_policy_doc(policy, &mut m).await {
+            // Convert the storage error to Error
             let converted_err = Error::other(format!("{}", err));
             if !is_err_no_such_policy(&converted_err) {
                 return Err(converted_err);
@@ -336,5 +331,20 @@ impl IamSysPolicyExt for crate::sys::IamSys {
 
         Ok(())
     }
+
+    async fn load_cached_policy_doc(&self, name: &str, m: &mut HashMap<String, PolicyDoc>) -> Result<()> {
+        if let Some(doc) = self.cache.get_policy(name) {
+            m.insert(name.to_string(), doc);
+            return Ok(());
+        }
+
+        let generation = self.cache.generation();
+        self.store.load_policy_doc(name, m).await?;
+        if let Some(doc) = m.get(name) {
+            self.cache.insert_policy(name, doc.clone(), generation);
+        }
+
+        Ok(())
+    }
 }
 
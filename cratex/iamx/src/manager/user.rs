@@ -1,7 +1,6 @@
 // User-related methods for IamSys
 
 use crate::error::{Error, Result};
-use crate::repository::{UserIdentityRepository, MappedPolicyRepository};
 use crate::types::UserType;
 use nebulafx_madmin::{AccountStatus, AddOrUpdateUserReq};
 use nebulafx_policy::auth::{Credentials, UserIdentity};
@@ -29,7 +28,7 @@ impl IamSysUserExt for crate::sys::IamSys {
     async fn get_user(&self, access_key: &str) -> Option<UserIdentity> {
         // Try Reg user first
         let mut users_map = HashMap::new();
-        if UserIdentityRepository::load_user(&self.pool, access_key, UserType::Reg, &mut users_map)
+        if self.store.load_user(access_key, UserType::Reg, &mut users_map)
             .await
             .is_ok()
         {
@@ -40,7 +39,7 @@ impl IamSysUserExt for crate::sys::IamSys {
 
         // Try Sts user
         let mut sts_users_map = HashMap::new();
-        if UserIdentityRepository::load_user(&self.pool, access_key, UserType::Sts, &mut sts_users_map)
+        if self.store.load_user(access_key, UserType::Sts, &mut sts_users_map)
             .await
             .is_ok()
         {
@@ -51,7 +50,7 @@ impl IamSysUserExt for crate::sys::IamSys {
 
         // Try Svc user
         let mut svc_users_map = HashMap::new();
-        if UserIdentityRepository::load_user(&self.pool, access_key, UserType::Svc, &mut svc_users_map)
+        if self.store.load_user(access_key, UserType::Svc, &mut svc_users_map)
             .await
             .is_ok()
         {
@@ -66,7 +65,7 @@ impl IamSysUserExt for crate::sys::IamSys {
     async fn list_temp_accounts(&self, access_key: &str) -> Result<Vec<UserIdentity>> {
         // Check if parent user exists
         let mut parent_users = HashMap::new();
-        UserIdentityRepository::load_user(&self.pool, access_key, UserType::Reg, &mut parent_users)
+        self.store.load_user(access_key, UserType::Reg, &mut parent_users)
             .await
             .map_err(|e| Error::other(format!("Failed to load parent user: {}", e)))?;
         
@@ -76,7 +75,7 @@ impl IamSysUserExt for crate::sys::IamSys {
 
         // Load all STS users with this parent
         let mut all_sts_users = HashMap::new();
-        UserIdentityRepository::load_users(&self.pool, UserType::Sts, &mut all_sts_users)
+        self.store.load_users(UserType::Sts, &mut all_sts_users)
             .await
             .map_err(|e| Error::other(format!("Failed to load STS users: {}", e)))?;
 
@@ -95,7 +94,7 @@ impl IamSysUserExt for crate::sys::IamSys {
 
     async fn list_sts_accounts(&self, access_key: &str) -> Result<Vec<Credentials>> {
         let mut all_sts_users = HashMap::new();
-        UserIdentityRepository::load_users(&self.pool, UserType::Sts, &mut all_sts_users)
+        self.store.load_users(UserType::Sts, &mut all_sts_users)
             .await
             .map_err(|e| Error::other(format!("Failed to load STS users: {}", e)))?;
 
@@ -115,7 +114,7 @@ impl IamSysUserExt for crate::sys::IamSys {
 
     async fn list_service_accounts(&self, access_key: &str) -> Result<Vec<Credentials>> {
         let mut all_svc_users = HashMap::new();
-        UserIdentityRepository::load_users(&self.pool, UserType::Svc, &mut all_svc_users)
+        self.store.load_users(UserType::Svc, &mut all_svc_users)
             .await
             .map_err(|e| Error::other(format!("Failed to load service account users: {}", e)))?;
 
@@ -144,7 +143,7 @@ impl IamSysUserExt for crate::sys::IamSys {
 
         // Check if service account already exists
         let mut existing_users = HashMap::new();
-        if UserIdentityRepository::load_user(&self.pool, &cred.access_key, UserType::Svc, &mut existing_users)
+        if self.store.load_user(&cred.access_key, UserType::Svc, &mut existing_users)
             .await
             .is_ok()
         {
@@ -155,7 +154,7 @@ impl IamSysUserExt for crate::sys::IamSys {
 
         let u = UserIdentity::new(cred);
 
-        UserIdentityRepository::save(&self.pool, &u.credentials.access_key, UserType::Svc, &u, None).await
+        self.store.save_user_identity(&u.credentials.access_key, UserType::Svc, &u, None).await
             .map_err(|e| Error::other(format!("Failed to save user identity: {}", e)))?;
 
         Ok(OffsetDateTime::now_utc())
@@ -175,7 +174,7 @@ impl IamSysUserExt for crate::sys::IamSys {
         use base64_simd;
 
         let mut users_map = HashMap::new();
-        UserIdentityRepository::load_user(&self.pool, name, UserType::Svc, &mut users_map)
+        self.store.load_user(name, UserType::Svc, &mut users_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load service account: {}", e)))?;
         
@@ -255,7 +254,7 @@ impl IamSysUserExt for crate::sys::IamSys {
         cr.session_token = jwt_sign(&m, &cr.secret_key)?;
 
         let u = UserIdentity::new(cr);
-        UserIdentityRepository::save(&self.pool, &u.credentials.access_key, UserType::Svc, &u, None).await
+        self.store.save_user_identity(&u.credentials.access_key, UserType::Svc, &u, None).await
             .map_err(|e| Error::other(format!("Failed to save user identity: {}", e)))?;
 
         Ok(OffsetDateTime::now_utc())
@@ -277,14 +276,13 @@ impl IamSysUserExt for crate::sys::IamSys {
 
         if let Some(policy) = policy_name {
             use crate::types::MappedPolicy;
-            use crate::repository::PolicyRepository;
             let mp = MappedPolicy::new(policy);
             
             // Verify policy exists by loading it
             let mut policy_docs = HashMap::new();
             for p in mp.to_slice() {
                 if !p.is_empty() {
-                    PolicyRepository::load_policy_doc(&self.pool, &p, &mut policy_docs)
+                    self.store.load_policy_doc(&p, &mut policy_docs)
                         .await
                         .map_err(|e| Error::other(format!("Failed to load policy doc: {}", e)))?;
                 }
@@ -294,23 +292,22 @@ impl IamSysUserExt for crate::sys::IamSys {
                 return Err(Error::other(format!("Required policy not found: {}", Error::NoSuchPolicy)));
             }
 
-            MappedPolicyRepository::save(&self.pool, &cred.parent_user, UserType::Sts, false, &mp).await
+            self.store.save_mapped_policy(&cred.parent_user, UserType::Sts, false, &mp).await
                 .map_err(|e| Error::other(format!("Failed to save mapped policy: {}", e)))?;
         }
 
         let u = UserIdentity::new(cred.clone());
-        UserIdentityRepository::save(&self.pool, access_key, UserType::Sts, &u, None).await
+        self.store.save_user_identity(access_key, UserType::Sts, &u, None).await
             .map_err(|e| Error::other(format!("Failed to save user identity: {}", e)))?;
 
         Ok(OffsetDateTime::now_utc())
     }
 
     async fn get_user_info(&self, name: &str) -> Result<nebulafx_madmin::UserInfo> {
-        use crate::repository::{MappedPolicyRepository, GroupRepository};
         
         // Load user
         let mut users_map = HashMap::new();
-        UserIdentityRepository::load_user(&self.pool, name, UserType::Reg, &mut users_map)
+        self.store.load_user(name, UserType::Reg, &mut users_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load user: {}", e)))?;
         
@@ -335,7 +332,7 @@ impl IamSysUserExt for crate::sys::IamSys {
 
         // Load mapped policy
         let mut policy_map = HashMap::new();
-        if MappedPolicyRepository::load_mapped_policy(&self.pool, name, UserType::Reg, false, &mut policy_map)
+        if self.store.load_mapped_policy(name, UserType::Reg, false, &mut policy_map)
             .await
             .is_ok()
         {
@@ -347,7 +344,7 @@ impl IamSysUserExt for crate::sys::IamSys {
 
         // Load group memberships
         let mut groups_map = HashMap::new();
-        GroupRepository::load_groups(&self.pool, &mut groups_map)
+        self.store.load_groups(&mut groups_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load groups: {}", e)))?;
         
@@ -367,25 +364,24 @@ impl IamSysUserExt for crate::sys::IamSys {
     // Returns all users (not STS or service accounts)
     async fn get_users(&self) -> Result<std::collections::HashMap<String, nebulafx_madmin::UserInfo>> {
         use std::collections::HashMap;
-        use crate::repository::{MappedPolicyRepository, GroupRepository};
 
         let mut m = HashMap::new();
 
         // Load all Reg users
         let mut users_map = HashMap::new();
-        UserIdentityRepository::load_users(&self.pool, UserType::Reg, &mut users_map)
+        self.store.load_users(UserType::Reg, &mut users_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load users: {}", e)))?;
 
         // Load all user policies
         let mut policies_map = HashMap::new();
-        MappedPolicyRepository::load_mapped_policies(&self.pool, UserType::Reg, false, &mut policies_map)
+        self.store.load_mapped_policies(UserType::Reg, false, &mut policies_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load user policies: {}", e)))?;
 
         // Load all groups
         let mut groups_map = HashMap::new();
-        GroupRepository::load_groups(&self.pool, &mut groups_map)
+        self.store.load_groups(&mut groups_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load groups: {}", e)))?;
 
@@ -432,7 +428,7 @@ impl IamSysUserExt for crate::sys::IamSys {
 
         // Check if user already exists
         let mut existing_users = HashMap::new();
-        if UserIdentityRepository::load_user(&self.pool, access_key, UserType::Reg, &mut existing_users)
+        if self.store.load_user(access_key, UserType::Reg, &mut existing_users)
             .await
             .is_ok()
         {
@@ -457,7 +453,7 @@ impl IamSysUserExt for crate::sys::IamSys {
             ..Default::default()
         });
 
-        UserIdentityRepository::save(&self.pool, access_key, UserType::Reg, &user_entry, None).await
+        self.store.save_user_identity(access_key, UserType::Reg, &user_entry, None).await
             .map_err(|e| Error::other(format!("Failed to save user identity: {}", e)))?;
 
         Ok(OffsetDateTime::now_utc())
@@ -465,7 +461,6 @@ impl IamSysUserExt for crate::sys::IamSys {
 
     async fn delete_user(&self, access_key: &str, utype: UserType) -> Result<()> {
         use super::group::IamSysGroupExt;
-        use crate::repository::GroupRepository;
         
         if access_key.is_empty() {
             return Err(Error::InvalidArgument);
@@ -474,7 +469,7 @@ impl IamSysUserExt for crate::sys::IamSys {
         if utype == UserType::Reg {
             // Find groups this user belongs to
             let mut groups_map = HashMap::new();
-            GroupRepository::load_groups(&self.pool, &mut groups_map)
+            self.store.load_groups(&mut groups_map)
                 .await
                 .map_err(|e| Error::other(format!("Failed to load groups: {}", e)))?;
             
@@ -488,38 +483,38 @@ impl IamSysUserExt for crate::sys::IamSys {
 
             // Find and delete all child accounts (service accounts and STS accounts)
             let mut all_svc_users = HashMap::new();
-            UserIdentityRepository::load_users(&self.pool, UserType::Svc, &mut all_svc_users)
+            self.store.load_users(UserType::Svc, &mut all_svc_users)
                 .await
                 .map_err(|e| Error::other(format!("Failed to load service accounts: {}", e)))?;
             
             for (_, v) in all_svc_users.iter() {
                 let u = &v.credentials;
                 if u.parent_user.as_str() == access_key {
-                    let _ = UserIdentityRepository::delete(&self.pool, &u.access_key, UserType::Svc).await
+                    let _ = self.store.delete_user_identity(&u.access_key, UserType::Svc).await
                         .map_err(|e| Error::other(format!("Failed to delete service account: {}", e)));
                 }
             }
 
             let mut all_sts_users = HashMap::new();
-            UserIdentityRepository::load_users(&self.pool, UserType::Sts, &mut all_sts_users)
+            self.store.load_users(UserType::Sts, &mut all_sts_users)
                 .await
                 .map_err(|e| Error::other(format!("Failed to load STS accounts: {}", e)))?;
             
             for (_, v) in all_sts_users.iter() {
                 let u = &v.credentials;
                 if u.parent_user.as_str() == access_key && u.is_temp() {
-                    let _ = UserIdentityRepository::delete(&self.pool, &u.access_key, UserType::Sts).await
+                    let _ = self.store.delete_user_identity(&u.access_key, UserType::Sts).await
                         .map_err(|e| Error::other(format!("Failed to delete STS account: {}", e)));
                 }
             }
         }
 
         // Delete mapped policy
-        let _ = MappedPolicyRepository::delete(&self.pool, access_key, utype, false).await;
+        let _ = self.store.delete_mapped_policy(access_key, utype, false).await;
 
         // Delete user identity
-        if let Err(err) = UserIdentityRepository::delete(&self.pool, access_key, utype).await {
-            // sqlx::Error means database error, not "not found"
+        if let Err(err) = self.store.delete_user_identity(access_key, utype).await {
+            // A storage error, not "not found"
             return Err(Error::other(format!("Failed to delete user identity: {}", err)));
         }
 
@@ -532,7 +527,7 @@ impl IamSysUserExt for crate::sys::IamSys {
         }
 
         let mut users_map = HashMap::new();
-        UserIdentityRepository::load_user(&self.pool, access_key, UserType::Reg, &mut users_map)
+        self.store.load_user(access_key, UserType::Reg, &mut users_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load user: {}", e)))?;
         
@@ -546,7 +541,7 @@ impl IamSysUserExt for crate::sys::IamSys {
 
         let u = UserIdentity::from(cred);
 
-        UserIdentityRepository::save(&self.pool, access_key, UserType::Reg, &u, None).await
+        self.store.save_user_identity(access_key, UserType::Reg, &u, None).await
             .map_err(|e| Error::other(format!("Failed to save user identity: {}", e)))?;
 
         Ok(())
@@ -564,7 +559,7 @@ impl IamSysUserExt for crate::sys::IamSys {
         }
 
         let mut users_map = HashMap::new();
-        UserIdentityRepository::load_user(&self.pool, access_key, UserType::Reg, &mut users_map)
+        self.store.load_user(access_key, UserType::Reg, &mut users_map)
             .await
             .map_err(|e| Error::other(format!("Failed to load user: {}", e)))?;
         
@@ -591,7 +586,7 @@ impl IamSysUserExt for crate::sys::IamSys {
             ..Default::default()
         });
 
-        UserIdentityRepository::save(&self.pool, access_key, UserType::Reg, &user_entry, None).await
+        self.store.save_user_identity(access_key, UserType::Reg, &user_entry, None).await
             .map_err(|e| Error::other(format!("Failed to save user identity: {}", e)))?;
 
         Ok(OffsetDateTime::now_utc())
@@ -599,12 +594,11 @@ impl IamSysUserExt for crate::sys::IamSys {
 
     async fn user_notification_handler(&self, name: &str, user_type: UserType) -> Result<()> {
         use crate::error::is_err_no_such_user;
-        use crate::repository::GroupRepository;
         use std::collections::HashMap;
 
         let mut m = HashMap::new();
-        if let Err(err) = UserIdentityRepository::load_user(&self.pool, name, user_type, &mut m).await {
-            // Convert sqlx::Error to Error
+        if let Err(err) = self.store.load_user(name, user_type, &mut m).await {
+            // Convert the storage error to Error
             let converted_err = Error::other(format!("{}", err));
             if !is_err_no_such_user(&converted_err) {
                 return Err(converted_err);
@@ -613,7 +607,7 @@ impl IamSysUserExt for crate::sys::IamSys {
             // User not found - clean up related data
             // Find and remove from groups
             let mut groups_map = HashMap::new();
-            if GroupRepository::load_groups(&self.pool, &mut groups_map).await.is_ok() {
+            if self.store.load_groups(&mut groups_map).await.is_ok() {
                 for (group_name, group) in groups_map.iter() {
                     if group.members.contains(&name.to_string()) {
                         use super::group::IamSysGroupExt;
@@ -631,11 +625,11 @@ impl IamSysUserExt for crate::sys::IamSys {
             if user_type == UserType::Reg {
                 // Delete service accounts
                 let mut all_svc_users = HashMap::new();
-                if UserIdentityRepository::load_users(&self.pool, UserType::Svc, &mut all_svc_users).await.is_ok() {
+                if self.store.load_users(UserType::Svc, &mut all_svc_users).await.is_ok() {
                     for (_, v) in all_svc_users.iter() {
                         let u = &v.credentials;
                         if u.parent_user.as_str() == name {
-                            let _ = UserIdentityRepository::delete(&self.pool, &u.access_key, UserType::Svc).await
+                            let _ = self.store.delete_user_identity(&u.access_key, UserType::Svc).await
                                 .map_err(|e| Error::other(format!("Failed to delete service account: {}", e)));
                         }
                     }
@@ -643,11 +637,11 @@ impl IamSysUserExt for crate::sys::IamSys {
 
                 // Delete STS accounts
                 let mut all_sts_users = HashMap::new();
-                if UserIdentityRepository::load_users(&self.pool, UserType::Sts, &mut all_sts_users).await.is_ok() {
+                if self.store.load_users(UserType::Sts, &mut all_sts_users).await.is_ok() {
                     for (_, v) in all_sts_users.iter() {
                         let u = &v.credentials;
                         if u.parent_user.as_str() == name && u.is_temp() {
-                            let _ = UserIdentityRepository::delete(&self.pool, &u.access_key, UserType::Sts).await
+                            let _ = self.store.delete_user_identity(&u.access_key, UserType::Sts).await
                                 .map_err(|e| Error::other(format!("Failed to delete STS account: {}", e)));
                         }
                     }
@@ -10,3 +10,6 @@ pub use group::GroupRepository;
 pub use mapped_policy::MappedPolicyRepository;
 pub use user_identity::UserIdentityRepository;
 
+/// Postgres channel the `notify_iam_change` trigger publishes every write to the IAM tables on
+pub const IAM_NOTIFY_CHANNEL: &str = "iam_changes";
+
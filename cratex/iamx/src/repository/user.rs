@@ -1,6 +1,21 @@
 use crate::entity::UserEntity;
+use crate::seal::sealer;
 use sqlx::PgPool;
 
+/// Secret keys are sealed before they are written, see `crate::seal`
+fn seal_secret_key(secret_key: &str) -> Result<String, sqlx::Error> {
+    sealer()
+        .and_then(|s| s.seal(secret_key))
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))
+}
+
+fn open_secret_key(mut user: UserEntity) -> Result<UserEntity, sqlx::Error> {
+    user.secret_key = sealer()
+        .and_then(|s| s.open(&user.secret_key))
+        .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+    Ok(user)
+}
+
 /// Repository for user database operations
 pub struct UserRepository;
 
@@ -39,7 +54,9 @@ impl UserRepository {
         )
         .bind(id)
         .fetch_optional(pool)
-        .await
+        .await?
+        .map(open_secret_key)
+        .transpose()
     }
     
     /// Find user by access key
@@ -52,7 +69,9 @@ impl UserRepository {
         )
         .bind(access_key)
         .fetch_optional(pool)
-        .await
+        .await?
+        .map(open_secret_key)
+        .transpose()
     }
     
     /// Create a new user
@@ -67,10 +86,11 @@ impl UserRepository {
             "INSERT INTO users (access_key, secret_key, user_type) VALUES ($1, $2, $3) RETURNING id, access_key, secret_key, user_type, created_at, updated_at"
         )
         .bind(access_key)
-        .bind(secret_key)
+        .bind(seal_secret_key(secret_key)?)
         .bind(user_type)
         .fetch_one(pool)
         .await
+        .and_then(open_secret_key)
     }
     
     /// Create or update root user (ID = 1)
@@ -92,9 +112,10 @@ impl UserRepository {
             "#
         )
         .bind(access_key)
-        .bind(secret_key)
+        .bind(seal_secret_key(secret_key)?)
         .fetch_one(pool)
         .await
+        .and_then(open_secret_key)
     }
     
     /// Update user by ID
@@ -146,14 +167,14 @@ impl UserRepository {
             query_builder = query_builder.bind(ak);
         }
         if let Some(sk) = secret_key {
-            query_builder = query_builder.bind(sk);
+            query_builder = query_builder.bind(seal_secret_key(sk)?);
         }
         if let Some(ut) = user_type {
             query_builder = query_builder.bind(ut);
         }
         query_builder = query_builder.bind(id);
         
-        query_builder.fetch_optional(pool).await
+        query_builder.fetch_optional(pool).await?.map(open_secret_key).transpose()
     }
     
     /// Hard delete user by ID
@@ -192,9 +213,37 @@ impl UserRepository {
         .bind(limit)
         .bind(offset)
         .fetch_all(pool)
-        .await
+        .await?
+        .into_iter()
+        .map(open_secret_key)
+        .collect()
     }
     
+    /// Secret keys as they are stored, for sealing the rows written before sealing was enabled
+    pub async fn list_stored_secret_keys(pool: &PgPool) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, String)>("SELECT id, secret_key FROM users ORDER BY id")
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Replaces a stored secret key, unless the row was written since it was read
+    pub async fn replace_stored_secret_key(
+        pool: &PgPool,
+        id: i64,
+        stored: &str,
+        secret_key: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let rows_affected = sqlx::query("UPDATE users SET secret_key = $1 WHERE id = $2 AND secret_key = $3")
+            .bind(secret_key)
+            .bind(id)
+            .bind(stored)
+            .execute(pool)
+            .await?
+            .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
+
     /// Count total number of users
     pub async fn count(pool: &PgPool) -> Result<i64, sqlx::Error> {
         let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
@@ -1,8 +1,17 @@
 use crate::entity::UserIdentityEntity;
+use crate::seal::sealer;
 use crate::types::UserType;
+use serde_json::Value;
 use sqlx::PgPool;
 use std::collections::HashMap;
 
+/// The secret key and session token of an identity are sealed before it is written, see `crate::seal`
+fn open_identity(identity_data: Value) -> Result<nebulafx_policy::auth::UserIdentity, sqlx::Error> {
+    sealer()
+        .and_then(|s| s.open_identity(identity_data))
+        .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
+
 /// Repository for user identity database operations
 pub struct UserIdentityRepository;
 
@@ -30,8 +39,7 @@ impl UserIdentityRepository {
 
         match entity {
             Some(e) => {
-                let user_identity: nebulafx_policy::auth::UserIdentity = serde_json::from_value(e.identity_data)
-                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+                let user_identity = open_identity(e.identity_data)?;
                 Ok(Some(user_identity))
             }
             None => Ok(None),
@@ -53,8 +61,9 @@ impl UserIdentityRepository {
             UserType::None => "None",
         };
 
-        let identity_json = serde_json::to_value(user_identity)
-            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let identity_json = sealer()
+            .and_then(|s| s.seal_identity(user_identity))
+            .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
 
         let ttl_i32 = ttl.map(|v| v as i32);
 
@@ -137,14 +146,33 @@ impl UserIdentityRepository {
         .await?;
 
         for entity in entities {
-            let user_identity: nebulafx_policy::auth::UserIdentity = serde_json::from_value(entity.identity_data)
-                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+            let user_identity = open_identity(entity.identity_data)?;
             m.insert(entity.name, user_identity);
         }
 
         Ok(())
     }
 
+    /// Identities as they are stored, for sealing the rows written before sealing was enabled
+    pub async fn list_stored(pool: &PgPool) -> Result<Vec<(i64, Value)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, Value)>("SELECT id, identity_data FROM user_identities ORDER BY id")
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Replaces a stored identity, unless the row was written since it was read
+    pub async fn replace_stored(pool: &PgPool, id: i64, stored: &Value, identity_data: &Value) -> Result<bool, sqlx::Error> {
+        let rows_affected = sqlx::query("UPDATE user_identities SET identity_data = $1 WHERE id = $2 AND identity_data = $3")
+            .bind(identity_data)
+            .bind(id)
+            .bind(stored)
+            .execute(pool)
+            .await?
+            .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
+
     /// Load secret key for a user
     pub async fn load_secret_key(
         pool: &PgPool,
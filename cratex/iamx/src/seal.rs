@@ -0,0 +1,378 @@
+//! Secret keys and session tokens are sealed before the repositories write them and opened when they
+//! read them, so a dump of the IAM tables does not expose any credential. The AES-256-GCM key is
+//! derived from the configured key once, when the sealer is created, and every value is sealed under
+//! it with a random nonce; the keys of earlier rotations are only used to open what is not re-sealed yet.
+
+use crate::error::{Error, Result};
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, KeyInit},
+};
+use nebulafx_crypto::decrypt_data;
+use nebulafx_policy::auth::UserIdentity;
+use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::sync::OnceLock;
+
+/// Prefix of sealed values, values without it were written before sealing was enabled
+pub const SEALED_PREFIX: &str = "nfxenc:v2:";
+
+/// Prefix of values of the first format, which ran the password KDF for every value. They still open,
+/// and the startup migration seals them again in the current format.
+const LEGACY_SEALED_PREFIX: &str = "nfxenc:v1:";
+
+/// Field of `identity_data` holding the sealed secrets of the credentials, which are left empty
+const SEALED_SECRETS_FIELD: &str = "sealed_secrets";
+
+/// Salt and rounds of the key derivation, it runs once per configured key
+const KEY_DERIVATION_SALT: &[u8] = b"nebulafx-iam-seal";
+const KEY_DERIVATION_ROUNDS: u32 = 8192;
+
+const NONCE_LEN: usize = 12;
+
+static SEALER: OnceLock<SecretSealer> = OnceLock::new();
+
+#[cfg(test)]
+thread_local! {
+    static KEY_DERIVATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Sets the sealer used by the repositories, once at startup before any IAM row is read or written
+pub fn init_sealer(sealer: SecretSealer) -> Result<()> {
+    SEALER
+        .set(sealer)
+        .map_err(|_| Error::other("IAM secret sealer already initialized"))
+}
+
+pub fn sealer() -> Result<&'static SecretSealer> {
+    SEALER
+        .get()
+        .ok_or_else(|| Error::other("IAM secret sealer is not initialized"))
+}
+
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX) || value.starts_with(LEGACY_SEALED_PREFIX)
+}
+
+#[derive(Serialize, Deserialize)]
+struct IdentitySecrets {
+    secret_key: String,
+    session_token: String,
+}
+
+/// A configured key: the derived cipher, and the key itself for values of the legacy format
+struct SealingKey {
+    cipher: Aes256Gcm,
+    password: Vec<u8>,
+}
+
+impl SealingKey {
+    fn derive(password: &str) -> Self {
+        #[cfg(test)]
+        KEY_DERIVATIONS.with(|count| count.set(count.get() + 1));
+
+        let mut key = [0u8; 32];
+        pbkdf2_hmac::<Sha256>(password.as_bytes(), KEY_DERIVATION_SALT, KEY_DERIVATION_ROUNDS, &mut key);
+        Self {
+            cipher: Aes256Gcm::new(&Key::<Aes256Gcm>::from(key)),
+            password: password.as_bytes().to_vec(),
+        }
+    }
+
+    fn open(&self, data: &[u8]) -> Option<Vec<u8>> {
+        if data.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let nonce = Nonce::try_from(nonce).ok()?;
+        self.cipher.decrypt(&nonce, ciphertext).ok()
+    }
+}
+
+pub struct SecretSealer {
+    key: SealingKey,
+    previous_keys: Vec<SealingKey>,
+}
+
+impl SecretSealer {
+    pub fn new(key: &str, previous_keys: &[String]) -> Self {
+        Self {
+            key: SealingKey::derive(key),
+            previous_keys: previous_keys
+                .iter()
+                .filter(|k| !k.is_empty() && k.as_str() != key)
+                .map(|k| SealingKey::derive(k))
+                .collect(),
+        }
+    }
+
+    /// Key derived from the root credentials, for deployments that do not configure one
+    pub fn from_root_credentials(access_key: &str, secret_key: &str, previous_keys: &[String]) -> Self {
+        Self::new(&format!("{access_key}:{secret_key}"), previous_keys)
+    }
+
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        if plaintext.is_empty() {
+            return Ok(String::new());
+        }
+        let nonce_bytes: [u8; NONCE_LEN] = rand::random();
+        let nonce = Nonce::try_from(&nonce_bytes[..]).map_err(|_| Error::other("invalid nonce length"))?;
+        let ciphertext = self
+            .key
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|e| Error::other(format!("seal IAM secret failed: {e}")))?;
+
+        let mut data = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        data.extend_from_slice(&nonce_bytes);
+        data.extend_from_slice(&ciphertext);
+        Ok(format!("{SEALED_PREFIX}{}", base64_simd::STANDARD.encode_to_string(&data)))
+    }
+
+    /// Opens a sealed value, a value written before sealing was enabled is returned as it is
+    pub fn open(&self, stored: &str) -> Result<String> {
+        Ok(self.open_with_key(stored)?.0)
+    }
+
+    /// The plaintext, and whether the value is sealed in the current format under the current key
+    fn open_with_key(&self, stored: &str) -> Result<(String, bool)> {
+        let (encoded, legacy) = match (stored.strip_prefix(SEALED_PREFIX), stored.strip_prefix(LEGACY_SEALED_PREFIX)) {
+            (Some(encoded), _) => (encoded, false),
+            (None, Some(encoded)) => (encoded, true),
+            (None, None) => return Ok((stored.to_string(), false)),
+        };
+        let data = base64_simd::STANDARD
+            .decode_to_vec(encoded.as_bytes())
+            .map_err(|e| Error::other(format!("malformed sealed IAM secret: {e}")))?;
+
+        for (i, key) in std::iter::once(&self.key).chain(self.previous_keys.iter()).enumerate() {
+            let plaintext = if legacy {
+                decrypt_data(&key.password, &data).ok()
+            } else {
+                key.open(&data)
+            };
+            if let Some(plaintext) = plaintext {
+                let plaintext = String::from_utf8(plaintext).map_err(Error::other)?;
+                return Ok((plaintext, i == 0 && !legacy));
+            }
+        }
+        Err(Error::other(
+            "sealed IAM secret does not open with the configured keys, if the IAM encryption key or the root \
+             credentials changed add the previous key, or \"<root_user>:<root_password>\", to iam_previous_encryption_keys",
+        ))
+    }
+
+    /// The value to store for a secret that is not sealed, or sealed under an earlier key when rotating,
+    /// `None` when it can stay as it is. A sealed value is opened either way, so that one sealed under a
+    /// key that is no longer configured is an error.
+    pub fn reseal(&self, stored: &str, rotate: bool) -> Result<Option<String>> {
+        if stored.is_empty() {
+            return Ok(None);
+        }
+        if !is_sealed(stored) {
+            return self.seal(stored).map(Some);
+        }
+        let legacy = stored.starts_with(LEGACY_SEALED_PREFIX);
+        match self.open_with_key(stored)? {
+            (_, true) => Ok(None),
+            (_, false) if !rotate && !legacy => Ok(None),
+            (plaintext, false) => self.seal(&plaintext).map(Some),
+        }
+    }
+
+    /// `identity_data` of an identity, its secret key and session token are moved to a sealed field
+    pub fn seal_identity(&self, identity: &UserIdentity) -> Result<Value> {
+        let mut identity = identity.clone();
+        let secrets = IdentitySecrets {
+            secret_key: std::mem::take(&mut identity.credentials.secret_key),
+            session_token: std::mem::take(&mut identity.credentials.session_token),
+        };
+
+        let mut data = serde_json::to_value(&identity).map_err(Error::other)?;
+        if !secrets.secret_key.is_empty() || !secrets.session_token.is_empty() {
+            let sealed = self.seal(&serde_json::to_string(&secrets).map_err(Error::other)?)?;
+            if let Value::Object(fields) = &mut data {
+                fields.insert(SEALED_SECRETS_FIELD.to_string(), Value::String(sealed));
+            }
+        }
+        Ok(data)
+    }
+
+    pub fn open_identity(&self, data: Value) -> Result<UserIdentity> {
+        Ok(self.open_identity_with_key(data)?.0)
+    }
+
+    fn open_identity_with_key(&self, mut data: Value) -> Result<(UserIdentity, bool)> {
+        let sealed = match &mut data {
+            Value::Object(fields) => fields.remove(SEALED_SECRETS_FIELD),
+            _ => None,
+        };
+        let mut identity: UserIdentity = serde_json::from_value(data).map_err(Error::other)?;
+
+        let Some(Value::String(sealed)) = sealed else {
+            return Ok((identity, false));
+        };
+        let (secrets, current) = self.open_with_key(&sealed)?;
+        let secrets: IdentitySecrets = serde_json::from_str(&secrets).map_err(Error::other)?;
+        identity.credentials.secret_key = secrets.secret_key;
+        identity.credentials.session_token = secrets.session_token;
+        Ok((identity, current))
+    }
+
+    /// `identity_data` to store for an identity whose secrets are not sealed, or sealed under an earlier
+    /// key when rotating, `None` when it can stay as it is. Sealed secrets are opened either way.
+    pub fn reseal_identity(&self, data: &Value, rotate: bool) -> Result<Option<Value>> {
+        let sealed = data.get(SEALED_SECRETS_FIELD).and_then(Value::as_str);
+        let is_sealed = sealed.is_some();
+        let legacy = sealed.is_some_and(|sealed| sealed.starts_with(LEGACY_SEALED_PREFIX));
+
+        let (identity, current) = self.open_identity_with_key(data.clone())?;
+        if current || (is_sealed && !rotate && !legacy) {
+            return Ok(None);
+        }
+        if !is_sealed && identity.credentials.secret_key.is_empty() && identity.credentials.session_token.is_empty() {
+            return Ok(None);
+        }
+        self.seal_identity(&identity).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nebulafx_policy::auth::Credentials;
+
+    fn test_identity() -> UserIdentity {
+        UserIdentity::new(Credentials {
+            access_key: "AKIAEXAMPLE".to_string(),
+            secret_key: "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: "eyJhbGciOiJIUzUxMiJ9.e30.sig".to_string(),
+            parent_user: "alice".to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let sealer = SecretSealer::new("current-key", &[]);
+
+        let sealed = sealer.seal("wJalrXUtnFEMI/K7MDENG").unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("wJalrXUtnFEMI"));
+        assert_eq!(sealer.open(&sealed).unwrap(), "wJalrXUtnFEMI/K7MDENG");
+
+        // Values written before sealing was enabled are read as they are
+        assert_eq!(sealer.open("plain-secret").unwrap(), "plain-secret");
+        assert_eq!(sealer.seal("").unwrap(), "");
+
+        assert!(SecretSealer::new("other-key", &[]).open(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_keys_are_derived_once() {
+        let derivations = || KEY_DERIVATIONS.with(|count| count.get());
+
+        let before = derivations();
+        let sealer = SecretSealer::new("current-key", &["old-key".to_string(), "older-key".to_string()]);
+        let sealed_old = SecretSealer::new("older-key", &[]).seal("old-secret").unwrap();
+        assert_eq!(derivations() - before, 4);
+
+        // Sealing and opening, also under a previous key, derive nothing
+        let before = derivations();
+        for i in 0..100 {
+            let sealed = sealer.seal(&format!("secret-{i}")).unwrap();
+            assert_eq!(sealer.open(&sealed).unwrap(), format!("secret-{i}"));
+            assert_eq!(sealer.open(&sealed_old).unwrap(), "old-secret");
+        }
+        assert_eq!(derivations(), before);
+
+        // The same plaintext never seals to the same value
+        assert_ne!(sealer.seal("secret").unwrap(), sealer.seal("secret").unwrap());
+    }
+
+    #[test]
+    fn test_legacy_values_are_resealed() {
+        let legacy = format!(
+            "{LEGACY_SEALED_PREFIX}{}",
+            base64_simd::STANDARD.encode_to_string(nebulafx_crypto::encrypt_data(b"current-key", b"secret").unwrap())
+        );
+        let sealer = SecretSealer::new("current-key", &[]);
+        assert_eq!(sealer.open(&legacy).unwrap(), "secret");
+
+        let resealed = sealer.reseal(&legacy, false).unwrap().unwrap();
+        assert!(resealed.starts_with(SEALED_PREFIX));
+        assert_eq!(sealer.open(&resealed).unwrap(), "secret");
+        assert!(sealer.reseal(&resealed, false).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reseal_rotates_to_the_current_key() {
+        let old = SecretSealer::new("old-key", &[]);
+        let sealed_old = old.seal("secret").unwrap();
+
+        let sealer = SecretSealer::new("new-key", &["old-key".to_string()]);
+        assert_eq!(sealer.open(&sealed_old).unwrap(), "secret");
+
+        // Only plaintext is sealed outside of a rotation
+        assert!(sealer.reseal(&sealed_old, false).unwrap().is_none());
+        let plain = sealer.reseal("secret", false).unwrap().unwrap();
+        assert_eq!(sealer.open(&plain).unwrap(), "secret");
+
+        let rotated = sealer.reseal(&sealed_old, true).unwrap().unwrap();
+        assert!(SecretSealer::new("new-key", &[]).open(&rotated).is_ok());
+        assert!(sealer.reseal(&rotated, true).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reseal_fails_without_the_sealing_key() {
+        let root = SecretSealer::from_root_credentials("admin", "old-password", &[]);
+        let sealed = root.seal("secret").unwrap();
+        let identity = root.seal_identity(&test_identity()).unwrap();
+
+        // The root password changed and the old credentials are not configured
+        let sealer = SecretSealer::from_root_credentials("admin", "new-password", &[]);
+        let err = sealer.reseal(&sealed, false).unwrap_err();
+        assert!(err.to_string().contains("iam_previous_encryption_keys"));
+        assert!(sealer.reseal_identity(&identity, false).is_err());
+
+        let sealer = SecretSealer::from_root_credentials("admin", "new-password", &["admin:old-password".to_string()]);
+        assert!(sealer.reseal(&sealed, false).unwrap().is_none());
+        assert!(sealer.reseal_identity(&identity, false).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_seal_identity() {
+        let sealer = SecretSealer::new("current-key", &[]);
+        let identity = test_identity();
+
+        let data = sealer.seal_identity(&identity).unwrap();
+        let stored = data.to_string();
+        assert!(!stored.contains("wJalrXUtnFEMI"));
+        assert!(!stored.contains("eyJhbGciOiJIUzUxMiJ9"));
+        assert!(stored.contains("AKIAEXAMPLE"));
+
+        let opened = sealer.open_identity(data.clone()).unwrap();
+        assert_eq!(opened.credentials.secret_key, identity.credentials.secret_key);
+        assert_eq!(opened.credentials.session_token, identity.credentials.session_token);
+        assert_eq!(opened.credentials.parent_user, "alice");
+        assert!(sealer.reseal_identity(&data, true).unwrap().is_none());
+
+        // Rows written before sealing was enabled are opened as they are and sealed by the migration
+        let legacy = serde_json::to_value(&identity).unwrap();
+        assert_eq!(
+            sealer.open_identity(legacy.clone()).unwrap().credentials.secret_key,
+            identity.credentials.secret_key
+        );
+        let resealed = sealer.reseal_identity(&legacy, false).unwrap().unwrap();
+        assert!(!resealed.to_string().contains("wJalrXUtnFEMI"));
+
+        let rotated = SecretSealer::new("next-key", &["current-key".to_string()])
+            .reseal_identity(&data, true)
+            .unwrap()
+            .unwrap();
+        let opened = SecretSealer::new("next-key", &[]).open_identity(rotated).unwrap();
+        assert_eq!(opened.credentials.secret_key, identity.credentials.secret_key);
+    }
+}
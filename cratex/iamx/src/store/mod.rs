@@ -0,0 +1,443 @@
+//! Backends keeping the IAM objects: identities, groups, policy documents and the policies mapped to
+//! users and groups. `PostgresStore` keeps them in the tables of the migrations, `ObjectStore` as
+//! JSON objects under `.nebulafx.sys/config/iam/` of the object layer itself, so that a single node
+//! or edge deployment does not need a database. `migrate` copies everything from one to the other.
+
+pub mod object;
+pub mod postgres;
+
+pub use object::ObjectStore;
+pub use postgres::PostgresStore;
+
+use crate::entity::UserEntity;
+use crate::error::{Error, Result};
+use crate::types::{GroupInfo, MappedPolicy, UserType};
+use async_trait::async_trait;
+use nebulafx_policy::auth::UserIdentity;
+use nebulafx_policy::policy::PolicyDoc;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use tracing::info;
+
+/// User types whose identities and policy mappings are kept
+const USER_TYPES: [UserType; 3] = [UserType::Reg, UserType::Sts, UserType::Svc];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IamBackend {
+    Postgres,
+    Object,
+}
+
+impl FromStr for IamBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "postgres" | "postgresql" => Ok(IamBackend::Postgres),
+            "object" | "embedded" => Ok(IamBackend::Object),
+            _ => Err(Error::other(format!(
+                "unknown IAM storage backend '{s}', expected 'postgres' or 'object'"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for IamBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IamBackend::Postgres => write!(f, "postgres"),
+            IamBackend::Object => write!(f, "object"),
+        }
+    }
+}
+
+#[async_trait]
+pub trait IamStore: Send + Sync {
+    fn backend(&self) -> IamBackend;
+
+    /// Pool of the Postgres backend, whose notifications keep the caches of all nodes in sync
+    fn pg_pool(&self) -> Option<PgPool> {
+        None
+    }
+
+    /// Account of the users table, only the root user is kept there
+    async fn find_account(&self, access_key: &str) -> Result<Option<UserEntity>>;
+    async fn save_root_user(&self, access_key: &str, secret_key: &str) -> Result<()>;
+
+    async fn find_policy_doc(&self, name: &str) -> Result<Option<PolicyDoc>>;
+    async fn save_policy_doc(&self, name: &str, policy_doc: &PolicyDoc) -> Result<()>;
+    async fn delete_policy_doc(&self, name: &str) -> Result<bool>;
+    async fn list_policy_docs(&self) -> Result<HashMap<String, PolicyDoc>>;
+
+    async fn find_group(&self, name: &str) -> Result<Option<GroupInfo>>;
+    async fn save_group(&self, name: &str, group_info: &GroupInfo) -> Result<()>;
+    async fn delete_group(&self, name: &str) -> Result<bool>;
+    async fn list_groups(&self) -> Result<HashMap<String, GroupInfo>>;
+
+    async fn find_mapped_policy(&self, name: &str, user_type: UserType, is_group: bool) -> Result<Option<MappedPolicy>>;
+    async fn save_mapped_policy(
+        &self,
+        name: &str,
+        user_type: UserType,
+        is_group: bool,
+        mapped_policy: &MappedPolicy,
+    ) -> Result<()>;
+    async fn delete_mapped_policy(&self, name: &str, user_type: UserType, is_group: bool) -> Result<bool>;
+    async fn list_mapped_policies(&self, user_type: UserType, is_group: bool) -> Result<HashMap<String, MappedPolicy>>;
+
+    async fn find_user_identity(&self, name: &str, user_type: UserType) -> Result<Option<UserIdentity>>;
+    async fn save_user_identity(
+        &self,
+        name: &str,
+        user_type: UserType,
+        user_identity: &UserIdentity,
+        ttl: Option<usize>,
+    ) -> Result<()>;
+    async fn delete_user_identity(&self, name: &str, user_type: UserType) -> Result<bool>;
+    async fn list_user_identities(&self, user_type: UserType) -> Result<HashMap<String, UserIdentity>>;
+
+    /// Seals the secrets stored in plaintext, and with `rotate` the ones sealed under a previous key,
+    /// returns the number of entries that were re-written. Fails on an entry that does not open.
+    async fn reseal_secrets(&self, rotate: bool) -> Result<usize>;
+
+    /// Load a single policy document into HashMap
+    async fn load_policy_doc(&self, name: &str, m: &mut HashMap<String, PolicyDoc>) -> Result<()> {
+        if let Some(policy_doc) = self.find_policy_doc(name).await? {
+            m.insert(name.to_string(), policy_doc);
+        }
+        Ok(())
+    }
+
+    /// Load all policy documents into HashMap
+    async fn load_policy_docs(&self, m: &mut HashMap<String, PolicyDoc>) -> Result<()> {
+        m.extend(self.list_policy_docs().await?);
+        Ok(())
+    }
+
+    /// Load a single group into HashMap
+    async fn load_group(&self, name: &str, m: &mut HashMap<String, GroupInfo>) -> Result<()> {
+        if let Some(group_info) = self.find_group(name).await? {
+            m.insert(name.to_string(), group_info);
+        }
+        Ok(())
+    }
+
+    /// Load all groups into HashMap
+    async fn load_groups(&self, m: &mut HashMap<String, GroupInfo>) -> Result<()> {
+        m.extend(self.list_groups().await?);
+        Ok(())
+    }
+
+    /// Load a single mapped policy into HashMap
+    async fn load_mapped_policy(
+        &self,
+        name: &str,
+        user_type: UserType,
+        is_group: bool,
+        m: &mut HashMap<String, MappedPolicy>,
+    ) -> Result<()> {
+        if let Some(mapped_policy) = self.find_mapped_policy(name, user_type, is_group).await? {
+            m.insert(name.to_string(), mapped_policy);
+        }
+        Ok(())
+    }
+
+    /// Load all mapped policies for a user type and group flag into HashMap
+    async fn load_mapped_policies(
+        &self,
+        user_type: UserType,
+        is_group: bool,
+        m: &mut HashMap<String, MappedPolicy>,
+    ) -> Result<()> {
+        m.extend(self.list_mapped_policies(user_type, is_group).await?);
+        Ok(())
+    }
+
+    /// Load a single user identity into HashMap
+    async fn load_user(&self, name: &str, user_type: UserType, m: &mut HashMap<String, UserIdentity>) -> Result<()> {
+        if let Some(user_identity) = self.find_user_identity(name, user_type).await? {
+            m.insert(name.to_string(), user_identity);
+        }
+        Ok(())
+    }
+
+    /// Load all user identities for a user type into HashMap
+    async fn load_users(&self, user_type: UserType, m: &mut HashMap<String, UserIdentity>) -> Result<()> {
+        m.extend(self.list_user_identities(user_type).await?);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MigrateStats {
+    pub policy_docs: usize,
+    pub groups: usize,
+    pub mapped_policies: usize,
+    pub user_identities: usize,
+}
+
+impl fmt::Display for MigrateStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} policies, {} groups, {} policy mappings, {} identities",
+            self.policy_docs, self.groups, self.mapped_policies, self.user_identities
+        )
+    }
+}
+
+/// Copies every IAM object of `from` to `to`, overwriting the objects of the same name
+///
+/// The root account is not copied, every node writes it from its configuration at startup.
+/// Nothing is removed from `from`, a node switched back to it finds the data as it was
+/// before the migration.
+pub async fn migrate(from: &dyn IamStore, to: &dyn IamStore) -> Result<MigrateStats> {
+    info!("Migrating IAM data from the {} backend to the {} backend", from.backend(), to.backend());
+    let mut stats = MigrateStats::default();
+
+    for (name, policy_doc) in from.list_policy_docs().await? {
+        to.save_policy_doc(&name, &policy_doc).await?;
+        stats.policy_docs += 1;
+    }
+
+    for (name, group_info) in from.list_groups().await? {
+        to.save_group(&name, &group_info).await?;
+        stats.groups += 1;
+    }
+
+    for user_type in USER_TYPES {
+        for (name, user_identity) in from.list_user_identities(user_type).await? {
+            to.save_user_identity(&name, user_type, &user_identity, None).await?;
+            stats.user_identities += 1;
+        }
+
+        for is_group in [false, true] {
+            for (name, mapped_policy) in from.list_mapped_policies(user_type, is_group).await? {
+                to.save_mapped_policy(&name, user_type, is_group, &mapped_policy).await?;
+                stats.mapped_policies += 1;
+            }
+        }
+    }
+
+    info!("Migrated {}", stats);
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nebulafx_policy::auth::Credentials;
+    use std::sync::Mutex;
+
+    type MappedKey = (String, u64, bool);
+    type IdentityKey = (String, u64);
+
+    /// Store keeping everything in maps, for the code that only goes through `IamStore`
+    struct MemoryStore {
+        backend: IamBackend,
+        policy_docs: Mutex<HashMap<String, PolicyDoc>>,
+        groups: Mutex<HashMap<String, GroupInfo>>,
+        mapped_policies: Mutex<HashMap<MappedKey, MappedPolicy>>,
+        user_identities: Mutex<HashMap<IdentityKey, UserIdentity>>,
+    }
+
+    impl MemoryStore {
+        fn new(backend: IamBackend) -> Self {
+            Self {
+                backend,
+                policy_docs: Mutex::default(),
+                groups: Mutex::default(),
+                mapped_policies: Mutex::default(),
+                user_identities: Mutex::default(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl IamStore for MemoryStore {
+        fn backend(&self) -> IamBackend {
+            self.backend
+        }
+
+        async fn find_account(&self, _access_key: &str) -> Result<Option<UserEntity>> {
+            Ok(None)
+        }
+
+        async fn save_root_user(&self, _access_key: &str, _secret_key: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn find_policy_doc(&self, name: &str) -> Result<Option<PolicyDoc>> {
+            Ok(self.policy_docs.lock().unwrap().get(name).cloned())
+        }
+
+        async fn save_policy_doc(&self, name: &str, policy_doc: &PolicyDoc) -> Result<()> {
+            self.policy_docs.lock().unwrap().insert(name.to_string(), policy_doc.clone());
+            Ok(())
+        }
+
+        async fn delete_policy_doc(&self, name: &str) -> Result<bool> {
+            Ok(self.policy_docs.lock().unwrap().remove(name).is_some())
+        }
+
+        async fn list_policy_docs(&self) -> Result<HashMap<String, PolicyDoc>> {
+            Ok(self.policy_docs.lock().unwrap().clone())
+        }
+
+        async fn find_group(&self, name: &str) -> Result<Option<GroupInfo>> {
+            Ok(self.groups.lock().unwrap().get(name).cloned())
+        }
+
+        async fn save_group(&self, name: &str, group_info: &GroupInfo) -> Result<()> {
+            self.groups.lock().unwrap().insert(name.to_string(), group_info.clone());
+            Ok(())
+        }
+
+        async fn delete_group(&self, name: &str) -> Result<bool> {
+            Ok(self.groups.lock().unwrap().remove(name).is_some())
+        }
+
+        async fn list_groups(&self) -> Result<HashMap<String, GroupInfo>> {
+            Ok(self.groups.lock().unwrap().clone())
+        }
+
+        async fn find_mapped_policy(&self, name: &str, user_type: UserType, is_group: bool) -> Result<Option<MappedPolicy>> {
+            let key = (name.to_string(), user_type.to_u64(), is_group);
+            Ok(self.mapped_policies.lock().unwrap().get(&key).cloned())
+        }
+
+        async fn save_mapped_policy(
+            &self,
+            name: &str,
+            user_type: UserType,
+            is_group: bool,
+            mapped_policy: &MappedPolicy,
+        ) -> Result<()> {
+            let key = (name.to_string(), user_type.to_u64(), is_group);
+            self.mapped_policies.lock().unwrap().insert(key, mapped_policy.clone());
+            Ok(())
+        }
+
+        async fn delete_mapped_policy(&self, name: &str, user_type: UserType, is_group: bool) -> Result<bool> {
+            let key = (name.to_string(), user_type.to_u64(), is_group);
+            Ok(self.mapped_policies.lock().unwrap().remove(&key).is_some())
+        }
+
+        async fn list_mapped_policies(&self, user_type: UserType, is_group: bool) -> Result<HashMap<String, MappedPolicy>> {
+            Ok(self
+                .mapped_policies
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|((_, t, g), _)| *t == user_type.to_u64() && *g == is_group)
+                .map(|((name, _, _), mapped_policy)| (name.clone(), mapped_policy.clone()))
+                .collect())
+        }
+
+        async fn find_user_identity(&self, name: &str, user_type: UserType) -> Result<Option<UserIdentity>> {
+            let key = (name.to_string(), user_type.to_u64());
+            Ok(self.user_identities.lock().unwrap().get(&key).cloned())
+        }
+
+        async fn save_user_identity(
+            &self,
+            name: &str,
+            user_type: UserType,
+            user_identity: &UserIdentity,
+            _ttl: Option<usize>,
+        ) -> Result<()> {
+            let key = (name.to_string(), user_type.to_u64());
+            self.user_identities.lock().unwrap().insert(key, user_identity.clone());
+            Ok(())
+        }
+
+        async fn delete_user_identity(&self, name: &str, user_type: UserType) -> Result<bool> {
+            let key = (name.to_string(), user_type.to_u64());
+            Ok(self.user_identities.lock().unwrap().remove(&key).is_some())
+        }
+
+        async fn list_user_identities(&self, user_type: UserType) -> Result<HashMap<String, UserIdentity>> {
+            Ok(self
+                .user_identities
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|((_, t), _)| *t == user_type.to_u64())
+                .map(|((name, _), user_identity)| (name.clone(), user_identity.clone()))
+                .collect())
+        }
+
+        async fn reseal_secrets(&self, _rotate: bool) -> Result<usize> {
+            Ok(0)
+        }
+    }
+
+    fn identity(access_key: &str, parent_user: &str) -> UserIdentity {
+        UserIdentity::new(Credentials {
+            access_key: access_key.to_string(),
+            secret_key: format!("{access_key}-secret"),
+            parent_user: parent_user.to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_migrate_round_trip() {
+        let from = MemoryStore::new(IamBackend::Postgres);
+        from.save_policy_doc("readonly", &PolicyDoc::default()).await.unwrap();
+        from.save_group("devs", &GroupInfo::new(vec!["alice".to_string()])).await.unwrap();
+        from.save_user_identity("alice", UserType::Reg, &identity("alice", ""), None)
+            .await
+            .unwrap();
+        from.save_user_identity("AKIASVC", UserType::Svc, &identity("AKIASVC", "alice"), None)
+            .await
+            .unwrap();
+        from.save_user_identity("AKIASTS", UserType::Sts, &identity("AKIASTS", "alice"), Some(3600))
+            .await
+            .unwrap();
+        let mapped = MappedPolicy::new("readonly");
+        from.save_mapped_policy("alice", UserType::Reg, false, &mapped).await.unwrap();
+        from.save_mapped_policy("devs", UserType::Reg, true, &mapped).await.unwrap();
+
+        let to = MemoryStore::new(IamBackend::Object);
+        let stats = migrate(&from, &to).await.unwrap();
+        assert_eq!(stats.policy_docs, 1);
+        assert_eq!(stats.groups, 1);
+        assert_eq!(stats.user_identities, 3);
+        assert_eq!(stats.mapped_policies, 2);
+
+        // Migrating back gives the source the same data it started with
+        let back = MemoryStore::new(IamBackend::Postgres);
+        migrate(&to, &back).await.unwrap();
+
+        assert!(back.find_policy_doc("readonly").await.unwrap().is_some());
+        assert_eq!(back.find_group("devs").await.unwrap().unwrap().members, vec!["alice".to_string()]);
+        for (name, user_type) in [("alice", UserType::Reg), ("AKIASVC", UserType::Svc), ("AKIASTS", UserType::Sts)] {
+            let user_identity = back.find_user_identity(name, user_type).await.unwrap().unwrap();
+            assert_eq!(user_identity.credentials.access_key, name);
+            assert_eq!(user_identity.credentials.secret_key, format!("{name}-secret"));
+        }
+        let user_policy = back.find_mapped_policy("alice", UserType::Reg, false).await.unwrap().unwrap();
+        assert_eq!(user_policy.policies, "readonly");
+        assert!(back.find_mapped_policy("devs", UserType::Reg, true).await.unwrap().is_some());
+        assert!(back.find_mapped_policy("devs", UserType::Reg, false).await.unwrap().is_none());
+
+        // The source keeps everything
+        assert_eq!(from.list_user_identities(UserType::Sts).await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_iam_backend_parse() {
+        assert_eq!("postgres".parse::<IamBackend>().unwrap(), IamBackend::Postgres);
+        assert_eq!(" PostgreSQL ".parse::<IamBackend>().unwrap(), IamBackend::Postgres);
+        assert_eq!("object".parse::<IamBackend>().unwrap(), IamBackend::Object);
+        assert_eq!("embedded".parse::<IamBackend>().unwrap(), IamBackend::Object);
+        assert!("sqlite".parse::<IamBackend>().is_err());
+
+        for backend in [IamBackend::Postgres, IamBackend::Object] {
+            assert_eq!(backend.to_string().parse::<IamBackend>().unwrap(), backend);
+        }
+    }
+}
@@ -0,0 +1,502 @@
+use super::{IamBackend, IamStore, USER_TYPES};
+use crate::entity::UserEntity;
+use crate::error::{Error, Result};
+use crate::seal::sealer;
+use crate::types::{GroupInfo, MappedPolicy, UserType};
+use async_trait::async_trait;
+use chrono::Utc;
+use nebulafx_ecstore::config::com::{delete_config, read_config, save_config};
+use nebulafx_ecstore::disk::NEUBULAFX_META_BUCKET;
+use nebulafx_ecstore::error::StorageError;
+use nebulafx_ecstore::store::ECStore;
+use nebulafx_ecstore::store_api::StorageAPI;
+use nebulafx_policy::auth::UserIdentity;
+use nebulafx_policy::policy::PolicyDoc;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Prefix of the IAM objects in the meta bucket
+pub const IAM_CONFIG_PREFIX: &str = "config/iam";
+
+const ROOT_ACCOUNT_FILE: &str = "root.json";
+const POLICY_DOC_FILE: &str = "policy.json";
+const GROUP_FILE: &str = "members.json";
+const IDENTITY_FILE: &str = "identity.json";
+const JSON_EXT: &str = ".json";
+
+const LIST_MAX_KEYS: i32 = 1000;
+
+fn identity_dir(user_type: UserType) -> &'static str {
+    match user_type {
+        UserType::None => "untyped/",
+        _ => user_type.prefix(),
+    }
+}
+
+fn policy_doc_dir() -> String {
+    format!("{IAM_CONFIG_PREFIX}/policies/")
+}
+
+fn group_dir() -> String {
+    format!("{IAM_CONFIG_PREFIX}/groups/")
+}
+
+fn mapped_policy_dir(user_type: UserType, is_group: bool) -> String {
+    let kind = if is_group { "groups" } else { "users" };
+    format!("{IAM_CONFIG_PREFIX}/policydb/{kind}/{}", identity_dir(user_type))
+}
+
+fn user_identity_dir(user_type: UserType) -> String {
+    format!("{IAM_CONFIG_PREFIX}/{}", identity_dir(user_type))
+}
+
+fn root_account_path() -> String {
+    format!("{IAM_CONFIG_PREFIX}/{ROOT_ACCOUNT_FILE}")
+}
+
+/// Names become a path segment of the object, so they may not reach into another directory
+fn check_name(name: &str) -> Result<&str> {
+    if name.is_empty() || name == "." || name.contains('/') || name.contains('\\') || name.contains("..") {
+        return Err(Error::other(format!("invalid IAM object name '{name}'")));
+    }
+    Ok(name)
+}
+
+fn policy_doc_path(name: &str) -> Result<String> {
+    Ok(format!("{}{}/{POLICY_DOC_FILE}", policy_doc_dir(), check_name(name)?))
+}
+
+fn group_path(name: &str) -> Result<String> {
+    Ok(format!("{}{}/{GROUP_FILE}", group_dir(), check_name(name)?))
+}
+
+fn mapped_policy_path(name: &str, user_type: UserType, is_group: bool) -> Result<String> {
+    Ok(format!("{}{}{JSON_EXT}", mapped_policy_dir(user_type, is_group), check_name(name)?))
+}
+
+fn user_identity_path(name: &str, user_type: UserType) -> Result<String> {
+    Ok(format!("{}{}/{IDENTITY_FILE}", user_identity_dir(user_type), check_name(name)?))
+}
+
+/// Name of the IAM object stored as `object`, the part between the directory of its kind and its file
+fn object_name<'a>(object: &'a str, dir: &str, file: &str) -> Option<&'a str> {
+    object
+        .strip_prefix(dir)?
+        .strip_suffix(file)
+        .map(|name| name.strip_suffix('/').unwrap_or(name))
+        .filter(|name| !name.is_empty())
+}
+
+/// IAM objects kept as JSON objects under `IAM_CONFIG_PREFIX` of the meta bucket
+///
+/// Secrets are sealed as they are in the Postgres tables. A write is visible to the other nodes
+/// once they reload their cache, or once the peer notification for it reaches them.
+pub struct ObjectStore {
+    api: Arc<ECStore>,
+}
+
+impl ObjectStore {
+    pub fn new(api: Arc<ECStore>) -> Self {
+        Self { api }
+    }
+
+    async fn read<T: DeserializeOwned>(&self, path: &str) -> Result<Option<T>> {
+        match read_config(self.api.clone(), path).await {
+            Ok(data) => serde_json::from_slice(&data).map(Some).map_err(Error::other),
+            Err(StorageError::ConfigNotFound) => Ok(None),
+            Err(err) => Err(Error::other(err)),
+        }
+    }
+
+    async fn write<T: Serialize + ?Sized>(&self, path: &str, value: &T) -> Result<()> {
+        let data = serde_json::to_vec(value).map_err(Error::other)?;
+        save_config(self.api.clone(), path, data).await.map_err(Error::other)
+    }
+
+    async fn delete(&self, path: &str) -> Result<bool> {
+        match delete_config(self.api.clone(), path).await {
+            Ok(()) => Ok(true),
+            Err(StorageError::ConfigNotFound) => Ok(false),
+            Err(err) => Err(Error::other(err)),
+        }
+    }
+
+    /// Names of the objects of one kind, stored as `<dir><name>` followed by `file`
+    async fn list_names(&self, dir: &str, file: &str) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        let mut continuation = None;
+        loop {
+            let result = self
+                .api
+                .clone()
+                .list_objects_v2(NEUBULAFX_META_BUCKET, dir, continuation, None, LIST_MAX_KEYS, false, None)
+                .await
+                .map_err(Error::other)?;
+
+            names.extend(
+                result
+                    .objects
+                    .iter()
+                    .filter(|o| !o.is_dir)
+                    .filter_map(|o| object_name(&o.name, dir, file))
+                    .map(str::to_string),
+            );
+
+            continuation = result.next_continuation_token;
+            if !result.is_truncated || continuation.is_none() {
+                break;
+            }
+        }
+        Ok(names)
+    }
+
+    async fn read_all<T: DeserializeOwned>(
+        &self,
+        dir: &str,
+        file: &str,
+        path: impl Fn(&str) -> Result<String>,
+    ) -> Result<HashMap<String, T>> {
+        let mut m = HashMap::new();
+        for name in self.list_names(dir, file).await? {
+            // Deleted since it was listed
+            if let Some(value) = self.read(&path(&name)?).await? {
+                m.insert(name, value);
+            }
+        }
+        Ok(m)
+    }
+
+    /// Deletes the identity and the policy mapping of temporary credentials that expired, Postgres
+    /// drops them by TTL but the objects have none. Returns whether the identity was expired
+    async fn drop_expired(&self, name: &str, user_type: UserType, user_identity: &UserIdentity) -> Result<bool> {
+        if user_type != UserType::Sts || !user_identity.credentials.is_expired() {
+            return Ok(false);
+        }
+        self.delete(&user_identity_path(name, user_type)?).await?;
+        self.delete(&mapped_policy_path(name, user_type, false)?).await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl IamStore for ObjectStore {
+    fn backend(&self) -> IamBackend {
+        IamBackend::Object
+    }
+
+    async fn find_account(&self, access_key: &str) -> Result<Option<UserEntity>> {
+        let Some(mut account) = self.read::<UserEntity>(&root_account_path()).await? else {
+            return Ok(None);
+        };
+        if account.access_key != access_key {
+            return Ok(None);
+        }
+        account.secret_key = sealer()?.open(&account.secret_key)?;
+        Ok(Some(account))
+    }
+
+    async fn save_root_user(&self, access_key: &str, secret_key: &str) -> Result<()> {
+        let now = Utc::now();
+        let created_at = self
+            .read::<UserEntity>(&root_account_path())
+            .await?
+            .map_or(now, |account| account.created_at);
+        let account = UserEntity {
+            id: 1,
+            access_key: access_key.to_string(),
+            secret_key: sealer()?.seal(secret_key)?,
+            user_type: "root".to_string(),
+            created_at,
+            updated_at: now,
+        };
+        self.write(&root_account_path(), &account).await
+    }
+
+    async fn find_policy_doc(&self, name: &str) -> Result<Option<PolicyDoc>> {
+        self.read(&policy_doc_path(name)?).await
+    }
+
+    async fn save_policy_doc(&self, name: &str, policy_doc: &PolicyDoc) -> Result<()> {
+        self.write(&policy_doc_path(name)?, policy_doc).await
+    }
+
+    async fn delete_policy_doc(&self, name: &str) -> Result<bool> {
+        self.delete(&policy_doc_path(name)?).await
+    }
+
+    async fn list_policy_docs(&self) -> Result<HashMap<String, PolicyDoc>> {
+        self.read_all(&policy_doc_dir(), POLICY_DOC_FILE, policy_doc_path).await
+    }
+
+    async fn find_group(&self, name: &str) -> Result<Option<GroupInfo>> {
+        self.read(&group_path(name)?).await
+    }
+
+    async fn save_group(&self, name: &str, group_info: &GroupInfo) -> Result<()> {
+        self.write(&group_path(name)?, group_info).await
+    }
+
+    async fn delete_group(&self, name: &str) -> Result<bool> {
+        self.delete(&group_path(name)?).await
+    }
+
+    async fn list_groups(&self) -> Result<HashMap<String, GroupInfo>> {
+        self.read_all(&group_dir(), GROUP_FILE, group_path).await
+    }
+
+    async fn find_mapped_policy(&self, name: &str, user_type: UserType, is_group: bool) -> Result<Option<MappedPolicy>> {
+        self.read(&mapped_policy_path(name, user_type, is_group)?).await
+    }
+
+    async fn save_mapped_policy(
+        &self,
+        name: &str,
+        user_type: UserType,
+        is_group: bool,
+        mapped_policy: &MappedPolicy,
+    ) -> Result<()> {
+        self.write(&mapped_policy_path(name, user_type, is_group)?, mapped_policy)
+            .await
+    }
+
+    async fn delete_mapped_policy(&self, name: &str, user_type: UserType, is_group: bool) -> Result<bool> {
+        self.delete(&mapped_policy_path(name, user_type, is_group)?).await
+    }
+
+    async fn list_mapped_policies(&self, user_type: UserType, is_group: bool) -> Result<HashMap<String, MappedPolicy>> {
+        self.read_all(&mapped_policy_dir(user_type, is_group), JSON_EXT, |name| {
+            mapped_policy_path(name, user_type, is_group)
+        })
+        .await
+    }
+
+    async fn find_user_identity(&self, name: &str, user_type: UserType) -> Result<Option<UserIdentity>> {
+        let Some(data) = self.read::<Value>(&user_identity_path(name, user_type)?).await? else {
+            return Ok(None);
+        };
+        let user_identity = sealer()?.open_identity(data)?;
+        if self.drop_expired(name, user_type, &user_identity).await? {
+            return Ok(None);
+        }
+        Ok(Some(user_identity))
+    }
+
+    /// The TTL is not kept, temporary credentials are deleted when they are read after they expired
+    async fn save_user_identity(
+        &self,
+        name: &str,
+        user_type: UserType,
+        user_identity: &UserIdentity,
+        _ttl: Option<usize>,
+    ) -> Result<()> {
+        let data = sealer()?.seal_identity(user_identity)?;
+        self.write(&user_identity_path(name, user_type)?, &data).await
+    }
+
+    async fn delete_user_identity(&self, name: &str, user_type: UserType) -> Result<bool> {
+        self.delete(&user_identity_path(name, user_type)?).await
+    }
+
+    async fn list_user_identities(&self, user_type: UserType) -> Result<HashMap<String, UserIdentity>> {
+        let stored: HashMap<String, Value> = self
+            .read_all(&user_identity_dir(user_type), IDENTITY_FILE, |name| user_identity_path(name, user_type))
+            .await?;
+        let sealer = sealer()?;
+        let mut m = HashMap::new();
+        for (name, data) in stored {
+            let user_identity = sealer.open_identity(data)?;
+            if !self.drop_expired(&name, user_type, &user_identity).await? {
+                m.insert(name, user_identity);
+            }
+        }
+        Ok(m)
+    }
+
+    async fn reseal_secrets(&self, rotate: bool) -> Result<usize> {
+        let sealer = sealer()?;
+        let mut sealed = 0;
+
+        if let Some(mut account) = self.read::<UserEntity>(&root_account_path()).await? {
+            if let Some(secret_key) = sealer.reseal(&account.secret_key, rotate)? {
+                account.secret_key = secret_key;
+                self.write(&root_account_path(), &account).await?;
+                sealed += 1;
+            }
+        }
+
+        for user_type in USER_TYPES {
+            let stored: HashMap<String, Value> = self
+                .read_all(&user_identity_dir(user_type), IDENTITY_FILE, |name| user_identity_path(name, user_type))
+                .await?;
+            for (name, data) in stored {
+                if let Some(data) = sealer.reseal_identity(&data, rotate)? {
+                    self.write(&user_identity_path(&name, user_type)?, &data).await?;
+                    sealed += 1;
+                }
+            }
+        }
+
+        Ok(sealed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seal::{SecretSealer, init_sealer};
+    use nebulafx_ecstore::disk::endpoint::Endpoint;
+    use nebulafx_ecstore::endpoints::{EndpointServerPools, Endpoints, PoolEndpoints};
+    use nebulafx_policy::auth::Credentials;
+    use tokio_util::sync::CancellationToken;
+
+    /// Object store over a single set of four drives in a temporary directory
+    async fn test_store() -> ObjectStore {
+        let _ = init_sealer(SecretSealer::new("test-iam-key", &[]));
+
+        let base = std::env::temp_dir().join(format!("nebulafx_iam_object_store_{}", std::process::id()));
+        let mut endpoints = Vec::new();
+        for i in 0..4 {
+            let path = base.join(format!("disk{i}"));
+            tokio::fs::create_dir_all(&path).await.unwrap();
+            let mut endpoint = Endpoint::try_from(path.to_str().unwrap()).unwrap();
+            endpoint.set_pool_index(0);
+            endpoint.set_set_index(0);
+            endpoint.set_disk_index(i);
+            endpoints.push(endpoint);
+        }
+        let endpoint_pools = EndpointServerPools(vec![PoolEndpoints {
+            legacy: false,
+            set_count: 1,
+            drives_per_set: 4,
+            endpoints: Endpoints::from(endpoints),
+            cmd_line: "test".to_string(),
+            platform: String::new(),
+        }]);
+
+        nebulafx_ecstore::store::init_local_disks(endpoint_pools.clone()).await.unwrap();
+        let api = ECStore::new("127.0.0.1:9000".parse().unwrap(), endpoint_pools, CancellationToken::new())
+            .await
+            .unwrap();
+        ObjectStore::new(api)
+    }
+
+    fn identity(access_key: &str, expiration: Option<time::OffsetDateTime>) -> UserIdentity {
+        UserIdentity::new(Credentials {
+            access_key: access_key.to_string(),
+            secret_key: format!("{access_key}-secret"),
+            session_token: format!("{access_key}-token"),
+            expiration,
+            parent_user: "alice".to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_object_paths() {
+        assert_eq!(policy_doc_path("readwrite").unwrap(), "config/iam/policies/readwrite/policy.json");
+        assert_eq!(group_path("devs").unwrap(), "config/iam/groups/devs/members.json");
+        assert_eq!(user_identity_path("alice", UserType::Reg).unwrap(), "config/iam/users/alice/identity.json");
+        assert_eq!(
+            user_identity_path("AKIA1", UserType::Svc).unwrap(),
+            "config/iam/service-accounts/AKIA1/identity.json"
+        );
+        assert_eq!(
+            mapped_policy_path("alice", UserType::Reg, false).unwrap(),
+            "config/iam/policydb/users/users/alice.json"
+        );
+        assert_eq!(
+            mapped_policy_path("devs", UserType::Reg, true).unwrap(),
+            "config/iam/policydb/groups/users/devs.json"
+        );
+        assert_eq!(
+            mapped_policy_path("cn=devs,dc=example,dc=com", UserType::Sts, true).unwrap(),
+            "config/iam/policydb/groups/sts/cn=devs,dc=example,dc=com.json"
+        );
+    }
+
+    #[test]
+    fn test_object_name() {
+        let dir = user_identity_dir(UserType::Sts);
+        assert_eq!(
+            object_name(&user_identity_path("AKIA1", UserType::Sts).unwrap(), &dir, IDENTITY_FILE),
+            Some("AKIA1")
+        );
+        assert_eq!(object_name("config/iam/sts/AKIA1/other.json", &dir, IDENTITY_FILE), None);
+        assert_eq!(object_name("config/iam/users/alice/identity.json", &dir, IDENTITY_FILE), None);
+        assert_eq!(object_name("config/iam/sts/identity.json", &dir, IDENTITY_FILE), None);
+
+        let dir = mapped_policy_dir(UserType::Reg, false);
+        assert_eq!(
+            object_name(&mapped_policy_path("alice", UserType::Reg, false).unwrap(), &dir, JSON_EXT),
+            Some("alice")
+        );
+    }
+
+    #[test]
+    fn test_object_paths_reject_traversal() {
+        for name in ["", ".", "..", "../root", "a/b", "a\\b", "x/../../y"] {
+            assert!(policy_doc_path(name).is_err(), "{name}");
+            assert!(group_path(name).is_err(), "{name}");
+            assert!(user_identity_path(name, UserType::Sts).is_err(), "{name}");
+            assert!(mapped_policy_path(name, UserType::Reg, true).is_err(), "{name}");
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_object_store_crud() {
+        let store = test_store().await;
+
+        store.save_policy_doc("readonly", &PolicyDoc::default()).await.unwrap();
+        assert!(store.find_policy_doc("readonly").await.unwrap().is_some());
+        assert!(store.list_policy_docs().await.unwrap().contains_key("readonly"));
+        assert!(store.save_policy_doc("../readonly", &PolicyDoc::default()).await.is_err());
+
+        store.save_group("devs", &GroupInfo::new(vec!["alice".to_string()])).await.unwrap();
+        assert_eq!(store.find_group("devs").await.unwrap().unwrap().members, vec!["alice".to_string()]);
+        assert!(store.delete_group("devs").await.unwrap());
+        assert!(store.find_group("devs").await.unwrap().is_none());
+        assert!(!store.delete_group("devs").await.unwrap());
+
+        store
+            .save_mapped_policy("alice", UserType::Reg, false, &MappedPolicy::new("readonly"))
+            .await
+            .unwrap();
+        let mapped = store.list_mapped_policies(UserType::Reg, false).await.unwrap();
+        assert_eq!(mapped.get("alice").map(|p| p.policies.as_str()), Some("readonly"));
+        assert!(store.list_mapped_policies(UserType::Reg, true).await.unwrap().is_empty());
+
+        // Secrets are sealed at rest and opened on read
+        store
+            .save_user_identity("alice", UserType::Reg, &identity("alice", None), None)
+            .await
+            .unwrap();
+        let stored = store.read::<Value>(&user_identity_path("alice", UserType::Reg).unwrap()).await.unwrap();
+        assert!(!stored.unwrap().to_string().contains("alice-secret"));
+        let alice = store.find_user_identity("alice", UserType::Reg).await.unwrap().unwrap();
+        assert_eq!(alice.credentials.secret_key, "alice-secret");
+
+        // Expired temporary credentials are deleted once read, with their policy mapping
+        let now = time::OffsetDateTime::now_utc();
+        let valid = identity("AKIAVALID", Some(now + time::Duration::hours(1)));
+        let expired = identity("AKIAEXPIRED", Some(now - time::Duration::hours(1)));
+        store.save_user_identity("AKIAVALID", UserType::Sts, &valid, Some(3600)).await.unwrap();
+        store.save_user_identity("AKIAEXPIRED", UserType::Sts, &expired, Some(3600)).await.unwrap();
+        store
+            .save_mapped_policy("AKIAEXPIRED", UserType::Sts, false, &MappedPolicy::new("readonly"))
+            .await
+            .unwrap();
+
+        let sts = store.list_user_identities(UserType::Sts).await.unwrap();
+        assert!(sts.contains_key("AKIAVALID"));
+        assert!(!sts.contains_key("AKIAEXPIRED"));
+        assert!(store.find_user_identity("AKIAEXPIRED", UserType::Sts).await.unwrap().is_none());
+        assert!(!store.delete_user_identity("AKIAEXPIRED", UserType::Sts).await.unwrap());
+        assert!(store.find_mapped_policy("AKIAEXPIRED", UserType::Sts, false).await.unwrap().is_none());
+
+        assert!(store.delete_user_identity("AKIAVALID", UserType::Sts).await.unwrap());
+        assert!(store.find_user_identity("AKIAVALID", UserType::Sts).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,185 @@
+use super::{IamBackend, IamStore};
+use crate::entity::UserEntity;
+use crate::error::{Error, Result};
+use crate::repository::{GroupRepository, MappedPolicyRepository, PolicyRepository, UserIdentityRepository, UserRepository};
+use crate::seal::sealer;
+use crate::types::{GroupInfo, MappedPolicy, UserType};
+use async_trait::async_trait;
+use nebulafx_policy::auth::UserIdentity;
+use nebulafx_policy::policy::PolicyDoc;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// IAM objects kept in the Postgres tables of the migrations, through the repositories
+#[derive(Clone)]
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IamStore for PostgresStore {
+    fn backend(&self) -> IamBackend {
+        IamBackend::Postgres
+    }
+
+    fn pg_pool(&self) -> Option<PgPool> {
+        Some(self.pool.clone())
+    }
+
+    async fn find_account(&self, access_key: &str) -> Result<Option<UserEntity>> {
+        UserRepository::find_by_access_key(&self.pool, access_key)
+            .await
+            .map_err(Error::other)
+    }
+
+    async fn save_root_user(&self, access_key: &str, secret_key: &str) -> Result<()> {
+        UserRepository::create_root_user(&self.pool, access_key, secret_key)
+            .await
+            .map_err(Error::other)?;
+        Ok(())
+    }
+
+    async fn find_policy_doc(&self, name: &str) -> Result<Option<PolicyDoc>> {
+        PolicyRepository::find_by_name(&self.pool, name).await.map_err(Error::other)
+    }
+
+    async fn save_policy_doc(&self, name: &str, policy_doc: &PolicyDoc) -> Result<()> {
+        PolicyRepository::save(&self.pool, name, policy_doc)
+            .await
+            .map_err(Error::other)
+    }
+
+    async fn delete_policy_doc(&self, name: &str) -> Result<bool> {
+        PolicyRepository::delete(&self.pool, name).await.map_err(Error::other)
+    }
+
+    async fn list_policy_docs(&self) -> Result<HashMap<String, PolicyDoc>> {
+        PolicyRepository::list_all(&self.pool).await.map_err(Error::other)
+    }
+
+    async fn find_group(&self, name: &str) -> Result<Option<GroupInfo>> {
+        GroupRepository::find_by_name(&self.pool, name).await.map_err(Error::other)
+    }
+
+    async fn save_group(&self, name: &str, group_info: &GroupInfo) -> Result<()> {
+        GroupRepository::save(&self.pool, name, group_info)
+            .await
+            .map_err(Error::other)
+    }
+
+    async fn delete_group(&self, name: &str) -> Result<bool> {
+        GroupRepository::delete(&self.pool, name).await.map_err(Error::other)
+    }
+
+    async fn list_groups(&self) -> Result<HashMap<String, GroupInfo>> {
+        GroupRepository::list_all(&self.pool).await.map_err(Error::other)
+    }
+
+    async fn find_mapped_policy(&self, name: &str, user_type: UserType, is_group: bool) -> Result<Option<MappedPolicy>> {
+        MappedPolicyRepository::find(&self.pool, name, user_type, is_group)
+            .await
+            .map_err(Error::other)
+    }
+
+    async fn save_mapped_policy(
+        &self,
+        name: &str,
+        user_type: UserType,
+        is_group: bool,
+        mapped_policy: &MappedPolicy,
+    ) -> Result<()> {
+        MappedPolicyRepository::save(&self.pool, name, user_type, is_group, mapped_policy)
+            .await
+            .map_err(Error::other)
+    }
+
+    async fn delete_mapped_policy(&self, name: &str, user_type: UserType, is_group: bool) -> Result<bool> {
+        MappedPolicyRepository::delete(&self.pool, name, user_type, is_group)
+            .await
+            .map_err(Error::other)
+    }
+
+    async fn list_mapped_policies(&self, user_type: UserType, is_group: bool) -> Result<HashMap<String, MappedPolicy>> {
+        MappedPolicyRepository::list_all(&self.pool, user_type, is_group)
+            .await
+            .map_err(Error::other)
+    }
+
+    async fn find_user_identity(&self, name: &str, user_type: UserType) -> Result<Option<UserIdentity>> {
+        UserIdentityRepository::find(&self.pool, name, user_type)
+            .await
+            .map_err(Error::other)
+    }
+
+    async fn save_user_identity(
+        &self,
+        name: &str,
+        user_type: UserType,
+        user_identity: &UserIdentity,
+        ttl: Option<usize>,
+    ) -> Result<()> {
+        UserIdentityRepository::save(&self.pool, name, user_type, user_identity, ttl)
+            .await
+            .map_err(Error::other)
+    }
+
+    async fn delete_user_identity(&self, name: &str, user_type: UserType) -> Result<bool> {
+        UserIdentityRepository::delete(&self.pool, name, user_type)
+            .await
+            .map_err(Error::other)
+    }
+
+    async fn list_user_identities(&self, user_type: UserType) -> Result<HashMap<String, UserIdentity>> {
+        let mut m = HashMap::new();
+        UserIdentityRepository::load_users(&self.pool, user_type, &mut m)
+            .await
+            .map_err(Error::other)?;
+        Ok(m)
+    }
+
+    /// A row written concurrently is left to the writer, which seals it under the current key anyway
+    async fn reseal_secrets(&self, rotate: bool) -> Result<usize> {
+        let sealer = sealer()?;
+        let mut sealed = 0;
+
+        for (id, stored) in UserRepository::list_stored_secret_keys(&self.pool)
+            .await
+            .map_err(Error::other)?
+        {
+            let Some(secret_key) = sealer.reseal(&stored, rotate)? else {
+                continue;
+            };
+            if UserRepository::replace_stored_secret_key(&self.pool, id, &stored, &secret_key)
+                .await
+                .map_err(Error::other)?
+            {
+                sealed += 1;
+            } else {
+                warn!("User {} changed while sealing its secret key, skipped", id);
+            }
+        }
+
+        for (id, stored) in UserIdentityRepository::list_stored(&self.pool).await.map_err(Error::other)? {
+            let Some(identity_data) = sealer.reseal_identity(&stored, rotate)? else {
+                continue;
+            };
+            if UserIdentityRepository::replace_stored(&self.pool, id, &stored, &identity_data)
+                .await
+                .map_err(Error::other)?
+            {
+                sealed += 1;
+            } else {
+                warn!("User identity {} changed while sealing its secrets, skipped", id);
+            }
+        }
+
+        Ok(sealed)
+    }
+}
@@ -1,10 +1,11 @@
 
 
+use crate::cache::{IamCache, IamCacheStats, IamChange};
 use crate::error::Error as IamError;
 use crate::error::is_err_no_such_account;
 use crate::error::is_err_no_such_temp_account;
 use crate::error::{Error, Result};
-use sqlx::PgPool;
+use crate::store::IamStore;
 use crate::manager::utils::{extract_jwt_claims, get_default_policyes};
 use crate::types::{GroupInfo, MappedPolicy, UserType};
 use crate::utils::extract_claims;
@@ -14,13 +15,17 @@ use nebulafx_madmin::AddOrUpdateUserReq;
 use nebulafx_madmin::GroupDesc;
 use nebulafx_policy::arn::ARN;
 use nebulafx_policy::auth::Credentials;
+use nebulafx_policy::auth::ldap::{self, LDAP_GROUPS_CLAIM, LDAP_USER_CLAIM, LdapConfig, is_dn, normalize_dn};
+use nebulafx_policy::auth::openid::{self, OPENID_PARENT_USER_PREFIX, OpenIdConfig};
 use nebulafx_policy::auth::{
     ACCOUNT_ON, UserIdentity, contains_reserved_chars, create_new_credentials_with_metadata, generate_credentials,
     is_access_key_valid, is_secret_key_valid,
 };
 use nebulafx_policy::policy::Args;
 use nebulafx_policy::policy::opa;
-use nebulafx_policy::policy::{EMBEDDED_POLICY_TYPE, INHERITED_POLICY_TYPE, Policy, PolicyDoc, iam_policy_claim_name_sa};
+use nebulafx_policy::policy::{
+    EMBEDDED_POLICY_TYPE, INHERITED_POLICY_TYPE, Policy, PolicyDecision, PolicyDoc, iam_policy_claim_name_sa,
+};
 use serde_json::Value;
 use serde_json::json;
 use std::collections::HashMap;
@@ -46,12 +51,15 @@ fn get_policy_plugin_client() -> Arc<RwLock<Option<nebulafx_policy::policy::opa:
 }
 
 pub struct IamSys {
-    pub(crate) pool: PgPool,
+    pub(crate) store: Arc<dyn IamStore>,
+    pub(crate) cache: Arc<IamCache>,
+    openid: OpenIdConfig,
+    ldap: Option<LdapConfig>,
     roles_map: HashMap<ARN, String>,
 }
 
 impl IamSys {
-    pub fn new(pool: PgPool) -> Self {
+    pub fn new(store: Arc<dyn IamStore>) -> Self {
         tokio::spawn(async move {
             match opa::lookup_config().await {
                 Ok(conf) => {
@@ -66,14 +74,131 @@ impl IamSys {
             };
         });
 
+        let openid = openid::lookup_config().unwrap_or_else(|e| {
+            error!("Error loading OpenID configuration err:{}", e);
+            OpenIdConfig::default()
+        });
+        let roles_map = openid
+            .role_policies()
+            .into_iter()
+            .filter_map(|(arn, policy)| ARN::parse(&arn).ok().map(|arn| (arn, policy)))
+            .collect();
+
+        let ldap = ldap::lookup_config().unwrap_or_else(|e| {
+            error!("Error loading LDAP configuration err:{}", e);
+            None
+        });
+
         Self {
-            pool,
-            roles_map: HashMap::new(),
+            store,
+            cache: Arc::new(IamCache::new()),
+            openid,
+            ldap,
+            roles_map,
+        }
+    }
+
+    /// OpenID providers whose ID tokens are exchanged for temporary credentials
+    pub fn openid(&self) -> &OpenIdConfig {
+        &self.openid
+    }
+
+    /// Directory whose users are exchanged for temporary credentials, `None` when LDAP is not enabled
+    pub fn ldap(&self) -> Option<&LdapConfig> {
+        self.ldap.as_ref()
+    }
+
+    /// Revokes, every sync interval of the directory, the temporary credentials of the users that left it
+    pub fn start_ldap_sync(self: &Arc<Self>) {
+        let Some(conf) = self.ldap.clone() else { return };
+        let iam_sys = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(conf.sync_interval);
+            // The first tick completes immediately, nothing was issued yet at startup
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(err) = iam_sys.sync_ldap_users(&conf).await {
+                    warn!("LDAP sync failed: {}", err);
+                }
+            }
+        });
+    }
+
+    async fn sync_ldap_users(&self, conf: &LdapConfig) -> Result<()> {
+        let mut sts_users = HashMap::new();
+        self.store.load_users(UserType::Sts, &mut sts_users)
+            .await
+            .map_err(|e| Error::other(format!("Failed to load STS accounts: {}", e)))?;
+        let mut reg_users = HashMap::new();
+        self.store.load_users(UserType::Reg, &mut reg_users)
+            .await
+            .map_err(|e| Error::other(format!("Failed to load users: {}", e)))?;
+
+        // Credentials of directory users have the DN of the user as parent
+        let mut by_parent: HashMap<String, Vec<String>> = HashMap::new();
+        for (access_key, u) in sts_users {
+            let parent = &u.credentials.parent_user;
+            if u.credentials.is_temp() && is_dn(parent) && !reg_users.contains_key(parent) {
+                by_parent.entry(parent.clone()).or_default().push(access_key);
+            }
+        }
+        if by_parent.is_empty() {
+            return Ok(());
         }
+
+        let mut conn = conf.connect().await.map_err(|e| Error::other(e.to_string()))?;
+        for (dn, access_keys) in by_parent {
+            match conf.user_exists(&mut conn, &dn).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    info!("LDAP user {} is gone, revoking {} temporary credentials", dn, access_keys.len());
+                    for access_key in access_keys {
+                        if let Err(err) = self.store.delete_user_identity(&access_key, UserType::Sts).await {
+                            warn!("revoke temporary credentials {} failed: {}", access_key, err);
+                            continue;
+                        }
+                        self.invalidate("user_identities", &access_key, false);
+                        self.notify_for_user(&access_key, true).await;
+                    }
+                }
+                // The user is kept when the directory cannot tell, it is checked again on the next sync
+                Err(err) => warn!("LDAP lookup of {} failed: {}", dn, err),
+            }
+        }
+        conn.close().await;
+        Ok(())
     }
+
+    /// Starts keeping the IAM cache in sync with the database
+    pub fn start_cache(&self) {
+        self.cache.start(self.store.pg_pool());
+    }
+
+    /// Backend keeping the IAM objects
+    pub fn store(&self) -> &Arc<dyn IamStore> {
+        &self.store
+    }
+
+    /// Changes reach the other nodes through database notifications while the cache listens for them
     pub fn has_watcher(&self) -> bool {
-        // Database-based storage doesn't have watchers
-        false
+        self.cache.is_listening()
+    }
+
+    pub fn cache_stats(&self) -> IamCacheStats {
+        self.cache.stats()
+    }
+
+    fn invalidate(&self, table: &str, name: &str, is_group: bool) {
+        self.cache.apply(
+            &IamChange {
+                table: table.to_string(),
+                name: name.to_string(),
+                old_name: None,
+                is_group,
+            },
+            "local",
+        );
     }
 
     pub async fn set_policy_plugin_client(client: nebulafx_policy::policy::opa::AuthZPlugin) {
@@ -89,38 +214,41 @@ impl IamSys {
     }
 
     pub async fn load_group(&self, name: &str) -> Result<()> {
+        self.invalidate("groups", name, true);
         use crate::manager::group::IamSysGroupExt;
         IamSysGroupExt::group_notification_handler(self, name).await
     }
 
     pub async fn load_groups(&self, m: &mut HashMap<String, GroupInfo>) -> Result<()> {
-        use crate::repository::GroupRepository;
-        GroupRepository::load_groups(&self.pool, m).await
+        self.store.load_groups(m).await
             .map_err(|e| Error::other(format!("Failed to load groups: {}", e)))
     }
 
     pub async fn load_policy(&self, name: &str) -> Result<()> {
+        self.invalidate("policies", name, false);
         use crate::manager::policy::IamSysPolicyExt;
         IamSysPolicyExt::policy_notification_handler(self, name).await
     }
 
     pub async fn load_policy_mapping(&self, name: &str, user_type: UserType, is_group: bool) -> Result<()> {
+        self.invalidate("mapped_policies", name, is_group);
         use crate::manager::mapped_policy::IamSysMappedPolicyExt;
         IamSysMappedPolicyExt::policy_mapping_notification_handler(self, name, user_type, is_group).await
     }
 
     pub async fn load_user(&self, name: &str, user_type: UserType) -> Result<()> {
+        self.invalidate("user_identities", name, false);
         use crate::manager::user::IamSysUserExt;
         IamSysUserExt::user_notification_handler(self, name, user_type).await
     }
 
     pub async fn load_users(&self, user_type: UserType, m: &mut HashMap<String, UserIdentity>) -> Result<()> {
-        use crate::repository::UserIdentityRepository;
-        UserIdentityRepository::load_users(&self.pool, user_type, m).await
+        self.store.load_users(user_type, m).await
             .map_err(|e| Error::other(format!("Failed to load users: {}", e)))
     }
 
     pub async fn load_service_account(&self, name: &str) -> Result<()> {
+        self.invalidate("user_identities", name, false);
         use crate::manager::user::IamSysUserExt;
         IamSysUserExt::user_notification_handler(self, name, UserType::Svc).await
     }
@@ -134,6 +262,7 @@ impl IamSys {
 
         use crate::manager::policy::IamSysPolicyExt;
         IamSysPolicyExt::delete_policy(self, name, notify).await?;
+        self.invalidate("policies", name, false);
 
         if !notify || self.has_watcher() {
             return Ok(());
@@ -171,8 +300,7 @@ impl IamSys {
         is_group: bool,
         m: &mut HashMap<String, MappedPolicy>,
     ) -> Result<()> {
-        use crate::repository::MappedPolicyRepository;
-        MappedPolicyRepository::load_mapped_policies(&self.pool, user_type, is_group, m).await
+        self.store.load_mapped_policies(user_type, is_group, m).await
             .map_err(|e| Error::other(format!("Failed to load mapped policies: {}", e)))
     }
 
@@ -189,6 +317,7 @@ impl IamSys {
     pub async fn set_policy(&self, name: &str, policy: Policy) -> Result<OffsetDateTime> {
         use crate::manager::policy::IamSysPolicyExt;
         let updated_at = IamSysPolicyExt::set_policy(self, name, policy).await?;
+        self.invalidate("policies", name, false);
 
         if !self.has_watcher() {
             if let Some(notification_sys) = get_global_notification_sys() {
@@ -219,6 +348,7 @@ impl IamSys {
     pub async fn delete_user(&self, name: &str, notify: bool) -> Result<()> {
         use crate::manager::user::IamSysUserExt;
         IamSysUserExt::delete_user(self, name, UserType::Reg).await?;
+        self.invalidate("user_identities", name, false);
 
         if notify && !self.has_watcher() {
             if let Some(notification_sys) = get_global_notification_sys() {
@@ -282,6 +412,7 @@ impl IamSys {
     pub async fn set_temp_user(&self, name: &str, cred: &Credentials, policy_name: Option<&str>) -> Result<OffsetDateTime> {
         use crate::manager::user::IamSysUserExt;
         let updated_at = IamSysUserExt::set_temp_user(self, name, cred, policy_name).await?;
+        self.invalidate("user_identities", &cred.access_key, false);
 
         self.notify_for_user(&cred.access_key, true).await;
 
@@ -320,6 +451,7 @@ impl IamSys {
     pub async fn set_user_status(&self, name: &str, status: nebulafx_madmin::AccountStatus) -> Result<OffsetDateTime> {
         use crate::manager::user::IamSysUserExt;
         let updated_at = IamSysUserExt::set_user_status(self, name, status).await?;
+        self.invalidate("user_identities", name, false);
 
         self.notify_for_user(name, false).await;
 
@@ -411,6 +543,7 @@ impl IamSys {
 
         use crate::manager::user::IamSysUserExt;
         let create_at = self.add_service_account(cred.clone()).await?;
+        self.invalidate("user_identities", &cred.access_key, false);
 
         self.notify_for_service_account(&cred.access_key).await;
 
@@ -420,6 +553,7 @@ impl IamSys {
     pub async fn update_service_account(&self, name: &str, opts: UpdateServiceAccountOpts) -> Result<OffsetDateTime> {
         use crate::manager::user::IamSysUserExt;
         let updated_at = IamSysUserExt::update_service_account(self, name, opts).await?;
+        self.invalidate("user_identities", name, false);
 
         self.notify_for_service_account(name).await;
 
@@ -565,6 +699,7 @@ impl IamSys {
         }
 
         IamSysUserExt::delete_user(self, access_key, UserType::Svc).await?;
+        self.invalidate("user_identities", access_key, false);
 
         if notify && !self.has_watcher() {
             if let Some(notification_sys) = get_global_notification_sys() {
@@ -610,6 +745,7 @@ impl IamSys {
 
         use crate::manager::user::IamSysUserExt;
         let updated_at = IamSysUserExt::add_user(self, access_key, args).await?;
+        self.invalidate("user_identities", access_key, false);
 
         self.notify_for_user(access_key, false).await;
 
@@ -626,7 +762,10 @@ impl IamSys {
         }
 
         use crate::manager::user::IamSysUserExt;
-        IamSysUserExt::update_user_secret_key(self, access_key, secret_key).await
+        IamSysUserExt::update_user_secret_key(self, access_key, secret_key).await?;
+        self.invalidate("user_identities", access_key, false);
+
+        Ok(())
     }
 
     pub async fn check_key(&self, access_key: &str) -> Result<(Option<UserIdentity>, bool)> {
@@ -636,9 +775,25 @@ impl IamSys {
             }
         }
 
+        if let Some(u) = self.cache.get_user(access_key) {
+            let ok = u.credentials.is_valid();
+            return Ok((Some(u), ok));
+        }
+
+        // Lookups that miss are not cached, a failed query must not hide an existing user
+        let generation = self.cache.generation();
+        let user = self.load_user_identity(access_key).await;
+        if let Some(u) = &user {
+            self.cache.insert_user(access_key, u.clone(), generation);
+        }
+
+        let ok = user.as_ref().is_some_and(|u| u.credentials.is_valid());
+        Ok((user, ok))
+    }
+
+    async fn load_user_identity(&self, access_key: &str) -> Option<UserIdentity> {
         // First, try to get user from users table (database)
-        use crate::repository::UserRepository;
-        if let Ok(Some(user_entity)) = UserRepository::find_by_access_key(&self.pool, access_key).await {
+        if let Ok(Some(user_entity)) = self.store.find_account(access_key).await {
             // User found in users table - create UserIdentity from it
             use nebulafx_policy::auth::Credentials;
             let cred = Credentials {
@@ -649,7 +804,6 @@ impl IamSys {
             };
 
             // Try to get additional info from user_identities table
-            use crate::repository::UserIdentityRepository;
             use crate::types::UserType;
             
             // Determine user type from user_entity.user_type
@@ -660,7 +814,7 @@ impl IamSys {
                 _ => UserType::Reg,
             };
 
-            let user_identity = if let Ok(Some(mut identity)) = UserIdentityRepository::find(&self.pool, access_key, user_type).await {
+            let user_identity = if let Ok(Some(mut identity)) = self.store.find_user_identity(access_key, user_type).await {
                 // Merge: use secret_key from users table, but keep other info from user_identities
                 identity.credentials.secret_key = user_entity.secret_key.clone();
                 identity.credentials.access_key = user_entity.access_key.clone();
@@ -670,19 +824,12 @@ impl IamSys {
                 UserIdentity::new(cred)
             };
 
-            let ok = user_identity.credentials.is_valid();
-            return Ok((Some(user_identity), ok));
+            return Some(user_identity);
         }
 
         // Fallback: try user_identities table (for backward compatibility)
         use crate::manager::user::IamSysUserExt;
-        match IamSysUserExt::get_user(self, access_key).await {
-            Some(res) => {
-                let ok = res.credentials.is_valid();
-                Ok((Some(res), ok))
-            }
-            None => Ok((None, false)),
-        }
+        IamSysUserExt::get_user(self, access_key).await
     }
 
     pub async fn get_user(&self, access_key: &str) -> Option<UserIdentity> {
@@ -698,6 +845,7 @@ impl IamSys {
         }
         use crate::manager::group::IamSysGroupExt;
         let updated_at = IamSysGroupExt::add_users_to_group(self, group, users).await?;
+        self.invalidate("groups", group, true);
 
         self.notify_for_group(group).await;
 
@@ -707,6 +855,7 @@ impl IamSys {
     pub async fn remove_users_from_group(&self, group: &str, users: Vec<String>) -> Result<OffsetDateTime> {
         use crate::manager::group::IamSysGroupExt;
         let updated_at = IamSysGroupExt::remove_users_from_group(self, group, users).await?;
+        self.invalidate("groups", group, true);
 
         self.notify_for_group(group).await;
 
@@ -716,6 +865,7 @@ impl IamSys {
     pub async fn set_group_status(&self, group: &str, enable: bool) -> Result<OffsetDateTime> {
         use crate::manager::group::IamSysGroupExt;
         let updated_at = IamSysGroupExt::set_group_status(self, group, enable).await?;
+        self.invalidate("groups", group, true);
 
         self.notify_for_group(group).await;
 
@@ -739,6 +889,7 @@ impl IamSys {
     pub async fn policy_db_set(&self, name: &str, user_type: UserType, is_group: bool, policy: &str) -> Result<OffsetDateTime> {
         use crate::manager::mapped_policy::IamSysMappedPolicyExt;
         let updated_at = IamSysMappedPolicyExt::policy_db_set(self, name, user_type, is_group, policy).await?;
+        self.invalidate("mapped_policies", name, is_group);
 
         if !self.has_watcher() {
             if let Some(notification_sys) = get_global_notification_sys() {
@@ -759,6 +910,30 @@ impl IamSys {
         IamSysMappedPolicyExt::policy_db_get(self, name, groups).await
     }
 
+    /// Policies mapped to the DN of a directory user and to the DNs of its groups, the mappings of DNs are
+    /// stored as STS mappings
+    pub async fn ldap_policy_db_get(&self, user_dn: &str, groups: &[String]) -> Result<Vec<String>> {
+        let mut policies = Vec::new();
+        for (dn, is_group) in std::iter::once((user_dn, false)).chain(groups.iter().map(|g| (g.as_str(), true))) {
+            let dn = normalize_dn(dn);
+            if let Some((mapped, _)) = self.cache.get_policy_mapping(&dn, is_group) {
+                policies.extend(mapped);
+                continue;
+            }
+
+            let generation = self.cache.generation();
+            let mapping = self.store.find_mapped_policy(&dn, UserType::Sts, is_group)
+                .await
+                .map_err(|e| Error::other(format!("Failed to load mapped policy: {}", e)))?
+                .map_or_else(|| (Vec::new(), OffsetDateTime::now_utc()), |mp| (mp.to_slice(), mp.update_at));
+            self.cache.insert_policy_mapping(&dn, is_group, mapping.clone(), generation);
+            policies.extend(mapping.0);
+        }
+        policies.sort();
+        policies.dedup();
+        Ok(policies)
+    }
+
     pub async fn is_allowed_sts(&self, args: &Args<'_>, parent_user: &str) -> bool {
         let is_owner = parent_user == get_global_action_cred().unwrap().access_key;
         let role_arn = args.get_role_arn();
@@ -769,12 +944,18 @@ impl IamSys {
                 let Ok(arn) = ARN::parse(role_arn.unwrap_or_default()) else { return false };
 
                 MappedPolicy::new(self.roles_map.get(&arn).map_or_else(String::default, |v| v.clone()).as_str()).to_slice()
+            } else if parent_user.starts_with(OPENID_PARENT_USER_PREFIX) {
+                // Identities of an OpenID provider have no mapping, their policies come from their token
+                args.get_policies(POLICYNAME).0.into_iter().collect()
+            } else if let Some(user_dn) = args.claims.get(LDAP_USER_CLAIM).and_then(Value::as_str) {
+                // Directory users are mapped by DN, their groups come from their token
+                let Ok(p) = self.ldap_policy_db_get(user_dn, &ldap_groups(args.claims)).await else { return false };
+                p
             } else {
                 use crate::manager::mapped_policy::IamSysMappedPolicyExt;
         let Ok(p) = IamSysMappedPolicyExt::policy_db_get(self, parent_user, args.groups).await else { return false };
 
                 p
-                //TODO: FROM JWT
             }
         };
 
@@ -905,6 +1086,129 @@ impl IamSys {
 
         self.get_combined_policy(&policies).await.is_allowed(args)
     }
+
+    /// Decision of the requester's IAM policies, to be combined with the bucket policy
+    pub async fn evaluate(&self, args: &Args<'_>) -> PolicyDecision {
+        if self.is_allowed(args).await {
+            return PolicyDecision::Allow;
+        }
+
+        // The OPA plugin decides on its own, no bucket policy can grant what it refused
+        if Self::get_policy_plugin_client().await.is_some() {
+            return PolicyDecision::Deny;
+        }
+
+        if self.is_denied(args).await {
+            PolicyDecision::Deny
+        } else {
+            PolicyDecision::NotApplicable
+        }
+    }
+
+    /// Whether a Deny statement of the requester's policies matches. Service accounts and STS sessions
+    /// are also denied when their session policy leaves the request out, a session policy limits what
+    /// bucket policies grant their parent user as well.
+    async fn is_denied(&self, args: &Args<'_>) -> bool {
+        if args.is_owner {
+            return false;
+        }
+
+        let Ok((is_temp, temp_parent)) = self.is_temp_user(args.account).await else { return true };
+        let Ok((is_svc, svc_parent)) = self.is_service_account(args.account).await else { return true };
+        let parent_user = if is_temp { temp_parent } else { svc_parent };
+        if (is_temp || is_svc) && parent_user == get_global_action_cred().unwrap().access_key {
+            return false;
+        }
+
+        let Some(policies) = self.requester_policies(args, is_temp, is_svc, &parent_user).await else { return true };
+
+        let mut deny_args = args.clone();
+        deny_args.deny_only = true;
+        if is_svc {
+            deny_args.account = &parent_user;
+        }
+        if !policies.is_empty() && !self.get_combined_policy(&policies).await.is_allowed(&deny_args) {
+            return true;
+        }
+
+        let (has_session_policy, is_allowed_sp) = if is_temp {
+            is_allowed_by_session_policy(args)
+        } else if is_svc {
+            is_allowed_by_session_policy_for_service_account(args)
+        } else {
+            (false, false)
+        };
+        has_session_policy && !is_allowed_sp
+    }
+
+    /// Names of the policies that apply to the requester, None when they can not be resolved
+    async fn requester_policies(
+        &self,
+        args: &Args<'_>,
+        is_temp: bool,
+        is_svc: bool,
+        parent_user: &str,
+    ) -> Option<Vec<String>> {
+        let policies = match args.get_role_arn() {
+            Some(role_arn) if is_temp || is_svc => {
+                let Ok(arn) = ARN::parse(role_arn) else { return None };
+                MappedPolicy::new(self.roles_map.get(&arn).map_or_else(String::default, |v| v.clone()).as_str()).to_slice()
+            }
+            _ if is_temp && parent_user.starts_with(OPENID_PARENT_USER_PREFIX) => {
+                args.get_policies(POLICYNAME).0.into_iter().collect()
+            }
+            _ if is_temp && args.claims.contains_key(LDAP_USER_CLAIM) => {
+                let user_dn = args.claims.get(LDAP_USER_CLAIM).and_then(Value::as_str).unwrap_or_default();
+                let Ok(policies) = self.ldap_policy_db_get(user_dn, &ldap_groups(args.claims)).await else { return None };
+                policies
+            }
+            _ => {
+                let name = if is_temp || is_svc {
+                    parent_user
+                } else {
+                    args.account
+                };
+                let Ok(policies) = self.policy_db_get(name, args.groups).await else { return None };
+                policies
+            }
+        };
+        Some(policies)
+    }
+
+    /// Whether a policy that applies to the requester, its session policy included, may test a condition
+    /// key of `key_name`. Callers use it to skip loading condition values no policy looks at, when in
+    /// doubt it answers true.
+    pub async fn policies_mention_condition_key(&self, args: &Args<'_>, key_name: &str) -> bool {
+        if args.is_owner {
+            return false;
+        }
+
+        let Ok((is_temp, temp_parent)) = self.is_temp_user(args.account).await else { return true };
+        let Ok((is_svc, svc_parent)) = self.is_service_account(args.account).await else { return true };
+        let parent_user = if is_temp { temp_parent } else { svc_parent };
+        if (is_temp || is_svc) && parent_user == get_global_action_cred().unwrap().access_key {
+            return false;
+        }
+
+        if let Some(session_policy) = args.claims.get(SESSION_POLICY_NAME_EXTRACTED) {
+            let Some(session_policy) = session_policy.as_str() else { return true };
+            if session_policy.to_lowercase().contains(&key_name.to_lowercase()) {
+                return true;
+            }
+        }
+
+        let Some(policies) = self.requester_policies(args, is_temp, is_svc, &parent_user).await else { return true };
+        !policies.is_empty() && self.get_combined_policy(&policies).await.mentions_condition_key(key_name)
+    }
+}
+
+/// Group DNs of a directory user, from its token
+fn ldap_groups(claims: &HashMap<String, Value>) -> Vec<String> {
+    match claims.get(LDAP_GROUPS_CLAIM) {
+        Some(Value::Array(groups)) => groups.iter().filter_map(Value::as_str).map(String::from).collect(),
+        Some(Value::String(group)) if !group.is_empty() => vec![group.clone()],
+        _ => Vec::new(),
+    }
 }
 
 fn is_allowed_by_session_policy(args: &Args<'_>) -> (bool, bool) {
@@ -79,11 +79,10 @@ pub fn extract_claims<T: DeserializeOwned + Clone>(
     token: &str,
     secret: &str,
 ) -> std::result::Result<jsonwebtoken::TokenData<T>, jsonwebtoken::errors::Error> {
-    jsonwebtoken::decode::<T>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &jsonwebtoken::Validation::new(Algorithm::HS512),
-    )
+    let mut validation = jsonwebtoken::Validation::new(Algorithm::HS512);
+    // Session tokens of web identities carry the claims of the ID token, its audience included
+    validation.validate_aud = false;
+    jsonwebtoken::decode::<T>(token, &DecodingKey::from_secret(secret.as_bytes()), &validation)
 }
 
 #[cfg(test)]
@@ -11,19 +11,23 @@ use hyper::StatusCode;
 use matchit::Params;
 use nebulafx_common::heal_channel::HealOpts;
 use nebulafx_ecstore::admin_server_info::get_server_info;
+use nebulafx_ecstore::bucket::bandwidth::get_global_bucket_monitor;
 use nebulafx_ecstore::bucket::bucket_target_sys::BucketTargetSys;
 use nebulafx_ecstore::bucket::metadata::BUCKET_TARGETS_FILE;
 use nebulafx_ecstore::bucket::metadata_sys;
+use nebulafx_ecstore::bucket::replication::GLOBAL_REPLICATION_STATS;
 use nebulafx_ecstore::bucket::target::BucketTarget;
 use nebulafx_ecstore::bucket::versioning_sys::BucketVersioningSys;
 use nebulafx_ecstore::data_usage::{
     aggregate_local_snapshots, compute_bucket_usage, load_data_usage_from_backend, store_data_usage_in_backend,
 };
+use nebulafx_ecstore::disk::healing::local_bg_heal_state;
 use nebulafx_ecstore::error::StorageError;
 use nebulafx_ecstore::global::get_global_action_cred;
 use nebulafx_ecstore::global::global_nebulafx_port;
 use nebulafx_ecstore::metrics_realtime::{CollectMetricsOpts, MetricType, collect_local_metrics};
 use nebulafx_ecstore::new_object_layer_fn;
+use nebulafx_ecstore::notification_sys::get_global_notification_sys;
 use nebulafx_ecstore::pools::{get_total_usable_capacity, get_total_usable_capacity_free};
 use nebulafx_ecstore::store::is_valid_object_prefix;
 use nebulafx_ecstore::store_api::BucketOptions;
@@ -62,11 +66,16 @@ use url::Host;
 pub mod bucket;
 pub mod event;
 pub mod group;
+pub mod kms;
 pub mod policy;
 pub mod pools;
 pub mod profile;
+pub mod prometheus;
 pub mod rebalance;
+pub mod replication;
+pub mod service;
 pub mod service_account;
+pub mod site_replication;
 pub mod login;
 pub mod tier;
 pub mod trace;
@@ -299,16 +308,6 @@ impl Operation for AccountInfoHandler {
     }
 }
 
-pub struct ServiceHandle {}
-#[async_trait::async_trait]
-impl Operation for ServiceHandle {
-    async fn call(&self, _req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
-        warn!("handle ServiceHandle");
-
-        Err(s3_error!(NotImplemented))
-    }
-}
-
 pub struct ServerInfoHandler {}
 
 #[async_trait::async_trait]
@@ -898,10 +897,33 @@ pub struct BackgroundHealStatusHandler {}
 
 #[async_trait::async_trait]
 impl Operation for BackgroundHealStatusHandler {
-    async fn call(&self, _req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
         warn!("handle BackgroundHealStatusHandler");
 
-        Err(s3_error!(NotImplemented))
+        let Some(input_cred) = req.credentials else {
+            return Err(s3_error!(InvalidRequest, "get cred failed"));
+        };
+
+        let (cred, owner) =
+            check_key_valid(get_session_token(&req.uri, &req.headers).unwrap_or_default(), &input_cred.access_key).await?;
+
+        validate_admin_request(&req.headers, &cred, owner, false, vec![Action::AdminAction(AdminAction::HealAdminAction)])
+            .await?;
+
+        let mut state = local_bg_heal_state().await;
+        if let Some(notification_sys) = get_global_notification_sys() {
+            for peer_state in notification_sys.background_heal_status().await {
+                state.merge(peer_state);
+            }
+        }
+
+        let data = serde_json::to_vec(&state)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal heal state failed: {e}")))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
     }
 }
 
@@ -928,15 +950,49 @@ fn is_local_host(_host: String) -> bool {
 pub struct GetReplicationMetricsHandler {}
 #[async_trait::async_trait]
 impl Operation for GetReplicationMetricsHandler {
-    async fn call(&self, _req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
-        error!("GetReplicationMetricsHandler");
-        let queries = extract_query_params(&_req.uri);
-        if let Some(bucket) = queries.get("bucket") {
-            error!("get bucket:{} metrics", bucket);
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        let queries = extract_query_params(&req.uri);
+        let Some(bucket) = queries.get("bucket").filter(|b| !b.is_empty()) else {
+            return Err(s3_error!(InvalidRequest, "bucket is required"));
+        };
+
+        let Some(input_cred) = req.credentials else {
+            return Err(s3_error!(InvalidRequest, "get cred failed"));
+        };
+
+        let (cred, owner) =
+            check_key_valid(get_session_token(&req.uri, &req.headers).unwrap_or_default(), &input_cred.access_key).await?;
+
+        validate_admin_request(
+            &req.headers,
+            &cred,
+            owner,
+            false,
+            vec![Action::S3Action(S3Action::GetReplicationConfigurationAction)],
+        )
+        .await?;
+
+        metadata_sys::get_replication_config(bucket).await.map_err(ApiError::from)?;
+
+        let Some(stats) = GLOBAL_REPLICATION_STATS.get() else {
+            return Err(s3_error!(InternalError, "replication stats not initialized"));
+        };
+        let mut bucket_stats = stats.get_latest_replication_stats(bucket).await;
+
+        // Throughput is measured where the replication happens, report it per target
+        let bandwidth = get_global_bucket_monitor().report(bucket);
+        for (arn, details) in bandwidth.bucket_stats {
+            let stat = bucket_stats.replication_stats.stats.entry(arn).or_default();
+            stat.bandwidth_limit_in_bytes_per_second = details.limit_in_bytes_per_second;
+            stat.current_bandwidth_in_bytes_per_second = details.current_bandwidth_in_bytes_per_second;
         }
-        //return Err(s3_error!(InvalidArgument, "Invalid bucket name"));
-        //Ok(S3Response::with_headers((StatusCode::OK, Body::from()), header))
-        Ok(S3Response::new((StatusCode::OK, Body::from("Ok".to_string()))))
+
+        let data = serde_json::to_vec(&bucket_stats)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal replication metrics failed: {e}")))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
     }
 }
 
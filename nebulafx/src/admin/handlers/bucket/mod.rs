@@ -2,8 +2,10 @@ mod export;
 mod export_match;
 mod import;
 mod import_match;
+mod quota;
 mod error;
 
 pub use export::ExportBucketMetadata;
 pub use import::ImportBucketMetadata;
+pub use quota::{GetBucketQuota, RemoveBucketQuota, SetBucketQuota};
 
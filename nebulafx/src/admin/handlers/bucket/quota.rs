@@ -0,0 +1,149 @@
+
+
+use crate::{
+    admin::{auth::validate_admin_request, router::Operation},
+    auth::{check_key_valid, get_session_token},
+};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use nebulafx_ecstore::{
+    StorageAPI,
+    bucket::{metadata::BUCKET_QUOTA_CONFIG_FILE, metadata_sys, quota::BucketQuota, quota::BucketQuotaSys},
+    new_object_layer_fn,
+    store_api::BucketOptions,
+};
+use nebulafx_policy::policy::action::{Action, AdminAction};
+use s3s::{
+    Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result,
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    s3_error,
+};
+use serde::Deserialize;
+use serde_urlencoded::from_bytes;
+use tracing::warn;
+
+use super::error::messages;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BucketQuotaQuery {
+    pub bucket: String,
+}
+
+/// Parse the `bucket` query parameter and make sure the bucket exists
+async fn validate_bucket_query(req: &S3Request<Body>) -> S3Result<String> {
+    let query: BucketQuotaQuery = match req.uri.query() {
+        Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "{}", messages::GET_QUERY_FAILED))?,
+        None => BucketQuotaQuery::default(),
+    };
+
+    if query.bucket.is_empty() {
+        return Err(s3_error!(InvalidArgument, "bucket is required"));
+    }
+
+    let Some(store) = new_object_layer_fn() else {
+        return Err(S3Error::with_message(S3ErrorCode::InternalError, messages::OBJECT_STORE_NOT_INIT));
+    };
+
+    store
+        .get_bucket_info(&query.bucket, &BucketOptions::default())
+        .await
+        .map_err(|e| s3_error!(NoSuchBucket, "{}: {e}", messages::GET_BUCKET_FAILED))?;
+
+    Ok(query.bucket)
+}
+
+async fn authorize(req: &S3Request<Body>, action: AdminAction) -> S3Result<()> {
+    let Some(input_cred) = &req.credentials else {
+        return Err(S3Error::with_message(S3ErrorCode::InvalidRequest, messages::GET_CRED_FAILED));
+    };
+
+    let (cred, owner) =
+        check_key_valid(get_session_token(&req.uri, &req.headers).unwrap_or_default(), &input_cred.access_key).await?;
+
+    validate_admin_request(&req.headers, &cred, owner, false, vec![Action::AdminAction(action)]).await
+}
+
+fn empty_ok() -> S3Response<(StatusCode, Body)> {
+    let mut header = HeaderMap::new();
+    header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    header.insert(CONTENT_LENGTH, "0".parse().unwrap());
+    S3Response::with_headers((StatusCode::OK, Body::empty()), header)
+}
+
+pub struct SetBucketQuota {}
+
+#[async_trait::async_trait]
+impl Operation for SetBucketQuota {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::SetBucketQuotaAdminAction).await?;
+        let bucket = validate_bucket_query(&req).await?;
+
+        let mut input = req.input;
+        let body = match input.store_all_unlimited().await {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("get body failed, e: {:?}", e);
+                return Err(s3_error!(InvalidRequest, "{}", messages::GET_BODY_FAILED));
+            }
+        };
+
+        let quota = BucketQuota::unmarshal(&body).map_err(|e| s3_error!(InvalidArgument, "invalid quota config: {e}"))?;
+
+        // A zero limit is how clients ask for the quota to be lifted
+        if quota.size() == 0 {
+            metadata_sys::delete(&bucket, BUCKET_QUOTA_CONFIG_FILE)
+                .await
+                .map_err(|e| s3_error!(InternalError, "remove bucket quota failed: {e}"))?;
+            return Ok(empty_ok());
+        }
+
+        let data = quota
+            .marshal()
+            .map_err(|e| s3_error!(InternalError, "{}: {e}", messages::SERIALIZE_CONFIG_FAILED))?;
+
+        metadata_sys::update(&bucket, BUCKET_QUOTA_CONFIG_FILE, data)
+            .await
+            .map_err(|e| s3_error!(InternalError, "set bucket quota failed: {e}"))?;
+
+        Ok(empty_ok())
+    }
+}
+
+pub struct GetBucketQuota {}
+
+#[async_trait::async_trait]
+impl Operation for GetBucketQuota {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::GetBucketQuotaAdminAction).await?;
+        let bucket = validate_bucket_query(&req).await?;
+
+        let quota = BucketQuotaSys::get(&bucket)
+            .await
+            .map_err(|e| s3_error!(InternalError, "get bucket quota failed: {e}"))?
+            .unwrap_or_default();
+
+        let data = quota
+            .marshal()
+            .map_err(|e| s3_error!(InternalError, "{}: {e}", messages::SERIALIZE_CONFIG_FAILED))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
+pub struct RemoveBucketQuota {}
+
+#[async_trait::async_trait]
+impl Operation for RemoveBucketQuota {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::SetBucketQuotaAdminAction).await?;
+        let bucket = validate_bucket_query(&req).await?;
+
+        metadata_sys::delete(&bucket, BUCKET_QUOTA_CONFIG_FILE)
+            .await
+            .map_err(|e| s3_error!(InternalError, "remove bucket quota failed: {e}"))?;
+
+        Ok(empty_ok())
+    }
+}
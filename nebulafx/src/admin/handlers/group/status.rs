@@ -1,9 +1,11 @@
 use crate::{
     admin::{auth::validate_admin_request, router::Operation},
     auth::{check_key_valid, get_session_token},
+    site_replication,
 };
 use http::{HeaderMap, StatusCode};
 use matchit::Params;
+use nebulafx_madmin::site_replication::SRIAMItem;
 use nebulafx_policy::policy::action::{Action, AdminAction};
 use s3s::{
     Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result,
@@ -72,6 +74,13 @@ impl Operation for SetGroupStatus {
                     return Err(s3_error!(InvalidArgument, "invalid status"));
                 }
             }
+
+            site_replication::get()
+                .iam_hook(SRIAMItem::GroupStatus {
+                    group: query.group.clone(),
+                    enabled: status == "enabled",
+                })
+                .await;
         } else {
             return Err(s3_error!(InvalidArgument, "status is required"));
         }
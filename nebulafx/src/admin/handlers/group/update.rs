@@ -1,12 +1,14 @@
 use crate::{
     admin::{auth::validate_admin_request, router::Operation, utils::has_space_be},
     auth::{check_key_valid, get_session_token},
+    site_replication,
 };
 use http::{HeaderMap, StatusCode};
 use matchit::Params;
 use nebulafx_ecstore::global::get_global_action_cred;
 use nebulafx_iamx::error::{is_err_no_such_group, is_err_no_such_user};
 use nebulafx_madmin::GroupAddRemove;
+use nebulafx_madmin::site_replication::SRIAMItem;
 use nebulafx_policy::policy::action::{Action, AdminAction};
 use s3s::{
     Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result,
@@ -86,6 +88,12 @@ impl Operation for UpdateGroupMembers {
             }
         }
 
+        let sr_item = SRIAMItem::GroupMembers {
+            group: args.group.clone(),
+            members: args.members.clone(),
+            is_remove: args.is_remove,
+        };
+
         if args.is_remove {
             warn!("remove group members");
             iam_store
@@ -110,6 +118,8 @@ impl Operation for UpdateGroupMembers {
             })?;
         }
 
+        site_replication::get().iam_hook(sr_item).await;
+
         let mut header = HeaderMap::new();
         header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
         header.insert(CONTENT_LENGTH, "0".parse().unwrap());
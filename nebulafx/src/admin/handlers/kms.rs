@@ -0,0 +1,353 @@
+
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use nebulafx_kms::{
+    CancelKeyDeletionRequest, CreateKeyRequest, DeleteKeyRequest, DescribeKeyRequest, KmsConfigSummary, KmsError, KmsManager,
+    KmsServiceStatus, KmsStatusResponse, ListKeysRequest, get_global_kms_service_manager,
+};
+use nebulafx_policy::policy::action::{Action, KmsAction};
+use s3s::{
+    Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result,
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    s3_error,
+};
+use serde::{Deserialize, Serialize};
+use serde_urlencoded::from_bytes;
+use tracing::warn;
+
+use crate::{
+    admin::{auth::validate_admin_request, router::Operation},
+    auth::{check_key_valid, get_session_token},
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct KmsKeyQuery {
+    #[serde(rename = "key-id", default)]
+    pub key_id: String,
+    #[serde(rename = "pending-window-days")]
+    pub pending_window_days: Option<u32>,
+    pub limit: Option<u32>,
+    pub marker: Option<String>,
+}
+
+/// Optional body of a create key request
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CreateKmsKeyBody {
+    pub description: Option<String>,
+    pub tags: HashMap<String, String>,
+}
+
+fn parse_query(req: &S3Request<Body>) -> S3Result<KmsKeyQuery> {
+    match req.uri.query() {
+        Some(query) => from_bytes(query.as_bytes()).map_err(|e| s3_error!(InvalidArgument, "invalid query: {e}")),
+        None => Ok(KmsKeyQuery::default()),
+    }
+}
+
+/// Key ids are used as file names by the local backend, so only plain names are accepted
+fn is_valid_key_id(key_id: &str) -> bool {
+    key_id != "."
+        && key_id != ".."
+        && key_id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+}
+
+/// Parse the query and make sure it names a key
+fn parse_key_query(req: &S3Request<Body>) -> S3Result<KmsKeyQuery> {
+    let query = parse_query(req)?;
+    if query.key_id.is_empty() {
+        return Err(s3_error!(InvalidArgument, "key-id is required"));
+    }
+    if !is_valid_key_id(&query.key_id) {
+        return Err(s3_error!(InvalidArgument, "key-id may only contain letters, digits, '.', '_' and '-'"));
+    }
+    Ok(query)
+}
+
+async fn authorize(req: &S3Request<Body>, action: KmsAction) -> S3Result<()> {
+    let Some(input_cred) = &req.credentials else {
+        return Err(s3_error!(InvalidRequest, "get cred failed"));
+    };
+
+    let (cred, owner) =
+        check_key_valid(get_session_token(&req.uri, &req.headers).unwrap_or_default(), &input_cred.access_key).await?;
+
+    validate_admin_request(&req.headers, &cred, owner, false, vec![Action::KmsAction(action)]).await
+}
+
+async fn kms_manager() -> S3Result<Arc<KmsManager>> {
+    let Some(service) = get_global_kms_service_manager() else {
+        return Err(s3_error!(NotImplemented, "KMS is not configured"));
+    };
+    service
+        .get_manager()
+        .await
+        .ok_or_else(|| s3_error!(NotImplemented, "KMS is not running"))
+}
+
+fn kms_error(err: KmsError) -> S3Error {
+    let code = match &err {
+        KmsError::KeyNotFound { .. } => S3ErrorCode::NoSuchKey,
+        KmsError::KeyAlreadyExists { .. }
+        | KmsError::InvalidOperation { .. }
+        | KmsError::ValidationError { .. }
+        | KmsError::InvalidKey { .. } => S3ErrorCode::InvalidRequest,
+        KmsError::AccessDenied { .. } => S3ErrorCode::AccessDenied,
+        _ => S3ErrorCode::InternalError,
+    };
+    S3Error::with_message(code, err.to_string())
+}
+
+fn json_ok<T: Serialize>(value: &T) -> S3Result<S3Response<(StatusCode, Body)>> {
+    let data = serde_json::to_vec(value).map_err(|e| s3_error!(InternalError, "marshal response failed: {e}"))?;
+
+    let mut header = HeaderMap::new();
+    header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+}
+
+fn empty_ok() -> S3Response<(StatusCode, Body)> {
+    let mut header = HeaderMap::new();
+    header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    header.insert(CONTENT_LENGTH, "0".parse().unwrap());
+    S3Response::with_headers((StatusCode::OK, Body::empty()), header)
+}
+
+/// Reports the configured backend, the default SSE key and whether the backend is reachable
+pub struct KmsStatus {}
+
+#[async_trait::async_trait]
+impl Operation for KmsStatus {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, KmsAction::StatusAction).await?;
+
+        let Some(service) = get_global_kms_service_manager() else {
+            return json_ok(&KmsStatusResponse {
+                status: KmsServiceStatus::NotConfigured,
+                backend_type: None,
+                healthy: None,
+                config_summary: None,
+            });
+        };
+
+        let status = service.get_status().await;
+        let config = service.get_config().await;
+        let healthy = match status {
+            KmsServiceStatus::Running => Some(service.health_check().await.unwrap_or_else(|e| {
+                warn!("kms health check failed: {e}");
+                false
+            })),
+            _ => None,
+        };
+
+        json_ok(&KmsStatusResponse {
+            status,
+            backend_type: config.as_ref().map(|c| c.backend.clone()),
+            healthy,
+            config_summary: config.as_ref().map(KmsConfigSummary::from),
+        })
+    }
+}
+
+pub struct CreateKmsKey {}
+
+#[async_trait::async_trait]
+impl Operation for CreateKmsKey {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, KmsAction::CreateKeyAction).await?;
+        let query = parse_key_query(&req)?;
+        let manager = kms_manager().await?;
+
+        let mut input = req.input;
+        let body = match input.store_all_unlimited().await {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("get body failed, e: {:?}", e);
+                return Err(s3_error!(InvalidRequest, "get body failed"));
+            }
+        };
+        let body: CreateKmsKeyBody = if body.is_empty() {
+            CreateKmsKeyBody::default()
+        } else {
+            serde_json::from_slice(&body).map_err(|e| s3_error!(InvalidArgument, "invalid create key request: {e}"))?
+        };
+
+        let resp = manager
+            .create_key(CreateKeyRequest {
+                key_name: Some(query.key_id),
+                description: body.description,
+                tags: body.tags,
+                ..Default::default()
+            })
+            .await
+            .map_err(kms_error)?;
+
+        json_ok(&resp)
+    }
+}
+
+pub struct ListKmsKeys {}
+
+#[async_trait::async_trait]
+impl Operation for ListKmsKeys {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, KmsAction::ListKeysAction).await?;
+        let query = parse_query(&req)?;
+        let manager = kms_manager().await?;
+
+        let defaults = ListKeysRequest::default();
+        let resp = manager
+            .list_keys(ListKeysRequest {
+                limit: query.limit.or(defaults.limit),
+                marker: query.marker,
+                ..defaults
+            })
+            .await
+            .map_err(kms_error)?;
+
+        json_ok(&resp)
+    }
+}
+
+pub struct DescribeKmsKey {}
+
+#[async_trait::async_trait]
+impl Operation for DescribeKmsKey {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, KmsAction::DescribeKeyAction).await?;
+        let query = parse_key_query(&req)?;
+        let manager = kms_manager().await?;
+
+        let resp = manager
+            .describe_key(DescribeKeyRequest { key_id: query.key_id })
+            .await
+            .map_err(kms_error)?;
+
+        json_ok(&resp)
+    }
+}
+
+pub struct RotateKmsKey {}
+
+#[async_trait::async_trait]
+impl Operation for RotateKmsKey {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, KmsAction::RotateKeyAction).await?;
+        let query = parse_key_query(&req)?;
+        let manager = kms_manager().await?;
+
+        let master_key = manager.rotate_key(&query.key_id).await.map_err(kms_error)?;
+
+        json_ok(&master_key)
+    }
+}
+
+pub struct EnableKmsKey {}
+
+#[async_trait::async_trait]
+impl Operation for EnableKmsKey {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, KmsAction::EnableKeyAction).await?;
+        let query = parse_key_query(&req)?;
+        let manager = kms_manager().await?;
+
+        manager.enable_key(&query.key_id).await.map_err(kms_error)?;
+
+        Ok(empty_ok())
+    }
+}
+
+pub struct DisableKmsKey {}
+
+#[async_trait::async_trait]
+impl Operation for DisableKmsKey {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, KmsAction::DisableKeyAction).await?;
+        let query = parse_key_query(&req)?;
+        let manager = kms_manager().await?;
+
+        manager.disable_key(&query.key_id).await.map_err(kms_error)?;
+
+        Ok(empty_ok())
+    }
+}
+
+/// Schedules a key for deletion after `pending-window-days` (backend default when omitted)
+pub struct DeleteKmsKey {}
+
+#[async_trait::async_trait]
+impl Operation for DeleteKmsKey {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, KmsAction::DeleteKeyAction).await?;
+        let query = parse_key_query(&req)?;
+        let manager = kms_manager().await?;
+
+        let resp = manager
+            .delete_key(DeleteKeyRequest {
+                key_id: query.key_id,
+                pending_window_in_days: query.pending_window_days,
+                force_immediate: None,
+            })
+            .await
+            .map_err(kms_error)?;
+
+        json_ok(&resp)
+    }
+}
+
+pub struct CancelKmsKeyDeletion {}
+
+#[async_trait::async_trait]
+impl Operation for CancelKmsKeyDeletion {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, KmsAction::CancelKeyDeletionAction).await?;
+        let query = parse_key_query(&req)?;
+        let manager = kms_manager().await?;
+
+        let resp = manager
+            .cancel_key_deletion(CancelKeyDeletionRequest { key_id: query.key_id })
+            .await
+            .map_err(kms_error)?;
+
+        json_ok(&resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_kms_key_query() {
+        let query: KmsKeyQuery = from_bytes(b"key-id=my-key&pending-window-days=7").unwrap();
+        assert_eq!(query.key_id, "my-key");
+        assert_eq!(query.pending_window_days, Some(7));
+
+        let query: KmsKeyQuery = from_bytes(b"limit=10").unwrap();
+        assert!(query.key_id.is_empty());
+        assert_eq!(query.limit, Some(10));
+    }
+
+    #[test]
+    fn test_key_id_validation() {
+        for key_id in ["my-key", "tenant.key_1", "ABC123"] {
+            assert!(is_valid_key_id(key_id), "{key_id}");
+        }
+        for key_id in [".", "..", "../etc/passwd", "a/b", "a\\b", "key id", "kéy"] {
+            assert!(!is_valid_key_id(key_id), "{key_id}");
+        }
+    }
+
+    #[test]
+    fn test_kms_error_codes() {
+        assert_eq!(*kms_error(KmsError::key_not_found("k")).code(), S3ErrorCode::NoSuchKey);
+        assert_eq!(*kms_error(KmsError::key_already_exists("k")).code(), S3ErrorCode::InvalidRequest);
+        assert_eq!(*kms_error(KmsError::backend_error("down")).code(), S3ErrorCode::InternalError);
+    }
+}
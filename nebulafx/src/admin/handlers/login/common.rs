@@ -1,4 +1,4 @@
-use http::StatusCode;
+use http::{HeaderMap, StatusCode, header::CONTENT_TYPE};
 use nebulafx_ecstore::bucket::utils::serialize;
 use nebulafx_iamx::sys::SESSION_POLICY_NAME;
 use nebulafx_policy::{auth::Credentials as PolicyCredentials, policy::Policy};
@@ -11,10 +11,13 @@ use serde::Deserialize;
 use serde_json::Value;
 use serde_urlencoded::from_bytes;
 use std::collections::HashMap;
-use time::{Duration, OffsetDateTime};
+use time::{Duration, OffsetDateTime, format_description::well_known::Rfc3339};
 use tracing::warn;
 
 pub const ASSUME_ROLE_ACTION: &str = "AssumeRole";
+pub const ASSUME_ROLE_WITH_WEB_IDENTITY_ACTION: &str = "AssumeRoleWithWebIdentity";
+pub const ASSUME_ROLE_WITH_CLIENT_GRANTS_ACTION: &str = "AssumeRoleWithClientGrants";
+pub const ASSUME_ROLE_WITH_LDAP_IDENTITY_ACTION: &str = "AssumeRoleWithLDAPIdentity";
 pub const ASSUME_ROLE_VERSION: &str = "2011-06-15";
 
 #[derive(Deserialize, Debug, Default, Clone)]
@@ -27,6 +30,15 @@ pub struct AssumeRoleRequest {
     pub role_session_name: String,
     pub policy: String,
     pub external_id: String,
+    /// ID token of AssumeRoleWithWebIdentity
+    pub web_identity_token: String,
+    /// Access token of AssumeRoleWithClientGrants
+    pub token: String,
+    /// Directory credentials of AssumeRoleWithLDAPIdentity
+    #[serde(rename = "LDAPUsername")]
+    pub ldap_username: String,
+    #[serde(rename = "LDAPPassword")]
+    pub ldap_password: String,
 }
 
 /// Parse and validate the request body for AssumeRole
@@ -45,7 +57,13 @@ pub async fn parse_assume_role_request(mut input: Body) -> S3Result<AssumeRoleRe
         .map_err(|_e| S3Error::with_message(S3ErrorCode::InvalidRequest, messages::GET_BODY_FAILED))?;
 
     // Validate action and version
-    if body.action.as_str() != ASSUME_ROLE_ACTION {
+    let actions = [
+        ASSUME_ROLE_ACTION,
+        ASSUME_ROLE_WITH_WEB_IDENTITY_ACTION,
+        ASSUME_ROLE_WITH_CLIENT_GRANTS_ACTION,
+        ASSUME_ROLE_WITH_LDAP_IDENTITY_ACTION,
+    ];
+    if !actions.contains(&body.action.as_str()) {
         return Err(S3Error::with_message(S3ErrorCode::InvalidArgument, messages::NOT_SUPPORT_ACTION));
     }
 
@@ -134,3 +152,81 @@ pub fn build_assume_role_response(new_cred: &PolicyCredentials) -> S3Response<(S
     S3Response::new((StatusCode::OK, Body::from(output)))
 }
 
+/// Build the response of AssumeRoleWithWebIdentity or AssumeRoleWithClientGrants from temporary credentials
+/// s3s only has the AssumeRole output, so the STS document is written here
+pub fn build_web_identity_response(
+    action: &str,
+    new_cred: &PolicyCredentials,
+    subject: &str,
+    audience: &str,
+    provider: &str,
+) -> S3Response<(StatusCode, Body)> {
+    let subject_element = if action == ASSUME_ROLE_WITH_WEB_IDENTITY_ACTION {
+        "SubjectFromWebIdentityToken"
+    } else {
+        "SubjectFromToken"
+    };
+
+    let result = format!(
+        concat!(
+            "<{subject_element}>{subject}</{subject_element}><Audience>{audience}</Audience>",
+            "{credentials}<Provider>{provider}</Provider>"
+        ),
+        subject_element = subject_element,
+        subject = xml_escape(subject),
+        audience = xml_escape(audience),
+        credentials = credentials_xml(new_cred),
+        provider = xml_escape(provider),
+    );
+
+    sts_response(action, &result)
+}
+
+/// Build the response of AssumeRoleWithLDAPIdentity from temporary credentials
+pub fn build_ldap_identity_response(new_cred: &PolicyCredentials) -> S3Response<(StatusCode, Body)> {
+    sts_response(ASSUME_ROLE_WITH_LDAP_IDENTITY_ACTION, &credentials_xml(new_cred))
+}
+
+fn credentials_xml(new_cred: &PolicyCredentials) -> String {
+    let expiration = new_cred
+        .expiration
+        .unwrap_or(OffsetDateTime::now_utc().saturating_add(Duration::seconds(3600)))
+        .format(&Rfc3339)
+        .unwrap_or_default();
+    format!(
+        concat!(
+            "<Credentials><AccessKeyId>{access_key}</AccessKeyId><SecretAccessKey>{secret_key}</SecretAccessKey>",
+            "<SessionToken>{session_token}</SessionToken><Expiration>{expiration}</Expiration></Credentials>"
+        ),
+        access_key = xml_escape(&new_cred.access_key),
+        secret_key = xml_escape(&new_cred.secret_key),
+        session_token = xml_escape(&new_cred.session_token),
+        expiration = expiration,
+    )
+}
+
+fn sts_response(action: &str, result: &str) -> S3Response<(StatusCode, Body)> {
+    let output = format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<{action}Response xmlns="https://sts.amazonaws.com/doc/{version}/">"#,
+            "<{action}Result>{result}</{action}Result></{action}Response>"
+        ),
+        action = action,
+        version = ASSUME_ROLE_VERSION,
+        result = result,
+    );
+
+    let mut header = HeaderMap::new();
+    header.insert(CONTENT_TYPE, "application/xml".parse().unwrap());
+    S3Response::with_headers((StatusCode::OK, Body::from(output)), header)
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
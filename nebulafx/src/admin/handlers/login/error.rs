@@ -25,6 +25,18 @@ pub mod messages {
     pub const NOT_SUPPORT_ACTION: &str = "not support action";
     pub const NOT_SUPPORT_VERSION: &str = "not support version";
     
+    // Web identity errors
+    pub const MISSING_WEB_IDENTITY_TOKEN: &str = "missing web identity token";
+    pub const OPENID_NOT_CONFIGURED: &str = "no openid provider is configured";
+    pub const INVALID_WEB_IDENTITY_TOKEN: &str = "invalid web identity token";
+    pub const NO_POLICY_CLAIM: &str = "token has no policy claim";
+
+    // LDAP identity errors
+    pub const MISSING_LDAP_CREDENTIALS: &str = "missing LDAP username or password";
+    pub const LDAP_NOT_CONFIGURED: &str = "LDAP is not configured";
+    pub const LDAP_LOGIN_FAILED: &str = "LDAP login failed";
+    pub const NO_LDAP_POLICY: &str = "no policy is mapped to the LDAP user or its groups";
+
     // Signing key errors
     pub const GLOBAL_ACTIVE_SK_NOT_INIT: &str = "global active sk not init";
 }
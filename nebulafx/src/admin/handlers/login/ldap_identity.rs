@@ -0,0 +1,76 @@
+use http::StatusCode;
+use nebulafx_iamx::get_token_signing_key;
+use nebulafx_policy::auth::get_new_credentials_with_metadata;
+use nebulafx_policy::auth::ldap::{LDAP_GROUPS_CLAIM, LDAP_USER_CLAIM, LDAP_USERNAME_CLAIM};
+use s3s::{Body, S3Error, S3ErrorCode, S3Response, S3Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use super::common::{build_claims, build_ldap_identity_response, AssumeRoleRequest};
+use super::error::messages;
+use tracing::{error, info, warn};
+
+/// Handle AssumeRoleWithLDAPIdentity requests
+/// The request is not signed, the directory checks the username and password it carries
+/// The DN and groups of the user are kept in the session token, its policies are the ones mapped to them
+pub(super) async fn handle_ldap_identity_login(body: AssumeRoleRequest) -> S3Result<S3Response<(StatusCode, Body)>> {
+    if body.ldap_username.is_empty() || body.ldap_password.is_empty() {
+        return Err(S3Error::with_message(S3ErrorCode::InvalidArgument, messages::MISSING_LDAP_CREDENTIALS));
+    }
+
+    // Get IAM store
+    let Ok(iam_store) = nebulafx_iamx::get() else {
+        return Err(S3Error::with_message(S3ErrorCode::InvalidRequest, messages::IAM_NOT_INIT));
+    };
+
+    let Some(ldap) = iam_store.ldap() else {
+        return Err(S3Error::with_message(S3ErrorCode::InvalidRequest, messages::LDAP_NOT_CONFIGURED));
+    };
+
+    let user = ldap.authenticate(&body.ldap_username, &body.ldap_password).await.map_err(|e| {
+        warn!("{} authenticate {} failed, err: {:?}", body.action, body.ldap_username, e);
+        S3Error::with_message(S3ErrorCode::AccessDenied, messages::LDAP_LOGIN_FAILED)
+    })?;
+
+    // Credentials without a mapped policy would not be allowed anything
+    let policies = iam_store.ldap_policy_db_get(&user.dn, &user.groups).await.map_err(|e| {
+        error!("{} get policies of {} failed, err: {:?}", body.action, user.dn, e);
+        S3Error::with_message(S3ErrorCode::InternalError, e.to_string())
+    })?;
+    if policies.is_empty() {
+        return Err(S3Error::with_message(S3ErrorCode::AccessDenied, messages::NO_LDAP_POLICY));
+    }
+
+    let ldap_claims = HashMap::from([
+        (LDAP_USER_CLAIM.to_string(), Value::String(user.dn.clone())),
+        (LDAP_USERNAME_CLAIM.to_string(), Value::String(user.username.clone())),
+        (
+            LDAP_GROUPS_CLAIM.to_string(),
+            Value::Array(user.groups.iter().cloned().map(Value::String).collect()),
+        ),
+    ]);
+
+    // Build claims
+    let claims = build_claims(Some(ldap_claims), &body.policy, body.duration_seconds, &user.dn)?;
+
+    // Get signing key
+    let Some(secret) = get_token_signing_key() else {
+        return Err(S3Error::with_message(S3ErrorCode::InvalidArgument, messages::GLOBAL_ACTIVE_SK_NOT_INIT));
+    };
+
+    // Generate new temporary credentials
+    let mut new_cred = get_new_credentials_with_metadata(&claims, &secret)
+        .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("{} {}", messages::GET_NEW_CRED_FAILED, e)))?;
+
+    new_cred.parent_user = user.dn;
+
+    // Save temporary credentials
+    if let Err(err) = iam_store.set_temp_user(&new_cred.access_key, &new_cred, None).await {
+        error!("{} set temp user failed, err: {:?}, access_key: {:?}", body.action, err, new_cred.access_key);
+        return Err(S3Error::with_message(S3ErrorCode::InternalError, messages::SET_TEMP_USER_FAILED));
+    }
+
+    info!("{} issued credentials for {}", body.action, new_cred.parent_user);
+
+    // Build response
+    Ok(build_ldap_identity_response(&new_cred))
+}
@@ -1,5 +1,7 @@
 mod key_login;
+mod ldap_identity;
 mod sts_login;
+mod web_identity;
 mod common;
 mod error;
 
@@ -10,10 +12,15 @@ use s3s::{Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result};
 use crate::auth::get_session_token;
 
 use key_login::handle_key_login;
+use ldap_identity::handle_ldap_identity_login;
 use sts_login::handle_sts_login;
-use common::parse_assume_role_request;
+use web_identity::handle_web_identity_login;
+use common::{
+    parse_assume_role_request, ASSUME_ROLE_WITH_CLIENT_GRANTS_ACTION, ASSUME_ROLE_WITH_LDAP_IDENTITY_ACTION,
+    ASSUME_ROLE_WITH_WEB_IDENTITY_ACTION,
+};
 
-/// Main login handler that dispatches to KeyLogin or StsLogin based on request
+/// Main login handler that dispatches to KeyLogin, StsLogin, the web identity login or the LDAP login based on request
 /// This is the only public interface for login functionality
 pub struct LoginHandle {}
 
@@ -22,6 +29,16 @@ impl Operation for LoginHandle {
     async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
         let body = parse_assume_role_request(req.input).await?;
         let session_token = get_session_token(&req.uri, &req.headers);
+
+        // Web identity login: unsigned, the identity token authenticates the request
+        if body.action == ASSUME_ROLE_WITH_WEB_IDENTITY_ACTION || body.action == ASSUME_ROLE_WITH_CLIENT_GRANTS_ACTION {
+            return handle_web_identity_login(body).await;
+        }
+
+        // LDAP login: unsigned, the directory checks the username and password
+        if body.action == ASSUME_ROLE_WITH_LDAP_IDENTITY_ACTION {
+            return handle_ldap_identity_login(body).await;
+        }
         
         // Extract credentials
         let Some(user) = req.credentials else { 
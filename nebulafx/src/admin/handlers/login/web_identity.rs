@@ -0,0 +1,95 @@
+use http::StatusCode;
+use nebulafx_iamx::get_token_signing_key;
+use nebulafx_iamx::sys::POLICYNAME;
+use nebulafx_policy::auth::get_new_credentials_with_metadata;
+use nebulafx_policy::auth::openid::{OPENID_PARENT_USER_PREFIX, OpenIdProvider};
+use s3s::{Body, S3Error, S3ErrorCode, S3Response, S3Result};
+use serde_json::Value;
+use super::common::{build_claims, build_web_identity_response, AssumeRoleRequest, ASSUME_ROLE_WITH_WEB_IDENTITY_ACTION};
+use super::error::messages;
+use tracing::{error, info, warn};
+
+/// Handle AssumeRoleWithWebIdentity and AssumeRoleWithClientGrants requests
+/// The request is not signed, the token it carries is checked against the OpenID provider that issued it
+/// and its claims are kept in the session token so that policies can use them as `jwt:*` condition keys
+pub(super) async fn handle_web_identity_login(body: AssumeRoleRequest) -> S3Result<S3Response<(StatusCode, Body)>> {
+    let token = if body.action == ASSUME_ROLE_WITH_WEB_IDENTITY_ACTION {
+        body.web_identity_token.as_str()
+    } else {
+        body.token.as_str()
+    };
+    if token.is_empty() {
+        return Err(S3Error::with_message(S3ErrorCode::InvalidArgument, messages::MISSING_WEB_IDENTITY_TOKEN));
+    }
+
+    // Get IAM store
+    let Ok(iam_store) = nebulafx_iamx::get() else {
+        return Err(S3Error::with_message(S3ErrorCode::InvalidRequest, messages::IAM_NOT_INIT));
+    };
+
+    let openid = iam_store.openid();
+    if !openid.enabled() {
+        return Err(S3Error::with_message(S3ErrorCode::InvalidRequest, messages::OPENID_NOT_CONFIGURED));
+    }
+
+    let provider = openid.find_provider(token, &body.role_arn).map_err(|e| {
+        warn!("{} find openid provider failed, err: {:?}", body.action, e);
+        S3Error::with_message(S3ErrorCode::InvalidArgument, format!("{} {}", messages::INVALID_WEB_IDENTITY_TOKEN, e))
+    })?;
+
+    let mut token_claims = provider.validate(token).await.map_err(|e| {
+        warn!("{} validate token of {} failed, err: {:?}", body.action, provider.config.name, e);
+        S3Error::with_message(S3ErrorCode::AccessDenied, format!("{} {}", messages::INVALID_WEB_IDENTITY_TOKEN, e))
+    })?;
+
+    // Either the role of the provider or the policy claim of the token decides the policies, the
+    // claims the server sets itself are never taken over from the token
+    let policies = provider.policies(&token_claims);
+    OpenIdProvider::strip_reserved_claims(&mut token_claims);
+    if let Some(role_arn) = provider.config.role_arn() {
+        token_claims.insert("roleArn".to_string(), Value::String(role_arn));
+    } else if policies.is_empty() {
+        return Err(S3Error::with_message(S3ErrorCode::AccessDenied, messages::NO_POLICY_CLAIM));
+    } else {
+        token_claims.insert(POLICYNAME.to_string(), Value::String(policies.join(",")));
+    }
+
+    let subject = token_claims.get("sub").and_then(Value::as_str).unwrap_or_default().to_string();
+    let audience = match token_claims.get("aud") {
+        Some(Value::String(aud)) => aud.clone(),
+        Some(Value::Array(auds)) => auds.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(","),
+        _ => String::new(),
+    };
+    let parent_user = format!("{}{}:{}", OPENID_PARENT_USER_PREFIX, provider.config.name, subject);
+
+    // Build claims, the expiry of the ID token is replaced by the one of the session
+    let claims = build_claims(Some(token_claims), &body.policy, body.duration_seconds, &parent_user)?;
+
+    // Get signing key
+    let Some(secret) = get_token_signing_key() else {
+        return Err(S3Error::with_message(S3ErrorCode::InvalidArgument, messages::GLOBAL_ACTIVE_SK_NOT_INIT));
+    };
+
+    // Generate new temporary credentials
+    let mut new_cred = get_new_credentials_with_metadata(&claims, &secret)
+        .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("{} {}", messages::GET_NEW_CRED_FAILED, e)))?;
+
+    new_cred.parent_user = parent_user;
+
+    // Save temporary credentials
+    if let Err(err) = iam_store.set_temp_user(&new_cred.access_key, &new_cred, None).await {
+        error!("{} set temp user failed, err: {:?}, access_key: {:?}", body.action, err, new_cred.access_key);
+        return Err(S3Error::with_message(S3ErrorCode::InternalError, messages::SET_TEMP_USER_FAILED));
+    }
+
+    info!("{} issued credentials for {}", body.action, new_cred.parent_user);
+
+    // Build response
+    Ok(build_web_identity_response(
+        &body.action,
+        &new_cred,
+        &subject,
+        &audience,
+        &provider.config.issuer,
+    ))
+}
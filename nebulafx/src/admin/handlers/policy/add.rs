@@ -1,9 +1,11 @@
 use crate::{
     admin::{auth::validate_admin_request, router::Operation, utils::has_space_be},
     auth::{check_key_valid, get_session_token},
+    site_replication,
 };
 use http::{HeaderMap, StatusCode};
 use matchit::Params;
+use nebulafx_madmin::site_replication::SRIAMItem;
 use nebulafx_policy::policy::{
     Policy,
     action::{Action, AdminAction},
@@ -79,11 +81,20 @@ impl Operation for AddCannedPolicy {
 
         let Ok(iam_store) = nebulafx_iamx::get() else { return Err(s3_error!(InternalError, "iam not init")) };
 
+        let sr_item = SRIAMItem::Policy {
+            name: query.name.clone(),
+            policy: Some(
+                serde_json::to_value(&policy).map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, e.to_string()))?,
+            ),
+        };
+
         iam_store.set_policy(&query.name, policy).await.map_err(|e| {
             warn!("set policy failed, e: {:?}", e);
             S3Error::with_message(S3ErrorCode::InternalError, e.to_string())
         })?;
 
+        site_replication::get().iam_hook(sr_item).await;
+
         let mut header = HeaderMap::new();
         header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
         header.insert(CONTENT_LENGTH, "0".parse().unwrap());
@@ -1,9 +1,11 @@
 use crate::{
     admin::{auth::validate_admin_request, router::Operation},
     auth::{check_key_valid, get_session_token},
+    site_replication,
 };
 use http::{HeaderMap, StatusCode};
 use matchit::Params;
+use nebulafx_madmin::site_replication::SRIAMItem;
 use nebulafx_policy::policy::action::{Action, AdminAction};
 use s3s::{
     Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result,
@@ -59,6 +61,13 @@ impl Operation for RemoveCannedPolicy {
             S3Error::with_message(S3ErrorCode::InternalError, e.to_string())
         })?;
 
+        site_replication::get()
+            .iam_hook(SRIAMItem::Policy {
+                name: query.name.clone(),
+                policy: None,
+            })
+            .await;
+
         let mut header = HeaderMap::new();
         header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
         header.insert(CONTENT_LENGTH, "0".parse().unwrap());
@@ -1,12 +1,15 @@
 use crate::{
     admin::{auth::validate_admin_request, router::Operation},
     auth::{check_key_valid, get_session_token},
+    site_replication,
 };
 use http::{HeaderMap, StatusCode};
 use matchit::Params;
 use nebulafx_ecstore::global::get_global_action_cred;
 use nebulafx_iamx::error::is_err_no_such_user;
 use nebulafx_iamx::UserType;
+use nebulafx_madmin::site_replication::SRIAMItem;
+use nebulafx_policy::auth::ldap::{is_dn, normalize_dn};
 use nebulafx_policy::policy::action::{Action, AdminAction};
 use s3s::{
     Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result,
@@ -57,7 +60,16 @@ impl Operation for SetPolicyForUserOrGroup {
 
         let Ok(iam_store) = nebulafx_iamx::get() else { return Err(s3_error!(InternalError, "iam not init")) };
 
-        if !query.is_group {
+        // Directory users and groups are named by DN and are unknown until they log in,
+        // their mappings are stored as STS mappings
+        let is_ldap = iam_store.ldap().is_some() && is_dn(&query.user_or_group);
+        let (name, user_type) = if is_ldap {
+            (normalize_dn(&query.user_or_group), UserType::Sts)
+        } else {
+            (query.user_or_group.clone(), UserType::Reg)
+        };
+
+        if !is_ldap && !query.is_group {
             match iam_store.is_temp_user(&query.user_or_group).await {
                 Ok((ok, _)) => {
                     if ok {
@@ -81,11 +93,12 @@ impl Operation for SetPolicyForUserOrGroup {
             }
         }
 
-        if !query.is_group {
+        // DNs are checked against the directory when the user logs in
+        if !is_ldap && !query.is_group {
             if iam_store.get_user(&query.user_or_group).await.is_none() {
                 return Err(s3_error!(InvalidArgument, "user not exist"));
             }
-        } else {
+        } else if !is_ldap {
             iam_store.get_group_description(&query.user_or_group).await.map_err(|e| {
                 warn!("get group description failed, e: {:?}", e);
                 S3Error::with_message(S3ErrorCode::InternalError, e.to_string())
@@ -93,13 +106,21 @@ impl Operation for SetPolicyForUserOrGroup {
         }
 
         iam_store
-            .policy_db_set(&query.user_or_group, UserType::Reg, query.is_group, &query.policy_name)
+            .policy_db_set(&name, user_type, query.is_group, &query.policy_name)
             .await
             .map_err(|e| {
                 warn!("policy db set failed, e: {:?}", e);
                 S3Error::with_message(S3ErrorCode::InternalError, e.to_string())
             })?;
 
+        site_replication::get()
+            .iam_hook(SRIAMItem::PolicyMapping {
+                user_or_group: name.clone(),
+                is_group: query.is_group,
+                policy: query.policy_name.clone(),
+            })
+            .await;
+
         let mut header = HeaderMap::new();
         header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
         header.insert(CONTENT_LENGTH, "0".parse().unwrap());
@@ -0,0 +1,732 @@
+
+
+//! Prometheus scrape endpoints.
+//!
+//! Metrics are split into the `cluster`, `node`, `bucket` and `replication`
+//! groups. The v2 paths bundle them the way existing scrape configs expect,
+//! the v3 paths expose one group each. Scrapers authenticate with a bearer
+//! token signed by an access key's secret (see [`PrometheusTokenHandler`]),
+//! or with a regular signed request.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::header::AUTHORIZATION;
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use nebulafx_ahm::get_global_scanner;
+use nebulafx_common::data_usage::DataUsageInfo;
+use nebulafx_common::globals::GLOBAL_Local_Node_Name;
+use nebulafx_common::heal_channel::DriveState;
+use nebulafx_ecstore::bucket::replication::GLOBAL_REPLICATION_STATS;
+use nebulafx_ecstore::data_usage::load_data_usage_from_backend;
+use nebulafx_ecstore::new_object_layer_fn;
+use nebulafx_ecstore::store_api::StorageAPI;
+use nebulafx_iamx::utils::{extract_claims, generate_jwt};
+use nebulafx_madmin::Disk;
+use nebulafx_madmin::utils::parse_duration;
+use nebulafx_policy::policy::action::{Action, AdminAction};
+use s3s::header::CONTENT_TYPE;
+use s3s::{Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result, s3_error};
+use serde::{Deserialize, Serialize};
+use serde_urlencoded::from_bytes;
+use tracing::warn;
+
+use crate::admin::{auth::validate_admin_request, router::Operation};
+use crate::auth::{check_key_valid, get_session_token};
+
+pub const PROMETHEUS_V2_PREFIX: &str = "/nebulafx/v2/metrics";
+pub const PROMETHEUS_V3_PREFIX: &str = "/nebulafx/metrics/v3";
+
+/// `jwt` (default) requires a bearer token or signature, `public` disables authentication
+pub const ENV_PROMETHEUS_AUTH_TYPE: &str = "NEUBULAFX_PROMETHEUS_AUTH_TYPE";
+
+const PROMETHEUS_ISSUER: &str = "prometheus";
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const DEFAULT_TOKEN_EXPIRY: Duration = Duration::from_secs(365 * 24 * 3600);
+const METRIC_NAMESPACE: &str = "nebulafx";
+
+/// A set of related metrics that can be scraped together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsGroup {
+    /// Cluster-wide capacity, drive and usage totals
+    Cluster,
+    /// Drives, locks, scanner and process metrics of the node serving the scrape
+    Node,
+    /// Per-bucket usage
+    Bucket,
+    /// Per-bucket, per-target replication
+    Replication,
+}
+
+pub const V2_CLUSTER_GROUPS: &[MetricsGroup] = &[MetricsGroup::Cluster, MetricsGroup::Bucket, MetricsGroup::Replication];
+pub const V2_NODE_GROUPS: &[MetricsGroup] = &[MetricsGroup::Node];
+pub const V2_BUCKET_GROUPS: &[MetricsGroup] = &[MetricsGroup::Bucket, MetricsGroup::Replication];
+pub const ALL_GROUPS: &[MetricsGroup] = &[
+    MetricsGroup::Cluster,
+    MetricsGroup::Node,
+    MetricsGroup::Bucket,
+    MetricsGroup::Replication,
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct PrometheusClaims {
+    sub: String,
+    iss: String,
+    exp: u64,
+}
+
+pub struct PrometheusMetricsHandler {
+    pub groups: &'static [MetricsGroup],
+}
+
+#[async_trait::async_trait]
+impl Operation for PrometheusMetricsHandler {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize_scrape(&req).await?;
+
+        let usage = if self.groups.iter().any(|g| *g != MetricsGroup::Node) {
+            load_data_usage().await
+        } else {
+            None
+        };
+
+        let mut w = PrometheusWriter::default();
+        for group in self.groups {
+            match group {
+                MetricsGroup::Cluster => write_cluster_metrics(&mut w, usage.as_ref()).await,
+                MetricsGroup::Node => write_node_metrics(&mut w).await,
+                MetricsGroup::Bucket => write_bucket_metrics(&mut w, usage.as_ref()),
+                MetricsGroup::Replication => write_replication_metrics(&mut w, usage.as_ref()).await,
+            }
+        }
+
+        let mut body = w.finish();
+        if self.groups.contains(&MetricsGroup::Node) {
+            body.push_str(&nebulafx_obs::render_metrics());
+        }
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE.parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(body)), header))
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct PrometheusTokenQuery {
+    /// How long the token stays valid, e.g. `720h`
+    expiry: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PrometheusToken {
+    token: String,
+    expires_at: u64,
+}
+
+/// Issue a bearer token for the calling access key, usable in a Prometheus scrape config
+pub struct PrometheusTokenHandler {}
+
+#[async_trait::async_trait]
+impl Operation for PrometheusTokenHandler {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        let Some(input_cred) = req.credentials else {
+            return Err(s3_error!(InvalidRequest, "get cred failed"));
+        };
+
+        let (cred, owner) =
+            check_key_valid(get_session_token(&req.uri, &req.headers).unwrap_or_default(), &input_cred.access_key).await?;
+
+        validate_admin_request(
+            &req.headers,
+            &cred,
+            owner,
+            false,
+            vec![Action::AdminAction(AdminAction::PrometheusAdminAction)],
+        )
+        .await?;
+
+        if cred.is_temp() && !cred.is_service_account() {
+            return Err(s3_error!(InvalidRequest, "temporary credentials cannot sign prometheus tokens"));
+        }
+
+        let query: PrometheusTokenQuery = match req.uri.query() {
+            Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+            None => PrometheusTokenQuery::default(),
+        };
+        let expiry = match query.expiry {
+            Some(v) => parse_duration(&v).map_err(|e| s3_error!(InvalidArgument, "invalid expiry: {}", e))?,
+            None => DEFAULT_TOKEN_EXPIRY,
+        };
+
+        let expires_at = SystemTime::now()
+            .checked_add(expiry)
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .ok_or_else(|| s3_error!(InvalidArgument, "invalid expiry"))?;
+
+        let claims = PrometheusClaims {
+            sub: cred.access_key.clone(),
+            iss: PROMETHEUS_ISSUER.to_string(),
+            exp: expires_at,
+        };
+
+        let token = generate_jwt(&claims, &cred.secret_key)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("sign token failed: {e}")))?;
+
+        let data = serde_json::to_vec(&PrometheusToken { token, expires_at })
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal token failed: {e}")))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
+fn is_public_access() -> bool {
+    std::env::var(ENV_PROMETHEUS_AUTH_TYPE).is_ok_and(|v| v.trim().eq_ignore_ascii_case("public"))
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+    let token = token.trim();
+    if token.is_empty() { None } else { Some(token) }
+}
+
+/// Read the `sub` claim without verifying the signature; the secret needed to verify it depends on the subject.
+fn token_subject(token: &str) -> Option<String> {
+    let payload = token.split('.').nth(1)?;
+    let payload = base64_simd::URL_SAFE_NO_PAD.decode_to_vec(payload.as_bytes()).ok()?;
+    let claims: HashMap<String, serde_json::Value> = serde_json::from_slice(&payload).ok()?;
+    claims.get("sub")?.as_str().map(String::from)
+}
+
+async fn authorize_scrape(req: &S3Request<Body>) -> S3Result<()> {
+    if is_public_access() {
+        return Ok(());
+    }
+
+    let (cred, owner) = if let Some(input_cred) = &req.credentials {
+        check_key_valid(get_session_token(&req.uri, &req.headers).unwrap_or_default(), &input_cred.access_key).await?
+    } else {
+        let Some(token) = bearer_token(&req.headers) else {
+            return Err(s3_error!(AccessDenied, "Bearer token is required"));
+        };
+        let Some(access_key) = token_subject(token) else {
+            return Err(s3_error!(AccessDenied, "invalid bearer token"));
+        };
+
+        let (cred, owner) = check_key_valid("", &access_key).await?;
+
+        let claims = extract_claims::<PrometheusClaims>(token, &cred.secret_key).map_err(|e| {
+            warn!("prometheus token for {} rejected: {}", access_key, e);
+            s3_error!(AccessDenied, "invalid bearer token")
+        })?;
+        if claims.claims.iss != PROMETHEUS_ISSUER {
+            return Err(s3_error!(AccessDenied, "invalid bearer token"));
+        }
+
+        (cred, owner)
+    };
+
+    validate_admin_request(
+        &req.headers,
+        &cred,
+        owner,
+        false,
+        vec![Action::AdminAction(AdminAction::PrometheusAdminAction)],
+    )
+    .await
+}
+
+async fn load_data_usage() -> Option<DataUsageInfo> {
+    let store = new_object_layer_fn()?;
+    match load_data_usage_from_backend(store).await {
+        Ok(info) => Some(info),
+        Err(e) => {
+            warn!("prometheus: load data usage failed: {:?}", e);
+            None
+        }
+    }
+}
+
+fn is_drive_online(disk: &Disk) -> bool {
+    disk.state == DriveState::Ok.to_string()
+}
+
+async fn write_cluster_metrics(w: &mut PrometheusWriter, usage: Option<&DataUsageInfo>) {
+    let Some(store) = new_object_layer_fn() else {
+        return;
+    };
+
+    let info = store.storage_info().await;
+    let online = info.disks.iter().filter(|d| is_drive_online(d)).count();
+
+    w.family("cluster_drive_online_total", "Total drives online in the cluster", MetricKind::Gauge)
+        .sample(&[], online as f64);
+    w.family("cluster_drive_offline_total", "Total drives offline in the cluster", MetricKind::Gauge)
+        .sample(&[], (info.disks.len() - online) as f64);
+    w.family("cluster_drive_healing_total", "Total drives healing in the cluster", MetricKind::Gauge)
+        .sample(&[], info.disks.iter().filter(|d| d.healing).count() as f64);
+    w.family("cluster_capacity_raw_total_bytes", "Total raw capacity of all drives", MetricKind::Gauge)
+        .sample(&[], info.disks.iter().map(|d| d.total_space).sum::<u64>() as f64);
+    w.family(
+        "cluster_capacity_raw_free_bytes",
+        "Total raw free capacity of all drives",
+        MetricKind::Gauge,
+    )
+    .sample(&[], info.disks.iter().map(|d| d.available_space).sum::<u64>() as f64);
+
+    let Some(usage) = usage else {
+        return;
+    };
+
+    w.family("cluster_usage_object_total", "Total number of objects in the cluster", MetricKind::Gauge)
+        .sample(&[], usage.objects_total_count as f64);
+    w.family(
+        "cluster_usage_version_total",
+        "Total number of object versions in the cluster",
+        MetricKind::Gauge,
+    )
+    .sample(&[], usage.versions_total_count as f64);
+    w.family(
+        "cluster_usage_deletemarker_total",
+        "Total number of delete markers in the cluster",
+        MetricKind::Gauge,
+    )
+    .sample(&[], usage.delete_markers_total_count as f64);
+    w.family("cluster_usage_total_bytes", "Total size of objects in the cluster", MetricKind::Gauge)
+        .sample(&[], usage.objects_total_size as f64);
+    w.family("cluster_bucket_total", "Total number of buckets in the cluster", MetricKind::Gauge)
+        .sample(&[], usage.buckets_count as f64);
+    if let Some(ts) = usage.last_update.and_then(|t| t.duration_since(UNIX_EPOCH).ok()) {
+        w.family(
+            "cluster_usage_last_update_timestamp_seconds",
+            "Time the usage information was last updated",
+            MetricKind::Gauge,
+        )
+        .sample(&[], ts.as_secs() as f64);
+    }
+}
+
+async fn write_node_metrics(w: &mut PrometheusWriter) {
+    let node = GLOBAL_Local_Node_Name.read().await.clone();
+
+    if let Some(store) = new_object_layer_fn() {
+        let info = store.local_storage_info().await;
+        write_drive_metrics(w, &node, &info.disks);
+
+        for (pool_idx, pool) in store.pools.iter().enumerate() {
+            for set in pool.disk_set.iter() {
+                let m = set.fast_lock_manager.get_metrics();
+                let pool = pool_idx.to_string();
+                let set_idx = set.set_index.to_string();
+                let labels = [("server", node.as_str()), ("pool", pool.as_str()), ("set", set_idx.as_str())];
+                w.family("node_lock_acquired_total", "Object locks granted by this node", MetricKind::Counter)
+                    .sample(&labels, m.shard_metrics.total_acquisitions() as f64);
+                w.family("node_lock_timeout_total", "Object lock requests that timed out", MetricKind::Counter)
+                    .sample(&labels, m.shard_metrics.timeouts as f64);
+                w.family("node_lock_contention_total", "Object lock requests that had to wait", MetricKind::Counter)
+                    .sample(&labels, m.shard_metrics.contention_events as f64);
+                w.family("node_lock_released_total", "Object locks released", MetricKind::Counter)
+                    .sample(&labels, m.shard_metrics.releases as f64);
+                w.family(
+                    "node_lock_wait_seconds_total",
+                    "Total time spent waiting for object locks",
+                    MetricKind::Counter,
+                )
+                .sample(&labels, m.shard_metrics.total_wait_time_ns as f64 / 1e9);
+                w.family("node_lock_wait_max_seconds", "Longest wait for an object lock", MetricKind::Gauge)
+                    .sample(&labels, m.shard_metrics.max_wait_time_ns as f64 / 1e9);
+            }
+        }
+    }
+
+    if let Some(scanner) = get_global_scanner() {
+        let m = scanner.get_metrics().await;
+        let labels = [("server", node.as_str())];
+        w.family(
+            "node_scanner_objects_scanned_total",
+            "Objects scanned since server start",
+            MetricKind::Counter,
+        )
+        .sample(&labels, m.objects_scanned as f64);
+        w.family(
+            "node_scanner_versions_scanned_total",
+            "Object versions scanned since server start",
+            MetricKind::Counter,
+        )
+        .sample(&labels, m.versions_scanned as f64);
+        w.family(
+            "node_scanner_directories_scanned_total",
+            "Directories scanned since server start",
+            MetricKind::Counter,
+        )
+        .sample(&labels, m.directories_scanned as f64);
+        w.family(
+            "node_scanner_bucket_scans_started_total",
+            "Bucket scans started since server start",
+            MetricKind::Counter,
+        )
+        .sample(&labels, m.bucket_scans_started as f64);
+        w.family(
+            "node_scanner_bucket_scans_finished_total",
+            "Bucket scans finished since server start",
+            MetricKind::Counter,
+        )
+        .sample(&labels, m.bucket_scans_finished as f64);
+        w.family(
+            "node_scanner_objects_with_issues_total",
+            "Objects found with health issues",
+            MetricKind::Counter,
+        )
+        .sample(&labels, m.objects_with_issues as f64);
+        w.family(
+            "node_scanner_heal_tasks_queued_total",
+            "Heal tasks queued by the scanner",
+            MetricKind::Counter,
+        )
+        .sample(&labels, m.heal_tasks_queued as f64);
+        w.family("node_scanner_heal_tasks_completed_total", "Heal tasks completed", MetricKind::Counter)
+            .sample(&labels, m.heal_tasks_completed as f64);
+        w.family("node_scanner_heal_tasks_failed_total", "Heal tasks failed", MetricKind::Counter)
+            .sample(&labels, m.heal_tasks_failed as f64);
+        w.family("node_scanner_cycles_total", "Scan cycles completed", MetricKind::Counter)
+            .sample(&labels, m.total_cycles as f64);
+        w.family("node_scanner_current_cycle", "Current scan cycle", MetricKind::Gauge)
+            .sample(&labels, m.current_cycle as f64);
+    }
+}
+
+fn write_drive_metrics(w: &mut PrometheusWriter, node: &str, disks: &[Disk]) {
+    for disk in disks {
+        let labels = [("server", node), ("drive", disk.drive_path.as_str())];
+        w.family("node_drive_online", "Whether the drive is online (1) or offline (0)", MetricKind::Gauge)
+            .sample(&labels, if is_drive_online(disk) { 1.0 } else { 0.0 });
+        w.family("node_drive_healing", "Whether the drive is being healed", MetricKind::Gauge)
+            .sample(&labels, if disk.healing { 1.0 } else { 0.0 });
+        w.family("node_drive_total_bytes", "Total capacity of the drive", MetricKind::Gauge)
+            .sample(&labels, disk.total_space as f64);
+        w.family("node_drive_used_bytes", "Used capacity of the drive", MetricKind::Gauge)
+            .sample(&labels, disk.used_space as f64);
+        w.family("node_drive_free_bytes", "Available capacity of the drive", MetricKind::Gauge)
+            .sample(&labels, disk.available_space as f64);
+        w.family("node_drive_used_inodes", "Used inodes on the drive", MetricKind::Gauge)
+            .sample(&labels, disk.used_inodes as f64);
+        w.family("node_drive_free_inodes", "Free inodes on the drive", MetricKind::Gauge)
+            .sample(&labels, disk.free_inodes as f64);
+    }
+}
+
+fn write_bucket_metrics(w: &mut PrometheusWriter, usage: Option<&DataUsageInfo>) {
+    let Some(usage) = usage else {
+        return;
+    };
+
+    // Sorted so that consecutive scrapes produce the same series order
+    let buckets: BTreeMap<_, _> = usage.buckets_usage.iter().collect();
+    for (bucket, u) in buckets.iter() {
+        let labels = [("bucket", bucket.as_str())];
+        w.family("bucket_usage_total_bytes", "Total size of objects in the bucket", MetricKind::Gauge)
+            .sample(&labels, u.size as f64);
+        w.family("bucket_usage_object_total", "Total number of objects in the bucket", MetricKind::Gauge)
+            .sample(&labels, u.objects_count as f64);
+        w.family(
+            "bucket_usage_version_total",
+            "Total number of object versions in the bucket",
+            MetricKind::Gauge,
+        )
+        .sample(&labels, u.versions_count as f64);
+        w.family(
+            "bucket_usage_deletemarker_total",
+            "Total number of delete markers in the bucket",
+            MetricKind::Gauge,
+        )
+        .sample(&labels, u.delete_markers_count as f64);
+    }
+
+    for (bucket, u) in buckets.iter() {
+        let histogram: BTreeMap<_, _> = u.object_size_histogram.iter().collect();
+        for (range, count) in histogram {
+            w.family(
+                "bucket_objects_size_distribution",
+                "Distribution of object sizes in the bucket",
+                MetricKind::Gauge,
+            )
+            .sample(&[("bucket", bucket.as_str()), ("range", range.as_str())], *count as f64);
+        }
+    }
+}
+
+async fn write_replication_metrics(w: &mut PrometheusWriter, usage: Option<&DataUsageInfo>) {
+    if let Some(usage) = usage {
+        let buckets: BTreeMap<_, _> = usage.buckets_usage.iter().collect();
+        for (bucket, u) in buckets {
+            let targets: BTreeMap<_, _> = u.replication_info.iter().collect();
+            for (arn, t) in targets {
+                let labels = [("bucket", bucket.as_str()), ("targetArn", arn.as_str())];
+                w.family(
+                    "bucket_replication_pending_bytes",
+                    "Size of objects pending replication",
+                    MetricKind::Gauge,
+                )
+                .sample(&labels, t.replication_pending_size as f64);
+                w.family(
+                    "bucket_replication_pending_count",
+                    "Number of objects pending replication",
+                    MetricKind::Gauge,
+                )
+                .sample(&labels, t.replication_pending_count as f64);
+                w.family(
+                    "bucket_replication_failed_bytes",
+                    "Size of objects that failed replication",
+                    MetricKind::Gauge,
+                )
+                .sample(&labels, t.replication_failed_size as f64);
+                w.family(
+                    "bucket_replication_failed_count",
+                    "Number of objects that failed replication",
+                    MetricKind::Gauge,
+                )
+                .sample(&labels, t.replication_failed_count as f64);
+                w.family(
+                    "bucket_replication_replicated_bytes",
+                    "Size of objects replicated to the target",
+                    MetricKind::Gauge,
+                )
+                .sample(&labels, t.replicated_size as f64);
+            }
+        }
+    }
+
+    let Some(stats) = GLOBAL_REPLICATION_STATS.get() else {
+        return;
+    };
+
+    let all = stats.get_all().await;
+    let buckets: BTreeMap<_, _> = all.iter().collect();
+    for (bucket, s) in buckets {
+        let labels = [("bucket", bucket.as_str())];
+        w.family(
+            "bucket_replication_received_bytes",
+            "Size of replicas received for the bucket",
+            MetricKind::Counter,
+        )
+        .sample(&labels, s.replica_size as f64);
+        w.family(
+            "bucket_replication_received_count",
+            "Number of replicas received for the bucket",
+            MetricKind::Counter,
+        )
+        .sample(&labels, s.replica_count as f64);
+        w.family(
+            "bucket_replication_queued_bytes",
+            "Size of objects queued for replication on this node",
+            MetricKind::Gauge,
+        )
+        .sample(&labels, s.q_stat.get_current_bytes() as f64);
+        w.family(
+            "bucket_replication_queued_count",
+            "Number of objects queued for replication on this node",
+            MetricKind::Gauge,
+        )
+        .sample(&labels, s.q_stat.get_current_count() as f64);
+
+        let targets: BTreeMap<_, _> = s.stats.iter().collect();
+        for (arn, t) in targets {
+            let labels = [("bucket", bucket.as_str()), ("targetArn", arn.as_str())];
+            w.family(
+                "bucket_replication_sent_bytes",
+                "Size of objects replicated to the target by this node",
+                MetricKind::Counter,
+            )
+            .sample(&labels, t.replicated_size as f64);
+            w.family(
+                "bucket_replication_sent_count",
+                "Number of objects replicated to the target by this node",
+                MetricKind::Counter,
+            )
+            .sample(&labels, t.replicated_count as f64);
+            w.family(
+                "bucket_replication_latency_ms",
+                "Average replication latency to the target",
+                MetricKind::Gauge,
+            )
+            .sample(&labels, t.latency.avg);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+impl MetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+        }
+    }
+}
+
+/// Minimal encoder for the Prometheus text exposition format.
+///
+/// Samples of a family are grouped under a single `# HELP`/`# TYPE` header
+/// regardless of the order they are added in.
+#[derive(Debug, Default)]
+struct PrometheusWriter {
+    families: Vec<MetricFamily>,
+    current: usize,
+}
+
+#[derive(Debug)]
+struct MetricFamily {
+    name: String,
+    help: &'static str,
+    kind: MetricKind,
+    samples: String,
+}
+
+impl PrometheusWriter {
+    /// Select (creating if needed) the family that following samples belong to
+    fn family(&mut self, name: &str, help: &'static str, kind: MetricKind) -> &mut Self {
+        let name = format!("{METRIC_NAMESPACE}_{name}");
+        self.current = match self.families.iter().position(|f| f.name == name) {
+            Some(idx) => idx,
+            None => {
+                self.families.push(MetricFamily {
+                    name,
+                    help,
+                    kind,
+                    samples: String::new(),
+                });
+                self.families.len() - 1
+            }
+        };
+        self
+    }
+
+    fn sample(&mut self, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        let family = &mut self.families[self.current];
+        family.samples.push_str(&family.name);
+        if !labels.is_empty() {
+            family.samples.push('{');
+            for (i, (k, v)) in labels.iter().enumerate() {
+                if i > 0 {
+                    family.samples.push(',');
+                }
+                let _ = write!(family.samples, "{}=\"{}\"", k, escape_label_value(v));
+            }
+            family.samples.push('}');
+        }
+        let _ = writeln!(family.samples, " {}", format_value(value));
+        self
+    }
+
+    fn finish(self) -> String {
+        let mut out = String::new();
+        for f in self.families.into_iter().filter(|f| !f.samples.is_empty()) {
+            let _ = writeln!(out, "# HELP {} {}", f.name, f.help);
+            let _ = writeln!(out, "# TYPE {} {}", f.name, f.kind.as_str());
+            out.push_str(&f.samples);
+        }
+        out
+    }
+}
+
+fn escape_label_value(v: &str) -> String {
+    let mut out = String::with_capacity(v.len());
+    for c in v.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn format_value(v: f64) -> String {
+    if v.is_nan() {
+        "NaN".to_string()
+    } else if v.is_infinite() {
+        if v > 0.0 { "+Inf".to_string() } else { "-Inf".to_string() }
+    } else {
+        v.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writer_groups_samples_by_family() {
+        let mut w = PrometheusWriter::default();
+        w.family("drive_free_bytes", "Free bytes", MetricKind::Gauge)
+            .sample(&[("drive", "/d1")], 10.0);
+        w.family("lock_timeout_total", "Timeouts", MetricKind::Counter)
+            .sample(&[], 3.0);
+        w.family("drive_free_bytes", "Free bytes", MetricKind::Gauge)
+            .sample(&[("drive", "/d2")], 20.5);
+
+        let out = w.finish();
+        assert_eq!(
+            out,
+            "# HELP nebulafx_drive_free_bytes Free bytes\n\
+             # TYPE nebulafx_drive_free_bytes gauge\n\
+             nebulafx_drive_free_bytes{drive=\"/d1\"} 10\n\
+             nebulafx_drive_free_bytes{drive=\"/d2\"} 20.5\n\
+             # HELP nebulafx_lock_timeout_total Timeouts\n\
+             # TYPE nebulafx_lock_timeout_total counter\n\
+             nebulafx_lock_timeout_total 3\n"
+        );
+    }
+
+    #[test]
+    fn test_writer_skips_empty_families_and_escapes_labels() {
+        let mut w = PrometheusWriter::default();
+        w.family("unused", "Never sampled", MetricKind::Gauge);
+        w.family("bucket_usage_total_bytes", "Size", MetricKind::Gauge)
+            .sample(&[("bucket", "a\"b\\c\nd")], f64::INFINITY);
+
+        let out = w.finish();
+        assert!(!out.contains("unused"));
+        assert!(out.contains("nebulafx_bucket_usage_total_bytes{bucket=\"a\\\"b\\\\c\\nd\"} +Inf\n"));
+    }
+
+    #[test]
+    fn test_bearer_token_and_subject() {
+        let mut headers = HeaderMap::new();
+        assert!(bearer_token(&headers).is_none());
+
+        headers.insert(AUTHORIZATION, "AWS4-HMAC-SHA256 Credential=x".parse().unwrap());
+        assert!(bearer_token(&headers).is_none());
+
+        let claims = PrometheusClaims {
+            sub: "scraper".to_string(),
+            iss: PROMETHEUS_ISSUER.to_string(),
+            exp: u64::MAX / 2,
+        };
+        let token = generate_jwt(&claims, "secret-key").unwrap();
+        headers.insert(AUTHORIZATION, format!("Bearer {token}").parse().unwrap());
+
+        let parsed = bearer_token(&headers).unwrap();
+        assert_eq!(parsed, token);
+        assert_eq!(token_subject(parsed).as_deref(), Some("scraper"));
+        assert!(extract_claims::<PrometheusClaims>(parsed, "secret-key").is_ok());
+        assert!(extract_claims::<PrometheusClaims>(parsed, "other-key").is_err());
+    }
+}
@@ -0,0 +1,288 @@
+
+
+use std::sync::Arc;
+
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use nebulafx_ecstore::bucket::bucket_target_sys::BucketTargetSys;
+use nebulafx_ecstore::bucket::metadata::BUCKET_TARGETS_FILE;
+use nebulafx_ecstore::bucket::metadata_sys;
+use nebulafx_ecstore::bucket::replication::{
+    DynReplicationPool, GLOBAL_REPLICATION_POOL, ReplicationConfigurationExt as _, ResyncOpts, ResyncStatusType,
+    TargetReplicationResyncStatus,
+};
+use nebulafx_ecstore::error::StorageError;
+use nebulafx_ecstore::notification_sys::get_global_notification_sys;
+use nebulafx_madmin::replication::{ResyncTarget, ResyncTargetsInfo};
+use nebulafx_madmin::utils::parse_duration;
+use nebulafx_policy::policy::action::{Action, S3Action};
+use s3s::{Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::Deserialize;
+use serde_urlencoded::from_bytes;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    admin::{auth::validate_admin_request, router::Operation},
+    auth::{check_key_valid, get_session_token},
+    error::ApiError,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ResyncQuery {
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default)]
+    pub arn: String,
+    /// RFC 3339 timestamp or a duration such as `72h`, only objects modified before it are resynced
+    #[serde(rename = "older-than")]
+    pub older_than: Option<String>,
+}
+
+fn parse_query(req: &S3Request<Body>, arn_required: bool) -> S3Result<ResyncQuery> {
+    let query: ResyncQuery = match req.uri.query() {
+        Some(query) => from_bytes(query.as_bytes()).map_err(|e| s3_error!(InvalidArgument, "invalid query: {e}"))?,
+        None => ResyncQuery::default(),
+    };
+    if query.bucket.is_empty() {
+        return Err(s3_error!(InvalidArgument, "bucket is required"));
+    }
+    if arn_required && query.arn.is_empty() {
+        return Err(s3_error!(InvalidArgument, "arn is required"));
+    }
+    Ok(query)
+}
+
+/// Objects modified before the returned time get resynced, all of them when `older_than` is missing
+fn resync_before(older_than: Option<&str>, now: OffsetDateTime) -> S3Result<OffsetDateTime> {
+    let Some(older_than) = older_than.filter(|v| !v.is_empty()) else {
+        return Ok(now);
+    };
+    if let Ok(before) = OffsetDateTime::parse(older_than, &Rfc3339) {
+        return Ok(before);
+    }
+    let age = parse_duration(older_than).map_err(|e| s3_error!(InvalidArgument, "invalid older-than {older_than}: {e}"))?;
+    Ok(now - age)
+}
+
+fn resync_target(arn: &str, status: &TargetReplicationResyncStatus) -> ResyncTarget {
+    let finished = matches!(
+        status.resync_status,
+        ResyncStatusType::ResyncCompleted | ResyncStatusType::ResyncFailed | ResyncStatusType::ResyncCanceled
+    );
+    ResyncTarget {
+        arn: arn.to_string(),
+        reset_id: status.resync_id.clone(),
+        start_time: status.start_time,
+        end_time: if finished { status.last_update } else { None },
+        resync_status: status.resync_status.to_string(),
+        replicated_size: status.replicated_size,
+        failed_size: status.failed_size,
+        failed_count: status.failed_count,
+        replicated_count: status.replicated_count,
+        bucket: status.bucket.clone(),
+        object: status.object.clone(),
+    }
+}
+
+async fn authorize(req: &S3Request<Body>, action: S3Action) -> S3Result<()> {
+    let Some(input_cred) = &req.credentials else {
+        return Err(s3_error!(InvalidRequest, "get cred failed"));
+    };
+
+    let (cred, owner) =
+        check_key_valid(get_session_token(&req.uri, &req.headers).unwrap_or_default(), &input_cred.access_key).await?;
+
+    validate_admin_request(&req.headers, &cred, owner, false, vec![Action::S3Action(action)]).await
+}
+
+fn replication_pool() -> S3Result<&'static Arc<DynReplicationPool>> {
+    GLOBAL_REPLICATION_POOL
+        .get()
+        .ok_or_else(|| s3_error!(InternalError, "replication is not initialized"))
+}
+
+async fn targets_info(bucket: &str, arn: Option<&str>) -> S3Result<ResyncTargetsInfo> {
+    let status = replication_pool()?.resync_status(bucket).await.map_err(ApiError::from)?;
+    let mut targets: Vec<ResyncTarget> = status
+        .targets_map
+        .iter()
+        .filter(|(target_arn, _)| arn.is_none_or(|arn| arn == target_arn.as_str()))
+        .map(|(target_arn, st)| resync_target(target_arn, st))
+        .collect();
+    targets.sort_by(|a, b| a.arn.cmp(&b.arn));
+    Ok(ResyncTargetsInfo { targets })
+}
+
+fn json_ok(info: &ResyncTargetsInfo) -> S3Result<S3Response<(StatusCode, Body)>> {
+    let data = serde_json::to_vec(info).map_err(|e| s3_error!(InternalError, "marshal response failed: {e}"))?;
+
+    let mut header = HeaderMap::new();
+    header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+}
+
+/// Replicate the existing objects of a bucket to one of its targets again, e.g. to seed a new DR site
+pub struct StartResync {}
+
+#[async_trait::async_trait]
+impl Operation for StartResync {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, S3Action::ResetBucketReplicationStateAction).await?;
+        let query = parse_query(&req, true)?;
+        let before = resync_before(query.older_than.as_deref(), OffsetDateTime::now_utc())?;
+
+        let config = match metadata_sys::get_replication_config(&query.bucket).await {
+            Ok((config, _)) => config,
+            Err(StorageError::ConfigNotFound) => {
+                return Err(S3Error::with_message(
+                    S3ErrorCode::ReplicationConfigurationNotFoundError,
+                    "replication not found".to_string(),
+                ));
+            }
+            Err(err) => return Err(ApiError::from(err).into()),
+        };
+        match config.has_existing_object_replication(&query.arn) {
+            (false, _) => return Err(s3_error!(InvalidRequest, "arn {} is not a target of the replication config", query.arn)),
+            (true, false) => {
+                return Err(s3_error!(InvalidRequest, "existing object replication is not enabled for {}", query.arn));
+            }
+            (true, true) => {}
+        }
+
+        let pool = replication_pool()?;
+        if pool.resync_running(&query.bucket, &query.arn).await {
+            return Err(s3_error!(InvalidRequest, "a resync to {} is already in progress", query.arn));
+        }
+
+        // Objects carry the reset id they were resynced with, a new id makes every older object eligible again
+        let bucket_target_sys = BucketTargetSys::get();
+        let Some(mut target) = bucket_target_sys
+            .get_remote_bucket_target_by_arn(&query.bucket, &query.arn)
+            .await
+        else {
+            return Err(s3_error!(InvalidRequest, "remote target {} not found", query.arn));
+        };
+        let reset_id = Uuid::new_v4().to_string();
+        target.reset_id = reset_id.clone();
+        target.reset_before_date = Some(before);
+
+        bucket_target_sys
+            .set_target(&query.bucket, &target, true)
+            .await
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, e.to_string()))?;
+        let targets = bucket_target_sys
+            .list_bucket_targets(&query.bucket)
+            .await
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, e.to_string()))?;
+        let json_targets = serde_json::to_vec(&targets).map_err(|e| s3_error!(InternalError, "marshal targets failed: {e}"))?;
+        metadata_sys::update(&query.bucket, BUCKET_TARGETS_FILE, json_targets)
+            .await
+            .map_err(ApiError::from)?;
+
+        pool.start_resync(ResyncOpts {
+            bucket: query.bucket.clone(),
+            arn: query.arn.clone(),
+            resync_id: reset_id.clone(),
+            resync_before: Some(before),
+        })
+        .await
+        .map_err(ApiError::from)?;
+
+        info!("replication resync {} of bucket {} to {} started", reset_id, query.bucket, query.arn);
+
+        json_ok(&targets_info(&query.bucket, Some(&query.arn)).await?)
+    }
+}
+
+pub struct CancelResync {}
+
+#[async_trait::async_trait]
+impl Operation for CancelResync {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, S3Action::ResetBucketReplicationStateAction).await?;
+        let query = parse_query(&req, true)?;
+
+        // The resync runs on the node that started it, which need not be this one
+        let mut canceled = replication_pool()?
+            .cancel_resync(&query.bucket, &query.arn)
+            .await
+            .map_err(ApiError::from)?;
+        if let Some(notification_sys) = get_global_notification_sys() {
+            let (peer_canceled, errs) = notification_sys.cancel_replication_resync(&query.bucket, &query.arn).await;
+            for err in errs {
+                if let Some(e) = err.err {
+                    warn!("cancel replication resync on peer {} failed: {}", err.host, e);
+                }
+            }
+            canceled |= peer_canceled;
+        }
+        if !canceled {
+            return Err(s3_error!(InvalidRequest, "no resync to {} is in progress", query.arn));
+        }
+
+        info!("replication resync of bucket {} to {} canceled", query.bucket, query.arn);
+
+        json_ok(&targets_info(&query.bucket, Some(&query.arn)).await?)
+    }
+}
+
+/// Resync progress of every target of a bucket, or of the target named by `arn`
+pub struct ResyncStatus {}
+
+#[async_trait::async_trait]
+impl Operation for ResyncStatus {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, S3Action::GetReplicationConfigurationAction).await?;
+        let query = parse_query(&req, false)?;
+
+        let arn = (!query.arn.is_empty()).then_some(query.arn.as_str());
+        json_ok(&targets_info(&query.bucket, arn).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_resync_before() {
+        let now = datetime!(2025-06-10 12:00 UTC);
+
+        assert_eq!(resync_before(None, now).unwrap(), now);
+        assert_eq!(resync_before(Some(""), now).unwrap(), now);
+        assert_eq!(resync_before(Some("2025-01-01T00:00:00Z"), now).unwrap(), datetime!(2025-01-01 00:00 UTC));
+        assert_eq!(resync_before(Some("72h"), now).unwrap(), datetime!(2025-06-07 12:00 UTC));
+        assert!(resync_before(Some("yesterday"), now).is_err());
+    }
+
+    #[test]
+    fn test_resync_target() {
+        let mut status = TargetReplicationResyncStatus {
+            resync_id: "reset-1".to_string(),
+            start_time: Some(datetime!(2025-06-10 12:00 UTC)),
+            last_update: Some(datetime!(2025-06-10 12:30 UTC)),
+            resync_status: ResyncStatusType::ResyncStarted,
+            replicated_count: 3,
+            replicated_size: 300,
+            object: "photos/c.jpg".to_string(),
+            ..Default::default()
+        };
+
+        let target = resync_target("arn:nebulafx:replication::1:dr", &status);
+        assert_eq!(target.resync_status, "Ongoing");
+        assert_eq!(target.end_time, None);
+        assert_eq!(target.object, "photos/c.jpg");
+
+        status.resync_status = ResyncStatusType::ResyncCompleted;
+        let target = resync_target("arn:nebulafx:replication::1:dr", &status);
+        assert_eq!(target.end_time, status.last_update);
+
+        let json = serde_json::to_value(ResyncTargetsInfo { targets: vec![target] }).unwrap();
+        assert_eq!(json["target"][0]["resetid"], "reset-1");
+        assert_eq!(json["target"][0]["replicationCount"], 3);
+    }
+}
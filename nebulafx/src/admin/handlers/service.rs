@@ -0,0 +1,128 @@
+
+
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use nebulafx_common::globals::GLOBAL_Local_Node_Name;
+use nebulafx_ecstore::notification_sys::get_global_notification_sys;
+use nebulafx_madmin::service_commands::{ServiceAction, ServiceActionPeerResult, ServiceActionResult};
+use nebulafx_policy::policy::action::{Action, AdminAction};
+use s3s::{Body, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::Deserialize;
+use serde_urlencoded::from_bytes;
+use tracing::{info, warn};
+
+use crate::{
+    admin::{auth::validate_admin_request, router::Operation},
+    auth::{check_key_valid, get_session_token},
+    server::{SHUTDOWN_TIMEOUT, signal_service},
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ServiceQuery {
+    #[serde(default)]
+    pub action: String,
+    #[serde(rename = "dry-run", default)]
+    pub dry_run: bool,
+}
+
+fn admin_action(action: ServiceAction) -> AdminAction {
+    match action {
+        ServiceAction::Restart => AdminAction::ServiceRestartAdminAction,
+        ServiceAction::Stop => AdminAction::ServiceStopAdminAction,
+        ServiceAction::Freeze | ServiceAction::Unfreeze => AdminAction::ServiceFreezeAdminAction,
+    }
+}
+
+/// Apply a service action on this node.
+///
+/// Stop and restart are delayed a little so the admin response still reaches the client.
+pub(crate) fn apply_service_action(action: ServiceAction) {
+    match action {
+        ServiceAction::Freeze | ServiceAction::Unfreeze => signal_service(action),
+        ServiceAction::Stop | ServiceAction::Restart => {
+            tokio::spawn(async move {
+                tokio::time::sleep(SHUTDOWN_TIMEOUT).await;
+                signal_service(action);
+            });
+        }
+    }
+}
+
+/// Restart, stop, freeze or unfreeze every node of the cluster
+pub struct ServiceHandle {}
+
+#[async_trait::async_trait]
+impl Operation for ServiceHandle {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        let query: ServiceQuery = match req.uri.query() {
+            Some(query) => from_bytes(query.as_bytes()).map_err(|e| s3_error!(InvalidArgument, "invalid query: {e}"))?,
+            None => ServiceQuery::default(),
+        };
+        let Some(action) = ServiceAction::parse(&query.action) else {
+            return Err(s3_error!(InvalidArgument, "unsupported service action: {}", query.action));
+        };
+
+        let Some(input_cred) = &req.credentials else {
+            return Err(s3_error!(InvalidRequest, "get cred failed"));
+        };
+
+        let (cred, owner) =
+            check_key_valid(get_session_token(&req.uri, &req.headers).unwrap_or_default(), &input_cred.access_key).await?;
+
+        validate_admin_request(&req.headers, &cred, owner, false, vec![Action::AdminAction(admin_action(action))]).await?;
+
+        info!("service {} requested by {}, dry-run: {}", action.as_str(), cred.access_key, query.dry_run);
+
+        // Peers first, this node may be going away
+        let mut results = Vec::new();
+        if let Some(notification_sys) = get_global_notification_sys() {
+            for peer in notification_sys.signal_service(action.signal(), "", query.dry_run).await {
+                if let Some(err) = &peer.err {
+                    warn!("service {} failed on peer {}: {}", action.as_str(), peer.host, err);
+                }
+                results.push(ServiceActionPeerResult {
+                    host: peer.host,
+                    err: peer.err.map(|e| e.to_string()),
+                });
+            }
+        }
+
+        if !query.dry_run {
+            apply_service_action(action);
+        }
+        results.push(ServiceActionPeerResult {
+            host: GLOBAL_Local_Node_Name.read().await.clone(),
+            err: None,
+        });
+
+        let data = serde_json::to_vec(&ServiceActionResult {
+            action: action.as_str().to_string(),
+            dry_run: query.dry_run,
+            results,
+        })
+        .map_err(|e| s3_error!(InternalError, "marshal response failed: {e}"))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_service_query() {
+        let query: ServiceQuery = from_bytes(b"action=freeze&dry-run=true").unwrap();
+        assert_eq!(ServiceAction::parse(&query.action), Some(ServiceAction::Freeze));
+        assert!(query.dry_run);
+
+        let query: ServiceQuery = from_bytes(b"action=restart").unwrap();
+        assert!(!query.dry_run);
+        assert_eq!(
+            admin_action(ServiceAction::parse(&query.action).unwrap()),
+            AdminAction::ServiceRestartAdminAction
+        );
+    }
+}
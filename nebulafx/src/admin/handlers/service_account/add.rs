@@ -1,6 +1,6 @@
 use crate::admin::utils::has_space_be;
 use crate::auth::{get_condition_values, get_session_token};
-use crate::{admin::router::Operation, auth::check_key_valid};
+use crate::{admin::router::Operation, auth::check_key_valid, site_replication};
 use http::HeaderMap;
 use hyper::StatusCode;
 use matchit::Params;
@@ -157,6 +157,8 @@ impl Operation for AddServiceAccount {
                 s3_error!(InternalError, "create service account failed, e: {:?}", e)
             })?;
 
+        site_replication::get().service_account_hook(&new_cred.access_key).await;
+
         let resp = AddServiceAccountResp {
             credentials: Credentials {
                 access_key: &new_cred.access_key,
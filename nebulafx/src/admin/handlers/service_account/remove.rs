@@ -1,5 +1,5 @@
 use crate::auth::{get_condition_values, get_session_token};
-use crate::{admin::router::Operation, auth::check_key_valid};
+use crate::{admin::router::Operation, auth::check_key_valid, site_replication};
 use http::HeaderMap;
 use hyper::StatusCode;
 use matchit::Params;
@@ -90,6 +90,8 @@ impl Operation for DeleteServiceAccount {
             s3_error!(InternalError, "delete service account failed")
         })?;
 
+        site_replication::get().service_account_hook(&query.access_key).await;
+
         let mut header = HeaderMap::new();
         header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
         header.insert(CONTENT_LENGTH, "0".parse().unwrap());
@@ -1,5 +1,5 @@
 use crate::auth::{get_condition_values, get_session_token};
-use crate::{admin::router::Operation, auth::check_key_valid};
+use crate::{admin::router::Operation, auth::check_key_valid, site_replication};
 use http::HeaderMap;
 use hyper::StatusCode;
 use matchit::Params;
@@ -114,6 +114,8 @@ impl Operation for UpdateServiceAccount {
             s3_error!(InternalError, "update service account failed")
         })?;
 
+        site_replication::get().service_account_hook(&access_key).await;
+
         let mut header = HeaderMap::new();
         header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
         header.insert(CONTENT_LENGTH, "0".parse().unwrap());
@@ -0,0 +1,332 @@
+
+
+use std::collections::BTreeMap;
+
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use nebulafx_crypto::decrypt_data;
+use nebulafx_ecstore::bucket::replication::{GLOBAL_REPLICATION_STATS, SRMetricsSummary};
+use nebulafx_ecstore::error::Result as StorageResult;
+use nebulafx_ecstore::global::get_global_deployment_id;
+use nebulafx_ecstore::notification_sys::get_global_notification_sys;
+use nebulafx_madmin::site_replication::{
+    PeerInfo, PeerSite, SRBucketMeta, SRIAMItem, SRInfo, SRPeerJoinReq, SRRemoveReq, SRSiteStatus, SRStatusInfo,
+};
+use nebulafx_policy::auth::Credentials;
+use nebulafx_policy::policy::action::{Action, AdminAction};
+use s3s::{Body, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_urlencoded::from_bytes;
+use tracing::warn;
+
+use crate::{
+    admin::{auth::validate_admin_request, router::Operation},
+    auth::{check_key_valid, get_session_token},
+    error::ApiError,
+    site_replication::{self, find_mismatches},
+};
+
+/// Credentials of the request, once allowed to run `action`
+async fn authorize(req: &S3Request<Body>, action: AdminAction) -> S3Result<Credentials> {
+    let Some(input_cred) = &req.credentials else {
+        return Err(s3_error!(InvalidRequest, "get cred failed"));
+    };
+
+    let (cred, owner) =
+        check_key_valid(get_session_token(&req.uri, &req.headers).unwrap_or_default(), &input_cred.access_key).await?;
+
+    validate_admin_request(&req.headers, &cred, owner, false, vec![Action::AdminAction(action)]).await?;
+    Ok(cred)
+}
+
+async fn read_json<T: DeserializeOwned>(req: S3Request<Body>) -> S3Result<T> {
+    let mut input = req.input;
+    let body = input
+        .store_all_unlimited()
+        .await
+        .map_err(|e| s3_error!(InvalidRequest, "get body failed: {e}"))?;
+    serde_json::from_slice(&body).map_err(|e| s3_error!(InvalidRequest, "unmarshal body failed: {e}"))
+}
+
+/// Body a site encrypted with the secret key of the credentials it signed the request with
+async fn read_encrypted_json<T: DeserializeOwned>(req: S3Request<Body>, cred: &Credentials) -> S3Result<T> {
+    let mut input = req.input;
+    let body = input
+        .store_all_unlimited()
+        .await
+        .map_err(|e| s3_error!(InvalidRequest, "get body failed: {e}"))?;
+    let body =
+        decrypt_data(cred.secret_key.as_bytes(), &body).map_err(|e| s3_error!(InvalidArgument, "decrypt body failed: {e}"))?;
+    serde_json::from_slice(&body).map_err(|e| s3_error!(InvalidRequest, "unmarshal body failed: {e}"))
+}
+
+fn json_ok<T: Serialize>(value: &T) -> S3Result<S3Response<(StatusCode, Body)>> {
+    let data = serde_json::to_vec(value).map_err(|e| s3_error!(InternalError, "marshal response failed: {e}"))?;
+
+    let mut header = HeaderMap::new();
+    header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+}
+
+fn empty_ok() -> S3Result<S3Response<(StatusCode, Body)>> {
+    Ok(S3Response::new((StatusCode::OK, Body::empty())))
+}
+
+fn site_status(peer: &PeerInfo, info: &StorageResult<SRInfo>) -> SRSiteStatus {
+    let mut status = SRSiteStatus {
+        name: peer.name.clone(),
+        deployment_id: peer.deployment_id.clone(),
+        endpoint: peer.endpoint.clone(),
+        ..Default::default()
+    };
+    match info {
+        Ok(info) => {
+            status.online = true;
+            status.bucket_count = info.buckets.len();
+            status.policy_count = info.policies.len();
+            status.user_count = info.users.len();
+            status.group_count = info.groups.len();
+        }
+        Err(err) => status.err_detail = err.to_string(),
+    }
+    status
+}
+
+/// Replication metrics of every node of this deployment
+async fn local_metrics() -> SRMetricsSummary {
+    let mut summary = match GLOBAL_REPLICATION_STATS.get() {
+        Some(stats) => stats.get_sr_metrics_for_node().await,
+        None => SRMetricsSummary::default(),
+    };
+    if let Some(notification_sys) = get_global_notification_sys() {
+        for node in notification_sys.get_sr_metrics().await {
+            summary.merge(&node);
+        }
+    }
+    summary
+}
+
+/// Link deployments, the body lists every site including this one
+pub struct SiteReplicationAdd {}
+
+#[async_trait::async_trait]
+impl Operation for SiteReplicationAdd {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::SiteReplicationAddAction).await?;
+        let sites: Vec<PeerSite> = read_json(req).await?;
+
+        let status = site_replication::get().add(sites).await.map_err(ApiError::from)?;
+        json_ok(&status)
+    }
+}
+
+pub struct SiteReplicationRemove {}
+
+#[async_trait::async_trait]
+impl Operation for SiteReplicationRemove {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::SiteReplicationRemoveAction).await?;
+        let remove_req: SRRemoveReq = read_json(req).await?;
+        if !remove_req.remove_all && remove_req.site_names.is_empty() {
+            return Err(s3_error!(InvalidArgument, "sites to remove are required"));
+        }
+
+        let status = site_replication::get().remove(remove_req).await.map_err(ApiError::from)?;
+        json_ok(&status)
+    }
+}
+
+pub struct SiteReplicationInfo {}
+
+#[async_trait::async_trait]
+impl Operation for SiteReplicationInfo {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::SiteReplicationInfoAction).await?;
+
+        json_ok(&site_replication::get().info().await)
+    }
+}
+
+/// Reachability of every site and the buckets and IAM entities that are not on all of them
+pub struct SiteReplicationStatus {}
+
+#[async_trait::async_trait]
+impl Operation for SiteReplicationStatus {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::SiteReplicationInfoAction).await?;
+
+        let sys = site_replication::get();
+        let info = sys.info().await;
+        if !info.enabled {
+            return json_ok(&SRStatusInfo::default());
+        }
+
+        let local_id = get_global_deployment_id().unwrap_or_default();
+        let mut sites = Vec::with_capacity(info.sites.len());
+        let mut infos = Vec::with_capacity(info.sites.len());
+        if let Some(local) = info.sites.iter().find(|peer| peer.deployment_id == local_id) {
+            let local_info = sys.local_info().await;
+            sites.push(site_status(local, &local_info));
+            infos.extend(local_info);
+        }
+        for (peer, client) in sys.peer_clients().await.map_err(ApiError::from)? {
+            let peer_info = client.info().await;
+            sites.push(site_status(&peer, &peer_info));
+            infos.extend(peer_info);
+        }
+
+        json_ok(&SRStatusInfo {
+            enabled: true,
+            sites,
+            mismatches: find_mismatches(&infos),
+        })
+    }
+}
+
+/// Replication metrics of every linked site, by site name
+pub struct SiteReplicationMetrics {}
+
+#[async_trait::async_trait]
+impl Operation for SiteReplicationMetrics {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::SiteReplicationInfoAction).await?;
+
+        let sys = site_replication::get();
+        let clients = sys.peer_clients().await.map_err(ApiError::from)?;
+
+        let mut metrics = BTreeMap::new();
+        metrics.insert(sys.info().await.name, local_metrics().await);
+        for (peer, client) in clients {
+            match client.metrics().await {
+                Ok(summary) => {
+                    metrics.insert(peer.name, summary);
+                }
+                Err(err) => warn!("get replication metrics of site {} failed: {}", peer.name, err),
+            }
+        }
+        json_ok(&metrics)
+    }
+}
+
+pub struct SRPeerJoin {}
+
+#[async_trait::async_trait]
+impl Operation for SRPeerJoin {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        let cred = authorize(&req, AdminAction::SiteReplicationAddAction).await?;
+        let join_req: SRPeerJoinReq = read_encrypted_json(req, &cred).await?;
+
+        site_replication::get().join(join_req).await.map_err(ApiError::from)?;
+        empty_ok()
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BucketOpsQuery {
+    pub bucket: String,
+    pub operation: String,
+    #[serde(rename = "lockEnabled", default)]
+    pub lock_enabled: bool,
+}
+
+pub struct SRPeerBucketOps {}
+
+#[async_trait::async_trait]
+impl Operation for SRPeerBucketOps {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::SiteReplicationOperationAction).await?;
+        let query: BucketOpsQuery = from_bytes(req.uri.query().unwrap_or_default().as_bytes())
+            .map_err(|e| s3_error!(InvalidArgument, "invalid query: {e}"))?;
+        if query.bucket.is_empty() {
+            return Err(s3_error!(InvalidArgument, "bucket is required"));
+        }
+
+        site_replication::get()
+            .peer_bucket_op(&query.bucket, &query.operation, query.lock_enabled)
+            .await
+            .map_err(ApiError::from)?;
+        empty_ok()
+    }
+}
+
+pub struct SRPeerBucketMeta {}
+
+#[async_trait::async_trait]
+impl Operation for SRPeerBucketMeta {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::SiteReplicationOperationAction).await?;
+        let meta: SRBucketMeta = read_json(req).await?;
+
+        site_replication::get().peer_bucket_meta(meta).await.map_err(ApiError::from)?;
+        empty_ok()
+    }
+}
+
+pub struct SRPeerIAMItem {}
+
+#[async_trait::async_trait]
+impl Operation for SRPeerIAMItem {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        let cred = authorize(&req, AdminAction::SiteReplicationOperationAction).await?;
+        let item: SRIAMItem = read_encrypted_json(req, &cred).await?;
+
+        site_replication::get().peer_iam_item(item).await.map_err(ApiError::from)?;
+        empty_ok()
+    }
+}
+
+pub struct SRPeerInfo {}
+
+#[async_trait::async_trait]
+impl Operation for SRPeerInfo {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::SiteReplicationInfoAction).await?;
+
+        let info = site_replication::get().local_info().await.map_err(ApiError::from)?;
+        json_ok(&info)
+    }
+}
+
+pub struct SRPeerMetrics {}
+
+#[async_trait::async_trait]
+impl Operation for SRPeerMetrics {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::SiteReplicationInfoAction).await?;
+
+        json_ok(&local_metrics().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nebulafx_ecstore::error::Error;
+
+    #[test]
+    fn test_site_status() {
+        let peer = PeerInfo {
+            name: "site-b".to_string(),
+            endpoint: "http://127.0.0.1:9001".to_string(),
+            deployment_id: "b".to_string(),
+        };
+
+        let info = SRInfo {
+            deployment_id: "b".to_string(),
+            buckets: vec!["photos".to_string(), "logs".to_string()],
+            users: vec!["alice".to_string()],
+            ..Default::default()
+        };
+        let status = site_status(&peer, &Ok(info));
+        assert!(status.online);
+        assert_eq!(status.bucket_count, 2);
+        assert_eq!(status.user_count, 1);
+        assert!(status.err_detail.is_empty());
+
+        let status = site_status(&peer, &Err(Error::other("site http://127.0.0.1:9001/ is unreachable")));
+        assert!(!status.online);
+        assert_eq!(status.name, "site-b");
+        assert!(status.err_detail.contains("unreachable"));
+    }
+}
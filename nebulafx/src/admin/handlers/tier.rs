@@ -134,6 +134,9 @@ impl Operation for AddTier {
             TierType::R2 => {
                 args.name = args.r2.clone().unwrap().name;
             }
+            TierType::FileSystem => {
+                args.name = args.filesystem.clone().unwrap().name;
+            }
             _ => (),
         }
         debug!("add tier args {:?}", args);
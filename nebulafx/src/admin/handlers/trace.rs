@@ -1,14 +1,22 @@
-use http::StatusCode;
+use bytes::Bytes;
+use futures::StreamExt;
+use http::{HeaderMap, StatusCode};
 use hyper::Uri;
 use matchit::Params;
+use nebulafx_common::{globals::GLOBAL_Local_Node_Name, trace::subscribe_trace};
 use nebulafx_ecstore::{GLOBAL_Endpoints, rpc::PeerRestClient};
-use nebulafx_madmin::service_commands::ServiceTraceOpts;
-use s3s::{Body, S3Request, S3Response, S3Result, s3_error};
+use nebulafx_madmin::{service_commands::ServiceTraceOpts, trace::TraceInfo};
+use nebulafx_policy::policy::action::{Action, AdminAction};
+use s3s::{Body, S3Request, S3Response, S3Result, StdError, dto::StreamingBlob, header::CONTENT_TYPE, s3_error};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::warn;
 
-use crate::admin::router::Operation;
+use crate::{
+    admin::{auth::validate_admin_request, router::Operation},
+    auth::{check_key_valid, get_session_token},
+};
 
-#[allow(dead_code)]
 fn extract_trace_options(uri: &Uri) -> S3Result<ServiceTraceOpts> {
     let mut st_opts = ServiceTraceOpts::default();
     st_opts
@@ -18,21 +26,117 @@ fn extract_trace_options(uri: &Uri) -> S3Result<ServiceTraceOpts> {
     Ok(st_opts)
 }
 
-#[allow(dead_code)]
+/// One JSON document per line, so clients can decode records as they arrive
+fn encode_trace(info: &TraceInfo) -> Option<Bytes> {
+    match serde_json::to_vec(info) {
+        Ok(mut data) => {
+            data.push(b'\n');
+            Some(Bytes::from(data))
+        }
+        Err(err) => {
+            warn!("encode trace info failed: {:?}", err);
+            None
+        }
+    }
+}
+
+/// Streams trace records from this node and every peer until the client disconnects
 pub struct Trace {}
 
 #[async_trait::async_trait]
 impl Operation for Trace {
     async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
-        warn!("handle Trace");
+        let Some(input_cred) = &req.credentials else {
+            return Err(s3_error!(InvalidRequest, "get cred failed"));
+        };
+
+        let (cred, owner) =
+            check_key_valid(get_session_token(&req.uri, &req.headers).unwrap_or_default(), &input_cred.access_key).await?;
+
+        validate_admin_request(
+            &req.headers,
+            &cred,
+            owner,
+            false,
+            vec![Action::AdminAction(AdminAction::TraceAdminAction)],
+        )
+        .await?;
+
+        let trace_opts = extract_trace_options(&req.uri)?;
 
-        let _trace_opts = extract_trace_options(&req.uri)?;
+        let (tx, rx) = mpsc::channel::<Result<Bytes, StdError>>(1000);
 
-        // let (tx, rx) = mpsc::channel(10000);
-        let _peers = match GLOBAL_Endpoints.get() {
+        let mut subscription = subscribe_trace(trace_opts.trace_types());
+        let local_tx = tx.clone();
+        tokio::spawn(async move {
+            loop {
+                let mut info = tokio::select! {
+                    _ = local_tx.closed() => return,
+                    info = subscription.recv() => match info {
+                        Some(info) => info,
+                        None => return,
+                    },
+                };
+                if !trace_opts.matches(&info) {
+                    continue;
+                }
+                trace_opts.redact(&mut info);
+                if info.node_name.is_empty() {
+                    info.node_name = GLOBAL_Local_Node_Name.read().await.clone();
+                }
+
+                let Some(data) = encode_trace(&info) else { continue };
+                if local_tx.send(Ok(data)).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        // Peers apply the same filters, so their records are forwarded as they are
+        let (peers, _) = match GLOBAL_Endpoints.get() {
             Some(ep) => PeerRestClient::new_clients(ep.clone()).await,
             None => (Vec::new(), Vec::new()),
         };
-        Err(s3_error!(NotImplemented))
+        let query = req.uri.query().unwrap_or_default().to_string();
+        for peer in peers.into_iter().flatten() {
+            let peer_tx = tx.clone();
+            let query = query.clone();
+            tokio::spawn(async move {
+                let mut stream = match peer.trace(&query).await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!("start trace on peer {} failed: {:?}", peer.host, err);
+                        return;
+                    }
+                };
+
+                loop {
+                    let item = tokio::select! {
+                        _ = peer_tx.closed() => return,
+                        item = stream.next() => item,
+                    };
+                    let info = match item {
+                        Some(Ok(info)) => info,
+                        Some(Err(err)) => {
+                            warn!("trace stream from peer {} failed: {:?}", peer.host, err);
+                            return;
+                        }
+                        None => return,
+                    };
+
+                    let Some(data) = encode_trace(&info) else { continue };
+                    if peer_tx.send(Ok(data)).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        let body = Body::from(StreamingBlob::wrap(ReceiverStream::new(rx)));
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, body), header))
     }
 }
@@ -11,6 +11,7 @@ use handlers::{
     event::{ListNotificationTargets, ListTargetsArns, NotificationTarget, RemoveNotificationTarget},
     group, policy, pools,
     profile::{TriggerProfileCPU, TriggerProfileMemory},
+    prometheus::{self, PROMETHEUS_V2_PREFIX, PROMETHEUS_V3_PREFIX, PrometheusMetricsHandler},
     rebalance,
     service_account::{AddServiceAccount, DeleteServiceAccount, InfoServiceAccount, ListServiceAccount, UpdateServiceAccount},
     login, tier, user,
@@ -38,11 +39,12 @@ pub fn make_admin_route(_console_enabled: bool) -> std::io::Result<impl S3Route>
 
     register_rpc_route(&mut r)?;
     register_user_route(&mut r)?;
+    register_prometheus_route(&mut r)?;
 
     r.insert(
         Method::POST,
         format!("{}{}", ADMIN_PREFIX, "/v3/service").as_str(),
-        AdminOperation(&handlers::ServiceHandle {}),
+        AdminOperation(&handlers::service::ServiceHandle {}),
     )?;
     // 1
     r.insert(
@@ -77,6 +79,11 @@ pub fn make_admin_route(_console_enabled: bool) -> std::io::Result<impl S3Route>
         format!("{}{}", ADMIN_PREFIX, "/v3/metrics").as_str(),
         AdminOperation(&handlers::MetricsHandler {}),
     )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/trace").as_str(),
+        AdminOperation(&handlers::trace::Trace {}),
+    )?;
 
     // 1
     r.insert(
@@ -182,6 +189,78 @@ pub fn make_admin_route(_console_enabled: bool) -> std::io::Result<impl S3Route>
         AdminOperation(&bucket::ImportBucketMetadata {}),
     )?;
 
+    r.insert(
+        Method::PUT,
+        format!("{}{}", ADMIN_PREFIX, "/v3/set-bucket-quota").as_str(),
+        AdminOperation(&bucket::SetBucketQuota {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/get-bucket-quota").as_str(),
+        AdminOperation(&bucket::GetBucketQuota {}),
+    )?;
+
+    r.insert(
+        Method::DELETE,
+        format!("{}{}", ADMIN_PREFIX, "/v3/remove-bucket-quota").as_str(),
+        AdminOperation(&bucket::RemoveBucketQuota {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/kms/status").as_str(),
+        AdminOperation(&handlers::kms::KmsStatus {}),
+    )?;
+
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/kms/key/create").as_str(),
+        AdminOperation(&handlers::kms::CreateKmsKey {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/kms/key/list").as_str(),
+        AdminOperation(&handlers::kms::ListKmsKeys {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/kms/key/status").as_str(),
+        AdminOperation(&handlers::kms::DescribeKmsKey {}),
+    )?;
+
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/kms/key/rotate").as_str(),
+        AdminOperation(&handlers::kms::RotateKmsKey {}),
+    )?;
+
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/kms/key/enable").as_str(),
+        AdminOperation(&handlers::kms::EnableKmsKey {}),
+    )?;
+
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/kms/key/disable").as_str(),
+        AdminOperation(&handlers::kms::DisableKmsKey {}),
+    )?;
+
+    r.insert(
+        Method::DELETE,
+        format!("{}{}", ADMIN_PREFIX, "/v3/kms/key/delete").as_str(),
+        AdminOperation(&handlers::kms::DeleteKmsKey {}),
+    )?;
+
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/kms/key/cancel-deletion").as_str(),
+        AdminOperation(&handlers::kms::CancelKmsKeyDeletion {}),
+    )?;
+
     r.insert(
         Method::GET,
         format!("{}{}", ADMIN_PREFIX, "/v3/list-remote-targets").as_str(),
@@ -194,6 +273,90 @@ pub fn make_admin_route(_console_enabled: bool) -> std::io::Result<impl S3Route>
         AdminOperation(&GetReplicationMetricsHandler {}),
     )?;
 
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/replication/resync/start").as_str(),
+        AdminOperation(&handlers::replication::StartResync {}),
+    )?;
+
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/replication/resync/cancel").as_str(),
+        AdminOperation(&handlers::replication::CancelResync {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/replication/resync/status").as_str(),
+        AdminOperation(&handlers::replication::ResyncStatus {}),
+    )?;
+
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/site-replication/add").as_str(),
+        AdminOperation(&handlers::site_replication::SiteReplicationAdd {}),
+    )?;
+
+    r.insert(
+        Method::PUT,
+        format!("{}{}", ADMIN_PREFIX, "/v3/site-replication/remove").as_str(),
+        AdminOperation(&handlers::site_replication::SiteReplicationRemove {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/site-replication/info").as_str(),
+        AdminOperation(&handlers::site_replication::SiteReplicationInfo {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/site-replication/status").as_str(),
+        AdminOperation(&handlers::site_replication::SiteReplicationStatus {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/site-replication/metrics").as_str(),
+        AdminOperation(&handlers::site_replication::SiteReplicationMetrics {}),
+    )?;
+
+    r.insert(
+        Method::PUT,
+        format!("{}{}", ADMIN_PREFIX, "/v3/site-replication/peer/join").as_str(),
+        AdminOperation(&handlers::site_replication::SRPeerJoin {}),
+    )?;
+
+    r.insert(
+        Method::PUT,
+        format!("{}{}", ADMIN_PREFIX, "/v3/site-replication/peer/bucket-ops").as_str(),
+        AdminOperation(&handlers::site_replication::SRPeerBucketOps {}),
+    )?;
+
+    r.insert(
+        Method::PUT,
+        format!("{}{}", ADMIN_PREFIX, "/v3/site-replication/peer/bucket-meta").as_str(),
+        AdminOperation(&handlers::site_replication::SRPeerBucketMeta {}),
+    )?;
+
+    r.insert(
+        Method::PUT,
+        format!("{}{}", ADMIN_PREFIX, "/v3/site-replication/peer/iam-item").as_str(),
+        AdminOperation(&handlers::site_replication::SRPeerIAMItem {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/site-replication/peer/info").as_str(),
+        AdminOperation(&handlers::site_replication::SRPeerInfo {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/site-replication/peer/metrics").as_str(),
+        AdminOperation(&handlers::site_replication::SRPeerMetrics {}),
+    )?;
+
     r.insert(
         Method::PUT,
         format!("{}{}", ADMIN_PREFIX, "/v3/set-remote-target").as_str(),
@@ -224,6 +387,76 @@ pub fn make_admin_route(_console_enabled: bool) -> std::io::Result<impl S3Route>
     Ok(r)
 }
 
+/// prometheus scrape router
+fn register_prometheus_route(r: &mut S3Router<AdminOperation>) -> std::io::Result<()> {
+    r.insert(
+        Method::GET,
+        format!("{}{}", PROMETHEUS_V2_PREFIX, "/cluster").as_str(),
+        AdminOperation(&PrometheusMetricsHandler {
+            groups: prometheus::V2_CLUSTER_GROUPS,
+        }),
+    )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", PROMETHEUS_V2_PREFIX, "/node").as_str(),
+        AdminOperation(&PrometheusMetricsHandler {
+            groups: prometheus::V2_NODE_GROUPS,
+        }),
+    )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", PROMETHEUS_V2_PREFIX, "/bucket").as_str(),
+        AdminOperation(&PrometheusMetricsHandler {
+            groups: prometheus::V2_BUCKET_GROUPS,
+        }),
+    )?;
+
+    r.insert(
+        Method::GET,
+        PROMETHEUS_V3_PREFIX,
+        AdminOperation(&PrometheusMetricsHandler {
+            groups: prometheus::ALL_GROUPS,
+        }),
+    )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", PROMETHEUS_V3_PREFIX, "/cluster").as_str(),
+        AdminOperation(&PrometheusMetricsHandler {
+            groups: &[prometheus::MetricsGroup::Cluster],
+        }),
+    )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", PROMETHEUS_V3_PREFIX, "/node").as_str(),
+        AdminOperation(&PrometheusMetricsHandler {
+            groups: &[prometheus::MetricsGroup::Node],
+        }),
+    )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", PROMETHEUS_V3_PREFIX, "/bucket").as_str(),
+        AdminOperation(&PrometheusMetricsHandler {
+            groups: &[prometheus::MetricsGroup::Bucket],
+        }),
+    )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", PROMETHEUS_V3_PREFIX, "/replication").as_str(),
+        AdminOperation(&PrometheusMetricsHandler {
+            groups: &[prometheus::MetricsGroup::Replication],
+        }),
+    )?;
+
+    // Bearer token for scrape configs
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/prometheus/token").as_str(),
+        AdminOperation(&prometheus::PrometheusTokenHandler {}),
+    )?;
+
+    Ok(())
+}
+
 /// user router
 fn register_user_route(r: &mut S3Router<AdminOperation>) -> std::io::Result<()> {
     // 1
@@ -1,6 +1,7 @@
 use crate::admin::ADMIN_PREFIX;
 use crate::admin::console::is_console_path;
 use crate::admin::console::make_console_server;
+use crate::admin::handlers::prometheus::{PROMETHEUS_V2_PREFIX, PROMETHEUS_V3_PREFIX};
 use crate::admin::rpc::RPC_PREFIX;
 use hyper::HeaderMap;
 use hyper::Method;
@@ -84,7 +85,11 @@ where
             }
         }
 
-        path.starts_with(ADMIN_PREFIX) || path.starts_with(RPC_PREFIX) || is_console_path(path)
+        path.starts_with(ADMIN_PREFIX)
+            || path.starts_with(RPC_PREFIX)
+            || path.starts_with(PROMETHEUS_V2_PREFIX)
+            || path.starts_with(PROMETHEUS_V3_PREFIX)
+            || is_console_path(path)
     }
 
     // check_access before call
@@ -100,6 +105,11 @@ where
             return Ok(());
         }
 
+        // Prometheus scrapers usually send a bearer token instead of a signature; the handler authenticates them
+        if req.method == Method::GET && (path.starts_with(PROMETHEUS_V2_PREFIX) || path.starts_with(PROMETHEUS_V3_PREFIX)) {
+            return Ok(());
+        }
+
         // Check RPC signature verification
         if req.uri.path().starts_with(RPC_PREFIX) {
             // Skip signature verification for HEAD requests (health checks)
@@ -112,6 +122,12 @@ where
             return Ok(());
         }
 
+        // STS requests that exchange an identity token for credentials are not signed, the login handler
+        // requires a signature for the other actions
+        if req.method == Method::POST && path == "/" {
+            return Ok(());
+        }
+
         // For non-RPC admin requests, check credentials
         match req.credentials {
             Some(_) => Ok(()),
@@ -1,5 +1,6 @@
 use http::HeaderMap;
 use http::Uri;
+use nebulafx_ecstore::bucket::tagging::decode_tags_to_map;
 use nebulafx_ecstore::global::get_global_action_cred;
 use nebulafx_iamx::error::Error as IamError;
 use nebulafx_iamx::sys::SESSION_POLICY_NAME;
@@ -309,6 +310,10 @@ pub fn get_condition_values(
         clone_header.remove(*obj_lock);
     }
 
+    if let Some(tagging) = clone_header.get("x-amz-tagging").and_then(|v| v.to_str().ok()) {
+        add_request_object_tags(&mut args, &decode_tags_to_map(tagging));
+    }
+
     for (key, _values) in clone_header.iter() {
         if key.as_str().eq_ignore_ascii_case("x-amz-tagging") {
             continue;
@@ -331,6 +336,12 @@ pub fn get_condition_values(
         for (k, v) in claims {
             if let Some(v_str) = v.as_str() {
                 args.insert(k.trim_start_matches("ldap").to_lowercase(), vec![v_str.to_string()]);
+            } else if let Some(values) = v.as_array() {
+                // Multi-valued claims of ID tokens, such as `aud`, back the `jwt:*` condition keys
+                let values: Vec<String> = values.iter().filter_map(|v| v.as_str().map(String::from)).collect();
+                if !values.is_empty() {
+                    args.insert(k.trim_start_matches("ldap").to_lowercase(), values);
+                }
             }
         }
 
@@ -356,6 +367,28 @@ pub fn get_condition_values(
     args
 }
 
+/// Exposes the tags a request sets on the object as the `s3:RequestObjectTag/<key>` and
+/// `s3:RequestObjectTagKeys` condition keys, replacing those already there
+pub fn add_request_object_tags(args: &mut HashMap<String, Vec<String>>, tags: &HashMap<String, String>) {
+    args.retain(|key, _| !key.starts_with("RequestObjectTag"));
+
+    let mut keys: Vec<String> = tags.keys().cloned().collect();
+    keys.sort();
+    for key in keys.iter() {
+        args.insert(format!("RequestObjectTag/{key}"), vec![tags[key].clone()]);
+    }
+    if !keys.is_empty() {
+        args.insert("RequestObjectTagKeys".to_string(), keys);
+    }
+}
+
+/// Exposes the tags of the object a request acts on as the `s3:ExistingObjectTag/<key>` condition keys
+pub fn add_existing_object_tags(args: &mut HashMap<String, Vec<String>>, tags: &HashMap<String, String>) {
+    for (key, value) in tags {
+        args.insert(format!("ExistingObjectTag/{key}"), vec![value.clone()]);
+    }
+}
+
 // Get request authentication type
 pub fn get_request_auth_type(header: &HeaderMap) -> AuthType {
     if is_request_signature_v2(header) {
@@ -515,3 +548,32 @@ pub fn get_query_param<'a>(query: &'a str, param_name: &str) -> Option<&'a str>
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_tag_condition_values() {
+        let mut header = HeaderMap::new();
+        header.insert("x-amz-tagging", "classification=restricted&team=data".parse().unwrap());
+
+        let mut args = get_condition_values(&header, &auth::Credentials::default(), None, None);
+        assert_eq!(args.get("RequestObjectTag/classification"), Some(&vec!["restricted".to_string()]));
+        assert_eq!(args.get("RequestObjectTag/team"), Some(&vec!["data".to_string()]));
+        assert_eq!(
+            args.get("RequestObjectTagKeys"),
+            Some(&vec!["classification".to_string(), "team".to_string()])
+        );
+        assert!(!args.contains_key("x-amz-tagging"));
+
+        // a PutObjectTagging body replaces the header tags
+        add_request_object_tags(&mut args, &HashMap::from([("classification".to_string(), "public".to_string())]));
+        assert_eq!(args.get("RequestObjectTag/classification"), Some(&vec!["public".to_string()]));
+        assert!(!args.contains_key("RequestObjectTag/team"));
+        assert_eq!(args.get("RequestObjectTagKeys"), Some(&vec!["classification".to_string()]));
+
+        add_existing_object_tags(&mut args, &HashMap::from([("classification".to_string(), "restricted".to_string())]));
+        assert_eq!(args.get("ExistingObjectTag/classification"), Some(&vec!["restricted".to_string()]));
+    }
+}
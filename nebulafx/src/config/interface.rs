@@ -8,6 +8,7 @@ pub use nebulafx_tokiox::RuntimeConfig;
 pub struct Config {
     pub server: Option<ServerConfig>,
     pub database: Option<PostgreSQLConfig>,
+    pub iam: Option<IamConfig>,
     pub storage: Option<StorageConfig>,
     pub tls: Option<TlsConfig>,
     pub observability: Option<ObservabilityConfig>,
@@ -29,6 +30,18 @@ pub struct ServerConfig {
     pub secret_key: Option<String>,
     pub root_user: Option<String>,
     pub root_password: Option<String>,
+    /// Key sealing the IAM secret keys and session tokens kept by the IAM backend,
+    /// `root_user:root_password` when unset
+    pub iam_encryption_key: Option<String>,
+    /// Keys of earlier rotations, only used to open the secrets not re-sealed by `rotate-iam-key` yet
+    pub iam_previous_encryption_keys: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct IamConfig {
+    /// Where users, groups and policies are kept: `postgres`, or `object` for the object layer
+    /// itself. `postgres` when unset and `[database]` is configured, `object` otherwise
+    pub backend: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
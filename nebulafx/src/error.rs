@@ -1,6 +1,9 @@
 use nebulafx_ecstore::error::StorageError;
 use s3s::{S3Error, S3ErrorCode};
 
+/// Returned when a write would take a bucket over its hard quota
+pub const ERR_BUCKET_QUOTA_EXCEEDED: &str = "XNebulaFXAdminBucketQuotaExceeded";
+
 #[derive(Debug)]
 pub struct ApiError {
     pub code: S3ErrorCode,
@@ -168,10 +171,14 @@ impl ApiError {
 
 impl From<ApiError> for S3Error {
     fn from(err: ApiError) -> Self {
+        let quota_exceeded = matches!(&err.code, S3ErrorCode::Custom(c) if &**c == ERR_BUCKET_QUOTA_EXCEEDED);
         let mut s3e = S3Error::with_message(err.code, err.message);
         if let Some(source) = err.source {
             s3e.set_source(source);
         }
+        if quota_exceeded {
+            s3e.set_status_code(http::StatusCode::BAD_REQUEST);
+        }
         s3e
     }
 }
@@ -207,10 +214,11 @@ impl From<StorageError> for ApiError {
             StorageError::EntityTooSmall(_, _, _) => S3ErrorCode::EntityTooSmall,
             StorageError::PreconditionFailed => S3ErrorCode::PreconditionFailed,
             StorageError::InvalidRangeSpec(_) => S3ErrorCode::InvalidRange,
+            StorageError::BucketQuotaExceeded(_) => S3ErrorCode::Custom(ERR_BUCKET_QUOTA_EXCEEDED.into()),
             _ => S3ErrorCode::InternalError,
         };
 
-        let message = if code == S3ErrorCode::InternalError {
+        let message = if code == S3ErrorCode::InternalError || matches!(code, S3ErrorCode::Custom(_)) {
             err.to_string()
         } else {
             ApiError::error_code_to_message(&code)
@@ -5,16 +5,17 @@ mod error;
 // mod grpc;
 
 mod server;
+mod site_replication;
 mod storage;
 
 use crate::server::{
-    SHUTDOWN_TIMEOUT, ServiceState, ServiceStateManager, ShutdownSignal, init_event_notifier, shutdown_event_notifier,
-    start_audit_system, start_http_server, stop_audit_system, wait_for_shutdown,
+    SHUTDOWN_TIMEOUT, ServiceState, ServiceStateManager, ShutdownSignal, init_event_notifier, restart_process,
+    shutdown_event_notifier, start_audit_system, start_http_server, stop_audit_system, wait_for_shutdown,
 };
 use crate::storage::ecfs::{process_lambda_configurations, process_queue_configurations, process_topic_configurations};
 use nebulafx_ahm::{
     Scanner, create_ahm_services_cancel_token, heal::storage::ECStoreHealStorage, init_heal_manager,
-    scanner::data_scanner::ScannerConfig, shutdown_ahm_services,
+    scanner::data_scanner::ScannerConfig, set_global_scanner, shutdown_ahm_services,
 };
 use nebulafx_common::globals::set_global_addr;
 use nebulafx_ecstore::bucket::metadata_sys;
@@ -26,7 +27,8 @@ use nebulafx_profilingx::init_profiling;
 use nebulafx_ecstore::store_api::BucketOptions;
 use nebulafx_ecstore::{
     StorageAPI,
-    endpoints::EndpointServerPools,
+    cache_value::metacache::init_metacache_cleanup,
+    endpoints::{EndpointServerPools, SetupType},
     global::{set_global_nebulafx_port, shutdown_background_services},
     notification_sys::new_global_notification_sys,
     set_global_endpoints,
@@ -34,7 +36,10 @@ use nebulafx_ecstore::{
     store::init_local_disks,
     update_erasure_type,
 };
-use nebulafx_iamx::init_iam_sys;
+use nebulafx_iamx::init::{init_root_user, seal_secrets};
+use nebulafx_iamx::seal::{init_sealer, SecretSealer};
+use nebulafx_iamx::store::migrate as migrate_iam;
+use nebulafx_iamx::{init_iam_sys, IamBackend, IamStore, ObjectStore, PostgresStore};
 use nebulafx_notify::notifier_global;
 use nebulafx_obs::init_obs;
 use nebulafx_targets::arn::TargetID;
@@ -42,6 +47,7 @@ use nebulafx_utils::net::parse_and_resolve_address;
 use s3s::s3_error;
 use std::env;
 use std::io::{Error, Result};
+use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
@@ -59,6 +65,45 @@ static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
+/// Command re-sealing the IAM secrets under the current `iam_encryption_key` and exiting,
+/// run once every node is configured with the new key and the old one as a previous key
+const ROTATE_IAM_KEY_COMMAND: &str = "rotate-iam-key";
+
+/// Command copying the IAM data from one backend to the other and exiting, run before switching
+/// `[iam] backend`: `migrate-iam <from> <to>`. It runs on one node with the server of that node
+/// stopped; in a distributed setup the servers of the other nodes keep running, their drives make
+/// up the quorum of the object layer, and IAM changes made through them meanwhile are not copied.
+const MIGRATE_IAM_COMMAND: &str = "migrate-iam";
+
+/// What the process does, one-shot commands exit before the server listens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Serve,
+    RotateIamKey,
+    MigrateIam { from: IamBackend, to: IamBackend },
+}
+
+impl Command {
+    /// Arguments that are not a command, like the data volumes the container entrypoint appends, start the server
+    fn from_args(args: &[String]) -> Result<Self> {
+        match args.first().map(String::as_str) {
+            Some(ROTATE_IAM_KEY_COMMAND) => Ok(Command::RotateIamKey),
+            Some(MIGRATE_IAM_COMMAND) => {
+                let [from, to, ..] = &args[1..] else {
+                    return Err(Error::other(format!("usage: nebulafx {MIGRATE_IAM_COMMAND} <from> <to>, postgres or object")));
+                };
+                let from: IamBackend = from.parse().map_err(Error::other)?;
+                let to: IamBackend = to.parse().map_err(Error::other)?;
+                if from == to {
+                    return Err(Error::other(format!("{MIGRATE_IAM_COMMAND} needs two different backends")));
+                }
+                Ok(Command::MigrateIam { from, to })
+            }
+            _ => Ok(Command::Serve),
+        }
+    }
+}
+
 const LOGO: &str = r#"
 
 ╔═══════════════════════════════════════════════════════════════════╗
@@ -91,25 +136,23 @@ fn main() -> Result<()> {
     let runtime = get_tokio_runtime_builder(get_config().runtime.as_ref())
         .build()
         .expect("Failed to build Tokio runtime");
-    runtime.block_on(async_main())
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = Command::from_args(&args)?;
+    runtime.block_on(async_main(command))
 }
-async fn async_main() -> Result<()> {
+async fn async_main(command: Command) -> Result<()> {
     let config = get_config();
-    // Initialize PostgreSQL connection pool if database config exists
-    match PostgreSQLPool::init(config.database.as_ref()).await {
-        Ok(s) => info!("PostgreSQL connection pool initialized successfully: {}", s),
+    // Initialize PostgreSQL connection pool and schema if database config exists
+    if let Some(db_config) = config.database.as_ref() {
+        use nebulafx_iamx::init::init_database;
+
+        match PostgreSQLPool::init(Some(db_config)).await {
+            Ok(s) => info!("PostgreSQL connection pool initialized successfully: {}", s),
             Err(e) => {
                 error!("Failed to initialize PostgreSQL connection pool: {}", e);
                 return Err(Error::other(format!("Database connection failed: {}", e)));
             }
         }
-    
-    // Initialize database schema and root user if database is configured
-    if let Some(db_config) = config.database.as_ref() {
-        use nebulafx_iamx::init::{init_database, init_root_user};
-        
-        let pool = PostgreSQLPool::get()
-            .map_err(|e| Error::other(format!("Failed to get database pool: {}", e)))?;
         
         // Get database connection URL for migrations
         let database_url = db_config.build_connection_url()
@@ -120,21 +163,26 @@ async fn async_main() -> Result<()> {
             error!("Failed to initialize database tables: {}", e);
             return Err(Error::other(format!("Database initialization failed: {}", e)));
         }
-        
-        // Initialize root user
-        let root_user = config.server.as_ref()
-            .and_then(|s| s.root_user.as_deref())
-            .unwrap_or("nebulafxadmin");
-        let root_password = config.server.as_ref()
-            .and_then(|s| s.root_password.as_deref())
-            .unwrap_or("nebulafxadmin");
-        
-        if let Err(e) = init_root_user(pool.inner(), root_user, root_password).await {
-            error!("Failed to initialize root user: {}", e);
-            return Err(Error::other(format!("Root user initialization failed: {}", e)));
-        }
     }
 
+    // Secrets are sealed before any IAM object is read or written
+    let (root_user, root_password) = root_credentials(config);
+    let previous_keys = config.server.as_ref()
+        .and_then(|s| s.iam_previous_encryption_keys.clone())
+        .unwrap_or_default();
+    let sealer = match config.server.as_ref().and_then(|s| s.iam_encryption_key.as_deref()) {
+        Some(key) if !key.is_empty() => SecretSealer::new(key, &previous_keys),
+        _ => {
+            warn!(
+                "No iam_encryption_key is configured, IAM secrets are sealed under a key derived from the root \
+                 credentials; after changing the root password add the previous \"<root_user>:<root_password>\" \
+                 to iam_previous_encryption_keys"
+            );
+            SecretSealer::from_root_credentials(root_user, root_password, &previous_keys)
+        }
+    };
+    init_sealer(sealer).map_err(|e| Error::other(format!("Failed to initialize IAM secret sealer: {}", e)))?;
+
     // Initialize performance profiling if enabled
     match init_profiling(config.profiling.as_ref()).await {
         Ok(s) => info!("Profiling initialized successfully: {}", s),
@@ -144,7 +192,7 @@ async fn async_main() -> Result<()> {
         }
     }
     // Run with config
-    match run(config).await {
+    match run(config, command).await {
         Ok(_) => Ok(()),
         Err(e) => {
             error!("Server encountered an error and is shutting down: {}", e);
@@ -153,8 +201,123 @@ async fn async_main() -> Result<()> {
     }
 }
 
+/// Root credentials of the configuration, the IAM root user and the default sealing key
+fn root_credentials(config: &Config) -> (&str, &str) {
+    let root_user = config.server.as_ref()
+        .and_then(|s| s.root_user.as_deref())
+        .unwrap_or("nebulafxadmin");
+    let root_password = config.server.as_ref()
+        .and_then(|s| s.root_password.as_deref())
+        .unwrap_or("nebulafxadmin");
+    (root_user, root_password)
+}
+
+/// Backend keeping the IAM objects, `[iam] backend` or Postgres when `[database]` is configured
+fn iam_backend(config: &Config) -> Result<IamBackend> {
+    match config.iam.as_ref().and_then(|iam| iam.backend.as_deref()) {
+        Some(backend) => backend.parse().map_err(Error::other),
+        None if config.database.is_some() => Ok(IamBackend::Postgres),
+        None => Ok(IamBackend::Object),
+    }
+}
+
+fn open_iam_store(backend: IamBackend, store: Option<&Arc<ECStore>>) -> Result<Arc<dyn IamStore>> {
+    match backend {
+        IamBackend::Postgres => {
+            let pool = PostgreSQLPool::get()
+                .map_err(|e| Error::other(format!("The postgres IAM backend needs a database configuration: {}", e)))?;
+            Ok(Arc::new(PostgresStore::new(pool.inner().clone())))
+        }
+        IamBackend::Object => {
+            let store = store.ok_or_else(|| Error::other("The object IAM backend needs the object layer"))?;
+            Ok(Arc::new(ObjectStore::new(store.clone())))
+        }
+    }
+}
+
+/// Brings the object layer up on the drives of `endpoint_pools`. In a distributed setup the drives of
+/// the other nodes are reached through their servers, enough of them have to be running for a quorum.
+async fn start_object_layer(
+    server_addr: SocketAddr,
+    endpoint_pools: &EndpointServerPools,
+    setup_type: SetupType,
+    ctx: CancellationToken,
+) -> Result<Arc<ECStore>> {
+    set_global_endpoints(endpoint_pools.as_ref().clone());
+    update_erasure_type(setup_type).await;
+
+    // Initialize the local disk
+    init_local_disks(endpoint_pools.clone()).await.map_err(Error::other)?;
+
+    // init store
+    let store = ECStore::new(server_addr, endpoint_pools.clone(), ctx)
+        .await
+        .inspect_err(|err| {
+            error!("ECStore::new {:?}", err);
+        })?;
+    Ok(store)
+}
+
+/// Runs a one-shot command without starting the server, the object layer is only brought up when
+/// the command works on the object backend
+async fn run_command(
+    config: &Config,
+    command: Command,
+    server_addr: SocketAddr,
+    endpoint_pools: &EndpointServerPools,
+    setup_type: SetupType,
+) -> Result<()> {
+    let backend = iam_backend(config)?;
+    let needs_object_layer = match command {
+        Command::Serve => false,
+        Command::RotateIamKey => backend == IamBackend::Object,
+        Command::MigrateIam { from, to } => from == IamBackend::Object || to == IamBackend::Object,
+    };
+
+    let ctx = CancellationToken::new();
+    let store = if needs_object_layer {
+        Some(start_object_layer(server_addr, endpoint_pools, setup_type, ctx.clone()).await?)
+    } else {
+        None
+    };
+
+    let res = match command {
+        Command::Serve => Ok(()),
+        Command::RotateIamKey => {
+            let iam_store = open_iam_store(backend, store.as_ref())?;
+            match seal_secrets(iam_store.as_ref(), true).await {
+                Ok(sealed) => {
+                    info!("Re-sealed the IAM secrets of {} entries, the previous keys can be removed", sealed);
+                    Ok(())
+                }
+                Err(e) => {
+                    error!("Failed to rotate IAM encryption key: {}", e);
+                    Err(Error::other(format!("IAM key rotation failed: {}", e)))
+                }
+            }
+        }
+        Command::MigrateIam { from, to } => {
+            let from = open_iam_store(from, store.as_ref())?;
+            let to = open_iam_store(to, store.as_ref())?;
+            match migrate_iam(from.as_ref(), to.as_ref()).await {
+                Ok(stats) => {
+                    info!("Migrated {}, set [iam] backend to {} before starting the server", stats, to.backend());
+                    Ok(())
+                }
+                Err(e) => {
+                    error!("Failed to migrate IAM data: {}", e);
+                    Err(Error::other(format!("IAM migration failed: {}", e)))
+                }
+            }
+        }
+    };
+
+    ctx.cancel();
+    res
+}
+
 #[instrument(skip(config))]
-async fn run(config: &Config) -> Result<()> {
+async fn run(config: &Config, command: Command) -> Result<()> {
     debug!("config: {:?}", config);
 
     // Get server config
@@ -223,6 +386,10 @@ async fn run(config: &Config) -> Result<()> {
         }
     }
 
+    if command != Command::Serve {
+        return run_command(config, command, server_addr, &endpoint_pools, setup_type).await;
+    }
+
     let state_manager = ServiceStateManager::new();
     // Update service status to Starting
     state_manager.update(ServiceState::Starting);
@@ -234,20 +401,8 @@ async fn run(config: &Config) -> Result<()> {
         Some(s3_shutdown_tx)
     };
 
-    set_global_endpoints(endpoint_pools.as_ref().clone());
-    update_erasure_type(setup_type).await;
-
-    // Initialize the local disk
-    init_local_disks(endpoint_pools.clone()).await.map_err(Error::other)?;
-
     let ctx = CancellationToken::new();
-
-    // init store
-    let store = ECStore::new(server_addr, endpoint_pools.clone(), ctx.clone())
-        .await
-        .inspect_err(|err| {
-            error!("ECStore::new {:?}", err);
-        })?;
+    let store = start_object_layer(server_addr, &endpoint_pools, setup_type, ctx.clone()).await?;
 
     ecconfig::init();
     // config system configuration
@@ -281,15 +436,26 @@ async fn run(config: &Config) -> Result<()> {
 
     init_bucket_metadata_sys(store.clone(), buckets.clone()).await;
 
-    // Initialize IAM system with database pool
-    if let Some(db_config) = config.database.as_ref() {
-        let pool = PostgreSQLPool::get()
-            .map_err(|e| Error::other(format!("Failed to get database pool: {}", e)))?;
-        init_iam_sys(pool.inner().clone()).await.map_err(Error::other)?;
-    } else {
-        warn!("Database not configured, IAM system will not be initialized");
+    // Initialize IAM system with the configured backend
+    let backend = iam_backend(config)?;
+    let iam_store = open_iam_store(backend, Some(&store))?;
+    info!("IAM objects are kept in the {} backend", backend);
+
+    let (root_user, root_password) = root_credentials(config);
+    if let Err(e) = init_root_user(iam_store.as_ref(), root_user, root_password).await {
+        error!("Failed to initialize root user: {}", e);
+        return Err(Error::other(format!("Root user initialization failed: {}", e)));
+    }
+
+    // Seal the secrets written before sealing was enabled, and check the sealed ones still open so that
+    // a changed key fails here rather than on every IAM listing
+    if let Err(e) = seal_secrets(iam_store.as_ref(), false).await {
+        error!("Failed to seal IAM secrets: {}", e);
+        return Err(Error::other(format!("IAM secret sealing failed: {}", e)));
     }
 
+    init_iam_sys(iam_store).await.map_err(Error::other)?;
+
     add_bucket_notification_configuration(buckets.clone()).await;
 
     // Initialize the global notification system
@@ -298,6 +464,14 @@ async fn run(config: &Config) -> Result<()> {
         Error::other(err)
     })?;
 
+    // Load the sites this deployment is linked with, replication stays disabled if it fails
+    if let Err(err) = site_replication::get().init(store.clone()).await {
+        error!("site replication init failed {:?}", &err);
+    }
+
+    // Drop the cached listings of this node nobody pages through anymore
+    init_metacache_cleanup(store.clone(), ctx.clone());
+
     // Create a cancellation token for AHM services
     let _ = create_ahm_services_cancel_token();
 
@@ -321,15 +495,17 @@ async fn run(config: &Config) -> Result<()> {
 
             if enable_scanner {
                 info!(target: "nebulafx::main::run","Starting scanner with heal manager...");
-                let scanner = Scanner::new(Some(ScannerConfig::default()), Some(heal_manager));
+                let scanner = Arc::new(Scanner::new(Some(ScannerConfig::default()), Some(heal_manager)));
                 scanner.start().await?;
+                set_global_scanner(scanner)?;
             } else {
                 info!(target: "nebulafx::main::run","Scanner disabled, but heal manager is initialized and available");
             }
         } else if enable_scanner {
             info!("Starting scanner without heal manager...");
-            let scanner = Scanner::new(Some(ScannerConfig::default()), None);
+            let scanner = Arc::new(Scanner::new(Some(ScannerConfig::default()), None));
             scanner.start().await?;
+            set_global_scanner(scanner)?;
         }
     } else {
         info!(target: "nebulafx::main::run","Both scanner and heal are disabled, skipping AHM service initialization");
@@ -347,6 +523,15 @@ async fn run(config: &Config) -> Result<()> {
         ShutdownSignal::CtrlC => {
             handle_shutdown(&state_manager, s3_shutdown_tx, ctx.clone()).await;
         }
+        ShutdownSignal::Stop => {
+            handle_shutdown(&state_manager, s3_shutdown_tx, ctx.clone()).await;
+        }
+        ShutdownSignal::Restart => {
+            handle_shutdown(&state_manager, s3_shutdown_tx, ctx.clone()).await;
+            info!(target: "nebulafx::main::run", "restarting server");
+            // Only returns if the new process could not be started
+            return Err(restart_process());
+        }
     }
 
     info!(target: "nebulafx::main::run","server is stopped state: {:?}", state_manager.current_state());
@@ -0,0 +1,252 @@
+
+
+//! Per-bucket CORS evaluation.
+//!
+//! Requests carrying an `Origin` header for a bucket with a CORS configuration
+//! are answered from the bucket's rules. Everything else falls through to the
+//! global [`tower_http::cors::CorsLayer`] built from `cors_allowed_origins`.
+
+use http::header::{
+    ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, HOST,
+    ORIGIN, VARY,
+};
+use http::{HeaderMap, HeaderValue, Method, Request as HttpRequest, Response, StatusCode};
+use hyper::body::Incoming;
+use nebulafx_ecstore::bucket::{cors::CorsApi, metadata_sys};
+use s3s::dto::CORSRule;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Layer that applies bucket CORS rules in front of the global CORS layer
+#[derive(Clone)]
+pub struct BucketCorsLayer {
+    domains: Arc<Vec<String>>,
+}
+
+impl BucketCorsLayer {
+    /// `domains` are the server domains used for virtual-hosted-style requests
+    pub fn new(domains: Vec<String>) -> Self {
+        Self {
+            domains: Arc::new(domains),
+        }
+    }
+}
+
+impl<S> Layer<S> for BucketCorsLayer {
+    type Service = BucketCorsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BucketCorsService {
+            inner,
+            domains: self.domains.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct BucketCorsService<S> {
+    inner: S,
+    domains: Arc<Vec<String>>,
+}
+
+impl<S, B> Service<HttpRequest<Incoming>> for BucketCorsService<S>
+where
+    S: Service<HttpRequest<Incoming>, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    B: Default + Send + 'static,
+{
+    type Response = Response<B>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: HttpRequest<Incoming>) -> Self::Future {
+        let mut inner = self.inner.clone();
+
+        let origin = req.headers().get(ORIGIN).and_then(|v| v.to_str().ok()).map(str::to_string);
+        let bucket = origin.as_ref().and_then(|_| bucket_name(&req, &self.domains));
+        let (Some(origin), Some(bucket)) = (origin, bucket) else {
+            return Box::pin(inner.call(req));
+        };
+
+        Box::pin(async move {
+            // Only consult buckets already in the metadata cache so a stream of made-up
+            // bucket names cannot make every request load (and cache) metadata from disk
+            let Ok((config, _)) = metadata_sys::get_cached_cors_config(&bucket).await else {
+                return inner.call(req).await;
+            };
+
+            let preflight_method = req
+                .headers()
+                .get(ACCESS_CONTROL_REQUEST_METHOD)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+
+            if let (&Method::OPTIONS, Some(method)) = (req.method(), preflight_method) {
+                let requested = req
+                    .headers()
+                    .get(ACCESS_CONTROL_REQUEST_HEADERS)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                let requested: Vec<&str> = requested.split(',').map(str::trim).filter(|h| !h.is_empty()).collect();
+
+                let mut resp = Response::new(B::default());
+                match config.find_rule(&origin, &method, &requested) {
+                    Some(rule) => {
+                        set_cors_headers(resp.headers_mut(), rule, &origin);
+                        if !requested.is_empty() {
+                            if let Ok(value) = HeaderValue::from_str(&requested.join(", ")) {
+                                resp.headers_mut().insert(ACCESS_CONTROL_ALLOW_HEADERS, value);
+                            }
+                        }
+                        if let Some(max_age) = rule.max_age_seconds {
+                            resp.headers_mut().insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from(max_age));
+                        }
+                    }
+                    None => *resp.status_mut() = StatusCode::FORBIDDEN,
+                }
+                add_vary(resp.headers_mut());
+                return Ok(resp);
+            }
+
+            let method = req.method().to_string();
+            let mut resp = inner.call(req).await?;
+
+            // The bucket rules replace whatever the global layer allowed
+            let headers = resp.headers_mut();
+            for name in [
+                ACCESS_CONTROL_ALLOW_ORIGIN,
+                ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                ACCESS_CONTROL_ALLOW_METHODS,
+                ACCESS_CONTROL_ALLOW_HEADERS,
+                ACCESS_CONTROL_EXPOSE_HEADERS,
+                ACCESS_CONTROL_MAX_AGE,
+            ] {
+                headers.remove(name);
+            }
+            if let Some(rule) = config.find_rule(&origin, &method, &[]) {
+                set_cors_headers(headers, rule, &origin);
+            }
+            add_vary(headers);
+
+            Ok(resp)
+        })
+    }
+}
+
+/// Bucket addressed by a virtual-hosted-style or path-style request, if any
+fn bucket_name<T>(req: &HttpRequest<T>, domains: &[String]) -> Option<String> {
+    let host = req
+        .headers()
+        .get(HOST)
+        .and_then(|v| v.to_str().ok())
+        .or_else(|| req.uri().authority().map(|a| a.as_str()));
+
+    if let Some(host) = host {
+        for domain in domains {
+            if let Some(bucket) = host.strip_suffix(domain.as_str()).and_then(|h| h.strip_suffix('.')) {
+                return (!bucket.is_empty()).then(|| bucket.to_string());
+            }
+        }
+    }
+
+    let path = req.uri().path();
+    // Admin, console and node RPC routes are not buckets
+    if path.starts_with("/nebulafx/") {
+        return None;
+    }
+
+    let bucket = path.trim_start_matches('/').split('/').next().unwrap_or_default();
+    (!bucket.is_empty()).then(|| bucket.to_string())
+}
+
+fn set_cors_headers(headers: &mut HeaderMap, rule: &CORSRule, origin: &str) {
+    if rule.allowed_origins.iter().any(|o| o == "*") {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    } else if let Ok(value) = HeaderValue::from_str(origin) {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, value);
+        headers.insert(ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&rule.allowed_methods.join(", ")) {
+        headers.insert(ACCESS_CONTROL_ALLOW_METHODS, value);
+    }
+
+    if let Some(expose) = rule.expose_headers.as_ref().filter(|h| !h.is_empty()) {
+        if let Ok(value) = HeaderValue::from_str(&expose.join(", ")) {
+            headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, value);
+        }
+    }
+}
+
+fn add_vary(headers: &mut HeaderMap) {
+    let has_origin = headers
+        .get_all(VARY)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .any(|v| v.split(',').any(|h| h.trim().eq_ignore_ascii_case("origin")));
+    if !has_origin {
+        headers.append(VARY, HeaderValue::from_static("Origin"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(host: &str, path: &str) -> HttpRequest<()> {
+        HttpRequest::builder().uri(path).header(HOST, host).body(()).unwrap()
+    }
+
+    #[test]
+    fn test_bucket_name() {
+        let domains = vec!["s3.example.com".to_string(), "s3.example.com:9000".to_string()];
+
+        assert_eq!(
+            bucket_name(&request("photos.s3.example.com", "/a.jpg"), &domains).as_deref(),
+            Some("photos")
+        );
+        assert_eq!(
+            bucket_name(&request("photos.s3.example.com:9000", "/"), &domains).as_deref(),
+            Some("photos")
+        );
+        assert_eq!(
+            bucket_name(&request("localhost:9000", "/photos/a.jpg"), &domains).as_deref(),
+            Some("photos")
+        );
+        assert_eq!(bucket_name(&request("localhost:9000", "/"), &domains), None);
+        assert_eq!(bucket_name(&request("localhost:9000", "/nebulafx/admin/v3/info"), &domains), None);
+    }
+
+    #[test]
+    fn test_set_cors_headers() {
+        let rule = CORSRule {
+            allowed_headers: None,
+            allowed_methods: vec!["GET".to_string(), "PUT".to_string()],
+            allowed_origins: vec!["https://app.example.com".to_string()],
+            expose_headers: Some(vec!["ETag".to_string()]),
+            id: None,
+            max_age_seconds: None,
+        };
+
+        let mut headers = HeaderMap::new();
+        set_cors_headers(&mut headers, &rule, "https://app.example.com");
+        add_vary(&mut headers);
+        add_vary(&mut headers);
+
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_METHODS], "GET, PUT");
+        assert_eq!(headers[ACCESS_CONTROL_EXPOSE_HEADERS], "ETag");
+        assert_eq!(headers.get_all(VARY).iter().count(), 1);
+    }
+}
@@ -1,7 +1,13 @@
 use crate::admin;
 use crate::auth::IAMAuth;
 use crate::config;
-use crate::server::{ServiceState, ServiceStateManager, hybrid::hybrid, layer::RedirectLayer};
+use crate::server::{
+    ServiceState, ServiceStateManager,
+    cors::BucketCorsLayer,
+    hybrid::hybrid,
+    layer::{FreezeLayer, RedirectLayer},
+    trace::HttpTraceLayer,
+};
 use crate::storage;
 use crate::storage::tonic_service::make_server;
 use bytes::Bytes;
@@ -187,6 +193,9 @@ pub async fn start_http_server(
     info!(target: "nebulafx::main::startup","For more information, visit https://nebulafx.com/docs/");
     info!(target: "nebulafx::main::startup", "Frontend Console runs independently. See nebulafxconsole project for details.");
 
+    // Domains used for virtual-hosted-style requests, bucket CORS needs them to find the bucket
+    let mut server_domains = Vec::new();
+
     // Setup S3 service
     // This project uses the S3S library to implement S3 services
     let s3_service = {
@@ -214,6 +223,7 @@ pub async fn start_http_server(
                 }
 
                 info!("virtual-hosted-style requests are enabled use domain_name {:?}", &domain_sets);
+                server_domains = domain_sets.iter().cloned().collect();
                 b.set_host(MultiDomain::new(domain_sets).map_err(Error::other)?);
             }
         }
@@ -238,6 +248,7 @@ pub async fn start_http_server(
     tokio::spawn(async move {
         // Create CORS layer inside the server loop closure
         let cors_layer = parse_cors_origins(cors_allowed_origins.as_ref());
+        let bucket_cors_layer = BucketCorsLayer::new(server_domains);
 
         #[cfg(unix)]
         let (mut sigterm_inner, mut sigint_inner) = {
@@ -332,6 +343,7 @@ pub async fn start_http_server(
                 s3_service.clone(),
                 graceful.clone(),
                 cors_layer.clone(),
+                bucket_cors_layer.clone(),
                 is_console,
             );
         }
@@ -441,6 +453,7 @@ fn process_connection(
     s3_service: S3Service,
     graceful: Arc<GracefulShutdown>,
     cors_layer: CorsLayer,
+    bucket_cors_layer: BucketCorsLayer,
     is_console: bool,
 ) {
     tokio::spawn(async move {
@@ -506,10 +519,16 @@ fn process_connection(
                     }),
             )
             .layer(PropagateRequestIdLayer::x_request_id())
+            // Bucket CORS rules take precedence over the global CORS settings
+            .layer(bucket_cors_layer)
             .layer(cors_layer)
             // Compress responses
             .layer(CompressionLayer::new())
+            // Publish admin trace records, sees uncompressed response bodies
+            .layer(HttpTraceLayer)
             .option_layer(if is_console { Some(RedirectLayer) } else { None })
+            // Reject S3 calls while `admin service freeze` is in effect
+            .layer(FreezeLayer)
             .service(service);
 
         let hybrid_service = TowerToHyperService::new(hybrid_service);
@@ -1,4 +1,6 @@
 use crate::server::hybrid::HybridBody;
+use crate::server::is_frozen;
+use bytes::Bytes;
 use http::{Request as HttpRequest, Response, StatusCode};
 use hyper::body::Incoming;
 use std::future::Future;
@@ -75,3 +77,93 @@ where
         Box::pin(async move { inner.call(req).await.map_err(Into::into) })
     }
 }
+
+/// Layer that rejects S3 calls with 503 while the service is frozen by `admin service freeze`
+#[derive(Clone)]
+pub struct FreezeLayer;
+
+impl<S> Layer<S> for FreezeLayer {
+    type Service = FreezeService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FreezeService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct FreezeService<S> {
+    inner: S,
+}
+
+impl<S, RestBody, GrpcBody> Service<HttpRequest<Incoming>> for FreezeService<S>
+where
+    S: Service<HttpRequest<Incoming>, Response = Response<HybridBody<RestBody, GrpcBody>>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>> + Send + 'static,
+    RestBody: From<Bytes> + Send + 'static,
+    GrpcBody: Send + 'static,
+{
+    type Response = Response<HybridBody<RestBody, GrpcBody>>;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: HttpRequest<Incoming>) -> Self::Future {
+        // Admin, console and node RPC calls keep working so the service can be unfrozen
+        if is_frozen() && !is_internal_request(&req) {
+            debug!("Rejecting {} {} while the service is frozen", req.method(), req.uri().path());
+
+            let response = Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header(http::header::CONTENT_TYPE, "application/xml")
+                .header(http::header::RETRY_AFTER, "1")
+                .body(HybridBody::Rest {
+                    rest_body: RestBody::from(Bytes::from_static(SERVICE_FROZEN_ERROR.as_bytes())),
+                })
+                .expect("failed to build service unavailable response");
+
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let mut inner = self.inner.clone();
+        Box::pin(async move { inner.call(req).await.map_err(Into::into) })
+    }
+}
+
+const SERVICE_FROZEN_ERROR: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+    "<Error><Code>ServiceUnavailable</Code>",
+    "<Message>The service is frozen for maintenance, please retry later.</Message></Error>"
+);
+
+fn is_internal_request<T>(req: &HttpRequest<T>) -> bool {
+    let is_grpc = req
+        .headers()
+        .get(http::header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/grpc"));
+    is_grpc || req.uri().path().starts_with("/nebulafx/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_internal_request() {
+        let req = |path: &str| HttpRequest::builder().uri(path).body(()).unwrap();
+
+        assert!(is_internal_request(&req("/nebulafx/admin/v3/service?action=unfreeze")));
+        assert!(!is_internal_request(&req("/bucket/object")));
+        assert!(!is_internal_request(&req("/")));
+
+        let grpc = HttpRequest::builder()
+            .uri("/node_service.NodeService/Ping")
+            .header(http::header::CONTENT_TYPE, "application/grpc")
+            .body(())
+            .unwrap();
+        assert!(is_internal_request(&grpc));
+    }
+}
@@ -1,8 +1,10 @@
 mod audit;
+mod cors;
 mod http;
 mod hybrid;
 mod layer;
 mod service_state;
+mod trace;
 
 mod event;
 
@@ -14,3 +16,4 @@ pub(crate) use service_state::ServiceState;
 pub(crate) use service_state::ServiceStateManager;
 pub(crate) use service_state::ShutdownSignal;
 pub(crate) use service_state::wait_for_shutdown;
+pub(crate) use service_state::{is_frozen, restart_process, signal_service};
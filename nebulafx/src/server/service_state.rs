@@ -1,7 +1,9 @@
 use atomic_enum::atomic_enum;
-use std::sync::Arc;
-use std::sync::atomic::Ordering;
+use nebulafx_madmin::service_commands::ServiceAction;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
 use std::time::Duration;
+use tokio::sync::watch;
 use tracing::info;
 
 // a configurable shutdown timeout
@@ -40,6 +42,73 @@ pub enum ShutdownSignal {
     Sigterm,
     #[cfg(unix)]
     Sigint,
+    /// `admin service stop`
+    Stop,
+    /// `admin service restart`, the process re-executes itself once shut down
+    Restart,
+}
+
+// Stop/restart requested through the admin API or by a peer
+static SERVICE_SIGNAL: LazyLock<watch::Sender<Option<ServiceAction>>> = LazyLock::new(|| watch::channel(None).0);
+
+static FROZEN: AtomicBool = AtomicBool::new(false);
+
+/// Whether new S3 calls are currently rejected by `admin service freeze`
+pub(crate) fn is_frozen() -> bool {
+    FROZEN.load(Ordering::SeqCst)
+}
+
+/// Apply a service action on this node.
+///
+/// Freeze and unfreeze take effect immediately, stop and restart wake up
+/// [`wait_for_shutdown`] so the regular shutdown path runs.
+pub(crate) fn signal_service(action: ServiceAction) {
+    info!("NebulaFX Received service {} signal", action.as_str());
+    match action {
+        ServiceAction::Freeze => FROZEN.store(true, Ordering::SeqCst),
+        ServiceAction::Unfreeze => FROZEN.store(false, Ordering::SeqCst),
+        ServiceAction::Stop | ServiceAction::Restart => {
+            SERVICE_SIGNAL.send_replace(Some(action));
+        }
+    }
+}
+
+async fn wait_for_service_signal() -> ShutdownSignal {
+    let mut rx = SERVICE_SIGNAL.subscribe();
+    let action = match rx.wait_for(Option::is_some).await {
+        Ok(action) => *action,
+        // The sender lives in a static and is never dropped
+        Err(_) => std::future::pending().await,
+    };
+    match action {
+        Some(ServiceAction::Restart) => ShutdownSignal::Restart,
+        _ => ShutdownSignal::Stop,
+    }
+}
+
+/// Replace the current process with a fresh copy started with the same arguments
+#[cfg(unix)]
+pub(crate) fn restart_process() -> std::io::Error {
+    use std::os::unix::process::CommandExt;
+
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => return e,
+    };
+    std::process::Command::new(exe).args(std::env::args_os().skip(1)).exec()
+}
+
+/// Start a fresh copy of the process with the same arguments, the caller exits afterwards
+#[cfg(not(unix))]
+pub(crate) fn restart_process() -> std::io::Error {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => return e,
+    };
+    match std::process::Command::new(exe).args(std::env::args_os().skip(1)).spawn() {
+        Ok(_) => std::process::exit(0),
+        Err(e) => e,
+    }
 }
 
 #[atomic_enum]
@@ -70,6 +139,7 @@ pub(crate) async fn wait_for_shutdown() -> ShutdownSignal {
             info!("NebulaFX Received SIGTERM signal");
             ShutdownSignal::Sigterm
         }
+        signal = wait_for_service_signal() => signal,
     }
 }
 
@@ -80,6 +150,7 @@ pub(crate) async fn wait_for_shutdown() -> ShutdownSignal {
             info!("Received Ctrl-C signal");
             ShutdownSignal::CtrlC
         }
+        signal = wait_for_service_signal() => signal,
     }
 }
 
@@ -142,4 +213,20 @@ impl Default for ServiceStateManager {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_service_signal() {
+        signal_service(ServiceAction::Freeze);
+        assert!(is_frozen());
+        signal_service(ServiceAction::Unfreeze);
+        assert!(!is_frozen());
+
+        signal_service(ServiceAction::Restart);
+        assert!(matches!(wait_for_service_signal().await, ShutdownSignal::Restart));
+    }
+}
+
  
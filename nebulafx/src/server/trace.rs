@@ -0,0 +1,482 @@
+
+
+//! Publishes admin trace records for S3 and node RPC requests.
+//!
+//! Records are only built while an `admin trace` client is subscribed to the
+//! matching type. A record is published once the response body has been fully
+//! sent (or dropped), so latency and output bytes cover the whole transfer.
+
+use bytes::Bytes;
+use chrono::Utc;
+use http::{HeaderMap, Method, Request as HttpRequest, Response};
+use http_body::Frame;
+use hyper::body::Incoming;
+use nebulafx_common::trace::{publish_trace, trace_enabled};
+use nebulafx_madmin::trace::{TraceCallStats, TraceHTTPStats, TraceInfo, TraceRequestInfo, TraceResponseInfo, TraceType};
+use pin_project_lite::pin_project;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tower::{Layer, Service};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Response bodies larger than this are truncated in trace records
+const MAX_TRACE_BODY_SIZE: usize = 64 * 1024;
+
+const REDACTED: &str = "*REDACTED*";
+
+const REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "x-amz-security-token",
+    "x-amz-server-side-encryption-customer-key",
+    "x-amz-copy-source-server-side-encryption-customer-key",
+];
+
+// Query parameters of presigned URLs, which carry the same credentials as the headers above
+const REDACTED_QUERY_PARAMS: &[&str] = &["x-amz-security-token", "x-amz-signature", "x-amz-credential"];
+
+// Admin calls whose responses carry secret keys or tokens, their bodies are never kept
+const CREDENTIAL_RESPONSE_PATHS: &[&str] = &[
+    "/nebulafx/admin/v3/add-service-accounts",
+    "/nebulafx/admin/v3/info-service-account",
+    "/nebulafx/admin/v3/export-iam",
+    "/nebulafx/admin/v3/list-remote-targets",
+    "/nebulafx/admin/v3/prometheus/token",
+];
+
+// The trace streams themselves are never traced
+const ADMIN_TRACE_PATH: &str = "/nebulafx/admin/v3/trace";
+const RPC_TRACE_PATH: &str = "/node_service.NodeService/Trace";
+
+/// Layer that publishes a trace record for every request while someone is tracing
+#[derive(Clone)]
+pub struct HttpTraceLayer;
+
+impl<S> Layer<S> for HttpTraceLayer {
+    type Service = HttpTraceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HttpTraceService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpTraceService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<HttpRequest<Incoming>> for HttpTraceService<S>
+where
+    S: Service<HttpRequest<Incoming>, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError> + Send + 'static,
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+{
+    type Response = Response<TraceBody<B>>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: HttpRequest<Incoming>) -> Self::Future {
+        let mut inner = self.inner.clone();
+
+        let trace_type = if is_grpc(req.headers()) {
+            TraceType::INTERNAL
+        } else {
+            TraceType::S3
+        };
+        let path = req.uri().path();
+        let pending = if trace_enabled(trace_type) && path != ADMIN_TRACE_PATH && path != RPC_TRACE_PATH {
+            Some(PendingTrace::new(trace_type, &req))
+        } else {
+            None
+        };
+
+        Box::pin(async move {
+            let resp = inner.call(req).await.map_err(Into::into)?;
+            let (parts, body) = resp.into_parts();
+
+            let pending = pending.map(|mut p| {
+                p.set_response(parts.status.as_u16(), &parts.headers);
+                p
+            });
+
+            Ok(Response::from_parts(parts, TraceBody { inner: body, pending }))
+        })
+    }
+}
+
+pin_project! {
+    /// Response body that counts, and for S3 calls keeps a prefix of, what is sent to the client
+    pub struct TraceBody<B> {
+        #[pin]
+        inner: B,
+        pending: Option<PendingTrace>,
+    }
+}
+
+impl<B> http_body::Body for TraceBody<B>
+where
+    B: http_body::Body<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let res = this.inner.poll_frame(cx);
+
+        match &res {
+            Poll::Ready(Some(Ok(frame))) => {
+                if let (Some(pending), Some(data)) = (this.pending.as_mut(), frame.data_ref()) {
+                    pending.record_output(data);
+                }
+            }
+            Poll::Ready(Some(Err(_))) => {
+                if let Some(pending) = this.pending.as_mut() {
+                    pending.error = Some("response body failed".to_string());
+                }
+                // Dropping publishes the record
+                this.pending.take();
+            }
+            Poll::Ready(None) => {
+                this.pending.take();
+            }
+            Poll::Pending => {}
+        }
+
+        res
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// A request whose record is published when it is dropped
+pub struct PendingTrace {
+    trace_type: TraceType,
+    func_name: String,
+    start: Instant,
+    ttfb: Option<std::time::Duration>,
+    req_info: TraceRequestInfo,
+    resp_info: TraceResponseInfo,
+    input_bytes: i64,
+    output_bytes: i64,
+    capture_body: bool,
+    error: Option<String>,
+}
+
+impl PendingTrace {
+    fn new<B>(trace_type: TraceType, req: &HttpRequest<B>) -> Self {
+        let headers = req.headers();
+        let path = req.uri().path();
+
+        let func_name = if trace_type.contains(&TraceType::INTERNAL) {
+            format!("internal.{}", path.rsplit('/').next().unwrap_or_default())
+        } else if let Some(admin) = path.strip_prefix("/nebulafx/admin/") {
+            format!("admin.{}", admin.rsplit('/').next().unwrap_or_default())
+        } else {
+            format!("s3.{}", s3_api_name(req.method(), path, req.uri().query(), headers))
+        };
+
+        let input_bytes = headers
+            .get("x-amz-decoded-content-length")
+            .or_else(|| headers.get(http::header::CONTENT_LENGTH))
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or_default();
+
+        let client = headers
+            .get("x-forwarded-for")
+            .or_else(|| headers.get("x-real-ip"))
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.split(',').next().unwrap_or_default().trim().to_string())
+            .unwrap_or_default();
+
+        Self {
+            func_name,
+            start: Instant::now(),
+            ttfb: None,
+            req_info: TraceRequestInfo {
+                time: Utc::now(),
+                proto: format!("{:?}", req.version()),
+                method: req.method().to_string(),
+                path: Some(path.to_string()),
+                raw_query: req.uri().query().map(redact_query),
+                headers: Some(header_map(headers)),
+                body: None,
+                client,
+            },
+            resp_info: TraceResponseInfo::default(),
+            input_bytes,
+            output_bytes: 0,
+            // gRPC payloads are protobuf frames, keeping them would not help anyone reading a trace
+            capture_body: trace_type.contains(&TraceType::S3) && !returns_credentials(req.method(), path),
+            trace_type,
+            error: None,
+        }
+    }
+
+    fn set_response(&mut self, status: u16, headers: &HeaderMap) {
+        self.ttfb = Some(self.start.elapsed());
+        self.resp_info.status_code = Some(status as i32);
+        self.resp_info.headers = Some(header_map(headers));
+        if let Some(status) = headers.get("grpc-status").and_then(|v| v.to_str().ok()) {
+            if status != "0" {
+                self.error = Some(format!("grpc-status {status}"));
+            }
+        }
+    }
+
+    fn record_output(&mut self, data: &Bytes) {
+        self.output_bytes += data.len() as i64;
+
+        if !self.capture_body {
+            return;
+        }
+        let body = self.resp_info.body.get_or_insert_with(Vec::new);
+        let room = MAX_TRACE_BODY_SIZE.saturating_sub(body.len());
+        body.extend_from_slice(&data[..data.len().min(room)]);
+    }
+}
+
+impl Drop for PendingTrace {
+    fn drop(&mut self) {
+        let latency = self.start.elapsed();
+        self.resp_info.time = Utc::now();
+
+        publish_trace(TraceInfo {
+            trace_type: self.trace_type.mask(),
+            func_name: std::mem::take(&mut self.func_name),
+            time: self.req_info.time,
+            path: self.req_info.path.clone().unwrap_or_default(),
+            duration: latency,
+            bytes: Some(self.input_bytes + self.output_bytes),
+            error: self.error.take(),
+            http: Some(TraceHTTPStats {
+                req_info: std::mem::take(&mut self.req_info),
+                resp_info: std::mem::take(&mut self.resp_info),
+                call_stats: TraceCallStats {
+                    input_bytes: self.input_bytes,
+                    output_bytes: self.output_bytes,
+                    latency,
+                    time_to_first_byte: self.ttfb.unwrap_or(latency),
+                },
+            }),
+            ..Default::default()
+        });
+    }
+}
+
+fn is_grpc(headers: &HeaderMap) -> bool {
+    headers
+        .get(http::header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/grpc"))
+}
+
+fn header_map(headers: &HeaderMap) -> HashMap<String, String> {
+    headers
+        .iter()
+        .map(|(k, v)| {
+            let value = if REDACTED_HEADERS.contains(&k.as_str()) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(v.as_bytes()).into_owned()
+            };
+            (k.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+/// STS calls, form posts to `/`, and the admin calls that hand out credentials
+fn returns_credentials(method: &Method, path: &str) -> bool {
+    (method == Method::POST && path == "/") || CREDENTIAL_RESPONSE_PATHS.contains(&path)
+}
+
+fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| {
+            let key = pair.split('=').next().unwrap_or_default();
+            if REDACTED_QUERY_PARAMS.iter().any(|param| key.eq_ignore_ascii_case(param)) {
+                format!("{key}={REDACTED}")
+            } else {
+                pair.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Best-effort S3 operation name for a path-style request
+fn s3_api_name(method: &Method, path: &str, query: Option<&str>, headers: &HeaderMap) -> &'static str {
+    let has = |key: &str| {
+        query
+            .unwrap_or_default()
+            .split('&')
+            .any(|pair| pair.split('=').next() == Some(key))
+    };
+
+    let path = path.trim_start_matches('/');
+    let (bucket, object) = path.split_once('/').unwrap_or((path, ""));
+
+    if bucket.is_empty() {
+        return if method == Method::GET { "ListBuckets" } else { "Unknown" };
+    }
+
+    if !object.is_empty() {
+        return match *method {
+            Method::GET if has("uploadId") => "ListParts",
+            Method::GET if has("tagging") => "GetObjectTagging",
+            Method::GET if has("acl") => "GetObjectAcl",
+            Method::GET if has("retention") => "GetObjectRetention",
+            Method::GET if has("legal-hold") => "GetObjectLegalHold",
+            Method::GET if has("attributes") => "GetObjectAttributes",
+            Method::GET => "GetObject",
+            Method::HEAD => "HeadObject",
+            Method::PUT if has("uploadId") && headers.contains_key("x-amz-copy-source") => "UploadPartCopy",
+            Method::PUT if has("uploadId") => "UploadPart",
+            Method::PUT if has("tagging") => "PutObjectTagging",
+            Method::PUT if has("acl") => "PutObjectAcl",
+            Method::PUT if has("retention") => "PutObjectRetention",
+            Method::PUT if has("legal-hold") => "PutObjectLegalHold",
+            Method::PUT if headers.contains_key("x-amz-copy-source") => "CopyObject",
+            Method::PUT => "PutObject",
+            Method::POST if has("uploads") => "CreateMultipartUpload",
+            Method::POST if has("uploadId") => "CompleteMultipartUpload",
+            Method::POST if has("select") => "SelectObjectContent",
+            Method::POST if has("restore") => "RestoreObject",
+            Method::DELETE if has("uploadId") => "AbortMultipartUpload",
+            Method::DELETE if has("tagging") => "DeleteObjectTagging",
+            Method::DELETE => "DeleteObject",
+            _ => "Unknown",
+        };
+    }
+
+    match *method {
+        Method::GET if has("uploads") => "ListMultipartUploads",
+        Method::GET if has("versions") => "ListObjectVersions",
+        Method::GET if has("location") => "GetBucketLocation",
+        Method::GET if has("policy") => "GetBucketPolicy",
+        Method::GET if has("lifecycle") => "GetBucketLifecycle",
+        Method::GET if has("tagging") => "GetBucketTagging",
+        Method::GET if has("versioning") => "GetBucketVersioning",
+        Method::GET if has("encryption") => "GetBucketEncryption",
+        Method::GET if has("cors") => "GetBucketCors",
+        Method::GET if has("acl") => "GetBucketAcl",
+        Method::GET if has("notification") => "GetBucketNotification",
+        Method::GET if has("replication") => "GetBucketReplication",
+        Method::GET if has("object-lock") => "GetObjectLockConfiguration",
+        Method::GET if has("list-type") => "ListObjectsV2",
+        Method::GET => "ListObjects",
+        Method::HEAD => "HeadBucket",
+        Method::PUT if has("policy") => "PutBucketPolicy",
+        Method::PUT if has("lifecycle") => "PutBucketLifecycle",
+        Method::PUT if has("tagging") => "PutBucketTagging",
+        Method::PUT if has("versioning") => "PutBucketVersioning",
+        Method::PUT if has("encryption") => "PutBucketEncryption",
+        Method::PUT if has("cors") => "PutBucketCors",
+        Method::PUT if has("acl") => "PutBucketAcl",
+        Method::PUT if has("notification") => "PutBucketNotification",
+        Method::PUT if has("replication") => "PutBucketReplication",
+        Method::PUT if has("object-lock") => "PutObjectLockConfiguration",
+        Method::PUT => "CreateBucket",
+        Method::POST if has("delete") => "DeleteObjects",
+        Method::POST => "PostPolicyBucket",
+        Method::DELETE if has("policy") => "DeleteBucketPolicy",
+        Method::DELETE if has("lifecycle") => "DeleteBucketLifecycle",
+        Method::DELETE if has("tagging") => "DeleteBucketTagging",
+        Method::DELETE if has("encryption") => "DeleteBucketEncryption",
+        Method::DELETE if has("cors") => "DeleteBucketCors",
+        Method::DELETE if has("replication") => "DeleteBucketReplication",
+        Method::DELETE => "DeleteBucket",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_s3_api_name() {
+        let empty = HeaderMap::new();
+        assert_eq!(s3_api_name(&Method::GET, "/", None, &empty), "ListBuckets");
+        assert_eq!(
+            s3_api_name(&Method::GET, "/bucket", Some("list-type=2&prefix=a"), &empty),
+            "ListObjectsV2"
+        );
+        assert_eq!(s3_api_name(&Method::PUT, "/bucket/a/b.txt", None, &empty), "PutObject");
+        assert_eq!(
+            s3_api_name(&Method::PUT, "/bucket/key", Some("partNumber=1&uploadId=x"), &empty),
+            "UploadPart"
+        );
+        assert_eq!(
+            s3_api_name(&Method::POST, "/bucket/key", Some("uploads"), &empty),
+            "CreateMultipartUpload"
+        );
+        assert_eq!(s3_api_name(&Method::POST, "/bucket", Some("delete"), &empty), "DeleteObjects");
+
+        let mut copy = HeaderMap::new();
+        copy.insert("x-amz-copy-source", "src/key".parse().unwrap());
+        assert_eq!(s3_api_name(&Method::PUT, "/bucket/key", None, &copy), "CopyObject");
+    }
+
+    #[test]
+    fn test_header_map_redacts_credentials() {
+        let mut headers = HeaderMap::new();
+        headers.insert(http::header::AUTHORIZATION, "AWS4-HMAC-SHA256 Credential=secret".parse().unwrap());
+        headers.insert(http::header::CONTENT_TYPE, "text/plain".parse().unwrap());
+
+        let map = header_map(&headers);
+        assert_eq!(map["authorization"], REDACTED);
+        assert_eq!(map["content-type"], "text/plain");
+    }
+
+    #[test]
+    fn test_redact_query_redacts_presigned_credentials() {
+        let query = "X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Credential=AKIA%2F20240101%2Fus-east-1%2Fs3%2Faws4_request\
+                     &X-Amz-Security-Token=token&X-Amz-Signature=abcdef&versionId=1";
+        assert_eq!(
+            redact_query(query),
+            format!(
+                "X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Credential={REDACTED}&X-Amz-Security-Token={REDACTED}\
+                 &X-Amz-Signature={REDACTED}&versionId=1"
+            )
+        );
+        assert_eq!(redact_query("uploads"), "uploads");
+    }
+
+    #[test]
+    fn test_credential_responses_are_not_captured() {
+        assert!(returns_credentials(&Method::POST, "/"));
+        assert!(returns_credentials(&Method::PUT, "/nebulafx/admin/v3/add-service-accounts"));
+        assert!(returns_credentials(&Method::GET, "/nebulafx/admin/v3/info-service-account"));
+        assert!(!returns_credentials(&Method::GET, "/"));
+        assert!(!returns_credentials(&Method::GET, "/nebulafx/admin/v3/list-users"));
+        assert!(!returns_credentials(&Method::POST, "/bucket"));
+
+        let body = Bytes::from_static(b"<AssumeRoleResponse><SecretAccessKey>secret</SecretAccessKey></AssumeRoleResponse>");
+        let sts = HttpRequest::post("/").body(()).unwrap();
+        let mut pending = PendingTrace::new(TraceType::S3, &sts);
+        pending.record_output(&body);
+        assert!(pending.resp_info.body.is_none());
+        assert_eq!(pending.output_bytes, body.len() as i64);
+
+        let get = HttpRequest::get("/bucket/key").body(()).unwrap();
+        let mut pending = PendingTrace::new(TraceType::S3, &get);
+        pending.record_output(&body);
+        assert_eq!(pending.resp_info.body.as_deref(), Some(&body[..]));
+    }
+}
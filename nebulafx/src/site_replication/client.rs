@@ -0,0 +1,181 @@
+use super::{SR_PEER_PREFIX, SR_REQUEST_TIMEOUT};
+use bytes::Bytes;
+use http::{HeaderValue, Method};
+use nebulafx_crypto::encrypt_data;
+use nebulafx_ecstore::bucket::replication::SRMetricsSummary;
+use nebulafx_ecstore::error::{Error, Result};
+use nebulafx_madmin::site_replication::{SRBucketMeta, SRIAMItem, SRInfo, SRPeerJoinReq};
+use nebulafx_utils::crypto::hex_sha256;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::LazyLock;
+use url::Url;
+
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .timeout(SR_REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new())
+});
+
+/// Admin API client of a linked site, requests are signed with AWS signature v4
+#[derive(Debug, Clone)]
+pub struct PeerClient {
+    endpoint: Url,
+    access_key: String,
+    secret_key: String,
+}
+
+impl PeerClient {
+    pub fn new(endpoint: &str, access_key: &str, secret_key: &str) -> Result<Self> {
+        let endpoint = parse_endpoint(endpoint)?;
+        Ok(Self {
+            endpoint,
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+        })
+    }
+
+    pub fn endpoint(&self) -> &Url {
+        &self.endpoint
+    }
+
+    fn url(&self, path: &str, query: &[(&str, &str)]) -> Url {
+        let mut url = self.endpoint.clone();
+        url.set_path(&format!("{SR_PEER_PREFIX}{path}"));
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        url
+    }
+
+    async fn call(&self, method: Method, path: &str, query: &[(&str, &str)], body: Vec<u8>) -> Result<Bytes> {
+        let url = self.url(path, query);
+
+        let mut req = http::Request::builder()
+            .method(method.clone())
+            .uri(url.as_str())
+            .body(s3s::Body::empty())
+            .map_err(Error::other)?;
+        let content_sha256 = hex_sha256(&body, |s| HeaderValue::from_str(s)).map_err(Error::other)?;
+        req.headers_mut().insert("X-Amz-Content-Sha256", content_sha256);
+        let req = nebulafx_signer::sign_v4(req, body.len() as i64, &self.access_key, &self.secret_key, "", "us-east-1");
+
+        let resp = HTTP_CLIENT
+            .request(method, url.clone())
+            .headers(req.headers().clone())
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| Error::other(format!("site {} is unreachable: {e}", self.endpoint)))?;
+
+        let status = resp.status();
+        let data = resp.bytes().await.map_err(Error::other)?;
+        if !status.is_success() {
+            return Err(Error::other(format!(
+                "{} {} failed with {}: {}",
+                self.endpoint,
+                path,
+                status,
+                String::from_utf8_lossy(&data)
+            )));
+        }
+        Ok(data)
+    }
+
+    async fn put_json<T: Serialize>(&self, path: &str, query: &[(&str, &str)], body: &T) -> Result<()> {
+        let body = serde_json::to_vec(body).map_err(Error::other)?;
+        self.call(Method::PUT, path, query, body).await?;
+        Ok(())
+    }
+
+    /// Bodies carrying secret keys are encrypted with the secret key the request is signed with, like the
+    /// admin API does, so that they are not readable on the wire when the site is reached over plain http
+    async fn put_encrypted_json<T: Serialize>(&self, path: &str, body: &T) -> Result<()> {
+        let body = serde_json::to_vec(body).map_err(Error::other)?;
+        let body = encrypt_data(self.secret_key.as_bytes(), &body).map_err(Error::other)?;
+        self.call(Method::PUT, path, &[], body).await?;
+        Ok(())
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let data = self.call(Method::GET, path, &[], Vec::new()).await?;
+        serde_json::from_slice(&data).map_err(Error::other)
+    }
+
+    /// Buckets and IAM entities of the site, also tells its deployment id
+    pub async fn info(&self) -> Result<SRInfo> {
+        self.get_json("/info").await
+    }
+
+    pub async fn metrics(&self) -> Result<SRMetricsSummary> {
+        self.get_json("/metrics").await
+    }
+
+    pub async fn join(&self, req: &SRPeerJoinReq) -> Result<()> {
+        self.put_encrypted_json("/join", req).await
+    }
+
+    pub async fn bucket_ops(&self, bucket: &str, operation: &str, lock_enabled: bool) -> Result<()> {
+        let lock_enabled = if lock_enabled { "true" } else { "false" };
+        let query = [("bucket", bucket), ("operation", operation), ("lockEnabled", lock_enabled)];
+        self.call(Method::PUT, "/bucket-ops", &query, Vec::new()).await?;
+        Ok(())
+    }
+
+    pub async fn bucket_meta(&self, meta: &SRBucketMeta) -> Result<()> {
+        self.put_json("/bucket-meta", &[], meta).await
+    }
+
+    pub async fn iam_item(&self, item: &SRIAMItem) -> Result<()> {
+        self.put_encrypted_json("/iam-item", item).await
+    }
+}
+
+/// Sites are given as `http[s]://host[:port]`, a bare `host:port` means plain http
+pub fn parse_endpoint(endpoint: &str) -> Result<Url> {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    let endpoint = if endpoint.contains("://") {
+        endpoint.to_string()
+    } else {
+        format!("http://{endpoint}")
+    };
+
+    let url = Url::parse(&endpoint).map_err(|e| Error::other(format!("invalid site endpoint {endpoint}: {e}")))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(Error::other(format!("invalid site endpoint {endpoint}")));
+    }
+    if url.path() != "/" || url.query().is_some() {
+        return Err(Error::other(format!("site endpoint {endpoint} must not have a path")));
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_endpoint() {
+        let url = parse_endpoint("http://127.0.0.1:9001/").unwrap();
+        assert_eq!(url.as_str(), "http://127.0.0.1:9001/");
+
+        let url = parse_endpoint("site-b.example.com:9000").unwrap();
+        assert_eq!(url.scheme(), "http");
+        assert_eq!(url.port(), Some(9000));
+
+        assert!(parse_endpoint("ftp://site-b").is_err());
+        assert!(parse_endpoint("https://site-b/bucket").is_err());
+        assert!(parse_endpoint("").is_err());
+    }
+
+    #[test]
+    fn test_peer_url() {
+        let client = PeerClient::new("https://site-b:9000", "ak", "sk").unwrap();
+        let url = client.url("/bucket-ops", &[("bucket", "photos"), ("operation", "delete-bucket")]);
+        assert_eq!(
+            url.as_str(),
+            "https://site-b:9000/nebulafx/admin/v3/site-replication/peer/bucket-ops?bucket=photos&operation=delete-bucket"
+        );
+    }
+}
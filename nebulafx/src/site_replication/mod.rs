@@ -0,0 +1,28 @@
+
+
+//! Site replication links several deployments so that they share buckets, bucket configuration
+//! and IAM entities. Each site keeps the list of linked sites in its meta bucket, bucket and IAM
+//! changes made on one site are pushed to the others through the peer admin API, and objects are
+//! replicated with bucket replication rules site replication maintains on every bucket. The change
+//! hooks only queue the pushes, see `queue`.
+
+mod client;
+mod queue;
+mod state;
+mod sys;
+
+use std::time::Duration;
+
+pub use state::find_mismatches;
+pub use sys::get;
+
+/// Access key of the service account the sites use to talk to each other
+pub const SITE_REPLICATOR_SVC_ACC: &str = "siteReplicatorSvcAcc";
+
+/// Object in the meta bucket holding the site replication state
+pub const SR_STATE_FILE: &str = "config/site-replication/state.json";
+
+/// Admin API prefix of the calls sites make to each other
+pub const SR_PEER_PREFIX: &str = "/nebulafx/admin/v3/site-replication/peer";
+
+const SR_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
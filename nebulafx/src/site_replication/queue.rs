@@ -0,0 +1,182 @@
+//! Changes made on this site are pushed to the others from a queue per site, off the request path: the
+//! client gets its answer once the change is committed locally, a slow or unreachable site only holds up
+//! its own queue, and each site receives the changes in the order they were made.
+
+use super::client::PeerClient;
+use nebulafx_ecstore::error::{Error, Result};
+use nebulafx_madmin::site_replication::{PeerInfo, SRBucketMeta, SRIAMItem};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
+
+/// A push is given up on after this many attempts, each bounded by the timeout
+const SR_PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+const SR_PUSH_ATTEMPTS: usize = 3;
+const SR_PUSH_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// A change to apply on another site
+#[derive(Debug, Clone)]
+pub enum PeerChange {
+    BucketOp {
+        bucket: String,
+        operation: String,
+        lock_enabled: bool,
+    },
+    BucketMeta(SRBucketMeta),
+    IamItem(SRIAMItem),
+}
+
+impl PeerChange {
+    async fn send(&self, client: &PeerClient) -> Result<()> {
+        match self {
+            PeerChange::BucketOp {
+                bucket,
+                operation,
+                lock_enabled,
+            } => client.bucket_ops(bucket, operation, *lock_enabled).await,
+            PeerChange::BucketMeta(meta) => client.bucket_meta(meta).await,
+            PeerChange::IamItem(item) => client.iam_item(item).await,
+        }
+    }
+}
+
+impl fmt::Display for PeerChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerChange::BucketOp { bucket, operation, .. } => write!(f, "{operation} of bucket {bucket}"),
+            PeerChange::BucketMeta(meta) => write!(f, "{} of bucket {}", meta.config_file, meta.bucket),
+            PeerChange::IamItem(_) => write!(f, "IAM change"),
+        }
+    }
+}
+
+struct Push {
+    peer: PeerInfo,
+    client: PeerClient,
+    change: PeerChange,
+    done: oneshot::Sender<Result<()>>,
+}
+
+#[derive(Debug)]
+pub struct PeerQueues {
+    timeout: Duration,
+    attempts: usize,
+    retry_delay: Duration,
+    senders: Mutex<HashMap<String, mpsc::UnboundedSender<Push>>>,
+}
+
+impl Default for PeerQueues {
+    fn default() -> Self {
+        Self::new(SR_PUSH_TIMEOUT, SR_PUSH_ATTEMPTS, SR_PUSH_RETRY_DELAY)
+    }
+}
+
+impl PeerQueues {
+    pub fn new(timeout: Duration, attempts: usize, retry_delay: Duration) -> Self {
+        Self {
+            timeout,
+            attempts: attempts.max(1),
+            retry_delay,
+            senders: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Queues `change` for the site of `client` and returns at once, the receiver tells whether it was
+    /// applied there or given up on
+    pub fn push(&self, peer: &PeerInfo, client: &PeerClient, change: PeerChange) -> oneshot::Receiver<Result<()>> {
+        let (done, applied) = oneshot::channel();
+        let mut push = Push {
+            peer: peer.clone(),
+            client: client.clone(),
+            change,
+            done,
+        };
+
+        let mut senders = self.senders.lock().unwrap();
+        if let Some(sender) = senders.get(&peer.deployment_id) {
+            match sender.send(push) {
+                Ok(()) => return applied,
+                // The worker is gone with its runtime, a new one takes over
+                Err(err) => push = err.0,
+            }
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(run_queue(receiver, self.timeout, self.attempts, self.retry_delay));
+        let _ = sender.send(push);
+        senders.insert(peer.deployment_id.clone(), sender);
+        applied
+    }
+}
+
+async fn run_queue(mut receiver: mpsc::UnboundedReceiver<Push>, timeout: Duration, attempts: usize, retry_delay: Duration) {
+    while let Some(push) = receiver.recv().await {
+        let mut res = Err(Error::other("not sent"));
+        for attempt in 0..attempts {
+            if attempt > 0 {
+                tokio::time::sleep(retry_delay).await;
+            }
+            res = match tokio::time::timeout(timeout, push.change.send(&push.client)).await {
+                Ok(res) => res,
+                Err(_) => Err(Error::other(format!(
+                    "site {} did not answer within {:?}",
+                    push.client.endpoint(),
+                    timeout
+                ))),
+            };
+            if res.is_ok() {
+                break;
+            }
+        }
+        if let Err(err) = &res {
+            warn!("replicate {} to site {} failed: {}", push.change, push.peer.name, err);
+        }
+        let _ = push.done.send(res);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_unreachable_site_does_not_block_the_caller() {
+        // Accepts connections and never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut conns = Vec::new();
+            while let Ok((conn, _)) = listener.accept().await {
+                conns.push(conn);
+            }
+        });
+
+        let peer = PeerInfo {
+            name: "site-b".to_string(),
+            endpoint: endpoint.clone(),
+            deployment_id: "b".to_string(),
+        };
+        let client = PeerClient::new(&endpoint, "ak", "sk").unwrap();
+        let queues = PeerQueues::new(Duration::from_millis(100), 2, Duration::from_millis(10));
+        let change = |operation: &str| PeerChange::BucketOp {
+            bucket: "photos".to_string(),
+            operation: operation.to_string(),
+            lock_enabled: false,
+        };
+
+        let start = Instant::now();
+        let created = queues.push(&peer, &client, change("make-with-versioning"));
+        let deleted = queues.push(&peer, &client, change("delete-bucket"));
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        let err = created.await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("did not answer"));
+        assert!(deleted.await.unwrap().is_err());
+        // Both changes were tried twice, one after the other
+        assert!(start.elapsed() >= Duration::from_millis(400));
+    }
+}
@@ -0,0 +1,132 @@
+
+
+use nebulafx_madmin::site_replication::{PeerInfo, SRInfo, SRMismatches, SiteReplicationInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use time::OffsetDateTime;
+
+/// Site replication state of a deployment, every node of the deployment loads it from the
+/// meta bucket
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SrState {
+    /// Name of this site
+    pub name: String,
+    /// Linked sites by deployment id, this site included
+    pub peers: BTreeMap<String, PeerInfo>,
+    /// Service account shared by all sites, used for peer calls and bucket replication
+    #[serde(rename = "serviceAccountAccessKey")]
+    pub service_account_access_key: String,
+    #[serde(rename = "updatedAt", with = "time::serde::rfc3339::option", default)]
+    pub updated_at: Option<OffsetDateTime>,
+}
+
+impl SrState {
+    pub fn is_enabled(&self) -> bool {
+        !self.peers.is_empty()
+    }
+
+    /// Every linked site but the one with `deployment_id`
+    pub fn remote_peers<'a>(&'a self, deployment_id: &'a str) -> impl Iterator<Item = &'a PeerInfo> {
+        self.peers.values().filter(move |peer| peer.deployment_id != deployment_id)
+    }
+
+    pub fn peer_by_name(&self, name: &str) -> Option<&PeerInfo> {
+        self.peers.values().find(|peer| peer.name == name)
+    }
+
+    pub fn info(&self) -> SiteReplicationInfo {
+        SiteReplicationInfo {
+            enabled: self.is_enabled(),
+            name: self.name.clone(),
+            sites: self.peers.values().cloned().collect(),
+            service_account_access_key: self.service_account_access_key.clone(),
+        }
+    }
+}
+
+/// Entities some sites have and others lack, each mapped to the deployment ids of the sites lacking it
+pub fn find_mismatches(infos: &[SRInfo]) -> SRMismatches {
+    SRMismatches {
+        buckets: missing_from(infos, |info| &info.buckets),
+        policies: missing_from(infos, |info| &info.policies),
+        users: missing_from(infos, |info| &info.users),
+        groups: missing_from(infos, |info| &info.groups),
+        service_accounts: missing_from(infos, |info| &info.service_accounts),
+    }
+}
+
+fn missing_from(infos: &[SRInfo], entities: impl Fn(&SRInfo) -> &Vec<String>) -> BTreeMap<String, Vec<String>> {
+    let all: BTreeSet<&String> = infos.iter().flat_map(&entities).collect();
+
+    let mut missing = BTreeMap::new();
+    for name in all {
+        let lacking: Vec<String> = infos
+            .iter()
+            .filter(|info| !entities(info).contains(name))
+            .map(|info| info.deployment_id.clone())
+            .collect();
+        if !lacking.is_empty() {
+            missing.insert(name.clone(), lacking);
+        }
+    }
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(name: &str, deployment_id: &str) -> PeerInfo {
+        PeerInfo {
+            name: name.to_string(),
+            endpoint: format!("http://127.0.0.1:900{}", deployment_id.len()),
+            deployment_id: deployment_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sr_state() {
+        let mut state = SrState::default();
+        assert!(!state.is_enabled());
+        assert!(!state.info().enabled);
+
+        state.name = "site-a".to_string();
+        state.peers.insert("a".to_string(), peer("site-a", "a"));
+        state.peers.insert("bb".to_string(), peer("site-b", "bb"));
+        state.service_account_access_key = "siteReplicatorSvcAcc".to_string();
+
+        let remote: Vec<&str> = state.remote_peers("a").map(|p| p.name.as_str()).collect();
+        assert_eq!(remote, vec!["site-b"]);
+        assert_eq!(state.peer_by_name("site-b").unwrap().deployment_id, "bb");
+        assert!(state.peer_by_name("site-c").is_none());
+
+        let info = state.info();
+        assert!(info.enabled);
+        assert_eq!(info.sites.len(), 2);
+
+        let json = serde_json::to_vec(&state).unwrap();
+        let decoded: SrState = serde_json::from_slice(&json).unwrap();
+        assert_eq!(decoded, state);
+    }
+
+    #[test]
+    fn test_find_mismatches() {
+        let site_a = SRInfo {
+            deployment_id: "a".to_string(),
+            buckets: vec!["photos".to_string(), "logs".to_string()],
+            users: vec!["alice".to_string()],
+            ..Default::default()
+        };
+        let site_b = SRInfo {
+            deployment_id: "b".to_string(),
+            buckets: vec!["photos".to_string()],
+            users: vec!["alice".to_string(), "bob".to_string()],
+            ..Default::default()
+        };
+
+        let mismatches = find_mismatches(&[site_a, site_b]);
+        assert_eq!(mismatches.buckets, BTreeMap::from([("logs".to_string(), vec!["b".to_string()])]));
+        assert_eq!(mismatches.users, BTreeMap::from([("bob".to_string(), vec!["a".to_string()])]));
+        assert!(mismatches.policies.is_empty());
+    }
+}
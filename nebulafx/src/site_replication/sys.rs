@@ -0,0 +1,1160 @@
+
+
+use super::client::{PeerClient, parse_endpoint};
+use super::queue::{PeerChange, PeerQueues};
+use super::state::SrState;
+use super::{SITE_REPLICATOR_SVC_ACC, SR_STATE_FILE};
+use nebulafx_ecstore::bucket::bucket_target_sys::BucketTargetSys;
+use nebulafx_ecstore::bucket::metadata::{
+    BUCKET_LIFECYCLE_CONFIG, BUCKET_POLICY_CONFIG, BUCKET_REPLICATION_CONFIG, BUCKET_SSECONFIG, BUCKET_TAGGING_CONFIG,
+    BUCKET_TARGETS_FILE, BUCKET_VERSIONING_CONFIG, BucketMetadata, OBJECT_LOCK_CONFIG,
+};
+use nebulafx_ecstore::bucket::metadata_sys;
+use nebulafx_ecstore::bucket::target::{BucketTarget, BucketTargetType, Credentials as TargetCredentials};
+use nebulafx_ecstore::bucket::utils::{deserialize, serialize};
+use nebulafx_ecstore::config::com::{delete_config, read_config, save_config};
+use nebulafx_ecstore::error::{Error, Result};
+use nebulafx_ecstore::global::{get_global_action_cred, get_global_deployment_id};
+use nebulafx_ecstore::new_object_layer_fn;
+use nebulafx_ecstore::notification_sys::get_global_notification_sys;
+use nebulafx_ecstore::store::ECStore;
+use nebulafx_ecstore::store_api::{BucketOptions, DeleteBucketOptions, MakeBucketOptions, StorageAPI};
+use nebulafx_iamx::UserType;
+use nebulafx_iamx::error::is_err_no_such_service_account;
+use nebulafx_iamx::sys::{IamSys, NewServiceAccountOpts, UpdateServiceAccountOpts};
+use nebulafx_madmin::site_replication::{
+    PeerInfo, PeerSite, ReplicateAddStatus, ReplicateRemoveStatus, SR_BUCKET_OP_CONFIGURE_REPLICATION,
+    SR_BUCKET_OP_DELETE_BUCKET, SR_BUCKET_OP_MAKE_WITH_VERSIONING, SRBucketMeta, SRIAMItem, SRInfo, SRPeerJoinReq, SRRemoveReq,
+    SiteReplicationInfo,
+};
+use nebulafx_madmin::{AccountStatus, AddOrUpdateUserReq};
+use nebulafx_policy::auth::UserIdentity;
+use nebulafx_policy::policy::Policy;
+use nebulafx_policy::policy::default::DEFAULT_POLICIES;
+use s3s::dto::{BucketVersioningStatus, ReplicationConfiguration, VersioningConfiguration};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::sync::{Arc, LazyLock};
+use time::{Duration, OffsetDateTime};
+use tokio::sync::{RwLock, oneshot};
+use tracing::{info, warn};
+
+static GLOBAL_SITE_REPLICATION_SYS: LazyLock<SiteReplicationSys> = LazyLock::new(SiteReplicationSys::default);
+
+pub fn get() -> &'static SiteReplicationSys {
+    &GLOBAL_SITE_REPLICATION_SYS
+}
+
+/// Replication rules site replication adds to a bucket are named after the peer they replicate to
+const SR_RULE_ID_PREFIX: &str = "site-repl-";
+
+/// Bucket configuration files kept in sync across sites
+const SR_BUCKET_META_FILES: [&str; 6] = [
+    BUCKET_POLICY_CONFIG,
+    BUCKET_TAGGING_CONFIG,
+    BUCKET_LIFECYCLE_CONFIG,
+    BUCKET_SSECONFIG,
+    OBJECT_LOCK_CONFIG,
+    BUCKET_VERSIONING_CONFIG,
+];
+
+#[derive(Debug, Default)]
+pub struct SiteReplicationSys {
+    state: RwLock<SrState>,
+    queues: PeerQueues,
+}
+
+impl SiteReplicationSys {
+    pub async fn init(&self, store: Arc<ECStore>) -> Result<()> {
+        let state = match read_config(store, SR_STATE_FILE).await {
+            Ok(data) => serde_json::from_slice::<SrState>(&data).map_err(Error::other)?,
+            Err(Error::ConfigNotFound) => SrState::default(),
+            Err(err) => return Err(err),
+        };
+        if state.is_enabled() {
+            info!("site replication enabled on site {} with {} sites", state.name, state.peers.len());
+        }
+        *self.state.write().await = state;
+        Ok(())
+    }
+
+    pub async fn is_enabled(&self) -> bool {
+        self.state.read().await.is_enabled()
+    }
+
+    pub async fn info(&self) -> SiteReplicationInfo {
+        self.state.read().await.info()
+    }
+
+    async fn save(&self, state: SrState) -> Result<()> {
+        let store = object_layer()?;
+        if state.is_enabled() {
+            save_config(store, SR_STATE_FILE, serde_json::to_vec(&state).map_err(Error::other)?).await?;
+        } else {
+            match delete_config(store, SR_STATE_FILE).await {
+                Ok(()) | Err(Error::ConfigNotFound) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        *self.state.write().await = state;
+
+        if let Some(notification_sys) = get_global_notification_sys() {
+            for peer_err in notification_sys.reload_site_replication_config().await {
+                if let Some(err) = peer_err.err {
+                    warn!("reload site replication config on {} failed: {}", peer_err.host, err);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Link `sites`, this deployment must be one of them
+    pub async fn add(&self, sites: Vec<PeerSite>) -> Result<ReplicateAddStatus> {
+        if sites.len() < 2 {
+            return Err(Error::other("at least two sites are required"));
+        }
+        let mut names: Vec<&str> = sites.iter().map(|site| site.name.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        if names.len() != sites.len() || names.iter().any(|name| name.is_empty()) {
+            return Err(Error::other("sites must have unique, non empty names"));
+        }
+
+        let local_id = local_deployment_id()?;
+        let current = self.state.read().await.clone();
+
+        let mut peers = BTreeMap::new();
+        let mut clients = Vec::with_capacity(sites.len());
+        for site in &sites {
+            let client = PeerClient::new(&site.endpoint, &site.access_key, &site.secret_key)?;
+            let info = client.info().await?;
+            if info.deployment_id.is_empty() {
+                return Err(Error::other(format!("site {} did not report its deployment id", site.name)));
+            }
+            if !current.is_enabled() && info.deployment_id != local_id && !info.buckets.is_empty() {
+                return Err(Error::other(format!("site {} must not have any buckets to join", site.name)));
+            }
+            let peer = PeerInfo {
+                name: site.name.clone(),
+                endpoint: client.endpoint().as_str().trim_end_matches('/').to_string(),
+                deployment_id: info.deployment_id.clone(),
+            };
+            if peers.insert(info.deployment_id.clone(), peer).is_some() {
+                return Err(Error::other(format!("site {} is listed twice", site.name)));
+            }
+            clients.push((info.deployment_id, client));
+        }
+        let Some(local) = peers.get(&local_id).cloned() else {
+            return Err(Error::other("the local site must be one of the sites to link"));
+        };
+        // Sites linked before stay linked
+        for (deployment_id, peer) in &current.peers {
+            peers.entry(deployment_id.clone()).or_insert_with(|| peer.clone());
+        }
+
+        let iam = iam()?;
+        let access_key = SITE_REPLICATOR_SVC_ACC.to_string();
+        let secret_key = match iam.get_user(&access_key).await {
+            Some(identity) => identity.credentials.secret_key,
+            None => nebulafx_utils::string::gen_secret_key(40).map_err(Error::other)?,
+        };
+        ensure_service_account(&iam, &access_key, &secret_key).await?;
+
+        let join = SRPeerJoinReq {
+            svc_acct_access_key: access_key.clone(),
+            svc_acct_secret_key: secret_key,
+            peers: peers.clone(),
+            updated_at: Some(OffsetDateTime::now_utc()),
+        };
+        for (deployment_id, client) in clients.iter().filter(|(id, _)| *id != local_id) {
+            client
+                .join(&join)
+                .await
+                .map_err(|e| Error::other(format!("site {deployment_id} could not join: {e}")))?;
+        }
+        if current.is_enabled() {
+            for (peer, client) in peer_clients(&current, &local_id).await? {
+                if clients.iter().all(|(id, _)| *id != peer.deployment_id) {
+                    client
+                        .join(&join)
+                        .await
+                        .map_err(|e| Error::other(format!("site {} could not be updated: {e}", peer.name)))?;
+                }
+            }
+        }
+
+        let state = SrState {
+            name: local.name,
+            peers,
+            service_account_access_key: access_key,
+            updated_at: join.updated_at,
+        };
+        self.save(state.clone()).await?;
+        info!("site replication enabled with {} sites", state.peers.len());
+
+        let mut status = ReplicateAddStatus {
+            success: true,
+            status: "Requested sites were configured for replication successfully.".to_string(),
+            ..Default::default()
+        };
+        if let Err(err) = self.sync_to_peers(&state).await {
+            warn!("site replication initial sync failed: {}", err);
+            status.initial_sync_error_message = err.to_string();
+        }
+        Ok(status)
+    }
+
+    /// Sent by the site running `add` or `remove`, replaces the set of linked sites
+    pub async fn join(&self, req: SRPeerJoinReq) -> Result<()> {
+        let local_id = local_deployment_id()?;
+        let current = self.state.read().await.clone();
+
+        let Some(local) = req.peers.get(&local_id).cloned() else {
+            return self.disable(&current).await;
+        };
+        ensure_service_account(&iam()?, &req.svc_acct_access_key, &req.svc_acct_secret_key).await?;
+
+        let removed: Vec<PeerInfo> = current
+            .peers
+            .values()
+            .filter(|peer| !req.peers.contains_key(&peer.deployment_id))
+            .cloned()
+            .collect();
+        let state = SrState {
+            name: local.name,
+            peers: req.peers,
+            service_account_access_key: req.svc_acct_access_key,
+            updated_at: req.updated_at,
+        };
+        self.save(state.clone()).await?;
+
+        if !removed.is_empty() {
+            for bucket in list_buckets().await? {
+                if let Err(err) = remove_bucket_replication(&bucket, &removed).await {
+                    warn!("remove site replication of bucket {} failed: {}", bucket, err);
+                }
+            }
+        }
+
+        // The other sites sync theirs, this one pushes its own buckets and IAM entities
+        tokio::spawn(async move {
+            if let Err(err) = get().sync_to_peers(&state).await {
+                warn!("site replication sync to peers failed: {}", err);
+            }
+        });
+        Ok(())
+    }
+
+    /// Unlink sites, the remaining ones keep replicating to each other
+    pub async fn remove(&self, req: SRRemoveReq) -> Result<ReplicateRemoveStatus> {
+        let local_id = local_deployment_id()?;
+        let current = self.state.read().await.clone();
+        if !current.is_enabled() {
+            return Err(Error::other("site replication is not enabled"));
+        }
+
+        let mut peers = current.peers.clone();
+        if req.remove_all {
+            peers.clear();
+        } else {
+            for name in &req.site_names {
+                let Some(peer) = current.peer_by_name(name) else {
+                    return Err(Error::other(format!("site {name} is not linked")));
+                };
+                peers.remove(&peer.deployment_id);
+            }
+        }
+        // A single site has nobody to replicate to
+        if peers.len() < 2 {
+            peers.clear();
+        }
+
+        let join = SRPeerJoinReq {
+            svc_acct_access_key: current.service_account_access_key.clone(),
+            svc_acct_secret_key: service_account_secret(&current).await?,
+            peers: peers.clone(),
+            updated_at: Some(OffsetDateTime::now_utc()),
+        };
+        let mut errs = Vec::new();
+        for (peer, client) in peer_clients(&current, &local_id).await? {
+            if let Err(err) = client.join(&join).await {
+                errs.push(format!("{}: {}", peer.name, err));
+            }
+        }
+
+        if peers.contains_key(&local_id) {
+            let removed: Vec<PeerInfo> = current
+                .peers
+                .values()
+                .filter(|peer| !peers.contains_key(&peer.deployment_id))
+                .cloned()
+                .collect();
+            self.save(SrState {
+                peers,
+                updated_at: join.updated_at,
+                ..current
+            })
+            .await?;
+            for bucket in list_buckets().await? {
+                if let Err(err) = remove_bucket_replication(&bucket, &removed).await {
+                    warn!("remove site replication of bucket {} failed: {}", bucket, err);
+                }
+            }
+        } else {
+            self.disable(&current).await?;
+        }
+
+        Ok(ReplicateRemoveStatus {
+            status: "Requested site(s) were removed from cluster replication successfully.".to_string(),
+            err_detail: errs.join("; "),
+        })
+    }
+
+    async fn disable(&self, current: &SrState) -> Result<()> {
+        if !current.is_enabled() {
+            return Ok(());
+        }
+        let removed: Vec<PeerInfo> = current.peers.values().cloned().collect();
+        for bucket in list_buckets().await? {
+            if let Err(err) = remove_bucket_replication(&bucket, &removed).await {
+                warn!("remove site replication of bucket {} failed: {}", bucket, err);
+            }
+        }
+        self.save(SrState::default()).await?;
+        info!("site replication disabled");
+        Ok(())
+    }
+
+    /// Push every bucket, bucket configuration and IAM entity of this site to the other sites
+    ///
+    /// Every site is attempted, a site that fails is sent nothing more and its first error is reported
+    async fn sync_to_peers(&self, state: &SrState) -> Result<()> {
+        let local_id = local_deployment_id()?;
+        let clients = peer_clients(state, &local_id).await?;
+        let store = object_layer()?;
+        let mut failed = BTreeMap::new();
+        let mut local_errs = Vec::new();
+
+        for bucket in store.list_bucket(&BucketOptions::default()).await? {
+            for (peer, client) in live_peers(&clients, &failed) {
+                let res = client
+                    .bucket_ops(&bucket.name, SR_BUCKET_OP_MAKE_WITH_VERSIONING, bucket.object_locking)
+                    .await;
+                note_peer_err(&mut failed, peer, res);
+            }
+
+            let configured = match enable_versioning(&bucket.name).await {
+                Ok(()) => configure_bucket_replication(state, &local_id, &bucket.name).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = configured {
+                warn!("configure site replication of bucket {} failed: {}", bucket.name, err);
+                local_errs.push(format!("bucket {}: {}", bucket.name, err));
+                continue;
+            }
+            for (peer, client) in live_peers(&clients, &failed) {
+                let res = client
+                    .bucket_ops(&bucket.name, SR_BUCKET_OP_CONFIGURE_REPLICATION, false)
+                    .await;
+                note_peer_err(&mut failed, peer, res);
+            }
+
+            let meta = metadata_sys::get(&bucket.name).await?;
+            for file in SR_BUCKET_META_FILES {
+                let Some(data) = bucket_meta_data(&meta, file) else {
+                    continue;
+                };
+                let meta = SRBucketMeta {
+                    bucket: bucket.name.clone(),
+                    config_file: file.to_string(),
+                    data: Some(String::from_utf8_lossy(data).into_owned()),
+                };
+                for (peer, client) in live_peers(&clients, &failed) {
+                    let res = client.bucket_meta(&meta).await;
+                    note_peer_err(&mut failed, peer, res);
+                }
+            }
+        }
+
+        for item in iam_items().await? {
+            for (peer, client) in live_peers(&clients, &failed) {
+                let res = client.iam_item(&item).await;
+                note_peer_err(&mut failed, peer, res);
+            }
+        }
+
+        let errs: Vec<String> = local_errs
+            .into_iter()
+            .chain(failed.into_iter().map(|(name, err)| format!("{name}: {err}")))
+            .collect();
+        if !errs.is_empty() {
+            return Err(Error::other(errs.join("; ")));
+        }
+        Ok(())
+    }
+
+    /// Buckets and IAM entities of this site
+    pub async fn local_info(&self) -> Result<SRInfo> {
+        let state = self.state.read().await.clone();
+        let iam = iam()?;
+
+        let mut policies: Vec<String> = iam.list_polices("").await.map_err(Error::other)?.into_keys().collect();
+        let mut users: Vec<String> = iam.list_users().await.map_err(Error::other)?.into_keys().collect();
+        let mut groups = iam.list_groups().await.map_err(Error::other)?;
+        let mut svc_accounts = HashMap::new();
+        iam.load_users(UserType::Svc, &mut svc_accounts).await.map_err(Error::other)?;
+        let mut service_accounts: Vec<String> = svc_accounts.into_keys().filter(|ak| ak != SITE_REPLICATOR_SVC_ACC).collect();
+        policies.sort();
+        users.sort();
+        groups.sort();
+        service_accounts.sort();
+
+        Ok(SRInfo {
+            name: state.name,
+            deployment_id: local_deployment_id()?,
+            buckets: list_buckets().await?,
+            policies,
+            users,
+            groups,
+            service_accounts,
+        })
+    }
+
+    /// Clients of the other sites, authenticated with the shared service account
+    pub async fn peer_clients(&self) -> Result<Vec<(PeerInfo, PeerClient)>> {
+        let state = self.state.read().await.clone();
+        if !state.is_enabled() {
+            return Err(Error::other("site replication is not enabled"));
+        }
+        peer_clients(&state, &local_deployment_id()?).await
+    }
+
+    /// Clients of the other sites for the change hooks, `None` when site replication is disabled
+    async fn remote_clients(&self) -> Option<Vec<(PeerInfo, PeerClient)>> {
+        if !self.is_enabled().await {
+            return None;
+        }
+        match self.peer_clients().await {
+            Ok(clients) => Some(clients),
+            Err(err) => {
+                warn!("site replication peers unavailable: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Queues `change` for every site of `clients`, the receivers tell when each applied it
+    fn push_to_peers(&self, clients: &[(PeerInfo, PeerClient)], change: PeerChange) -> Vec<oneshot::Receiver<Result<()>>> {
+        clients
+            .iter()
+            .map(|(peer, client)| self.queues.push(peer, client, change.clone()))
+            .collect()
+    }
+
+    /// Create the bucket on the other sites and replicate it everywhere
+    pub async fn make_bucket_hook(&self, bucket: &str, lock_enabled: bool) {
+        let Some(clients) = self.remote_clients().await else {
+            return;
+        };
+        let created = self.push_to_peers(
+            &clients,
+            PeerChange::BucketOp {
+                bucket: bucket.to_string(),
+                operation: SR_BUCKET_OP_MAKE_WITH_VERSIONING.to_string(),
+                lock_enabled,
+            },
+        );
+
+        // The replication rules point at the buckets of the other sites, so they are set once those exist
+        let bucket = bucket.to_string();
+        tokio::spawn(async move {
+            for created in created {
+                let _ = created.await;
+            }
+
+            let sys = get();
+            let state = sys.state.read().await.clone();
+            let result = match local_deployment_id() {
+                Ok(local_id) => configure_bucket_replication(&state, &local_id, &bucket).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                warn!("configure site replication of bucket {} failed: {}", bucket, err);
+            }
+            sys.push_to_peers(
+                &clients,
+                PeerChange::BucketOp {
+                    bucket,
+                    operation: SR_BUCKET_OP_CONFIGURE_REPLICATION.to_string(),
+                    lock_enabled: false,
+                },
+            );
+        });
+    }
+
+    pub async fn delete_bucket_hook(&self, bucket: &str) {
+        let Some(clients) = self.remote_clients().await else {
+            return;
+        };
+        self.push_to_peers(
+            &clients,
+            PeerChange::BucketOp {
+                bucket: bucket.to_string(),
+                operation: SR_BUCKET_OP_DELETE_BUCKET.to_string(),
+                lock_enabled: false,
+            },
+        );
+    }
+
+    /// A bucket configuration file was updated, `data` is `None` when it was deleted
+    pub async fn bucket_meta_hook(&self, bucket: &str, config_file: &str, data: Option<Vec<u8>>) {
+        if !SR_BUCKET_META_FILES.contains(&config_file) {
+            return;
+        }
+        let Some(clients) = self.remote_clients().await else {
+            return;
+        };
+        let meta = SRBucketMeta {
+            bucket: bucket.to_string(),
+            config_file: config_file.to_string(),
+            data: data.map(|data| String::from_utf8_lossy(&data).into_owned()),
+        };
+        self.push_to_peers(&clients, PeerChange::BucketMeta(meta));
+    }
+
+    pub async fn iam_hook(&self, item: SRIAMItem) {
+        let Some(clients) = self.remote_clients().await else {
+            return;
+        };
+        self.push_to_peers(&clients, PeerChange::IamItem(item));
+    }
+
+    /// Replicate a user as it is now stored, or its removal
+    pub async fn user_hook(&self, access_key: &str) {
+        if !self.is_enabled().await {
+            return;
+        }
+        match user_item(access_key).await {
+            Ok(item) => self.iam_hook(item).await,
+            Err(err) => warn!("replicate user {} failed: {}", access_key, err),
+        }
+    }
+
+    /// Replicate a service account as it is now stored, or its removal
+    pub async fn service_account_hook(&self, access_key: &str) {
+        if !self.is_enabled().await || access_key == SITE_REPLICATOR_SVC_ACC {
+            return;
+        }
+        match service_account_item(access_key).await {
+            Ok(Some(item)) => self.iam_hook(item).await,
+            Ok(None) => {
+                self.iam_hook(SRIAMItem::ServiceAccountDelete {
+                    access_key: access_key.to_string(),
+                })
+                .await
+            }
+            Err(err) => warn!("replicate service account {} failed: {}", access_key, err),
+        }
+    }
+
+    /// Apply a bucket operation requested by another site
+    pub async fn peer_bucket_op(&self, bucket: &str, operation: &str, lock_enabled: bool) -> Result<()> {
+        let store = object_layer()?;
+        match operation {
+            SR_BUCKET_OP_MAKE_WITH_VERSIONING => {
+                let opts = MakeBucketOptions {
+                    lock_enabled,
+                    versioning_enabled: true,
+                    ..Default::default()
+                };
+                match store.make_bucket(bucket, &opts).await {
+                    Ok(()) | Err(Error::BucketExists(_)) => {}
+                    Err(err) => return Err(err),
+                }
+                enable_versioning(bucket).await
+            }
+            SR_BUCKET_OP_CONFIGURE_REPLICATION => {
+                let state = self.state.read().await.clone();
+                configure_bucket_replication(&state, &local_deployment_id()?, bucket).await
+            }
+            SR_BUCKET_OP_DELETE_BUCKET => {
+                let opts = DeleteBucketOptions {
+                    force: true,
+                    ..Default::default()
+                };
+                match store.delete_bucket(bucket, &opts).await {
+                    Ok(()) | Err(Error::BucketNotFound(_)) => Ok(()),
+                    Err(err) => Err(err),
+                }
+            }
+            _ => Err(Error::other(format!("unknown bucket operation {operation}"))),
+        }
+    }
+
+    /// Apply a bucket configuration change made on another site
+    pub async fn peer_bucket_meta(&self, meta: SRBucketMeta) -> Result<()> {
+        if !SR_BUCKET_META_FILES.contains(&meta.config_file.as_str()) {
+            return Err(Error::other(format!("{} is not replicated", meta.config_file)));
+        }
+        match meta.data {
+            Some(data) => metadata_sys::update(&meta.bucket, &meta.config_file, data.into_bytes()).await?,
+            None => metadata_sys::delete(&meta.bucket, &meta.config_file).await?,
+        };
+        Ok(())
+    }
+
+    /// Apply an IAM change made on another site
+    pub async fn peer_iam_item(&self, item: SRIAMItem) -> Result<()> {
+        let iam = iam()?;
+        match item {
+            SRIAMItem::Policy {
+                name,
+                policy: Some(policy),
+            } => {
+                let data = serde_json::to_vec(&policy).map_err(Error::other)?;
+                let policy = Policy::parse_config(&data).map_err(Error::other)?;
+                iam.set_policy(&name, policy).await.map_err(Error::other)?;
+            }
+            SRIAMItem::Policy { name, policy: None } => {
+                iam.delete_policy(&name, true).await.map_err(Error::other)?;
+            }
+            SRIAMItem::PolicyMapping {
+                user_or_group,
+                is_group,
+                policy,
+            } => {
+                iam.policy_db_set(&user_or_group, UserType::Reg, is_group, &policy)
+                    .await
+                    .map_err(Error::other)?;
+            }
+            SRIAMItem::User {
+                access_key,
+                secret_key: Some(secret_key),
+                status,
+                policy,
+            } => {
+                let args = AddOrUpdateUserReq {
+                    secret_key,
+                    policy: policy.clone(),
+                    status: AccountStatus::try_from(status.as_str()).unwrap_or(AccountStatus::Enabled),
+                };
+                iam.create_user(&access_key, &args).await.map_err(Error::other)?;
+                if let Some(policy) = policy.filter(|policy| !policy.is_empty()) {
+                    iam.policy_db_set(&access_key, UserType::Reg, false, &policy)
+                        .await
+                        .map_err(Error::other)?;
+                }
+            }
+            SRIAMItem::User {
+                access_key,
+                secret_key: None,
+                ..
+            } => {
+                iam.delete_user(&access_key, true).await.map_err(Error::other)?;
+            }
+            SRIAMItem::GroupMembers {
+                group,
+                members,
+                is_remove,
+            } => {
+                if is_remove {
+                    iam.remove_users_from_group(&group, members).await.map_err(Error::other)?;
+                } else {
+                    iam.add_users_to_group(&group, members).await.map_err(Error::other)?;
+                }
+            }
+            SRIAMItem::GroupStatus { group, enabled } => {
+                iam.set_group_status(&group, enabled).await.map_err(Error::other)?;
+            }
+            SRIAMItem::ServiceAccount {
+                access_key,
+                secret_key,
+                parent,
+                parent_is_root,
+                groups,
+                session_policy,
+                status,
+                name,
+                description,
+                expiration,
+            } => {
+                let session_policy = match session_policy {
+                    Some(policy) => {
+                        let data = serde_json::to_vec(&policy).map_err(Error::other)?;
+                        Some(Policy::parse_config(&data).map_err(Error::other)?)
+                    }
+                    None => None,
+                };
+                match iam.get_service_account(&access_key).await {
+                    Ok(_) => {
+                        let opts = UpdateServiceAccountOpts {
+                            session_policy,
+                            secret_key: Some(secret_key),
+                            name: Some(name),
+                            description: Some(description),
+                            expiration,
+                            status: Some(status),
+                        };
+                        iam.update_service_account(&access_key, opts).await.map_err(Error::other)?;
+                    }
+                    Err(err) if is_err_no_such_service_account(&err) => {
+                        let parent = if parent_is_root { root_access_key()? } else { parent };
+                        let opts = NewServiceAccountOpts {
+                            session_policy,
+                            access_key,
+                            secret_key,
+                            name: Some(name),
+                            description: Some(description),
+                            expiration,
+                            ..Default::default()
+                        };
+                        let groups = (!groups.is_empty()).then_some(groups);
+                        iam.new_service_account(&parent, groups, opts).await.map_err(Error::other)?;
+                    }
+                    Err(err) => return Err(Error::other(err)),
+                }
+            }
+            SRIAMItem::ServiceAccountDelete { access_key } => {
+                iam.delete_service_account(&access_key, true).await.map_err(Error::other)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn object_layer() -> Result<Arc<ECStore>> {
+    new_object_layer_fn().ok_or_else(|| Error::other("errServerNotInitialized"))
+}
+
+fn iam() -> Result<Arc<IamSys>> {
+    nebulafx_iamx::get().map_err(Error::other)
+}
+
+fn local_deployment_id() -> Result<String> {
+    get_global_deployment_id().ok_or_else(|| Error::other("deployment id is not initialized"))
+}
+
+fn root_access_key() -> Result<String> {
+    get_global_action_cred()
+        .map(|cred| cred.access_key)
+        .ok_or_else(|| Error::other("root credentials are not initialized"))
+}
+
+async fn list_buckets() -> Result<Vec<String>> {
+    let buckets = object_layer()?.list_bucket(&BucketOptions::default()).await?;
+    Ok(buckets.into_iter().map(|bucket| bucket.name).collect())
+}
+
+async fn service_account_secret(state: &SrState) -> Result<String> {
+    let Some(identity) = iam()?.get_user(&state.service_account_access_key).await else {
+        return Err(Error::other(format!(
+            "site replication service account {} not found",
+            state.service_account_access_key
+        )));
+    };
+    Ok(identity.credentials.secret_key)
+}
+
+/// Sites of `clients` that did not fail yet
+fn live_peers<'a>(
+    clients: &'a [(PeerInfo, PeerClient)],
+    failed: &BTreeMap<String, Error>,
+) -> Vec<&'a (PeerInfo, PeerClient)> {
+    clients.iter().filter(|(peer, _)| !failed.contains_key(&peer.name)).collect()
+}
+
+fn note_peer_err(failed: &mut BTreeMap<String, Error>, peer: &PeerInfo, res: Result<()>) {
+    if let Err(err) = res {
+        warn!("site replication sync to {} failed: {}", peer.name, err);
+        failed.insert(peer.name.clone(), err);
+    }
+}
+
+async fn peer_clients(state: &SrState, local_id: &str) -> Result<Vec<(PeerInfo, PeerClient)>> {
+    let secret_key = service_account_secret(state).await?;
+    state
+        .remote_peers(local_id)
+        .map(|peer| {
+            let client = PeerClient::new(&peer.endpoint, &state.service_account_access_key, &secret_key)?;
+            Ok((peer.clone(), client))
+        })
+        .collect()
+}
+
+/// The service account is owned by the root user of each site, so it may call the peer APIs
+async fn ensure_service_account(iam: &IamSys, access_key: &str, secret_key: &str) -> Result<()> {
+    match iam.get_service_account(access_key).await {
+        Ok(_) => {
+            let opts = UpdateServiceAccountOpts {
+                session_policy: None,
+                secret_key: Some(secret_key.to_string()),
+                name: None,
+                description: None,
+                expiration: None,
+                status: None,
+            };
+            iam.update_service_account(access_key, opts).await.map_err(Error::other)?;
+        }
+        Err(err) if is_err_no_such_service_account(&err) => {
+            let opts = NewServiceAccountOpts {
+                access_key: access_key.to_string(),
+                secret_key: secret_key.to_string(),
+                name: Some(SITE_REPLICATOR_SVC_ACC.to_string()),
+                description: Some("Site replication service account".to_string()),
+                expiration: Some(OffsetDateTime::now_utc() + Duration::days(365 * 100)),
+                allow_site_replicator_account: true,
+                ..Default::default()
+            };
+            iam.new_service_account(&root_access_key()?, None, opts)
+                .await
+                .map_err(Error::other)?;
+        }
+        Err(err) => return Err(Error::other(err)),
+    }
+    Ok(())
+}
+
+async fn enable_versioning(bucket: &str) -> Result<()> {
+    let config = VersioningConfiguration {
+        status: Some(BucketVersioningStatus::from_static(BucketVersioningStatus::ENABLED)),
+        ..Default::default()
+    };
+    let data = serialize(&config).map_err(Error::other)?;
+    metadata_sys::update(bucket, BUCKET_VERSIONING_CONFIG, data).await?;
+    Ok(())
+}
+
+fn bucket_meta_data<'a>(meta: &'a BucketMetadata, config_file: &str) -> Option<&'a [u8]> {
+    let data = match config_file {
+        BUCKET_POLICY_CONFIG => &meta.policy_config_json,
+        BUCKET_TAGGING_CONFIG => &meta.tagging_config_xml,
+        BUCKET_LIFECYCLE_CONFIG => &meta.lifecycle_config_xml,
+        BUCKET_SSECONFIG => &meta.encryption_config_xml,
+        OBJECT_LOCK_CONFIG => &meta.object_lock_config_xml,
+        BUCKET_VERSIONING_CONFIG => &meta.versioning_config_xml,
+        _ => return None,
+    };
+    (!data.is_empty()).then_some(data.as_slice())
+}
+
+fn site_rule_id(deployment_id: &str) -> String {
+    format!("{SR_RULE_ID_PREFIX}{deployment_id}")
+}
+
+/// Replication rules sending every object, delete and replica change to each target, keyed by rule id
+fn site_replication_config(rules: &BTreeMap<String, String>) -> Result<ReplicationConfiguration> {
+    let mut xml = String::from("<ReplicationConfiguration><Role></Role>");
+    for (priority, (id, arn)) in rules.iter().enumerate() {
+        let _ = write!(
+            xml,
+            "<Rule><ID>{id}</ID><Status>Enabled</Status><Priority>{}</Priority>\
+             <DeleteMarkerReplication><Status>Enabled</Status></DeleteMarkerReplication>\
+             <DeleteReplication><Status>Enabled</Status></DeleteReplication>\
+             <Filter></Filter>\
+             <SourceSelectionCriteria><ReplicaModifications><Status>Enabled</Status></ReplicaModifications></SourceSelectionCriteria>\
+             <ExistingObjectReplication><Status>Enabled</Status></ExistingObjectReplication>\
+             <Destination><Bucket>{arn}</Bucket></Destination></Rule>",
+            priority + 1
+        );
+    }
+    xml.push_str("</ReplicationConfiguration>");
+    deserialize(xml.as_bytes()).map_err(Error::other)
+}
+
+fn bucket_target(peer: &PeerInfo, bucket: &str, access_key: &str, secret_key: &str) -> Result<BucketTarget> {
+    let url = parse_endpoint(&peer.endpoint)?;
+    let host = url.host_str().unwrap_or_default();
+    let endpoint = match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    };
+    Ok(BucketTarget {
+        source_bucket: bucket.to_string(),
+        endpoint,
+        credentials: Some(TargetCredentials {
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+            session_token: None,
+            expiration: None,
+        }),
+        target_bucket: bucket.to_string(),
+        secure: url.scheme() == "https",
+        target_type: BucketTargetType::ReplicationService,
+        deployment_id: peer.deployment_id.clone(),
+        ..Default::default()
+    })
+}
+
+async fn save_bucket_targets(bucket: &str) -> Result<()> {
+    let targets = BucketTargetSys::get()
+        .list_bucket_targets(bucket)
+        .await
+        .map_err(Error::other)?;
+    let data = serde_json::to_vec(&targets).map_err(Error::other)?;
+    metadata_sys::update(bucket, BUCKET_TARGETS_FILE, data).await?;
+    Ok(())
+}
+
+/// Add a replication target and rule for every other site, rules not managed by site replication are kept
+async fn configure_bucket_replication(state: &SrState, local_id: &str, bucket: &str) -> Result<()> {
+    if !state.is_enabled() {
+        return Ok(());
+    }
+    let secret_key = service_account_secret(state).await?;
+    let target_sys = BucketTargetSys::get();
+
+    let mut rules = BTreeMap::new();
+    for peer in state.remote_peers(local_id) {
+        let mut target = bucket_target(peer, bucket, &state.service_account_access_key, &secret_key)?;
+        let (arn, exists) = target_sys.get_remote_arn(bucket, Some(&target), &peer.deployment_id).await;
+        target.arn = arn.clone();
+        target_sys.set_target(bucket, &target, exists).await.map_err(Error::other)?;
+        rules.insert(site_rule_id(&peer.deployment_id), arn);
+    }
+    save_bucket_targets(bucket).await?;
+
+    let mut config = site_replication_config(&rules)?;
+    match metadata_sys::get_replication_config(bucket).await {
+        Ok((existing, _)) => {
+            config.role = existing.role;
+            config
+                .rules
+                .extend(existing.rules.into_iter().filter(|rule| !is_site_rule(rule.id.as_deref())));
+        }
+        Err(Error::ConfigNotFound) => {}
+        Err(err) => return Err(err),
+    }
+    let data = serialize(&config).map_err(Error::other)?;
+    metadata_sys::update(bucket, BUCKET_REPLICATION_CONFIG, data).await?;
+    Ok(())
+}
+
+fn is_site_rule(id: Option<&str>) -> bool {
+    id.is_some_and(|id| id.starts_with(SR_RULE_ID_PREFIX))
+}
+
+/// Drop the replication targets and rules of `removed` sites from a bucket
+async fn remove_bucket_replication(bucket: &str, removed: &[PeerInfo]) -> Result<()> {
+    let config = match metadata_sys::get_replication_config(bucket).await {
+        Ok((config, _)) => config,
+        Err(Error::ConfigNotFound) => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    let removed_rules: Vec<String> = removed.iter().map(|peer| site_rule_id(&peer.deployment_id)).collect();
+    let (dropped, kept): (Vec<_>, Vec<_>) = config
+        .rules
+        .into_iter()
+        .partition(|rule| rule.id.as_ref().is_some_and(|id| removed_rules.contains(id)));
+    if dropped.is_empty() {
+        return Ok(());
+    }
+
+    let target_sys = BucketTargetSys::get();
+    for rule in &dropped {
+        if !rule.destination.bucket.is_empty() {
+            target_sys
+                .remove_target(bucket, &rule.destination.bucket)
+                .await
+                .map_err(Error::other)?;
+        }
+    }
+    save_bucket_targets(bucket).await?;
+
+    if kept.is_empty() {
+        metadata_sys::delete(bucket, BUCKET_REPLICATION_CONFIG).await?;
+    } else {
+        let config = ReplicationConfiguration {
+            role: config.role,
+            rules: kept,
+        };
+        metadata_sys::update(bucket, BUCKET_REPLICATION_CONFIG, serialize(&config).map_err(Error::other)?).await?;
+    }
+    Ok(())
+}
+
+async fn user_item(access_key: &str) -> Result<SRIAMItem> {
+    let iam = iam()?;
+    let Some(identity) = iam.get_user(access_key).await else {
+        return Ok(SRIAMItem::User {
+            access_key: access_key.to_string(),
+            secret_key: None,
+            status: String::new(),
+            policy: None,
+        });
+    };
+    Ok(user_item_from_identity(&iam, access_key, identity).await)
+}
+
+async fn user_item_from_identity(iam: &IamSys, access_key: &str, identity: UserIdentity) -> SRIAMItem {
+    let policies = iam.policy_db_get(access_key, &None).await.unwrap_or_default();
+    let status = if identity.credentials.status == "off" {
+        AccountStatus::Disabled
+    } else {
+        AccountStatus::Enabled
+    };
+    SRIAMItem::User {
+        access_key: access_key.to_string(),
+        secret_key: Some(identity.credentials.secret_key),
+        status: status.as_ref().to_string(),
+        policy: (!policies.is_empty()).then(|| policies.join(",")),
+    }
+}
+
+async fn service_account_item(access_key: &str) -> Result<Option<SRIAMItem>> {
+    let iam = iam()?;
+    let Some(identity) = iam.get_user(access_key).await else {
+        return Ok(None);
+    };
+    if !identity.credentials.is_service_account() {
+        return Ok(None);
+    }
+    let (_, session_policy) = iam.get_service_account(access_key).await.map_err(Error::other)?;
+    let session_policy = match session_policy {
+        Some(policy) => Some(serde_json::to_value(&policy).map_err(Error::other)?),
+        None => None,
+    };
+
+    let cred = identity.credentials;
+    Ok(Some(SRIAMItem::ServiceAccount {
+        access_key: access_key.to_string(),
+        secret_key: cred.secret_key,
+        parent_is_root: cred.parent_user == root_access_key()?,
+        parent: cred.parent_user,
+        groups: cred.groups.unwrap_or_default(),
+        session_policy,
+        status: cred.status,
+        name: cred.name.unwrap_or_default(),
+        description: cred.description.unwrap_or_default(),
+        expiration: cred.expiration,
+    }))
+}
+
+/// Every IAM entity of this site in the order peers must apply them: policies before the
+/// users and groups they are attached to, users before the service accounts they own
+async fn iam_items() -> Result<Vec<SRIAMItem>> {
+    let iam = iam()?;
+    let mut items = Vec::new();
+
+    let mut policies: Vec<(String, Policy)> = iam.list_polices("").await.map_err(Error::other)?.into_iter().collect();
+    policies.sort_by(|a, b| a.0.cmp(&b.0));
+    for (name, policy) in policies {
+        if DEFAULT_POLICIES.iter().any(|(default, _)| *default == name) {
+            continue;
+        }
+        items.push(SRIAMItem::Policy {
+            name,
+            policy: Some(serde_json::to_value(&policy).map_err(Error::other)?),
+        });
+    }
+
+    let mut users: Vec<String> = iam.list_users().await.map_err(Error::other)?.into_keys().collect();
+    users.sort();
+    for access_key in users {
+        if let Some(identity) = iam.get_user(&access_key).await {
+            items.push(user_item_from_identity(&iam, &access_key, identity).await);
+        }
+    }
+
+    let mut groups = iam.list_groups().await.map_err(Error::other)?;
+    groups.sort();
+    for group in groups {
+        let desc = iam.get_group_description(&group).await.map_err(Error::other)?;
+        items.push(SRIAMItem::GroupMembers {
+            group: group.clone(),
+            members: desc.members,
+            is_remove: false,
+        });
+        items.push(SRIAMItem::GroupStatus {
+            group: group.clone(),
+            enabled: desc.status != "disabled",
+        });
+        if !desc.policy.is_empty() {
+            items.push(SRIAMItem::PolicyMapping {
+                user_or_group: group,
+                is_group: true,
+                policy: desc.policy,
+            });
+        }
+    }
+
+    let mut svc_accounts = HashMap::new();
+    iam.load_users(UserType::Svc, &mut svc_accounts).await.map_err(Error::other)?;
+    let mut svc_accounts: Vec<String> = svc_accounts.into_keys().filter(|ak| ak != SITE_REPLICATOR_SVC_ACC).collect();
+    svc_accounts.sort();
+    for access_key in svc_accounts {
+        if let Some(item) = service_account_item(&access_key).await? {
+            items.push(item);
+        }
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_replication_config() {
+        let rules = BTreeMap::from([
+            (site_rule_id("b"), "arn:nebulafx:replication::b:photos".to_string()),
+            (site_rule_id("c"), "arn:nebulafx:replication::c:photos".to_string()),
+        ]);
+        let config = site_replication_config(&rules).unwrap();
+
+        assert_eq!(config.rules.len(), 2);
+        let rule = &config.rules[0];
+        assert_eq!(rule.id.as_deref(), Some("site-repl-b"));
+        assert!(is_site_rule(rule.id.as_deref()));
+        assert_eq!(rule.destination.bucket, "arn:nebulafx:replication::b:photos");
+        assert_eq!(rule.priority, Some(1));
+        assert_eq!(config.rules[1].priority, Some(2));
+        assert!(rule.delete_marker_replication.is_some());
+        assert!(rule.existing_object_replication.is_some());
+
+        assert!(!is_site_rule(Some("backup")));
+        assert!(!is_site_rule(None));
+    }
+
+    #[test]
+    fn test_failed_peers_are_skipped() {
+        let clients: Vec<(PeerInfo, PeerClient)> = ["site-b", "site-c"]
+            .into_iter()
+            .map(|name| {
+                let peer = PeerInfo {
+                    name: name.to_string(),
+                    endpoint: format!("http://{name}:9000"),
+                    deployment_id: name.to_string(),
+                };
+                (peer, PeerClient::new(&format!("http://{name}:9000"), "ak", "sk").unwrap())
+            })
+            .collect();
+
+        let mut failed = BTreeMap::new();
+        note_peer_err(&mut failed, &clients[0].0, Ok(()));
+        assert_eq!(live_peers(&clients, &failed).len(), 2);
+
+        note_peer_err(&mut failed, &clients[0].0, Err(Error::other("site-b is unreachable")));
+        let live = live_peers(&clients, &failed);
+        assert_eq!(live.len(), 1);
+        assert_eq!(live[0].0.name, "site-c");
+        assert!(failed["site-b"].to_string().contains("site-b is unreachable"));
+    }
+
+    #[test]
+    fn test_bucket_target() {
+        let peer = PeerInfo {
+            name: "site-b".to_string(),
+            endpoint: "https://site-b.example.com:9000".to_string(),
+            deployment_id: "b".to_string(),
+        };
+        let target = bucket_target(&peer, "photos", SITE_REPLICATOR_SVC_ACC, "secret").unwrap();
+        assert_eq!(target.endpoint, "site-b.example.com:9000");
+        assert!(target.secure);
+        assert_eq!(target.source_bucket, "photos");
+        assert_eq!(target.target_bucket, "photos");
+        assert_eq!(target.deployment_id, "b");
+        assert_eq!(target.target_type, BucketTargetType::ReplicationService);
+        assert_eq!(target.credentials.unwrap().access_key, SITE_REPLICATOR_SVC_ACC);
+    }
+}
@@ -1,13 +1,19 @@
 use super::ecfs::FS;
-use crate::auth::{check_key_valid, get_condition_values, get_session_token};
+use crate::auth::{add_existing_object_tags, add_request_object_tags, check_key_valid, get_condition_values, get_session_token};
 use nebulafx_ecstore::bucket::policy_sys::PolicySys;
+use nebulafx_ecstore::bucket::tagging::decode_tags_to_map;
+use nebulafx_ecstore::error::{StorageError, is_err_object_not_found, is_err_version_not_found};
+use nebulafx_ecstore::new_object_layer_fn;
+use nebulafx_ecstore::store_api::{ObjectOptions, StorageAPI};
 use nebulafx_iamx::error::Error as IamError;
+use nebulafx_iamx::sys::IamSys;
 use nebulafx_policy::auth;
 use nebulafx_policy::policy::action::{Action, S3Action};
-use nebulafx_policy::policy::{Args, BucketPolicyArgs};
+use nebulafx_policy::policy::{Args, BucketPolicy, BucketPolicyArgs, PolicyDecision};
 use s3s::access::{S3Access, S3AccessContext};
 use s3s::{S3Error, S3ErrorCode, S3Request, S3Result, dto::*, s3_error};
 use std::collections::HashMap;
+use tracing::{debug, warn};
 
 #[allow(dead_code)]
 #[derive(Default, Clone)]
@@ -18,6 +24,8 @@ pub(crate) struct ReqInfo {
     pub object: Option<String>,
     pub version_id: Option<String>,
     pub region: Option<String>,
+    /// Tags a PutObjectTagging request sets, the `x-amz-tagging` header is read with the other headers
+    pub request_tags: Option<HashMap<String, String>>,
 }
 
 /// Authorizes the request based on the action and credentials.
@@ -34,68 +42,104 @@ pub async fn authorize_request<T>(req: &mut S3Request<T>, action: Action) -> S3R
 
         let default_claims = HashMap::new();
         let claims = cred.claims.as_ref().unwrap_or(&default_claims);
-        let conditions = get_condition_values(&req.headers, cred, req_info.version_id.as_deref(), None);
+        let mut conditions = get_condition_values(&req.headers, cred, req_info.version_id.as_deref(), None);
+        add_request_tag_conditions(&mut conditions, req_info);
+        let bucket = req_info.bucket.as_deref().unwrap_or("");
+
+        // The bucket policy takes part only for requests on a bucket, the owner is never locked out by it
+        let bucket_policy = if bucket.is_empty() || req_info.is_owner {
+            None
+        } else {
+            match PolicySys::get(bucket).await {
+                Ok(policy) => Some(policy),
+                Err(err) => {
+                    if err != StorageError::ConfigNotFound {
+                        warn!("get bucket policy of {} failed: {:?}", bucket, err);
+                    }
+                    None
+                }
+            }
+        };
+
+        let groups = if bucket_policy.is_some() {
+            requester_groups(&iam_store, cred).await
+        } else {
+            None
+        };
+
+        // The owner passes every policy, for anyone else the tags are read only when a policy tests them
+        if !req_info.is_owner && uses_existing_object_tags(action) {
+            let tested = bucket_policy
+                .as_ref()
+                .is_some_and(|policy| policy.mentions_condition_key(EXISTING_OBJECT_TAG_KEY))
+                || iam_store
+                    .policies_mention_condition_key(
+                        &Args {
+                            account: &cred.access_key,
+                            groups: &cred.groups,
+                            action,
+                            bucket,
+                            conditions: &conditions,
+                            is_owner: false,
+                            object: req_info.object.as_deref().unwrap_or(""),
+                            claims,
+                            deny_only: false,
+                        },
+                        EXISTING_OBJECT_TAG_KEY,
+                    )
+                    .await;
+            if tested {
+                add_existing_tag_conditions(&mut conditions, req_info).await;
+            }
+        }
+
+        let combined = CombinedPolicies {
+            iam_store: &iam_store,
+            bucket_policy: bucket_policy.as_ref(),
+            parent_user: &cred.parent_user,
+            groups: &groups,
+        };
+        let args = |action: Action| Args {
+            account: &cred.access_key,
+            groups: &cred.groups,
+            action,
+            bucket,
+            conditions: &conditions,
+            is_owner: req_info.is_owner,
+            object: req_info.object.as_deref().unwrap_or(""),
+            claims,
+            deny_only: false,
+        };
 
         if action == Action::S3Action(S3Action::DeleteObjectAction)
             && req_info.version_id.is_some()
-            && !iam_store
-                .is_allowed(&Args {
-                    account: &cred.access_key,
-                    groups: &cred.groups,
-                    action: Action::S3Action(S3Action::DeleteObjectVersionAction),
-                    bucket: req_info.bucket.as_deref().unwrap_or(""),
-                    conditions: &conditions,
-                    is_owner: req_info.is_owner,
-                    object: req_info.object.as_deref().unwrap_or(""),
-                    claims,
-                    deny_only: false,
-                })
+            && !combined
+                .is_allowed(&args(Action::S3Action(S3Action::DeleteObjectVersionAction)))
                 .await
         {
             return Err(s3_error!(AccessDenied, "Access Denied"));
         }
 
-        if iam_store
-            .is_allowed(&Args {
-                account: &cred.access_key,
-                groups: &cred.groups,
-                action,
-                bucket: req_info.bucket.as_deref().unwrap_or(""),
-                conditions: &conditions,
-                is_owner: req_info.is_owner,
-                object: req_info.object.as_deref().unwrap_or(""),
-                claims,
-                deny_only: false,
-            })
-            .await
-        {
+        if combined.is_allowed(&args(action)).await {
             return Ok(());
         }
 
         if action == Action::S3Action(S3Action::ListBucketVersionsAction)
-            && iam_store
-                .is_allowed(&Args {
-                    account: &cred.access_key,
-                    groups: &cred.groups,
-                    action: Action::S3Action(S3Action::ListBucketAction),
-                    bucket: req_info.bucket.as_deref().unwrap_or(""),
-                    conditions: &conditions,
-                    is_owner: req_info.is_owner,
-                    object: req_info.object.as_deref().unwrap_or(""),
-                    claims,
-                    deny_only: false,
-                })
-                .await
+            && combined.is_allowed(&args(Action::S3Action(S3Action::ListBucketAction))).await
         {
             return Ok(());
         }
     } else {
-        let conditions = get_condition_values(
+        let mut conditions = get_condition_values(
             &req.headers,
             &auth::Credentials::default(),
             req_info.version_id.as_deref(),
             req.region.as_deref(),
         );
+        add_request_tag_conditions(&mut conditions, req_info);
+        if uses_existing_object_tags(action) && bucket_policy_tests_existing_tags(req_info.bucket.as_deref()).await {
+            add_existing_tag_conditions(&mut conditions, req_info).await;
+        }
 
         if action != Action::S3Action(S3Action::ListAllMyBucketsAction) {
             if PolicySys::is_allowed(&BucketPolicyArgs {
@@ -103,6 +147,7 @@ pub async fn authorize_request<T>(req: &mut S3Request<T>, action: Action) -> S3R
                 action,
                 is_owner: false,
                 account: "",
+                parent_user: "",
                 groups: &None,
                 conditions: &conditions,
                 object: req_info.object.as_deref().unwrap_or(""),
@@ -118,6 +163,7 @@ pub async fn authorize_request<T>(req: &mut S3Request<T>, action: Action) -> S3R
                     action: Action::S3Action(S3Action::ListBucketAction),
                     is_owner: false,
                     account: "",
+                    parent_user: "",
                     groups: &None,
                     conditions: &conditions,
                     object: "",
@@ -132,6 +178,127 @@ pub async fn authorize_request<T>(req: &mut S3Request<T>, action: Action) -> S3R
     Err(s3_error!(AccessDenied, "Access Denied"))
 }
 
+/// Actions whose policies may test the tags of the object they act on with `s3:ExistingObjectTag/<key>`
+fn uses_existing_object_tags(action: Action) -> bool {
+    matches!(
+        action,
+        Action::S3Action(
+            S3Action::GetObjectAction
+                | S3Action::GetObjectVersionAction
+                | S3Action::GetObjectAttributesAction
+                | S3Action::GetObjectVersionAttributesAction
+                | S3Action::GetObjectTaggingAction
+                | S3Action::GetObjectVersionTaggingAction
+                | S3Action::PutObjectTaggingAction
+                | S3Action::PutObjectVersionTaggingAction
+                | S3Action::DeleteObjectTaggingAction
+                | S3Action::DeleteObjectVersionTaggingAction
+                | S3Action::GetObjectRetentionAction
+                | S3Action::PutObjectRetentionAction
+                | S3Action::GetObjectLegalHoldAction
+                | S3Action::PutObjectLegalHoldAction
+                | S3Action::DeleteObjectAction
+                | S3Action::DeleteObjectVersionAction
+                | S3Action::RestoreObjectAction
+        )
+    )
+}
+
+/// Condition key prefix of the tags of the object a request acts on
+const EXISTING_OBJECT_TAG_KEY: &str = "s3:ExistingObjectTag";
+
+/// Fills the request object tag condition keys from the tags of a PutObjectTagging body
+fn add_request_tag_conditions(conditions: &mut HashMap<String, Vec<String>>, req_info: &ReqInfo) {
+    if let Some(tags) = &req_info.request_tags {
+        add_request_object_tags(conditions, tags);
+    }
+}
+
+/// Whether the policy of the bucket tests the tags of existing objects, read for anonymous requests
+async fn bucket_policy_tests_existing_tags(bucket: Option<&str>) -> bool {
+    let Some(bucket) = bucket.filter(|bucket| !bucket.is_empty()) else {
+        return false;
+    };
+    PolicySys::get(bucket)
+        .await
+        .is_ok_and(|policy| policy.mentions_condition_key(EXISTING_OBJECT_TAG_KEY))
+}
+
+/// Fills the existing object tag condition keys with the current tags of the object, read from the
+/// object metadata only. Costs a metadata read, so callers check first that a policy tests them.
+async fn add_existing_tag_conditions(conditions: &mut HashMap<String, Vec<String>>, req_info: &ReqInfo) {
+    let (Some(bucket), Some(object)) = (req_info.bucket.as_deref(), req_info.object.as_deref()) else {
+        return;
+    };
+    let Some(store) = new_object_layer_fn() else {
+        return;
+    };
+
+    let opts = ObjectOptions {
+        version_id: req_info.version_id.clone(),
+        ..Default::default()
+    };
+    match store.get_object_info(bucket, object, &opts).await {
+        Ok(info) => add_existing_object_tags(conditions, &decode_tags_to_map(&info.user_tags)),
+        Err(err) => {
+            if !is_err_object_not_found(&err) && !is_err_version_not_found(&err) {
+                debug!("get tags of {}/{} for authorization failed: {:?}", bucket, object, err);
+            }
+        }
+    }
+}
+
+/// IAM policies of the requester together with the policy of the bucket, evaluated the AWS way: an
+/// explicit Deny in either wins, otherwise an Allow in either grants.
+struct CombinedPolicies<'a> {
+    iam_store: &'a IamSys,
+    bucket_policy: Option<&'a BucketPolicy>,
+    parent_user: &'a str,
+    /// Groups of the requester and of the user it acts for
+    groups: &'a Option<Vec<String>>,
+}
+
+impl CombinedPolicies<'_> {
+    async fn is_allowed(&self, args: &Args<'_>) -> bool {
+        let iam = self.iam_store.evaluate(args).await;
+        let Some(bucket_policy) = self.bucket_policy else {
+            return iam == PolicyDecision::Allow;
+        };
+
+        let bucket = bucket_policy.evaluate(&BucketPolicyArgs {
+            account: args.account,
+            parent_user: self.parent_user,
+            groups: self.groups,
+            action: args.action,
+            bucket: args.bucket,
+            conditions: args.conditions,
+            is_owner: false,
+            object: args.object,
+        });
+        PolicyDecision::combine(&[iam, bucket])
+    }
+}
+
+/// Groups the credentials carry, plus the groups of the user they belong to
+async fn requester_groups(iam_store: &IamSys, cred: &auth::Credentials) -> Option<Vec<String>> {
+    let user = if cred.parent_user.is_empty() {
+        &cred.access_key
+    } else {
+        &cred.parent_user
+    };
+
+    let mut groups = cred.groups.clone().unwrap_or_default();
+    if let Ok(info) = iam_store.get_user_info(user).await {
+        for group in info.member_of.unwrap_or_default() {
+            if !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+    }
+
+    if groups.is_empty() { None } else { Some(groups) }
+}
+
 #[async_trait::async_trait]
 impl S3Access for FS {
     // /// Checks whether the current request has accesses to the resources.
@@ -1063,6 +1230,14 @@ impl S3Access for FS {
         req_info.bucket = Some(req.input.bucket.clone());
         req_info.object = Some(req.input.key.clone());
         req_info.version_id = req.input.version_id.clone();
+        req_info.request_tags = Some(
+            req.input
+                .tagging
+                .tag_set
+                .iter()
+                .filter_map(|tag| Some((tag.key.clone()?, tag.value.clone().unwrap_or_default())))
+                .collect(),
+        );
 
         authorize_request(req, Action::S3Action(S3Action::PutObjectTaggingAction)).await
     }
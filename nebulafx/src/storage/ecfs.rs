@@ -1,8 +1,10 @@
 use crate::auth::get_condition_values;
 use crate::error::ApiError;
+use crate::site_replication;
 use crate::storage::entity;
 use crate::storage::helper::OperationHelper;
 use crate::storage::options::{filter_object_metadata, get_content_sha256};
+use crate::storage::select::{SelectOutputFormat, select_event_stream};
 use crate::storage::{
     access::{ReqInfo, authorize_request},
     options::{
@@ -13,26 +15,25 @@ use crate::storage::{
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64_STANDARD};
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
-use datafusion::arrow::{
-    csv::WriterBuilder as CsvWriterBuilder, json::WriterBuilder as JsonWriterBuilder, json::writer::JsonArray,
-};
 use futures::StreamExt;
 use http::{HeaderMap, StatusCode};
 use metrics::counter;
 use nebulafx_ecstore::{
     bucket::{
+        cors::CorsApi,
         lifecycle::{
             bucket_lifecycle_ops::{RestoreRequestOps, post_restore_opts, validate_transition_tier},
             lifecycle::{self, Lifecycle, TransitionOptions},
         },
         metadata::{
-            BUCKET_LIFECYCLE_CONFIG, BUCKET_NOTIFICATION_CONFIG, BUCKET_POLICY_CONFIG, BUCKET_REPLICATION_CONFIG,
-            BUCKET_SSECONFIG, BUCKET_TAGGING_CONFIG, BUCKET_VERSIONING_CONFIG, OBJECT_LOCK_CONFIG,
+            BUCKET_CORS_CONFIG, BUCKET_LIFECYCLE_CONFIG, BUCKET_NOTIFICATION_CONFIG, BUCKET_POLICY_CONFIG,
+            BUCKET_REPLICATION_CONFIG, BUCKET_SSECONFIG, BUCKET_TAGGING_CONFIG, BUCKET_VERSIONING_CONFIG, OBJECT_LOCK_CONFIG,
         },
         metadata_sys,
         metadata_sys::get_replication_config,
         object_lock::objectlock_sys::BucketObjectLockSys,
         policy_sys::PolicySys,
+        quota::BucketQuotaSys,
         replication::{
             DeletedObjectReplicationInfo, ReplicationConfigurationExt, check_replicate_delete, get_must_replicate_options,
             must_replicate, schedule_replication, schedule_replication_delete,
@@ -65,7 +66,7 @@ use nebulafx_ecstore::{
     },
 };
 use nebulafx_filemeta::REPLICATE_INCOMING_DELETE;
-use nebulafx_filemeta::{ObjectPartInfo, RestoreStatusOps};
+use nebulafx_filemeta::{ObjectPartInfo, PART_NONCES_KEY, RestoreStatusOps};
 use nebulafx_filemeta::{ReplicationStatusType, ReplicationType, VersionPurgeStatusType};
 use nebulafx_notify::{EventArgsBuilder, notifier_global};
 use nebulafx_policy::{
@@ -77,7 +78,7 @@ use nebulafx_policy::{
 };
 use nebulafx_rio::{CompressReader, DecryptReader, EncryptReader, EtagReader, HardLimitReader, HashReader, Reader, WarpReader};
 use nebulafx_s3select_api::{
-    object_store::bytes_stream,
+    object_store::{bytes_stream, validate_select_input},
     query::{Context, Query},
 };
 use nebulafx_s3select_query::get_global_db;
@@ -107,8 +108,7 @@ use std::{
     sync::{Arc, LazyLock},
 };
 use time::{OffsetDateTime, format_description::well_known::Rfc3339};
-use tokio::{io::AsyncRead, sync::mpsc};
-use tokio_stream::wrappers::ReceiverStream;
+use tokio::io::AsyncRead;
 use tokio_tar::Archive;
 use tokio_util::io::{ReaderStream, StreamReader};
 use tracing::{debug, error, info, instrument, warn};
@@ -189,39 +189,239 @@ impl AsyncRead for InMemoryAsyncReader {
     }
 }
 
-async fn decrypt_multipart_managed_stream(
-    mut encrypted_stream: Box<dyn AsyncRead + Unpin + Send + Sync>,
+fn decrypt_multipart_managed_stream(
+    encrypted_stream: Box<dyn AsyncRead + Unpin + Send + Sync>,
     parts: &[ObjectPartInfo],
     key_bytes: [u8; 32],
     base_nonce: [u8; 12],
-) -> Result<(Box<dyn Reader>, i64), StorageError> {
-    let total_plain_capacity: usize = parts.iter().map(|part| part.actual_size.max(0) as usize).sum();
+    recorded_nonces: &HashMap<usize, [u8; 12]>,
+) -> (Box<dyn Reader>, i64) {
+    let reader = decrypt_parts(encrypted_stream, parts, key_bytes, |number| {
+        recorded_nonces
+            .get(&number)
+            .copied()
+            .unwrap_or_else(|| derive_part_nonce(base_nonce, number))
+    });
+    let total_plain_size = parts.iter().map(|part| part.actual_size).sum();
+
+    (Box::new(reader), total_plain_size)
+}
+
+/// Decrypts `parts` read back to back from `encrypted_stream`, each sealed with `part_nonce(part.number)`,
+/// as the plaintext is read
+fn decrypt_parts(
+    encrypted_stream: Box<dyn AsyncRead + Unpin + Send + Sync>,
+    parts: &[ObjectPartInfo],
+    key_bytes: [u8; 32],
+    part_nonce: impl Fn(usize) -> [u8; 12],
+) -> DecryptReader<WarpReader<Box<dyn AsyncRead + Unpin + Send + Sync>>> {
+    let nonces = parts
+        .iter()
+        .filter(|part| part.size > 0)
+        .map(|part| part_nonce(part.number))
+        .collect();
+
+    DecryptReader::new_parts(WarpReader::new(encrypted_stream), key_bytes, nonces)
+}
+
+/// Base nonce of a multipart SSE-C upload; parts without a recorded nonce are sealed with `derive_part_nonce(base, N)`
+const SSEC_MULTIPART_IV: &str = "x-nebulafx-internal-ssec-iv";
+
+/// Nonces the parts of a completed multipart object were sealed with, empty for objects whose parts
+/// are sealed with nonces derived from the part number
+fn recorded_part_nonces(metadata: &HashMap<String, String>) -> Result<HashMap<usize, [u8; 12]>, ApiError> {
+    let Some(recorded) = metadata.get(PART_NONCES_KEY) else {
+        return Ok(HashMap::new());
+    };
+
+    let invalid = || ApiError::from(StorageError::other("Invalid part nonces"));
+    recorded
+        .split(',')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (number, nonce) = entry.split_once(':').ok_or_else(invalid)?;
+            let number = number.parse::<usize>().map_err(|_| invalid())?;
+            let nonce: [u8; 12] = BASE64_STANDARD
+                .decode(nonce)
+                .map_err(|_| invalid())?
+                .try_into()
+                .map_err(|_| invalid())?;
+            Ok((number, nonce))
+        })
+        .collect()
+}
+
+/// Decode an SSE-C key and check it against the MD5 sent along with it
+fn parse_sse_customer_key(sse_key: &str, sse_key_md5: &str) -> Result<[u8; 32], ApiError> {
+    let key_bytes = BASE64_STANDARD
+        .decode(sse_key)
+        .map_err(|e| ApiError::from(StorageError::other(format!("Invalid SSE-C key: {e}"))))?;
+
+    if key_bytes.len() != 32 {
+        return Err(ApiError::from(StorageError::other("SSE-C key must be 32 bytes")));
+    }
+
+    let computed_md5 = format!("{:x}", md5::compute(&key_bytes));
+    if computed_md5 != sse_key_md5 {
+        return Err(ApiError::from(StorageError::other("SSE-C key MD5 mismatch")));
+    }
+
+    let mut key_array = [0u8; 32];
+    key_array.copy_from_slice(&key_bytes);
+    Ok(key_array)
+}
+
+/// Customer key for a request against an object or upload, which must match the key it was created with.
+///
+/// Returns `None` when neither the stored metadata nor the request use SSE-C.
+fn sse_customer_key_for(
+    stored: &HashMap<String, String>,
+    sse_key: Option<&String>,
+    sse_key_md5: Option<&String>,
+) -> Result<Option<[u8; 32]>, ApiError> {
+    let stored_md5 = stored.get("x-amz-server-side-encryption-customer-key-md5");
+    if !stored.contains_key("x-amz-server-side-encryption-customer-algorithm") {
+        if sse_key.is_some() {
+            return Err(ApiError::from(StorageError::other(
+                "SSE-C key provided for an object that is not SSE-C encrypted",
+            )));
+        }
+        return Ok(None);
+    }
+
+    let (Some(sse_key), Some(sse_key_md5)) = (sse_key, sse_key_md5) else {
+        return Err(ApiError::from(StorageError::other(
+            "Object encrypted with SSE-C but no customer key provided",
+        )));
+    };
+    if stored_md5 != Some(sse_key_md5) {
+        return Err(ApiError::from(StorageError::other("SSE-C key does not match object encryption key")));
+    }
+
+    parse_sse_customer_key(sse_key, sse_key_md5).map(Some)
+}
+
+/// Nonce of a single part SSE-C object, derived from its name
+fn sse_c_object_nonce(bucket: &str, key: &str) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    let nonce_hash = md5::compute(format!("{bucket}-{key}").as_bytes());
+    nonce.copy_from_slice(&nonce_hash.0[..12]);
+    nonce
+}
+
+/// Random nonce to seal a part upload with, kept with the part through `opts`
+fn new_part_nonce(opts: &mut ObjectOptions) -> [u8; 12] {
+    let nonce: [u8; 12] = rand::random();
+    opts.part_nonce = Some(Bytes::copy_from_slice(&nonce));
+    nonce
+}
+
+/// Nonce used to seal `part_number` of an SSE-C object
+fn sse_c_part_nonce(
+    bucket: &str,
+    key: &str,
+    metadata: &HashMap<String, String>,
+    part_number: usize,
+) -> Result<[u8; 12], ApiError> {
+    if let Some(nonce) = recorded_part_nonces(metadata)?.get(&part_number) {
+        return Ok(*nonce);
+    }
+    let Some(iv) = metadata.get(SSEC_MULTIPART_IV) else {
+        return Ok(sse_c_object_nonce(bucket, key));
+    };
+
+    let iv = BASE64_STANDARD
+        .decode(iv)
+        .map_err(|e| ApiError::from(StorageError::other(format!("Invalid SSE-C multipart IV: {e}"))))?;
+    let base_nonce: [u8; 12] = iv
+        .try_into()
+        .map_err(|_| ApiError::from(StorageError::other("Invalid SSE-C multipart IV length")))?;
+
+    Ok(derive_part_nonce(base_nonce, part_number))
+}
 
-    let mut plaintext = Vec::with_capacity(total_plain_capacity);
+/// Parts holding plaintext bytes `[offset, offset + length)`, with the offset of the first one in
+/// the stored (encrypted) object and how many plaintext bytes of it precede `offset`
+fn sse_c_part_span(parts: &[ObjectPartInfo], offset: usize, length: i64) -> Option<(Vec<ObjectPartInfo>, usize, usize)> {
+    let end = offset as i64 + length;
+    let mut plain_start = 0i64;
+    let mut encrypted_start = 0usize;
+    let mut span: Option<(Vec<ObjectPartInfo>, usize, usize)> = None;
 
     for part in parts {
-        if part.size == 0 {
-            continue;
+        let plain_end = plain_start + part.actual_size;
+        if part.size > 0 && plain_end > offset as i64 && plain_start < end {
+            let (selected, _, _) =
+                span.get_or_insert_with(|| (Vec::new(), encrypted_start, offset.saturating_sub(plain_start as usize)));
+            selected.push(part.clone());
         }
+        plain_start = plain_end;
+        encrypted_start += part.size;
+    }
 
-        let mut encrypted_part = vec![0u8; part.size];
-        tokio::io::AsyncReadExt::read_exact(&mut encrypted_stream, &mut encrypted_part)
-            .await
-            .map_err(|e| StorageError::other(format!("failed to read encrypted multipart segment {}: {}", part.number, e)))?;
+    span
+}
 
-        let part_nonce = derive_part_nonce(base_nonce, part.number);
-        let cursor = std::io::Cursor::new(encrypted_part);
-        let mut decrypt_reader = DecryptReader::new(WarpReader::new(cursor), key_bytes, part_nonce);
+/// Plaintext range of `part_number`, the SSE-C counterpart of [`HTTPRangeSpec::from_object_info`]
+fn sse_c_part_range(parts: &[ObjectPartInfo], part_number: usize) -> Option<HTTPRangeSpec> {
+    if part_number == 0 || part_number > parts.len() {
+        return None;
+    }
 
-        tokio::io::AsyncReadExt::read_to_end(&mut decrypt_reader, &mut plaintext)
-            .await
-            .map_err(|e| StorageError::other(format!("failed to decrypt multipart segment {}: {}", part.number, e)))?;
+    let start: i64 = parts[..part_number - 1].iter().map(|p| p.actual_size).sum();
+    Some(HTTPRangeSpec {
+        is_suffix_length: false,
+        start,
+        end: start + parts[part_number - 1].actual_size - 1,
+    })
+}
+
+/// Stream of plaintext bytes `[offset, offset + length)` of an SSE-C object.
+///
+/// Only the parts overlapping the range are fetched, and decrypted as they are read, each with its own nonce.
+#[allow(clippy::too_many_arguments)]
+async fn sse_c_range_reader(
+    store: &nebulafx_ecstore::store::ECStore,
+    bucket: &str,
+    key: &str,
+    info: &ObjectInfo,
+    opts: &ObjectOptions,
+    key_bytes: [u8; 32],
+    offset: usize,
+    length: i64,
+) -> Result<Box<dyn AsyncRead + Unpin + Send + Sync>, ApiError> {
+    let Some((parts, encrypted_start, skip)) = sse_c_part_span(&info.parts, offset, length) else {
+        return Ok(Box::new(InMemoryAsyncReader::new(Vec::new())));
+    };
+
+    let mut nonces = HashMap::with_capacity(parts.len());
+    for part in &parts {
+        nonces.insert(part.number, sse_c_part_nonce(bucket, key, &info.user_defined, part.number)?);
     }
 
-    let total_plain_size = plaintext.len() as i64;
-    let reader = Box::new(WarpReader::new(InMemoryAsyncReader::new(plaintext))) as Box<dyn Reader>;
+    let encrypted_len: usize = parts.iter().map(|p| p.size).sum();
+    let rs = HTTPRangeSpec {
+        is_suffix_length: false,
+        start: encrypted_start as i64,
+        end: (encrypted_start + encrypted_len) as i64 - 1,
+    };
+    let opts = ObjectOptions {
+        part_number: None,
+        ..opts.clone()
+    };
+    let reader = store
+        .get_object_reader(bucket, key, Some(rs), HeaderMap::new(), &opts)
+        .await
+        .map_err(ApiError::from)?;
+
+    let mut plaintext = decrypt_parts(reader.stream, &parts, key_bytes, |number| nonces[&number]);
 
-    Ok((reader, total_plain_size))
+    // The plaintext of the first part before the range, less than a part, is read and dropped
+    tokio::io::copy(&mut tokio::io::AsyncReadExt::take(&mut plaintext, skip as u64), &mut tokio::io::sink())
+        .await
+        .map_err(|e| ApiError::from(StorageError::other(format!("failed to decrypt SSE-C object: {e}"))))?;
+
+    Ok(Box::new(tokio::io::AsyncReadExt::take(plaintext, length.max(0) as u64)))
 }
 
 fn strip_managed_encryption_metadata(metadata: &mut HashMap<String, String>) {
@@ -516,18 +716,21 @@ impl S3 for FS {
 
         counter!("nebulafx_create_bucket_total").increment(1);
 
+        let lock_enabled = object_lock_enabled_for_bucket.is_some_and(|v| v);
         store
             .make_bucket(
                 &bucket,
                 &MakeBucketOptions {
                     force_create: false, // TODO: force support
-                    lock_enabled: object_lock_enabled_for_bucket.is_some_and(|v| v),
+                    lock_enabled,
                     ..Default::default()
                 },
             )
             .await
             .map_err(ApiError::from)?;
 
+        site_replication::get().make_bucket_hook(&bucket, lock_enabled).await;
+
         let output = CreateBucketOutput::default();
 
         let result = Ok(S3Response::new(output));
@@ -674,7 +877,11 @@ impl S3 for FS {
 
         src_info.put_object_reader = Some(PutObjReader::new(reader));
 
-        // check quota
+        if !cp_src_dst_same {
+            BucketQuotaSys::check_bucket_quota(&bucket, actual_size)
+                .await
+                .map_err(ApiError::from)?;
+        }
         // TODO: src metadata
 
         for (k, v) in compress_metadata {
@@ -688,6 +895,10 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
+        if !cp_src_dst_same {
+            BucketQuotaSys::record_write(&bucket, actual_size);
+        }
+
         // warn!("copy_object oi {:?}", &oi);
         let object_info = oi.clone();
         let copy_object_result = CopyObjectResult {
@@ -967,6 +1178,8 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
+        site_replication::get().delete_bucket_hook(&input.bucket).await;
+
         let result = Ok(S3Response::new(DeleteBucketOutput {}));
         let _ = helper.complete(&result);
         result
@@ -1474,7 +1687,7 @@ impl S3 for FS {
 
         let mut content_length = info.size;
 
-        let content_range = if let Some(rs) = &rs {
+        let mut content_range = if let Some(rs) = &rs {
             let total_size = info.get_actual_size().map_err(ApiError::from)?;
             let (start, length) = rs.get_offset_length(total_size).map_err(ApiError::from)?;
             content_length = length;
@@ -1497,79 +1710,46 @@ impl S3 for FS {
             req.input.sse_customer_key.is_some()
         );
 
+        let mut sse_c_plain_length: Option<i64> = None;
+
         if stored_sse_algorithm.is_some() {
             // Object was encrypted with SSE-C, so customer must provide matching key
             if let (Some(sse_key), Some(sse_key_md5_provided)) = (&req.input.sse_customer_key, &req.input.sse_customer_key_md5) {
-                // For true multipart objects (more than 1 part), SSE-C decryption is currently not fully implemented
-                // Each part needs to be decrypted individually, which requires storage layer changes
-                // Note: Single part objects also have info.parts.len() == 1, but they are not true multipart uploads
-                if info.parts.len() > 1 {
-                    warn!(
-                        "SSE-C multipart object detected with {} parts. Currently, multipart SSE-C upload parts are not encrypted during upload_part, so no decryption is needed during GET.",
-                        info.parts.len()
-                    );
-
-                    // Verify that the provided key MD5 matches the stored MD5 for security
-                    if let Some(stored_md5) = stored_sse_key_md5 {
-                        debug!("SSE-C MD5 comparison: provided='{}', stored='{}'", sse_key_md5_provided, stored_md5);
-                        if sse_key_md5_provided != stored_md5 {
-                            error!("SSE-C key MD5 mismatch: provided='{}', stored='{}'", sse_key_md5_provided, stored_md5);
-                            return Err(
-                                ApiError::from(StorageError::other("SSE-C key does not match object encryption key")).into()
-                            );
-                        }
-                    } else {
-                        return Err(ApiError::from(StorageError::other(
-                            "Object encrypted with SSE-C but stored key MD5 not found",
-                        ))
-                        .into());
+                // Verify that the provided key MD5 matches the stored MD5
+                if let Some(stored_md5) = stored_sse_key_md5 {
+                    debug!("SSE-C MD5 comparison: provided='{}', stored='{}'", sse_key_md5_provided, stored_md5);
+                    if sse_key_md5_provided != stored_md5 {
+                        error!("SSE-C key MD5 mismatch: provided='{}', stored='{}'", sse_key_md5_provided, stored_md5);
+                        return Err(ApiError::from(StorageError::other("SSE-C key does not match object encryption key")).into());
                     }
-
-                    // Since upload_part currently doesn't encrypt the data (SSE-C code is commented out),
-                    // we don't need to decrypt it either. Just return the data as-is.
-                    // TODO: Implement proper multipart SSE-C encryption/decryption
                 } else {
-                    // Verify that the provided key MD5 matches the stored MD5
-                    if let Some(stored_md5) = stored_sse_key_md5 {
-                        debug!("SSE-C MD5 comparison: provided='{}', stored='{}'", sse_key_md5_provided, stored_md5);
-                        if sse_key_md5_provided != stored_md5 {
-                            error!("SSE-C key MD5 mismatch: provided='{}', stored='{}'", sse_key_md5_provided, stored_md5);
-                            return Err(
-                                ApiError::from(StorageError::other("SSE-C key does not match object encryption key")).into()
-                            );
-                        }
-                    } else {
-                        return Err(ApiError::from(StorageError::other(
-                            "Object encrypted with SSE-C but stored key MD5 not found",
-                        ))
-                        .into());
-                    }
-
-                    // Decode the base64 key
-                    let key_bytes = BASE64_STANDARD
-                        .decode(sse_key)
-                        .map_err(|e| ApiError::from(StorageError::other(format!("Invalid SSE-C key: {e}"))))?;
-
-                    // Verify key length (should be 32 bytes for AES-256)
-                    if key_bytes.len() != 32 {
-                        return Err(ApiError::from(StorageError::other("SSE-C key must be 32 bytes")).into());
-                    }
+                    return Err(
+                        ApiError::from(StorageError::other("Object encrypted with SSE-C but stored key MD5 not found")).into(),
+                    );
+                }
 
-                    // Convert Vec<u8> to [u8; 32]
-                    let mut key_array = [0u8; 32];
-                    key_array.copy_from_slice(&key_bytes[..32]);
+                let key_array = parse_sse_customer_key(sse_key, sse_key_md5_provided)?;
 
-                    // Verify MD5 hash of the key matches what we expect
-                    let computed_md5 = format!("{:x}", md5::compute(&key_bytes));
-                    if computed_md5 != *sse_key_md5_provided {
-                        return Err(ApiError::from(StorageError::other("SSE-C key MD5 mismatch")).into());
+                // Multipart uploads seal every part with its own nonce, even an upload of a single part,
+                // and a range has to be mapped onto the parts it covers before anything can be decrypted
+                if info.user_defined.contains_key(SSEC_MULTIPART_IV) || rs.is_some() {
+                    let plain_size: i64 = info.parts.iter().map(|p| p.actual_size).sum();
+                    let plain_rs = match part_number {
+                        Some(part_number) => sse_c_part_range(&info.parts, part_number),
+                        None => rs.clone(),
+                    };
+                    let (offset, length) = match &plain_rs {
+                        Some(plain_rs) => plain_rs.get_offset_length(plain_size).map_err(ApiError::from)?,
+                        None => (0, plain_size),
+                    };
+                    if plain_rs.is_some() {
+                        content_range = Some(format!("bytes {}-{}/{}", offset, offset as i64 + length - 1, plain_size));
                     }
 
-                    // Generate the same deterministic nonce from object key
-                    let mut nonce = [0u8; 12];
-                    let nonce_source = format!("{bucket}-{key}");
-                    let nonce_hash = md5::compute(nonce_source.as_bytes());
-                    nonce.copy_from_slice(&nonce_hash.0[..12]);
+                    final_stream = sse_c_range_reader(&store, &bucket, &key, &info, &opts, key_array, offset, length).await?;
+                    sse_c_plain_length = Some(length);
+                } else {
+                    let nonce = sse_c_object_nonce(&bucket, &key);
 
                     // Apply decryption
                     // We need to wrap the stream in a Reader first since DecryptReader expects a Reader
@@ -1588,10 +1768,10 @@ impl S3 for FS {
             if let Some((key_bytes, nonce, original_size)) =
                 decrypt_managed_encryption_key(&bucket, &key, &info.user_defined).await?
             {
-                if info.parts.len() > 1 {
-                    let (reader, plain_size) = decrypt_multipart_managed_stream(final_stream, &info.parts, key_bytes, nonce)
-                        .await
-                        .map_err(ApiError::from)?;
+                let recorded_nonces = recorded_part_nonces(&info.user_defined)?;
+                if info.parts.len() > 1 || !recorded_nonces.is_empty() {
+                    let (reader, plain_size) =
+                        decrypt_multipart_managed_stream(final_stream, &info.parts, key_bytes, nonce, &recorded_nonces);
                     final_stream = reader;
                     managed_original_size = Some(plain_size);
                 } else {
@@ -1605,7 +1785,9 @@ impl S3 for FS {
         }
 
         // For SSE-C encrypted objects, use the original size instead of encrypted size
-        let response_content_length = if stored_sse_algorithm.is_some() {
+        let response_content_length = if let Some(length) = sse_c_plain_length {
+            length
+        } else if stored_sse_algorithm.is_some() {
             if let Some(original_size_str) = info.user_defined.get("x-amz-server-side-encryption-customer-original-size") {
                 let original_size = original_size_str.parse::<i64>().unwrap_or(content_length);
                 info!(
@@ -2215,6 +2397,10 @@ impl S3 for FS {
 
         let store = get_validated_store(&bucket).await?;
 
+        BucketQuotaSys::check_bucket_quota(&bucket, size)
+            .await
+            .map_err(ApiError::from)?;
+
         // TDD: Get bucket default encryption configuration
         let bucket_sse_config = metadata_sys::get_sse_config(&bucket).await.ok();
         debug!("TDD: bucket_sse_config={:?}", bucket_sse_config);
@@ -2333,25 +2519,7 @@ impl S3 for FS {
         if let (Some(_), Some(sse_key), Some(sse_key_md5_provided)) =
             (&sse_customer_algorithm, &sse_customer_key, &sse_customer_key_md5)
         {
-            // Decode the base64 key
-            let key_bytes = BASE64_STANDARD
-                .decode(sse_key)
-                .map_err(|e| ApiError::from(StorageError::other(format!("Invalid SSE-C key: {e}"))))?;
-
-            // Verify key length (should be 32 bytes for AES-256)
-            if key_bytes.len() != 32 {
-                return Err(ApiError::from(StorageError::other("SSE-C key must be 32 bytes")).into());
-            }
-
-            // Convert Vec<u8> to [u8; 32]
-            let mut key_array = [0u8; 32];
-            key_array.copy_from_slice(&key_bytes[..32]);
-
-            // Verify MD5 hash of the key
-            let computed_md5 = format!("{:x}", md5::compute(&key_bytes));
-            if computed_md5 != *sse_key_md5_provided {
-                return Err(ApiError::from(StorageError::other("SSE-C key MD5 mismatch")).into());
-            }
+            let key_array = parse_sse_customer_key(sse_key, sse_key_md5_provided)?;
 
             // Store original size for later retrieval during decryption
             let original_size = if size >= 0 { size } else { actual_size };
@@ -2360,14 +2528,8 @@ impl S3 for FS {
                 original_size.to_string(),
             );
 
-            // Generate a deterministic nonce from object key for consistency
-            let mut nonce = [0u8; 12];
-            let nonce_source = format!("{bucket}-{key}");
-            let nonce_hash = md5::compute(nonce_source.as_bytes());
-            nonce.copy_from_slice(&nonce_hash.0[..12]);
-
             // Apply encryption
-            let encrypt_reader = EncryptReader::new(reader, key_array, nonce);
+            let encrypt_reader = EncryptReader::new(reader, key_array, sse_c_object_nonce(&bucket, &key));
             reader = HashReader::new(Box::new(encrypt_reader), -1, actual_size, None, None, false).map_err(ApiError::from)?;
         }
 
@@ -2403,6 +2565,7 @@ impl S3 for FS {
             .put_object(&bucket, &key, &mut reader, &opts)
             .await
             .map_err(ApiError::from)?;
+        BucketQuotaSys::record_write(&bucket, actual_size);
         let e_tag = obj_info.etag.clone().map(|etag| to_s3s_etag(&etag));
 
         let repoptions =
@@ -2480,6 +2643,7 @@ impl S3 for FS {
             storage_class,
             server_side_encryption,
             sse_customer_algorithm,
+            sse_customer_key,
             sse_customer_key_md5,
             ssekms_key_id,
             ..
@@ -2543,10 +2707,19 @@ impl S3 for FS {
 
         // Store effective SSE information in metadata for multipart upload
         if let Some(sse_alg) = &sse_customer_algorithm {
+            let (Some(sse_key), Some(sse_key_md5)) = (&sse_customer_key, &sse_customer_key_md5) else {
+                return Err(s3_error!(InvalidArgument, "SSE-C requires both the customer key and its MD5"));
+            };
+            parse_sse_customer_key(sse_key, sse_key_md5)?;
+
             metadata.insert(
                 "x-amz-server-side-encryption-customer-algorithm".to_string(),
                 sse_alg.as_str().to_string(),
             );
+
+            // Parts are sealed with nonces derived from this one, see `upload_part`
+            let base_nonce = &Uuid::new_v4().into_bytes()[..12];
+            metadata.insert(SSEC_MULTIPART_IV.to_string(), BASE64_STANDARD.encode(base_nonce));
         }
         if let Some(sse_md5) = &sse_customer_key_md5 {
             metadata.insert("x-amz-server-side-encryption-customer-key-md5".to_string(), sse_md5.clone());
@@ -2564,7 +2737,8 @@ impl S3 for FS {
             metadata.insert("x-amz-server-side-encryption-aws-kms-key-id".to_string(), kms_key_id.clone());
         }
 
-        if is_compressible(&req.headers, &key) {
+        // Compressed parts would be decompressed by the storage layer before they are decrypted
+        if is_compressible(&req.headers, &key) && sse_customer_algorithm.is_none() {
             metadata.insert(
                 format!("{RESERVED_METADATA_PREFIX_LOWER}compression"),
                 CompressionAlgorithm::default().to_string(),
@@ -2600,6 +2774,7 @@ impl S3 for FS {
             upload_id: Some(upload_id),
             server_side_encryption: effective_sse, // TDD: Return effective encryption config
             sse_customer_algorithm,
+            sse_customer_key_md5,
             ssekms_key_id: effective_kms_key_id, // TDD: Return effective KMS key ID
             checksum_algorithm: checksum_algo.map(ChecksumAlgorithm::from),
             checksum_type: checksum_type.map(ChecksumType::from),
@@ -2621,9 +2796,9 @@ impl S3 for FS {
             upload_id,
             part_number,
             content_length,
-            sse_customer_algorithm: _sse_customer_algorithm,
-            sse_customer_key: _sse_customer_key,
-            sse_customer_key_md5: _sse_customer_key_md5,
+            sse_customer_algorithm,
+            sse_customer_key,
+            sse_customer_key_md5,
             // content_md5,
             ..
         } = input;
@@ -2667,20 +2842,30 @@ impl S3 for FS {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
-        let opts = ObjectOptions::default();
+        let mut opts = ObjectOptions::default();
         let fi = store
             .get_multipart_info(&bucket, &key, &upload_id, &opts)
             .await
             .map_err(ApiError::from)?;
 
+        // Every part of an SSE-C upload must carry the key the upload was created with
+        if sse_customer_algorithm.is_some()
+            != fi
+                .user_defined
+                .contains_key("x-amz-server-side-encryption-customer-algorithm")
+        {
+            return Err(s3_error!(InvalidRequest, "SSE-C parameters do not match the multipart upload"));
+        }
+        let sse_customer_key = sse_customer_key_for(&fi.user_defined, sse_customer_key.as_ref(), sse_customer_key_md5.as_ref())?;
+
         // Check if managed encryption will be applied
         let will_apply_managed_encryption = decrypt_managed_encryption_key(&bucket, &key, &fi.user_defined)
             .await?
             .is_some();
 
-        // If managed encryption will be applied, and we have Content-Length, buffer the entire body
+        // If encryption will be applied, and we have Content-Length, buffer the entire body
         // This is necessary because encryption changes the data size, which causes Content-Length mismatches
-        if will_apply_managed_encryption && size.is_some() {
+        if (will_apply_managed_encryption || sse_customer_key.is_some()) && size.is_some() {
             let mut total = 0i64;
             let mut buffer = bytes::BytesMut::new();
             while let Some(chunk) = body_stream.next().await {
@@ -2701,6 +2886,11 @@ impl S3 for FS {
 
         let mut size = size.ok_or_else(|| s3_error!(UnexpectedContent))?;
 
+        // Parts are only counted against the quota once the upload completes
+        BucketQuotaSys::check_bucket_quota(&bucket, size)
+            .await
+            .map_err(ApiError::from)?;
+
         let body = StreamReader::new(body_stream.map(|f| f.map_err(|e| std::io::Error::other(e.to_string()))));
 
         // mc cp step 4
@@ -2713,46 +2903,6 @@ impl S3 for FS {
 
         let actual_size = size;
 
-        // TODO: Apply SSE-C encryption for upload_part if needed
-        // Temporarily commented out to debug multipart issues
-        /*
-        // Apply SSE-C encryption if customer provided key before any other processing
-        if let (Some(_), Some(sse_key), Some(sse_key_md5_provided)) =
-            (&_sse_customer_algorithm, &_sse_customer_key, &_sse_customer_key_md5) {
-
-            // Decode the base64 key
-            let key_bytes = BASE64_STANDARD.decode(sse_key)
-                .map_err(|e| ApiError::from(StorageError::other(format!("Invalid SSE-C key: {}", e))))?;
-
-            // Verify key length (should be 32 bytes for AES-256)
-            if key_bytes.len() != 32 {
-                return Err(ApiError::from(StorageError::other("SSE-C key must be 32 bytes")).into());
-            }
-
-            // Convert Vec<u8> to [u8; 32]
-            let mut key_array = [0u8; 32];
-            key_array.copy_from_slice(&key_bytes[..32]);
-
-            // Verify MD5 hash of the key
-            let computed_md5 = format!("{:x}", md5::compute(&key_bytes));
-            if computed_md5 != *sse_key_md5_provided {
-                return Err(ApiError::from(StorageError::other("SSE-C key MD5 mismatch")).into());
-            }
-
-            // Generate a deterministic nonce from object key for consistency
-            let mut nonce = [0u8; 12];
-            let nonce_source = format!("{}-{}", bucket, key);
-            let nonce_hash = md5::compute(nonce_source.as_bytes());
-            nonce.copy_from_slice(&nonce_hash.0[..12]);
-
-            // Apply encryption - this will change the size so we need to handle it
-            let encrypt_reader = EncryptReader::new(reader, key_array, nonce);
-            reader = Box::new(encrypt_reader);
-            // When encrypting, size becomes unknown since encryption adds authentication tags
-            size = -1;
-        }
-        */
-
         let mut md5hex = if let Some(base64_md5) = input.content_md5 {
             let md5 = base64_simd::STANDARD
                 .decode_to_vec(base64_md5.as_bytes())
@@ -2784,8 +2934,16 @@ impl S3 for FS {
             return Err(ApiError::from(StorageError::other(format!("add_checksum error={err:?}"))).into());
         }
 
-        if let Some((key_bytes, base_nonce, _)) = decrypt_managed_encryption_key(&bucket, &key, &fi.user_defined).await? {
-            let part_nonce = derive_part_nonce(base_nonce, part_id);
+        // Every upload of a part is sealed under a fresh nonce, recorded in the part metadata, so that
+        // uploading a part number again does not reuse the nonce of the previous upload
+        if let Some((key_bytes, _, _)) = decrypt_managed_encryption_key(&bucket, &key, &fi.user_defined).await? {
+            let part_nonce = new_part_nonce(&mut opts);
+            let encrypt_reader = EncryptReader::new(reader, key_bytes, part_nonce);
+            reader = HashReader::new(Box::new(encrypt_reader), -1, actual_size, None, None, false).map_err(ApiError::from)?;
+        }
+
+        if let Some(key_bytes) = sse_customer_key {
+            let part_nonce = new_part_nonce(&mut opts);
             let encrypt_reader = EncryptReader::new(reader, key_bytes, part_nonce);
             reader = HashReader::new(Box::new(encrypt_reader), -1, actual_size, None, None, false).map_err(ApiError::from)?;
         }
@@ -2837,6 +2995,8 @@ impl S3 for FS {
             checksum_sha256,
             checksum_crc64nvme,
             e_tag: info.etag.map(|etag| to_s3s_etag(&etag)),
+            sse_customer_algorithm,
+            sse_customer_key_md5,
             ..Default::default()
         };
 
@@ -2854,6 +3014,11 @@ impl S3 for FS {
             upload_id,
             copy_source_if_match,
             copy_source_if_none_match,
+            copy_source_sse_customer_key,
+            copy_source_sse_customer_key_md5,
+            sse_customer_algorithm,
+            sse_customer_key,
+            sse_customer_key_md5,
             ..
         } = req.input;
 
@@ -2908,6 +3073,22 @@ impl S3 for FS {
 
         let mut src_info = src_reader.object_info;
 
+        // An SSE-C source is decrypted with the copy-source key, an SSE-C upload sealed with its own key
+        let src_sse_c_key = sse_customer_key_for(
+            &src_info.user_defined,
+            copy_source_sse_customer_key.as_ref(),
+            copy_source_sse_customer_key_md5.as_ref(),
+        )?;
+        if sse_customer_algorithm.is_some()
+            != mp_info
+                .user_defined
+                .contains_key("x-amz-server-side-encryption-customer-algorithm")
+        {
+            return Err(s3_error!(InvalidRequest, "SSE-C parameters do not match the multipart upload"));
+        }
+        let dst_sse_c_key =
+            sse_customer_key_for(&mp_info.user_defined, sse_customer_key.as_ref(), sse_customer_key_md5.as_ref())?;
+
         // Validate copy conditions (simplified for now)
         if let Some(if_match) = copy_source_if_match {
             if let Some(ref etag) = src_info.etag {
@@ -2933,10 +3114,12 @@ impl S3 for FS {
         // Calculate actual range and length
         // Note: These values are used implicitly through the range specification (rs)
         // passed to get_object_reader, which handles the offset and length internally
-        let (_start_offset, length) = if let Some(ref range_spec) = rs {
+        let src_sse_c_size: i64 = src_info.parts.iter().map(|p| p.actual_size).sum();
+        let (start_offset, length) = if let Some(ref range_spec) = rs {
             // For range validation, use the actual logical size of the file
             // For compressed files, this means using the uncompressed size
             let validation_size = match src_info.is_compressed_ok() {
+                _ if src_sse_c_key.is_some() => src_sse_c_size,
                 Ok((_, true)) => {
                     // For compressed files, use actual uncompressed size for range validation
                     src_info.get_actual_size().unwrap_or(src_info.size)
@@ -2950,13 +3133,14 @@ impl S3 for FS {
             range_spec
                 .get_offset_length(validation_size)
                 .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidRange, e.to_string()))?
+        } else if src_sse_c_key.is_some() {
+            (0, src_sse_c_size)
         } else {
             (0, src_info.size)
         };
 
         // Create a new reader from the source data with the correct range
         // We need to re-read from the source with the correct range specification
-        let h = HeaderMap::new();
         let get_opts = ObjectOptions {
             version_id: src_opts.version_id.clone(),
             versioned: src_opts.versioned,
@@ -2965,13 +3149,15 @@ impl S3 for FS {
         };
 
         // Get the source object reader once with the validated range
-        let src_reader = store
-            .get_object_reader(&src_bucket, &src_key, rs.clone(), h, &get_opts)
-            .await
-            .map_err(ApiError::from)?;
-
-        // Use the same reader for streaming
-        let src_stream = src_reader.stream;
+        let src_stream = if let Some(key_bytes) = src_sse_c_key {
+            sse_c_range_reader(&store, &src_bucket, &src_key, &src_info, &get_opts, key_bytes, start_offset, length).await?
+        } else {
+            store
+                .get_object_reader(&src_bucket, &src_key, rs.clone(), HeaderMap::new(), &get_opts)
+                .await
+                .map_err(ApiError::from)?
+                .stream
+        };
 
         // Check if compression is enabled for this multipart upload
         let is_compressible = mp_info
@@ -3000,19 +3186,25 @@ impl S3 for FS {
 
         let mut reader = HashReader::new(reader, size, actual_size, None, None, false).map_err(ApiError::from)?;
 
-        if let Some((key_bytes, base_nonce, _)) = decrypt_managed_encryption_key(&bucket, &key, &mp_info.user_defined).await? {
-            let part_nonce = derive_part_nonce(base_nonce, part_id);
+        // Set up destination options (inherit from multipart upload)
+        let mut dst_opts = ObjectOptions {
+            user_defined: mp_info.user_defined.clone(),
+            ..Default::default()
+        };
+
+        if let Some((key_bytes, _, _)) = decrypt_managed_encryption_key(&bucket, &key, &mp_info.user_defined).await? {
+            let part_nonce = new_part_nonce(&mut dst_opts);
             let encrypt_reader = EncryptReader::new(reader, key_bytes, part_nonce);
             reader = HashReader::new(Box::new(encrypt_reader), -1, actual_size, None, None, false).map_err(ApiError::from)?;
         }
 
-        let mut reader = PutObjReader::new(reader);
+        if let Some(key_bytes) = dst_sse_c_key {
+            let part_nonce = new_part_nonce(&mut dst_opts);
+            let encrypt_reader = EncryptReader::new(reader, key_bytes, part_nonce);
+            reader = HashReader::new(Box::new(encrypt_reader), -1, actual_size, None, None, false).map_err(ApiError::from)?;
+        }
 
-        // Set up destination options (inherit from multipart upload)
-        let dst_opts = ObjectOptions {
-            user_defined: mp_info.user_defined.clone(),
-            ..Default::default()
-        };
+        let mut reader = PutObjReader::new(reader);
 
         // Write the copied data as a new part
         let part_info = store
@@ -3030,6 +3222,8 @@ impl S3 for FS {
         let output = UploadPartCopyOutput {
             copy_part_result: Some(copy_part_result),
             copy_source_version_id: src_version_id,
+            sse_customer_algorithm,
+            sse_customer_key_md5,
             ..Default::default()
         };
 
@@ -3220,6 +3414,15 @@ impl S3 for FS {
         info!("TDD: Got multipart info successfully");
         info!("TDD: Multipart info metadata: {:?}", multipart_info.user_defined);
 
+        // The customer key is optional here, but when sent it must be the one the parts were sealed with
+        if input.sse_customer_key.is_some() {
+            sse_customer_key_for(
+                &multipart_info.user_defined,
+                input.sse_customer_key.as_ref(),
+                input.sse_customer_key_md5.as_ref(),
+            )?;
+        }
+
         // TDD: Extract encryption information from multipart upload metadata
         let server_side_encryption = multipart_info
             .user_defined
@@ -3241,11 +3444,30 @@ impl S3 for FS {
             server_side_encryption, ssekms_key_id
         );
 
+        // Listing the parts reads their metadata, it is only needed to check a hard quota
+        if BucketQuotaSys::hard_quota(&bucket).await.is_some() {
+            let listed_parts = store
+                .list_object_parts(&bucket, &key, &upload_id, None, MAX_PARTS_COUNT, &ObjectOptions::default())
+                .await
+                .map_err(ApiError::from)?;
+            let upload_size: i64 = listed_parts
+                .parts
+                .iter()
+                .filter(|p| uploaded_parts.iter().any(|u| u.part_num == p.part_num))
+                .map(|p| p.actual_size)
+                .sum();
+
+            BucketQuotaSys::check_bucket_quota(&bucket, upload_size)
+                .await
+                .map_err(ApiError::from)?;
+        }
+
         let obj_info = store
             .clone()
             .complete_multipart_upload(&bucket, &key, &upload_id, uploaded_parts, opts)
             .await
             .map_err(ApiError::from)?;
+        BucketQuotaSys::record_write(&bucket, obj_info.get_actual_size().unwrap_or(obj_info.size));
 
         info!(
             "TDD: Creating output with SSE: {:?}, KMS Key: {:?}",
@@ -3395,10 +3617,14 @@ impl S3 for FS {
 
         let data = try_!(serialize(&tagging));
 
-        metadata_sys::update(&bucket, BUCKET_TAGGING_CONFIG, data)
+        metadata_sys::update(&bucket, BUCKET_TAGGING_CONFIG, data.clone())
             .await
             .map_err(ApiError::from)?;
 
+        site_replication::get()
+            .bucket_meta_hook(&bucket, BUCKET_TAGGING_CONFIG, Some(data))
+            .await;
+
         Ok(S3Response::new(Default::default()))
     }
 
@@ -3413,6 +3639,10 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
+        site_replication::get()
+            .bucket_meta_hook(&bucket, BUCKET_TAGGING_CONFIG, None)
+            .await;
+
         Ok(S3Response::new(DeleteBucketTaggingOutput {}))
     }
 
@@ -3562,17 +3792,30 @@ impl S3 for FS {
         } = req.input;
 
         // TODO: check other sys
-        // check site replication enable
         // check bucket object lock enable
         // check replication suspended
 
+        // Site replication relies on versioning, buckets must stay versioned while it is enabled
+        let suspending = versioning_configuration
+            .status
+            .as_ref()
+            .is_some_and(|status| status.as_str() == BucketVersioningStatus::SUSPENDED);
+        if suspending && site_replication::get().is_enabled().await {
+            return Err(s3_error!(
+                InvalidBucketState,
+                "versioning cannot be suspended while site replication is enabled"
+            ));
+        }
+
         let data = try_!(serialize(&versioning_configuration));
 
-        metadata_sys::update(&bucket, BUCKET_VERSIONING_CONFIG, data)
+        metadata_sys::update(&bucket, BUCKET_VERSIONING_CONFIG, data.clone())
             .await
             .map_err(ApiError::from)?;
 
-        // TODO: globalSiteReplicationSys.BucketMetaHook
+        site_replication::get()
+            .bucket_meta_hook(&bucket, BUCKET_VERSIONING_CONFIG, Some(data))
+            .await;
 
         Ok(S3Response::new(PutBucketVersioningOutput {}))
     }
@@ -3599,6 +3842,7 @@ impl S3 for FS {
             action: Action::S3Action(S3Action::ListBucketAction),
             is_owner: false,
             account: "",
+            parent_user: "",
             groups: &None,
             conditions: &conditions,
             object: "",
@@ -3610,6 +3854,7 @@ impl S3 for FS {
             action: Action::S3Action(S3Action::PutObjectAction),
             is_owner: false,
             account: "",
+            parent_user: "",
             groups: &None,
             conditions: &conditions,
             object: "",
@@ -3627,6 +3872,81 @@ impl S3 for FS {
         Ok(S3Response::new(output))
     }
 
+    async fn get_bucket_cors(&self, req: S3Request<GetBucketCorsInput>) -> S3Result<S3Response<GetBucketCorsOutput>> {
+        let GetBucketCorsInput { bucket, .. } = req.input;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+
+        let cfg = match metadata_sys::get_cors_config(&bucket).await {
+            Ok((cfg, _)) => cfg,
+            Err(err) => {
+                if StorageError::ConfigNotFound == err {
+                    return Err(s3_error!(NoSuchCORSConfiguration));
+                }
+                return Err(ApiError::from(err).into());
+            }
+        };
+
+        Ok(S3Response::new(GetBucketCorsOutput {
+            cors_rules: Some(cfg.cors_rules),
+        }))
+    }
+
+    async fn put_bucket_cors(&self, req: S3Request<PutBucketCorsInput>) -> S3Result<S3Response<PutBucketCorsOutput>> {
+        let PutBucketCorsInput {
+            bucket,
+            cors_configuration,
+            ..
+        } = req.input;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+
+        if let Err(err) = cors_configuration.validate() {
+            return Err(S3Error::with_message(S3ErrorCode::MalformedXML, err.to_string()));
+        }
+
+        let data = try_!(serialize(&cors_configuration));
+
+        metadata_sys::update(&bucket, BUCKET_CORS_CONFIG, data)
+            .await
+            .map_err(ApiError::from)?;
+
+        Ok(S3Response::new(PutBucketCorsOutput::default()))
+    }
+
+    async fn delete_bucket_cors(&self, req: S3Request<DeleteBucketCorsInput>) -> S3Result<S3Response<DeleteBucketCorsOutput>> {
+        let DeleteBucketCorsInput { bucket, .. } = req.input;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+
+        metadata_sys::delete(&bucket, BUCKET_CORS_CONFIG)
+            .await
+            .map_err(ApiError::from)?;
+
+        Ok(S3Response::new(DeleteBucketCorsOutput::default()))
+    }
+
     async fn get_bucket_policy(&self, req: S3Request<GetBucketPolicyInput>) -> S3Result<S3Response<GetBucketPolicyOutput>> {
         let GetBucketPolicyInput { bucket, .. } = req.input;
 
@@ -3678,10 +3998,14 @@ impl S3 for FS {
 
         let data = serde_json::to_vec(&cfg).map_err(|e| s3_error!(InternalError, "parse policy failed {:?}", e))?;
 
-        metadata_sys::update(&bucket, BUCKET_POLICY_CONFIG, data)
+        metadata_sys::update(&bucket, BUCKET_POLICY_CONFIG, data.clone())
             .await
             .map_err(ApiError::from)?;
 
+        site_replication::get()
+            .bucket_meta_hook(&bucket, BUCKET_POLICY_CONFIG, Some(data))
+            .await;
+
         Ok(S3Response::new(PutBucketPolicyOutput {}))
     }
 
@@ -3704,6 +4028,10 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
+        site_replication::get()
+            .bucket_meta_hook(&bucket, BUCKET_POLICY_CONFIG, None)
+            .await;
+
         Ok(S3Response::new(DeleteBucketPolicyOutput {}))
     }
 
@@ -3767,10 +4095,14 @@ impl S3 for FS {
         }
 
         let data = try_!(serialize(&input_cfg));
-        metadata_sys::update(&bucket, BUCKET_LIFECYCLE_CONFIG, data)
+        metadata_sys::update(&bucket, BUCKET_LIFECYCLE_CONFIG, data.clone())
             .await
             .map_err(ApiError::from)?;
 
+        site_replication::get()
+            .bucket_meta_hook(&bucket, BUCKET_LIFECYCLE_CONFIG, Some(data))
+            .await;
+
         Ok(S3Response::new(PutBucketLifecycleConfigurationOutput::default()))
     }
 
@@ -3794,6 +4126,10 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
+        site_replication::get()
+            .bucket_meta_hook(&bucket, BUCKET_LIFECYCLE_CONFIG, None)
+            .await;
+
         Ok(S3Response::new(DeleteBucketLifecycleOutput::default()))
     }
 
@@ -3852,9 +4188,13 @@ impl S3 for FS {
         // TODO: check kms
 
         let data = try_!(serialize(&server_side_encryption_configuration));
-        metadata_sys::update(&bucket, BUCKET_SSECONFIG, data)
+        metadata_sys::update(&bucket, BUCKET_SSECONFIG, data.clone())
             .await
             .map_err(ApiError::from)?;
+
+        site_replication::get()
+            .bucket_meta_hook(&bucket, BUCKET_SSECONFIG, Some(data))
+            .await;
         Ok(S3Response::new(PutBucketEncryptionOutput::default()))
     }
 
@@ -3876,6 +4216,10 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
+        site_replication::get()
+            .bucket_meta_hook(&bucket, BUCKET_SSECONFIG, None)
+            .await;
+
         Ok(S3Response::new(DeleteBucketEncryptionOutput::default()))
     }
 
@@ -3932,10 +4276,14 @@ impl S3 for FS {
 
         let data = try_!(serialize(&input_cfg));
 
-        metadata_sys::update(&bucket, OBJECT_LOCK_CONFIG, data)
+        metadata_sys::update(&bucket, OBJECT_LOCK_CONFIG, data.clone())
             .await
             .map_err(ApiError::from)?;
 
+        site_replication::get()
+            .bucket_meta_hook(&bucket, OBJECT_LOCK_CONFIG, Some(data))
+            .await;
+
         Ok(S3Response::new(PutObjectLockConfigurationOutput::default()))
     }
 
@@ -4341,64 +4689,38 @@ impl S3 for FS {
 
         let input = Arc::new(req.input);
         info!("{:?}", input);
+        validate_select_input(&input)?;
 
         let db = get_global_db((*input).clone(), false).await.map_err(|e| {
             error!("get global db failed, {}", e.to_string());
             s3_error!(InternalError, "{}", e.to_string())
         })?;
-        let query = Query::new(Context { input: input.clone() }, input.request.expression.clone());
-        let result = db
-            .execute(&query)
-            .await
-            .map_err(|e| s3_error!(InternalError, "{}", e.to_string()))?;
-
-        let results = result.result().chunk_result().await.unwrap().to_vec();
-
-        let mut buffer = Vec::new();
-        if input.request.output_serialization.csv.is_some() {
-            let mut csv_writer = CsvWriterBuilder::new().with_header(false).build(&mut buffer);
-            for batch in results {
-                csv_writer
-                    .write(&batch)
-                    .map_err(|e| s3_error!(InternalError, "can't encode output to csv. e: {}", e.to_string()))?;
-            }
+        let format = if input.request.output_serialization.csv.is_some() {
+            SelectOutputFormat::Csv
         } else if input.request.output_serialization.json.is_some() {
-            let mut json_writer = JsonWriterBuilder::new()
-                .with_explicit_nulls(true)
-                .build::<_, JsonArray>(&mut buffer);
-            for batch in results {
-                json_writer
-                    .write(&batch)
-                    .map_err(|e| s3_error!(InternalError, "can't encode output to json. e: {}", e.to_string()))?;
-            }
-            json_writer
-                .finish()
-                .map_err(|e| s3_error!(InternalError, "writer output into json error, e: {}", e.to_string()))?;
+            SelectOutputFormat::Json
         } else {
             return Err(s3_error!(
                 InvalidArgument,
                 "Unsupported output format. Supported formats are CSV and JSON"
             ));
-        }
-
-        let (tx, rx) = mpsc::channel::<S3Result<SelectObjectContentEvent>>(2);
-        let stream = ReceiverStream::new(rx);
-        tokio::spawn(async move {
-            let _ = tx
-                .send(Ok(SelectObjectContentEvent::Cont(ContinuationEvent::default())))
-                .await;
-            let _ = tx
-                .send(Ok(SelectObjectContentEvent::Records(RecordsEvent {
-                    payload: Some(Bytes::from(buffer)),
-                })))
-                .await;
-            let _ = tx.send(Ok(SelectObjectContentEvent::End(EndEvent::default()))).await;
-
-            drop(tx);
-        });
+        };
+        let request_progress = input
+            .request
+            .request_progress
+            .as_ref()
+            .and_then(|p| p.enabled)
+            .unwrap_or(false);
+
+        let query = Query::new(Context::new(input.clone()), input.request.expression.clone());
+        let stats = query.context().stats.clone();
+        let result = db
+            .execute(&query)
+            .await
+            .map_err(|e| s3_error!(InternalError, "{}", e.to_string()))?;
 
         Ok(S3Response::new(SelectObjectContentOutput {
-            payload: Some(SelectObjectContentEventStream::new(stream)),
+            payload: Some(select_event_stream(result.result(), format, stats, request_progress)),
         }))
     }
     async fn get_object_legal_hold(
@@ -4724,4 +5046,174 @@ pub(crate) async fn has_replication_rules(bucket: &str, objects: &[ObjectToDelet
     false
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(number: usize, size: usize, actual_size: i64) -> ObjectPartInfo {
+        ObjectPartInfo {
+            number,
+            size,
+            actual_size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sse_c_part_span() {
+        let parts = vec![part(1, 120, 100), part(2, 120, 100), part(3, 60, 50)];
+
+        // Range within the second part
+        let (selected, encrypted_start, skip) = sse_c_part_span(&parts, 110, 20).unwrap();
+        assert_eq!(selected.iter().map(|p| p.number).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(encrypted_start, 120);
+        assert_eq!(skip, 10);
+
+        // Range crossing from the first into the third part
+        let (selected, encrypted_start, skip) = sse_c_part_span(&parts, 90, 120).unwrap();
+        assert_eq!(selected.iter().map(|p| p.number).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(encrypted_start, 0);
+        assert_eq!(skip, 90);
+
+        assert!(sse_c_part_span(&parts, 250, 10).is_none());
+    }
+
+    #[test]
+    fn test_sse_c_part_range() {
+        let parts = vec![part(1, 120, 100), part(2, 120, 100), part(3, 60, 50)];
+
+        let rs = sse_c_part_range(&parts, 2).unwrap();
+        assert_eq!((rs.start, rs.end), (100, 199));
+        assert!(sse_c_part_range(&parts, 0).is_none());
+        assert!(sse_c_part_range(&parts, 4).is_none());
+    }
+    #[tokio::test]
+    async fn test_sse_c_single_part_upload_round_trip() {
+        use tokio::io::AsyncReadExt;
+
+        let key = [3u8; 32];
+        let base_nonce = [9u8; 12];
+        let data = b"a multipart upload of a single part".to_vec();
+        let metadata = HashMap::from([(SSEC_MULTIPART_IV.to_string(), BASE64_STANDARD.encode(base_nonce))]);
+
+        // UploadPart seals the only part of the upload with the nonce of part 1
+        let reader = WarpReader::new(std::io::Cursor::new(data.clone()));
+        let mut encrypted = Vec::new();
+        EncryptReader::new(reader, key, derive_part_nonce(base_nonce, 1))
+            .read_to_end(&mut encrypted)
+            .await
+            .unwrap();
+
+        let parts = vec![part(1, encrypted.len(), data.len() as i64)];
+        let nonce = sse_c_part_nonce("bucket", "object", &metadata, 1).unwrap();
+        assert_ne!(nonce, sse_c_object_nonce("bucket", "object"));
+
+        let stream: Box<dyn AsyncRead + Unpin + Send + Sync> = Box::new(std::io::Cursor::new(encrypted));
+        let mut decrypted = Vec::new();
+        decrypt_parts(stream, &parts, key, |_| nonce)
+            .read_to_end(&mut decrypted)
+            .await
+            .unwrap();
+        assert_eq!(decrypted, data);
+    }
+
+    #[tokio::test]
+    async fn test_sse_c_reuploaded_part_gets_a_new_nonce() {
+        use nebulafx_ecstore::disk::endpoint::Endpoint;
+        use nebulafx_ecstore::endpoints::{EndpointServerPools, Endpoints, PoolEndpoints};
+        use tokio::io::AsyncReadExt;
+
+        let base = std::env::temp_dir().join(format!("nebulafx_ecfs_part_nonce_{}", Uuid::new_v4()));
+        let mut endpoints = Vec::new();
+        for i in 0..4 {
+            let path = base.join(format!("disk{i}"));
+            tokio::fs::create_dir_all(&path).await.unwrap();
+            let mut endpoint = Endpoint::try_from(path.to_str().unwrap()).unwrap();
+            endpoint.set_pool_index(0);
+            endpoint.set_set_index(0);
+            endpoint.set_disk_index(i);
+            endpoints.push(endpoint);
+        }
+        let endpoint_pools = EndpointServerPools(vec![PoolEndpoints {
+            legacy: false,
+            set_count: 1,
+            drives_per_set: 4,
+            endpoints: Endpoints::from(endpoints),
+            cmd_line: "test".to_string(),
+            platform: String::new(),
+        }]);
+        nebulafx_ecstore::store::init_local_disks(endpoint_pools.clone()).await.unwrap();
+        let store = nebulafx_ecstore::store::ECStore::new(
+            "127.0.0.1:9000".parse().unwrap(),
+            endpoint_pools,
+            tokio_util::sync::CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+        nebulafx_ecstore::bucket::metadata_sys::init_bucket_metadata_sys(store.clone(), Vec::new()).await;
+        store.make_bucket("bucket", &MakeBucketOptions::default()).await.unwrap();
+
+        let key = [3u8; 32];
+        let metadata = HashMap::from([(SSEC_MULTIPART_IV.to_string(), BASE64_STANDARD.encode([9u8; 12]))]);
+        let upload = store
+            .new_multipart_upload(
+                "bucket",
+                "object",
+                &ObjectOptions {
+                    user_defined: metadata,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // Part 1 is uploaded twice with different contents, the way UploadPart seals it
+        let mut nonces = Vec::new();
+        let mut etag = None;
+        for data in [b"first upload of part 1".to_vec(), b"second upload of part 1".to_vec()] {
+            let mut opts = ObjectOptions::default();
+            let nonce = new_part_nonce(&mut opts);
+            let mut encrypted = Vec::new();
+            EncryptReader::new(WarpReader::new(std::io::Cursor::new(data)), key, nonce)
+                .read_to_end(&mut encrypted)
+                .await
+                .unwrap();
+            let info = store
+                .put_object_part("bucket", "object", &upload.upload_id, 1, &mut PutObjReader::from_vec(encrypted), &opts)
+                .await
+                .unwrap();
+            nonces.push(nonce);
+            etag = info.etag;
+        }
+        assert_ne!(nonces[0], nonces[1]);
+
+        let parts = vec![CompletePart {
+            part_num: 1,
+            etag,
+            ..Default::default()
+        }];
+        let info = store
+            .clone()
+            .complete_multipart_upload("bucket", "object", &upload.upload_id, parts, &ObjectOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(sse_c_part_nonce("bucket", "object", &info.user_defined, 1).unwrap(), nonces[1]);
+
+        let reader = store
+            .get_object_reader("bucket", "object", None, HeaderMap::new(), &ObjectOptions::default())
+            .await
+            .unwrap();
+        let mut decrypted = Vec::new();
+        decrypt_parts(reader.stream, &info.parts, key, |number| {
+            sse_c_part_nonce("bucket", "object", &info.user_defined, number).unwrap()
+        })
+        .read_to_end(&mut decrypted)
+        .await
+        .unwrap();
+        assert_eq!(decrypted, b"second upload of part 1");
+
+        let _ = tokio::fs::remove_dir_all(&base).await;
+    }
+}
+
  
@@ -5,4 +5,5 @@ pub mod ecfs;
 pub(crate) mod entity;
 pub(crate) mod helper;
 pub mod options;
+pub(crate) mod select;
 pub mod tonic_service;
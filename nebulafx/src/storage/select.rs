@@ -0,0 +1,241 @@
+
+
+//! Event stream of a `SelectObjectContent` response.
+//!
+//! Record batches are encoded as they come out of the query and sent in
+//! Records events of at most [`RECORDS_PAYLOAD_SIZE`] bytes. While the scan
+//! runs, a Progress event (when requested) or a Continuation keep-alive is sent
+//! every [`PROGRESS_INTERVAL`]. The stream ends with a Stats and an End event.
+
+use bytes::Bytes;
+use datafusion::arrow::{
+    csv::WriterBuilder as CsvWriterBuilder, json::WriterBuilder as JsonWriterBuilder, json::writer::LineDelimited,
+    record_batch::RecordBatch,
+};
+use futures::StreamExt;
+use nebulafx_s3select_api::query::{ScanStats, execution::Output};
+use s3s::dto::{
+    ContinuationEvent, EndEvent, Progress, ProgressEvent, RecordsEvent, SelectObjectContentEvent, SelectObjectContentEventStream,
+    Stats, StatsEvent,
+};
+use s3s::{S3Result, s3_error};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, warn};
+
+/// Upper bound for the payload of one Records event
+pub(crate) const RECORDS_PAYLOAD_SIZE: usize = 128 * 1024;
+
+/// How often Progress or Continuation events are sent during a scan
+pub(crate) const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SelectOutputFormat {
+    Csv,
+    Json,
+}
+
+pub(crate) fn encode_batch(format: SelectOutputFormat, batch: &RecordBatch) -> S3Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    match format {
+        SelectOutputFormat::Csv => {
+            let mut csv_writer = CsvWriterBuilder::new().with_header(false).build(&mut buffer);
+            csv_writer
+                .write(batch)
+                .map_err(|e| s3_error!(InternalError, "can't encode output to csv. e: {}", e.to_string()))?;
+        }
+        SelectOutputFormat::Json => {
+            let mut json_writer = JsonWriterBuilder::new()
+                .with_explicit_nulls(true)
+                .build::<_, LineDelimited>(&mut buffer);
+            json_writer
+                .write(batch)
+                .map_err(|e| s3_error!(InternalError, "can't encode output to json. e: {}", e.to_string()))?;
+            json_writer
+                .finish()
+                .map_err(|e| s3_error!(InternalError, "writer output into json error, e: {}", e.to_string()))?;
+        }
+    }
+    Ok(buffer)
+}
+
+/// Split off the leading full-size payloads of `pending`, keeping the remainder buffered
+fn take_full_payloads(pending: &mut Vec<u8>) -> Vec<Bytes> {
+    let mut payloads = Vec::new();
+    while pending.len() >= RECORDS_PAYLOAD_SIZE {
+        let rest = pending.split_off(RECORDS_PAYLOAD_SIZE);
+        payloads.push(Bytes::from(std::mem::replace(pending, rest)));
+    }
+    payloads
+}
+
+fn progress(stats: &ScanStats, bytes_returned: u64) -> Progress {
+    Progress {
+        bytes_processed: Some(stats.bytes_processed() as i64),
+        bytes_returned: Some(bytes_returned as i64),
+        bytes_scanned: Some(stats.bytes_scanned() as i64),
+    }
+}
+
+fn final_stats(stats: &ScanStats, bytes_returned: u64) -> Stats {
+    Stats {
+        bytes_processed: Some(stats.bytes_processed() as i64),
+        bytes_returned: Some(bytes_returned as i64),
+        bytes_scanned: Some(stats.bytes_scanned() as i64),
+    }
+}
+
+struct EventSender {
+    tx: mpsc::Sender<S3Result<SelectObjectContentEvent>>,
+    bytes_returned: u64,
+    // Whether anything was sent since the last tick, idle ticks send a keep-alive
+    active: bool,
+}
+
+impl EventSender {
+    /// Returns false once the client went away
+    async fn send(&mut self, event: SelectObjectContentEvent) -> bool {
+        self.active = true;
+        self.tx.send(Ok(event)).await.is_ok()
+    }
+
+    async fn send_records(&mut self, payload: Bytes) -> bool {
+        self.bytes_returned += payload.len() as u64;
+        self.send(SelectObjectContentEvent::Records(RecordsEvent { payload: Some(payload) }))
+            .await
+    }
+}
+
+/// Stream the query output as select events; dropping the response stops the query
+pub(crate) fn select_event_stream(
+    mut output: Output,
+    format: SelectOutputFormat,
+    stats: Arc<ScanStats>,
+    request_progress: bool,
+) -> SelectObjectContentEventStream {
+    let (tx, rx) = mpsc::channel::<S3Result<SelectObjectContentEvent>>(2);
+
+    tokio::spawn(async move {
+        let mut sender = EventSender {
+            tx,
+            bytes_returned: 0,
+            active: false,
+        };
+        let mut pending = Vec::new();
+
+        let mut ticker = tokio::time::interval(PROGRESS_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            tokio::select! {
+                batch = output.next() => match batch {
+                    Some(Ok(batch)) => {
+                        match encode_batch(format, &batch) {
+                            Ok(data) => pending.extend_from_slice(&data),
+                            Err(e) => {
+                                let _ = sender.tx.send(Err(e)).await;
+                                return;
+                            }
+                        }
+                        for payload in take_full_payloads(&mut pending) {
+                            if !sender.send_records(payload).await {
+                                debug!("select client went away");
+                                return;
+                            }
+                        }
+                    }
+                    Some(Err(e)) => {
+                        warn!("select query failed: {}", e);
+                        let _ = sender.tx.send(Err(s3_error!(InternalError, "{}", e.to_string()))).await;
+                        return;
+                    }
+                    None => break,
+                },
+                _ = ticker.tick() => {
+                    // Don't hold back a partial payload while the scan is slow
+                    if !pending.is_empty() && !sender.send_records(Bytes::from(std::mem::take(&mut pending))).await {
+                        return;
+                    }
+
+                    let event = if request_progress {
+                        Some(SelectObjectContentEvent::Progress(ProgressEvent {
+                            details: Some(progress(&stats, sender.bytes_returned)),
+                        }))
+                    } else if !sender.active {
+                        Some(SelectObjectContentEvent::Cont(ContinuationEvent::default()))
+                    } else {
+                        None
+                    };
+                    if let Some(event) = event {
+                        if !sender.send(event).await {
+                            return;
+                        }
+                    }
+                    sender.active = false;
+                }
+            }
+        }
+
+        if !pending.is_empty() && !sender.send_records(Bytes::from(pending)).await {
+            return;
+        }
+        let details = final_stats(&stats, sender.bytes_returned);
+        if sender
+            .send(SelectObjectContentEvent::Stats(StatsEvent { details: Some(details) }))
+            .await
+        {
+            let _ = sender.send(SelectObjectContentEvent::End(EndEvent::default())).await;
+        }
+    });
+
+    SelectObjectContentEventStream::new(ReceiverStream::new(rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::{Int32Array, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+
+    fn batch() -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_encode_batch() {
+        let csv = encode_batch(SelectOutputFormat::Csv, &batch()).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "1,a\n2,\n");
+
+        let json = encode_batch(SelectOutputFormat::Json, &batch()).unwrap();
+        assert_eq!(String::from_utf8(json).unwrap(), "{\"id\":1,\"name\":\"a\"}\n{\"id\":2,\"name\":null}\n");
+    }
+
+    #[test]
+    fn test_take_full_payloads() {
+        let mut pending = vec![0u8; RECORDS_PAYLOAD_SIZE * 2 + 10];
+        let payloads = take_full_payloads(&mut pending);
+        assert_eq!(payloads.len(), 2);
+        assert!(payloads.iter().all(|p| p.len() == RECORDS_PAYLOAD_SIZE));
+        assert_eq!(pending.len(), 10);
+
+        let mut small = vec![0u8; 10];
+        assert!(take_full_payloads(&mut small).is_empty());
+        assert_eq!(small.len(), 10);
+    }
+}
@@ -1,20 +1,27 @@
+use crate::admin::handlers::service::apply_service_action;
 use bytes::Bytes;
 use futures::Stream;
 use futures_util::future::join_all;
 use rmp_serde::{Deserializer, Serializer};
-use nebulafx_common::{globals::GLOBAL_Local_Node_Name, heal_channel::HealOpts};
+use nebulafx_common::{globals::GLOBAL_Local_Node_Name, heal_channel::HealOpts, trace::subscribe_trace};
 use nebulafx_ecstore::{
     admin_server_info::get_local_server_property,
-    bucket::{metadata::load_bucket_metadata, metadata_sys},
+    bucket::{
+        metadata::load_bucket_metadata,
+        metadata_sys,
+        replication::{GLOBAL_REPLICATION_POOL, GLOBAL_REPLICATION_STATS},
+    },
+    cache_value::metacache::{GLOBAL_METACACHE, Metacache},
     disk::{
         DeleteOptions, DiskAPI, DiskInfoOptions, DiskStore, FileInfoVersions, ReadMultipleReq, ReadOptions, UpdateMetadataOpts,
-        error::DiskError,
+        error::DiskError, healing::local_bg_heal_state,
     },
     metrics_realtime::{CollectMetricsOpts, MetricType, collect_local_metrics},
     new_object_layer_fn,
-    rpc::{LocalPeerS3Client, PeerS3Client},
+    rpc::{LocalPeerS3Client, PEER_RESTDRY_RUN, PEER_RESTSIGNAL, PeerS3Client},
     store::{all_local_disk_path, find_local_disk},
     store_api::{BucketOptions, DeleteBucketOptions, MakeBucketOptions, StorageAPI},
+    store_list_objects::ListPathOptions,
 };
 use nebulafx_filemeta::{FileInfo, MetacacheReader};
 use nebulafx_iamx::{get_global_iam_sys, UserType};
@@ -23,6 +30,7 @@ use nebulafx_madmin::health::{
     get_cpus, get_mem_info, get_os_info, get_partitions, get_proc_info, get_sys_config, get_sys_errors, get_sys_services,
 };
 use nebulafx_madmin::net::get_net_info;
+use nebulafx_madmin::service_commands::{ServiceAction, ServiceTraceOpts};
 use nebulafx_protos::{
     models::{PingBody, PingBodyBuilder},
     proto_gen::node_service::{node_service_server::NodeService as Node, *},
@@ -66,6 +74,8 @@ pub struct NodeService {
 pub fn make_server() -> NodeService {
     let local_peer = LocalPeerS3Client::new(None, None);
     let lock_manager = Arc::new(nebulafx_lock::LocalClient::new());
+    // Drop locks held for peers that stopped refreshing their lease (e.g. the node died)
+    lock_manager.start_lease_sweeper(nebulafx_lock::client::local::DEFAULT_LEASE_SWEEP_INTERVAL);
     NodeService {
         local_peer,
         lock_manager,
@@ -1550,7 +1560,7 @@ impl Node for NodeService {
 
     async fn refresh(&self, request: Request<GenerallyLockRequest>) -> Result<Response<GenerallyLockResponse>, Status> {
         let request = request.into_inner();
-        let _args: LockRequest = match serde_json::from_str(&request.args) {
+        let args: LockRequest = match serde_json::from_str(&request.args) {
             Ok(args) => args,
             Err(err) => {
                 return Ok(Response::new(GenerallyLockResponse {
@@ -1560,10 +1570,19 @@ impl Node for NodeService {
             }
         };
 
-        Ok(Response::new(GenerallyLockResponse {
-            success: true,
-            error_info: None,
-        }))
+        match self.lock_manager.refresh(&args.lock_id).await {
+            Ok(success) => Ok(Response::new(GenerallyLockResponse {
+                success,
+                error_info: None,
+            })),
+            Err(err) => Ok(Response::new(GenerallyLockResponse {
+                success: false,
+                error_info: Some(format!(
+                    "can not refresh, resource: {0}, owner: {1}, err: {2}",
+                    args.resource, args.owner, err
+                )),
+            })),
+        }
     }
 
     async fn local_storage_info(
@@ -1826,7 +1845,23 @@ impl Node for NodeService {
         &self,
         _request: Request<GetSrMetricsDataRequest>,
     ) -> Result<Response<GetSrMetricsDataResponse>, Status> {
-        todo!()
+        let summary = match GLOBAL_REPLICATION_STATS.get() {
+            Some(stats) => stats.get_sr_metrics_for_node().await,
+            None => Default::default(),
+        };
+        let mut buf = Vec::new();
+        if let Err(err) = summary.serialize(&mut Serializer::new(&mut buf)) {
+            return Ok(Response::new(GetSrMetricsDataResponse {
+                success: false,
+                sr_metrics_summary: Bytes::new(),
+                error_info: Some(err.to_string()),
+            }));
+        }
+        Ok(Response::new(GetSrMetricsDataResponse {
+            success: true,
+            sr_metrics_summary: buf.into(),
+            error_info: None,
+        }))
     }
 
     async fn get_all_bucket_stats(
@@ -2149,43 +2184,139 @@ impl Node for NodeService {
         &self,
         _request: Request<ReloadSiteReplicationConfigRequest>,
     ) -> Result<Response<ReloadSiteReplicationConfigResponse>, Status> {
-        let Some(_store) = new_object_layer_fn() else {
+        let Some(store) = new_object_layer_fn() else {
             return Ok(Response::new(ReloadSiteReplicationConfigResponse {
                 success: false,
                 error_info: Some("errServerNotInitialized".to_string()),
             }));
         };
-        todo!()
+        match crate::site_replication::get().init(store).await {
+            Ok(()) => Ok(Response::new(ReloadSiteReplicationConfigResponse {
+                success: true,
+                error_info: None,
+            })),
+            Err(err) => Ok(Response::new(ReloadSiteReplicationConfigResponse {
+                success: false,
+                error_info: Some(err.to_string()),
+            })),
+        }
     }
 
     async fn signal_service(&self, request: Request<SignalServiceRequest>) -> Result<Response<SignalServiceResponse>, Status> {
         let request = request.into_inner();
-        let _vars = match request.vars {
+        let vars = match request.vars {
             Some(vars) => vars.value,
             None => HashMap::new(),
         };
-        todo!()
+
+        let action = vars
+            .get(PEER_RESTSIGNAL)
+            .and_then(|sig| sig.parse::<u64>().ok())
+            .and_then(ServiceAction::from_signal);
+        let Some(action) = action else {
+            return Ok(Response::new(SignalServiceResponse {
+                success: false,
+                error_info: Some(format!("unsupported service signal: {:?}", vars.get(PEER_RESTSIGNAL))),
+            }));
+        };
+
+        let dry_run = vars.get(PEER_RESTDRY_RUN).is_some_and(|v| v == "true");
+        if !dry_run {
+            apply_service_action(action);
+        }
+
+        Ok(Response::new(SignalServiceResponse {
+            success: true,
+            error_info: None,
+        }))
     }
 
     async fn background_heal_status(
         &self,
         _request: Request<BackgroundHealStatusRequest>,
     ) -> Result<Response<BackgroundHealStatusResponse>, Status> {
-        todo!()
+        let state = local_bg_heal_state().await;
+        let mut buf = Vec::new();
+        if let Err(err) = state.serialize(&mut Serializer::new(&mut buf)) {
+            return Ok(Response::new(BackgroundHealStatusResponse {
+                success: false,
+                bg_heal_state: Bytes::new(),
+                error_info: Some(err.to_string()),
+            }));
+        }
+        Ok(Response::new(BackgroundHealStatusResponse {
+            success: true,
+            bg_heal_state: buf.into(),
+            error_info: None,
+        }))
     }
 
     async fn get_metacache_listing(
         &self,
-        _request: Request<GetMetacacheListingRequest>,
+        request: Request<GetMetacacheListingRequest>,
     ) -> Result<Response<GetMetacacheListingResponse>, Status> {
-        todo!()
+        let request = request.into_inner();
+        let mut buf_o = Deserializer::new(Cursor::new(request.opts));
+        let opts: ListPathOptions = match Deserialize::deserialize(&mut buf_o) {
+            Ok(opts) => opts,
+            Err(err) => {
+                return Ok(Response::new(GetMetacacheListingResponse {
+                    success: false,
+                    metacache: Bytes::new(),
+                    error_info: Some(format!("decode ListPathOptions failed: {err}")),
+                }));
+            }
+        };
+
+        let metacache = GLOBAL_METACACHE.find(&opts);
+
+        let mut buf = Vec::new();
+        if let Err(err) = metacache.serialize(&mut Serializer::new(&mut buf)) {
+            return Ok(Response::new(GetMetacacheListingResponse {
+                success: false,
+                metacache: Bytes::new(),
+                error_info: Some(err.to_string()),
+            }));
+        }
+        Ok(Response::new(GetMetacacheListingResponse {
+            success: true,
+            metacache: buf.into(),
+            error_info: None,
+        }))
     }
 
     async fn update_metacache_listing(
         &self,
-        _request: Request<UpdateMetacacheListingRequest>,
+        request: Request<UpdateMetacacheListingRequest>,
     ) -> Result<Response<UpdateMetacacheListingResponse>, Status> {
-        todo!()
+        let request = request.into_inner();
+        let mut buf_m = Deserializer::new(Cursor::new(request.metacache));
+        let update: Metacache = match Deserialize::deserialize(&mut buf_m) {
+            Ok(update) => update,
+            Err(err) => {
+                return Ok(Response::new(UpdateMetacacheListingResponse {
+                    success: false,
+                    metacache: Bytes::new(),
+                    error_info: Some(format!("decode Metacache failed: {err}")),
+                }));
+            }
+        };
+
+        let metacache = GLOBAL_METACACHE.update(update);
+
+        let mut buf = Vec::new();
+        if let Err(err) = metacache.serialize(&mut Serializer::new(&mut buf)) {
+            return Ok(Response::new(UpdateMetacacheListingResponse {
+                success: false,
+                metacache: Bytes::new(),
+                error_info: Some(err.to_string()),
+            }));
+        }
+        Ok(Response::new(UpdateMetacacheListingResponse {
+            success: true,
+            metacache: buf.into(),
+            error_info: None,
+        }))
     }
 
     async fn reload_pool_meta(
@@ -2268,5 +2399,86 @@ impl Node for NodeService {
     ) -> Result<Response<LoadTransitionTierConfigResponse>, Status> {
         todo!()
     }
+
+    async fn cancel_replication_resync(
+        &self,
+        request: Request<CancelReplicationResyncRequest>,
+    ) -> Result<Response<CancelReplicationResyncResponse>, Status> {
+        let Some(pool) = GLOBAL_REPLICATION_POOL.get() else {
+            return Ok(Response::new(CancelReplicationResyncResponse {
+                success: false,
+                error_info: Some("replication is not initialized".to_string()),
+                canceled: false,
+            }));
+        };
+
+        let CancelReplicationResyncRequest { bucket, arn } = request.into_inner();
+        match pool.cancel_resync(&bucket, &arn).await {
+            Ok(canceled) => Ok(Response::new(CancelReplicationResyncResponse {
+                success: true,
+                error_info: None,
+                canceled,
+            })),
+            Err(err) => Ok(Response::new(CancelReplicationResyncResponse {
+                success: false,
+                error_info: Some(err.to_string()),
+                canceled: false,
+            })),
+        }
+    }
+
+    type TraceStream = ResponseStream<TraceResponse>;
+    async fn trace(&self, request: Request<TraceRequest>) -> Result<Response<Self::TraceStream>, Status> {
+        let request = request.into_inner();
+        let uri = format!("/?{}", request.opts)
+            .parse::<hyper::Uri>()
+            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+        let mut opts = ServiceTraceOpts::default();
+        opts.parse_params(&uri).map_err(Status::invalid_argument)?;
+
+        let mut subscription = subscribe_trace(opts.trace_types());
+        let node_name = GLOBAL_Local_Node_Name.read().await.clone();
+        let (tx, rx) = mpsc::channel(1000);
+
+        tokio::spawn(async move {
+            loop {
+                // The admin client went away, dropping the subscription stops tracing on this node
+                let mut info = tokio::select! {
+                    _ = tx.closed() => break,
+                    info = subscription.recv() => match info {
+                        Some(info) => info,
+                        None => break,
+                    },
+                };
+                if !opts.matches(&info) {
+                    continue;
+                }
+                opts.redact(&mut info);
+                if info.node_name.is_empty() {
+                    info.node_name = node_name.clone();
+                }
+
+                let response = match serde_json::to_string(&info) {
+                    Ok(trace_info) => TraceResponse {
+                        success: true,
+                        trace_info,
+                        error_info: None,
+                    },
+                    Err(err) => TraceResponse {
+                        success: false,
+                        trace_info: String::new(),
+                        error_info: Some(err.to_string()),
+                    },
+                };
+
+                if tx.send(Ok(response)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let out_stream = ReceiverStream::new(rx);
+        Ok(Response::new(Box::pin(out_stream)))
+    }
 }
 